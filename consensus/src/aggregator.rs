@@ -191,6 +191,37 @@ impl<V: StepVote> Aggregator<V> {
 
         Ok((step_votes, quorum_reached))
     }
+
+    /// Returns the credits collected for each vote cast in `step`.
+    pub fn tallies(&self, step: u8) -> impl Iterator<Item = (&Vote, usize)> {
+        self.votes.iter().filter(move |((s, _), _)| *s == step).map(
+            |((_, vote), (_, cluster))| (vote, cluster.total_occurrences()),
+        )
+    }
+
+    /// Returns true if no vote can reach its quorum in `step`, even if all
+    /// the remaining committee members voted for it.
+    pub fn is_quorum_unreachable(
+        &self,
+        committee: &Committee,
+        step: u8,
+    ) -> bool {
+        let cast: usize = self.tallies(step).map(|(_, total)| total).sum();
+        let remaining = committee.credits().saturating_sub(cast);
+
+        // A vote not cast yet could still reach a majority quorum
+        if remaining >= committee.majority_quorum() {
+            return false;
+        }
+
+        self.tallies(step).all(|(vote, total)| {
+            let quorum_target = match vote {
+                Vote::Valid(_) => committee.super_majority_quorum(),
+                _ => committee.majority_quorum(),
+            };
+            total + remaining < quorum_target
+        })
+    }
 }
 
 impl<V> fmt::Display for Aggregator<V> {
@@ -242,6 +273,8 @@ mod tests {
     use hex::FromHex;
     use node_data::ledger::{Header, Seed};
     use node_data::message::StepMessage;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
 
     use super::*;
     use crate::aggregator::Aggregator;
@@ -407,4 +440,58 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_quorum_unreachable() {
+        let mut p = Provisioners::empty();
+        let mut keys = vec![];
+        for i in 0..10 {
+            let sk = BlsSecretKey::random(&mut StdRng::seed_from_u64(i));
+            let pk = node_data::bls::PublicKey::new(BlsPublicKey::from(&sk));
+            p.add_member_with_value(pk.clone(), 1000 * DUSK);
+            keys.push((pk, sk));
+        }
+
+        let iteration = 1;
+        let cfg = Config::raw(Seed::from([4u8; 48]), 1, 1, 10, vec![]);
+        let c = Committee::new(&p, &cfg);
+        let tip_header = Header::default();
+
+        let validation = |idx: usize, vote: Vote| {
+            let (pk, sk) = keys[idx].clone();
+            let ru =
+                RoundUpdate::new(pk, sk, &tip_header, HashMap::new(), vec![]);
+            crate::build_validation_payload(vote, &ru, iteration)
+        };
+        let members: Vec<_> = keys
+            .iter()
+            .enumerate()
+            .filter(|(_, (pk, _))| c.is_member(pk))
+            .map(|(idx, _)| idx)
+            .collect();
+
+        // Votes split between two candidates
+        let mut a = Aggregator::default();
+        let step = validation(members[0], Vote::NoCandidate).get_step();
+        assert!(!a.is_quorum_unreachable(&c, step));
+        for (i, idx) in members.iter().enumerate() {
+            let msg = validation(*idx, Vote::Valid([(i % 2) as u8; 32]));
+            let (_, quorum_reached) = a.collect_vote(&c, &msg).unwrap();
+            assert!(!quorum_reached);
+        }
+        assert_eq!(a.tallies(step).count(), 2);
+        assert_eq!(a.tallies(step).map(|(_, t)| t).sum::<usize>(), c.credits());
+        assert!(a.is_quorum_unreachable(&c, step));
+
+        // Votes on a single candidate
+        let mut a = Aggregator::default();
+        for idx in &members {
+            let msg = validation(*idx, Vote::Valid([1; 32]));
+            assert!(!a.is_quorum_unreachable(&c, msg.get_step()));
+            let (_, quorum_reached) = a.collect_vote(&c, &msg).unwrap();
+            if quorum_reached {
+                break;
+            }
+        }
+    }
 }
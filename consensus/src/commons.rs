@@ -13,7 +13,7 @@ use std::time::Duration;
 use dusk_core::signatures::bls::SecretKey as BlsSecretKey;
 use node_data::bls::PublicKey;
 use node_data::ledger::*;
-use node_data::message::{payload, ConsensusHeader};
+use node_data::message::{payload, ConsensusHeader, Payload};
 use node_data::StepName;
 
 use crate::operations::Voter;
@@ -87,6 +87,12 @@ impl RoundUpdate {
     }
 }
 
+/// Storage of the consensus.
+///
+/// A round is resumed after the last iteration recorded for it, so that an
+/// iteration already taken part in isn't run again. The record must never be
+/// behind the candidates and validation results stored for the round: storing
+/// one of them raises the record to its iteration atomically.
 #[async_trait::async_trait]
 pub trait Database: Send + Sync {
     async fn store_candidate_block(&mut self, b: Block);
@@ -97,4 +103,15 @@ pub trait Database: Send + Sync {
     );
     async fn get_last_iter(&self) -> (Hash, u8);
     async fn store_last_iter(&mut self, data: (Hash, u8));
+    /// Checks the last iteration record against the candidates and
+    /// validation results stored for the round following `prev_block_hash`,
+    /// repairing it before the round is resumed
+    async fn recover_last_iter(&mut self, _prev_block_hash: Hash) {}
+    /// Records the outcome of a step, for post-mortem of the failed rounds
+    async fn store_step_outcome(
+        &mut self,
+        ch: &ConsensusHeader,
+        step: StepName,
+        outcome: &Payload,
+    );
 }
@@ -22,8 +22,6 @@ pub const RELAX_ITERATION_THRESHOLD: u8 = MESSAGE_MAX_FAILED_ITERATIONS;
 pub const MAX_NUMBER_OF_TRANSACTIONS: usize = 1_000;
 pub const MAX_NUMBER_OF_FAULTS: usize = 100;
 
-pub const MAX_BLOCK_SIZE: usize = 1_024 * 1_024;
-
 /// Emergency mode is enabled after 16 iterations
 pub const EMERGENCY_MODE_ITERATION_THRESHOLD: u8 = 16;
 pub const EMERGENCY_BLOCK_ITERATION: u8 = u8::MAX;
@@ -47,15 +45,29 @@ pub const MIN_EMERGENCY_BLOCK_TIME: Duration =
 
 mod default {
     pub const MINIMUM_BLOCK_TIME: u64 = 10;
+    pub const MAX_BLOCK_SIZE: usize = 1_024 * 1_024;
 }
 
+/// Minimum time between two blocks, in seconds.
+///
+/// A value of 0 isn't a valid block time, the default one is used instead.
 pub static MINIMUM_BLOCK_TIME: LazyLock<u64> = LazyLock::new(|| {
     env::var("RUSK_MINIMUM_BLOCK_TIME")
         .unwrap_or_default()
         .parse()
+        .ok()
+        .filter(|&time| time > 0)
         .unwrap_or(default::MINIMUM_BLOCK_TIME)
 });
 
+/// Maximum serialized size of a block, in bytes
+pub static MAX_BLOCK_SIZE: LazyLock<usize> = LazyLock::new(|| {
+    env::var("RUSK_MAX_BLOCK_SIZE")
+        .unwrap_or_default()
+        .parse()
+        .unwrap_or(default::MAX_BLOCK_SIZE)
+});
+
 /// Maximum allowable round difference for message signature verification and
 /// for determining if a consensus message is close enough to the network tip
 /// for enqueuing.
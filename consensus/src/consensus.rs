@@ -7,7 +7,7 @@
 use std::cmp;
 use std::sync::Arc;
 
-use node_data::message::{AsyncQueue, Message, Payload};
+use node_data::message::{AsyncQueue, ConsensusHeader, Message, Payload};
 use tokio::sync::{oneshot, Mutex};
 use tokio::task::JoinHandle;
 use tracing::{debug, error, warn, Instrument};
@@ -17,7 +17,7 @@ use crate::config::{CONSENSUS_MAX_ITER, EMERGENCY_MODE_ITERATION_THRESHOLD};
 use crate::errors::ConsensusError;
 use crate::execution_ctx::ExecutionCtx;
 use crate::iteration_ctx::IterationCtx;
-use crate::operations::Operations;
+use crate::operations::{Operations, StepEvent};
 use crate::phase::Phase;
 use crate::queue::MsgRegistry;
 use crate::step_votes_reg::AttInfoRegistry;
@@ -181,8 +181,11 @@ impl<T: Operations + 'static, D: Database + 'static> Consensus<T, D> {
                 ru.base_timeouts.clone(),
             );
 
-            let (prev_block_hash, saved_iter) =
-                db.lock().await.get_last_iter().await;
+            let (prev_block_hash, saved_iter) = {
+                let mut db = db.lock().await;
+                db.recover_last_iter(ru.hash()).await;
+                db.get_last_iter().await
+            };
 
             let saved_iter =
                 cmp::min(EMERGENCY_MODE_ITERATION_THRESHOLD, saved_iter);
@@ -223,6 +226,26 @@ impl<T: Operations + 'static, D: Database + 'static> Consensus<T, D> {
                     // phase.
                     phase.reinitialize(msg, ru.round, iter).await;
 
+                    executor.step_started(ru.round, iter, step_name).await;
+
+                    if let Some(committee) = iter_ctx
+                        .committees
+                        .get_committee(step_name.to_step(iter))
+                    {
+                        let seats = committee
+                            .votes_for(&ru.pubkey_bls)
+                            .unwrap_or_default();
+                        executor
+                            .committee_seats(
+                                ru.round,
+                                iter,
+                                step_name,
+                                seats,
+                                committee.credits(),
+                            )
+                            .await;
+                    }
+
                     // Construct phase execution context
                     let ctx = ExecutionCtx::new(
                         &mut iter_ctx,
@@ -249,6 +272,16 @@ impl<T: Operations + 'static, D: Database + 'static> Consensus<T, D> {
                         ))
                         .await;
 
+                    let ch = ConsensusHeader {
+                        prev_block_hash: ru.hash(),
+                        round: ru.round,
+                        iteration: iter,
+                    };
+                    db.lock()
+                        .await
+                        .store_step_outcome(&ch, step_name, &msg.payload)
+                        .await;
+
                     // Handle Quorum messages produced by Consensus or received
                     // from the network. A Quorum for the current iteration
                     // means the iteration is over.
@@ -261,6 +294,14 @@ impl<T: Operations + 'static, D: Database + 'static> Consensus<T, D> {
                             is_local = msg.is_local()
                         );
 
+                        executor
+                            .step_event(StepEvent::QuorumReached {
+                                round: qmsg.header.round,
+                                iteration: qmsg.header.iteration,
+                                vote: *qmsg.vote(),
+                            })
+                            .await;
+
                         // Broadcast/Rebroadcast
                         outbound.try_send(msg.clone());
 
@@ -29,6 +29,24 @@ impl From<BlsSigError> for StepSigError {
     }
 }
 
+#[derive(Debug, Error)]
+pub enum BundleError {
+    #[error("Unsupported bundle version {0}")]
+    UnsupportedVersion(u8),
+    #[error("Invalid block hash")]
+    InvalidBlockHash,
+    #[error("Attestation result {0:?} doesn't finalize the block")]
+    InvalidResult(RatificationResult),
+    #[error("Invalid {0:?} committee: {1}")]
+    InvalidCommittee(StepName, &'static str),
+    #[error("Invalid {0:?} aggregate key")]
+    InvalidAggregateKey(StepName),
+    #[error("Provisioners root mismatch")]
+    InvalidProvisionersRoot,
+    #[error("Invalid {0:?} votes: {1}")]
+    InvalidVotes(StepName, StepSigError),
+}
+
 #[derive(Debug, Clone)]
 pub enum ConsensusError {
     InvalidBlock,
@@ -103,6 +121,14 @@ pub enum HeaderError {
     InvalidBlockSignature(String),
     #[error("invalid seed: {0}")]
     InvalidSeed(String),
+    #[error(
+        "mismatch, provisioners_root: {}, candidate_provisioners_root: {}",
+        hex::encode(.0),
+        hex::encode(.1)
+    )]
+    InvalidProvisionersRoot([u8; 32], [u8; 32]),
+    #[error("extra-data of {0} bytes exceeds the maximum size")]
+    InvalidExtraData(usize),
 
     #[error("Invalid Attestation: {0}")]
     InvalidAttestation(AttestationError),
@@ -136,6 +162,10 @@ pub enum VstError {
     InvalidSlash(io::Error),
     #[error("Invalid generator: {0:?}")]
     InvalidGenerator(dusk_bytes::Error),
+    #[error("Invalid stake call: {0}")]
+    InvalidStakeCall(String),
+    #[error("Transactions not in canonical order")]
+    InvalidTxOrder,
     #[error("Generic error in vst: {0}")]
     Generic(String),
 }
@@ -160,6 +190,8 @@ impl HeaderError {
             HeaderError::UnsupportedVersion => true,
             HeaderError::EmptyHash => true,
             HeaderError::InvalidSeed(_) => true,
+            HeaderError::InvalidProvisionersRoot(..) => true,
+            HeaderError::InvalidExtraData(_) => true,
             HeaderError::InvalidAttestation(_) => true,
             HeaderError::InvalidFailedIterations(_) => true,
 
@@ -26,7 +26,7 @@ use crate::config::{
 use crate::errors::ConsensusError;
 use crate::iteration_ctx::IterationCtx;
 use crate::msg_handler::{MsgHandler, StepOutcome};
-use crate::operations::Operations;
+use crate::operations::{Operations, StepEvent};
 use crate::queue::{MsgRegistry, MsgRegistryError};
 use crate::ratification::step::RatificationStep;
 use crate::step_votes_reg::SafeAttestationInfoRegistry;
@@ -52,6 +52,8 @@ pub struct ExecutionCtx<'a, T, DB: Database> {
     pub iteration: u8,
     step: StepName,
     step_start_time: Option<Instant>,
+    /// Number of votes collected by the step
+    votes_received: usize,
 
     pub client: Arc<T>,
 
@@ -85,6 +87,7 @@ impl<'a, T: Operations + 'static, DB: Database> ExecutionCtx<'a, T, DB> {
             client,
             sv_registry,
             step_start_time: None,
+            votes_received: 0,
         }
     }
 
@@ -170,6 +173,7 @@ impl<'a, T: Operations + 'static, DB: Database> ExecutionCtx<'a, T, DB> {
                                 // to Consensus
                                 if !open_consensus_mode {
                                     self.report_elapsed_time().await;
+                                    self.report_votes_received().await;
                                     return step_result;
                                 }
 
@@ -361,6 +365,7 @@ impl<'a, T: Operations + 'static, DB: Database> ExecutionCtx<'a, T, DB> {
                         open_consensus_mode = true;
                     } else {
                         self.process_timeout_event(phase).await;
+                        self.report_votes_received().await;
                         return Message::empty();
                     }
                 }
@@ -609,6 +614,9 @@ impl<'a, T: Operations + 'static, DB: Database> ExecutionCtx<'a, T, DB> {
                     Err(MsgRegistryError::SignerAlreadyEnqueue(msg)) => {
                         log_msg("discarded msg (duplicated)", SRC, &msg);
                     }
+                    Err(MsgRegistryError::OverBudget(msg)) => {
+                        log_msg("discarded msg (over budget)", SRC, &msg);
+                    }
                 }
 
                 return None;
@@ -617,6 +625,11 @@ impl<'a, T: Operations + 'static, DB: Database> ExecutionCtx<'a, T, DB> {
                 self.handle_past_msg(msg).await;
                 return None;
             }
+            Err(ConsensusError::InvalidSignature(e)) => {
+                error!("phase handler err: invalid signature {:?}", e);
+                self.client.report_invalid_signature(&msg).await;
+                return None;
+            }
             // An error here means this message is invalid due to failed
             // verification.
             Err(e) => {
@@ -643,6 +656,12 @@ impl<'a, T: Operations + 'static, DB: Database> ExecutionCtx<'a, T, DB> {
             )
             .await;
 
+        if collected.is_ok()
+            && matches!(msg_topic, Topics::Validation | Topics::Ratification)
+        {
+            self.votes_received += 1;
+        }
+
         match collected {
             // Fully valid state reached on this step. Return it as an output to
             // populate next step with it.
@@ -663,7 +682,16 @@ impl<'a, T: Operations + 'static, DB: Database> ExecutionCtx<'a, T, DB> {
         &mut self,
         phase: Arc<Mutex<C>>,
     ) {
-        self.iter_ctx.on_timeout_event(self.step_name());
+        let step_name = self.step_name();
+        self.iter_ctx.on_timeout_event(step_name);
+        self.client
+            .step_event(StepEvent::TimeoutIncreased {
+                round: self.round_update.round,
+                iteration: self.iteration,
+                step_name,
+                timeout: self.iter_ctx.get_timeout(step_name),
+            })
+            .await;
 
         if let Some(msg) = phase
             .lock()
@@ -757,6 +785,18 @@ impl<'a, T: Operations + 'static, DB: Database> ExecutionCtx<'a, T, DB> {
             .await;
     }
 
+    async fn report_votes_received(&mut self) {
+        let count = std::mem::take(&mut self.votes_received);
+        self.client
+            .step_event(StepEvent::VotesReceived {
+                round: self.round_update.round,
+                iteration: self.iteration,
+                step_name: self.step_name(),
+                count,
+            })
+            .await;
+    }
+
     pub(crate) fn get_curr_generator(&self) -> Option<PublicKeyBytes> {
         self.iter_ctx.get_generator(self.iteration)
     }
@@ -0,0 +1,877 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Adversarial message schedules for the step message handlers.
+//!
+//! A [`Schedule`] is decoded from arbitrary bytes, as provided by a fuzzer:
+//! the step and the iteration executed by the local provisioner, and the
+//! messages it receives from a known provisioner set. Messages can be of past
+//! and future rounds or iterations, built on another tip, carry a corrupted
+//! signature or malformed step votes, be duplicated or equivocate.
+//!
+//! The messages are delivered to the handler of the step the way the
+//! execution context does: verified first, then collected if of the step
+//! executed, or collected from the past if of a former step of an emergency
+//! iteration.
+//! Running a schedule panics if a handler panics, or if:
+//! - a message is rejected with an error not expected for it
+//! - a message of another round or tip, or with a corrupted signature, passes
+//!   the verification
+//! - a vote is collected twice, or two votes of a provisioner are collected in
+//!   the same step outside of the emergency mode
+//! - malformed step votes are adopted as the local validation result
+//! - a step outcome is not the one of the step
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use dusk_bytes::Serializable;
+use dusk_core::signatures::bls::{
+    PublicKey as BlsPublicKey, SecretKey as BlsSecretKey,
+};
+use node_data::bls::{PublicKey, PublicKeyBytes};
+use node_data::ledger::{Block, Hash, Header, Signature, StepVotes};
+use node_data::message::payload::{
+    Candidate, QuorumType, Ratification, Validation, ValidationQuorum,
+    ValidationResult, Vote,
+};
+use node_data::message::{
+    ConsensusHeader, Message, Payload, SignedStepMessage, StepMessage, Topics,
+};
+use node_data::StepName;
+use tokio::sync::Mutex;
+
+use crate::commons::{Database, RoundUpdate, TimeoutSet};
+use crate::config::{is_emergency_iter, EMERGENCY_MODE_ITERATION_THRESHOLD};
+use crate::errors::ConsensusError;
+use crate::iteration_ctx::RoundCommittees;
+use crate::msg_handler::{MsgHandler, StepOutcome};
+use crate::proposal::handler::ProposalHandler;
+use crate::ratification::handler::RatificationHandler;
+use crate::step_votes_reg::AttInfoRegistry;
+use crate::user::committee::Committee;
+use crate::user::provisioners::{Provisioners, DUSK};
+use crate::user::sortition::Config;
+use crate::validation::handler::ValidationHandler;
+use crate::{build_ratification_payload, build_validation_payload};
+
+/// Number of provisioners in the set. The key following theirs is the one of
+/// a non-provisioner.
+const PROVISIONERS: u8 = 8;
+
+/// Maximum number of messages of a schedule
+const MAX_MESSAGES: usize = 64;
+
+const TIP_HEIGHT: u64 = 9;
+const TIP_HASH: Hash = [0xaa; 32];
+const OTHER_TIP_HASH: Hash = [0xbb; 32];
+
+/// Hashes of the candidates voted
+const CANDIDATES: [Hash; 2] = [[1; 32], [2; 32]];
+
+/// Iterations a schedule can be executed at
+const ITERATIONS: [u8; 5] = [
+    0,
+    1,
+    2,
+    EMERGENCY_MODE_ITERATION_THRESHOLD,
+    EMERGENCY_MODE_ITERATION_THRESHOLD + 1,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    /// A message of the step executed
+    Present,
+    PastRound,
+    FutureRound,
+    PastIteration,
+    FutureIteration,
+    /// A message of the round built on another tip
+    OtherTip,
+    /// A message of the step executed with a corrupted signature
+    BadSignature,
+    /// A ratification or validation quorum carrying validation step votes
+    /// with an arbitrary bitset and signature
+    MalformedVotes,
+    /// The previous message, delivered again
+    Duplicate,
+    /// The previous message, with another vote
+    Equivocation,
+}
+
+const KINDS: [Kind; 10] = [
+    Kind::Present,
+    Kind::PastRound,
+    Kind::FutureRound,
+    Kind::PastIteration,
+    Kind::FutureIteration,
+    Kind::OtherTip,
+    Kind::BadSignature,
+    Kind::MalformedVotes,
+    Kind::Duplicate,
+    Kind::Equivocation,
+];
+
+#[derive(Debug, Clone, Copy)]
+struct Delivery {
+    kind: Kind,
+    /// Index of the key of the sender
+    signer: u8,
+    vote: u8,
+    topic: u8,
+}
+
+impl Delivery {
+    fn vote(&self) -> Vote {
+        vote(self.vote)
+    }
+
+    fn topic(&self) -> Topics {
+        match (self.kind, self.topic % 4) {
+            (Kind::MalformedVotes, t) if t % 2 == 0 => Topics::Ratification,
+            (Kind::MalformedVotes, _) => Topics::ValidationQuorum,
+            (_, 0) => Topics::Candidate,
+            (_, 1) => Topics::Validation,
+            (_, 2) => Topics::Ratification,
+            _ => Topics::ValidationQuorum,
+        }
+    }
+
+    /// Returns whether a candidate is signed by the generator of its
+    /// iteration rather than by the signer
+    fn from_generator(&self) -> bool {
+        self.topic / 8 % 2 == 0
+    }
+
+    /// Returns the step votes of a malformed validation result
+    fn malformed_votes(&self) -> StepVotes {
+        let bitset = u64::from_le_bytes([
+            self.vote,
+            self.signer,
+            self.topic,
+            self.vote,
+            0,
+            0,
+            0,
+            0,
+        ]) | 1;
+        StepVotes::new([self.topic; 48], bitset)
+    }
+}
+
+fn vote(byte: u8) -> Vote {
+    match byte % 5 {
+        0 => Vote::Valid(CANDIDATES[0]),
+        1 => Vote::Valid(CANDIDATES[1]),
+        2 => Vote::Invalid(CANDIDATES[0]),
+        3 => Vote::NoCandidate,
+        _ => Vote::NoQuorum,
+    }
+}
+
+fn quorum_type(vote: Vote) -> QuorumType {
+    match vote {
+        Vote::Valid(_) => QuorumType::Valid,
+        Vote::Invalid(_) => QuorumType::Invalid,
+        Vote::NoCandidate => QuorumType::NoCandidate,
+        Vote::NoQuorum => QuorumType::NoQuorum,
+    }
+}
+
+fn secret_key(idx: u8) -> BlsSecretKey {
+    BlsSecretKey::from_bytes(&[idx + 1; 32]).expect("key to be a valid scalar")
+}
+
+fn tip(height: u64, hash: Hash) -> Header {
+    Header {
+        height,
+        hash,
+        ..Default::default()
+    }
+}
+
+/// Builds the step messages of a provisioner
+pub struct VoteBuilder {
+    ru: RoundUpdate,
+    iteration: u8,
+}
+
+impl VoteBuilder {
+    /// Creates a builder of the messages signed by `sk`, in the round
+    /// following `tip` at `iteration`
+    pub fn new(sk: BlsSecretKey, tip: &Header, iteration: u8) -> Self {
+        let pk = PublicKey::new(BlsPublicKey::from(&sk));
+        let ru = RoundUpdate::new(pk, sk, tip, TimeoutSet::new(), vec![]);
+        Self { ru, iteration }
+    }
+
+    pub fn header(&self) -> ConsensusHeader {
+        ConsensusHeader {
+            prev_block_hash: self.ru.hash(),
+            round: self.ru.round,
+            iteration: self.iteration,
+        }
+    }
+
+    pub fn candidate(&self) -> Candidate {
+        let header = Header {
+            height: self.ru.round,
+            iteration: self.iteration,
+            prev_block_hash: self.ru.hash(),
+            generator_bls_pubkey: *self.ru.pubkey_bls.bytes(),
+            ..Default::default()
+        };
+        let candidate =
+            Block::new(header, vec![], vec![]).expect("block to be created");
+        let mut candidate = Candidate { candidate };
+        candidate.sign(&self.ru.secret_key, self.ru.pubkey_bls.inner());
+        candidate
+    }
+
+    pub fn validation(&self, vote: Vote) -> Validation {
+        build_validation_payload(vote, &self.ru, self.iteration)
+    }
+
+    pub fn ratification(&self, result: &ValidationResult) -> Ratification {
+        build_ratification_payload(&self.ru, self.iteration, result)
+    }
+}
+
+/// Flips a bit of the signature of `msg`
+fn corrupt(msg: &mut Message) {
+    let flip = |signature: Signature| {
+        let mut bytes = *signature.inner();
+        bytes[0] ^= 1;
+        Signature::from(bytes)
+    };
+    match &mut msg.payload {
+        Payload::Candidate(c) => {
+            let signature = flip(c.candidate.header().signature);
+            c.candidate.set_signature(signature);
+        }
+        Payload::Validation(v) => {
+            v.sign_info.signature = flip(v.sign_info.signature)
+        }
+        Payload::Ratification(r) => {
+            r.sign_info.signature = flip(r.sign_info.signature)
+        }
+        _ => {}
+    }
+}
+
+/// Database discarding everything
+struct NoDatabase;
+
+#[async_trait]
+impl Database for NoDatabase {
+    async fn store_candidate_block(&mut self, _: Block) {}
+    async fn store_validation_result(
+        &mut self,
+        _: &ConsensusHeader,
+        _: &ValidationResult,
+    ) {
+    }
+    async fn get_last_iter(&self) -> (Hash, u8) {
+        (Hash::default(), 0)
+    }
+    async fn store_last_iter(&mut self, _: (Hash, u8)) {}
+    async fn store_step_outcome(
+        &mut self,
+        _: &ConsensusHeader,
+        _: StepName,
+        _: &Payload,
+    ) {
+    }
+}
+
+/// Returns whether `err` is expected from the verification of a message
+fn is_verification_error(kind: Kind, err: &ConsensusError) -> bool {
+    match kind {
+        Kind::PastRound => matches!(err, ConsensusError::PastEvent),
+        Kind::FutureRound => matches!(err, ConsensusError::FutureEvent),
+        Kind::OtherTip => match err {
+            ConsensusError::InvalidPrevBlockHash(hash) => {
+                *hash == OTHER_TIP_HASH
+            }
+            _ => false,
+        },
+        _ => matches!(
+            err,
+            ConsensusError::PastEvent
+                | ConsensusError::FutureEvent
+                | ConsensusError::NotCommitteeMember
+                | ConsensusError::InvalidSignature(_)
+                | ConsensusError::InvalidMsgType
+                | ConsensusError::VoteAlreadyCollected
+                | ConsensusError::InvalidValidationStepVotes(_)
+                | ConsensusError::CommitteeNotGenerated
+        ),
+    }
+}
+
+/// Returns whether `err` is expected from the collection of a verified
+/// message
+fn is_collection_error(err: &ConsensusError) -> bool {
+    matches!(
+        err,
+        ConsensusError::InvalidVote(_)
+            | ConsensusError::VoteMismatch(..)
+            | ConsensusError::InvalidValidationStepVotes(_)
+    )
+}
+
+/// Checks `outcome` is a result of `topic` for `round` and `iteration`
+fn check_outcome(
+    outcome: &StepOutcome,
+    topic: Topics,
+    round: u64,
+    iteration: u8,
+) {
+    let StepOutcome::Ready(msg) = outcome else {
+        return;
+    };
+    let valid = match (topic, &msg.payload) {
+        (Topics::Candidate, Payload::Candidate(_)) => true,
+        (
+            Topics::Validation | Topics::ValidationQuorum,
+            Payload::ValidationResult(_),
+        ) => true,
+        (Topics::Ratification, Payload::Quorum(q)) => {
+            q.header.round == round && q.header.iteration == iteration
+        }
+        _ => false,
+    };
+    assert!(valid, "unexpected outcome {msg:?} of a {topic:?} message");
+}
+
+/// Counters of a schedule execution
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Report {
+    /// Messages collected in the step executed
+    pub collected: usize,
+    /// Messages rejected, either by verification or collection
+    pub rejected: usize,
+    /// Step outcomes reached
+    pub ready: usize,
+}
+
+/// Messages delivered to the handler of a step
+#[derive(Debug, Clone)]
+pub struct Schedule {
+    step: StepName,
+    iteration: u8,
+    /// Vote of the local validation result, as the Ratification step starts
+    validation_vote: Vote,
+    deliveries: Vec<Delivery>,
+}
+
+impl Schedule {
+    /// Decodes a schedule from arbitrary bytes
+    pub fn decode(data: &[u8]) -> Self {
+        let byte = |i: usize| data.get(i).copied().unwrap_or_default();
+        let step = match byte(0) % 3 {
+            0 => StepName::Proposal,
+            1 => StepName::Validation,
+            _ => StepName::Ratification,
+        };
+        let iteration = ITERATIONS[usize::from(byte(1)) % ITERATIONS.len()];
+        let deliveries = data
+            .get(3..)
+            .unwrap_or_default()
+            .chunks_exact(4)
+            .take(MAX_MESSAGES)
+            .map(|chunk| Delivery {
+                kind: KINDS[usize::from(chunk[0]) % KINDS.len()],
+                signer: chunk[1] % (PROVISIONERS + 1),
+                vote: chunk[2],
+                topic: chunk[3],
+            })
+            .collect();
+
+        Self {
+            step,
+            iteration,
+            validation_vote: vote(byte(2)),
+            deliveries,
+        }
+    }
+
+    /// Delivers the messages of the schedule, panicking on a violation
+    pub async fn execute(&self) -> Report {
+        let mut round =
+            Round::new(self.step, self.iteration, self.validation_vote);
+        let mut report = Report::default();
+
+        let mut last: Option<(Delivery, Message)> = None;
+        for delivery in &self.deliveries {
+            let (delivery, msg) = match (delivery.kind, &last) {
+                (Kind::Duplicate, Some((prev, msg))) => (*prev, msg.clone()),
+                (Kind::Equivocation, Some((prev, _))) => {
+                    let delivery = Delivery {
+                        vote: prev.vote % 5 + 1,
+                        ..*prev
+                    };
+                    (delivery, round.message(&delivery))
+                }
+                (Kind::Duplicate | Kind::Equivocation, None) => {
+                    let delivery = Delivery {
+                        kind: Kind::Present,
+                        ..*delivery
+                    };
+                    (delivery, round.message(&delivery))
+                }
+                _ => (*delivery, round.message(delivery)),
+            };
+            round.deliver(delivery, msg.clone(), &mut report).await;
+            last = Some((delivery, msg));
+        }
+
+        report
+    }
+}
+
+/// Runs the schedule decoded from `data`, panicking if a handler misbehaves
+pub fn run(data: &[u8]) -> Report {
+    tokio::runtime::Builder::new_current_thread()
+        .build()
+        .expect("runtime to be built")
+        .block_on(Schedule::decode(data).execute())
+}
+
+/// Round executed by the local provisioner
+struct Round {
+    ru: RoundUpdate,
+    committees: RoundCommittees,
+    step: StepName,
+    iteration: u8,
+    /// Keys of the provisioners, followed by the one of a non-provisioner
+    keys: Vec<(PublicKey, BlsSecretKey)>,
+
+    proposal: ProposalHandler<NoDatabase>,
+    validation: ValidationHandler<NoDatabase>,
+    ratification: RatificationHandler,
+
+    /// Votes collected by step and signer
+    collected: HashMap<(u8, PublicKeyBytes), Vec<Vote>>,
+    /// Malformed step votes delivered
+    malformed: Vec<StepVotes>,
+}
+
+impl Round {
+    fn new(step: StepName, iteration: u8, validation_vote: Vote) -> Self {
+        let keys: Vec<_> = (0..=PROVISIONERS)
+            .map(|idx| {
+                let sk = secret_key(idx);
+                (PublicKey::new(BlsPublicKey::from(&sk)), sk)
+            })
+            .collect();
+
+        let mut provisioners = Provisioners::empty();
+        for (pk, _) in &keys[..usize::from(PROVISIONERS)] {
+            provisioners.add_member_with_value(pk.clone(), 1000 * DUSK);
+        }
+
+        let (pk, sk) = keys[0].clone();
+        let ru = RoundUpdate::new(
+            pk,
+            sk,
+            &tip(TIP_HEIGHT, TIP_HASH),
+            TimeoutSet::new(),
+            vec![],
+        );
+
+        let mut committees = RoundCommittees::default();
+        for iter in 0..=iteration + 1 {
+            for step_name in [
+                StepName::Proposal,
+                StepName::Validation,
+                StepName::Ratification,
+            ] {
+                let cfg =
+                    Config::new(ru.seed(), ru.round, iter, step_name, vec![]);
+                committees.insert(
+                    step_name.to_step(iter),
+                    Committee::new(&provisioners, &cfg),
+                );
+            }
+        }
+
+        let db = Arc::new(Mutex::new(NoDatabase));
+        let sv_registry =
+            Arc::new(Mutex::new(AttInfoRegistry::new(ru.clone())));
+        let mut validation =
+            ValidationHandler::new(sv_registry.clone(), db.clone());
+        validation.reset(iteration);
+        let mut ratification = RatificationHandler::new(sv_registry);
+        ratification.reset(
+            iteration,
+            ValidationResult::new(
+                StepVotes::default(),
+                validation_vote,
+                quorum_type(validation_vote),
+            ),
+        );
+
+        Self {
+            ru,
+            committees,
+            step,
+            iteration,
+            keys,
+            proposal: ProposalHandler::new(db),
+            validation,
+            ratification,
+            collected: HashMap::new(),
+            malformed: vec![],
+        }
+    }
+
+    /// Builds the message of `delivery`
+    fn message(&mut self, delivery: &Delivery) -> Message {
+        let tip = match delivery.kind {
+            Kind::PastRound => tip(TIP_HEIGHT - 1, OTHER_TIP_HASH),
+            Kind::FutureRound => tip(TIP_HEIGHT + 1, OTHER_TIP_HASH),
+            Kind::OtherTip => tip(TIP_HEIGHT, OTHER_TIP_HASH),
+            _ => tip(TIP_HEIGHT, TIP_HASH),
+        };
+        let iteration = match delivery.kind {
+            Kind::PastIteration => self.iteration.saturating_sub(1),
+            Kind::FutureIteration => {
+                self.iteration + 1 + delivery.topic / 4 % 2
+            }
+            _ => self.iteration,
+        };
+
+        let topic = delivery.topic();
+        let generator =
+            self.committees.get_generator(iteration).and_then(|g| {
+                self.keys.iter().position(|(pk, _)| *pk.bytes() == g)
+            });
+        let signer = match generator {
+            Some(idx)
+                if topic == Topics::Candidate && delivery.from_generator() =>
+            {
+                idx
+            }
+            _ => usize::from(delivery.signer),
+        };
+        let builder =
+            VoteBuilder::new(self.keys[signer].1.clone(), &tip, iteration);
+
+        let vote = delivery.vote();
+        let result = if delivery.kind == Kind::MalformedVotes {
+            let sv = delivery.malformed_votes();
+            self.malformed.push(sv);
+            ValidationResult::new(
+                sv,
+                Vote::Valid(CANDIDATES[0]),
+                QuorumType::Valid,
+            )
+        } else {
+            ValidationResult::new(StepVotes::default(), vote, quorum_type(vote))
+        };
+
+        let mut msg = match topic {
+            Topics::Candidate => builder.candidate().into(),
+            Topics::Validation => builder.validation(vote).into(),
+            Topics::Ratification => builder.ratification(&result).into(),
+            _ => ValidationQuorum {
+                header: builder.header(),
+                result,
+            }
+            .into(),
+        };
+        if delivery.kind == Kind::BadSignature {
+            corrupt(&mut msg);
+        }
+        msg
+    }
+
+    /// Delivers `msg` as the execution context does, checking the handlers
+    /// behave
+    async fn deliver(
+        &mut self,
+        delivery: Delivery,
+        msg: Message,
+        report: &mut Report,
+    ) {
+        let topic = msg.topic();
+        let valid = match self.step {
+            StepName::Proposal => self.is_valid(&self.proposal, &msg),
+            StepName::Validation => self.is_valid(&self.validation, &msg),
+            StepName::Ratification => self.is_valid(&self.ratification, &msg),
+        };
+
+        match (&valid, delivery.kind) {
+            (
+                Ok(()),
+                Kind::PastRound
+                | Kind::FutureRound
+                | Kind::OtherTip
+                | Kind::BadSignature,
+            ) => panic!("{delivery:?} verified"),
+            (Err(err), kind) => assert!(
+                is_verification_error(kind, err),
+                "unexpected error {err:?} verifying {delivery:?}"
+            ),
+            _ => {}
+        }
+
+        match valid {
+            Ok(()) => {
+                let vote = match &msg.payload {
+                    Payload::Validation(v) => Some((v.get_step(), v.vote)),
+                    Payload::Ratification(r) => Some((r.get_step(), r.vote)),
+                    _ => None,
+                };
+                let signer = msg.get_signer();
+
+                let collected = self.collect(msg).await;
+                match &collected {
+                    Ok(outcome) => {
+                        check_outcome(
+                            outcome,
+                            topic,
+                            self.ru.round,
+                            self.iteration,
+                        );
+                        report.collected += 1;
+                        if let StepOutcome::Ready(_) = outcome {
+                            report.ready += 1;
+                        }
+                        if let (Some((step, vote)), Some(signer)) =
+                            (vote, signer)
+                        {
+                            self.on_vote_collected(step, *signer.bytes(), vote);
+                        }
+                    }
+                    Err(err) => {
+                        assert!(
+                            is_collection_error(err),
+                            "unexpected error {err:?} collecting {delivery:?}"
+                        );
+                        report.rejected += 1;
+                    }
+                }
+            }
+            Err(ConsensusError::PastEvent) => {
+                report.rejected += 1;
+                if msg.header.round == self.ru.round
+                    && is_emergency_iter(msg.header.iteration)
+                {
+                    let iteration = msg.header.iteration;
+                    if let Some(outcome) = self.collect_from_past(msg).await {
+                        check_outcome(
+                            &outcome,
+                            topic,
+                            self.ru.round,
+                            iteration,
+                        );
+                        if let StepOutcome::Ready(_) = outcome {
+                            report.ready += 1;
+                        }
+                    }
+                }
+            }
+            Err(_) => report.rejected += 1,
+        }
+
+        assert!(
+            !self
+                .malformed
+                .contains(self.ratification.validation_result().sv()),
+            "malformed step votes adopted delivering {delivery:?}"
+        );
+    }
+
+    fn is_valid<H: MsgHandler>(
+        &self,
+        handler: &H,
+        msg: &Message,
+    ) -> Result<(), ConsensusError> {
+        let committee = self.committee();
+        handler.is_valid(
+            msg,
+            &self.ru,
+            self.iteration,
+            self.step,
+            committee,
+            &self.committees,
+        )
+    }
+
+    fn committee(&self) -> &Committee {
+        self.committees
+            .get_committee(self.step.to_step(self.iteration))
+            .expect("committee of the step to be generated")
+    }
+
+    async fn collect(
+        &mut self,
+        msg: Message,
+    ) -> Result<StepOutcome, ConsensusError> {
+        let committee = self
+            .committees
+            .get_committee(self.step.to_step(self.iteration))
+            .expect("committee of the step to be generated");
+        let generator = self.committees.get_generator(self.iteration);
+        let (ru, committees) = (&self.ru, &self.committees);
+        match self.step {
+            StepName::Proposal => {
+                self.proposal
+                    .collect(msg, ru, committee, generator, committees)
+                    .await
+            }
+            StepName::Validation => {
+                self.validation
+                    .collect(msg, ru, committee, generator, committees)
+                    .await
+            }
+            StepName::Ratification => {
+                self.ratification
+                    .collect(msg, ru, committee, generator, committees)
+                    .await
+            }
+        }
+    }
+
+    /// Collects a message of a former step, ignoring the errors as the
+    /// execution context does
+    async fn collect_from_past(&mut self, msg: Message) -> Option<StepOutcome> {
+        let committee = self.committees.get_committee(msg.get_step())?;
+        let generator = self.committees.get_generator(msg.header.iteration);
+        let collected = match msg.topic() {
+            Topics::Candidate => {
+                self.proposal
+                    .collect_from_past(msg, committee, generator)
+                    .await
+            }
+            Topics::Validation | Topics::ValidationQuorum => {
+                self.validation
+                    .collect_from_past(msg, committee, generator)
+                    .await
+            }
+            Topics::Ratification => {
+                self.ratification
+                    .collect_from_past(msg, committee, generator)
+                    .await
+            }
+            _ => return None,
+        };
+        collected.ok()
+    }
+
+    fn on_vote_collected(
+        &mut self,
+        step: u8,
+        signer: PublicKeyBytes,
+        vote: Vote,
+    ) {
+        let emergency = is_emergency_iter(self.iteration);
+        // Votes other than Valid aren't aggregated in emergency mode
+        if emergency && !vote.is_valid() {
+            return;
+        }
+        let votes = self.collected.entry((step, signer)).or_default();
+        assert!(!votes.contains(&vote), "vote {vote:?} collected twice");
+        assert!(
+            emergency || votes.is_empty(),
+            "vote {vote:?} collected after {votes:?} from the same signer"
+        );
+        votes.push(vote);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::{RngCore, SeedableRng};
+
+    use super::*;
+
+    fn schedule(
+        step: StepName,
+        iteration: u8,
+        deliveries: Vec<Delivery>,
+    ) -> Schedule {
+        Schedule {
+            step,
+            iteration,
+            validation_vote: Vote::NoQuorum,
+            deliveries,
+        }
+    }
+
+    fn delivery(kind: Kind, signer: u8) -> Delivery {
+        Delivery {
+            kind,
+            signer,
+            // A Valid vote on a validation
+            vote: 0,
+            topic: 1,
+        }
+    }
+
+    #[test]
+    fn test_schedule_decoding() {
+        let schedule = Schedule::decode(&[]);
+        assert_eq!(schedule.step, StepName::Proposal);
+        assert_eq!(schedule.iteration, 0);
+        assert!(schedule.deliveries.is_empty());
+
+        // An incomplete message is discarded
+        let schedule = Schedule::decode(&[2, 3, 0, 1, 2, 3, 4, 5]);
+        assert_eq!(schedule.step, StepName::Ratification);
+        assert_eq!(schedule.iteration, EMERGENCY_MODE_ITERATION_THRESHOLD);
+        assert_eq!(schedule.validation_vote, Vote::Valid(CANDIDATES[0]));
+        assert_eq!(schedule.deliveries.len(), 1);
+        assert_eq!(schedule.deliveries[0].kind, Kind::PastRound);
+
+        let schedule = Schedule::decode(&[0xff; 1024]);
+        assert_eq!(schedule.deliveries.len(), MAX_MESSAGES);
+        assert!(schedule.deliveries.iter().all(|d| d.signer <= PROVISIONERS));
+    }
+
+    #[tokio::test]
+    async fn test_honest_votes() {
+        let deliveries = (0..PROVISIONERS)
+            .map(|i| delivery(Kind::Present, i))
+            .collect();
+        let report = schedule(StepName::Validation, 1, deliveries)
+            .execute()
+            .await;
+        assert!(report.ready > 0);
+    }
+
+    #[tokio::test]
+    async fn test_equivocations() {
+        let deliveries = (0..PROVISIONERS)
+            .flat_map(|i| {
+                [
+                    delivery(Kind::Present, i),
+                    delivery(Kind::Equivocation, i),
+                    delivery(Kind::Duplicate, i),
+                ]
+            })
+            .collect();
+        let report = schedule(StepName::Validation, 0, deliveries)
+            .execute()
+            .await;
+        assert!(report.collected > 0);
+        assert!(report.rejected >= 2 * report.collected);
+    }
+
+    #[test]
+    fn test_adversarial_schedules() {
+        let mut rng = StdRng::seed_from_u64(0xbeef);
+        for _ in 0..8 {
+            let mut data = vec![0; 3 + 4 * 16];
+            rng.fill_bytes(&mut data);
+            run(&data);
+        }
+    }
+}
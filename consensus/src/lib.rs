@@ -32,6 +32,9 @@ pub use validation::step::build_validation_payload;
 mod iteration_ctx;
 pub mod merkle;
 
+#[cfg(any(test, feature = "fuzz"))]
+pub mod fuzz;
+
 #[cfg(test)]
 mod tests {
     // Adding benchmark dependencies here to satisfy `unused_crate_dependencies`
@@ -11,6 +11,8 @@ use node_data::bls::{PublicKey, PublicKeyBytes};
 use node_data::ledger::{
     Block, Fault, Header, Slash, SpentTransaction, Transaction,
 };
+use node_data::message::payload::Vote;
+use node_data::message::Message;
 use node_data::StepName;
 
 use crate::errors::*;
@@ -62,6 +64,31 @@ impl fmt::Display for VerificationOutput {
     }
 }
 
+/// Event of a consensus step, notified for observability
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StepEvent {
+    /// A quorum was reached for the iteration, locally or by the network
+    QuorumReached {
+        round: u64,
+        iteration: u8,
+        vote: Vote,
+    },
+    /// The step timed out, and its timeout was increased to `timeout`
+    TimeoutIncreased {
+        round: u64,
+        iteration: u8,
+        step_name: StepName,
+        timeout: Duration,
+    },
+    /// The step is over, after collecting `count` votes
+    VotesReceived {
+        round: u64,
+        iteration: u8,
+        step_name: StepName,
+        count: usize,
+    },
+}
+
 #[async_trait::async_trait]
 pub trait Operations: Send + Sync {
     async fn verify_candidate_header(
@@ -88,6 +115,28 @@ pub trait Operations: Send + Sync {
         params: CallParams,
     ) -> Result<Output, OperationError>;
 
+    /// Notifies that a step of the given round and iteration has started.
+    async fn step_started(
+        &self,
+        round: u64,
+        iteration: u8,
+        step_name: StepName,
+    );
+
+    /// Notifies the seats the local provisioner has in the committee of the
+    /// given step, out of the `credits` of the whole committee.
+    async fn committee_seats(
+        &self,
+        round: u64,
+        iteration: u8,
+        step_name: StepName,
+        seats: usize,
+        credits: usize,
+    );
+
+    /// Notifies an event of a consensus step
+    async fn step_event(&self, event: StepEvent);
+
     async fn add_step_elapsed_time(
         &self,
         round: u64,
@@ -96,4 +145,20 @@ pub trait Operations: Send + Sync {
     ) -> Result<(), OperationError>;
 
     async fn get_block_gas_limit(&self) -> u64;
+
+    /// Returns the provisioner set commitment to include in the block at
+    /// `round`, if any
+    async fn get_provisioners_root(
+        &self,
+        round: u64,
+        prev_state_root: StateRoot,
+    ) -> Result<Option<[u8; 32]>, OperationError>;
+
+    /// Returns the extra-data to include in the block at `round`, if such
+    /// blocks carry any
+    async fn get_header_extra_data(&self, round: u64) -> Option<Vec<u8>>;
+
+    /// Notifies that `msg` carries an invalid signature, so that the peer
+    /// that relayed it can be penalized
+    async fn report_invalid_signature(&self, msg: &Message);
 }
@@ -11,14 +11,19 @@ use std::time::Instant;
 use dusk_bytes::Serializable;
 use node_data::ledger::{to_str, Block, Fault, IterationsInfo, Seed, Slash};
 use node_data::message::payload::Candidate;
-use node_data::message::{Message, SignedStepMessage, BLOCK_HEADER_VERSION};
+use node_data::message::{
+    Message, SignedStepMessage, BLOCK_HEADER_VERSION,
+    EXTRA_DATA_HEADER_VERSION, PROVISIONERS_ROOT_HEADER_VERSION,
+};
 use node_data::{get_current_timestamp, ledger};
-use tracing::{debug, info};
+use tracing::{debug, error, info};
 
 use crate::commons::RoundUpdate;
 use crate::config::{MAX_BLOCK_SIZE, MAX_NUMBER_OF_FAULTS, MINIMUM_BLOCK_TIME};
+use crate::errors::OperationError;
 use crate::merkle::merkle_root;
 use crate::operations::{CallParams, Operations};
+use crate::validation::step::call_vst;
 
 pub struct Generator<T: Operations> {
     executor: Arc<T>,
@@ -39,6 +44,17 @@ impl<T: Operations> Generator<T> {
             .generate_block(ru, iteration, failed_iterations, &[])
             .await?;
 
+        self.verify_candidate(ru, &candidate).await.map_err(|err| {
+            error!(
+                event = "Refusing to propose invalid candidate",
+                round = ru.round,
+                iteration,
+                hash = &to_str(&candidate.header().hash),
+                ?err,
+            );
+            err
+        })?;
+
         let mut candidate_msg = Candidate { candidate };
 
         candidate_msg.sign(&ru.secret_key, ru.pubkey_bls.inner());
@@ -48,6 +64,31 @@ impl<T: Operations> Generator<T> {
         Ok(candidate_msg.into())
     }
 
+    /// Verifies a generated candidate with the same checks it's going to
+    /// undergo in the Validation step, so that an invalid candidate caused by
+    /// a drift of the local state is never broadcast.
+    async fn verify_candidate(
+        &self,
+        ru: &RoundUpdate,
+        candidate: &Block,
+    ) -> Result<(), OperationError> {
+        let header = candidate.header();
+
+        let (_, voters, _) = self
+            .executor
+            .verify_candidate_header(header, ru.pubkey_bls.bytes())
+            .await
+            .map_err(OperationError::InvalidHeader)?;
+
+        self.executor
+            .verify_faults(header.height, candidate.faults())
+            .await?;
+
+        call_vst(ru.state_root(), candidate, &voters, &self.executor)
+            .await
+            .map_err(OperationError::InvalidVST)
+    }
+
     pub async fn generate_block(
         &self,
         ru: &RoundUpdate,
@@ -89,6 +130,22 @@ impl<T: Operations> Generator<T> {
             ..Default::default()
         };
 
+        if let Some(root) = self
+            .executor
+            .get_provisioners_root(ru.round, ru.state_root())
+            .await?
+        {
+            blk_header.version = PROVISIONERS_ROOT_HEADER_VERSION;
+            blk_header.provisioners_root = root;
+        }
+
+        if let Some(extra_data) =
+            self.executor.get_header_extra_data(ru.round).await
+        {
+            blk_header.version = EXTRA_DATA_HEADER_VERSION;
+            blk_header.extra_data = extra_data;
+        }
+
         let header_size = blk_header.size().map_err(|e| {
             crate::errors::OperationError::InvalidEST(anyhow::anyhow!(
                 "Cannot get header size {e}. This should be a bug"
@@ -107,8 +164,9 @@ impl<T: Operations> Generator<T> {
 
         blk_header.faultroot = merkle_root(&fault_digests);
 
-        // We know for sure that this operation cannot underflow
-        let max_txs_bytes = MAX_BLOCK_SIZE - header_size - faults_size;
+        let max_txs_bytes = MAX_BLOCK_SIZE
+            .saturating_sub(header_size)
+            .saturating_sub(faults_size);
         let voters = ru.att_voters();
 
         let call_params = CallParams {
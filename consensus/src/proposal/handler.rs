@@ -162,7 +162,7 @@ fn verify_candidate_msg(
         .candidate
         .size()
         .map_err(|_| ConsensusError::UnknownBlockSize)?;
-    if candidate_size > MAX_BLOCK_SIZE {
+    if candidate_size > *MAX_BLOCK_SIZE {
         return Err(ConsensusError::InvalidBlockSize(candidate_size));
     }
 
@@ -7,7 +7,7 @@
 use std::collections::{BTreeMap, VecDeque};
 use std::fmt::Debug;
 
-use node_data::message::Message;
+use node_data::message::{MemorySize, Message};
 use thiserror::Error;
 use tracing::warn;
 
@@ -16,8 +16,20 @@ type RoundMap<T> = BTreeMap<u64, StepMap<T>>;
 
 const MAX_MESSAGES_PER_QUEUE: usize = 1000;
 
-#[derive(Debug, Default)]
-pub struct MsgRegistry<T: QueueMessage>(RoundMap<T>);
+#[derive(Debug)]
+pub struct MsgRegistry<T: QueueMessage> {
+    msgs: RoundMap<T>,
+    /// Number of bytes held by the messages
+    bytes: usize,
+    /// Maximum number of bytes held by the messages
+    budget: usize,
+}
+
+impl<T: QueueMessage> Default for MsgRegistry<T> {
+    fn default() -> Self {
+        Self::with_budget(usize::MAX)
+    }
+}
 
 pub trait QueueMessage: Debug + Clone {
     fn step(&self) -> u8;
@@ -25,6 +37,11 @@ pub trait QueueMessage: Debug + Clone {
     fn round(&self) -> u64;
 
     fn signer(&self) -> Option<node_data::bls::PublicKeyBytes>;
+
+    /// Estimate of the memory held by the message
+    fn memory_size(&self) -> usize {
+        std::mem::size_of::<Self>()
+    }
 }
 
 impl QueueMessage for Message {
@@ -37,6 +54,9 @@ impl QueueMessage for Message {
     fn signer(&self) -> Option<node_data::bls::PublicKeyBytes> {
         self.get_signer().map(|s| *s.bytes())
     }
+    fn memory_size(&self) -> usize {
+        MemorySize::memory_size(self)
+    }
 }
 
 #[derive(Debug, Error)]
@@ -45,37 +65,87 @@ pub enum MsgRegistryError<T> {
     SignerAlreadyEnqueue(T),
     #[error("This msg has no signer")]
     NoSigner(T),
+    #[error("Registry over its memory budget")]
+    OverBudget(T),
 }
 
 /// A message registry that stores messages based on their round and step.
 impl<T: QueueMessage> MsgRegistry<T> {
+    /// Creates a registry holding at most `budget` bytes of messages.
+    ///
+    /// When a message doesn't fit, the messages of the rounds farthest from
+    /// it are dropped to make room for it, the ones of its round and of the
+    /// rounds before it being kept.
+    pub fn with_budget(budget: usize) -> Self {
+        Self {
+            msgs: RoundMap::new(),
+            bytes: 0,
+            budget,
+        }
+    }
+
     /// Inserts a message into the registry based on its round and step.
     pub fn put_msg(&mut self, msg: T) -> Result<T, MsgRegistryError<T>> {
         let round = msg.round();
         let step = msg.step();
-        let vec = self
-            .0
-            .entry(round)
-            .or_default()
-            .entry(step)
-            .or_insert(VecDeque::with_capacity(MAX_MESSAGES_PER_QUEUE));
         if msg.signer().is_none() {
             return Err(MsgRegistryError::NoSigner(msg));
         }
-        if vec.iter().any(|m| m.signer() == msg.signer()) {
+        let duplicated = self
+            .msgs
+            .get(&round)
+            .and_then(|r| r.get(&step))
+            .is_some_and(|vec| vec.iter().any(|m| m.signer() == msg.signer()));
+        if duplicated {
             return Err(MsgRegistryError::SignerAlreadyEnqueue(msg));
         }
 
+        let size = msg.memory_size();
+        if !self.make_room(round, size) {
+            warn!("future msgs over budget, dropping ({round}, {step})");
+            return Err(MsgRegistryError::OverBudget(msg));
+        }
+
+        let vec = self
+            .msgs
+            .entry(round)
+            .or_default()
+            .entry(step)
+            .or_insert(VecDeque::with_capacity(MAX_MESSAGES_PER_QUEUE));
         if vec.len() == vec.capacity() {
             warn!("queue ({}, {}) is full, dropping", round, step);
-            vec.pop_front();
+            if let Some(dropped) = vec.pop_front() {
+                self.bytes -= dropped.memory_size();
+            }
         }
 
         let ret = msg.clone();
         vec.push_back(msg);
+        self.bytes += size;
         Ok(ret)
     }
 
+    /// Drops the messages of the rounds after `round`, farthest first, until
+    /// `size` more bytes fit the budget. Returns whether they fit.
+    fn make_room(&mut self, round: u64, size: usize) -> bool {
+        while self.bytes.saturating_add(size) > self.budget {
+            let Some(mut last) = self.msgs.last_entry() else {
+                break;
+            };
+            if *last.key() <= round {
+                break;
+            }
+            let steps = last.get_mut();
+            match steps.values_mut().rev().find_map(VecDeque::pop_back) {
+                Some(dropped) => self.bytes -= dropped.memory_size(),
+                None => {
+                    last.remove();
+                }
+            }
+        }
+        self.bytes.saturating_add(size) <= self.budget
+    }
+
     /// Drains and returns all messages that belong to the specified round and
     /// step.
     pub fn drain_msg_by_round_step(
@@ -83,18 +153,19 @@ impl<T: QueueMessage> MsgRegistry<T> {
         round: u64,
         step: u8,
     ) -> Option<VecDeque<T>> {
-        self.0
+        let msgs = self
+            .msgs
             .get_mut(&round)
-            .and_then(|r| r.remove_entry(&step).map(|(_, v)| v))
+            .and_then(|r| r.remove_entry(&step).map(|(_, v)| v))?;
+        self.bytes -= msgs.iter().map(T::memory_size).sum::<usize>();
+        Some(msgs)
     }
 
     /// Removes all messages that belong to the specified round.
     pub fn remove_msgs_by_round(&mut self, round: u64) {
-        if let Some(r) = self.0.get_mut(&round) {
-            r.clear();
-        };
-
-        self.0.remove(&round);
+        if let Some(r) = self.msgs.remove(&round) {
+            self.bytes -= Self::steps_size(&r);
+        }
     }
 
     /// Removes all messages that do not belong to the range (closed interval)
@@ -102,21 +173,31 @@ impl<T: QueueMessage> MsgRegistry<T> {
     pub fn remove_msgs_out_of_range(&mut self, start_round: u64, offset: u64) {
         let end_round = start_round + offset;
 
-        self.0 = self
-            .0
+        self.msgs = self
+            .msgs
             .split_off(&start_round)
             .into_iter()
             .filter(|(k, _)| *k <= end_round)
             .collect();
+        self.bytes = self.msgs.values().map(Self::steps_size).sum();
     }
 
     /// Returns the total number of messages in the registry.
     pub fn msg_count(&self) -> usize {
-        self.0
+        self.msgs
             .values()
             .map(|round| round.values().map(|items| items.len()).sum::<usize>())
             .sum()
     }
+
+    /// Returns the number of bytes held by the messages in the registry.
+    pub fn memory_usage(&self) -> usize {
+        self.bytes
+    }
+
+    fn steps_size(steps: &StepMap<T>) -> usize {
+        steps.values().flatten().map(T::memory_size).sum()
+    }
 }
 
 #[cfg(test)]
@@ -209,4 +290,31 @@ mod tests {
         assert!(reg.drain_msg_by_round_step(round + 2, 1).is_some());
         Ok(())
     }
+
+    #[test]
+    fn test_budget() -> Result<(), super::MsgRegistryError<Item>> {
+        let size = std::mem::size_of::<Item>();
+        let mut reg = MsgRegistry::<Item>::with_budget(3 * size);
+        reg.put_msg(Item::new(10, 1, 1))?;
+        reg.put_msg(Item::new(10, 1, 2))?;
+        reg.put_msg(Item::new(20, 1, 1))?;
+        assert_eq!(reg.memory_usage(), 3 * size);
+
+        // The farthest round makes room for a nearer one
+        reg.put_msg(Item::new(15, 1, 1))?;
+        assert_eq!(reg.msg_count(), 3);
+        assert!(reg.drain_msg_by_round_step(20, 1).is_none());
+
+        // Nothing farther makes room for the farthest round
+        assert!(matches!(
+            reg.put_msg(Item::new(30, 1, 1)),
+            Err(super::MsgRegistryError::OverBudget(_))
+        ));
+
+        reg.drain_msg_by_round_step(10, 1);
+        assert_eq!(reg.memory_usage(), size);
+        reg.remove_msgs_out_of_range(16, 10);
+        assert_eq!(reg.memory_usage(), 0);
+        Ok(())
+    }
 }
@@ -4,4 +4,5 @@
 //
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
+pub mod bundle;
 pub mod verifiers;
@@ -0,0 +1,531 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Quorum certificate bundles.
+//!
+//! A bundle packs the header of a block, whose attestation finalizes it, with
+//! the committees that produced the attestation, so that third parties can
+//! archive and verify the finality of the block without running a node.
+//!
+//! The members of each committee are listed in the canonical order the
+//! attestation bitsets refer to, along with their credits and the aggregate
+//! key of the members that voted.
+//!
+//! A bundle is bound to the provisioner set it was extracted from by the
+//! commitment to that set, which blocks carrying a provisioners root commit
+//! to as well. [`verify_bundle`] checks the attestation against the bundled
+//! committees, while [`verify_bundle_with`] also extracts the committees again
+//! from provisioners known to the auditor.
+//!
+//! Bundles are encoded with [`Serializable`] as the canonical binary format,
+//! and with `serde` for JSON.
+
+use std::collections::BTreeMap;
+use std::io::{self, Read, Write};
+
+use dusk_bytes::Serializable as BytesSerializable;
+use node_data::bls::{PublicKey, PublicKeyBytes};
+use node_data::ledger::{Header, Seed, StepVotes};
+use node_data::message::payload::{RatificationResult, Vote};
+use node_data::message::{ConsensusHeader, PROVISIONERS_ROOT_HEADER_VERSION};
+use node_data::{Serializable, StepName};
+use serde::ser::SerializeStruct;
+use serde::Serialize;
+
+use crate::config::{
+    exclude_next_generator, RATIFICATION_COMMITTEE_CREDITS,
+    VALIDATION_COMMITTEE_CREDITS,
+};
+use crate::errors::BundleError;
+use crate::quorum::verifiers::verify_votes;
+use crate::user::committee::Committee;
+use crate::user::provisioners::Provisioners;
+use crate::user::sortition;
+
+/// Version of the bundle format
+pub const QUORUM_BUNDLE_VERSION: u8 = 1;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BundleMember {
+    pub key: PublicKeyBytes,
+    pub credits: u8,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BundleCommittee {
+    /// Members of the committee, in canonical order
+    pub members: Vec<BundleMember>,
+    /// Aggregate key of the members that voted
+    #[serde(serialize_with = "node_data::serialize_hex")]
+    pub aggregate_key: [u8; 96],
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuorumBundle {
+    pub version: u8,
+    /// Header of the block, including its attestation
+    pub header: Header,
+    /// Seed of the previous block, from which the committees are extracted
+    pub prev_seed: Seed,
+    /// Commitment to the provisioner set the committees are extracted from
+    pub provisioners_root: [u8; 32],
+    pub validation: BundleCommittee,
+    pub ratification: BundleCommittee,
+}
+
+fn committee_credits(step: StepName) -> usize {
+    match step {
+        StepName::Ratification => RATIFICATION_COMMITTEE_CREDITS,
+        _ => VALIDATION_COMMITTEE_CREDITS,
+    }
+}
+
+/// Extracts the committee of `step` for the block of `header`
+fn step_committee(
+    header: &Header,
+    prev_seed: Seed,
+    provisioners: &Provisioners,
+    step: StepName,
+) -> Committee {
+    let round = header.height;
+    let iteration = header.iteration;
+
+    let mut exclusion_list =
+        vec![provisioners.get_generator(iteration, prev_seed, round)];
+    if exclude_next_generator(iteration) {
+        exclusion_list.push(provisioners.get_generator(
+            iteration + 1,
+            prev_seed,
+            round,
+        ));
+    }
+
+    let cfg = sortition::Config::new(
+        prev_seed,
+        round,
+        iteration,
+        step,
+        exclusion_list,
+    );
+    Committee::new(provisioners, &cfg)
+}
+
+/// Lists the members of `committee` in canonical order
+fn bundle_members(committee: &Committee) -> Vec<BundleMember> {
+    committee
+        .members()
+        .iter()
+        .map(|(key, credits)| BundleMember {
+            key: *key.bytes(),
+            credits: *credits as u8,
+        })
+        .collect()
+}
+
+impl BundleCommittee {
+    fn new(
+        committee: &Committee,
+        step: StepName,
+        step_votes: &StepVotes,
+    ) -> Result<Self, BundleError> {
+        let members = bundle_members(committee);
+        let aggregate_key = committee
+            .intersect(step_votes.bitset)
+            .aggregate_pks()
+            .map_err(|e| BundleError::InvalidVotes(step, e))?
+            .to_bytes();
+
+        Ok(Self {
+            members,
+            aggregate_key,
+        })
+    }
+
+    fn verify(
+        &self,
+        header: &ConsensusHeader,
+        step: StepName,
+        vote: &Vote,
+        step_votes: &StepVotes,
+    ) -> Result<(), BundleError> {
+        let invalid = |reason| BundleError::InvalidCommittee(step, reason);
+
+        if self.members.len() > u64::BITS as usize {
+            return Err(invalid("too many members"));
+        }
+
+        let mut members = BTreeMap::new();
+        for member in &self.members {
+            let key = PublicKey::try_from(member.key.0)
+                .map_err(|_| invalid("invalid member key"))?;
+            if member.credits == 0 {
+                return Err(invalid("member without credits"));
+            }
+            if members
+                .last_key_value()
+                .is_some_and(|(last, _)| last >= &key)
+            {
+                return Err(invalid("members not in canonical order"));
+            }
+            members.insert(key, member.credits as usize);
+        }
+
+        let credits = committee_credits(step);
+        if members.values().sum::<usize>() != credits {
+            return Err(invalid("wrong committee credits"));
+        }
+        let committee = Committee::from_members(members, credits);
+
+        verify_votes(header, step, vote, step_votes, &committee)
+            .map_err(|e| BundleError::InvalidVotes(step, e))?;
+
+        let aggregate_key = committee
+            .intersect(step_votes.bitset)
+            .aggregate_pks()
+            .map_err(|e| BundleError::InvalidVotes(step, e))?;
+        if aggregate_key.to_bytes() != self.aggregate_key {
+            return Err(BundleError::InvalidAggregateKey(step));
+        }
+
+        Ok(())
+    }
+}
+
+impl QuorumBundle {
+    /// Exports the quorum certificate of the block of `header`.
+    ///
+    /// The committees are extracted from `provisioners`, the provisioners
+    /// eligible for the block, and `prev_seed`, the seed of the previous
+    /// block. The bundle is verified before being returned.
+    pub fn export(
+        header: &Header,
+        prev_seed: Seed,
+        provisioners: &Provisioners,
+    ) -> Result<Self, BundleError> {
+        let att = &header.att;
+        let committee = |step, step_votes| {
+            let committee =
+                step_committee(header, prev_seed, provisioners, step);
+            BundleCommittee::new(&committee, step, step_votes)
+        };
+
+        let bundle = Self {
+            version: QUORUM_BUNDLE_VERSION,
+            header: header.clone(),
+            prev_seed,
+            provisioners_root: provisioners.commitment(),
+            validation: committee(StepName::Validation, &att.validation)?,
+            ratification: committee(StepName::Ratification, &att.ratification)?,
+        };
+        verify_bundle(&bundle)?;
+        Ok(bundle)
+    }
+}
+
+/// Verifies that the attestation of `bundle` finalizes its block.
+///
+/// The block hash is recomputed from the header, and the votes of both steps
+/// are checked to reach the quorum of the bundled committees, under their
+/// aggregate keys. If the block commits to its provisioner set, the bundle
+/// must have been extracted from that same set.
+pub fn verify_bundle(bundle: &QuorumBundle) -> Result<(), BundleError> {
+    if bundle.version != QUORUM_BUNDLE_VERSION {
+        return Err(BundleError::UnsupportedVersion(bundle.version));
+    }
+
+    let header = &bundle.header;
+    let hash = header
+        .compute_hash()
+        .map_err(|_| BundleError::InvalidBlockHash)?;
+    if hash != header.hash {
+        return Err(BundleError::InvalidBlockHash);
+    }
+
+    if header.version >= PROVISIONERS_ROOT_HEADER_VERSION
+        && header.provisioners_root != bundle.provisioners_root
+    {
+        return Err(BundleError::InvalidProvisionersRoot);
+    }
+
+    let att = &header.att;
+    let vote = Vote::Valid(hash);
+    if att.result != RatificationResult::Success(vote) {
+        return Err(BundleError::InvalidResult(att.result));
+    }
+
+    let consensus_header = header.to_consensus_header();
+    bundle.validation.verify(
+        &consensus_header,
+        StepName::Validation,
+        &vote,
+        &att.validation,
+    )?;
+    bundle.ratification.verify(
+        &consensus_header,
+        StepName::Ratification,
+        &vote,
+        &att.ratification,
+    )?;
+
+    Ok(())
+}
+
+/// Verifies `bundle` as [`verify_bundle`] does, also checking that its
+/// committees are the ones extracted from `provisioners`.
+///
+/// `provisioners` are the provisioners eligible for the block of the bundle,
+/// as known to the auditor.
+pub fn verify_bundle_with(
+    bundle: &QuorumBundle,
+    provisioners: &Provisioners,
+) -> Result<(), BundleError> {
+    if provisioners.commitment() != bundle.provisioners_root {
+        return Err(BundleError::InvalidProvisionersRoot);
+    }
+
+    let header = &bundle.header;
+    for (step, bundled) in [
+        (StepName::Validation, &bundle.validation),
+        (StepName::Ratification, &bundle.ratification),
+    ] {
+        let committee =
+            step_committee(header, bundle.prev_seed, provisioners, step);
+        if bundle_members(&committee) != bundled.members {
+            return Err(BundleError::InvalidCommittee(
+                step,
+                "not extracted from the provisioners",
+            ));
+        }
+    }
+
+    verify_bundle(bundle)
+}
+
+impl Serialize for QuorumBundle {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        // The attestation is not part of the header serialization
+        let mut bundle = serializer.serialize_struct("QuorumBundle", 7)?;
+        bundle.serialize_field("version", &self.version)?;
+        bundle.serialize_field("header", &self.header)?;
+        bundle.serialize_field("attestation", &self.header.att)?;
+        bundle.serialize_field("prev_seed", &self.prev_seed)?;
+        bundle.serialize_field(
+            "provisioners_root",
+            &hex::encode(self.provisioners_root),
+        )?;
+        bundle.serialize_field("validation", &self.validation)?;
+        bundle.serialize_field("ratification", &self.ratification)?;
+        bundle.end()
+    }
+}
+
+impl Serializable for BundleCommittee {
+    fn write<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        let len = u8::try_from(self.members.len()).map_err(|_| {
+            io::Error::new(io::ErrorKind::InvalidData, "too many members")
+        })?;
+        w.write_all(&[len])?;
+        for member in &self.members {
+            w.write_all(member.key.inner())?;
+            w.write_all(&[member.credits])?;
+        }
+        w.write_all(&self.aggregate_key)
+    }
+
+    fn read<R: Read>(r: &mut R) -> io::Result<Self>
+    where
+        Self: Sized,
+    {
+        let len = Self::read_u8(r)?;
+        let mut members = Vec::with_capacity(len as usize);
+        for _ in 0..len {
+            let key = PublicKeyBytes(Self::read_bytes(r)?);
+            let credits = Self::read_u8(r)?;
+            members.push(BundleMember { key, credits });
+        }
+        let aggregate_key = Self::read_bytes(r)?;
+
+        Ok(Self {
+            members,
+            aggregate_key,
+        })
+    }
+}
+
+impl Serializable for QuorumBundle {
+    fn write<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        w.write_all(&[self.version])?;
+        self.header.write(w)?;
+        w.write_all(self.prev_seed.inner())?;
+        w.write_all(&self.provisioners_root)?;
+        self.validation.write(w)?;
+        self.ratification.write(w)
+    }
+
+    fn read<R: Read>(r: &mut R) -> io::Result<Self>
+    where
+        Self: Sized,
+    {
+        let version = Self::read_u8(r)?;
+        if version != QUORUM_BUNDLE_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unsupported bundle version {version}"),
+            ));
+        }
+
+        Ok(Self {
+            version,
+            header: Header::read(r)?,
+            prev_seed: Seed::from(Self::read_bytes(r)?),
+            provisioners_root: Self::read_bytes(r)?,
+            validation: BundleCommittee::read(r)?,
+            ratification: BundleCommittee::read(r)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use dusk_core::signatures::bls::{
+        PublicKey as BlsPublicKey, SecretKey as BlsSecretKey,
+    };
+    use node_data::ledger::Attestation;
+    use node_data::message::{payload, SignedStepMessage};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::*;
+    use crate::user::provisioners::DUSK;
+
+    /// Signs the vote of `step` with all the members of `committee`
+    fn sign_step(
+        header: &ConsensusHeader,
+        step: StepName,
+        vote: &Vote,
+        committee: &Committee,
+        sks: &BTreeMap<PublicKey, BlsSecretKey>,
+    ) -> StepVotes {
+        let mut msg = header.signable();
+        msg.extend_from_slice(match step {
+            StepName::Validation => payload::Validation::SIGN_SEED,
+            _ => payload::Ratification::SIGN_SEED,
+        });
+        vote.write(&mut msg).unwrap();
+
+        let sigs: Vec<_> = committee
+            .iter()
+            .map(|pk| sks[pk].sign_multisig(pk.inner(), &msg))
+            .collect();
+        let signature = sigs[0].aggregate(&sigs[1..]);
+        let bitset = (1u64 << committee.size()) - 1;
+        StepVotes::new(signature.to_bytes(), bitset)
+    }
+
+    #[test]
+    fn test_bundle_export_verify() {
+        let mut rng = StdRng::seed_from_u64(0xbeef);
+        let mut provisioners = Provisioners::empty();
+        let mut sks = BTreeMap::new();
+        for _ in 0..10 {
+            let sk = BlsSecretKey::random(&mut rng);
+            let pk = PublicKey::new(BlsPublicKey::from(&sk));
+            provisioners.add_member_with_value(pk.clone(), 1000 * DUSK);
+            sks.insert(pk, sk);
+        }
+
+        let prev_seed = Seed::from([7; 48]);
+        let mut header = Header {
+            version: PROVISIONERS_ROOT_HEADER_VERSION,
+            height: 1,
+            seed: Seed::from([3; 48]),
+            provisioners_root: provisioners.commitment(),
+            ..Default::default()
+        };
+        header.hash = header.compute_hash().unwrap();
+        let vote = Vote::Valid(header.hash);
+
+        let consensus_header = header.to_consensus_header();
+        let mut att = Attestation {
+            result: RatificationResult::Success(vote),
+            ..Default::default()
+        };
+        for step in [StepName::Validation, StepName::Ratification] {
+            let committee =
+                step_committee(&header, prev_seed, &provisioners, step);
+            let step_votes =
+                sign_step(&consensus_header, step, &vote, &committee, &sks);
+            match step {
+                StepName::Validation => att.validation = step_votes,
+                _ => att.ratification = step_votes,
+            }
+        }
+        header.att = att;
+
+        let bundle = QuorumBundle::export(&header, prev_seed, &provisioners)
+            .expect("bundle to be exported");
+
+        let mut bytes = vec![];
+        bundle.write(&mut bytes).unwrap();
+        let decoded = QuorumBundle::read(&mut &bytes[..]).unwrap();
+        assert_eq!(decoded, bundle);
+        verify_bundle(&decoded).expect("decoded bundle to be valid");
+        verify_bundle_with(&decoded, &provisioners)
+            .expect("decoded bundle to match the provisioners");
+
+        let json = serde_json::to_value(&bundle).unwrap();
+        let bitset = bundle.header.att.validation.bitset;
+        assert_eq!(json["attestation"]["validation"]["bitset"], bitset);
+
+        // The bundle is bound to the provisioners committed by the block
+        let mut others = provisioners.clone();
+        let sk = BlsSecretKey::random(&mut rng);
+        let pk = PublicKey::new(BlsPublicKey::from(&sk));
+        others.add_member_with_value(pk, 1000 * DUSK);
+        assert!(matches!(
+            verify_bundle_with(&bundle, &others),
+            Err(BundleError::InvalidProvisionersRoot)
+        ));
+        let mut tampered = bundle.clone();
+        tampered.provisioners_root = others.commitment();
+        assert!(matches!(
+            verify_bundle(&tampered),
+            Err(BundleError::InvalidProvisionersRoot)
+        ));
+
+        // The committees are extracted from the seed of the previous block
+        let mut tampered = bundle.clone();
+        tampered.prev_seed = Seed::from([8; 48]);
+        assert!(matches!(
+            verify_bundle_with(&tampered, &provisioners),
+            Err(BundleError::InvalidCommittee(StepName::Validation, _))
+        ));
+
+        // A committee with other credits doesn't reach the same quorum
+        let mut tampered = bundle.clone();
+        tampered.ratification.members[0].credits += 1;
+        assert!(matches!(
+            verify_bundle(&tampered),
+            Err(BundleError::InvalidCommittee(StepName::Ratification, _))
+        ));
+
+        // Votes of other members don't match the signature
+        let mut tampered = bundle.clone();
+        tampered.header.att.validation.bitset >>= 1;
+        assert!(verify_bundle(&tampered).is_err());
+
+        // An attestation is only valid for its block
+        let mut tampered = bundle;
+        tampered.header.seed = Seed::from([4; 48]);
+        assert!(matches!(
+            verify_bundle(&tampered),
+            Err(BundleError::InvalidBlockHash)
+        ));
+    }
+}
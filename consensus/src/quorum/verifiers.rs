@@ -22,6 +22,7 @@ use crate::errors::StepSigError;
 use crate::operations::Voter;
 use crate::user::cluster::Cluster;
 use crate::user::committee::{Committee, CommitteeSet};
+use crate::user::provisioners::Provisioners;
 use crate::user::sortition;
 
 pub async fn verify_step_votes(
@@ -32,30 +33,40 @@ pub async fn verify_step_votes(
     seed: Seed,
     step: StepName,
 ) -> Result<(QuorumResult, Vec<Voter>), StepSigError> {
-    let round = header.round;
-    let iteration = header.iteration;
-
-    let mut exclusion_list = vec![];
-    let generator = committees_set
-        .read()
-        .await
-        .provisioners()
-        .get_generator(iteration, seed, round);
-
-    exclusion_list.push(generator);
-
-    if exclude_next_generator(iteration) {
-        let next_generator = committees_set
-            .read()
-            .await
-            .provisioners()
-            .get_generator(iteration + 1, seed, round);
-
-        exclusion_list.push(next_generator);
-    }
+    let mut deferred = DeferredSignatures::default();
+    let result = verify_step_votes_deferred(
+        header,
+        vote,
+        sv,
+        committees_set,
+        seed,
+        step,
+        &mut deferred,
+    )
+    .await?;
+    deferred.verify().map_err(|(_, e)| e)?;
+
+    Ok(result)
+}
 
-    let cfg =
-        sortition::Config::new(seed, round, iteration, step, exclusion_list);
+/// Verifies the quorum of the step votes, deferring the verification of their
+/// signature to `deferred`
+pub async fn verify_step_votes_deferred(
+    header: &ConsensusHeader,
+    vote: &Vote,
+    sv: &StepVotes,
+    committees_set: &RwLock<CommitteeSet<'_>>,
+    seed: Seed,
+    step: StepName,
+    deferred: &mut DeferredSignatures,
+) -> Result<(QuorumResult, Vec<Voter>), StepSigError> {
+    let cfg = step_config(
+        committees_set.read().await.provisioners(),
+        header.round,
+        header.iteration,
+        seed,
+        step,
+    );
 
     if committees_set.read().await.get(&cfg).is_none() {
         let _ = committees_set.write().await.get_or_create(&cfg);
@@ -64,8 +75,8 @@ pub async fn verify_step_votes(
     let set = committees_set.read().await;
     let committee = set.get(&cfg).expect("committee to be created");
 
-    let (quorum_result, voters) =
-        verify_votes(header, step, vote, sv, committee)
+    let (quorum_result, voters, signature) =
+        check_votes(header, step, vote, sv, committee)
         .map_err(|e|
             {
                 error!( "invalid {:?}, vote = {:?}, round = {}, iter = {}, seed = {}, sv = {:?}, err = {}",
@@ -81,9 +92,48 @@ pub async fn verify_step_votes(
             }
         )?;
 
+    if let Some(signature) = signature {
+        deferred.push(signature);
+    }
+
     Ok((quorum_result, voters))
 }
 
+/// Returns the sortition config of the committee of `step`, excluding the
+/// generator of the iteration, and the one of the next iteration if any
+fn step_config(
+    provisioners: &Provisioners,
+    round: u64,
+    iteration: u8,
+    seed: Seed,
+    step: StepName,
+) -> sortition::Config {
+    let mut exclusion_list =
+        vec![provisioners.get_generator(iteration, seed, round)];
+    if exclude_next_generator(iteration) {
+        exclusion_list.push(provisioners.get_generator(
+            iteration + 1,
+            seed,
+            round,
+        ));
+    }
+
+    sortition::Config::new(seed, round, iteration, step, exclusion_list)
+}
+
+/// Returns the committee whose votes of `step` are verified for the given
+/// `round` and `iteration`
+pub fn step_committee(
+    provisioners: &Provisioners,
+    round: u64,
+    iteration: u8,
+    seed: Seed,
+    step: StepName,
+) -> Committee {
+    let cfg = step_config(provisioners, round, iteration, seed, step);
+    Committee::new(provisioners, &cfg)
+}
+
 pub struct QuorumResult {
     pub total: usize,
     pub target_quorum: usize,
@@ -102,6 +152,24 @@ pub fn verify_votes(
     step_votes: &StepVotes,
     committee: &Committee,
 ) -> Result<(QuorumResult, Vec<Voter>), StepSigError> {
+    let (quorum_result, voters, signature) =
+        check_votes(header, step, vote, step_votes, committee)?;
+    if let Some(signature) = signature {
+        signature.verify()?;
+    }
+    Ok((quorum_result, voters))
+}
+
+/// Checks the quorum of the step votes, returning the aggregate signature to
+/// verify, if any
+fn check_votes(
+    header: &ConsensusHeader,
+    step: StepName,
+    vote: &Vote,
+    step_votes: &StepVotes,
+    committee: &Committee,
+) -> Result<(QuorumResult, Vec<Voter>, Option<PendingSignature>), StepSigError>
+{
     let bitset = step_votes.bitset;
     let signature = step_votes.aggregate_signature().inner();
     let sub_committee = committee.intersect(bitset);
@@ -137,19 +205,22 @@ pub fn verify_votes(
     // fail to pass the quorum and results in VoteSetTooSmall.
     // FIXME: Anyway this should be handled properly, maybe with a different
     // function
-    if bitset > 0 {
+    let pending = if bitset > 0 {
         // aggregate public keys
         let apk = sub_committee.aggregate_pks()?;
 
-        // verify signatures
-        verify_step_signature(header, step, vote, apk, signature)?;
-    }
-    // Verification done
-    Ok((quorum_result, sub_committee.to_voters()))
+        Some(PendingSignature::new(header, step, vote, apk, signature)?)
+    } else {
+        None
+    };
+
+    Ok((quorum_result, sub_committee.to_voters(), pending))
 }
 
 impl Cluster<PublicKey> {
-    fn aggregate_pks(&self) -> Result<BlsMultisigPublicKey, StepSigError> {
+    pub(crate) fn aggregate_pks(
+        &self,
+    ) -> Result<BlsMultisigPublicKey, StepSigError> {
         let pks: Vec<_> =
             self.iter().map(|(pubkey, _)| *pubkey.inner()).collect();
         Ok(BlsMultisigPublicKey::aggregate(&pks)?)
@@ -160,26 +231,108 @@ impl Cluster<PublicKey> {
     }
 }
 
-fn verify_step_signature(
-    header: &ConsensusHeader,
+/// Aggregate signature of a step, along with the key and message it is to be
+/// verified against
+pub struct PendingSignature {
     step: StepName,
-    vote: &Vote,
     apk: BlsMultisigPublicKey,
-    signature: &[u8; 48],
-) -> Result<(), StepSigError> {
-    // Compile message to verify
-    let sign_seed = match step {
-        StepName::Validation => payload::Validation::SIGN_SEED,
-        StepName::Ratification => payload::Ratification::SIGN_SEED,
-        StepName::Proposal => Err(StepSigError::InvalidType)?,
-    };
+    sig: BlsMultisigSignature,
+    msg: Vec<u8>,
+}
+
+impl PendingSignature {
+    fn new(
+        header: &ConsensusHeader,
+        step: StepName,
+        vote: &Vote,
+        apk: BlsMultisigPublicKey,
+        signature: &[u8; 48],
+    ) -> Result<Self, StepSigError> {
+        // Compile message to verify
+        let sign_seed = match step {
+            StepName::Validation => payload::Validation::SIGN_SEED,
+            StepName::Ratification => payload::Ratification::SIGN_SEED,
+            StepName::Proposal => Err(StepSigError::InvalidType)?,
+        };
+
+        let sig = BlsMultisigSignature::from_bytes(signature)?;
+        let mut msg = header.signable();
+        msg.extend_from_slice(sign_seed);
+        vote.write(&mut msg).expect("Writing to vec should succeed");
+
+        Ok(Self {
+            step,
+            apk,
+            sig,
+            msg,
+        })
+    }
 
-    let sig = BlsMultisigSignature::from_bytes(signature)?;
-    let mut msg = header.signable();
-    msg.extend_from_slice(sign_seed);
-    vote.write(&mut msg).expect("Writing to vec should succeed");
-    apk.verify(&sig, &msg)?;
-    Ok(())
+    pub fn step(&self) -> StepName {
+        self.step
+    }
+
+    pub fn verify(&self) -> Result<(), StepSigError> {
+        self.apk.verify(&self.sig, &self.msg)?;
+        Ok(())
+    }
+}
+
+/// Step signatures collected across the attestations of a block, to be
+/// verified once all their quorums are checked.
+///
+/// This is not a batch verification: each signature is verified on its own,
+/// the signatures being spread over parallel threads. The first invalid one
+/// is reported.
+#[derive(Default)]
+pub struct DeferredSignatures {
+    pending: Vec<PendingSignature>,
+}
+
+impl DeferredSignatures {
+    pub fn push(&mut self, signature: PendingSignature) {
+        self.pending.push(signature);
+    }
+
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Verifies all the deferred signatures, returning the step and the error
+    /// of the first invalid one
+    pub fn verify(self) -> Result<(), (StepName, StepSigError)> {
+        let check = |s: &PendingSignature| s.verify().map_err(|e| (s.step, e));
+
+        if self.pending.len() <= 1 {
+            return self.pending.iter().try_for_each(check);
+        }
+
+        let threads = std::thread::available_parallelism()
+            .map_or(1, |n| n.get())
+            .min(self.pending.len());
+        let chunk_size = self.pending.len().div_ceil(threads);
+
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = self
+                .pending
+                .chunks(chunk_size)
+                .map(|chunk| {
+                    scope.spawn(move || chunk.iter().try_for_each(check))
+                })
+                .collect();
+
+            // Join all the threads before reporting, in insertion order
+            let results: Vec<_> = handles
+                .into_iter()
+                .map(|h| h.join().expect("verification not to panic"))
+                .collect();
+            results.into_iter().collect()
+        })
+    }
 }
 
 pub async fn get_step_voters(
@@ -242,3 +395,55 @@ async fn get_step_committee(
 
     committee.clone()
 }
+
+#[cfg(test)]
+mod tests {
+    use dusk_core::signatures::bls::{
+        PublicKey as BlsPublicKey, SecretKey as BlsSecretKey,
+    };
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::*;
+
+    fn pending(
+        sk: &BlsSecretKey,
+        step: StepName,
+        msg: &[u8],
+    ) -> PendingSignature {
+        let pk = BlsPublicKey::from(sk);
+        PendingSignature {
+            step,
+            apk: BlsMultisigPublicKey::aggregate(&[pk]).unwrap(),
+            sig: sk.sign_multisig(&pk, msg),
+            msg: msg.to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_deferred_signatures() {
+        let mut rng = StdRng::seed_from_u64(0xbeef);
+        let sks: Vec<_> =
+            (0..8).map(|_| BlsSecretKey::random(&mut rng)).collect();
+
+        let mut deferred = DeferredSignatures::default();
+        for (i, sk) in sks.iter().enumerate() {
+            deferred.push(pending(sk, StepName::Validation, &[i as u8]));
+        }
+        assert_eq!(deferred.len(), 8);
+        assert!(deferred.verify().is_ok());
+
+        let mut deferred = DeferredSignatures::default();
+        for (i, sk) in sks.iter().enumerate() {
+            deferred.push(pending(sk, StepName::Validation, &[i as u8]));
+        }
+        // A signature over another message is reported
+        let mut forged = pending(&sks[0], StepName::Ratification, &[0]);
+        forged.msg = vec![1];
+        deferred.push(forged);
+        assert!(matches!(
+            deferred.verify(),
+            Err((StepName::Ratification, StepSigError::VerificationFailed(_)))
+        ));
+    }
+}
@@ -57,6 +57,20 @@ impl Committee {
         committee
     }
 
+    /// Creates a committee out of its members and their credits, with the
+    /// quorums of a committee of `committee_credits`.
+    pub(crate) fn from_members(
+        members: BTreeMap<PublicKey, usize>,
+        committee_credits: usize,
+    ) -> Self {
+        Self {
+            members,
+            super_majority: supermajority(committee_credits),
+            majority: majority(committee_credits),
+            excluded: vec![],
+        }
+    }
+
     pub fn excluded(&self) -> &Vec<PublicKeyBytes> {
         &self.excluded
     }
@@ -84,6 +98,11 @@ impl Committee {
         self.members.len()
     }
 
+    /// Returns the number of credits assigned to the committee members.
+    pub fn credits(&self) -> usize {
+        self.members.values().sum()
+    }
+
     /// Returns target supermajority quorum for the generated committee.
     pub fn super_majority_quorum(&self) -> usize {
         self.super_majority
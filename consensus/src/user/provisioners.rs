@@ -13,8 +13,10 @@ use node_data::bls::{PublicKey, PublicKeyBytes};
 use node_data::ledger::Seed;
 use node_data::StepName;
 use num_bigint::BigInt;
+use sha3::{Digest, Sha3_256};
 
 use super::committee::Committee;
+use crate::merkle::merkle_root;
 use crate::user::sortition;
 use crate::user::stake::Stake;
 
@@ -29,6 +31,26 @@ impl Provisioners {
     pub fn iter(&self) -> impl Iterator<Item = (&PublicKey, &Stake)> {
         self.members.iter()
     }
+
+    /// Returns a commitment to the provisioner set.
+    ///
+    /// The commitment is the merkle root of the provisioners sorted by public
+    /// key, each leaf hashing the public key, the stake value and its
+    /// eligibility.
+    pub fn commitment(&self) -> [u8; 32] {
+        let leaves: Vec<[u8; 32]> = self
+            .members
+            .iter()
+            .map(|(pk, stake)| {
+                let mut hasher = Sha3_256::new();
+                hasher.update(pk.bytes().inner());
+                hasher.update(stake.value().to_le_bytes());
+                hasher.update(stake.eligible_since.to_le_bytes());
+                hasher.finalize().into()
+            })
+            .collect();
+        merkle_root(&leaves[..])
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -121,6 +143,10 @@ impl Provisioners {
         self.members.entry(pubkey_bls).or_insert_with(|| stake);
     }
 
+    pub fn get_member(&self, pubkey_bls: &PublicKey) -> Option<&Stake> {
+        self.members.get(pubkey_bls)
+    }
+
     pub fn get_member_mut(
         &mut self,
         pubkey_bls: &PublicKey,
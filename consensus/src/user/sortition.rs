@@ -281,6 +281,23 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_provisioners_commitment() {
+        assert_eq!(Provisioners::empty().commitment(), [0u8; 32]);
+
+        let p = generate_provisioners(5);
+        assert_eq!(p.commitment(), generate_provisioners(5).commitment());
+        assert_ne!(p.commitment(), generate_provisioners(4).commitment());
+
+        let mut changed = p.clone();
+        let (pk, _) = p.iter().next().expect("provisioner to exist");
+        changed
+            .get_member_mut(pk)
+            .expect("provisioner to exist")
+            .change_eligibility(100);
+        assert_ne!(p.commitment(), changed.commitment());
+    }
+
     fn generate_provisioners(n: usize) -> Provisioners {
         let sks = [
             "7f6f2ccdb23f2abb7b69278e947c01c6160a31cf02c19d06d0f6e5ab1d768b15",
@@ -213,6 +213,32 @@ impl<D: Database> MsgHandler for ValidationHandler<D> {
             return Ok(StepOutcome::Ready(vrmsg));
         }
 
+        // Votes split across several candidates may leave no vote able to
+        // reach its quorum. In such case there is no point in waiting for the
+        // step timeout
+        let step = p.get_step();
+        if !is_emergency_iter(iteration)
+            && self.aggr.is_quorum_unreachable(committee, step)
+        {
+            info!(
+                event = "Quorum unreachable",
+                round = p.header().round,
+                iter = iteration,
+                tallies = ?self.aggr.tallies(step).collect::<Vec<_>>(),
+            );
+
+            let vrmsg = self
+                .build_validation_result(
+                    StepVotes::default(),
+                    Vote::NoQuorum,
+                    QuorumType::NoQuorum,
+                    &p.header(),
+                )
+                .await;
+
+            return Ok(StepOutcome::Ready(vrmsg));
+        }
+
         Ok(StepOutcome::Pending)
     }
 
@@ -103,7 +103,7 @@ impl<T: Operations + 'static, D: Database> ValidationStep<T, D> {
                     error!(event = "invalid faults", ?err);
                     Vote::Invalid(header.hash)
                 } else {
-                    match Self::call_vst(
+                    match call_vst(
                         ru.state_root(),
                         candidate,
                         &voters,
@@ -163,37 +163,39 @@ impl<T: Operations + 'static, D: Database> ValidationStep<T, D> {
             inbound.try_send(msg);
         }
     }
+}
 
-    async fn call_vst(
-        prev_commit: [u8; 32],
-        candidate: &Block,
-        voters: &[Voter],
-        executor: &Arc<T>,
-    ) -> Result<(), VstError> {
-        let output = executor
-            .verify_state_transition(prev_commit, candidate, voters)
-            .await?;
-
-        // Ensure the `event_bloom` and `state_root` returned
-        // from the VST call are the
-        // ones we expect to have with the
-        // current candidate block.
-        if output.event_bloom != candidate.header().event_bloom {
-            return Err(VstError::MismatchEventBloom(
-                Box::new(output.event_bloom),
-                Box::new(candidate.header().event_bloom),
-            ));
-        }
+/// Verifies the state transition of `candidate` and checks its outcome against
+/// the candidate header
+pub(crate) async fn call_vst<T: Operations>(
+    prev_commit: [u8; 32],
+    candidate: &Block,
+    voters: &[Voter],
+    executor: &Arc<T>,
+) -> Result<(), VstError> {
+    let output = executor
+        .verify_state_transition(prev_commit, candidate, voters)
+        .await?;
 
-        if output.state_root != candidate.header().state_hash {
-            return Err(VstError::MismatchStateHash(
-                output.state_root,
-                candidate.header().state_hash,
-            ));
-        }
+    // Ensure the `event_bloom` and `state_root` returned
+    // from the VST call are the
+    // ones we expect to have with the
+    // current candidate block.
+    if output.event_bloom != candidate.header().event_bloom {
+        return Err(VstError::MismatchEventBloom(
+            Box::new(output.event_bloom),
+            Box::new(candidate.header().event_bloom),
+        ));
+    }
 
-        Ok(())
+    if output.state_root != candidate.header().state_hash {
+        return Err(VstError::MismatchStateHash(
+            output.state_root,
+            candidate.header().state_hash,
+        ));
     }
+
+    Ok(())
 }
 
 pub fn build_validation_payload(
@@ -13,6 +13,7 @@
 extern crate alloc;
 
 use dusk_core::abi;
+use dusk_core::stake::decode_stake;
 use dusk_core::transfer::TRANSFER_CONTRACT;
 
 mod state;
@@ -24,10 +25,15 @@ static mut STATE: StakeState = StakeState::new();
 
 #[no_mangle]
 unsafe fn stake(arg_len: u32) -> u32 {
-    abi::wrap_call(arg_len, |arg| {
-        assert_transfer_caller();
-        STATE.stake(arg)
-    })
+    // Both the versioned and the legacy arguments are accepted, which
+    // `wrap_call` can't do
+    let stake = abi::arg_buf::with_arg_buf(|buf| {
+        decode_stake(&buf[..arg_len as usize])
+            .expect("Argument should correctly deserialize")
+    });
+    assert_transfer_caller();
+    STATE.stake(stake);
+    0
 }
 
 #[no_mangle]
@@ -46,6 +52,14 @@ unsafe fn withdraw(arg_len: u32) -> u32 {
     })
 }
 
+#[no_mangle]
+unsafe fn rotate_key(arg_len: u32) -> u32 {
+    abi::wrap_call(arg_len, |arg| {
+        assert_transfer_caller();
+        STATE.rotate_key(arg)
+    })
+}
+
 #[no_mangle]
 unsafe fn stake_from_contract(arg_len: u32) -> u32 {
     abi::wrap_call(arg_len, |receive| {
@@ -83,6 +97,11 @@ unsafe fn get_stake_keys(arg_len: u32) -> u32 {
     abi::wrap_call(arg_len, |pk| STATE.get_stake_keys(&pk).cloned())
 }
 
+#[no_mangle]
+unsafe fn get_stake_location(arg_len: u32) -> u32 {
+    abi::wrap_call(arg_len, |pk| STATE.get_stake_location(&pk))
+}
+
 #[no_mangle]
 unsafe fn burnt_amount(arg_len: u32) -> u32 {
     abi::wrap_call(arg_len, |_: ()| STATE.burnt_amount())
@@ -7,14 +7,16 @@
 use alloc::collections::BTreeMap;
 use alloc::vec::Vec;
 use core::cmp::min;
+use core::mem;
 
 use dusk_bytes::Serializable;
 use dusk_core::abi::{self, ContractId};
 use dusk_core::signatures::bls::PublicKey as BlsPublicKey;
 use dusk_core::stake::{
-    next_epoch, Reward, SlashEvent, Stake, StakeAmount, StakeConfig, StakeData,
-    StakeEvent, StakeFundOwner, StakeKeys, Withdraw, WithdrawToContract, EPOCH,
-    STAKE_CONTRACT,
+    next_epoch, KeyRotation, KeyRotationEvent, ProofOfPossession, Reward,
+    SlashEvent, Stake, StakeAmount, StakeConfig, StakeData, StakeEvent,
+    StakeFundOwner, StakeKeys, VersionedStake, Withdraw, WithdrawToContract,
+    EPOCH, STAKE_CONTRACT,
 };
 use dusk_core::transfer::{
     ContractToContract, ReceiveFromContract, TRANSFER_CONTRACT,
@@ -35,9 +37,12 @@ pub struct StakeState {
     previous_block_state:
         BTreeMap<[u8; BlsPublicKey::SIZE], (Option<StakeData>, BlsPublicKey)>,
     stakes: BTreeMap<[u8; BlsPublicKey::SIZE], (StakeData, StakeKeys)>,
+    /// Pending key rotations, with the next account key and the height they
+    /// take effect at
+    rotations: BTreeMap<[u8; BlsPublicKey::SIZE], (BlsPublicKey, u64)>,
 }
 
-const STAKE_CONTRACT_VERSION: u64 = 8;
+const STAKE_CONTRACT_VERSION: u64 = 9;
 
 impl StakeState {
     pub const fn new() -> Self {
@@ -46,6 +51,7 @@ impl StakeState {
             config: StakeConfig::new(),
             previous_block_state: BTreeMap::new(),
             stakes: BTreeMap::new(),
+            rotations: BTreeMap::new(),
         }
     }
 
@@ -58,7 +64,8 @@ impl StakeState {
     }
 
     pub fn on_new_block(&mut self) {
-        self.previous_block_state.clear()
+        self.previous_block_state.clear();
+        self.apply_rotations(abi::block_height());
     }
 
     fn unwrap_account_owner(owner: &StakeFundOwner) -> BlsPublicKey {
@@ -85,7 +92,9 @@ impl StakeState {
         }
     }
 
-    pub fn stake(&mut self, stake: Stake) {
+    pub fn stake(&mut self, stake: VersionedStake) {
+        let pop = stake.pop().copied();
+        let stake = stake.stake();
         let minimum_stake = self.config.minimum_stake;
         let value = stake.value();
         let signature = *stake.signature();
@@ -111,6 +120,12 @@ impl StakeState {
         if !abi::verify_bls(msg, keys.account, signature.account) {
             panic!("Invalid account signature!");
         }
+        if let Some(pop) = pop {
+            let msg = ProofOfPossession::message(&keys.account);
+            if !abi::verify_bls(msg, keys.account, *pop.signature()) {
+                panic!("Invalid proof of possession!");
+            }
+        }
 
         // make call to transfer contract to transfer balance from the user to
         // this contract
@@ -204,6 +219,105 @@ impl StakeState {
             .or_insert((prev_stake, account));
     }
 
+    pub fn rotate_key(&mut self, rotation: KeyRotation) {
+        if rotation.chain_id() != self.chain_id() {
+            panic!("The key rotation must target the correct chain");
+        }
+
+        let activation = rotation.activation();
+        if activation != next_epoch(abi::block_height()) {
+            panic!("The key rotation must take effect at the next epoch");
+        }
+
+        let account = *rotation.account();
+        let next_account = *rotation.next_account();
+
+        let staked = self.get_stake(&account).and_then(|s| s.amount);
+        if staked.is_none() {
+            panic!("A stake should exist for its key to be rotated!");
+        }
+
+        let next_in_use = self.stakes.contains_key(&next_account.to_bytes())
+            || self
+                .rotations
+                .values()
+                .any(|(next, _)| next == &next_account);
+        if next_in_use {
+            panic!("The next account key is already in use");
+        }
+
+        let msg = rotation.signature_message().to_vec();
+        if !abi::verify_bls(msg, account, *rotation.signature()) {
+            panic!("Invalid account signature!");
+        }
+        let msg = ProofOfPossession::message(&next_account);
+        if !abi::verify_bls(msg, next_account, *rotation.pop().signature()) {
+            panic!("Invalid proof of possession!");
+        }
+
+        self.rotations
+            .insert(account.to_bytes(), (next_account, activation));
+        abi::emit(
+            "rotate_key",
+            KeyRotationEvent {
+                account,
+                next_account,
+                activation,
+            },
+        );
+    }
+
+    /// Moves the stakes whose key rotation takes effect at `block_height` to
+    /// their next account key, emitting a `key_rotated` event for each.
+    ///
+    /// The rotations of stakes withdrawn in the meantime, or whose next key
+    /// has been staked to, are dropped.
+    fn apply_rotations(&mut self, block_height: u64) {
+        let due: Vec<_> = self
+            .rotations
+            .iter()
+            .filter(|(_, (_, activation))| *activation <= block_height)
+            .map(|(key, _)| *key)
+            .collect();
+
+        for key in due {
+            let (next_account, activation) =
+                self.rotations.remove(&key).expect("rotation to exist");
+            let next_key = next_account.to_bytes();
+            if self.stakes.contains_key(&next_key) {
+                continue;
+            }
+            let staked = self
+                .stakes
+                .get(&key)
+                .is_some_and(|(stake, _)| stake.amount.is_some());
+            if !staked {
+                continue;
+            }
+            let (stake, keys) =
+                self.stakes.remove(&key).expect("stake to exist");
+
+            let next_keys = StakeKeys::new(next_account, keys.owner);
+            self.stakes.insert(next_key, (stake, next_keys));
+
+            self.previous_block_state
+                .entry(key)
+                .or_insert((Some(stake), keys.account));
+            self.previous_block_state
+                .entry(next_key)
+                .or_insert((None, next_account));
+
+            abi::emit(
+                "key_rotated",
+                KeyRotationEvent {
+                    account: keys.account,
+                    next_account,
+                    activation,
+                },
+            );
+        }
+    }
+
     pub fn unstake(&mut self, unstake: Withdraw) {
         let transfer_withdraw = unstake.transfer_withdraw();
         let account = *unstake.account();
@@ -441,6 +555,16 @@ impl StakeState {
         self.stakes.get(&key.to_bytes()).map(|(_, k)| k)
     }
 
+    /// Gets the offset and the size of the `(StakeData, StakeKeys)` entry of
+    /// a stake in the memory of the contract, for the memory pages holding it
+    /// to be proven against a state root.
+    pub fn get_stake_location(&self, key: &BlsPublicKey) -> Option<(u64, u64)> {
+        self.stakes.get(&key.to_bytes()).map(|entry| {
+            let offset = entry as *const (StakeData, StakeKeys) as usize;
+            (offset as u64, mem::size_of_val(entry) as u64)
+        })
+    }
+
     /// Gets a mutable reference to a stake.
     pub fn get_stake_mut(
         &mut self,
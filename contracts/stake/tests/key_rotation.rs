@@ -0,0 +1,351 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use dusk_core::abi::Event;
+use dusk_core::dusk;
+use dusk_core::signatures::bls::{
+    PublicKey as BlsPublicKey, SecretKey as BlsSecretKey,
+};
+use dusk_core::stake::{
+    next_epoch, KeyRotation, KeyRotationEvent, Reward, RewardReason, StakeData,
+    StakeFundOwner, StakeKeys, EPOCH, STAKE_CONTRACT,
+};
+use dusk_core::transfer::data::ContractCall;
+use dusk_core::transfer::{Transaction, TRANSFER_CONTRACT};
+use dusk_vm::{
+    execute, ContractData, Error as VMError, ExecutionConfig, Session, VM,
+};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rkyv::{check_archived_root, Deserialize, Infallible};
+use wallet_core::transaction::{moonlight, moonlight_stake, moonlight_unstake};
+
+pub mod common;
+use crate::common::assert::*;
+use crate::common::init::CHAIN_ID;
+use crate::common::utils::*;
+
+const GENESIS_VALUE: u64 = dusk(1_000_000.0);
+const STAKE_VALUE: u64 = GENESIS_VALUE / 2;
+const GENESIS_NONCE: u64 = 0;
+
+const NO_CONFIG: ExecutionConfig = ExecutionConfig::DEFAULT;
+
+#[test]
+fn rotate_key() -> Result<(), VMError> {
+    let rng = &mut StdRng::seed_from_u64(0xfeeb);
+
+    let moonlight_sk = BlsSecretKey::random(rng);
+    let moonlight_pk = BlsPublicKey::from(&moonlight_sk);
+
+    let stake_sk = BlsSecretKey::random(rng);
+    let stake_pk = BlsPublicKey::from(&stake_sk);
+
+    let next_sk = BlsSecretKey::random(rng);
+    let next_pk = BlsPublicKey::from(&next_sk);
+
+    let mut vm = &mut VM::ephemeral()?;
+    let mut session = instantiate(&mut vm, &moonlight_pk);
+
+    // initial stake
+    let mut nonce = GENESIS_NONCE + 1;
+    let tx = moonlight_stake(
+        &moonlight_sk,
+        &stake_sk,
+        &stake_sk,
+        STAKE_VALUE,
+        GAS_LIMIT,
+        GAS_PRICE,
+        nonce,
+        CHAIN_ID,
+    )
+    .expect("tx creation should pass");
+    execute(&mut session, &tx, &NO_CONFIG)?
+        .data
+        .expect("stake should succeed");
+
+    // ------
+    // A rotation must take effect at the next epoch
+
+    nonce += 1;
+    let rotation = KeyRotation::new(&stake_sk, &next_sk, 2 * EPOCH, CHAIN_ID);
+    let tx = rotate_key_tx(&moonlight_sk, &rotation, nonce);
+    let receipt = execute(&mut session, &tx, &NO_CONFIG)?;
+    assert!(receipt.data.is_err(), "rotation past the next epoch");
+
+    // ------
+    // Register the rotation
+
+    let activation = next_epoch(1);
+    nonce += 1;
+    let rotation = KeyRotation::new(&stake_sk, &next_sk, activation, CHAIN_ID);
+    let tx = rotate_key_tx(&moonlight_sk, &rotation, nonce);
+    let receipt = execute(&mut session, &tx, &NO_CONFIG)?;
+    receipt.data.expect("rotation should succeed");
+
+    let event = rotation_event(&receipt.events, "rotate_key");
+    assert_eq!(event.account, stake_pk);
+    assert_eq!(event.next_account, next_pk);
+    assert_eq!(event.activation, activation);
+
+    // The stake is only moved at the start of the next epoch
+    let base = session.commit()?;
+    let mut session = vm.session(base, CHAIN_ID, activation - 1)?;
+    let receipt = session.call::<_, ()>(
+        STAKE_CONTRACT,
+        "before_state_transition",
+        &(),
+        GAS_LIMIT,
+    )?;
+    assert!(receipt.events.is_empty());
+    assert_stake(&mut session, &stake_pk, STAKE_VALUE, 0, 0);
+
+    // ------
+    // Apply the rotation
+
+    let base = session.commit()?;
+    let mut session = vm.session(base, CHAIN_ID, activation)?;
+    let receipt = session.call::<_, ()>(
+        STAKE_CONTRACT,
+        "before_state_transition",
+        &(),
+        GAS_LIMIT,
+    )?;
+
+    let event = rotation_event(&receipt.events, "key_rotated");
+    assert_eq!(event.account, stake_pk);
+    assert_eq!(event.next_account, next_pk);
+    assert_eq!(event.activation, activation);
+
+    assert_stake(&mut session, &stake_pk, 0, 0, 0);
+    assert_stake(&mut session, &next_pk, STAKE_VALUE, 0, 0);
+    let keys: Option<StakeKeys> = session
+        .call(STAKE_CONTRACT, "get_stake_keys", &next_pk, GAS_LIMIT)?
+        .data;
+    let keys = keys.expect("the stake keys should be rotated");
+    assert_eq!(keys.account, next_pk);
+    assert_eq!(keys.owner, StakeFundOwner::Account(stake_pk));
+
+    // ------
+    // The old account key is rejected after the boundary
+
+    nonce += 1;
+    let tx = moonlight_unstake(
+        rng,
+        &moonlight_sk,
+        &stake_sk,
+        &stake_sk,
+        STAKE_VALUE,
+        GAS_LIMIT,
+        GAS_PRICE,
+        nonce,
+        CHAIN_ID,
+    )
+    .expect("tx creation should pass");
+    let receipt = execute(&mut session, &tx, &NO_CONFIG)?;
+    assert!(receipt.data.is_err(), "unstake signed with the old key");
+    assert_stake(&mut session, &next_pk, STAKE_VALUE, 0, 0);
+
+    nonce += 1;
+    let rotation =
+        KeyRotation::new(&stake_sk, &next_sk, next_epoch(activation), CHAIN_ID);
+    let tx = rotate_key_tx(&moonlight_sk, &rotation, nonce);
+    let receipt = execute(&mut session, &tx, &NO_CONFIG)?;
+    assert!(receipt.data.is_err(), "rotation signed with the old key");
+
+    // ------
+    // The next account key signs for the stake
+
+    nonce += 1;
+    let tx = moonlight_unstake(
+        rng,
+        &moonlight_sk,
+        &next_sk,
+        &stake_sk,
+        STAKE_VALUE,
+        GAS_LIMIT,
+        GAS_PRICE,
+        nonce,
+        CHAIN_ID,
+    )
+    .expect("tx creation should pass");
+    let receipt = execute(&mut session, &tx, &NO_CONFIG)?;
+    receipt.data.expect("unstake should succeed");
+    assert_stake_event(&receipt.events, "unstake", &next_pk, STAKE_VALUE, 0);
+
+    Ok(())
+}
+
+#[test]
+fn rotation_of_withdrawn_stake() -> Result<(), VMError> {
+    let rng = &mut StdRng::seed_from_u64(0xfeeb);
+
+    let moonlight_sk = BlsSecretKey::random(rng);
+    let moonlight_pk = BlsPublicKey::from(&moonlight_sk);
+
+    let stake_sk = BlsSecretKey::random(rng);
+    let stake_pk = BlsPublicKey::from(&stake_sk);
+
+    let next_sk = BlsSecretKey::random(rng);
+    let next_pk = BlsPublicKey::from(&next_sk);
+
+    let mut vm = &mut VM::ephemeral()?;
+    let mut session = instantiate(&mut vm, &moonlight_pk);
+
+    let mut nonce = GENESIS_NONCE + 1;
+    let tx = moonlight_stake(
+        &moonlight_sk,
+        &stake_sk,
+        &stake_sk,
+        STAKE_VALUE,
+        GAS_LIMIT,
+        GAS_PRICE,
+        nonce,
+        CHAIN_ID,
+    )
+    .expect("tx creation should pass");
+    execute(&mut session, &tx, &NO_CONFIG)?
+        .data
+        .expect("stake should succeed");
+
+    let activation = next_epoch(1);
+    nonce += 1;
+    let rotation = KeyRotation::new(&stake_sk, &next_sk, activation, CHAIN_ID);
+    let tx = rotate_key_tx(&moonlight_sk, &rotation, nonce);
+    execute(&mut session, &tx, &NO_CONFIG)?
+        .data
+        .expect("rotation should succeed");
+
+    // The whole stake is withdrawn before the rotation takes effect, leaving
+    // only its reward
+    let rewards = vec![Reward {
+        account: stake_pk,
+        value: dusk(5.0),
+        reason: RewardReason::Other,
+    }];
+    session.call::<_, ()>(STAKE_CONTRACT, "reward", &rewards, GAS_LIMIT)?;
+
+    nonce += 1;
+    let tx = moonlight_unstake(
+        rng,
+        &moonlight_sk,
+        &stake_sk,
+        &stake_sk,
+        STAKE_VALUE,
+        GAS_LIMIT,
+        GAS_PRICE,
+        nonce,
+        CHAIN_ID,
+    )
+    .expect("tx creation should pass");
+    execute(&mut session, &tx, &NO_CONFIG)?
+        .data
+        .expect("unstake should succeed");
+
+    // The rotation is dropped
+    let base = session.commit()?;
+    let mut session = vm.session(base, CHAIN_ID, activation)?;
+    let receipt = session.call::<_, ()>(
+        STAKE_CONTRACT,
+        "before_state_transition",
+        &(),
+        GAS_LIMIT,
+    )?;
+    assert!(receipt.events.is_empty());
+    assert_stake(&mut session, &next_pk, 0, 0, 0);
+
+    let stake: Option<StakeData> = session
+        .call(STAKE_CONTRACT, "get_stake", &stake_pk, GAS_LIMIT)?
+        .data;
+    let stake = stake.expect("the reward should be left");
+    assert!(stake.amount.is_none());
+
+    Ok(())
+}
+
+/// Creates a moonlight transaction calling `rotate_key` with `rotation`
+fn rotate_key_tx(
+    moonlight_sk: &BlsSecretKey,
+    rotation: &KeyRotation,
+    nonce: u64,
+) -> Transaction {
+    let fn_args = rkyv::to_bytes::<_, 1024>(rotation)
+        .expect("Should serialize KeyRotation correctly")
+        .to_vec();
+    let contract_call = ContractCall {
+        contract: STAKE_CONTRACT,
+        fn_name: String::from("rotate_key"),
+        fn_args,
+    };
+    moonlight(
+        moonlight_sk,
+        None,
+        0,
+        0,
+        GAS_LIMIT,
+        GAS_PRICE,
+        nonce,
+        CHAIN_ID,
+        Some(contract_call),
+    )
+    .expect("tx creation should pass")
+}
+
+fn rotation_event(events: &[Event], topic: &str) -> KeyRotationEvent {
+    let event = events
+        .iter()
+        .find(|e| e.topic == topic)
+        .unwrap_or_else(|| panic!("event: {topic} should exist"));
+    check_archived_root::<KeyRotationEvent>(event.data.as_slice())
+        .expect("Key rotation event data should deserialize correctly")
+        .deserialize(&mut Infallible)
+        .expect("Infallible")
+}
+
+fn instantiate(vm: &mut VM, moonlight_pk: &BlsPublicKey) -> Session {
+    let mut session = vm.genesis_session(CHAIN_ID);
+
+    const OWNER: [u8; 32] = [0; 32];
+    let transfer_bytecode = include_bytes!(
+        "../../../target/dusk/wasm64-unknown-unknown/release/transfer_contract.wasm"
+    );
+    session
+        .deploy(
+            transfer_bytecode,
+            ContractData::builder()
+                .owner(OWNER)
+                .contract_id(TRANSFER_CONTRACT),
+            GAS_LIMIT,
+        )
+        .expect("Deploying the transfer contract should succeed");
+
+    let stake_bytecode = include_bytes!(
+        "../../../target/dusk/wasm32-unknown-unknown/release/stake_contract.wasm"
+    );
+    session
+        .deploy(
+            stake_bytecode,
+            ContractData::builder()
+                .owner(OWNER)
+                .contract_id(STAKE_CONTRACT),
+            GAS_LIMIT,
+        )
+        .expect("Deploying the stake contract should succeed");
+
+    session
+        .call::<_, ()>(
+            TRANSFER_CONTRACT,
+            "add_account_balance",
+            &(*moonlight_pk, GENESIS_VALUE),
+            GAS_LIMIT,
+        )
+        .expect("Inserting genesis account should succeed");
+
+    // sets the block height for all subsequent operations to 1
+    let base = session.commit().expect("Committing should succeed");
+    vm.session(base, CHAIN_ID, 1)
+        .expect("Instantiating new session should succeed")
+}
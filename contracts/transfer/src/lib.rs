@@ -98,6 +98,11 @@ unsafe fn chain_id(arg_len: u32) -> u32 {
     abi::wrap_call(arg_len, |_: ()| STATE.chain_id())
 }
 
+#[no_mangle]
+unsafe fn get_version(arg_len: u32) -> u32 {
+    abi::wrap_call(arg_len, |_: ()| STATE.get_version())
+}
+
 // "Feeder" queries
 
 #[no_mangle]
@@ -148,6 +153,14 @@ unsafe fn spend_and_execute(arg_len: u32) -> u32 {
     })
 }
 
+#[no_mangle]
+unsafe fn sponsor(arg_len: u32) -> u32 {
+    abi::wrap_call(arg_len, |(tx_hash, sponsorship)| {
+        assert_external_caller();
+        STATE.sponsor(tx_hash, sponsorship)
+    })
+}
+
 #[no_mangle]
 unsafe fn refund(arg_len: u32) -> u32 {
     abi::wrap_call(arg_len, |gas_spent| {
@@ -16,7 +16,7 @@ use dusk_core::stake::EPOCH;
 use dusk_core::transfer::MINT_CONTRACT_TOPIC;
 use ringbuffer::{ConstGenericRingBuffer, RingBuffer};
 
-use dusk_core::abi::{self, ContractError, ContractId};
+use dusk_core::abi::{self, ContractError, ContractId, PanicReason};
 use dusk_core::signatures::bls::PublicKey as AccountPublicKey;
 use dusk_core::stake::STAKE_CONTRACT;
 use dusk_core::transfer::moonlight::{
@@ -25,6 +25,7 @@ use dusk_core::transfer::moonlight::{
 use dusk_core::transfer::phoenix::{
     Note, NoteLeaf, NoteOpening, Sender, Transaction as PhoenixTransaction,
 };
+use dusk_core::transfer::sponsor::{SponsorEvent, Sponsorship};
 use dusk_core::transfer::withdraw::{
     Withdraw, WithdrawReceiver, WithdrawReplayToken, WithdrawSignature,
 };
@@ -34,7 +35,7 @@ use dusk_core::transfer::{
     MoonlightTransactionEvent, PhoenixTransactionEvent, ReceiveFromContract,
     Transaction, WithdrawEvent, CONTRACT_TO_ACCOUNT_TOPIC,
     CONTRACT_TO_CONTRACT_TOPIC, CONVERT_TOPIC, DEPOSIT_TOPIC, MINT_TOPIC,
-    MOONLIGHT_TOPIC, PANIC_NONCE_NOT_READY, PHOENIX_TOPIC, TRANSFER_CONTRACT,
+    MOONLIGHT_TOPIC, PHOENIX_TOPIC, SPONSOR_TOPIC, TRANSFER_CONTRACT,
     WITHDRAW_TOPIC,
 };
 use dusk_core::BlsScalar;
@@ -44,6 +45,9 @@ use crate::transitory::{self, Deposit};
 /// Number of roots stored
 pub const MAX_ROOTS: usize = 2 * EPOCH as usize;
 
+/// The transfer contract deployed before the sponsorships has no version
+const TRANSFER_CONTRACT_VERSION: u64 = 1;
+
 /// An empty account, used as the default return and for instantiating new
 /// entries.
 const EMPTY_ACCOUNT: AccountData = AccountData {
@@ -109,7 +113,7 @@ impl TransferState {
                 let moonlight_tx = transitory::moonlight_transaction();
 
                 if *nonce != moonlight_tx.nonce() {
-                    panic!("Incorrect nonce signed");
+                    panic!("{}", PanicReason::IncorrectNonce);
                 }
             }
         }
@@ -127,7 +131,7 @@ impl TransferState {
                 let pk = address.note_pk();
 
                 if !abi::verify_schnorr(hash, *pk, *signature) {
-                    panic!("Invalid signature");
+                    panic!("{}", PanicReason::InvalidSignature);
                 }
 
                 let sender = contract_fn_sender(fn_name, *contract);
@@ -144,7 +148,7 @@ impl TransferState {
                 };
 
                 if !abi::verify_bls(msg, *account, *signature) {
-                    panic!("Invalid signature");
+                    panic!("{}", PanicReason::InvalidSignature);
                 }
 
                 let account_bytes = account.to_raw_bytes();
@@ -241,7 +245,7 @@ impl TransferState {
         let value = withdraw.value();
 
         if self.contract_balance(contract) < value {
-            panic!("The contract doesn't have enough balance");
+            panic!("{}", PanicReason::ContractBalance);
         }
 
         self.sub_contract_balance(contract, value)
@@ -404,7 +408,7 @@ impl TransferState {
             .expect("Caller must have a balance");
 
         if *sender_balance < transfer.value {
-            panic!("Caller must have enough balance");
+            panic!("{}", PanicReason::CallerBalance);
         }
 
         *sender_balance -= transfer.value;
@@ -456,7 +460,7 @@ impl TransferState {
             .expect("Caller must have a balance");
 
         if *sender_balance < transfer.value {
-            panic!("Caller must have enough balance");
+            panic!("{}", PanicReason::CallerBalance);
         }
 
         let account = self
@@ -501,7 +505,7 @@ impl TransferState {
         tx: Transaction,
     ) -> Result<Vec<u8>, ContractError> {
         if tx.gas_price() == 0 {
-            panic!("Gas price too low!");
+            panic!("{}", PanicReason::GasPriceTooLow);
         }
 
         transitory::put_transaction(tx);
@@ -531,25 +535,25 @@ impl TransferState {
     /// change in state.
     fn spend_phoenix(&mut self, phoenix_tx: &PhoenixTransaction) {
         if phoenix_tx.chain_id() != self.chain_id() {
-            panic!("The tx must target the correct chain");
+            panic!("{}", PanicReason::InvalidChain);
         }
 
         // panic if the root is invalid
         if !self.root_exists(phoenix_tx.root()) {
-            panic!("Root not found in the state!");
+            panic!("{}", PanicReason::RootNotFound);
         }
 
         // append the nullifiers to the set, and panic if an equal one has
         // already been inserted
         for nullifier in phoenix_tx.nullifiers() {
             if !self.nullifiers.insert(*nullifier) {
-                panic!("A provided nullifier has already been spent");
+                panic!("{}", PanicReason::SpentNullifier);
             }
         }
 
         // verify the phoenix-circuit
         if !verify_tx_proof(phoenix_tx) {
-            panic!("Invalid transaction proof!");
+            panic!("{}", PanicReason::InvalidProof);
         }
 
         // append the output notes to the phoenix-notes tree
@@ -568,18 +572,39 @@ impl TransferState {
     /// all checks necessary to ensure the transaction is valid - signature
     /// check, available funds, etc...
     ///
+    /// The gas of a sponsored transaction is charged to its sponsor, once both
+    /// the sponsorship and the signature of the sender committing to the
+    /// sponsor are verified.
+    ///
     /// # Panics
     /// Any failure in the checks performed in processing the transaction will
     /// result in a panic. The contract expects the environment to roll back any
     /// change in state.
     fn spend_moonlight(&mut self, moonlight_tx: &MoonlightTransaction) {
         if moonlight_tx.chain_id() != self.chain_id() {
-            panic!("The tx must target the correct chain");
+            panic!("{}", PanicReason::InvalidChain);
         }
 
-        // check the signature is valid and made by `sender`
+        // check the sponsorship, if any, is valid and made by `sponsor`
+        let sponsor = transitory::sponsorship().map(|sponsorship| {
+            if !abi::verify_bls(
+                Sponsorship::signature_message(transitory::transaction()),
+                *sponsorship.sponsor(),
+                *sponsorship.signature(),
+            ) {
+                panic!("{}", PanicReason::InvalidSignature);
+            }
+            *sponsorship.sponsor()
+        });
+
+        // check the signature is valid and made by `sender`, committing to
+        // the sponsor if any
+        let msg = match &sponsor {
+            Some(sponsor) => moonlight_tx.sponsored_signature_message(sponsor),
+            None => moonlight_tx.signature_message(),
+        };
         if !abi::verify_bls(
-            moonlight_tx.signature_message(),
+            msg,
             *moonlight_tx.sender(),
             *moonlight_tx.signature(),
         ) {
@@ -603,15 +628,18 @@ impl TransferState {
         let sender_bytes = moonlight_tx.sender().to_raw_bytes();
 
         // the total value carried by a transaction is the sum of the value, the
-        // deposit, and gas_limit * gas_price.
-        let total_value = moonlight_tx.value()
-            + moonlight_tx.deposit()
-            + moonlight_tx.gas_limit() * moonlight_tx.gas_price();
+        // deposit, and gas_limit * gas_price, unless the latter is paid by a
+        // sponsor.
+        let fee = moonlight_tx.gas_limit() * moonlight_tx.gas_price();
+        let total_value = match sponsor {
+            Some(_) => moonlight_tx.value() + moonlight_tx.deposit(),
+            None => moonlight_tx.value() + moonlight_tx.deposit() + fee,
+        };
 
         match self.accounts.get_mut(&sender_bytes) {
             Some(account) => {
                 if total_value > account.balance {
-                    panic!("Account doesn't have enough funds");
+                    panic!("{}", PanicReason::NotEnoughFunds);
                 }
 
                 // NOTE: exhausting the nonce is nearly impossible, since it
@@ -620,16 +648,22 @@ impl TransferState {
                 //       skip overflow checks.
                 let incremented_nonce = account.nonce + 1;
                 if moonlight_tx.nonce() < incremented_nonce {
-                    panic!("Already used nonce");
+                    panic!("{}", PanicReason::UsedNonce);
                 }
                 if moonlight_tx.nonce() > incremented_nonce {
-                    panic!("{PANIC_NONCE_NOT_READY}",);
+                    panic!("{}", PanicReason::NonceNotReady);
                 }
 
                 account.balance -= total_value;
                 account.nonce = moonlight_tx.nonce();
             }
-            None => panic!("Account has no funds"),
+            None => panic!("{}", PanicReason::NoFunds),
+        }
+
+        // charge the gas to the sponsor, if any, relying on `refund` to give
+        // it back the gas left
+        if let Some(sponsor) = sponsor {
+            self.sub_account_balance(&sponsor, fee);
         }
 
         // add the value to the receiver account
@@ -646,6 +680,12 @@ impl TransferState {
         }
     }
 
+    /// Insert the `sponsorship` of the transaction with hash `tx_hash`, to be
+    /// executed next.
+    pub fn sponsor(&mut self, tx_hash: BlsScalar, sponsorship: Sponsorship) {
+        transitory::put_sponsorship(tx_hash, sponsorship);
+    }
+
     /// Refund the previously performed transaction, taking into account the
     /// given gas spent and a potential deposit that hasn't been picked up by
     /// the contract. The note produced will be refunded to the address present
@@ -697,9 +737,27 @@ impl TransferState {
             }
             Transaction::Moonlight(tx) => {
                 let remaining_gas = tx.gas_limit() - gas_spent;
-                let refund = remaining_gas * tx.gas_price()
+                let mut refund = remaining_gas * tx.gas_price()
                     + deposit.unwrap_or_default();
 
+                // the gas left is refunded to the sponsor that paid it, if any
+                if let Some(sponsorship) = &ongoing.sponsorship {
+                    let sponsor = *sponsorship.sponsor();
+                    let sponsor_refund = remaining_gas * tx.gas_price();
+                    self.add_account_balance(&sponsor, sponsor_refund);
+                    refund -= sponsor_refund;
+
+                    abi::emit(
+                        SPONSOR_TOPIC,
+                        SponsorEvent {
+                            sponsor,
+                            sender: *tx.sender(),
+                            fee: gas_spent * tx.gas_price(),
+                            refund: sponsor_refund,
+                        },
+                    );
+                }
+
                 let refund_account = self
                     .accounts
                     .entry(tx.refund_address().to_raw_bytes())
@@ -849,11 +907,20 @@ impl TransferState {
         account.balance = account.balance.saturating_add(value);
     }
 
+    /// Subtracts `value` from the balance of the account of `key`.
+    ///
+    /// # Panics
+    /// When the account doesn't hold `value`.
     pub fn sub_account_balance(&mut self, key: &AccountPublicKey, value: u64) {
         let key_bytes = key.to_raw_bytes();
-        if let Some(account) = self.accounts.get_mut(&key_bytes) {
-            account.balance = account.balance.saturating_sub(value);
-        }
+        let account = match self.accounts.get_mut(&key_bytes) {
+            Some(account) => account,
+            None => panic!("{}", PanicReason::NoFunds),
+        };
+        account.balance = match account.balance.checked_sub(value) {
+            Some(balance) => balance,
+            None => panic!("{}", PanicReason::NotEnoughFunds),
+        };
     }
 
     /// Return the balance of a given contract.
@@ -915,6 +982,11 @@ impl TransferState {
     pub fn chain_id(&self) -> u8 {
         abi::chain_id()
     }
+
+    /// Version of the transfer contract
+    pub fn get_version(&self) -> u64 {
+        TRANSFER_CONTRACT_VERSION
+    }
 }
 
 fn verify_tx_proof(tx: &PhoenixTransaction) -> bool {
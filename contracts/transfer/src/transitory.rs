@@ -21,8 +21,10 @@ use dusk_core::signatures::bls::PublicKey as AccountPublicKey;
 use dusk_core::transfer::{
     moonlight::Transaction as MoonlightTransaction,
     phoenix::{Note, Transaction as PhoenixTransaction},
+    sponsor::Sponsorship,
     Transaction,
 };
+use dusk_core::BlsScalar;
 
 /// The state of a deposit while a transaction is executing.
 pub enum Deposit {
@@ -76,10 +78,27 @@ pub struct OngoingTransaction {
     pub deposit: Deposit,
     /// The notes that have been inserted into the tree.
     pub notes: Vec<Note>,
+    /// The sponsorship paying the gas of the transaction, if any.
+    pub sponsorship: Option<Sponsorship>,
 }
 
 static mut CURRENT_TX: Option<OngoingTransaction> = None;
 
+/// The sponsorship put ahead of the transaction it sponsors, along with the
+/// hash of the transaction.
+static mut NEXT_SPONSORSHIP: Option<(BlsScalar, Sponsorship)> = None;
+
+/// Insert the sponsorship of the transaction with the given hash, to be
+/// picked up by [`put_transaction`].
+///
+/// A sponsorship is only kept until the next transaction is inserted, whether
+/// it's the sponsored one or not.
+pub fn put_sponsorship(tx_hash: BlsScalar, sponsorship: Sponsorship) {
+    unsafe {
+        NEXT_SPONSORSHIP = Some((tx_hash, sponsorship));
+    }
+}
+
 /// Insert the transaction into the state.
 ///
 /// Calling this is required to use any of the other functions in this module
@@ -109,10 +128,19 @@ pub fn put_transaction(tx: impl Into<Transaction>) {
             };
         }
 
+        let sponsorship =
+            match ptr::replace(addr_of_mut!(NEXT_SPONSORSHIP), None) {
+                Some((tx_hash, sponsorship)) if tx_hash == tx.hash() => {
+                    Some(sponsorship)
+                }
+                _ => None,
+            };
+
         CURRENT_TX = Some(OngoingTransaction {
             tx,
             deposit,
             notes: Vec::new(),
+            sponsorship,
         });
     }
 }
@@ -150,6 +178,17 @@ pub fn transaction() -> &'static Transaction {
     }
 }
 
+/// Get the sponsorship of the current ongoing transaction, if any.
+pub fn sponsorship() -> Option<&'static Sponsorship> {
+    unsafe {
+        CURRENT_TX
+            .as_ref()
+            .expect("There must be an ongoing transaction")
+            .sponsorship
+            .as_ref()
+    }
+}
+
 /// Get a reference of the current ongoing transaction, assuming it's Moonlight.
 pub fn moonlight_transaction() -> &'static MoonlightTransaction {
     match transaction() {
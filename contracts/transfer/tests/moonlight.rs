@@ -9,22 +9,27 @@ use dusk_core::signatures::bls::{
     PublicKey as AccountPublicKey, SecretKey as AccountSecretKey,
 };
 use dusk_core::transfer::data::{ContractCall, TransactionData};
-use dusk_core::transfer::moonlight::Transaction as MoonlightTransaction;
+use dusk_core::transfer::moonlight::{
+    Fee, Payload, Transaction as MoonlightTransaction,
+};
 use dusk_core::transfer::phoenix::{
     Note, PublicKey as PhoenixPublicKey, SecretKey as PhoenixSecretKey,
     ViewKey as PhoenixViewKey,
 };
+use dusk_core::transfer::sponsor::{SponsorEvent, Sponsorship};
 use dusk_core::transfer::withdraw::{
     Withdraw, WithdrawReceiver, WithdrawReplayToken,
 };
 use dusk_core::transfer::{
-    ContractToAccount, ContractToContract, Transaction, TRANSFER_CONTRACT,
+    ContractToAccount, ContractToContract, Transaction, SPONSOR_TOPIC,
+    TRANSFER_CONTRACT,
 };
 use dusk_core::{dusk, JubJubScalar, LUX};
 use dusk_vm::{execute, ContractData, ExecutionConfig, Session, VM};
 use ff::Field;
 use rand::rngs::StdRng;
 use rand::SeedableRng;
+use rkyv::{check_archived_root, Deserialize, Infallible};
 
 pub mod common;
 use crate::common::utils::{
@@ -279,6 +284,126 @@ fn transfer_with_refund() {
     );
 }
 
+/// Perform a transfer between moonlight accounts, where the gas is paid by a
+/// sponsor.
+#[test]
+fn sponsored_transfer() {
+    const TRANSFER_VALUE: u64 = dusk(1.0);
+    const SPONSOR_VALUE: u64 = dusk(1.0);
+
+    let rng = &mut StdRng::seed_from_u64(0xfeeb);
+
+    let moonlight_sender_sk = AccountSecretKey::random(rng);
+    let moonlight_sender_pk = AccountPublicKey::from(&moonlight_sender_sk);
+
+    let moonlight_sponsor_sk = AccountSecretKey::random(rng);
+    let moonlight_sponsor_pk = AccountPublicKey::from(&moonlight_sponsor_sk);
+
+    let moonlight_receiver_pk =
+        AccountPublicKey::from(&AccountSecretKey::random(rng));
+
+    let session = &mut instantiate(&moonlight_sender_pk);
+
+    // Nodes execute sponsored transactions only with a versioned contract
+    let version: u64 = session
+        .call(TRANSFER_CONTRACT, "get_version", &(), GAS_LIMIT)
+        .expect("Querying the version should succeed")
+        .data;
+    assert_eq!(version, 1);
+
+    session
+        .call::<_, ()>(
+            TRANSFER_CONTRACT,
+            "add_account_balance",
+            &(moonlight_sponsor_pk, SPONSOR_VALUE),
+            GAS_LIMIT,
+        )
+        .expect("Inserting the sponsor account should succeed");
+
+    let payload = Payload {
+        chain_id: CHAIN_ID,
+        sender: moonlight_sender_pk,
+        receiver: moonlight_receiver_pk,
+        value: TRANSFER_VALUE,
+        deposit: 0,
+        fee: Fee {
+            gas_limit: GAS_LIMIT,
+            gas_price: LUX,
+            refund_address: moonlight_sender_pk,
+        },
+        nonce: MOONLIGHT_GENESIS_NONCE + 1,
+        data: None,
+    };
+    let transaction: Transaction =
+        MoonlightTransaction::sign_sponsored_payload(
+            &moonlight_sender_sk,
+            payload,
+            &moonlight_sponsor_pk,
+        )
+        .expect("Creating moonlight transaction should succeed")
+        .into();
+    let sponsorship = Sponsorship::new(&moonlight_sponsor_sk, &transaction);
+
+    // the transaction stripped of its sponsorship is invalid, since the
+    // signature of the sender commits to the sponsor
+    let result = execute(session, &transaction, &NO_CONFIG);
+    assert!(
+        result.is_err(),
+        "Transaction should fail without its sponsorship"
+    );
+
+    session
+        .call::<_, ()>(
+            TRANSFER_CONTRACT,
+            "sponsor",
+            &(transaction.hash(), sponsorship),
+            GAS_LIMIT,
+        )
+        .expect("Sponsoring the transaction should succeed");
+    let receipt = execute(session, &transaction, &NO_CONFIG)
+        .expect("Transaction should succeed");
+    let gas_spent = receipt.gas_spent;
+
+    println!("SPONSORED TRANSFER: {} gas", gas_spent);
+
+    let event = receipt
+        .events
+        .iter()
+        .find(|e| e.topic == SPONSOR_TOPIC)
+        .expect("The sponsor event should be emitted");
+    let event: SponsorEvent =
+        check_archived_root::<SponsorEvent>(event.data.as_slice())
+            .expect("The sponsor event should deserialize correctly")
+            .deserialize(&mut Infallible)
+            .expect("Infallible");
+    assert_eq!(event.sponsor, moonlight_sponsor_pk);
+    assert_eq!(event.sender, moonlight_sender_pk);
+    assert_eq!(event.fee, gas_spent * LUX);
+    assert_eq!(event.refund, (GAS_LIMIT - gas_spent) * LUX);
+
+    let sender_account = account(session, &moonlight_sender_pk)
+        .expect("Getting the sender account should succeed");
+    let sponsor_account = account(session, &moonlight_sponsor_pk)
+        .expect("Getting the sponsor account should succeed");
+    let receiver_account = account(session, &moonlight_receiver_pk)
+        .expect("Getting the receiver account should succeed");
+
+    assert_eq!(
+        sender_account.balance,
+        MOONLIGHT_GENESIS_VALUE - TRANSFER_VALUE,
+        "The sender account should only decrease by the value transferred"
+    );
+    assert_eq!(
+        sponsor_account.balance,
+        SPONSOR_VALUE - gas_spent * LUX,
+        "The sponsor account should decrease by the gas spent"
+    );
+    assert_eq!(
+        receiver_account.balance, TRANSFER_VALUE,
+        "The receiver account should have the transferred value"
+    );
+}
+
 /// Checks if a transaction fails when the gas-price is 0.
 #[test]
 fn transfer_gas_fails() {
@@ -11,9 +11,33 @@ pub use piecrust_uplink::{
     CONTRACT_ID_BYTES,
 };
 
+mod error;
+pub use self::error::{ExecutionError, PanicReason};
+
+pub use crate::stake::STAKE_CONTRACT;
+pub use crate::transfer::TRANSFER_CONTRACT;
+
 #[cfg(feature = "abi")]
 pub use self::host_queries::*;
 
+/// Returns `true` if `id` is the ID of the genesis stake contract.
+#[must_use]
+pub fn is_stake_contract(id: &ContractId) -> bool {
+    *id == STAKE_CONTRACT
+}
+
+/// Returns `true` if `id` is the ID of the genesis transfer contract.
+#[must_use]
+pub fn is_transfer_contract(id: &ContractId) -> bool {
+    *id == TRANSFER_CONTRACT
+}
+
+/// Returns `true` if `id` is the ID of one of the genesis contracts.
+#[must_use]
+pub fn is_genesis_contract(id: &ContractId) -> bool {
+    is_stake_contract(id) || is_transfer_contract(id)
+}
+
 /// Enum storing the metadata identifiers.
 pub enum Metadata {}
 
@@ -51,7 +75,7 @@ pub(crate) mod host_queries {
     #[cfg(feature = "abi-debug")]
     pub use piecrust_uplink::debug as piecrust_debug;
     pub use piecrust_uplink::{
-        call, call_raw, call_raw_with_limit, call_with_limit, caller,
+        arg_buf, call, call_raw, call_raw_with_limit, call_with_limit, caller,
         callstack, emit, emit_raw, feed, limit, self_id, spent, wrap_call,
         wrap_call_unchecked, /* maybe use for our Transaction in
                               * spend_and_execute */
@@ -0,0 +1,287 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Error-type of the contract calls.
+
+use alloc::string::{String, ToString};
+use core::convert::Infallible;
+use core::fmt;
+use core::str::FromStr;
+
+use piecrust_uplink::ContractError;
+
+/// The reason a contract call failed.
+///
+/// Each reason has a stable numeric [`code`], for clients to branch on
+/// without parsing the error message. The reason of a panic is the
+/// [`PanicReason`] it was raised with, and its message is kept along.
+///
+/// [`code`]: ExecutionError::code
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExecutionError {
+    /// The call failed for a reason not listed, described by the message.
+    Unknown(String),
+    /// The call ran out of gas.
+    OutOfGas,
+    /// The called contract doesn't exist.
+    ContractNotFound,
+    /// The contract panicked, for a reason not listed.
+    Panic(String),
+    /// The proof of the transaction is invalid.
+    InvalidProof(String),
+    /// The nonce of the transaction is used already or isn't usable yet.
+    InvalidNonce(String),
+    /// A signature of the transaction is invalid.
+    InvalidSignature(String),
+    /// The balance doesn't cover the value transferred.
+    InsufficientBalance(String),
+    /// A nullifier of the transaction is spent already.
+    SpentNullifier(String),
+    /// The transaction targets another chain.
+    InvalidChain(String),
+    /// The root of the transaction isn't in the state.
+    InvalidRoot(String),
+    /// The gas price of the transaction is too low.
+    GasPriceTooLow(String),
+    /// The contract of the transaction couldn't be deployed.
+    DeploymentFailed(String),
+}
+
+/// The reasons the genesis contracts and the VM make a call panic with.
+///
+/// A call panics with the [`Display`] message of its reason, optionally
+/// followed by `": "` and a detail, which [`PanicReason::parse`] turns back
+/// into the reason. This keeps the classification of the panics in sync with
+/// the panics raised, instead of guessing the reason from free-form text.
+///
+/// [`Display`]: fmt::Display
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PanicReason {
+    /// The proof of a Phoenix transaction is invalid.
+    InvalidProof,
+    /// The nonce of a Moonlight transaction is used already.
+    UsedNonce,
+    /// The nonce signed isn't the one of the transaction.
+    IncorrectNonce,
+    /// The nonce of a Moonlight transaction is ahead of the account's.
+    NonceNotReady,
+    /// A signature of the transaction is invalid.
+    InvalidSignature,
+    /// The sender account has no funds.
+    NoFunds,
+    /// The sender account doesn't have enough funds.
+    NotEnoughFunds,
+    /// The caller doesn't have enough balance.
+    CallerBalance,
+    /// The contract doesn't have enough balance.
+    ContractBalance,
+    /// A nullifier of the transaction is spent already.
+    SpentNullifier,
+    /// The transaction targets another chain.
+    InvalidChain,
+    /// The root of the transaction isn't in the state.
+    RootNotFound,
+    /// The gas price of the transaction is too low.
+    GasPriceTooLow,
+    /// The bytecode of a deployment doesn't match its hash.
+    BytecodeHash,
+    /// The contract of a deployment couldn't be deployed.
+    DeploymentFailed,
+}
+
+impl PanicReason {
+    const ALL: [Self; 15] = [
+        Self::InvalidProof,
+        Self::UsedNonce,
+        Self::IncorrectNonce,
+        Self::NonceNotReady,
+        Self::InvalidSignature,
+        Self::NoFunds,
+        Self::NotEnoughFunds,
+        Self::CallerBalance,
+        Self::ContractBalance,
+        Self::SpentNullifier,
+        Self::InvalidChain,
+        Self::RootNotFound,
+        Self::GasPriceTooLow,
+        Self::BytecodeHash,
+        Self::DeploymentFailed,
+    ];
+
+    /// Returns the message a call panics with for this reason.
+    #[must_use]
+    pub const fn message(self) -> &'static str {
+        match self {
+            Self::InvalidProof => "Invalid transaction proof!",
+            Self::UsedNonce => "Already used nonce",
+            Self::IncorrectNonce => "Incorrect nonce signed",
+            Self::NonceNotReady => "Nonce not ready to be used yet",
+            Self::InvalidSignature => "Invalid signature",
+            Self::NoFunds => "Account has no funds",
+            Self::NotEnoughFunds => "Account doesn't have enough funds",
+            Self::CallerBalance => "Caller must have enough balance",
+            Self::ContractBalance => "The contract doesn't have enough balance",
+            Self::SpentNullifier => {
+                "A provided nullifier has already been spent"
+            }
+            Self::InvalidChain => "The tx must target the correct chain",
+            Self::RootNotFound => "Root not found in the state!",
+            Self::GasPriceTooLow => "Gas price too low!",
+            Self::BytecodeHash => "failed bytecode hash check",
+            Self::DeploymentFailed => "failed deployment",
+        }
+    }
+
+    /// Returns the message calls panicked with for this reason before it was
+    /// raised as such, if it differs from the current one.
+    ///
+    /// The deployed contracts keep panicking with it until upgraded.
+    const fn legacy_message(self) -> Option<&'static str> {
+        match self {
+            Self::InvalidSignature => Some("Invalid signature!"),
+            _ => None,
+        }
+    }
+
+    /// Returns the reason a call panicked with the given message, if it
+    /// panicked with one.
+    #[must_use]
+    pub fn parse(msg: &str) -> Option<Self> {
+        let matches = |reason: &str| {
+            msg == reason
+                || msg
+                    .strip_prefix(reason)
+                    .is_some_and(|detail| detail.starts_with(": "))
+        };
+        Self::ALL.into_iter().find(|reason| {
+            matches(reason.message())
+                || reason.legacy_message().is_some_and(matches)
+        })
+    }
+
+    /// Returns the execution error of a call panicked with this reason.
+    fn error(self, msg: String) -> ExecutionError {
+        match self {
+            Self::InvalidProof => ExecutionError::InvalidProof(msg),
+            Self::UsedNonce | Self::IncorrectNonce | Self::NonceNotReady => {
+                ExecutionError::InvalidNonce(msg)
+            }
+            Self::InvalidSignature => ExecutionError::InvalidSignature(msg),
+            Self::NoFunds
+            | Self::NotEnoughFunds
+            | Self::CallerBalance
+            | Self::ContractBalance => ExecutionError::InsufficientBalance(msg),
+            Self::SpentNullifier => ExecutionError::SpentNullifier(msg),
+            Self::InvalidChain => ExecutionError::InvalidChain(msg),
+            Self::RootNotFound => ExecutionError::InvalidRoot(msg),
+            Self::GasPriceTooLow => ExecutionError::GasPriceTooLow(msg),
+            Self::BytecodeHash | Self::DeploymentFailed => {
+                ExecutionError::DeploymentFailed(msg)
+            }
+        }
+    }
+}
+
+impl fmt::Display for PanicReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+const PANIC_PREFIX: &str = "Panic: ";
+const OUT_OF_GAS: &str = "OutOfGas";
+const CONTRACT_NOT_FOUND: &str = "Contract does not exist";
+
+impl ExecutionError {
+    /// Returns the error of a call that panicked with the given message.
+    #[must_use]
+    pub fn from_panic(msg: String) -> Self {
+        match PanicReason::parse(&msg) {
+            Some(reason) => reason.error(msg),
+            None => Self::Panic(msg),
+        }
+    }
+
+    /// Returns the stable numeric code of the reason.
+    #[must_use]
+    pub const fn code(&self) -> u16 {
+        match self {
+            Self::Unknown(_) => 0,
+            Self::OutOfGas => 1,
+            Self::ContractNotFound => 2,
+            Self::Panic(_) => 3,
+            Self::InvalidProof(_) => 4,
+            Self::InvalidNonce(_) => 5,
+            Self::InvalidSignature(_) => 6,
+            Self::InsufficientBalance(_) => 7,
+            Self::SpentNullifier(_) => 8,
+            Self::InvalidChain(_) => 9,
+            Self::InvalidRoot(_) => 10,
+            Self::GasPriceTooLow(_) => 11,
+            Self::DeploymentFailed(_) => 12,
+        }
+    }
+
+    /// Returns the message of the panic, if the call panicked.
+    #[must_use]
+    pub fn panic_msg(&self) -> Option<&str> {
+        match self {
+            Self::Unknown(_) | Self::OutOfGas | Self::ContractNotFound => None,
+            Self::Panic(msg)
+            | Self::InvalidProof(msg)
+            | Self::InvalidNonce(msg)
+            | Self::InvalidSignature(msg)
+            | Self::InsufficientBalance(msg)
+            | Self::SpentNullifier(msg)
+            | Self::InvalidChain(msg)
+            | Self::InvalidRoot(msg)
+            | Self::GasPriceTooLow(msg)
+            | Self::DeploymentFailed(msg) => Some(msg),
+        }
+    }
+}
+
+/// The message of an error is the one of the [`ContractError`] it comes
+/// from, so that it parses back into the same error.
+impl fmt::Display for ExecutionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unknown(desc) => write!(f, "{desc}"),
+            Self::OutOfGas => write!(f, "{OUT_OF_GAS}"),
+            Self::ContractNotFound => write!(f, "{CONTRACT_NOT_FOUND}"),
+            _ => {
+                let msg = self.panic_msg().unwrap_or_default();
+                write!(f, "{PANIC_PREFIX}{msg}")
+            }
+        }
+    }
+}
+
+impl FromStr for ExecutionError {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            OUT_OF_GAS => Self::OutOfGas,
+            CONTRACT_NOT_FOUND => Self::ContractNotFound,
+            _ => match s.strip_prefix(PANIC_PREFIX) {
+                Some(msg) => Self::from_panic(msg.to_string()),
+                None => Self::Unknown(s.to_string()),
+            },
+        })
+    }
+}
+
+impl From<ContractError> for ExecutionError {
+    fn from(err: ContractError) -> Self {
+        match err {
+            ContractError::Panic(msg) => Self::from_panic(msg),
+            ContractError::OutOfGas => Self::OutOfGas,
+            ContractError::DoesNotExist => Self::ContractNotFound,
+            ContractError::Unknown => Self::Unknown(err.to_string()),
+        }
+    }
+}
@@ -0,0 +1,81 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Checksummed string encoding of keys and addresses.
+//!
+//! Keys and addresses are encoded with `Base58Check`: the base58 encoding of a
+//! version byte identifying the type, followed by the bytes of the key and a
+//! 4-byte checksum.
+
+use alloc::format;
+use alloc::string::String;
+
+use dusk_bytes::Serializable;
+
+use crate::signatures::bls::PublicKey as AccountPublicKey;
+use crate::transfer::phoenix::{PublicKey as PhoenixPublicKey, StealthAddress};
+use crate::Error;
+
+/// Types with a checksummed base58 string encoding.
+pub trait Base58Check: Sized {
+    /// The version byte identifying the encoded type.
+    const VERSION: u8;
+
+    /// Encodes `self` as a checksummed base58 string.
+    fn to_base58_check(&self) -> String;
+
+    /// Decodes a checksummed base58 string.
+    ///
+    /// # Errors
+    /// The decoding fails if the string is not valid base58, if its checksum
+    /// or version don't match, or if it doesn't encode a valid `Self`.
+    fn from_base58_check(s: &str) -> Result<Self, Error>;
+}
+
+macro_rules! base58_check {
+    ($ty:ty, $version:expr) => {
+        impl Base58Check for $ty {
+            const VERSION: u8 = $version;
+
+            fn to_base58_check(&self) -> String {
+                bs58::encode(self.to_bytes())
+                    .with_check_version(Self::VERSION)
+                    .into_string()
+            }
+
+            fn from_base58_check(s: &str) -> Result<Self, Error> {
+                let decoded = bs58::decode(s)
+                    .with_check(Some(Self::VERSION))
+                    .into_vec()?;
+
+                // the first byte is the version
+                let bytes = &decoded[1..];
+                let bytes = bytes
+                    .try_into()
+                    .map_err(|_| Error::BadLength(bytes.len(), Self::SIZE))?;
+                Ok(Self::from_bytes(bytes)?)
+            }
+        }
+    };
+}
+
+base58_check!(StealthAddress, 0x01);
+base58_check!(PhoenixPublicKey, 0x02);
+base58_check!(AccountPublicKey, 0x03);
+
+impl From<bs58::decode::Error> for Error {
+    fn from(err: bs58::decode::Error) -> Self {
+        match err {
+            bs58::decode::Error::InvalidChecksum { .. } => {
+                Self::InvalidChecksum
+            }
+            bs58::decode::Error::InvalidVersion { ver, expected_ver } => {
+                Self::InvalidVersion(ver, expected_ver)
+            }
+            err => Self::InvalidBase58(format!("{err}")),
+        }
+    }
+}
@@ -35,6 +35,27 @@ pub enum Error {
     ///
     /// [`MAX_MEMO_SIZE`]: crate::transfer::data::MAX_MEMO_SIZE
     MemoTooLarge(usize),
+    /// The string is not valid base58.
+    InvalidBase58(String),
+    /// The checksum of an encoded key or address doesn't match.
+    InvalidChecksum,
+    /// The version byte of an encoded key or address doesn't match its type.
+    /// Contains the version found and the expected one.
+    InvalidVersion(u8, u8),
+    /// The number of transactions of a bundle is either zero or larger than
+    /// [`MAX_BUNDLE_TXS`]. Contains the number of transactions given.
+    ///
+    /// [`MAX_BUNDLE_TXS`]: crate::transfer::bundle::MAX_BUNDLE_TXS
+    BundleSize(usize),
+    /// A Phoenix transaction can't be sponsored, its gas being paid from its
+    /// input notes.
+    PhoenixSponsorship,
+    /// The version of the encrypted note metadata isn't supported. Contains
+    /// the version found.
+    MetadataVersion(u8),
+    /// The note metadata can't be decrypted, being encrypted for another key
+    /// or tampered with.
+    MetadataDecryption,
 }
 
 impl fmt::Display for Error {
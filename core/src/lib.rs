@@ -20,6 +20,8 @@ extern crate alloc;
 
 pub mod abi;
 
+pub mod encoding;
+
 pub mod stake;
 pub mod transfer;
 
@@ -9,21 +9,24 @@
 #[cfg(feature = "serde")]
 use serde_with::{hex::Hex, serde_as, DisplayFromStr};
 
+use alloc::format;
 use alloc::string::String;
 use alloc::vec::Vec;
 
 use bytecheck::CheckBytes;
 use dusk_bytes::Serializable;
 use piecrust_uplink::CONTRACT_ID_BYTES;
-use rkyv::{Archive, Deserialize, Serialize};
+use rkyv::validation::validators::DefaultValidator;
+use rkyv::{Archive, Deserialize, Infallible, Serialize};
 
 use crate::abi::ContractId;
 use crate::signatures::bls::{
     PublicKey as BlsPublicKey, SecretKey as BlsSecretKey,
     Signature as BlsSignature,
 };
+use crate::transfer::data::ContractCall;
 use crate::transfer::withdraw::Withdraw as TransferWithdraw;
-use crate::{dusk, Dusk};
+use crate::{dusk, Dusk, Error};
 
 /// ID of the genesis stake contract
 pub const STAKE_CONTRACT: ContractId = crate::reserved(0x2);
@@ -371,6 +374,115 @@ impl Withdraw {
     }
 }
 
+/// Rotation of the account key of a stake to a new key.
+///
+/// The rotation is registered during an epoch and takes effect at the start of
+/// the next one, when the stake contract moves the stake to the next account
+/// key. From then on, the next key replaces the current one in the consensus.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+#[cfg_attr(feature = "serde", cfg_eval, serde_as)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KeyRotation {
+    chain_id: u8,
+    account: BlsPublicKey,
+    next_account: BlsPublicKey,
+    #[cfg_attr(feature = "serde", serde_as(as = "DisplayFromStr"))]
+    activation: u64,
+    pop: ProofOfPossession,
+    signature: BlsSignature,
+}
+
+impl KeyRotation {
+    const MESSAGE_SIZE: usize =
+        1 + BlsPublicKey::SIZE + BlsPublicKey::SIZE + u64::SIZE;
+
+    /// Creates the rotation of the account key of `account_sk` to the one of
+    /// `next_sk`, taking effect at the `activation` epoch height.
+    ///
+    /// The rotation is signed with the current account key, and carries the
+    /// proof of possession of the next one.
+    #[must_use]
+    pub fn new(
+        account_sk: &BlsSecretKey,
+        next_sk: &BlsSecretKey,
+        activation: u64,
+        chain_id: u8,
+    ) -> Self {
+        let mut rotation = KeyRotation {
+            chain_id,
+            account: BlsPublicKey::from(account_sk),
+            next_account: BlsPublicKey::from(next_sk),
+            activation,
+            pop: ProofOfPossession::new(next_sk),
+            signature: BlsSignature::default(),
+        };
+
+        let msg = rotation.signature_message();
+        rotation.signature = account_sk.sign(&msg);
+
+        rotation
+    }
+
+    /// Account key being rotated.
+    #[must_use]
+    pub fn account(&self) -> &BlsPublicKey {
+        &self.account
+    }
+
+    /// Account key replacing the current one.
+    #[must_use]
+    pub fn next_account(&self) -> &BlsPublicKey {
+        &self.next_account
+    }
+
+    /// Height of the epoch the rotation takes effect at.
+    #[must_use]
+    pub fn activation(&self) -> u64 {
+        self.activation
+    }
+
+    /// Returns the chain ID of the rotation.
+    #[must_use]
+    pub fn chain_id(&self) -> u8 {
+        self.chain_id
+    }
+
+    /// Proof of possession of the next account key.
+    #[must_use]
+    pub fn pop(&self) -> &ProofOfPossession {
+        &self.pop
+    }
+
+    /// Signature of the rotation, created with the current account key.
+    #[must_use]
+    pub fn signature(&self) -> &BlsSignature {
+        &self.signature
+    }
+
+    /// Return the message that is used as the input to the signature.
+    #[must_use]
+    pub fn signature_message(&self) -> [u8; Self::MESSAGE_SIZE] {
+        let mut bytes = [0u8; Self::MESSAGE_SIZE];
+
+        bytes[0] = self.chain_id;
+        let mut offset = 1;
+
+        bytes[offset..offset + BlsPublicKey::SIZE]
+            .copy_from_slice(&self.account.to_bytes());
+        offset += BlsPublicKey::SIZE;
+
+        bytes[offset..offset + BlsPublicKey::SIZE]
+            .copy_from_slice(&self.next_account.to_bytes());
+        offset += BlsPublicKey::SIZE;
+
+        bytes[offset..offset + u64::SIZE]
+            .copy_from_slice(&self.activation.to_bytes());
+
+        bytes
+    }
+}
+
 /// Event emitted after a stake contract operation is performed.
 #[derive(Debug, Clone, Archive, Deserialize, Serialize, PartialEq)]
 #[archive_attr(derive(CheckBytes))]
@@ -434,6 +546,23 @@ pub struct SlashEvent {
     pub next_eligibility: u64,
 }
 
+/// Event emitted after the rotation of an account key is registered, with the
+/// `rotate_key` topic, and once the stake is moved to the next key, with the
+/// `key_rotated` topic.
+#[derive(Debug, Clone, Archive, Deserialize, Serialize, PartialEq)]
+#[archive_attr(derive(CheckBytes))]
+#[cfg_attr(feature = "serde", cfg_eval, serde_as)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KeyRotationEvent {
+    /// Account key being rotated.
+    pub account: BlsPublicKey,
+    /// Account key replacing the current one.
+    pub next_account: BlsPublicKey,
+    /// Height of the epoch the rotation takes effect at
+    #[cfg_attr(feature = "serde", serde_as(as = "DisplayFromStr"))]
+    pub activation: u64,
+}
+
 /// The representation of a public key's stake.
 ///
 /// A user can stake for a particular `amount` larger in value than the
@@ -561,6 +690,51 @@ pub struct DoubleSignature {
     pub owner: BlsSignature,
 }
 
+/// Proof of possession of a BLS secret key.
+///
+/// It's the signature of the public key itself, so that a key can only be
+/// registered by whoever holds its secret key. This prevents forging a "rogue"
+/// key out of other provisioners' keys, which would let an aggregated
+/// signature pass for one of them all.
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, Archive, Deserialize, Serialize,
+)]
+#[archive_attr(derive(CheckBytes))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProofOfPossession(BlsSignature);
+
+impl ProofOfPossession {
+    /// Domain separating the proofs of possession from the other signatures
+    const DOMAIN: &'static [u8] = b"DUSK_BLS_POP";
+
+    /// Creates the proof of possession of `sk`.
+    #[must_use]
+    pub fn new(sk: &BlsSecretKey) -> Self {
+        let pk = BlsPublicKey::from(sk);
+        Self(sk.sign(&Self::message(&pk)))
+    }
+
+    /// Returns the message signed to prove the possession of `pk`.
+    #[must_use]
+    pub fn message(pk: &BlsPublicKey) -> Vec<u8> {
+        let mut msg = Self::DOMAIN.to_vec();
+        msg.extend(pk.to_bytes());
+        msg
+    }
+
+    /// Returns the signature proving the possession.
+    #[must_use]
+    pub fn signature(&self) -> &BlsSignature {
+        &self.0
+    }
+
+    /// Returns `true` if this is a valid proof of possession of `pk`.
+    #[must_use]
+    pub fn verify(&self, pk: &BlsPublicKey) -> bool {
+        pk.verify(&self.0, &Self::message(pk)).is_ok()
+    }
+}
+
 impl StakeData {
     /// An empty stake.
     pub const EMPTY: Self = Self {
@@ -721,3 +895,157 @@ pub enum RewardReason {
     /// Amount awarded for another reason, such as rewarding Dusk.
     Other,
 }
+
+/// Versioned arguments of the `stake` call.
+///
+/// Future additions to the call arguments are introduced as new variants, so
+/// that transactions carrying older versions remain decodable.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub enum VersionedStake {
+    /// The first version, equal to the unversioned [`Stake`].
+    V1(Stake),
+    /// A stake along with the proof of possession of its account key.
+    V2 {
+        /// The stake
+        stake: Stake,
+        /// Proof of possession of the account key of the stake
+        pop: ProofOfPossession,
+    },
+}
+
+impl From<Stake> for VersionedStake {
+    fn from(stake: Stake) -> Self {
+        Self::V1(stake)
+    }
+}
+
+impl VersionedStake {
+    /// Creates a stake carrying the proof of possession of its account key.
+    #[must_use]
+    pub fn with_pop(stake: Stake, account_sk: &BlsSecretKey) -> Self {
+        let pop = ProofOfPossession::new(account_sk);
+        Self::V2 { stake, pop }
+    }
+
+    /// Returns the stake, whatever the version.
+    #[must_use]
+    pub fn stake(&self) -> &Stake {
+        match self {
+            Self::V1(stake) | Self::V2 { stake, .. } => stake,
+        }
+    }
+
+    /// Returns the proof of possession of the account key, if any.
+    #[must_use]
+    pub fn pop(&self) -> Option<&ProofOfPossession> {
+        match self {
+            Self::V1(_) => None,
+            Self::V2 { pop, .. } => Some(pop),
+        }
+    }
+}
+
+/// Versioned arguments of the `unstake` and `withdraw` calls.
+///
+/// Future additions to the call arguments are introduced as new variants, so
+/// that transactions carrying older versions remain decodable.
+#[derive(Debug, Clone, PartialEq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub enum VersionedWithdraw {
+    /// The first version, equal to the unversioned [`Withdraw`].
+    V1(Withdraw),
+}
+
+impl From<Withdraw> for VersionedWithdraw {
+    fn from(withdraw: Withdraw) -> Self {
+        Self::V1(withdraw)
+    }
+}
+
+/// A call to the stake contract, with its arguments decoded.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StakeCall {
+    /// A call to `stake`.
+    Stake(VersionedStake),
+    /// A call to `unstake`.
+    Unstake(VersionedWithdraw),
+    /// A call to `withdraw`.
+    Withdraw(VersionedWithdraw),
+    /// A call to `rotate_key`.
+    RotateKey(KeyRotation),
+}
+
+/// Decodes the arguments of a `stake`, `unstake`, `withdraw` or `rotate_key`
+/// call to the stake contract.
+///
+/// Both the versioned and the legacy, unversioned, encodings of the
+/// arguments are accepted. Returns `None` if `call` targets another contract
+/// or function.
+///
+/// # Errors
+/// Errors if the arguments cannot be decoded.
+pub fn parse_stake_call(
+    call: &ContractCall,
+) -> Result<Option<StakeCall>, Error> {
+    if call.contract != STAKE_CONTRACT {
+        return Ok(None);
+    }
+
+    let call = match call.fn_name.as_str() {
+        "stake" => StakeCall::Stake(decode_stake(&call.fn_args)?),
+        "unstake" => {
+            StakeCall::Unstake(decode_versioned::<_, Withdraw>(&call.fn_args)?)
+        }
+        "withdraw" => {
+            StakeCall::Withdraw(decode_versioned::<_, Withdraw>(&call.fn_args)?)
+        }
+        "rotate_key" => {
+            let rotation =
+                rkyv::check_archived_root::<KeyRotation>(&call.fn_args)
+                    .map_err(|e| Error::Rkyv(format!("{e:?}")))?;
+            StakeCall::RotateKey(
+                rotation
+                    .deserialize(&mut Infallible)
+                    .expect("Infallible deserialization"),
+            )
+        }
+        _ => return Ok(None),
+    };
+
+    Ok(Some(call))
+}
+
+/// Decodes the arguments of a `stake` call, either versioned or legacy.
+///
+/// # Errors
+/// Errors if the arguments cannot be decoded.
+pub fn decode_stake(bytes: &[u8]) -> Result<VersionedStake, Error> {
+    decode_versioned::<_, Stake>(bytes)
+}
+
+/// Decodes `bytes` as the versioned type `V`, falling back to its legacy
+/// unversioned counterpart `L`.
+fn decode_versioned<V, L>(bytes: &[u8]) -> Result<V, Error>
+where
+    V: Archive + From<L>,
+    V::Archived:
+        for<'a> CheckBytes<DefaultValidator<'a>> + Deserialize<V, Infallible>,
+    L: Archive,
+    L::Archived:
+        for<'a> CheckBytes<DefaultValidator<'a>> + Deserialize<L, Infallible>,
+{
+    if let Ok(versioned) = rkyv::check_archived_root::<V>(bytes) {
+        return Ok(versioned
+            .deserialize(&mut Infallible)
+            .expect("Infallible deserialization"));
+    }
+
+    let legacy = rkyv::check_archived_root::<L>(bytes)
+        .map_err(|e| Error::Rkyv(format!("{e:?}")))?;
+    Ok(V::from(
+        legacy
+            .deserialize(&mut Infallible)
+            .expect("Infallible deserialization"),
+    ))
+}
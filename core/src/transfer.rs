@@ -36,16 +36,20 @@ use self::phoenix::{
 };
 use self::withdraw::{Withdraw, WithdrawReceiver};
 
+pub mod bundle;
 pub mod data;
 pub mod moonlight;
+pub mod params;
 pub mod phoenix;
+pub mod sponsor;
 pub mod withdraw;
 
 /// ID of the genesis transfer contract
 pub const TRANSFER_CONTRACT: ContractId = crate::reserved(0x1);
 
 /// Panic of "Nonce not ready to be used yet"
-pub const PANIC_NONCE_NOT_READY: &str = "Nonce not ready to be used yet";
+pub const PANIC_NONCE_NOT_READY: &str =
+    crate::abi::PanicReason::NonceNotReady.message();
 
 /// Topic for the moonlight transaction event.
 pub const MOONLIGHT_TOPIC: &str = "moonlight";
@@ -65,6 +69,8 @@ pub const CONVERT_TOPIC: &str = "convert";
 pub const MINT_TOPIC: &str = "mint";
 /// Topic for the mint to contract event.
 pub const MINT_CONTRACT_TOPIC: &str = "mint_c";
+/// Topic for the sponsor event.
+pub const SPONSOR_TOPIC: &str = "sponsor";
 
 /// The transaction used by the transfer contract.
 #[derive(Debug, Clone, Archive, PartialEq, Eq, Serialize, Deserialize)]
@@ -84,7 +90,8 @@ impl Transaction {
     /// The creation of a transaction is not possible and will error if:
     /// - one of the input-notes doesn't belong to the `sender_sk`
     /// - the transaction input doesn't cover the transaction costs
-    /// - the `inputs` vector is either empty or larger than 4 elements
+    /// - the `inputs` vector is either empty or larger than
+    ///   [`MAX_INPUT_NOTES`](phoenix::MAX_INPUT_NOTES) elements
     /// - the `inputs` vector contains duplicate `Note`s
     /// - the `Prove` trait is implemented incorrectly
     #[allow(clippy::too_many_arguments)]
@@ -0,0 +1,163 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Bundles of transactions to be included in a block all together, in order,
+//! or not at all.
+
+use alloc::vec::Vec;
+
+use bytecheck::CheckBytes;
+use dusk_bytes::{DeserializableSlice, Error as BytesError, Serializable};
+use rkyv::{Archive, Deserialize, Serialize};
+
+use crate::transfer::Transaction;
+use crate::Error;
+
+/// The maximum number of transactions in a bundle.
+pub const MAX_BUNDLE_TXS: usize = 16;
+
+/// The header of a [`TransactionBundle`], summing up its transactions.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Archive, Serialize, Deserialize,
+)]
+#[archive_attr(derive(CheckBytes))]
+pub struct BundleHeader {
+    /// The number of transactions in the bundle.
+    pub tx_count: u32,
+    /// The combined size of the serialized transactions, in bytes.
+    pub size: u64,
+    /// The combined gas limit of the transactions.
+    pub gas_limit: u64,
+}
+
+impl BundleHeader {
+    /// The size of the serialized header.
+    pub const SIZE: usize = u32::SIZE + 2 * u64::SIZE;
+
+    fn compute(txs: &[Transaction]) -> Self {
+        let size = txs.iter().map(|tx| tx.to_var_bytes().len() as u64).sum();
+        let gas_limit = txs
+            .iter()
+            .fold(0u64, |gas, tx| gas.saturating_add(tx.gas_limit()));
+
+        Self {
+            tx_count: txs.len() as u32,
+            size,
+            gas_limit,
+        }
+    }
+}
+
+/// An ordered list of transactions that are to be executed one after the
+/// other in the same block, and included only if none of them is discarded.
+///
+/// This allows for workflows with dependent transactions, such as deploying a
+/// contract and calling it in the same block.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct TransactionBundle {
+    header: BundleHeader,
+    txs: Vec<Transaction>,
+}
+
+impl TransactionBundle {
+    /// Create a new bundle out of the given transactions.
+    ///
+    /// # Errors
+    /// The creation of a bundle fails if the `txs` vector is either empty or
+    /// larger than [`MAX_BUNDLE_TXS`] elements.
+    pub fn new(txs: Vec<Transaction>) -> Result<Self, Error> {
+        if txs.is_empty() || txs.len() > MAX_BUNDLE_TXS {
+            return Err(Error::BundleSize(txs.len()));
+        }
+
+        Ok(Self {
+            header: BundleHeader::compute(&txs),
+            txs,
+        })
+    }
+
+    /// Return the header of the bundle.
+    #[must_use]
+    pub fn header(&self) -> &BundleHeader {
+        &self.header
+    }
+
+    /// Return the transactions of the bundle, in execution order.
+    #[must_use]
+    pub fn txs(&self) -> &[Transaction] {
+        &self.txs
+    }
+
+    /// Consume the bundle, returning its transactions in execution order.
+    #[must_use]
+    pub fn into_txs(self) -> Vec<Transaction> {
+        self.txs
+    }
+
+    /// Returns the combined gas limit of the transactions of the bundle.
+    #[must_use]
+    pub fn gas_limit(&self) -> u64 {
+        self.header.gas_limit
+    }
+
+    /// Serialize the bundle into a byte buffer.
+    #[must_use]
+    pub fn to_var_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(BundleHeader::SIZE);
+
+        bytes.extend(self.header.tx_count.to_bytes());
+        bytes.extend(self.header.size.to_bytes());
+        bytes.extend(self.header.gas_limit.to_bytes());
+
+        for tx in &self.txs {
+            let tx_bytes = tx.to_var_bytes();
+            bytes.extend((tx_bytes.len() as u64).to_bytes());
+            bytes.extend(tx_bytes);
+        }
+
+        bytes
+    }
+
+    /// Deserialize the bundle from a byte slice.
+    ///
+    /// # Errors
+    /// Errors when the bytes are not canonical, or when the header doesn't
+    /// match the transactions of the bundle.
+    pub fn from_slice(buf: &[u8]) -> Result<Self, BytesError> {
+        let mut buf = buf;
+
+        let header = BundleHeader {
+            tx_count: u32::from_reader(&mut buf)?,
+            size: u64::from_reader(&mut buf)?,
+            gas_limit: u64::from_reader(&mut buf)?,
+        };
+
+        let tx_count = header.tx_count as usize;
+        if tx_count == 0 || tx_count > MAX_BUNDLE_TXS {
+            return Err(BytesError::InvalidData);
+        }
+
+        let mut txs = Vec::with_capacity(tx_count);
+        for _ in 0..tx_count {
+            let tx_len = u64::from_reader(&mut buf)? as usize;
+            if buf.len() < tx_len {
+                return Err(BytesError::BadLength {
+                    found: buf.len(),
+                    expected: tx_len,
+                });
+            }
+            txs.push(Transaction::from_slice(&buf[..tx_len])?);
+            buf = &buf[tx_len..];
+        }
+
+        if !buf.is_empty() || BundleHeader::compute(&txs) != header {
+            return Err(BytesError::InvalidData);
+        }
+
+        Ok(Self { header, txs })
+    }
+}
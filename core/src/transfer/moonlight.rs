@@ -26,6 +26,10 @@ use crate::transfer::data::{
 };
 use crate::{BlsScalar, Error};
 
+/// Domain separating the signatures of the senders of sponsored transactions
+/// from the signatures of the other transactions.
+const SPONSORED_DOMAIN: &[u8] = b"sponsored";
+
 /// A Moonlight account's information.
 #[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
 #[archive_attr(derive(CheckBytes))]
@@ -151,6 +155,32 @@ impl Transaction {
         Ok(Self { payload, signature })
     }
 
+    /// Create a transaction whose gas is paid by `sponsor`, by signing a
+    /// previously generated payload with a given secret-key.
+    ///
+    /// The signature commits to the sponsor, so that the transaction is only
+    /// valid along with the sponsorship of `sponsor`.
+    ///
+    /// # Errors
+    /// The creation of a transaction is not possible and will error if:
+    /// - the payload memo, if given, is too large
+    pub fn sign_sponsored_payload(
+        sender_sk: &AccountSecretKey,
+        payload: Payload,
+        sponsor: &AccountPublicKey,
+    ) -> Result<Self, Error> {
+        if let Some(TransactionData::Memo(memo)) = payload.data.as_ref() {
+            if memo.len() > MAX_MEMO_SIZE {
+                return Err(Error::MemoTooLarge(memo.len()));
+            }
+        }
+
+        let digest = payload.sponsored_signature_message(sponsor);
+        let signature = sender_sk.sign(&digest);
+
+        Ok(Self { payload, signature })
+    }
+
     /// The proof of the transaction.
     #[must_use]
     pub fn signature(&self) -> &AccountSignature {
@@ -332,6 +362,16 @@ impl Transaction {
         self.payload.signature_message()
     }
 
+    /// Return the message that is meant to be signed over to make the
+    /// transaction a valid one, once its gas is paid by `sponsor`.
+    #[must_use]
+    pub fn sponsored_signature_message(
+        &self,
+        sponsor: &AccountPublicKey,
+    ) -> Vec<u8> {
+        self.payload.sponsored_signature_message(sponsor)
+    }
+
     /// Create the transaction hash.
     #[must_use]
     pub fn hash(&self) -> BlsScalar {
@@ -534,6 +574,22 @@ impl Payload {
 
         bytes
     }
+
+    /// Return input bytes to hash the payload of a transaction whose gas is
+    /// paid by `sponsor`.
+    ///
+    /// Note: The result of this function is *only* meant to be used as an input
+    /// for hashing and *cannot* be used to deserialize the payload again.
+    #[must_use]
+    pub fn sponsored_signature_message(
+        &self,
+        sponsor: &AccountPublicKey,
+    ) -> Vec<u8> {
+        let mut bytes = Vec::from(SPONSORED_DOMAIN);
+        bytes.extend(self.signature_message());
+        bytes.extend(sponsor.to_bytes());
+        bytes
+    }
 }
 
 /// The Fee structure
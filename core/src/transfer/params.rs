@@ -0,0 +1,36 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Parameters shared by the transfer-contract types and the phoenix
+//! transaction circuits.
+//!
+//! Proofs are generated and verified with circuits instantiated with these
+//! parameters, while the transfer-contract stores its notes in a tree built
+//! with them. The assertions below fail the compilation if the circuit or tree
+//! dependencies disagree with them, instead of producing proofs that silently
+//! fail verification.
+
+/// The depth of the merkle tree of notes stored in the transfer-contract.
+pub const NOTES_TREE_DEPTH: usize = 17;
+/// The arity of the merkle tree of notes stored in the transfer-contract.
+pub const NOTES_TREE_ARITY: usize = 4;
+/// The maximum number of input-notes of a phoenix-transaction, each number of
+/// inputs up to it having its own circuit.
+pub const MAX_INPUT_NOTES: usize = 4;
+/// The number of output-notes of a phoenix-transaction.
+pub const OUTPUT_NOTES: usize = 2;
+
+#[allow(clippy::assertions_on_constants)]
+const _: () = {
+    assert!(
+        NOTES_TREE_ARITY == poseidon_merkle::ARITY,
+        "the notes tree arity must match the one of the circuit openings"
+    );
+    assert!(
+        OUTPUT_NOTES == phoenix_core::OUTPUT_NOTES,
+        "the output-notes must match the ones of the circuits"
+    );
+};
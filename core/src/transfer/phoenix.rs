@@ -26,17 +26,18 @@ use crate::transfer::data::{
 };
 use crate::{BlsScalar, Error, JubJubAffine, JubJubScalar};
 
+pub mod metadata;
+
 // phoenix types
 pub use phoenix_circuits::{InputNoteInfo, OutputNoteInfo, TxCircuit};
 pub use phoenix_core::{
     value_commitment, Error as CoreError, Note, PublicKey, SecretKey, Sender,
-    StealthAddress, TxSkeleton, ViewKey, NOTE_VAL_ENC_SIZE, OUTPUT_NOTES,
+    StealthAddress, TxSkeleton, ViewKey, NOTE_VAL_ENC_SIZE,
 };
 
-/// The depth of the merkle tree of notes stored in the transfer-contract.
-pub const NOTES_TREE_DEPTH: usize = 17;
-/// The arity of the merkle tree of notes stored in the transfer-contract.
-pub use poseidon_merkle::ARITY as NOTES_TREE_ARITY;
+pub use crate::transfer::params::{
+    MAX_INPUT_NOTES, NOTES_TREE_ARITY, NOTES_TREE_DEPTH, OUTPUT_NOTES,
+};
 /// The merkle tree of notes stored in the transfer-contract.
 pub type NotesTree = poseidon_merkle::Tree<(), NOTES_TREE_DEPTH>;
 /// The merkle opening for a note-hash in the merkle tree of notes.
@@ -73,6 +74,47 @@ impl cmp::PartialOrd for NoteLeaf {
     }
 }
 
+/// The leaves of the merkle tree of notes inserted by a single block.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct NoteLeafBatch {
+    /// The height of the block that inserted the notes.
+    pub block_height: u64,
+    /// The hash of the block that inserted the notes.
+    pub block_hash: [u8; 32],
+    /// The notes inserted by the block, ordered by position.
+    pub leaves: Vec<BatchedNoteLeaf>,
+}
+
+/// A leaf of a [`NoteLeafBatch`].
+///
+/// The stealth address is enough for a wallet to check the ownership of the
+/// note, while the full note carries the encrypted value and sender needed to
+/// spend it.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct BatchedNoteLeaf {
+    /// The position of the note in the tree.
+    pub pos: u64,
+    /// The stealth address the note is sent to.
+    pub stealth_address: StealthAddress,
+    /// The full note, only present if it was requested.
+    pub note: Option<Note>,
+}
+
+impl BatchedNoteLeaf {
+    /// Creates a new batched leaf out of `note`, dropping the note itself
+    /// unless `with_note` is set.
+    #[must_use]
+    pub fn new(note: Note, with_note: bool) -> Self {
+        Self {
+            pos: *note.pos(),
+            stealth_address: *note.stealth_address(),
+            note: with_note.then_some(note),
+        }
+    }
+}
+
 /// Label used for the ZK transcript initialization. Must be the same for prover
 /// and verifier.
 pub const TRANSCRIPT_LABEL: &[u8] = b"dusk-network";
@@ -102,7 +144,8 @@ impl Transaction {
     /// The creation of a transaction is not possible and will error if:
     /// - one of the input-notes doesn't belong to the `sender_sk`
     /// - the transaction input doesn't cover the transaction costs
-    /// - the `inputs` vector is either empty or larger than 4 elements
+    /// - the `inputs` vector is either empty or larger than [`MAX_INPUT_NOTES`]
+    ///   elements
     /// - the `inputs` vector contains duplicate `Note`s
     /// - the `prover` is implemented incorrectly
     /// - the memo, if given, is too large
@@ -1011,6 +1054,23 @@ impl TxCircuitVec {
     }
 }
 
+// A `TxCircuitVec` is serialized as the circuit it mimics, prefixed by the
+// amount of input-notes, and there is a circuit for each amount of them.
+#[allow(clippy::assertions_on_constants)]
+const _: () = {
+    const fn circuit_size<const I: usize>() -> usize {
+        u64::SIZE + TxCircuit::<NOTES_TREE_DEPTH, I>::SIZE
+    }
+    assert!(TxCircuitVec::size(1) == circuit_size::<1>());
+    assert!(TxCircuitVec::size(2) == circuit_size::<2>());
+    assert!(TxCircuitVec::size(3) == circuit_size::<3>());
+    assert!(TxCircuitVec::size(4) == circuit_size::<4>());
+    assert!(
+        MAX_INPUT_NOTES == 4,
+        "a circuit is needed per input-notes amount"
+    );
+};
+
 impl<const I: usize> From<TxCircuit<NOTES_TREE_DEPTH, I>> for TxCircuitVec {
     fn from(circuit: TxCircuit<NOTES_TREE_DEPTH, I>) -> Self {
         TxCircuitVec {
@@ -0,0 +1,165 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Encryption of the metadata of a note, such as its memo, for the view key
+//! of its recipient.
+//!
+//! The metadata is encrypted with AES-256-GCM, under a key derived with
+//! HKDF-SHA256 from a Diffie-Hellman exchange between an ephemeral key of the
+//! sender and the `A` key of the recipient.
+//!
+//! The encrypted metadata is laid out as:
+//!
+//! | version | ephemeral key | nonce    | ciphertext and tag      |
+//! |---------|---------------|----------|-------------------------|
+//! | 1 byte  | 32 bytes      | 12 bytes | metadata size + 16 bytes |
+//!
+//! the version and the ephemeral key being authenticated along with the
+//! metadata.
+
+use alloc::vec::Vec;
+
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use dusk_bytes::{DeserializableSlice, Serializable};
+use ff::Field;
+use hkdf::Hkdf;
+use rand::{CryptoRng, RngCore};
+use sha2::Sha256;
+
+use crate::transfer::data::MAX_MEMO_SIZE;
+use crate::transfer::phoenix::{PublicKey, ViewKey};
+use crate::{
+    Error, JubJubAffine, JubJubExtended, JubJubScalar, GENERATOR_EXTENDED,
+};
+
+/// Version of the encryption scheme.
+pub const VERSION: u8 = 1;
+
+/// Number of bytes the encrypted metadata is larger than the metadata.
+pub const OVERHEAD: usize = HEADER_SIZE + NONCE_SIZE + TAG_SIZE;
+
+/// Maximum size of the metadata, for its encryption to fit in a memo.
+pub const MAX_METADATA_SIZE: usize = MAX_MEMO_SIZE - OVERHEAD;
+
+const KEY_INFO: &[u8] = b"dusk-note-metadata-v1";
+const HEADER_SIZE: usize = 1 + JubJubAffine::SIZE;
+const NONCE_SIZE: usize = 12;
+const TAG_SIZE: usize = 16;
+
+/// Encrypts `metadata` for the owner of `pk`, to be decrypted with their
+/// view key.
+///
+/// # Errors
+/// Returns [`Error::MemoTooLarge`] if `metadata` is larger than
+/// [`MAX_METADATA_SIZE`].
+pub fn encrypt<R: RngCore + CryptoRng>(
+    rng: &mut R,
+    pk: &PublicKey,
+    metadata: &[u8],
+) -> Result<Vec<u8>, Error> {
+    if metadata.len() > MAX_METADATA_SIZE {
+        return Err(Error::MemoTooLarge(metadata.len() + OVERHEAD));
+    }
+
+    let r = JubJubScalar::random(&mut *rng);
+    let ephemeral = JubJubAffine::from(GENERATOR_EXTENDED * r).to_bytes();
+    let shared = JubJubAffine::from(pk.A() * &r).to_bytes();
+
+    let mut nonce = [0u8; NONCE_SIZE];
+    rng.fill_bytes(&mut nonce);
+
+    Ok(seal(&shared, &ephemeral, &nonce, metadata))
+}
+
+/// Decrypts the metadata encrypted for the owner of `vk`.
+///
+/// # Errors
+/// Returns [`Error::MetadataVersion`] if the version of `encrypted` isn't
+/// supported, [`Error::BadLength`] if it's shorter than [`OVERHEAD`],
+/// [`Error::InvalidData`] if its ephemeral key isn't a valid point, and
+/// [`Error::MetadataDecryption`] if it isn't encrypted for `vk` or has been
+/// tampered with.
+pub fn decrypt(vk: &ViewKey, encrypted: &[u8]) -> Result<Vec<u8>, Error> {
+    match encrypted.first() {
+        Some(&VERSION) => {}
+        Some(&version) => return Err(Error::MetadataVersion(version)),
+        None => return Err(Error::BadLength(0, OVERHEAD)),
+    }
+    if encrypted.len() < OVERHEAD {
+        return Err(Error::BadLength(encrypted.len(), OVERHEAD));
+    }
+
+    let (header, rest) = encrypted.split_at(HEADER_SIZE);
+    let (nonce, ciphertext) = rest.split_at(NONCE_SIZE);
+
+    let ephemeral = JubJubAffine::from_slice(&header[1..])?;
+    let shared =
+        JubJubAffine::from(JubJubExtended::from(ephemeral) * vk.a()).to_bytes();
+
+    cipher(&shared, &header[1..])
+        .decrypt(
+            Nonce::from_slice(nonce),
+            Payload {
+                msg: ciphertext,
+                aad: header,
+            },
+        )
+        .map_err(|_| Error::MetadataDecryption)
+}
+
+/// Derives the cipher from the shared secret and the ephemeral key.
+fn cipher(shared: &[u8], ephemeral: &[u8]) -> Aes256Gcm {
+    let mut key = Key::<Aes256Gcm>::default();
+    Hkdf::<Sha256>::new(Some(ephemeral), shared)
+        .expand(KEY_INFO, &mut key)
+        .expect("the key size to be a valid HKDF-SHA256 output size");
+    Aes256Gcm::new(&key)
+}
+
+fn seal(
+    shared: &[u8],
+    ephemeral: &[u8],
+    nonce: &[u8; NONCE_SIZE],
+    metadata: &[u8],
+) -> Vec<u8> {
+    let mut encrypted = Vec::with_capacity(metadata.len() + OVERHEAD);
+    encrypted.push(VERSION);
+    encrypted.extend_from_slice(ephemeral);
+
+    let ciphertext = cipher(shared, ephemeral)
+        .encrypt(
+            Nonce::from_slice(nonce),
+            Payload {
+                msg: metadata,
+                aad: &encrypted,
+            },
+        )
+        .expect("the metadata size to be within the AES-GCM limits");
+
+    encrypted.extend_from_slice(nonce);
+    encrypted.extend(ciphertext);
+    encrypted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vector() {
+        let encrypted = seal(&[1; 32], &[2; 32], &[3; NONCE_SIZE], b"dusk");
+
+        let mut expected = Vec::from([VERSION]);
+        expected.extend([2; 32]);
+        expected.extend([3; NONCE_SIZE]);
+        expected.extend([
+            0x7e, 0x39, 0x3d, 0xd8, 0xaf, 0x26, 0x5b, 0xf6, 0xf7, 0x2b, 0xf7,
+            0xe6, 0xb6, 0x1e, 0xc3, 0xa0, 0xe2, 0x11, 0xbe, 0xec,
+        ]);
+        assert_eq!(encrypted, expected);
+    }
+}
@@ -0,0 +1,222 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Sponsorship of the gas of a transaction by a third party.
+//!
+//! The sender of a sponsored Moonlight transaction signs its payload along
+//! with the key of the sponsor, so that the transaction isn't valid without
+//! the sponsorship. The sponsor then signs the hash of the transaction,
+//! authorizing its gas to be paid from the sponsor's account instead of the
+//! sender's. The sponsor is charged the gas spent by the transaction, while
+//! the sender is only charged the value transferred and deposited.
+//!
+//! The hash of a sponsored transaction covers its sponsorship, telling it
+//! apart from the transaction alone.
+
+#[cfg(feature = "serde")]
+use serde_with::{serde_as, DisplayFromStr};
+
+use alloc::vec::Vec;
+
+use bytecheck::CheckBytes;
+use dusk_bytes::{DeserializableSlice, Error as BytesError, Serializable};
+use rkyv::{Archive, Deserialize, Serialize};
+
+use crate::signatures::bls::{
+    PublicKey as AccountPublicKey, SecretKey as AccountSecretKey,
+    Signature as AccountSignature,
+};
+use crate::transfer::Transaction;
+use crate::{BlsScalar, Error};
+
+/// Domain separating the sponsorship signatures from the other signatures
+/// of an account.
+const SPONSORSHIP_DOMAIN: &[u8] = b"sponsorship";
+
+/// The authorization of a sponsor to pay the gas of a transaction.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct Sponsorship {
+    sponsor: AccountPublicKey,
+    signature: AccountSignature,
+}
+
+impl Sponsorship {
+    /// The size of a serialized sponsorship.
+    pub const SIZE: usize = AccountPublicKey::SIZE + AccountSignature::SIZE;
+
+    /// Creates the sponsorship of `tx` by the account of `sponsor_sk`.
+    #[must_use]
+    pub fn new(sponsor_sk: &AccountSecretKey, tx: &Transaction) -> Self {
+        let msg = Self::signature_message(tx);
+
+        Self {
+            sponsor: AccountPublicKey::from(sponsor_sk),
+            signature: sponsor_sk.sign(&msg),
+        }
+    }
+
+    /// The account paying the gas of the transaction.
+    #[must_use]
+    pub fn sponsor(&self) -> &AccountPublicKey {
+        &self.sponsor
+    }
+
+    /// Signature of the sponsor over the transaction.
+    #[must_use]
+    pub fn signature(&self) -> &AccountSignature {
+        &self.signature
+    }
+
+    /// Return the message that is used as the input to the signature of the
+    /// sponsorship of `tx`.
+    #[must_use]
+    pub fn signature_message(tx: &Transaction) -> Vec<u8> {
+        let mut bytes = Vec::from(SPONSORSHIP_DOMAIN);
+        bytes.extend(tx.hash().to_bytes());
+        bytes
+    }
+
+    /// Create the hash of `tx` sponsored with this sponsorship.
+    #[must_use]
+    pub fn sponsored_hash(&self, tx: &Transaction) -> BlsScalar {
+        let mut bytes = tx.to_hash_input_bytes();
+        bytes.extend(self.to_bytes());
+        BlsScalar::hash_to_scalar(&bytes)
+    }
+
+    /// Serialize the sponsorship into a byte buffer.
+    #[must_use]
+    pub fn to_bytes(&self) -> [u8; Self::SIZE] {
+        let mut bytes = [0u8; Self::SIZE];
+        bytes[..AccountPublicKey::SIZE]
+            .copy_from_slice(&self.sponsor.to_bytes());
+        bytes[AccountPublicKey::SIZE..]
+            .copy_from_slice(&self.signature.to_bytes());
+        bytes
+    }
+
+    /// Deserialize the sponsorship from a byte slice.
+    ///
+    /// # Errors
+    /// Errors when the bytes are not canonical.
+    pub fn from_slice(buf: &[u8]) -> Result<Self, BytesError> {
+        let mut buf = buf;
+
+        let sponsor = AccountPublicKey::from_reader(&mut buf)?;
+        let signature = AccountSignature::from_reader(&mut buf)?;
+        if !buf.is_empty() {
+            return Err(BytesError::InvalidData);
+        }
+
+        Ok(Self { sponsor, signature })
+    }
+}
+
+/// A Moonlight transaction whose gas is paid by a sponsor.
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+pub struct SponsoredTransaction {
+    tx: Transaction,
+    sponsorship: Sponsorship,
+}
+
+impl SponsoredTransaction {
+    /// Attaches the `sponsorship` to `tx`.
+    ///
+    /// # Errors
+    /// Only Moonlight transactions can be sponsored, the gas of a Phoenix
+    /// transaction being paid from its input notes.
+    pub fn new(
+        tx: Transaction,
+        sponsorship: Sponsorship,
+    ) -> Result<Self, Error> {
+        if !matches!(tx, Transaction::Moonlight(_)) {
+            return Err(Error::PhoenixSponsorship);
+        }
+
+        Ok(Self { tx, sponsorship })
+    }
+
+    /// Return the sponsored transaction.
+    #[must_use]
+    pub fn tx(&self) -> &Transaction {
+        &self.tx
+    }
+
+    /// Return the sponsorship of the transaction.
+    #[must_use]
+    pub fn sponsorship(&self) -> &Sponsorship {
+        &self.sponsorship
+    }
+
+    /// Consume the sponsored transaction, returning the transaction and its
+    /// sponsorship.
+    #[must_use]
+    pub fn into_parts(self) -> (Transaction, Sponsorship) {
+        (self.tx, self.sponsorship)
+    }
+
+    /// Create the unique hash of the sponsored transaction, covering its
+    /// sponsorship.
+    #[must_use]
+    pub fn hash(&self) -> BlsScalar {
+        self.sponsorship.sponsored_hash(&self.tx)
+    }
+
+    /// Returns the maximum fee the sponsor pays, i.e. the gas limit of the
+    /// transaction at its gas price.
+    #[must_use]
+    pub fn max_fee(&self) -> u64 {
+        self.tx.gas_limit().saturating_mul(self.tx.gas_price())
+    }
+
+    /// Serialize the sponsored transaction into a byte buffer.
+    #[must_use]
+    pub fn to_var_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::from(self.sponsorship.to_bytes());
+        bytes.extend(self.tx.to_var_bytes());
+        bytes
+    }
+
+    /// Deserialize the sponsored transaction from a byte slice.
+    ///
+    /// # Errors
+    /// Errors when the bytes are not canonical, or when the transaction is
+    /// not a Moonlight one.
+    pub fn from_slice(buf: &[u8]) -> Result<Self, BytesError> {
+        if buf.len() < Sponsorship::SIZE {
+            return Err(BytesError::BadLength {
+                found: buf.len(),
+                expected: Sponsorship::SIZE,
+            });
+        }
+        let (sponsorship, tx) = buf.split_at(Sponsorship::SIZE);
+
+        let sponsorship = Sponsorship::from_slice(sponsorship)?;
+        let tx = Transaction::from_slice(tx)?;
+
+        Self::new(tx, sponsorship).map_err(|_| BytesError::InvalidData)
+    }
+}
+
+/// Event data emitted on the completion of a sponsored transaction.
+#[derive(Debug, Clone, Archive, PartialEq, Serialize, Deserialize)]
+#[archive_attr(derive(CheckBytes))]
+#[cfg_attr(feature = "serde", cfg_eval, serde_as)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SponsorEvent {
+    /// The account that paid the gas of the transaction.
+    pub sponsor: AccountPublicKey,
+    /// The account that initiated the transaction.
+    pub sender: AccountPublicKey,
+    /// Fee charged to the sponsor for the gas spent.
+    #[cfg_attr(feature = "serde", serde_as(as = "DisplayFromStr"))]
+    pub fee: u64,
+    /// Value refunded to the sponsor for the gas left.
+    #[cfg_attr(feature = "serde", serde_as(as = "DisplayFromStr"))]
+    pub refund: u64,
+}
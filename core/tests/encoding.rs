@@ -0,0 +1,90 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use dusk_bytes::Serializable;
+use dusk_core::encoding::Base58Check;
+use dusk_core::signatures::bls::{
+    PublicKey as AccountPublicKey, SecretKey as AccountSecretKey,
+};
+use dusk_core::transfer::phoenix::{
+    PublicKey as PhoenixPublicKey, SecretKey as PhoenixSecretKey,
+    StealthAddress,
+};
+use dusk_core::{Error, JubJubScalar};
+use ff::Field;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+#[test]
+fn base58_check_roundtrip() {
+    let mut rng = StdRng::seed_from_u64(0xc0de);
+
+    let phoenix_pk =
+        PhoenixPublicKey::from(&PhoenixSecretKey::random(&mut rng));
+    let encoded = phoenix_pk.to_base58_check();
+    assert_eq!(
+        PhoenixPublicKey::from_base58_check(&encoded),
+        Ok(phoenix_pk)
+    );
+
+    let address =
+        phoenix_pk.gen_stealth_address(&JubJubScalar::random(&mut rng));
+    let encoded = address.to_base58_check();
+    assert_eq!(StealthAddress::from_base58_check(&encoded), Ok(address));
+
+    let account_pk =
+        AccountPublicKey::from(&AccountSecretKey::random(&mut rng));
+    let encoded = account_pk.to_base58_check();
+    assert_eq!(
+        AccountPublicKey::from_base58_check(&encoded),
+        Ok(account_pk)
+    );
+}
+
+#[test]
+fn base58_check_errors() {
+    let mut rng = StdRng::seed_from_u64(0xc0de);
+    let phoenix_pk =
+        PhoenixPublicKey::from(&PhoenixSecretKey::random(&mut rng));
+    let encoded = phoenix_pk.to_base58_check();
+
+    // a public key is not a stealth address
+    assert_eq!(
+        StealthAddress::from_base58_check(&encoded),
+        Err(Error::InvalidVersion(
+            PhoenixPublicKey::VERSION,
+            StealthAddress::VERSION
+        ))
+    );
+
+    // altering a character breaks the checksum
+    let mut altered = encoded.clone().into_bytes();
+    let last = altered.len() - 1;
+    altered[last] = if altered[last] == b'1' { b'2' } else { b'1' };
+    let altered = String::from_utf8(altered).unwrap();
+    assert_eq!(
+        PhoenixPublicKey::from_base58_check(&altered),
+        Err(Error::InvalidChecksum)
+    );
+
+    assert!(matches!(
+        PhoenixPublicKey::from_base58_check("0OIl"),
+        Err(Error::InvalidBase58(_))
+    ));
+
+    // a valid encoding of the wrong length
+    let short = bs58_check(&[0u8; 10], PhoenixPublicKey::VERSION);
+    assert_eq!(
+        PhoenixPublicKey::from_base58_check(&short),
+        Err(Error::BadLength(10, PhoenixPublicKey::SIZE))
+    );
+}
+
+fn bs58_check(bytes: &[u8], version: u8) -> String {
+    bs58::encode(bytes)
+        .with_check_version(version)
+        .into_string()
+}
@@ -0,0 +1,89 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use dusk_core::abi::{ContractError, ExecutionError, PanicReason};
+
+#[test]
+fn classify_contract_errors() {
+    let err = ExecutionError::from(ContractError::Panic(
+        "Invalid transaction proof!".into(),
+    ));
+    assert_eq!(
+        err,
+        ExecutionError::InvalidProof("Invalid transaction proof!".into())
+    );
+    assert_eq!(err.code(), 4);
+
+    let err =
+        ExecutionError::from(ContractError::Panic("Already used nonce".into()));
+    assert_eq!(err.code(), 5);
+
+    let err =
+        ExecutionError::from(ContractError::Panic("Keys mismatch".into()));
+    assert_eq!(err, ExecutionError::Panic("Keys mismatch".into()));
+    assert_eq!(err.code(), 3);
+
+    assert_eq!(ExecutionError::from(ContractError::OutOfGas).code(), 1);
+    assert_eq!(ExecutionError::from(ContractError::Unknown).code(), 0);
+}
+
+#[test]
+fn display_roundtrip() {
+    for err in [
+        ContractError::Panic("Account has no funds".into()),
+        ContractError::Panic("Keys mismatch".into()),
+        ContractError::OutOfGas,
+        ContractError::DoesNotExist,
+        ContractError::Unknown,
+    ] {
+        let msg = err.to_string();
+        let err = ExecutionError::from(err);
+        assert_eq!(err.to_string(), msg);
+        assert_eq!(msg.parse::<ExecutionError>(), Ok(err));
+    }
+
+    assert_eq!(
+        "error".parse::<ExecutionError>(),
+        Ok(ExecutionError::Unknown("error".into()))
+    );
+}
+
+#[test]
+fn classify_panic_reasons() {
+    let msg = PanicReason::NoFunds.to_string();
+    assert_eq!(PanicReason::parse(&msg), Some(PanicReason::NoFunds));
+    assert_eq!(
+        ExecutionError::from_panic(msg.clone()),
+        ExecutionError::InsufficientBalance(msg)
+    );
+
+    let msg = format!("{}: wasm error", PanicReason::DeploymentFailed);
+    assert_eq!(
+        PanicReason::parse(&msg),
+        Some(PanicReason::DeploymentFailed)
+    );
+    assert_eq!(
+        ExecutionError::from_panic(msg.clone()),
+        ExecutionError::DeploymentFailed(msg)
+    );
+
+    // The messages of the deployed contracts are raised with their reason
+    let msg = "Invalid signature!";
+    assert_eq!(PanicReason::parse(msg), Some(PanicReason::InvalidSignature));
+    assert_eq!(
+        ExecutionError::from_panic(msg.into()),
+        ExecutionError::InvalidSignature(msg.into())
+    );
+
+    // Messages extending the one of a reason are not raised with it
+    for msg in ["Invalid signature to aggregate", "Account has no funds!"] {
+        assert_eq!(PanicReason::parse(msg), None);
+        assert_eq!(
+            ExecutionError::from_panic(msg.into()),
+            ExecutionError::Panic(msg.into())
+        );
+    }
+}
@@ -0,0 +1,99 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use dusk_core::transfer::phoenix::metadata::{
+    decrypt, encrypt, MAX_METADATA_SIZE, OVERHEAD, VERSION,
+};
+use dusk_core::transfer::phoenix::{PublicKey, SecretKey, ViewKey};
+use dusk_core::Error;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+const METADATA: &[u8] = b"invoice #42";
+
+#[test]
+fn roundtrip() -> Result<(), Error> {
+    let mut rng = StdRng::seed_from_u64(0xbeef);
+    let sk = SecretKey::random(&mut rng);
+
+    let encrypted = encrypt(&mut rng, &PublicKey::from(&sk), METADATA)?;
+    assert_eq!(encrypted.len(), METADATA.len() + OVERHEAD);
+    assert_eq!(encrypted[0], VERSION);
+
+    assert_eq!(decrypt(&ViewKey::from(&sk), &encrypted)?, METADATA);
+
+    let empty = encrypt(&mut rng, &PublicKey::from(&sk), &[])?;
+    assert!(decrypt(&ViewKey::from(&sk), &empty)?.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn wrong_key() -> Result<(), Error> {
+    let mut rng = StdRng::seed_from_u64(0xbeef);
+    let sk = SecretKey::random(&mut rng);
+    let other_sk = SecretKey::random(&mut rng);
+
+    let encrypted = encrypt(&mut rng, &PublicKey::from(&sk), METADATA)?;
+    assert_eq!(
+        decrypt(&ViewKey::from(&other_sk), &encrypted),
+        Err(Error::MetadataDecryption)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn tampered() -> Result<(), Error> {
+    let mut rng = StdRng::seed_from_u64(0xbeef);
+    let sk = SecretKey::random(&mut rng);
+    let vk = ViewKey::from(&sk);
+
+    let encrypted = encrypt(&mut rng, &PublicKey::from(&sk), METADATA)?;
+
+    // The nonce, the ciphertext and the tag are authenticated
+    for i in 1 + 32..encrypted.len() {
+        let mut tampered = encrypted.clone();
+        tampered[i] ^= 1;
+        assert_eq!(decrypt(&vk, &tampered), Err(Error::MetadataDecryption));
+    }
+
+    assert_eq!(
+        decrypt(&vk, &encrypted[..OVERHEAD - 1]),
+        Err(Error::BadLength(OVERHEAD - 1, OVERHEAD))
+    );
+    assert_eq!(decrypt(&vk, &[]), Err(Error::BadLength(0, OVERHEAD)));
+
+    Ok(())
+}
+
+#[test]
+fn unsupported_version() -> Result<(), Error> {
+    let mut rng = StdRng::seed_from_u64(0xbeef);
+    let sk = SecretKey::random(&mut rng);
+
+    let mut encrypted = encrypt(&mut rng, &PublicKey::from(&sk), METADATA)?;
+    encrypted[0] = VERSION + 1;
+    assert_eq!(
+        decrypt(&ViewKey::from(&sk), &encrypted),
+        Err(Error::MetadataVersion(VERSION + 1))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn too_large() {
+    let mut rng = StdRng::seed_from_u64(0xbeef);
+    let sk = SecretKey::random(&mut rng);
+    let pk = PublicKey::from(&sk);
+
+    assert!(encrypt(&mut rng, &pk, &[0; MAX_METADATA_SIZE]).is_ok());
+    assert_eq!(
+        encrypt(&mut rng, &pk, &[0; MAX_METADATA_SIZE + 1]),
+        Err(Error::MemoTooLarge(MAX_METADATA_SIZE + 1 + OVERHEAD))
+    );
+}
@@ -4,17 +4,31 @@
 //
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
+use dusk_bytes::Serializable;
 use dusk_core::signatures::bls::{
     PublicKey as AccountPublicKey, SecretKey as AccountSecretKey,
 };
+use dusk_core::stake::{
+    next_epoch, parse_stake_call, KeyRotation, ProofOfPossession, Stake,
+    StakeCall, VersionedStake, VersionedWithdraw, Withdraw as StakeWithdraw,
+    STAKE_CONTRACT,
+};
+use dusk_core::transfer::bundle::{TransactionBundle, MAX_BUNDLE_TXS};
 use dusk_core::transfer::data::{
     ContractBytecode, ContractCall, ContractDeploy, TransactionData,
 };
+use dusk_core::transfer::moonlight::{
+    Fee, Payload, Transaction as MoonlightTransaction,
+};
 use dusk_core::transfer::phoenix::{
-    Note, NoteTreeItem, NotesTree, Prove, PublicKey as PhoenixPublicKey,
-    SecretKey as PhoenixSecretKey, TxCircuitVec,
+    BatchedNoteLeaf, Note, NoteLeafBatch, NoteTreeItem, NotesTree, Prove,
+    PublicKey as PhoenixPublicKey, SecretKey as PhoenixSecretKey, TxCircuitVec,
+};
+use dusk_core::transfer::sponsor::{SponsoredTransaction, Sponsorship};
+use dusk_core::transfer::withdraw::{
+    Withdraw as TransferWithdraw, WithdrawReceiver, WithdrawReplayToken,
 };
-use dusk_core::transfer::Transaction;
+use dusk_core::transfer::{Transaction, TRANSFER_CONTRACT};
 use dusk_core::{BlsScalar, Error, JubJubScalar};
 use ff::Field;
 use rand::rngs::StdRng;
@@ -378,3 +392,274 @@ fn nonsense_bytes_fails() -> Result<(), Error> {
     }
     Ok(())
 }
+
+#[test]
+fn note_leaf_batch() {
+    let mut rng = StdRng::seed_from_u64(42);
+
+    let pk = PhoenixPublicKey::from(&PhoenixSecretKey::random(&mut rng));
+    let sender_blinder = [
+        JubJubScalar::random(&mut rng),
+        JubJubScalar::random(&mut rng),
+    ];
+    let mut note = Note::transparent(&mut rng, &pk, &pk, 42, sender_blinder);
+    note.set_pos(7);
+
+    for with_note in [false, true] {
+        let batch = NoteLeafBatch {
+            block_height: 1,
+            block_hash: [1; 32],
+            leaves: vec![BatchedNoteLeaf::new(note.clone(), with_note)],
+        };
+        assert_eq!(batch.leaves[0].pos, 7);
+        assert_eq!(batch.leaves[0].note.is_some(), with_note);
+
+        let bytes = rkyv::to_bytes::<_, 4096>(&batch)
+            .expect("serialization should be ok");
+        let deserialized: NoteLeafBatch =
+            rkyv::from_bytes(&bytes).expect("deserialization should be ok");
+        assert_eq!(batch, deserialized);
+    }
+}
+
+#[test]
+fn stake_calldata() -> Result<(), Error> {
+    let mut rng = StdRng::seed_from_u64(42);
+
+    let sk = AccountSecretKey::random(&mut rng);
+    let pk = AccountPublicKey::from(&sk);
+
+    let stake = Stake::new(&sk, &sk, 1_000, CHAIN_ID);
+    let withdraw = StakeWithdraw::new(
+        &sk,
+        &sk,
+        TransferWithdraw::new(
+            &mut rng,
+            &sk,
+            STAKE_CONTRACT,
+            1_000,
+            WithdrawReceiver::Moonlight(pk),
+            WithdrawReplayToken::Moonlight(1),
+        ),
+    );
+
+    // Both the legacy and the versioned arguments are decoded
+    for args in [
+        ContractCall::new(STAKE_CONTRACT, "stake", &stake)?,
+        ContractCall::new(
+            STAKE_CONTRACT,
+            "stake",
+            &VersionedStake::from(stake.clone()),
+        )?,
+    ] {
+        assert_eq!(
+            parse_stake_call(&args)?,
+            Some(StakeCall::Stake(VersionedStake::V1(stake.clone())))
+        );
+    }
+    for args in [
+        ContractCall::new(STAKE_CONTRACT, "unstake", &withdraw)?,
+        ContractCall::new(
+            STAKE_CONTRACT,
+            "unstake",
+            &VersionedWithdraw::from(withdraw.clone()),
+        )?,
+    ] {
+        assert_eq!(
+            parse_stake_call(&args)?,
+            Some(StakeCall::Unstake(VersionedWithdraw::V1(withdraw.clone())))
+        );
+    }
+
+    // Calls to other functions and contracts are ignored
+    let call = ContractCall::new(STAKE_CONTRACT, "get_stake", &pk)?;
+    assert_eq!(parse_stake_call(&call)?, None);
+    let call = ContractCall::new(TRANSFER_CONTRACT, "stake", &stake)?;
+    assert_eq!(parse_stake_call(&call)?, None);
+
+    // Invalid arguments are rejected
+    let call = ContractCall {
+        contract: STAKE_CONTRACT,
+        fn_name: "stake".into(),
+        fn_args: vec![0; 8],
+    };
+    assert!(parse_stake_call(&call).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn stake_proof_of_possession() -> Result<(), Error> {
+    let mut rng = StdRng::seed_from_u64(42);
+
+    let sk = AccountSecretKey::random(&mut rng);
+    let pk = AccountPublicKey::from(&sk);
+    let other_pk = AccountPublicKey::from(&AccountSecretKey::random(&mut rng));
+
+    let pop = ProofOfPossession::new(&sk);
+    assert!(pop.verify(&pk));
+    assert!(!pop.verify(&other_pk));
+
+    // A key signature is not a proof of possession
+    let signature = sk.sign(&pk.to_bytes());
+    assert_ne!(pop.signature(), &signature);
+
+    let stake = Stake::new(&sk, &sk, 1_000, CHAIN_ID);
+    let versioned = VersionedStake::with_pop(stake.clone(), &sk);
+    assert_eq!(versioned.stake(), &stake);
+    assert_eq!(versioned.pop(), Some(&pop));
+
+    let call = ContractCall::new(STAKE_CONTRACT, "stake", &versioned)?;
+    assert_eq!(parse_stake_call(&call)?, Some(StakeCall::Stake(versioned)));
+
+    let call = ContractCall::new(STAKE_CONTRACT, "stake", &stake)?;
+    let Some(StakeCall::Stake(legacy)) = parse_stake_call(&call)? else {
+        panic!("stake call should be parsed");
+    };
+    assert_eq!(legacy.pop(), None);
+
+    Ok(())
+}
+
+#[test]
+fn stake_key_rotation() -> Result<(), Error> {
+    let mut rng = StdRng::seed_from_u64(42);
+
+    let sk = AccountSecretKey::random(&mut rng);
+    let pk = AccountPublicKey::from(&sk);
+    let next_sk = AccountSecretKey::random(&mut rng);
+    let next_pk = AccountPublicKey::from(&next_sk);
+
+    let rotation = KeyRotation::new(&sk, &next_sk, next_epoch(10), CHAIN_ID);
+    assert_eq!(rotation.account(), &pk);
+    assert_eq!(rotation.next_account(), &next_pk);
+    assert!(rotation.pop().verify(&next_pk));
+
+    // The rotation is signed by the current key only
+    let msg = rotation.signature_message();
+    assert!(pk.verify(rotation.signature(), &msg).is_ok());
+    assert!(next_pk.verify(rotation.signature(), &msg).is_err());
+
+    let call = ContractCall::new(STAKE_CONTRACT, "rotate_key", &rotation)?;
+    assert_eq!(
+        parse_stake_call(&call)?,
+        Some(StakeCall::RotateKey(rotation))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn transaction_bundle() -> Result<(), Error> {
+    let mut rng = StdRng::seed_from_u64(42);
+
+    let txs = vec![
+        new_moonlight_tx(&mut rng, None),
+        new_phoenix_tx(&mut rng, None),
+    ];
+    let bundle = TransactionBundle::new(txs.clone())?;
+    assert_eq!(bundle.header().tx_count, 2);
+    assert_eq!(
+        bundle.gas_limit(),
+        txs[0].gas_limit().saturating_add(txs[1].gas_limit())
+    );
+
+    let bundle_bytes = bundle.to_var_bytes();
+    let deserialized = TransactionBundle::from_slice(&bundle_bytes)?;
+    assert_eq!(bundle, deserialized);
+    assert_eq!(deserialized.into_txs(), txs);
+
+    let bytes =
+        rkyv::to_bytes::<_, 4096>(&bundle).expect("serialization should be ok");
+    let deserialized: TransactionBundle =
+        rkyv::from_bytes(&bytes).expect("deserialization should be ok");
+    assert_eq!(bundle, deserialized);
+
+    // A header not matching the transactions is refused
+    let mut bundle_bytes = bundle_bytes;
+    bundle_bytes[4] ^= 1;
+    TransactionBundle::from_slice(&bundle_bytes)
+        .expect_err("deserialization should fail");
+
+    assert_eq!(TransactionBundle::new(vec![]), Err(Error::BundleSize(0)));
+    let too_many = vec![txs[0].clone(); MAX_BUNDLE_TXS + 1];
+    assert_eq!(
+        TransactionBundle::new(too_many),
+        Err(Error::BundleSize(MAX_BUNDLE_TXS + 1))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn sponsored_transaction() -> Result<(), Error> {
+    let mut rng = StdRng::seed_from_u64(42);
+
+    let sponsor_sk = AccountSecretKey::random(&mut rng);
+    let sponsor_pk = AccountPublicKey::from(&sponsor_sk);
+
+    let sender_sk = AccountSecretKey::random(&mut rng);
+    let sender_pk = AccountPublicKey::from(&sender_sk);
+    let payload = Payload {
+        chain_id: CHAIN_ID,
+        sender: sender_pk,
+        receiver: AccountPublicKey::from(&AccountSecretKey::random(&mut rng)),
+        value: rng.gen(),
+        deposit: rng.gen(),
+        fee: Fee {
+            gas_limit: rng.gen(),
+            gas_price: rng.gen(),
+            refund_address: sender_pk,
+        },
+        nonce: rng.gen(),
+        data: None,
+    };
+    let moonlight_tx = MoonlightTransaction::sign_sponsored_payload(
+        &sender_sk,
+        payload,
+        &sponsor_pk,
+    )?;
+
+    // The signature of the sender commits to the sponsor
+    assert!(sender_pk
+        .verify(
+            moonlight_tx.signature(),
+            &moonlight_tx.sponsored_signature_message(&sponsor_pk)
+        )
+        .is_ok());
+    assert!(sender_pk
+        .verify(moonlight_tx.signature(), &moonlight_tx.signature_message())
+        .is_err());
+
+    let tx = Transaction::from(moonlight_tx);
+    let sponsorship = Sponsorship::new(&sponsor_sk, &tx);
+    assert_eq!(sponsorship.sponsor(), &AccountPublicKey::from(&sponsor_sk));
+    assert!(sponsorship
+        .sponsor()
+        .verify(
+            sponsorship.signature(),
+            &Sponsorship::signature_message(&tx)
+        )
+        .is_ok());
+
+    let sponsored = SponsoredTransaction::new(tx.clone(), sponsorship)?;
+    assert_ne!(sponsored.hash(), tx.hash());
+    assert_eq!(
+        sponsored.max_fee(),
+        tx.gas_limit().saturating_mul(tx.gas_price())
+    );
+
+    let sponsored_bytes = sponsored.to_var_bytes();
+    let deserialized = SponsoredTransaction::from_slice(&sponsored_bytes)?;
+    assert_eq!(sponsored, deserialized);
+
+    // The gas of a Phoenix transaction can't be sponsored
+    let phoenix_tx = new_phoenix_tx(&mut rng, None);
+    let sponsorship = Sponsorship::new(&sponsor_sk, &phoenix_tx);
+    assert_eq!(
+        SponsoredTransaction::new(phoenix_tx, sponsorship),
+        Err(Error::PhoenixSponsorship)
+    );
+
+    Ok(())
+}
@@ -8,6 +8,7 @@ use std::cmp::Ordering;
 use std::fmt::Debug;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
 use aes::Aes256;
 use block_modes::block_padding::Pkcs7;
@@ -16,6 +17,7 @@ use dusk_bytes::{DeserializableSlice, Serializable};
 use dusk_core::signatures::bls::{
     PublicKey as BlsPublicKey, SecretKey as BlsSecretKey,
 };
+use dusk_core::stake::ProofOfPossession;
 use rand::rngs::{OsRng, StdRng};
 use rand::RngCore;
 use rand::SeedableRng;
@@ -24,6 +26,8 @@ use serde_with::base64::Base64;
 use serde_with::serde_as;
 use sha2::{Digest, Sha256};
 
+use crate::id::{decode_bs58, serde_from_str, ParseIdError};
+
 pub const PUBLIC_BLS_SIZE: usize = BlsPublicKey::SIZE;
 
 /// Extends BlsPublicKey by implementing a few traits
@@ -100,6 +104,24 @@ impl Ord for PublicKey {
     }
 }
 
+impl std::fmt::Display for PublicKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.to_base58())
+    }
+}
+
+impl FromStr for PublicKey {
+    type Err = ParseIdError;
+
+    /// Parses the full base58 representation of a public key
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes = decode_bs58::<PUBLIC_BLS_SIZE>(s)?;
+        Self::try_from(bytes).map_err(ParseIdError::InvalidKey)
+    }
+}
+
+serde_from_str!(PublicKey);
+
 impl std::fmt::Debug for PublicKey {
     fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
         let bs = self.to_base58();
@@ -136,6 +158,22 @@ impl PublicKeyBytes {
     }
 }
 
+impl std::fmt::Display for PublicKeyBytes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.to_base58())
+    }
+}
+
+impl FromStr for PublicKeyBytes {
+    type Err = ParseIdError;
+
+    /// Parses the full base58 representation of a public key, without
+    /// checking it is a valid point
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        decode_bs58(s).map(Self)
+    }
+}
+
 impl Debug for PublicKeyBytes {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.write_str(&self.to_bs58())
@@ -150,6 +188,11 @@ pub fn load_keys(
     let path_buf = PathBuf::from(path);
     let (pk, sk) = read_from_file(path_buf, &pwd)?;
 
+    // Refuse key pairs whose secret key does not own the public key
+    if !ProofOfPossession::new(&sk).verify(&pk) {
+        anyhow::bail!("consensus keys should prove the possession of pk");
+    }
+
     Ok((sk, PublicKey::new(pk)))
 }
 
@@ -301,6 +344,36 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_load_mismatched_consensus_keys(
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempdir()?;
+
+        let mut rng = StdRng::seed_from_u64(64);
+        let sk = BlsSecretKey::random(&mut rng);
+        let other_pk = BlsPublicKey::from(&BlsSecretKey::random(&mut rng));
+        let pwd = "password";
+
+        save_consensus_keys(
+            dir.path(),
+            "consensus",
+            &other_pk,
+            &sk,
+            &hashed_password(pwd),
+        )?;
+        let keys_path = dir.path().join("consensus.keys");
+        let loaded = load_keys(
+            keys_path
+                .to_str()
+                .ok_or(anyhow!("Failed to convert path to string"))?
+                .to_string(),
+            pwd.to_string(),
+        );
+        assert!(loaded.is_err(), "keys not owning the pk should be refused");
+
+        Ok(())
+    }
+
     #[test]
     fn test_can_still_load_keys_saved_by_wallet_impl(
     ) -> Result<(), Box<dyn std::error::Error>> {
@@ -6,12 +6,15 @@
 
 use std::io::{self, Read, Write};
 
+use dusk_core::transfer::bundle::TransactionBundle as ProtocolBundle;
+use dusk_core::transfer::sponsor::Sponsorship;
 use dusk_core::transfer::Transaction as ProtocolTransaction;
 
 use crate::bls::PublicKeyBytes;
 use crate::ledger::{
-    Attestation, Block, Fault, Header, IterationsInfo, Label, Signature,
-    SpentTransaction, StepVotes, Transaction,
+    Attestation, Block, BundlePosition, Fault, Header, IterationsInfo, Label,
+    Signature, SpentTransaction, StepVotes, Transaction, TransactionBundle,
+    BUNDLED_TX_VERSION, SPONSORED_TX_TYPE,
 };
 use crate::message::payload::{
     QuorumType, Ratification, RatificationResult, ValidationQuorum,
@@ -22,6 +25,9 @@ use crate::message::{
 };
 use crate::Serializable;
 
+/// Version of the gas accounting trailer appended to a [`SpentTransaction`]
+const SPENT_TX_ACCOUNTING_VERSION: u8 = 1;
+
 impl Serializable for Block {
     fn write<W: Write>(&self, w: &mut W) -> io::Result<()> {
         self.header().write(w)?;
@@ -79,6 +85,19 @@ impl Serializable for Transaction {
         // Write inner transaction
         Self::write_var_le_bytes32(w, &data)?;
 
+        // Write the sponsorship of sponsored transactions
+        if let Some(sponsorship) = &self.sponsorship {
+            w.write_all(&sponsorship.to_bytes())?;
+        }
+
+        // Write the bundle position of bundled transactions
+        if self.version == BUNDLED_TX_VERSION {
+            let bundle = self.bundle().ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidData, "Missing bundle")
+            })?;
+            w.write_all(&bundle.to_bytes())?;
+        }
+
         Ok(())
     }
 
@@ -94,15 +113,76 @@ impl Serializable for Transaction {
         let inner = ProtocolTransaction::from_slice(&protocol_tx[..])
             .map_err(|_| io::Error::from(io::ErrorKind::InvalidData))?;
 
+        // Only moonlight transactions can be sponsored
+        let sponsorship = match (tx_type, &inner) {
+            (SPONSORED_TX_TYPE, ProtocolTransaction::Moonlight(_)) => {
+                let mut buf = [0u8; Sponsorship::SIZE];
+                r.read_exact(&mut buf)?;
+                let sponsorship = Sponsorship::from_slice(&buf)
+                    .map_err(|_| io::Error::from(io::ErrorKind::InvalidData))?;
+                Some(sponsorship)
+            }
+            (SPONSORED_TX_TYPE, _) => {
+                return Err(io::Error::from(io::ErrorKind::InvalidData))
+            }
+            _ => None,
+        };
+        let tx_size = match sponsorship {
+            Some(_) => tx_size + Sponsorship::SIZE,
+            None => tx_size,
+        };
+
+        let bundle = match version {
+            BUNDLED_TX_VERSION => {
+                let mut buf = [0u8; BundlePosition::SIZE];
+                r.read_exact(&mut buf)?;
+                Some(BundlePosition::from_bytes(&buf))
+            }
+            _ => None,
+        };
+        let tx_size = match bundle {
+            Some(_) => tx_size + BundlePosition::SIZE,
+            None => tx_size,
+        };
+
         Ok(Self {
             inner,
             version,
             r#type: tx_type,
+            sponsorship,
             size: Some(tx_size),
+            bundle,
         })
     }
 }
 
+impl Serializable for TransactionBundle {
+    fn write<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        // Write version
+        w.write_all(&self.version.to_le_bytes())?;
+
+        let data = self.inner.to_var_bytes();
+
+        // Write inner bundle
+        Self::write_var_le_bytes32(w, &data)?;
+
+        Ok(())
+    }
+
+    fn read<R: Read>(r: &mut R) -> io::Result<Self>
+    where
+        Self: Sized,
+    {
+        let version = Self::read_u32_le(r)?;
+
+        let protocol_bundle = Self::read_var_le_bytes32(r)?;
+        let inner = ProtocolBundle::from_slice(&protocol_bundle[..])
+            .map_err(|_| io::Error::from(io::ErrorKind::InvalidData))?;
+
+        Ok(Self { version, inner })
+    }
+}
+
 impl Serializable for SpentTransaction {
     fn write<W: Write>(&self, w: &mut W) -> io::Result<()> {
         self.inner.write(w)?;
@@ -111,15 +191,20 @@ impl Serializable for SpentTransaction {
 
         match &self.err {
             Some(e) => {
+                let e = e.to_string();
                 let b = e.as_bytes();
                 w.write_all(&(b.len() as u32).to_le_bytes())?;
                 w.write_all(b)?;
             }
             None => {
-                w.write_all(&0_u64.to_le_bytes())?;
+                w.write_all(&0_u32.to_le_bytes())?;
             }
         }
 
+        w.write_all(&[SPENT_TX_ACCOUNTING_VERSION])?;
+        w.write_all(&self.gas_refunded.to_le_bytes())?;
+        w.write_all(&self.fee_charged.to_le_bytes())?;
+
         Ok(())
     }
 
@@ -137,16 +222,34 @@ impl Serializable for SpentTransaction {
             let mut buf = vec![0u8; error_len as usize];
             r.read_exact(&mut buf[..])?;
 
-            Some(String::from_utf8(buf).expect("Cannot from_utf8"))
+            let err = String::from_utf8(buf).expect("Cannot from_utf8");
+            Some(err.parse().expect("Parsing an error is infallible"))
         } else {
             None
         };
 
+        // Records written before gas accounting was introduced either end
+        // here or are followed by zero padding: derive the values from the
+        // transaction itself.
+        let version = match Self::read_u8(r) {
+            Ok(version) => version,
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => 0,
+            Err(e) => return Err(e),
+        };
+        if version < SPENT_TX_ACCOUNTING_VERSION {
+            return Ok(Self::new(inner, block_height, gas_spent, err));
+        }
+
+        let gas_refunded = Self::read_u64_le(r)?;
+        let fee_charged = Self::read_u64_le(r)?;
+
         Ok(Self {
             inner,
             block_height,
             gas_spent,
             err,
+            gas_refunded,
+            fee_charged,
         })
     }
 }
@@ -378,11 +481,21 @@ impl Serializable for Ratification {
     where
         Self: Sized,
     {
+        Self::read_with(r, SignInfo::read)
+    }
+}
+
+impl Ratification {
+    /// Reads a ratification, using `read_sign_info` to read its sign info
+    pub(crate) fn read_with<R: Read>(
+        r: &mut R,
+        read_sign_info: impl FnOnce(&mut R) -> io::Result<SignInfo>,
+    ) -> io::Result<Self> {
         let header = ConsensusHeader::read(r)?;
         let vote = Vote::read(r)?;
         let timestamp = Self::read_u64_le(r)?;
         let validation_result = ValidationResult::read(r)?;
-        let sign_info = SignInfo::read(r)?;
+        let sign_info = read_sign_info(r)?;
 
         Ok(Ratification {
             header,
@@ -450,7 +563,16 @@ impl Serializable for QuorumType {
 
 #[cfg(test)]
 mod tests {
+    use dusk_core::signatures::bls::{
+        PublicKey as AccountPublicKey, SecretKey as AccountSecretKey,
+    };
+    use dusk_core::transfer::moonlight::{
+        Fee, Payload, Transaction as MoonlightTransaction,
+    };
+    use dusk_core::transfer::sponsor::SponsoredTransaction;
     use fake::{Dummy, Fake, Faker};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
 
     use super::*;
     use crate::message::payload::{Candidate, Validation};
@@ -494,16 +616,161 @@ mod tests {
         assert_serializable::<Transaction>();
     }
 
+    #[test]
+    fn test_encoding_sponsored_transaction() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let sender_sk = AccountSecretKey::random(&mut rng);
+        let sponsor_sk = AccountSecretKey::random(&mut rng);
+
+        let sender_pk = AccountPublicKey::from(&sender_sk);
+        let sponsor_pk = AccountPublicKey::from(&sponsor_sk);
+
+        let payload = Payload {
+            chain_id: 0,
+            sender: sender_pk,
+            receiver: sender_pk,
+            value: 10,
+            deposit: 0,
+            fee: Fee {
+                gas_limit: 1_000,
+                gas_price: 1,
+                refund_address: sender_pk,
+            },
+            nonce: 1,
+            data: None,
+        };
+        let tx: ProtocolTransaction =
+            MoonlightTransaction::sign_sponsored_payload(
+                &sender_sk,
+                payload,
+                &sponsor_pk,
+            )
+            .expect("transaction to be valid")
+            .into();
+        let sponsorship = Sponsorship::new(&sponsor_sk, &tx);
+        let unsponsored = Transaction::from(tx.clone());
+        let tx: Transaction = SponsoredTransaction::new(tx, sponsorship)
+            .expect("moonlight transaction to be sponsored")
+            .into();
+
+        let mut buf = vec![];
+        tx.write(&mut buf).expect("should be writable");
+        let read =
+            Transaction::read(&mut &buf[..]).expect("should be readable");
+        assert_eq!(read, tx);
+        assert_eq!(read.sponsorship(), tx.sponsorship());
+
+        // The sponsorship is covered by both the digest and the id
+        assert_ne!(read.id(), unsponsored.id());
+        assert_ne!(read.digest(), unsponsored.digest());
+    }
+
+    #[test]
+    fn test_encoding_transaction_bundle() {
+        assert_serializable::<TransactionBundle>();
+    }
+
     #[test]
     fn test_encoding_spent_transaction() {
         assert_serializable::<SpentTransaction>();
     }
 
+    #[test]
+    fn test_decoding_legacy_spent_transaction() {
+        let tx: SpentTransaction = Faker.fake();
+
+        for err in [None, Some("error".to_string())] {
+            // Legacy records had no accounting trailer and wrote an empty
+            // error as a u64
+            let mut buf = vec![];
+            tx.inner.write(&mut buf).unwrap();
+            buf.extend_from_slice(&tx.block_height.to_le_bytes());
+            buf.extend_from_slice(&tx.gas_spent.to_le_bytes());
+            match &err {
+                Some(e) => {
+                    buf.extend_from_slice(&(e.len() as u32).to_le_bytes());
+                    buf.extend_from_slice(e.as_bytes());
+                }
+                None => buf.extend_from_slice(&0_u64.to_le_bytes()),
+            }
+
+            let decoded = SpentTransaction::read(&mut &buf[..]).unwrap();
+            assert_eq!(decoded.err.map(|e| e.to_string()), err);
+            assert_eq!(decoded.gas_refunded, tx.gas_refunded);
+            assert_eq!(decoded.fee_charged, tx.fee_charged);
+        }
+    }
+
     #[test]
     fn test_encoding_header() {
         assert_serializable::<ConsensusHeader>();
     }
 
+    #[test]
+    fn test_encoding_provisioners_root() {
+        use crate::message::{
+            BLOCK_HEADER_VERSION, PROVISIONERS_ROOT_HEADER_VERSION,
+        };
+
+        let mut header: Header = Faker.fake();
+        header.provisioners_root = [7; 32];
+
+        header.version = PROVISIONERS_ROOT_HEADER_VERSION;
+        let mut buf = vec![];
+        header.write(&mut buf).unwrap();
+        let decoded = Header::read(&mut &buf[..]).unwrap();
+        assert_eq!(decoded.provisioners_root, [7; 32]);
+
+        // Legacy headers do not commit to the provisioner set
+        header.version = BLOCK_HEADER_VERSION;
+        let mut legacy_buf = vec![];
+        header.write(&mut legacy_buf).unwrap();
+        assert_eq!(legacy_buf.len(), buf.len() - 32);
+        let decoded = Header::read(&mut &legacy_buf[..]).unwrap();
+        assert_eq!(decoded.provisioners_root, [0; 32]);
+    }
+
+    #[test]
+    fn test_encoding_extra_data() {
+        use crate::message::{
+            EXTRA_DATA_HEADER_VERSION, MAX_HEADER_EXTRA_DATA_SIZE,
+            PROVISIONERS_ROOT_HEADER_VERSION,
+        };
+
+        let mut header: Header = Faker.fake();
+        header.extra_data = b"rusk/1.2.0".to_vec();
+
+        header.version = EXTRA_DATA_HEADER_VERSION;
+        let mut buf = vec![];
+        header.write(&mut buf).unwrap();
+        let decoded = Header::read(&mut &buf[..]).unwrap();
+        assert_eq!(decoded.extra_data, b"rusk/1.2.0");
+        assert_eq!(decoded.extra_data_lossy(), "rusk/1.2.0");
+
+        // The extra-data is part of the hash
+        let hash = header.compute_hash().unwrap();
+        header.extra_data = b"rusk/1.2.1".to_vec();
+        assert_ne!(header.compute_hash().unwrap(), hash);
+
+        // Previous headers carry no extra-data
+        header.version = PROVISIONERS_ROOT_HEADER_VERSION;
+        let mut legacy_buf = vec![];
+        header.write(&mut legacy_buf).unwrap();
+        assert_eq!(legacy_buf.len(), buf.len() - 1 - header.extra_data.len());
+        let decoded = Header::read(&mut &legacy_buf[..]).unwrap();
+        assert!(decoded.extra_data.is_empty());
+
+        // Oversize extra-data can be neither encoded nor decoded
+        header.version = EXTRA_DATA_HEADER_VERSION;
+        let mut hashable = vec![];
+        header.marshal_hashable(&mut hashable).unwrap();
+        let len_pos = hashable.len() - 1 - header.extra_data.len();
+        buf[len_pos] = MAX_HEADER_EXTRA_DATA_SIZE as u8 + 1;
+        header.extra_data = vec![0; MAX_HEADER_EXTRA_DATA_SIZE + 1];
+        assert!(header.write(&mut vec![]).is_err());
+        assert!(Header::read(&mut &buf[..]).is_err());
+    }
+
     #[test]
     fn test_encoding_block() {
         assert_serializable::<Block>();
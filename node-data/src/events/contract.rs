@@ -8,6 +8,7 @@
 
 use dusk_core::abi::{ContractId, Event};
 use serde::{Deserialize, Serialize};
+use sha3::{Digest, Sha3_256};
 
 pub const ORIGIN_HASH_BYTES: usize = 32;
 /// Origin hash of a contract event. This is in most cases the transaction hash.
@@ -44,6 +45,18 @@ impl From<Event> for ContractEvent {
     }
 }
 
+impl ContractEvent {
+    /// Computes the Sha3 256 digest of the event target, topic and data
+    pub fn digest(&self) -> [u8; 32] {
+        let mut hasher = Sha3_256::new();
+        hasher.update(self.target.as_bytes());
+        hasher.update((self.topic.len() as u32).to_le_bytes());
+        hasher.update(self.topic.as_bytes());
+        hasher.update(&self.data);
+        hasher.finalize().into()
+    }
+}
+
 impl From<ContractEvent> for Event {
     fn from(contract_event: ContractEvent) -> Self {
         Event {
@@ -75,6 +88,18 @@ mod tests {
         assert_eq!(Event::from(contract_event), exec_core_event());
     }
 
+    #[test]
+    fn test_contract_event_digest() {
+        let event: ContractEvent = exec_core_event().into();
+        assert_eq!(event.digest(), event.clone().digest());
+
+        // Moving bytes from the topic to the data changes the digest
+        let mut shifted = event.clone();
+        shifted.topic.pop();
+        shifted.data.insert(0, b't');
+        assert_ne!(event.digest(), shifted.digest());
+    }
+
     #[test]
     fn test_serialize_contract_event() {
         let event: ContractEvent = exec_core_event().into();
@@ -135,6 +135,10 @@ impl Serialize for Transaction {
                 }
             };
             fee.insert("refund_address", encoded_address);
+            if let Some(sponsorship) = self.sponsorship() {
+                let sponsor = sponsorship.sponsor().to_bytes();
+                fee.insert("sponsor", bs58::encode(sponsor).into_string());
+            }
             if let ProtocolTransaction::Phoenix(tx) = tx {
                 fee.insert(
                     "phoenix sender",
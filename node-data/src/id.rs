@@ -0,0 +1,204 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Canonical string formats of identifiers.
+//!
+//! - block hashes and state roots are rendered as plain hex
+//! - transaction ids are rendered as hex, prefixed by [`TxId::PREFIX`]
+//! - BLS public keys are rendered as full base58 (see [`crate::bls`])
+//!
+//! Every identifier implements `Display` and `FromStr` with its canonical
+//! format, and is (de)serialized with serde as such a string.
+
+use std::fmt;
+use std::str::FromStr;
+
+use thiserror::Error;
+
+#[derive(Debug, Error, Clone, PartialEq)]
+pub enum ParseIdError {
+    #[error("invalid hex: {0}")]
+    InvalidHex(#[from] hex::FromHexError),
+    #[error("invalid base58: {0}")]
+    InvalidBase58(#[from] bs58::decode::Error),
+    #[error("invalid length: expected {expected} bytes, found {found}")]
+    InvalidLength { expected: usize, found: usize },
+    #[error("missing prefix {0}")]
+    MissingPrefix(&'static str),
+    #[error("invalid key: {0:?}")]
+    InvalidKey(dusk_bytes::Error),
+}
+
+/// Decodes a hex string of exactly `N` bytes
+pub(crate) fn decode_hex<const N: usize>(
+    s: &str,
+) -> Result<[u8; N], ParseIdError> {
+    let bytes = hex::decode(s)?;
+    to_array(&bytes)
+}
+
+/// Decodes a base58 string of exactly `N` bytes
+pub(crate) fn decode_bs58<const N: usize>(
+    s: &str,
+) -> Result<[u8; N], ParseIdError> {
+    let bytes = bs58::decode(s).into_vec()?;
+    to_array(&bytes)
+}
+
+fn to_array<const N: usize>(bytes: &[u8]) -> Result<[u8; N], ParseIdError> {
+    bytes.try_into().map_err(|_| ParseIdError::InvalidLength {
+        expected: N,
+        found: bytes.len(),
+    })
+}
+
+/// Implements serde for a type with `Display` and `FromStr`
+macro_rules! serde_from_str {
+    ($name:ident) => {
+        impl serde::Serialize for $name {
+            fn serialize<S: serde::Serializer>(
+                &self,
+                serializer: S,
+            ) -> Result<S::Ok, S::Error> {
+                serializer.collect_str(self)
+            }
+        }
+
+        impl<'de> serde::Deserialize<'de> for $name {
+            fn deserialize<D: serde::Deserializer<'de>>(
+                deserializer: D,
+            ) -> Result<Self, D::Error> {
+                let s =
+                    <String as serde::Deserialize>::deserialize(deserializer)?;
+                s.parse().map_err(serde::de::Error::custom)
+            }
+        }
+    };
+}
+pub(crate) use serde_from_str;
+
+/// Defines a 32 bytes identifier rendered as hex, optionally prefixed
+macro_rules! hex_id {
+    ($(#[$meta:meta])* $name:ident, $prefix:expr) => {
+        $(#[$meta])*
+        #[derive(Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+        pub struct $name(pub [u8; 32]);
+
+        impl $name {
+            pub const PREFIX: &'static str = $prefix;
+
+            pub fn inner(&self) -> &[u8; 32] {
+                &self.0
+            }
+        }
+
+        impl From<[u8; 32]> for $name {
+            fn from(bytes: [u8; 32]) -> Self {
+                Self(bytes)
+            }
+        }
+
+        impl From<$name> for [u8; 32] {
+            fn from(id: $name) -> Self {
+                id.0
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "{}{}", Self::PREFIX, hex::encode(self.0))
+            }
+        }
+
+        impl fmt::Debug for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt::Display::fmt(self, f)
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = ParseIdError;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                let s = s
+                    .strip_prefix(Self::PREFIX)
+                    .ok_or(ParseIdError::MissingPrefix(Self::PREFIX))?;
+                decode_hex(s).map(Self)
+            }
+        }
+
+        serde_from_str!($name);
+    };
+}
+
+hex_id!(
+    /// Hash of a block header
+    BlockHash,
+    ""
+);
+hex_id!(
+    /// Root of the VM state
+    StateRoot,
+    ""
+);
+hex_id!(
+    /// Id of a transaction
+    TxId,
+    "tx_"
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hex_ids() {
+        let hash = BlockHash([0xab; 32]);
+        let s = hash.to_string();
+        assert_eq!(s, "ab".repeat(32));
+        assert_eq!(s.parse::<BlockHash>(), Ok(hash));
+
+        let id = TxId([1; 32]);
+        let s = id.to_string();
+        assert_eq!(s, format!("tx_{}", "01".repeat(32)));
+        assert_eq!(s.parse::<TxId>(), Ok(id));
+        assert_eq!(
+            "01".repeat(32).parse::<TxId>(),
+            Err(ParseIdError::MissingPrefix("tx_"))
+        );
+        assert_eq!(
+            "abcd".parse::<StateRoot>(),
+            Err(ParseIdError::InvalidLength {
+                expected: 32,
+                found: 2
+            })
+        );
+
+        let json = serde_json::to_string(&id).unwrap();
+        assert_eq!(json, format!("\"{id}\""));
+        assert_eq!(serde_json::from_str::<TxId>(&json).unwrap(), id);
+    }
+
+    #[test]
+    fn test_public_key() {
+        let pk = crate::bls::PublicKey::from_sk_seed_u64(1);
+        let s = pk.to_string();
+        assert_eq!(s, pk.to_base58());
+        assert_eq!(s.parse::<crate::bls::PublicKey>(), Ok(pk.clone()));
+        assert_eq!(s.parse::<crate::bls::PublicKeyBytes>(), Ok(*pk.bytes()));
+
+        let json = serde_json::to_string(&pk).unwrap();
+        assert_eq!(
+            serde_json::from_str::<crate::bls::PublicKey>(&json).unwrap(),
+            pk
+        );
+
+        assert!(bs58::encode([0u8; 96])
+            .into_string()
+            .parse::<crate::bls::PublicKey>()
+            .is_err());
+    }
+}
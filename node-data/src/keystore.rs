@@ -0,0 +1,369 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Encrypted keystore of the consensus keys of a provisioner.
+//!
+//! The secret key is encrypted with AES-256-GCM, under a key derived from the
+//! password with scrypt, and stored as JSON along with its public key, which
+//! is authenticated with it:
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "public_key": "<base58>",
+//!   "kdf": { "log_n": 15, "r": 8, "p": 1 },
+//!   "salt": "<hex>",
+//!   "nonce": "<hex>",
+//!   "ciphertext": "<hex>"
+//! }
+//! ```
+//!
+//! The files of the former consensus keys format are still unlocked by
+//! [`unlock_file`].
+
+use std::fmt::Debug;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use dusk_bytes::{DeserializableSlice, Serializable};
+use dusk_core::signatures::bls::{
+    PublicKey as BlsPublicKey, SecretKey as BlsSecretKey,
+};
+use rand::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+use serde_with::hex::Hex;
+use serde_with::serde_as;
+
+use crate::bls::{self, PublicKey};
+
+/// Version of the keystore format.
+pub const VERSION: u8 = 1;
+
+const SALT_SIZE: usize = 32;
+const NONCE_SIZE: usize = 12;
+
+/// Parameters of the scrypt derivation of the encryption key
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KdfParams {
+    /// Logarithm of the CPU/memory cost
+    pub log_n: u8,
+    /// Block size
+    pub r: u32,
+    /// Parallelization
+    pub p: u32,
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        Self {
+            log_n: 15,
+            r: 8,
+            p: 1,
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum KeystoreError {
+    #[error("unsupported keystore version {0}")]
+    Version(u8),
+    #[error("invalid scrypt parameters")]
+    InvalidKdf,
+    #[error("invalid keystore password")]
+    InvalidPassword,
+    #[error("keystore secret key doesn't own its public key")]
+    KeyMismatch,
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+/// Consensus keys, locked with a password
+#[serde_as]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Keystore {
+    version: u8,
+    public_key: PublicKey,
+    kdf: KdfParams,
+    #[serde_as(as = "Hex")]
+    salt: [u8; SALT_SIZE],
+    #[serde_as(as = "Hex")]
+    nonce: [u8; NONCE_SIZE],
+    #[serde_as(as = "Hex")]
+    ciphertext: Vec<u8>,
+}
+
+impl Keystore {
+    /// Locks `sk` with `pwd`, deriving the encryption key with the default
+    /// scrypt parameters
+    pub fn create<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        sk: &BlsSecretKey,
+        pwd: &str,
+    ) -> Result<Self, KeystoreError> {
+        Self::create_with(rng, sk, pwd, KdfParams::default())
+    }
+
+    /// Locks `sk` with `pwd`, deriving the encryption key with `kdf`
+    pub fn create_with<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        sk: &BlsSecretKey,
+        pwd: &str,
+        kdf: KdfParams,
+    ) -> Result<Self, KeystoreError> {
+        let public_key = PublicKey::new(BlsPublicKey::from(sk));
+
+        let mut salt = [0u8; SALT_SIZE];
+        rng.fill_bytes(&mut salt);
+        let mut nonce = [0u8; NONCE_SIZE];
+        rng.fill_bytes(&mut nonce);
+
+        let ciphertext = cipher(pwd, &salt, &kdf)?
+            .encrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: &sk.to_bytes(),
+                    aad: public_key.bytes().inner(),
+                },
+            )
+            .expect("the secret key size to be within the AES-GCM limits");
+
+        Ok(Self {
+            version: VERSION,
+            public_key,
+            kdf,
+            salt,
+            nonce,
+            ciphertext,
+        })
+    }
+
+    /// Returns the public key of the locked secret key
+    pub fn public_key(&self) -> &PublicKey {
+        &self.public_key
+    }
+
+    /// Unlocks the keystore with `pwd`
+    pub fn unlock(&self, pwd: &str) -> Result<UnlockedKeystore, KeystoreError> {
+        if self.version != VERSION {
+            return Err(KeystoreError::Version(self.version));
+        }
+
+        let bytes = cipher(pwd, &self.salt, &self.kdf)?
+            .decrypt(
+                Nonce::from_slice(&self.nonce),
+                Payload {
+                    msg: &self.ciphertext,
+                    aad: self.public_key.bytes().inner(),
+                },
+            )
+            .map_err(|_| KeystoreError::InvalidPassword)?;
+
+        let sk = BlsSecretKey::from_slice(&bytes)
+            .map_err(|_| KeystoreError::KeyMismatch)?;
+        if BlsPublicKey::from(&sk) != *self.public_key.inner() {
+            return Err(KeystoreError::KeyMismatch);
+        }
+
+        Ok(UnlockedKeystore {
+            sk,
+            pk: self.public_key.clone(),
+            kdf: self.kdf,
+        })
+    }
+
+    /// Reads the keystore at `path`
+    pub fn read(path: &Path) -> Result<Self, KeystoreError> {
+        Ok(serde_json::from_slice(&fs::read(path)?)?)
+    }
+
+    /// Writes the keystore to `path`
+    pub fn write(&self, path: &Path) -> Result<(), KeystoreError> {
+        fs::write(path, serde_json::to_vec_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// Derives the cipher from the password
+fn cipher(
+    pwd: &str,
+    salt: &[u8],
+    kdf: &KdfParams,
+) -> Result<Aes256Gcm, KeystoreError> {
+    let params = scrypt::Params::new(kdf.log_n, kdf.r, kdf.p, 32)
+        .map_err(|_| KeystoreError::InvalidKdf)?;
+
+    let mut key = Key::<Aes256Gcm>::default();
+    scrypt::scrypt(pwd.as_bytes(), salt, &params, &mut key)
+        .expect("the key size to be a valid scrypt output size");
+    Ok(Aes256Gcm::new(&key))
+}
+
+/// Consensus keys, unlocked to sign with them
+#[derive(Clone)]
+pub struct UnlockedKeystore {
+    sk: BlsSecretKey,
+    pk: PublicKey,
+    /// Parameters the keys are locked with again
+    kdf: KdfParams,
+}
+
+impl Debug for UnlockedKeystore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("UnlockedKeystore")
+            .field("pk", &self.pk)
+            .finish_non_exhaustive()
+    }
+}
+
+impl UnlockedKeystore {
+    pub fn secret_key(&self) -> &BlsSecretKey {
+        &self.sk
+    }
+
+    pub fn public_key(&self) -> &PublicKey {
+        &self.pk
+    }
+
+    /// Returns the secret and public keys
+    pub fn into_keys(self) -> (BlsSecretKey, PublicKey) {
+        (self.sk, self.pk)
+    }
+
+    /// Locks the keys again with `pwd`, e.g. to change their password, with
+    /// the scrypt parameters they were locked with
+    pub fn export<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        pwd: &str,
+    ) -> Result<Keystore, KeystoreError> {
+        Keystore::create_with(rng, &self.sk, pwd, self.kdf)
+    }
+}
+
+/// Unlocks the consensus keys stored at `path` with `pwd`, either in a
+/// keystore or in the former consensus keys format
+pub fn unlock_file(path: &Path, pwd: &str) -> anyhow::Result<UnlockedKeystore> {
+    let bytes = fs::read(path).map_err(|e| {
+        anyhow::anyhow!(
+            "{} should be valid consensus keys file {e}",
+            path.display()
+        )
+    })?;
+
+    match serde_json::from_slice::<Keystore>(&bytes) {
+        Ok(keystore) => Ok(keystore.unlock(pwd)?),
+        Err(_) => {
+            let (sk, pk) =
+                bls::load_keys(path.display().to_string(), pwd.to_string())?;
+            Ok(UnlockedKeystore {
+                sk,
+                pk,
+                kdf: KdfParams::default(),
+            })
+        }
+    }
+}
+
+/// Returns the path of the keys replacing the ones at `path` once rotated,
+/// i.e. `path` with the `next.keys` extension
+pub fn next_keys_path(path: &Path) -> PathBuf {
+    path.with_extension("next.keys")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use tempfile::tempdir;
+
+    use super::*;
+
+    const TEST_KDF: KdfParams = KdfParams {
+        log_n: 4,
+        r: 8,
+        p: 1,
+    };
+
+    #[test]
+    fn test_keystore() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempdir()?;
+        let path = dir.path().join("consensus.keys");
+
+        let rng = &mut StdRng::seed_from_u64(0xbeef);
+        let sk = BlsSecretKey::random(rng);
+        let keystore = Keystore::create_with(rng, &sk, "password", TEST_KDF)?;
+        keystore.write(&path)?;
+
+        let read = Keystore::read(&path)?;
+        assert_eq!(read, keystore);
+        assert_eq!(read.public_key().inner(), &BlsPublicKey::from(&sk));
+
+        let unlocked = unlock_file(&path, "password")?;
+        assert_eq!(unlocked.secret_key(), &sk);
+        assert_eq!(unlocked.public_key(), keystore.public_key());
+        assert!(matches!(
+            keystore.unlock("wrong"),
+            Err(KeystoreError::InvalidPassword)
+        ));
+
+        // The public key is authenticated along with the secret key
+        let mut tampered = keystore.clone();
+        tampered.public_key = PublicKey::from_sk_seed_u64(1);
+        assert!(matches!(
+            tampered.unlock("password"),
+            Err(KeystoreError::InvalidPassword)
+        ));
+
+        let mut future = keystore;
+        future.version = VERSION + 1;
+        assert!(matches!(
+            future.unlock("password"),
+            Err(KeystoreError::Version(_))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_export() -> Result<(), Box<dyn std::error::Error>> {
+        let rng = &mut StdRng::seed_from_u64(0xcafe);
+        let sk = BlsSecretKey::random(rng);
+        let unlocked = Keystore::create_with(rng, &sk, "password", TEST_KDF)?
+            .unlock("password")?;
+
+        let exported = unlocked.export(rng, "new password")?;
+        assert_eq!(exported.kdf, TEST_KDF);
+        assert!(exported.unlock("password").is_err());
+        assert_eq!(exported.unlock("new password")?.into_keys().0, sk);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_unlock_former_format() -> Result<(), Box<dyn std::error::Error>> {
+        let mut path = PathBuf::from(file!());
+        path.pop();
+        let path: PathBuf = path.components().skip(1).collect();
+        let path = path
+            .join("test-data")
+            .join("wallet-generated-consensus-keys")
+            .join("consensus.keys");
+
+        let mut rng = StdRng::seed_from_u64(64);
+        let sk = BlsSecretKey::random(&mut rng);
+
+        let unlocked = unlock_file(&path, "password")?;
+        assert_eq!(unlocked.secret_key(), &sk);
+
+        Ok(())
+    }
+}
@@ -11,11 +11,21 @@ mod block;
 pub use block::*;
 
 mod transaction;
-pub use transaction::{SpendingId, SpentTransaction, Transaction};
+pub use transaction::{
+    canonical_order, is_canonically_ordered, OrderKey, SpendingId,
+    SpentTransaction, Transaction, BUNDLED_TX_VERSION, SPONSORED_TX_TYPE,
+    TX_TYPE, TX_VERSION,
+};
+
+mod bundle;
+pub use bundle::{BundlePosition, TransactionBundle};
 
 mod faults;
 pub use faults::{Fault, InvalidFault, Slash, SlashType};
 
+mod epoch_root;
+pub use epoch_root::{epoch_heights, epoch_of, epoch_root, EpochProof};
+
 mod attestation;
 pub use attestation::{
     Attestation, IterationInfo, IterationsInfo, Signature, StepVotes,
@@ -46,10 +46,7 @@ impl Block {
             return Ok(());
         }
 
-        let mut hasher = sha3::Sha3_256::new();
-        self.header.marshal_hashable(&mut hasher)?;
-
-        self.header.hash = hasher.finalize().into();
+        self.header.hash = self.header.compute_hash()?;
         Ok(())
     }
 
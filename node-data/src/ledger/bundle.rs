@@ -0,0 +1,161 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use dusk_bytes::Serializable as DuskSerializable;
+use dusk_core::transfer::bundle::TransactionBundle as ProtocolBundle;
+use sha3::Digest;
+
+use super::Transaction;
+
+/// A bundle of transactions, to be included in a candidate block all
+/// together, in order, or not at all.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransactionBundle {
+    pub version: u32,
+    pub inner: ProtocolBundle,
+}
+
+/// The position of a mempool transaction within its bundle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BundlePosition {
+    /// The id of the bundle
+    pub id: [u8; 32],
+    /// The index of the transaction in the bundle
+    pub index: u32,
+    /// The number of transactions in the bundle
+    pub len: u32,
+}
+
+impl BundlePosition {
+    /// Size of the serialized position
+    pub const SIZE: usize = 32 + 4 + 4;
+
+    pub fn to_bytes(&self) -> [u8; Self::SIZE] {
+        let mut bytes = [0u8; Self::SIZE];
+        bytes[..32].copy_from_slice(&self.id);
+        bytes[32..36].copy_from_slice(&self.index.to_le_bytes());
+        bytes[36..].copy_from_slice(&self.len.to_le_bytes());
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8; Self::SIZE]) -> Self {
+        let mut id = [0u8; 32];
+        id.copy_from_slice(&bytes[..32]);
+        let mut index = [0u8; 4];
+        index.copy_from_slice(&bytes[32..36]);
+        let mut len = [0u8; 4];
+        len.copy_from_slice(&bytes[36..]);
+        Self {
+            id,
+            index: u32::from_le_bytes(index),
+            len: u32::from_le_bytes(len),
+        }
+    }
+
+    pub fn is_first(&self) -> bool {
+        self.index == 0
+    }
+
+    pub fn is_last(&self) -> bool {
+        self.index + 1 >= self.len
+    }
+}
+
+impl From<ProtocolBundle> for TransactionBundle {
+    fn from(value: ProtocolBundle) -> Self {
+        Self {
+            inner: value,
+            version: 1,
+        }
+    }
+}
+
+impl TransactionBundle {
+    /// Computes the bundle ID.
+    ///
+    /// This is the Sha3 256 digest of the IDs of the transactions of the
+    /// bundle, in order.
+    pub fn id(&self) -> [u8; 32] {
+        bundle_id(self.inner.txs().iter().map(|tx| tx.hash().to_bytes()))
+    }
+
+    /// Returns the transactions of the bundle in execution order, each one
+    /// carrying its position in the bundle.
+    pub fn txs(&self) -> Vec<Transaction> {
+        let id = self.id();
+        let len = self.inner.txs().len() as u32;
+        self.inner
+            .txs()
+            .iter()
+            .enumerate()
+            .map(|(index, tx)| {
+                Transaction::from(tx.clone()).with_bundle(BundlePosition {
+                    id,
+                    index: index as u32,
+                    len,
+                })
+            })
+            .collect()
+    }
+
+    pub fn gas_limit(&self) -> u64 {
+        self.inner.gas_limit()
+    }
+}
+
+/// Computes the id of the bundle of the transactions of ids `tx_ids`, in
+/// order
+pub(crate) fn bundle_id(tx_ids: impl Iterator<Item = [u8; 32]>) -> [u8; 32] {
+    let mut hasher = sha3::Sha3_256::new();
+    for tx_id in tx_ids {
+        hasher.update(tx_id);
+    }
+    hasher.finalize().into()
+}
+
+#[cfg(any(feature = "faker", test))]
+pub mod faker {
+    use rand::Rng;
+
+    use super::*;
+    use crate::ledger::faker::gen_dummy_tx;
+    use crate::ledger::Dummy;
+
+    impl<T> Dummy<T> for TransactionBundle {
+        fn dummy_with_rng<R: Rng + ?Sized>(_config: &T, rng: &mut R) -> Self {
+            let txs = (0..rng.gen_range(1..4))
+                .map(|i| gen_dummy_tx(1_000_000 + i).inner)
+                .collect();
+            ProtocolBundle::new(txs).expect("valid bundle").into()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ledger::faker::gen_dummy_tx;
+
+    #[test]
+    fn test_bundle_positions() {
+        let txs = vec![gen_dummy_tx(1), gen_dummy_tx(2), gen_dummy_tx(3)];
+        let inner = txs.iter().map(|tx| tx.inner.clone()).collect();
+        let bundle = TransactionBundle::from(
+            ProtocolBundle::new(inner).expect("valid bundle"),
+        );
+
+        let bundle_txs = bundle.txs();
+        let ids: Vec<_> = bundle_txs.iter().map(Transaction::id).collect();
+        assert_eq!(ids, txs.iter().map(Transaction::id).collect::<Vec<_>>());
+        for (index, tx) in bundle_txs.iter().enumerate() {
+            let pos = tx.bundle().expect("tx to be bundled");
+            assert_eq!(pos.id, bundle.id());
+            assert_eq!(pos.index, index as u32);
+            assert_eq!(pos.is_first(), index == 0);
+            assert_eq!(pos.is_last(), index == 2);
+        }
+    }
+}
@@ -0,0 +1,238 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Merkle commitments to the block hashes of an epoch.
+//!
+//! The leaves of the tree are the hashes of the blocks of the epoch, in
+//! height order. Leaves and inner nodes are hashed with distinct prefixes,
+//! and a node without a sibling is moved up to the next level unchanged, so
+//! that no two lists of hashes share the same root.
+
+use std::io::{self, Read, Write};
+use std::ops::Range;
+
+use dusk_core::stake::EPOCH;
+use sha3::{Digest, Sha3_256};
+
+use super::Hash;
+use crate::Serializable;
+
+const LEAF_PREFIX: u8 = 0;
+const NODE_PREFIX: u8 = 1;
+
+/// Returns the epoch of the block at `height`
+pub fn epoch_of(height: u64) -> u64 {
+    height / EPOCH
+}
+
+/// Returns the heights of the blocks of `epoch`
+pub fn epoch_heights(epoch: u64) -> Range<u64> {
+    epoch * EPOCH..(epoch + 1) * EPOCH
+}
+
+fn leaf(hash: &Hash) -> Hash {
+    let mut hasher = Sha3_256::new();
+    hasher.update([LEAF_PREFIX]);
+    hasher.update(hash);
+    hasher.finalize().into()
+}
+
+fn node(left: &Hash, right: &Hash) -> Hash {
+    let mut hasher = Sha3_256::new();
+    hasher.update([NODE_PREFIX]);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+fn next_level(level: &[Hash]) -> Vec<Hash> {
+    level
+        .chunks(2)
+        .map(|pair| match pair {
+            [left, right] => node(left, right),
+            [single] => *single,
+            _ => unreachable!(),
+        })
+        .collect()
+}
+
+/// Computes the root of the tree over `hashes`.
+///
+/// The root of an empty list is all zeros.
+pub fn epoch_root(hashes: &[Hash]) -> Hash {
+    let mut level: Vec<_> = hashes.iter().map(leaf).collect();
+    if level.is_empty() {
+        return [0u8; 32];
+    }
+    while level.len() > 1 {
+        level = next_level(&level);
+    }
+    level[0]
+}
+
+/// Proof that a block hash is a leaf of an epoch root
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EpochProof {
+    pub epoch: u64,
+    /// Number of the leaves of the tree
+    pub len: u32,
+    /// Position of the block in the epoch
+    pub index: u32,
+    /// Siblings of the path from the leaf to the root, bottom up
+    pub siblings: Vec<Hash>,
+}
+
+impl EpochProof {
+    /// Builds the proof of the hash at `index` in the `hashes` of `epoch`.
+    ///
+    /// Returns `None` if `index` is out of bounds.
+    pub fn new(epoch: u64, hashes: &[Hash], index: usize) -> Option<Self> {
+        if index >= hashes.len() {
+            return None;
+        }
+
+        let mut level: Vec<_> = hashes.iter().map(leaf).collect();
+        let mut position = index;
+        let mut siblings = vec![];
+        while level.len() > 1 {
+            if let Some(sibling) = level.get(position ^ 1) {
+                siblings.push(*sibling);
+            }
+            level = next_level(&level);
+            position /= 2;
+        }
+
+        Some(Self {
+            epoch,
+            len: hashes.len() as u32,
+            index: index as u32,
+            siblings,
+        })
+    }
+
+    /// Returns the height of the block the proof is for
+    pub fn height(&self) -> u64 {
+        epoch_heights(self.epoch).start + self.index as u64
+    }
+
+    /// Checks that `hash` is the leaf of the proof in the tree of `root`
+    pub fn verify(&self, hash: &Hash, root: &Hash) -> bool {
+        if self.index >= self.len {
+            return false;
+        }
+
+        let mut hash = leaf(hash);
+        let mut position = self.index;
+        let mut len = self.len;
+        let mut siblings = self.siblings.iter();
+        while len > 1 {
+            if position % 2 == 1 {
+                let Some(sibling) = siblings.next() else {
+                    return false;
+                };
+                hash = node(sibling, &hash);
+            } else if position + 1 < len {
+                let Some(sibling) = siblings.next() else {
+                    return false;
+                };
+                hash = node(&hash, sibling);
+            }
+            position /= 2;
+            len = len.div_ceil(2);
+        }
+
+        siblings.next().is_none() && &hash == root
+    }
+}
+
+impl Serializable for EpochProof {
+    fn write<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        w.write_all(&self.epoch.to_le_bytes())?;
+        w.write_all(&self.len.to_le_bytes())?;
+        w.write_all(&self.index.to_le_bytes())?;
+
+        let siblings_len = self.siblings.len() as u32;
+        w.write_all(&siblings_len.to_le_bytes())?;
+        for sibling in &self.siblings {
+            w.write_all(sibling)?;
+        }
+
+        Ok(())
+    }
+
+    fn read<R: Read>(r: &mut R) -> io::Result<Self>
+    where
+        Self: Sized,
+    {
+        let epoch = Self::read_u64_le(r)?;
+        let len = Self::read_u32_le(r)?;
+        let index = Self::read_u32_le(r)?;
+
+        // A tree of u32 leaves has at most 32 levels
+        let siblings_len = Self::read_u32_le(r)?;
+        if siblings_len > 32 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Too many siblings",
+            ));
+        }
+        let siblings = (0..siblings_len)
+            .map(|_| Self::read_bytes(r))
+            .collect::<io::Result<_>>()?;
+
+        Ok(Self {
+            epoch,
+            len,
+            index,
+            siblings,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hashes(len: usize) -> Vec<Hash> {
+        (0..len).map(|i| [i as u8; 32]).collect()
+    }
+
+    #[test]
+    fn test_epoch_proofs() {
+        for len in [1, 2, 3, 7, 8, 13] {
+            let hashes = hashes(len);
+            let root = epoch_root(&hashes);
+
+            for (index, hash) in hashes.iter().enumerate() {
+                let proof = EpochProof::new(4, &hashes, index).unwrap();
+                assert!(proof.verify(hash, &root));
+                assert!(!proof.verify(&[0xff; 32], &root));
+
+                let mut buf = vec![];
+                proof.write(&mut buf).unwrap();
+                assert_eq!(EpochProof::read(&mut &buf[..]).unwrap(), proof);
+            }
+            assert!(EpochProof::new(4, &hashes, len).is_none());
+        }
+    }
+
+    #[test]
+    fn test_epoch_root_distinct() {
+        // A duplicated last hash must not yield the same root
+        let mut hashes = hashes(3);
+        let root = epoch_root(&hashes);
+        hashes.push(hashes[2]);
+        assert_ne!(epoch_root(&hashes), root);
+
+        let proof = EpochProof::new(0, &hashes, 1).unwrap();
+        let mut moved = proof.clone();
+        moved.index = 0;
+        assert!(!moved.verify(&hashes[1], &epoch_root(&hashes)));
+        assert_eq!(epoch_heights(2).start, 2 * EPOCH);
+        assert_eq!(epoch_of(2 * EPOCH + 1), 2);
+        assert_eq!(proof.height(), 1);
+    }
+}
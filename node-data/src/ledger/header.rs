@@ -7,7 +7,10 @@
 use serde::Serialize;
 
 use super::*;
-use crate::message::ConsensusHeader;
+use crate::message::{
+    ConsensusHeader, EXTRA_DATA_HEADER_VERSION, MAX_HEADER_EXTRA_DATA_SIZE,
+    PROVISIONERS_ROOT_HEADER_VERSION,
+};
 
 pub type Seed = Signature;
 #[derive(Eq, PartialEq, Clone, Serialize)]
@@ -34,6 +37,17 @@ pub struct Header {
     pub iteration: u8,
     pub prev_block_cert: Attestation,
     pub failed_iterations: IterationsInfo,
+    /// Commitment to the provisioner set active for this block. Only part
+    /// of the header since [`PROVISIONERS_ROOT_HEADER_VERSION`]
+    #[serde(serialize_with = "crate::serialize_hex")]
+    #[cfg_attr(any(feature = "faker", test), dummy(expr = "[0; 32]"))]
+    pub provisioners_root: Hash,
+    /// Arbitrary data of the generator, such as its client identifier, of at
+    /// most [`MAX_HEADER_EXTRA_DATA_SIZE`] bytes. Only part of the header
+    /// since [`EXTRA_DATA_HEADER_VERSION`]
+    #[serde(serialize_with = "crate::serialize_hex")]
+    #[cfg_attr(any(feature = "faker", test), dummy(expr = "vec![]"))]
+    pub extra_data: Vec<u8>,
 
     // Block hash
     #[serde(serialize_with = "crate::serialize_hex")]
@@ -63,6 +77,8 @@ impl Default for Header {
             iteration: Default::default(),
             prev_block_cert: Default::default(),
             failed_iterations: Default::default(),
+            provisioners_root: Default::default(),
+            extra_data: Default::default(),
             hash: Default::default(),
             signature: Default::default(),
             att: Default::default(),
@@ -100,6 +116,8 @@ impl std::fmt::Debug for Header {
             .field("att", &self.att)
             .field("tx_root", &to_str(&self.txroot))
             .field("fault_root", &to_str(&self.faultroot))
+            .field("provisioners_root", &to_str(&self.provisioners_root))
+            .field("extra_data", &self.extra_data_lossy())
             .finish()
     }
 }
@@ -114,6 +132,19 @@ impl Header {
         }
     }
 
+    /// Returns the extra-data as a string, replacing any invalid UTF-8
+    pub fn extra_data_lossy(&self) -> String {
+        String::from_utf8_lossy(&self.extra_data).into_owned()
+    }
+
+    /// Computes the hash of the hashable fields, regardless of the `hash`
+    /// field
+    pub fn compute_hash(&self) -> io::Result<Hash> {
+        let mut hasher = sha3::Sha3_256::new();
+        self.marshal_hashable(&mut hasher)?;
+        Ok(hasher.finalize().into())
+    }
+
     /// Marshal hashable fields.
     pub(crate) fn marshal_hashable<W: Write>(
         &self,
@@ -135,6 +166,19 @@ impl Header {
         w.write_all(&self.iteration.to_le_bytes())?;
         self.prev_block_cert.write(w)?;
         self.failed_iterations.write(w)?;
+        if self.version >= PROVISIONERS_ROOT_HEADER_VERSION {
+            w.write_all(&self.provisioners_root)?;
+        }
+        if self.version >= EXTRA_DATA_HEADER_VERSION {
+            if self.extra_data.len() > MAX_HEADER_EXTRA_DATA_SIZE {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "header extra-data too long",
+                ));
+            }
+            w.write_all(&[self.extra_data.len() as u8])?;
+            w.write_all(&self.extra_data)?;
+        }
 
         Ok(())
     }
@@ -156,6 +200,25 @@ impl Header {
 
         let prev_block_cert = Attestation::read(r)?;
         let failed_iterations = IterationsInfo::read(r)?;
+        let provisioners_root = if version >= PROVISIONERS_ROOT_HEADER_VERSION {
+            Self::read_bytes(r)?
+        } else {
+            [0; 32]
+        };
+        let extra_data = if version >= EXTRA_DATA_HEADER_VERSION {
+            let len = Self::read_u8(r)? as usize;
+            if len > MAX_HEADER_EXTRA_DATA_SIZE {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "header extra-data too long",
+                ));
+            }
+            let mut extra_data = vec![0; len];
+            r.read_exact(&mut extra_data)?;
+            extra_data
+        } else {
+            vec![]
+        };
 
         Ok(Header {
             version,
@@ -174,6 +237,8 @@ impl Header {
             att: Default::default(),
             prev_block_cert,
             failed_iterations,
+            provisioners_root,
+            extra_data,
             signature: Default::default(),
         })
     }
@@ -4,24 +4,45 @@
 //
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
+use std::cmp::Reverse;
+use std::collections::hash_map::Entry;
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
 use std::io;
 
 use dusk_bytes::Serializable as DuskSerializable;
+use dusk_core::abi::ExecutionError;
 use dusk_core::signatures::bls::PublicKey as AccountPublicKey;
 use dusk_core::transfer::moonlight::Transaction as MoonlightTransaction;
 use dusk_core::transfer::phoenix::Transaction as PhoenixTransaction;
+use dusk_core::transfer::sponsor::{SponsoredTransaction, Sponsorship};
 use dusk_core::transfer::Transaction as ProtocolTransaction;
-use serde::Serialize;
+use serde::ser::SerializeMap;
+use serde::{Serialize, Serializer};
 use sha3::Digest;
 
+use super::bundle::bundle_id;
+use super::BundlePosition;
 use crate::Serializable;
 
+/// Type of the transactions whose gas is paid by their sender
+pub const TX_TYPE: u32 = 1;
+/// Type of the transactions whose gas is paid by a sponsor
+pub const SPONSORED_TX_TYPE: u32 = 2;
+
+/// Version of the transactions
+pub const TX_VERSION: u32 = 1;
+/// Version of the transactions serialized along with their position in their
+/// bundle
+pub const BUNDLED_TX_VERSION: u32 = 2;
+
 #[derive(Debug, Clone)]
 pub struct Transaction {
     pub version: u32,
     pub r#type: u32,
     pub inner: ProtocolTransaction,
+    pub(crate) sponsorship: Option<Sponsorship>,
     pub(crate) size: Option<usize>,
+    pub(crate) bundle: Option<BundlePosition>,
 }
 
 impl Transaction {
@@ -35,15 +56,64 @@ impl Transaction {
             }
         }
     }
+
+    /// Returns the position of the transaction in its bundle, if it was
+    /// propagated as part of one.
+    ///
+    /// The position is serialized along with the transaction, whose version
+    /// is then [`BUNDLED_TX_VERSION`], so that the bundles of a block can be
+    /// told apart.
+    pub fn bundle(&self) -> Option<&BundlePosition> {
+        self.bundle.as_ref()
+    }
+
+    pub fn with_bundle(mut self, bundle: BundlePosition) -> Self {
+        if self.bundle.is_none() {
+            self.size = self.size.map(|size| size + BundlePosition::SIZE);
+        }
+        self.version = BUNDLED_TX_VERSION;
+        self.bundle = Some(bundle);
+        self
+    }
+
+    /// Drops the position of the transaction in its bundle, if any
+    pub fn without_bundle(mut self) -> Self {
+        if self.bundle.take().is_some() {
+            self.size = self.size.map(|size| size - BundlePosition::SIZE);
+        }
+        self.version = TX_VERSION;
+        self
+    }
+
+    /// Returns the sponsorship paying the gas of the transaction, if any.
+    pub fn sponsorship(&self) -> Option<&Sponsorship> {
+        self.sponsorship.as_ref()
+    }
 }
 
 impl From<ProtocolTransaction> for Transaction {
     fn from(value: ProtocolTransaction) -> Self {
         Self {
             inner: value,
-            r#type: 1,
-            version: 1,
+            r#type: TX_TYPE,
+            version: TX_VERSION,
+            sponsorship: None,
             size: None,
+            bundle: None,
+        }
+    }
+}
+
+impl From<SponsoredTransaction> for Transaction {
+    fn from(value: SponsoredTransaction) -> Self {
+        let (inner, sponsorship) = value.into_parts();
+        Self {
+            inner,
+            r#type: SPONSORED_TX_TYPE,
+            version: TX_VERSION,
+            sponsorship: Some(sponsorship),
+            size: None,
+            bundle: None,
         }
     }
 }
@@ -59,12 +129,49 @@ pub struct SpentTransaction {
     /// The amount of gas that was spent during the execution of the
     /// transaction.
     pub gas_spent: u64,
-    /// An optional error message if the transaction execution yielded an
-    /// error.
-    pub err: Option<String>,
+    /// The error the transaction execution yielded, if any. It is serialized
+    /// as its message along with its numeric code.
+    #[serde(flatten, serialize_with = "serialize_execution_error")]
+    pub err: Option<ExecutionError>,
+    /// The amount of gas refunded to the sender, i.e. the unused part of the
+    /// gas limit.
+    pub gas_refunded: u64,
+    /// The final fee charged to the sender, in LUX.
+    pub fee_charged: u64,
+}
+
+/// Serializes an execution error as the `err` message and its `err_code`
+fn serialize_execution_error<S: Serializer>(
+    err: &Option<ExecutionError>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let mut map = serializer.serialize_map(Some(2))?;
+    map.serialize_entry("err", &err.as_ref().map(ToString::to_string))?;
+    map.serialize_entry("err_code", &err.as_ref().map(ExecutionError::code))?;
+    map.end()
 }
 
 impl SpentTransaction {
+    /// Creates a spent transaction, deriving the refunded gas and the charged
+    /// fee from the gas spent during execution.
+    pub fn new(
+        inner: Transaction,
+        block_height: u64,
+        gas_spent: u64,
+        err: Option<ExecutionError>,
+    ) -> Self {
+        let gas_refunded = inner.inner.gas_limit().saturating_sub(gas_spent);
+        let fee_charged = gas_spent.saturating_mul(inner.gas_price());
+        Self {
+            inner,
+            block_height,
+            gas_spent,
+            err,
+            gas_refunded,
+            fee_charged,
+        }
+    }
+
     /// Returns the underlying public transaction, if it is one. Otherwise,
     /// returns `None`.
     pub fn public(&self) -> Option<&MoonlightTransaction> {
@@ -90,12 +197,22 @@ impl Transaction {
     /// This method returns the Sha3 256 digest of the entire
     /// transaction in its serialized form
     ///
-    /// The digest hash is currently only being used in the merkle tree.
+    /// The digest hash is currently only being used in the merkle tree. It
+    /// covers the sponsorship of the transaction and its position in its
+    /// bundle, if any.
     ///
     /// ### Returns
     /// An array of 32 bytes representing the hash of the transaction.
     pub fn digest(&self) -> [u8; 32] {
-        sha3::Sha3_256::digest(self.inner.to_var_bytes()).into()
+        let mut hasher = sha3::Sha3_256::new();
+        hasher.update(self.inner.to_var_bytes());
+        if let Some(sponsorship) = &self.sponsorship {
+            hasher.update(sponsorship.to_bytes());
+        }
+        if let Some(bundle) = &self.bundle {
+            hasher.update(bundle.to_bytes());
+        }
+        hasher.finalize().into()
     }
 
     /// Computes the transaction ID.
@@ -106,16 +223,30 @@ impl Transaction {
     /// fields of the transaction and serves as a unique identifier of the
     /// transaction itself.
     ///
+    /// The ID of a sponsored transaction covers its sponsorship, so that it
+    /// never collides with the one of the same transaction unsponsored.
+    ///
     /// ### Returns
     /// An array of 32 bytes representing the transaction ID.
     pub fn id(&self) -> [u8; 32] {
-        self.inner.hash().to_bytes()
+        match &self.sponsorship {
+            Some(sponsorship) => {
+                sponsorship.sponsored_hash(&self.inner).to_bytes()
+            }
+            None => self.inner.hash().to_bytes(),
+        }
     }
 
     pub fn gas_price(&self) -> u64 {
         self.inner.gas_price()
     }
 
+    /// Returns the key of the transaction in the canonical order of the
+    /// block transactions
+    pub fn order_key(&self) -> OrderKey {
+        OrderKey::new(self.gas_price(), self.id())
+    }
+
     pub fn to_spend_ids(&self) -> Vec<SpendingId> {
         match &self.inner {
             ProtocolTransaction::Phoenix(p) => p
@@ -157,6 +288,177 @@ impl PartialEq<Self> for SpentTransaction {
 
 impl Eq for SpentTransaction {}
 
+/// Key of a transaction in the canonical order of the block transactions.
+///
+/// Transactions are ordered by decreasing fee density, i.e. gas price, then
+/// by increasing id, so that the order of a set of transactions doesn't
+/// depend on the node building the block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct OrderKey(Reverse<u64>, [u8; 32]);
+
+impl OrderKey {
+    pub fn new(gas_price: u64, tx_id: [u8; 32]) -> Self {
+        Self(Reverse(gas_price), tx_id)
+    }
+}
+
+/// Returns `txs` in the canonical order of the block transactions.
+///
+/// The transactions are ordered by their [`OrderKey`], except that:
+/// - the Moonlight transactions of an account follow each other by nonce
+/// - the transactions of a bundle follow each other by their position in the
+///   bundle, the bundle being ordered by the key of its first transaction
+///
+/// That is, the next transaction is always the one with the lowest key among
+/// those whose account has no transaction with a lower nonce left, along with
+/// the rest of its bundle. The transactions that can't be ordered this way,
+/// i.e. the bundles holding nonces of an account around the one of another
+/// transaction, are left out along with the transactions waiting for them.
+pub fn canonical_order(txs: Vec<Transaction>) -> Vec<Transaction> {
+    // The transactions are ordered in units, either a bundle or a single
+    // transaction
+    let mut units: Vec<Vec<Transaction>> = vec![];
+    let mut bundles = HashMap::new();
+    for tx in txs {
+        match tx.bundle().map(|pos| pos.id) {
+            Some(id) => match bundles.entry(id) {
+                Entry::Occupied(unit) => units[*unit.get()].push(tx),
+                Entry::Vacant(unit) => {
+                    unit.insert(units.len());
+                    units.push(vec![tx]);
+                }
+            },
+            None => units.push(vec![tx]),
+        }
+    }
+    for unit in &mut units {
+        unit.sort_by_key(|tx| tx.bundle().map(|pos| pos.index));
+    }
+
+    // The units holding the transactions of each account, by nonce
+    let mut accounts: HashMap<_, Vec<(u64, usize)>> = HashMap::new();
+    for (i, unit) in units.iter().enumerate() {
+        for tx in unit {
+            if let ProtocolTransaction::Moonlight(m) = &tx.inner {
+                let account = m.sender().to_bytes();
+                accounts.entry(account).or_default().push((m.nonce(), i));
+            }
+        }
+    }
+    // The units holding nonces of an account around the ones of another unit
+    // can't be ordered
+    let mut blocked = vec![false; units.len()];
+    let mut pending: HashMap<_, VecDeque<usize>> = HashMap::new();
+    for (account, mut nonces) in accounts {
+        nonces.sort_unstable();
+        let mut queue = VecDeque::with_capacity(nonces.len());
+        let mut seen = HashSet::new();
+        for (_, i) in nonces {
+            if queue.back() == Some(&i) {
+                continue;
+            }
+            if !seen.insert(i) {
+                blocked[i] = true;
+            }
+            queue.push_back(i);
+        }
+        pending.insert(account, queue);
+    }
+
+    let unit_accounts: Vec<Vec<_>> = units
+        .iter()
+        .map(|unit| {
+            let mut accounts: Vec<_> = unit
+                .iter()
+                .filter_map(|tx| match &tx.inner {
+                    ProtocolTransaction::Moonlight(m) => {
+                        Some(m.sender().to_bytes())
+                    }
+                    _ => None,
+                })
+                .collect();
+            accounts.sort_unstable();
+            accounts.dedup();
+            accounts
+        })
+        .collect();
+
+    // A unit is ready once it's the next one of all its accounts
+    let is_ready = |pending: &HashMap<_, VecDeque<usize>>, i: usize| {
+        !blocked[i]
+            && unit_accounts[i]
+                .iter()
+                .all(|account| pending[account].front() == Some(&i))
+    };
+
+    let mut ready = BinaryHeap::new();
+    let mut queued = vec![false; units.len()];
+    for i in 0..units.len() {
+        if is_ready(&pending, i) {
+            queued[i] = true;
+            ready.push(Reverse((units[i][0].order_key(), i)));
+        }
+    }
+
+    let mut units: Vec<_> = units.into_iter().map(Some).collect();
+    let mut ordered = vec![];
+    while let Some(Reverse((_, i))) = ready.pop() {
+        for account in &unit_accounts[i] {
+            let queue = pending.get_mut(account).expect("account to be known");
+            queue.pop_front();
+            let next = queue.front().copied();
+            if let Some(next) = next {
+                if !queued[next] && is_ready(&pending, next) {
+                    queued[next] = true;
+                    let key = units[next].as_ref().expect("unit pending")[0]
+                        .order_key();
+                    ready.push(Reverse((key, next)));
+                }
+            }
+        }
+        ordered.extend(units[i].take().expect("unit ordered once"));
+    }
+    ordered
+}
+
+/// Returns true if `txs` are in canonical order, see [`canonical_order`].
+///
+/// The transactions must be unique, and the ones of a bundle must be the
+/// whole bundle, in order.
+pub fn is_canonically_ordered(txs: &[Transaction]) -> bool {
+    let mut ids = HashSet::new();
+    if !txs.iter().all(|tx| ids.insert(tx.id())) {
+        return false;
+    }
+
+    let mut bundles = HashSet::new();
+    let mut i = 0;
+    while i < txs.len() {
+        let Some(pos) = txs[i].bundle() else {
+            i += 1;
+            continue;
+        };
+        let len = pos.len as usize;
+        let Some(bundle) = txs.get(i..i + len) else {
+            return false;
+        };
+        let complete = bundle.iter().enumerate().all(|(index, tx)| {
+            tx.bundle().is_some_and(|p| {
+                p.id == pos.id && p.len == pos.len && p.index as usize == index
+            })
+        });
+        let id = bundle_id(bundle.iter().map(Transaction::id));
+        if !complete || id != pos.id || !bundles.insert(id) {
+            return false;
+        }
+        i += len;
+    }
+
+    let ordered = canonical_order(txs.to_vec());
+    ordered.len() == txs.len()
+        && ordered.iter().zip(txs).all(|(a, b)| a.id() == b.id())
+}
+
 pub enum SpendingId {
     Nullifier([u8; 32]),
     AccountNonce(AccountPublicKey, u64),
@@ -207,12 +509,8 @@ pub mod faker {
     impl<T> Dummy<T> for SpentTransaction {
         fn dummy_with_rng<R: Rng + ?Sized>(_config: &T, _rng: &mut R) -> Self {
             let tx = gen_dummy_tx(1_000_000);
-            SpentTransaction {
-                inner: tx,
-                block_height: 0,
-                gas_spent: 3,
-                err: Some("error".to_string()),
-            }
+            let err = ExecutionError::Panic("error".to_string());
+            SpentTransaction::new(tx, 0, 3, Some(err))
         }
     }
 
@@ -262,3 +560,115 @@ pub mod faker {
         tx.into()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use dusk_core::signatures::bls::SecretKey as AccountSecretKey;
+    use dusk_core::transfer::bundle::TransactionBundle as ProtocolBundle;
+    use dusk_core::transfer::data::TransactionData;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::faker::gen_dummy_tx;
+    use super::*;
+    use crate::ledger::TransactionBundle;
+
+    fn moonlight_tx(
+        sk: &AccountSecretKey,
+        nonce: u64,
+        gas_price: u64,
+    ) -> Transaction {
+        let tx = MoonlightTransaction::new(
+            sk,
+            None,
+            0,
+            0,
+            1,
+            gas_price,
+            nonce,
+            0xFA,
+            None::<TransactionData>,
+        )
+        .expect("valid transaction");
+        ProtocolTransaction::Moonlight(tx).into()
+    }
+
+    fn prices(txs: &[Transaction]) -> Vec<u64> {
+        txs.iter().map(Transaction::gas_price).collect()
+    }
+
+    #[test]
+    fn test_canonical_order() {
+        // Higher gas prices first, then lower ids
+        assert!(OrderKey::new(6, [9; 32]) < OrderKey::new(5, [0; 32]));
+        assert!(OrderKey::new(5, [0; 32]) < OrderKey::new(5, [1; 32]));
+
+        let txs: Vec<_> =
+            [5, 1, 10, 6, 7].into_iter().map(gen_dummy_tx).collect();
+        assert!(!is_canonically_ordered(&txs));
+
+        let mut txs = canonical_order(txs);
+        assert!(is_canonically_ordered(&txs));
+        assert_eq!(prices(&txs), vec![10, 7, 6, 5, 1]);
+
+        // Duplicates are not in canonical order
+        txs.insert(1, txs[0].clone());
+        assert!(!is_canonically_ordered(&txs));
+        assert!(is_canonically_ordered(&[]));
+    }
+
+    #[test]
+    fn test_canonical_order_by_nonce() {
+        let sk = AccountSecretKey::random(&mut StdRng::seed_from_u64(1));
+
+        // The transactions of an account follow each other by nonce, whatever
+        // their gas price
+        let txs = vec![
+            moonlight_tx(&sk, 2, 10),
+            gen_dummy_tx(5),
+            moonlight_tx(&sk, 1, 1),
+        ];
+        let ordered = canonical_order(txs);
+        assert_eq!(prices(&ordered), vec![5, 1, 10]);
+        assert!(is_canonically_ordered(&ordered));
+
+        let swapped =
+            vec![ordered[0].clone(), ordered[2].clone(), ordered[1].clone()];
+        assert!(!is_canonically_ordered(&swapped));
+    }
+
+    #[test]
+    fn test_canonical_order_of_bundles() {
+        let inner = [1, 8].into_iter().map(|p| gen_dummy_tx(p).inner);
+        let bundle = TransactionBundle::from(
+            ProtocolBundle::new(inner.collect()).expect("valid bundle"),
+        );
+
+        // A bundle is ordered by its first transaction, and kept together
+        let mut txs = bundle.txs();
+        txs.extend([gen_dummy_tx(5), gen_dummy_tx(3)]);
+        let ordered = canonical_order(txs);
+        assert_eq!(prices(&ordered), vec![5, 3, 1, 8]);
+        assert!(is_canonically_ordered(&ordered));
+
+        // Without their positions, the transactions are out of order
+        let unbundled: Vec<_> = ordered
+            .iter()
+            .cloned()
+            .map(Transaction::without_bundle)
+            .collect();
+        assert!(!is_canonically_ordered(&unbundled));
+
+        // Bundles are included whole
+        assert!(!is_canonically_ordered(&ordered[..3]));
+
+        // The positions are serialized along with the transactions
+        let mut buf = vec![];
+        ordered[2].write(&mut buf).expect("serialize");
+        let tx = Transaction::read(&mut &buf[..]).expect("deserialize");
+        assert_eq!(tx.version, BUNDLED_TX_VERSION);
+        assert_eq!(tx.bundle(), ordered[2].bundle());
+        assert_eq!(tx.digest(), ordered[2].digest());
+        assert_ne!(tx.digest(), unbundled[2].digest());
+    }
+}
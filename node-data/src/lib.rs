@@ -10,6 +10,8 @@
 pub mod bls;
 pub mod encoding;
 pub mod events;
+pub mod id;
+pub mod keystore;
 pub mod ledger;
 pub mod message;
 
@@ -93,8 +95,8 @@ impl<const N: usize> Serializable for [u8; N] {
     }
 }
 
-pub fn serialize_hex<const N: usize, S>(
-    t: &[u8; N],
+pub fn serialize_hex<T: AsRef<[u8]>, S>(
+    t: &T,
     serializer: S,
 ) -> Result<S::Ok, S::Error>
 where
@@ -7,7 +7,11 @@
 use core::fmt;
 use std::cmp::Ordering;
 use std::io::{self, Read, Write};
+use std::mem;
 use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering as AtomicOrdering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use async_channel::TrySendError;
 use dusk_bytes::Serializable as DuskSerializable;
@@ -31,6 +35,15 @@ pub const PROTOCOL_VERSION: Version = Version(1, 0, 0);
 /// Block version
 pub const BLOCK_HEADER_VERSION: u8 = 1;
 
+/// Block version committing to the active provisioner set
+pub const PROVISIONERS_ROOT_HEADER_VERSION: u8 = 2;
+
+/// Block version carrying the extra-data of the generator
+pub const EXTRA_DATA_HEADER_VERSION: u8 = 3;
+
+/// Max size in bytes of the extra-data of a block header
+pub const MAX_HEADER_EXTRA_DATA_SIZE: usize = 32;
+
 /// Max value for failed iterations.
 pub const MESSAGE_MAX_FAILED_ITERATIONS: u8 = 8;
 
@@ -201,10 +214,14 @@ impl Serializable for Message {
 
             Payload::Block(p) => p.write(w),
             Payload::Transaction(p) => p.write(w),
+            Payload::TransactionBundle(p) => p.write(w),
             Payload::GetMempool(p) => p.write(w),
             Payload::Inv(p) => p.write(w),
             Payload::GetBlocks(p) => p.write(w),
+            Payload::GetHeaders(p) => p.write(w),
+            Payload::Headers(p) => p.write(w),
             Payload::GetResource(p) => p.write(w),
+            Payload::AdminCommand(p) => p.write(w),
 
             Payload::Empty | Payload::ValidationResult(_) => Ok(()), /* internal message, not sent on the wire */
         }
@@ -229,10 +246,14 @@ impl Serializable for Message {
 
             Topics::Block => ledger::Block::read(r)?.into(),
             Topics::Tx => ledger::Transaction::read(r)?.into(),
+            Topics::TxBundle => ledger::TransactionBundle::read(r)?.into(),
             Topics::GetResource => payload::GetResource::read(r)?.into(),
             Topics::GetBlocks => payload::GetBlocks::read(r)?.into(),
+            Topics::GetHeaders => payload::GetHeaders::read(r)?.into(),
+            Topics::Headers => payload::Headers::read(r)?.into(),
             Topics::GetMempool => payload::GetMempool::read(r)?.into(),
             Topics::Inv => payload::Inv::read(r)?.into(),
+            Topics::Admin => payload::AdminCommand::read(r)?.into(),
 
             Topics::Unknown => {
                 return Err(io::Error::new(
@@ -246,6 +267,73 @@ impl Serializable for Message {
     }
 }
 
+/// A borrowed view of a serialized [`Validation`] or [`Ratification`], the
+/// most frequent messages on the wire.
+///
+/// The view neither copies the message nor decompresses the key of its
+/// signer, so that the receiver can decode it with a key it already knows.
+#[derive(Debug, Clone, Copy)]
+pub struct VoteMsgRef<'a> {
+    topic: Topics,
+    bytes: &'a [u8],
+}
+
+impl<'a> VoteMsgRef<'a> {
+    /// Creates a view of `bytes`, if they are a serialized validation or
+    /// ratification.
+    pub fn new(bytes: &'a [u8]) -> Option<Self> {
+        let topic = Topics::from(*bytes.get(TOPIC_FIELD_POS)?);
+        if !matches!(topic, Topics::Validation | Topics::Ratification) {
+            return None;
+        }
+        if bytes.len() < TOPIC_FIELD_POS + 1 + SignInfo::SIZE {
+            return None;
+        }
+        Some(Self { topic, bytes })
+    }
+
+    pub fn topic(&self) -> Topics {
+        self.topic
+    }
+
+    /// Returns the serialized key of the signer.
+    ///
+    /// The sign info being the last field of both validations and
+    /// ratifications, the key is read from the tail of the message.
+    pub fn signer(&self) -> &'a [u8; bls::PUBLIC_BLS_SIZE] {
+        let start = self.bytes.len() - SignInfo::SIZE;
+        self.bytes[start..start + bls::PUBLIC_BLS_SIZE]
+            .try_into()
+            .expect("length to be checked on creation")
+    }
+
+    /// Decodes the message, with `signer` as the decoded key of
+    /// [`Self::signer`].
+    pub fn decode(&self, signer: bls::PublicKey) -> io::Result<Message> {
+        let r = &mut &self.bytes[..];
+        let version = Version::read(r)?;
+        Message::read_u8(r)?;
+
+        let read_sign_info =
+            |r: &mut &[u8]| SignInfo::read_signed_by(r, signer);
+        let message: Message = match self.topic {
+            Topics::Validation => {
+                Validation::read_with(r, read_sign_info)?.into()
+            }
+            _ => Ratification::read_with(r, read_sign_info)?.into(),
+        };
+
+        if !r.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Trailing bytes",
+            ));
+        }
+
+        Ok(message.with_version(version))
+    }
+}
+
 impl<W: WireMessage> From<W> for Message {
     fn from(wire_msg: W) -> Self {
         Self {
@@ -304,10 +392,22 @@ impl WireMessage for payload::GetBlocks {
     const TOPIC: Topics = Topics::GetBlocks;
 }
 
+impl WireMessage for payload::GetHeaders {
+    const TOPIC: Topics = Topics::GetHeaders;
+}
+
+impl WireMessage for payload::Headers {
+    const TOPIC: Topics = Topics::Headers;
+}
+
 impl WireMessage for payload::GetResource {
     const TOPIC: Topics = Topics::GetResource;
 }
 
+impl WireMessage for payload::AdminCommand {
+    const TOPIC: Topics = Topics::Admin;
+}
+
 impl WireMessage for ledger::Block {
     const TOPIC: Topics = Topics::Block;
 }
@@ -316,6 +416,10 @@ impl WireMessage for ledger::Transaction {
     const TOPIC: Topics = Topics::Tx;
 }
 
+impl WireMessage for ledger::TransactionBundle {
+    const TOPIC: Topics = Topics::TxBundle;
+}
+
 impl WireMessage for payload::ValidationResult {
     const TOPIC: Topics = Topics::Unknown;
 }
@@ -409,10 +513,14 @@ pub enum Payload {
 
     Block(Box<ledger::Block>),
     Transaction(Box<ledger::Transaction>),
+    TransactionBundle(Box<ledger::TransactionBundle>),
     GetMempool(payload::GetMempool),
     Inv(payload::Inv),
     GetBlocks(payload::GetBlocks),
+    GetHeaders(payload::GetHeaders),
+    Headers(Box<payload::Headers>),
     GetResource(payload::GetResource),
+    AdminCommand(Box<payload::AdminCommand>),
 
     // Internal messages payload
     // Result message passed from Validation step to Ratification step
@@ -427,9 +535,54 @@ impl Payload {
         match self {
             Payload::GetMempool(p) => p.set_nonce(nonce),
             Payload::GetBlocks(p) => p.set_nonce(nonce),
+            Payload::GetHeaders(p) => p.set_nonce(nonce),
             _ => {}
         }
     }
+
+    /// Returns an estimate of the memory held by the payload out of its
+    /// inline size
+    fn heap_size(&self) -> usize {
+        fn txs_size(txs: &[ledger::Transaction]) -> usize {
+            txs.iter()
+                .map(|tx| {
+                    mem::size_of::<ledger::Transaction>()
+                        + tx.size().unwrap_or_default()
+                })
+                .sum()
+        }
+
+        match self {
+            Payload::Candidate(c) => {
+                mem::size_of::<payload::Candidate>()
+                    + txs_size(c.candidate.txs())
+            }
+            Payload::ValidationQuorum(_) => {
+                mem::size_of::<payload::ValidationQuorum>()
+            }
+            Payload::Block(b) => {
+                mem::size_of::<ledger::Block>() + txs_size(b.txs())
+            }
+            Payload::Transaction(tx) => {
+                txs_size(std::slice::from_ref(tx.as_ref()))
+            }
+            Payload::TransactionBundle(b) => {
+                mem::size_of::<ledger::TransactionBundle>() + txs_size(&b.txs())
+            }
+            Payload::Inv(inv) => {
+                inv.inv_list.len() * mem::size_of::<payload::InvVect>()
+            }
+            Payload::Headers(h) => {
+                mem::size_of::<payload::Headers>()
+                    + h.headers.len() * mem::size_of::<ledger::Header>()
+            }
+            Payload::AdminCommand(_) => mem::size_of::<payload::AdminCommand>(),
+            Payload::ValidationResult(_) => {
+                mem::size_of::<payload::ValidationResult>()
+            }
+            _ => 0,
+        }
+    }
 }
 
 // Consensus messages
@@ -470,6 +623,11 @@ impl From<ledger::Transaction> for Payload {
         Self::Transaction(Box::new(value))
     }
 }
+impl From<ledger::TransactionBundle> for Payload {
+    fn from(value: ledger::TransactionBundle) -> Self {
+        Self::TransactionBundle(Box::new(value))
+    }
+}
 impl From<payload::GetMempool> for Payload {
     fn from(value: payload::GetMempool) -> Self {
         Self::GetMempool(value)
@@ -485,6 +643,16 @@ impl From<payload::GetBlocks> for Payload {
         Self::GetBlocks(value)
     }
 }
+impl From<payload::GetHeaders> for Payload {
+    fn from(value: payload::GetHeaders) -> Self {
+        Self::GetHeaders(value)
+    }
+}
+impl From<payload::Headers> for Payload {
+    fn from(value: payload::Headers) -> Self {
+        Self::Headers(Box::new(value))
+    }
+}
 impl From<payload::GetResource> for Payload {
     fn from(value: payload::GetResource) -> Self {
         Self::GetResource(value)
@@ -492,6 +660,12 @@ impl From<payload::GetResource> for Payload {
 }
 
 // Internal messages
+impl From<payload::AdminCommand> for Payload {
+    fn from(value: payload::AdminCommand) -> Self {
+        Self::AdminCommand(Box::new(value))
+    }
+}
+
 impl From<payload::ValidationResult> for Payload {
     fn from(value: payload::ValidationResult) -> Self {
         Self::ValidationResult(Box::new(value))
@@ -507,7 +681,15 @@ pub mod payload {
 
     use serde::Serialize;
 
+    use dusk_bytes::Serializable as DuskSerializable;
+    use dusk_core::signatures::bls::{
+        Error as BlsSigError, MultisigPublicKey as BlsMultisigPublicKey,
+        MultisigSignature as BlsMultisigSignature, PublicKey as BlsPublicKey,
+        SecretKey as BlsSecretKey,
+    };
+
     use super::{ConsensusHeader, SignInfo};
+    use crate::bls::PublicKey;
     use crate::ledger::{self, to_str, Attestation, Block, Hash, StepVotes};
     use crate::{get_current_timestamp, Serializable};
 
@@ -626,9 +808,19 @@ pub mod payload {
         where
             Self: Sized,
         {
+            Self::read_with(r, SignInfo::read)
+        }
+    }
+
+    impl Validation {
+        /// Reads a validation, using `read_sign_info` to read its sign info
+        pub(crate) fn read_with<R: Read>(
+            r: &mut R,
+            read_sign_info: impl FnOnce(&mut R) -> io::Result<SignInfo>,
+        ) -> io::Result<Self> {
             let header = ConsensusHeader::read(r)?;
             let vote = Vote::read(r)?;
-            let sign_info = SignInfo::read(r)?;
+            let sign_info = read_sign_info(r)?;
 
             Ok(Validation {
                 header,
@@ -1179,6 +1371,113 @@ pub mod payload {
         }
     }
 
+    /// Request of the headers of the blocks following `locator`, along with
+    /// their attestations, for light clients to verify the chain without
+    /// downloading the blocks
+    #[derive(Clone)]
+    pub struct GetHeaders {
+        pub locator: [u8; 32],
+        /// Maximum number of headers requested
+        pub max_headers: u16,
+        pub(crate) nonce: Nonce,
+    }
+
+    impl GetHeaders {
+        pub fn new(locator: [u8; 32], max_headers: u16) -> Self {
+            Self {
+                locator,
+                max_headers,
+                nonce: Nonce::default(),
+            }
+        }
+        pub fn set_nonce<N: Into<Nonce>>(&mut self, nonce: N) {
+            self.nonce = nonce.into()
+        }
+    }
+
+    impl fmt::Debug for GetHeaders {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(
+                f,
+                "GetHeaders, locator: {}, max_headers: {}",
+                to_str(&self.locator),
+                self.max_headers
+            )
+        }
+    }
+
+    impl Serializable for GetHeaders {
+        fn write<W: Write>(&self, w: &mut W) -> io::Result<()> {
+            w.write_all(&self.locator[..])?;
+            w.write_all(&self.max_headers.to_le_bytes())?;
+            self.nonce.write(w)?;
+            Ok(())
+        }
+
+        fn read<R: Read>(r: &mut R) -> io::Result<Self>
+        where
+            Self: Sized,
+        {
+            let locator = Self::read_bytes(r)?;
+            let max_headers = Self::read_u16_le(r)?;
+            let nonce = Nonce::read(r)?;
+            Ok(Self {
+                locator,
+                max_headers,
+                nonce,
+            })
+        }
+    }
+
+    /// Consecutive block headers, each carrying the attestation of its block
+    #[derive(Debug, Clone, Default, PartialEq, Eq)]
+    pub struct Headers {
+        pub headers: Vec<ledger::Header>,
+    }
+
+    impl Headers {
+        /// Maximum number of headers in a message
+        pub const MAX_HEADERS: usize = 256;
+
+        pub fn new(headers: Vec<ledger::Header>) -> Self {
+            Self { headers }
+        }
+    }
+
+    impl Serializable for Headers {
+        fn write<W: Write>(&self, w: &mut W) -> io::Result<()> {
+            if self.headers.len() > Self::MAX_HEADERS {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "Too many headers",
+                ));
+            }
+            let len = self.headers.len() as u16;
+            w.write_all(&len.to_le_bytes())?;
+            for header in &self.headers {
+                header.write(w)?;
+            }
+            Ok(())
+        }
+
+        fn read<R: Read>(r: &mut R) -> io::Result<Self>
+        where
+            Self: Sized,
+        {
+            let len = Self::read_u16_le(r)? as usize;
+            if len > Self::MAX_HEADERS {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "Too many headers",
+                ));
+            }
+            let headers = (0..len)
+                .map(|_| ledger::Header::read(r))
+                .collect::<io::Result<_>>()?;
+            Ok(Self { headers })
+        }
+    }
+
     #[derive(Debug, Clone)]
     pub struct GetResource {
         /// Inventory/Resource to search for
@@ -1269,6 +1568,230 @@ pub mod payload {
         }
     }
 
+    /// Operation requested by the operator of a node
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum AdminAction {
+        /// Revert the chain to the last finalized state
+        Revert,
+        /// Ban a peer
+        Ban(IpAddr),
+        /// Lift the ban of a peer
+        Unban(IpAddr),
+        /// Replace the log filter directives, e.g.
+        /// `info,node::chain::acceptor=debug`
+        SetLogFilter(String),
+        /// Reload the consensus keys from their file, to sign from the next
+        /// round on
+        ReloadConsensusKeys,
+    }
+
+    impl AdminAction {
+        /// Maximum size of the log filter directives
+        pub const MAX_LOG_FILTER_SIZE: usize = 1024;
+    }
+
+    impl Serializable for AdminAction {
+        fn write<W: Write>(&self, w: &mut W) -> io::Result<()> {
+            match self {
+                Self::Revert => w.write_all(&[0]),
+                Self::Ban(ip) => {
+                    w.write_all(&[1])?;
+                    write_ip(w, ip)
+                }
+                Self::Unban(ip) => {
+                    w.write_all(&[2])?;
+                    write_ip(w, ip)
+                }
+                Self::SetLogFilter(filter) => {
+                    w.write_all(&[3])?;
+                    Self::write_var_le_bytes32(w, filter.as_bytes())
+                }
+                Self::ReloadConsensusKeys => w.write_all(&[4]),
+            }
+        }
+
+        fn read<R: Read>(r: &mut R) -> io::Result<Self>
+        where
+            Self: Sized,
+        {
+            let action = match Self::read_u8(r)? {
+                0 => Self::Revert,
+                1 => Self::Ban(read_ip(r)?),
+                2 => Self::Unban(read_ip(r)?),
+                3 => {
+                    let len = Self::read_u32_le(r)? as usize;
+                    if len > Self::MAX_LOG_FILTER_SIZE {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            "Log filter too long",
+                        ));
+                    }
+                    let mut filter = vec![0u8; len];
+                    r.read_exact(&mut filter)?;
+                    let filter = String::from_utf8(filter).map_err(|_| {
+                        io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            "Invalid log filter",
+                        )
+                    })?;
+                    Self::SetLogFilter(filter)
+                }
+                4 => Self::ReloadConsensusKeys,
+                _ => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "Invalid admin action",
+                    ))
+                }
+            };
+            Ok(action)
+        }
+    }
+
+    fn write_ip<W: Write>(w: &mut W, ip: &IpAddr) -> io::Result<()> {
+        match ip {
+            IpAddr::V4(ip) => {
+                w.write_all(&[4])?;
+                w.write_all(&ip.octets())
+            }
+            IpAddr::V6(ip) => {
+                w.write_all(&[6])?;
+                w.write_all(&ip.octets())
+            }
+        }
+    }
+
+    fn read_ip<R: Read>(r: &mut R) -> io::Result<IpAddr> {
+        let mut ip_type = [0u8; 1];
+        r.read_exact(&mut ip_type)?;
+
+        let ip = match ip_type[0] {
+            4 => {
+                let mut octets = [0u8; 4];
+                r.read_exact(&mut octets)?;
+                IpAddr::V4(Ipv4Addr::from(octets))
+            }
+            6 => {
+                let mut octets = [0u8; 16];
+                r.read_exact(&mut octets)?;
+                IpAddr::V6(Ipv6Addr::from(octets))
+            }
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "Invalid IP type",
+                ))
+            }
+        };
+        Ok(ip)
+    }
+
+    /// Admin operation, signed by the operator key of the target node.
+    ///
+    /// The target prevents a command from acting on any other node, and the
+    /// timestamp prevents a captured command from being replayed.
+    #[derive(Debug, Clone)]
+    #[cfg_attr(any(feature = "faker", test), derive(Eq, PartialEq))]
+    pub struct AdminCommand {
+        /// Identifier of the node the command is addressed to
+        pub target: String,
+        pub action: AdminAction,
+        /// Timestamp (in seconds) the command was issued at
+        pub timestamp: u64,
+        pub sign_info: SignInfo,
+    }
+
+    impl AdminCommand {
+        /// Domain separator of the admin commands signatures
+        const SIGN_SEED: &'static [u8] = b"dusk-admin";
+
+        /// Maximum size of the target node identifier
+        pub const MAX_TARGET_SIZE: usize = 64;
+
+        pub fn new(
+            target: impl Into<String>,
+            action: AdminAction,
+            timestamp: u64,
+        ) -> Self {
+            Self {
+                target: target.into(),
+                action,
+                timestamp,
+                sign_info: SignInfo::default(),
+            }
+        }
+
+        pub fn signable(&self) -> Vec<u8> {
+            let mut signable = Self::SIGN_SEED.to_vec();
+            Self::write_var_le_bytes32(&mut signable, self.target.as_bytes())
+                .expect("Writing to vec should succeed");
+            self.action
+                .write(&mut signable)
+                .expect("Writing to vec should succeed");
+            signable.extend_from_slice(&self.timestamp.to_le_bytes());
+            signable
+        }
+
+        pub fn sign(&mut self, sk: &BlsSecretKey, pk: &BlsPublicKey) {
+            let signature = sk.sign_multisig(pk, &self.signable()).to_bytes();
+            self.sign_info.signature = signature.into();
+            self.sign_info.signer = PublicKey::new(*pk);
+        }
+
+        /// Verifies the command is signed by `operator`
+        pub fn verify_signature(
+            &self,
+            operator: &BlsPublicKey,
+        ) -> Result<(), BlsSigError> {
+            if self.sign_info.signer.inner() != operator {
+                return Err(BlsSigError::InvalidSignature);
+            }
+            let signature = self.sign_info.signature.inner();
+            let sig = BlsMultisigSignature::from_bytes(signature)?;
+            let pk = BlsMultisigPublicKey::aggregate(&[*operator])?;
+            pk.verify(&sig, &self.signable())
+        }
+    }
+
+    impl Serializable for AdminCommand {
+        fn write<W: Write>(&self, w: &mut W) -> io::Result<()> {
+            Self::write_var_le_bytes32(w, self.target.as_bytes())?;
+            self.action.write(w)?;
+            w.write_all(&self.timestamp.to_le_bytes())?;
+            self.sign_info.write(w)
+        }
+
+        fn read<R: Read>(r: &mut R) -> io::Result<Self>
+        where
+            Self: Sized,
+        {
+            let len = Self::read_u32_le(r)? as usize;
+            if len > Self::MAX_TARGET_SIZE {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "Admin command target too long",
+                ));
+            }
+            let mut target = vec![0u8; len];
+            r.read_exact(&mut target)?;
+            let target = String::from_utf8(target).map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "Invalid admin command target",
+                )
+            })?;
+            let action = AdminAction::read(r)?;
+            let timestamp = Self::read_u64_le(r)?;
+            let sign_info = SignInfo::read(r)?;
+            Ok(Self {
+                target,
+                action,
+                timestamp,
+                sign_info,
+            })
+        }
+    }
+
     impl Serializable for SocketAddr {
         fn write<W: Write>(&self, w: &mut W) -> io::Result<()> {
             match self {
@@ -1352,6 +1875,7 @@ pub enum Topics {
     // Fire-and-forget messaging
     Tx = 10,
     Block = 11,
+    TxBundle = 12,
 
     // Consensus main loop topics
     Candidate = 16,
@@ -1360,6 +1884,13 @@ pub enum Topics {
     Quorum = 19,
     ValidationQuorum = 20,
 
+    // Operator topics
+    Admin = 21,
+
+    // Light client topics
+    GetHeaders = 22,
+    Headers = 23,
+
     #[default]
     Unknown = 255,
 }
@@ -1383,6 +1914,7 @@ impl From<u8> for Topics {
         map_topic!(v, Topics::GetBlocks);
         map_topic!(v, Topics::Tx);
         map_topic!(v, Topics::Block);
+        map_topic!(v, Topics::TxBundle);
         map_topic!(v, Topics::GetMempool);
         map_topic!(v, Topics::Inv);
         map_topic!(v, Topics::Candidate);
@@ -1390,6 +1922,9 @@ impl From<u8> for Topics {
         map_topic!(v, Topics::Ratification);
         map_topic!(v, Topics::Quorum);
         map_topic!(v, Topics::ValidationQuorum);
+        map_topic!(v, Topics::Admin);
+        map_topic!(v, Topics::GetHeaders);
+        map_topic!(v, Topics::Headers);
 
         Topics::Unknown
     }
@@ -1401,6 +1936,41 @@ impl From<Topics> for u8 {
     }
 }
 
+/// Estimate of the memory held by a message, accounted by the queues it
+/// waits in
+pub trait MemorySize {
+    fn memory_size(&self) -> usize;
+
+    /// Round the message belongs to, the messages of the farthest rounds
+    /// being the first dropped by a queue over its budget
+    fn round(&self) -> u64 {
+        0
+    }
+}
+
+impl MemorySize for Message {
+    fn memory_size(&self) -> usize {
+        mem::size_of::<Self>() + self.payload.heap_size()
+    }
+
+    fn round(&self) -> u64 {
+        self.get_height()
+    }
+}
+
+/// What a queue does with a message that doesn't fit its memory budget
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Drop the incoming message
+    DropNewest,
+    /// Drop the oldest messages until the incoming one fits
+    DropOldest,
+    /// Drop the messages of the farthest rounds, oldest first, until the
+    /// incoming one fits. The messages of the rounds before the incoming one
+    /// are kept, the incoming one being dropped if they don't leave room
+    DropFarthest,
+}
+
 /// AsyncQueue is a thin wrapper of async_channel.
 #[derive(Clone)]
 pub struct AsyncQueue<M: Clone> {
@@ -1409,6 +1979,32 @@ pub struct AsyncQueue<M: Clone> {
 
     cap: usize,
     label: &'static str,
+    stats: Arc<QueueStats>,
+
+    /// Maximum number of bytes held by the queued messages, along with the
+    /// policy applied to the messages exceeding it
+    budget: Option<(usize, OverflowPolicy)>,
+    size_of: fn(&M) -> usize,
+    round_of: fn(&M) -> u64,
+}
+
+struct QueueStats {
+    created: Instant,
+    /// Number of messages dropped because the queue was full or closed
+    dropped: AtomicU64,
+    /// Milliseconds elapsed from `created` to the last received message
+    last_recv: AtomicU64,
+    /// Number of bytes held by the queued messages
+    bytes: AtomicUsize,
+    /// Held by the senders of a budgeted queue from the moment they make
+    /// room for a message until it is sent
+    sending: Mutex<()>,
+}
+
+impl QueueStats {
+    fn elapsed_ms(&self) -> u64 {
+        self.created.elapsed().as_millis() as u64
+    }
 }
 
 impl<M: Clone> AsyncQueue<M> {
@@ -1424,25 +2020,194 @@ impl<M: Clone> AsyncQueue<M> {
             sender,
             cap,
             label,
+            stats: Arc::new(QueueStats {
+                created: Instant::now(),
+                dropped: AtomicU64::new(0),
+                last_recv: AtomicU64::new(0),
+                bytes: AtomicUsize::new(0),
+                sending: Mutex::new(()),
+            }),
+            budget: None,
+            size_of: |_| mem::size_of::<M>(),
+            round_of: |_| 0,
         }
     }
+
+    /// Limits the memory held by the queued messages to `bytes`, applying
+    /// `policy` to the messages exceeding it.
+    ///
+    /// A message is always accepted by an empty queue, even if larger than
+    /// the budget.
+    pub fn with_memory_budget(
+        mut self,
+        bytes: usize,
+        policy: OverflowPolicy,
+    ) -> Self
+    where
+        M: MemorySize,
+    {
+        self.budget = Some((bytes, policy));
+        self.size_of = M::memory_size;
+        self.round_of = M::round;
+        self
+    }
 }
 
 impl<M: Clone> AsyncQueue<M> {
     pub fn try_send(&self, msg: M) {
         let label = self.label;
-        let _ = self.sender.try_send(msg).map_err(|err| match err {
-            TrySendError::Full(_) => {
-                error!("queue ({label}) is full, cap: {}", self.cap);
-            }
-            TrySendError::Closed(_) => {
-                error!("queue ({label}) is closed");
+        let size = (self.size_of)(&msg);
+
+        // Concurrent senders would otherwise all fit in the room made by
+        // each of them
+        let _sending = self.budget.map(|_| {
+            self.stats.sending.lock().expect("lock not to be poisoned")
+        });
+        if !self.make_room(&msg, size) {
+            self.stats.dropped.fetch_add(1, AtomicOrdering::Relaxed);
+            warn!("queue ({label}) is over its memory budget, dropping");
+            return;
+        }
+
+        self.stats.bytes.fetch_add(size, AtomicOrdering::Relaxed);
+        let _ = self.sender.try_send(msg).map_err(|err| {
+            self.stats.bytes.fetch_sub(size, AtomicOrdering::Relaxed);
+            self.stats.dropped.fetch_add(1, AtomicOrdering::Relaxed);
+            match err {
+                TrySendError::Full(_) => {
+                    error!("queue ({label}) is full, cap: {}", self.cap);
+                }
+                TrySendError::Closed(_) => {
+                    error!("queue ({label}) is closed");
+                }
             }
         });
     }
 
-    pub fn recv(&self) -> async_channel::Recv<'_, M> {
-        self.receiver.recv()
+    pub async fn recv(&self) -> Result<M, async_channel::RecvError> {
+        let msg = self.receiver.recv().await;
+        if let Ok(msg) = &msg {
+            self.stats
+                .bytes
+                .fetch_sub((self.size_of)(msg), AtomicOrdering::Relaxed);
+        }
+        self.stats
+            .last_recv
+            .store(self.stats.elapsed_ms(), AtomicOrdering::Relaxed);
+        msg
+    }
+
+    /// Returns whether `msg`, of `size` bytes, fits the memory budget,
+    /// dropping queued messages to make room for it if the policy says so
+    fn make_room(&self, msg: &M, size: usize) -> bool {
+        let Some((budget, policy)) = self.budget else {
+            return true;
+        };
+        let fits = || {
+            self.is_empty()
+                || self.memory_usage().saturating_add(size) <= budget
+        };
+
+        match policy {
+            OverflowPolicy::DropNewest => {}
+            OverflowPolicy::DropOldest => {
+                while !fits() {
+                    let Ok(oldest) = self.receiver.try_recv() else {
+                        break;
+                    };
+                    self.drop_queued(&oldest);
+                }
+            }
+            OverflowPolicy::DropFarthest => {
+                if !fits() {
+                    self.drop_farthest((self.round_of)(msg), size, budget);
+                }
+            }
+        }
+        fits()
+    }
+
+    /// Drops the queued messages of the rounds from `round` on, farthest
+    /// then oldest first, until `size` more bytes fit `budget`.
+    ///
+    /// The queue is drained and the kept messages sent back in their order.
+    fn drop_farthest(&self, round: u64, size: usize, budget: usize) {
+        let queued: Vec<M> =
+            std::iter::from_fn(|| self.receiver.try_recv().ok()).collect();
+
+        let mut order: Vec<(u64, usize)> = queued
+            .iter()
+            .enumerate()
+            .map(|(idx, m)| ((self.round_of)(m), idx))
+            .filter(|(r, _)| *r >= round)
+            .collect();
+        order.sort_unstable_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+
+        let mut dropped = vec![false; queued.len()];
+        for (_, idx) in order {
+            let usage = self.memory_usage();
+            if usage == 0 || usage.saturating_add(size) <= budget {
+                break;
+            }
+            self.drop_queued(&queued[idx]);
+            dropped[idx] = true;
+        }
+
+        for (msg, dropped) in queued.into_iter().zip(dropped) {
+            if !dropped {
+                let _ = self.sender.try_send(msg);
+            }
+        }
+    }
+
+    /// Accounts a queued message dropped to make room for another one
+    fn drop_queued(&self, msg: &M) {
+        self.stats
+            .bytes
+            .fetch_sub((self.size_of)(msg), AtomicOrdering::Relaxed);
+        self.stats.dropped.fetch_add(1, AtomicOrdering::Relaxed);
+    }
+
+    pub fn label(&self) -> &'static str {
+        self.label
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.cap
+    }
+
+    /// Returns the number of messages dropped so far
+    pub fn dropped(&self) -> u64 {
+        self.stats.dropped.load(AtomicOrdering::Relaxed)
+    }
+
+    /// Returns the time elapsed since the consumer last received a message,
+    /// or zero if no message is waiting in the queue
+    pub fn consumer_lag(&self) -> Duration {
+        if self.is_empty() {
+            return Duration::ZERO;
+        }
+        let last_recv = self.stats.last_recv.load(AtomicOrdering::Relaxed);
+        Duration::from_millis(self.stats.elapsed_ms().saturating_sub(last_recv))
+    }
+
+    /// Returns the number of messages waiting in the queue
+    pub fn len(&self) -> usize {
+        self.receiver.len()
+    }
+
+    /// Returns the number of bytes held by the messages waiting in the queue
+    pub fn memory_usage(&self) -> usize {
+        self.stats.bytes.load(AtomicOrdering::Relaxed)
+    }
+
+    /// Returns the memory budget of the queue, if any
+    pub fn memory_budget(&self) -> Option<usize> {
+        self.budget.map(|(bytes, _)| bytes)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.receiver.is_empty()
     }
 }
 
@@ -1585,6 +2350,30 @@ pub struct SignInfo {
     pub signature: Signature,
 }
 
+impl SignInfo {
+    /// The size of the serialized sign info
+    pub const SIZE: usize = bls::PUBLIC_BLS_SIZE + 48;
+
+    /// Reads a sign info whose signer key is already decoded, failing if
+    /// the serialized key is not the one of `signer`
+    fn read_signed_by<R: Read>(
+        r: &mut R,
+        signer: bls::PublicKey,
+    ) -> io::Result<Self> {
+        let bytes: [u8; bls::PUBLIC_BLS_SIZE] = Self::read_bytes(r)?;
+        if &bytes != signer.bytes().inner() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Mismatched signer",
+            ));
+        }
+
+        let signature = Self::read_bytes(r)?.into();
+
+        Ok(Self { signer, signature })
+    }
+}
+
 impl Serializable for SignInfo {
     fn write<W: Write>(&self, w: &mut W) -> io::Result<()> {
         w.write_all(self.signer.bytes().inner())?;
@@ -1621,11 +2410,120 @@ impl std::fmt::Debug for SignInfo {
 #[cfg(test)]
 #[allow(unused)]
 mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
     use self::payload::ValidationResult;
     use super::*;
     use crate::ledger::*;
     use crate::{ledger, Serializable};
 
+    #[test]
+    fn test_queue_stats() {
+        let queue = AsyncQueue::bounded(1, "test");
+        assert_eq!(queue.consumer_lag(), Duration::ZERO);
+
+        queue.try_send(1u8);
+        queue.try_send(2u8);
+        assert_eq!(queue.len(), 1);
+        assert_eq!(queue.capacity(), 1);
+        assert_eq!(queue.dropped(), 1);
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Blob(usize);
+
+    impl MemorySize for Blob {
+        fn memory_size(&self) -> usize {
+            self.0
+        }
+    }
+
+    #[test]
+    fn test_queue_memory_budget() {
+        let queue = AsyncQueue::bounded(10, "test")
+            .with_memory_budget(100, OverflowPolicy::DropNewest);
+        queue.try_send(Blob(60));
+        queue.try_send(Blob(50));
+        assert_eq!(queue.len(), 1);
+        assert_eq!(queue.memory_usage(), 60);
+        assert_eq!(queue.dropped(), 1);
+
+        let queue = AsyncQueue::bounded(10, "test")
+            .with_memory_budget(100, OverflowPolicy::DropOldest);
+        queue.try_send(Blob(30));
+        queue.try_send(Blob(40));
+        queue.try_send(Blob(50));
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue.memory_usage(), 90);
+        assert_eq!(queue.dropped(), 1);
+
+        // A message larger than the budget is accepted by an empty queue only
+        queue.try_send(Blob(200));
+        assert_eq!(queue.len(), 1);
+        assert_eq!(queue.memory_usage(), 200);
+        assert_eq!(queue.dropped(), 3);
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Vote(u64, usize);
+
+    impl MemorySize for Vote {
+        fn memory_size(&self) -> usize {
+            self.1
+        }
+
+        fn round(&self) -> u64 {
+            self.0
+        }
+    }
+
+    #[test]
+    fn test_queue_drop_farthest() {
+        let queue = AsyncQueue::bounded(10, "test")
+            .with_memory_budget(100, OverflowPolicy::DropFarthest);
+        queue.try_send(Vote(10, 30));
+        queue.try_send(Vote(12, 30));
+        queue.try_send(Vote(11, 30));
+
+        // The farthest round makes room for a nearer one
+        queue.try_send(Vote(10, 30));
+        assert_eq!(queue.len(), 3);
+        assert_eq!(queue.memory_usage(), 90);
+        assert_eq!(queue.dropped(), 1);
+
+        // Nothing farther makes room for the farthest round
+        queue.try_send(Vote(12, 30));
+        assert_eq!(queue.len(), 3);
+        assert_eq!(queue.dropped(), 2);
+
+        // The kept messages are still in their order
+        let kept: Vec<_> =
+            std::iter::from_fn(|| queue.receiver.try_recv().ok()).collect();
+        assert_eq!(kept, vec![Vote(10, 30), Vote(11, 30), Vote(10, 30)]);
+    }
+
+    #[test]
+    fn test_queue_budget_concurrent_senders() {
+        let queue = AsyncQueue::bounded(100, "test")
+            .with_memory_budget(100, OverflowPolicy::DropNewest);
+        let senders: Vec<_> = (0..8)
+            .map(|_| {
+                let queue = queue.clone();
+                std::thread::spawn(move || {
+                    (0..10).for_each(|_| queue.try_send(Blob(30)))
+                })
+            })
+            .collect();
+        senders
+            .into_iter()
+            .for_each(|s| s.join().expect("sender not to panic"));
+
+        assert_eq!(queue.len(), 3);
+        assert_eq!(queue.memory_usage(), 90);
+        assert_eq!(queue.dropped(), 77);
+    }
+
     #[test]
     fn test_serialize() {
         let consensus_header = ConsensusHeader {
@@ -1660,9 +2558,13 @@ mod tests {
                 ..Default::default()
             },
             failed_iterations: Default::default(),
+            provisioners_root: [10; 32],
+            extra_data: vec![],
             signature: Signature::from([9; 48]),
         };
 
+        assert_serialize(payload::Headers::new(vec![header.clone(); 3]));
+
         let sample_block = ledger::Block::new(header, vec![], vec![])
             .expect("should be valid block");
 
@@ -1707,6 +2609,144 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_get_headers() {
+        let mut get_headers = payload::GetHeaders::new([3; 32], 100);
+        get_headers.set_nonce(42u64);
+        let msg = Message::from(get_headers);
+        assert_eq!(msg.topic(), Topics::GetHeaders);
+
+        let mut buf = vec![];
+        msg.write(&mut buf).expect("serialize");
+        let msg = Message::read(&mut &buf[..]).expect("deserialize");
+        let Payload::GetHeaders(get_headers) = msg.payload else {
+            panic!("GetHeaders payload expected");
+        };
+        assert_eq!(get_headers.locator, [3; 32]);
+        assert_eq!(get_headers.max_headers, 100);
+        assert_eq!(u64::from(get_headers.nonce), 42);
+
+        let too_many = payload::Headers::MAX_HEADERS + 1;
+        let headers =
+            payload::Headers::new(vec![ledger::Header::default(); too_many]);
+        assert!(headers.write(&mut vec![]).is_err());
+    }
+
+    #[test]
+    fn test_admin_command_signature() {
+        let rng = &mut StdRng::seed_from_u64(1);
+        let sk = BlsSecretKey::random(rng);
+        let pk = BlsPublicKey::from(&sk);
+
+        let ip = "10.0.0.1".parse().unwrap();
+        let mut cmd = payload::AdminCommand::new(
+            "node-1",
+            payload::AdminAction::Ban(ip),
+            1_000,
+        );
+        cmd.sign(&sk, &pk);
+        assert_serialize(cmd.clone());
+        assert!(cmd.verify_signature(&pk).is_ok());
+
+        let other = BlsPublicKey::from(&BlsSecretKey::random(rng));
+        assert!(cmd.verify_signature(&other).is_err());
+
+        // Tampering with the command invalidates the signature
+        let mut retargeted = cmd.clone();
+        retargeted.target = "node-2".to_string();
+        assert!(retargeted.verify_signature(&pk).is_err());
+        cmd.timestamp += 1;
+        assert!(cmd.verify_signature(&pk).is_err());
+
+        let target = "a".repeat(payload::AdminCommand::MAX_TARGET_SIZE + 1);
+        let mut buf = vec![];
+        payload::AdminCommand::new(target, payload::AdminAction::Revert, 1)
+            .write(&mut buf)
+            .expect("serialize");
+        assert!(payload::AdminCommand::read(&mut &buf[..]).is_err());
+
+        let filter = "info,node::chain::acceptor=debug".to_string();
+        let mut cmd = payload::AdminCommand::new(
+            "node-1",
+            payload::AdminAction::SetLogFilter(filter),
+            1_000,
+        );
+        cmd.sign(&sk, &pk);
+        assert_serialize(cmd);
+
+        let too_long =
+            "a".repeat(payload::AdminAction::MAX_LOG_FILTER_SIZE + 1);
+        let mut buf = vec![];
+        payload::AdminAction::SetLogFilter(too_long)
+            .write(&mut buf)
+            .expect("serialize");
+        assert!(payload::AdminAction::read(&mut &buf[..]).is_err());
+
+        let mut cmd = payload::AdminCommand::new(
+            "node-1",
+            payload::AdminAction::ReloadConsensusKeys,
+            1_000,
+        );
+        cmd.sign(&sk, &pk);
+        assert_serialize(cmd);
+    }
+
+    #[test]
+    fn test_vote_msg_ref() {
+        let signer = bls::PublicKey::from_sk_seed_u64(3);
+        let sign_info = SignInfo {
+            signer: signer.clone(),
+            signature: [5; 48].into(),
+        };
+        let header = ConsensusHeader {
+            iteration: 1,
+            prev_block_hash: [2; 32],
+            round: 4,
+        };
+        let validation = payload::Validation {
+            header,
+            vote: payload::Vote::Valid([4; 32]),
+            sign_info: sign_info.clone(),
+        };
+        let ratification = payload::Ratification {
+            header,
+            vote: payload::Vote::Valid([4; 32]),
+            sign_info,
+            validation_result: ValidationResult::new(
+                ledger::StepVotes::new([1; 48], 12345),
+                payload::Vote::Valid([4; 32]),
+                payload::QuorumType::Valid,
+            ),
+            timestamp: 1_000_000,
+        };
+
+        for msg in [Message::from(validation), Message::from(ratification)] {
+            let mut buf = vec![];
+            msg.write(&mut buf).expect("serialize to work");
+
+            let view = VoteMsgRef::new(&buf).expect("vote message");
+            assert_eq!(view.topic(), msg.topic());
+            assert_eq!(view.signer(), signer.bytes().inner());
+
+            let mut decoded = vec![];
+            view.decode(signer.clone())
+                .expect("decode to work")
+                .write(&mut decoded)
+                .expect("serialize to work");
+            assert_eq!(decoded, buf);
+
+            // The key of another signer is refused
+            let other = bls::PublicKey::from_sk_seed_u64(4);
+            assert!(view.decode(other).is_err());
+        }
+
+        let mut buf = vec![];
+        Message::from(payload::GetMempool::default())
+            .write(&mut buf)
+            .expect("serialize to work");
+        assert!(VoteMsgRef::new(&buf).is_none());
+    }
+
     fn assert_serialize<S: Serializable + PartialEq + core::fmt::Debug>(v: S) {
         let mut buf = vec![];
         assert!(v.write(&mut buf).is_ok());
@@ -0,0 +1,4 @@
+-- Indexes for the queries of the events of a contract, by topic and height
+-- range, and for the removal of its expired events.
+CREATE INDEX events_source_block_height_idx ON finalized_events (source, block_height);
+CREATE INDEX events_source_topic_block_height_idx ON finalized_events (source, topic, block_height);
@@ -0,0 +1,388 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Operator alerts.
+//!
+//! This service periodically evaluates a small set of rules over the node
+//! state, so that operators without a monitoring stack still get notified of
+//! critical conditions:
+//!
+//! - no block accepted for a while
+//! - too few alive peers
+//! - too many transactions in the mempool
+//! - the consensus task being restarted too often
+//! - transactions submitted locally not included in time
+//!
+//! Every rule is disabled unless its threshold is configured. A notification
+//! is logged, and optionally POSTed to a webhook, when an alert fires, every
+//! `repeat_interval` while it keeps firing, and once it's resolved.
+
+pub mod conf;
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use metrics::gauge;
+use node_data::get_current_timestamp;
+use serde::Serialize;
+use tokio::sync::RwLock;
+use tokio::time::sleep;
+use tracing::{info, warn};
+
+use crate::alerts::conf::Params;
+use crate::chain::consensus_health;
+use crate::database::rocksdb::MD_HASH_KEY;
+use crate::database::{Ledger, Mempool, Metadata};
+use crate::mempool::inclusion::inclusion_stats;
+use crate::{database, vm, LongLivedService, Network};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Rule {
+    NoBlockAccepted,
+    LowPeerCount,
+    MempoolFull,
+    ConsensusRestarts,
+    InclusionSla,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertState {
+    Firing,
+    Resolved,
+}
+
+/// Node state the rules are evaluated over
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Sample {
+    /// Time elapsed since the last block was accepted
+    pub since_last_block: Duration,
+    pub peers: usize,
+    pub mempool_txs: usize,
+    /// Number of consensus task restarts within the restarts window
+    pub consensus_restarts: u64,
+    /// Time the oldest transaction submitted locally has been waiting for
+    /// inclusion
+    pub oldest_local_tx: Duration,
+    /// 90th percentile of the inclusion latency of the recent transactions
+    /// submitted locally
+    pub local_tx_inclusion_p90: Duration,
+}
+
+/// Notification sent to the webhook
+#[derive(Debug, Serialize)]
+struct Notification<'a> {
+    rule: Rule,
+    state: AlertState,
+    message: &'a str,
+    timestamp: u64,
+}
+
+/// Returns the rules violated by `sample`, along with a description of the
+/// violation
+pub fn evaluate(conf: &Params, sample: &Sample) -> Vec<(Rule, String)> {
+    let mut violations = vec![];
+
+    if let Some(max) = conf.no_block_for {
+        if sample.since_last_block >= max {
+            let elapsed = sample.since_last_block.as_secs();
+            violations.push((
+                Rule::NoBlockAccepted,
+                format!("no block accepted for {elapsed}s"),
+            ));
+        }
+    }
+
+    if let Some(min) = conf.min_peers {
+        if sample.peers < min {
+            violations.push((
+                Rule::LowPeerCount,
+                format!(
+                    "{} alive peers, expected at least {min}",
+                    sample.peers
+                ),
+            ));
+        }
+    }
+
+    if let Some(max) = conf.max_mempool_txs {
+        if sample.mempool_txs > max {
+            violations.push((
+                Rule::MempoolFull,
+                format!(
+                    "{} transactions in mempool, expected at most {max}",
+                    sample.mempool_txs
+                ),
+            ));
+        }
+    }
+
+    if let Some(max) = conf.max_consensus_restarts {
+        if sample.consensus_restarts > max {
+            violations.push((
+                Rule::ConsensusRestarts,
+                format!(
+                    "consensus restarted {} times within {:?}",
+                    sample.consensus_restarts, conf.restarts_window
+                ),
+            ));
+        }
+    }
+
+    if let Some(max) = conf.max_inclusion_time {
+        if sample.oldest_local_tx > max {
+            violations.push((
+                Rule::InclusionSla,
+                format!(
+                    "local transaction pending for {}s, expected at most {}s",
+                    sample.oldest_local_tx.as_secs(),
+                    max.as_secs()
+                ),
+            ));
+        } else if sample.local_tx_inclusion_p90 > max {
+            violations.push((
+                Rule::InclusionSla,
+                format!(
+                    "local transactions included in {}s (p90), expected at \
+                     most {}s",
+                    sample.local_tx_inclusion_p90.as_secs(),
+                    max.as_secs()
+                ),
+            ));
+        }
+    }
+
+    violations
+}
+
+pub struct AlertsSrv {
+    conf: Params,
+    client: reqwest::Client,
+    /// Firing alerts, along with the time of their last notification
+    firing: HashMap<Rule, Instant>,
+    /// Last seen tip height, along with the time it was first seen
+    last_tip: Option<(u64, Instant)>,
+    /// Total consensus restarts, sampled over the restarts window
+    restarts: VecDeque<(Instant, u64)>,
+}
+
+impl AlertsSrv {
+    pub fn new(conf: Params) -> Self {
+        info!("AlertsSrv::new with conf {:?}", conf);
+        let client = reqwest::Client::builder()
+            .timeout(conf.webhook_timeout)
+            .build()
+            .unwrap_or_default();
+        Self {
+            conf,
+            client,
+            firing: HashMap::new(),
+            last_tip: None,
+            restarts: VecDeque::new(),
+        }
+    }
+
+    async fn sample<N: Network, DB: database::DB>(
+        &mut self,
+        network: &Arc<RwLock<N>>,
+        db: &Arc<RwLock<DB>>,
+    ) -> anyhow::Result<Sample> {
+        let now = Instant::now();
+
+        let (tip_height, mempool_txs) = db.read().await.view(|t| {
+            let height = match t.op_read(MD_HASH_KEY)? {
+                Some(hash) => t.block_header(&hash)?.map(|h| h.height),
+                None => None,
+            };
+            anyhow::Ok((height, t.mempool_txs_count()))
+        })?;
+
+        let since_last_block = match (self.last_tip, tip_height) {
+            (Some((height, since)), Some(tip)) if height == tip => {
+                now.duration_since(since)
+            }
+            (_, Some(tip)) => {
+                self.last_tip = Some((tip, now));
+                Duration::ZERO
+            }
+            (_, None) => Duration::ZERO,
+        };
+
+        let total = consensus_health().await.restarts;
+        self.restarts.push_back((now, total));
+        // Keep the newest sample older than the window as the baseline
+        while self.restarts.len() > 1
+            && now.duration_since(self.restarts[1].0)
+                >= self.conf.restarts_window
+        {
+            self.restarts.pop_front();
+        }
+        let baseline = self.restarts.front().map_or(total, |(_, r)| *r);
+
+        let inclusion = inclusion_stats();
+
+        Ok(Sample {
+            since_last_block,
+            peers: network.read().await.alive_nodes_count().await,
+            mempool_txs,
+            consensus_restarts: total - baseline,
+            oldest_local_tx: inclusion.oldest_pending.unwrap_or_default(),
+            local_tx_inclusion_p90: inclusion.p90.unwrap_or_default(),
+        })
+    }
+
+    async fn check(&mut self, sample: &Sample) {
+        let violations: HashMap<_, _> =
+            evaluate(&self.conf, sample).into_iter().collect();
+
+        for (rule, message) in &violations {
+            let notify = self.firing.get(rule).map_or(true, |last| {
+                last.elapsed() >= self.conf.repeat_interval
+            });
+            if notify {
+                self.firing.insert(*rule, Instant::now());
+                self.notify(*rule, AlertState::Firing, message).await;
+            }
+        }
+
+        let resolved: Vec<_> = self
+            .firing
+            .keys()
+            .filter(|rule| !violations.contains_key(rule))
+            .copied()
+            .collect();
+        for rule in resolved {
+            self.firing.remove(&rule);
+            self.notify(rule, AlertState::Resolved, "alert resolved")
+                .await;
+        }
+
+        gauge!("dusk_alerts_firing").set(self.firing.len() as f64);
+    }
+
+    async fn notify(&self, rule: Rule, state: AlertState, message: &str) {
+        match state {
+            AlertState::Firing => warn!(event = "alert", ?rule, message),
+            AlertState::Resolved => info!(event = "alert", ?rule, message),
+        }
+
+        let Some(url) = &self.conf.webhook else {
+            return;
+        };
+        let notification = Notification {
+            rule,
+            state,
+            message,
+            timestamp: get_current_timestamp(),
+        };
+        let res = self
+            .client
+            .post(url)
+            .json(&notification)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status());
+        if let Err(err) = res {
+            warn!(event = "alert webhook failed", ?rule, %err);
+        }
+    }
+}
+
+#[async_trait]
+impl<N: Network, DB: database::DB, VM: vm::VMExecution>
+    LongLivedService<N, DB, VM> for AlertsSrv
+{
+    async fn execute(
+        &mut self,
+        network: Arc<RwLock<N>>,
+        db: Arc<RwLock<DB>>,
+        _: Arc<RwLock<VM>>,
+    ) -> anyhow::Result<usize> {
+        let Some(interval) = self.conf.check_interval else {
+            return Ok(0);
+        };
+
+        loop {
+            match self.sample(&network, &db).await {
+                Ok(sample) => self.check(&sample).await,
+                Err(err) => warn!(event = "alerts sampling failed", ?err),
+            }
+            sleep(interval).await;
+        }
+    }
+
+    /// Returns service name.
+    fn name(&self) -> &'static str {
+        "alerts"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(conf: &Params, sample: &Sample) -> Vec<Rule> {
+        evaluate(conf, sample).into_iter().map(|(r, _)| r).collect()
+    }
+
+    #[test]
+    fn test_evaluate_rules() {
+        let conf = Params {
+            no_block_for: Some(Duration::from_secs(60)),
+            min_peers: Some(3),
+            max_mempool_txs: Some(100),
+            max_consensus_restarts: Some(2),
+            max_inclusion_time: Some(Duration::from_secs(30)),
+            ..Default::default()
+        };
+
+        let healthy = Sample {
+            since_last_block: Duration::from_secs(59),
+            peers: 3,
+            mempool_txs: 100,
+            consensus_restarts: 2,
+            oldest_local_tx: Duration::from_secs(30),
+            local_tx_inclusion_p90: Duration::from_secs(30),
+        };
+        assert!(rules(&conf, &healthy).is_empty());
+
+        let unhealthy = Sample {
+            since_last_block: Duration::from_secs(60),
+            peers: 2,
+            mempool_txs: 101,
+            consensus_restarts: 3,
+            oldest_local_tx: Duration::from_secs(31),
+            local_tx_inclusion_p90: Duration::ZERO,
+        };
+        assert_eq!(
+            rules(&conf, &unhealthy),
+            vec![
+                Rule::NoBlockAccepted,
+                Rule::LowPeerCount,
+                Rule::MempoolFull,
+                Rule::ConsensusRestarts,
+                Rule::InclusionSla,
+            ]
+        );
+
+        // Slow inclusions breach the SLA, even with no pending transaction
+        let slow = Sample {
+            local_tx_inclusion_p90: Duration::from_secs(31),
+            ..healthy
+        };
+        assert_eq!(rules(&conf, &slow), vec![Rule::InclusionSla]);
+
+        // Rules without a threshold are disabled
+        let conf = Params {
+            no_block_for: None,
+            ..Default::default()
+        };
+        assert!(rules(&conf, &unhealthy).is_empty());
+    }
+}
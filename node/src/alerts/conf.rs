@@ -0,0 +1,76 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+/// Alerts configuration parameters
+pub const DEFAULT_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+pub const DEFAULT_NO_BLOCK_FOR: Duration = Duration::from_secs(5 * 60);
+pub const DEFAULT_RESTARTS_WINDOW: Duration = Duration::from_secs(10 * 60);
+pub const DEFAULT_REPEAT_INTERVAL: Duration = Duration::from_secs(30 * 60);
+pub const DEFAULT_WEBHOOK_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct Params {
+    /// Interval between two evaluations of the rules. Set to `None` to
+    /// disable the alerts
+    #[serde(with = "humantime_serde")]
+    pub check_interval: Option<Duration>,
+
+    /// Alert if no block is accepted for this long
+    #[serde(with = "humantime_serde")]
+    pub no_block_for: Option<Duration>,
+
+    /// Alert if the number of alive peers is below this value
+    pub min_peers: Option<usize>,
+
+    /// Alert if the number of transactions in the mempool is above this value
+    pub max_mempool_txs: Option<usize>,
+
+    /// Alert if the consensus task is restarted more than this many times
+    /// within `restarts_window`
+    pub max_consensus_restarts: Option<u64>,
+
+    #[serde(with = "humantime_serde")]
+    pub restarts_window: Duration,
+
+    /// Alert if a transaction submitted locally waits longer than this to be
+    /// included in a block, or if the 90th percentile of the recent
+    /// inclusion latencies exceeds it
+    #[serde(with = "humantime_serde")]
+    pub max_inclusion_time: Option<Duration>,
+
+    /// Interval between two notifications of an alert that keeps firing
+    #[serde(with = "humantime_serde")]
+    pub repeat_interval: Duration,
+
+    /// URL the notifications are POSTed to, as JSON. Notifications are always
+    /// logged
+    pub webhook: Option<String>,
+
+    #[serde(with = "humantime_serde")]
+    pub webhook_timeout: Duration,
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Self {
+            check_interval: Some(DEFAULT_CHECK_INTERVAL),
+            no_block_for: Some(DEFAULT_NO_BLOCK_FOR),
+            min_peers: None,
+            max_mempool_txs: None,
+            max_consensus_restarts: None,
+            restarts_window: DEFAULT_RESTARTS_WINDOW,
+            max_inclusion_time: None,
+            repeat_interval: DEFAULT_REPEAT_INTERVAL,
+            webhook: None,
+            webhook_timeout: DEFAULT_WEBHOOK_TIMEOUT,
+        }
+    }
+}
@@ -12,11 +12,13 @@ use rocksdb::OptimisticTransactionDB;
 use sqlx::sqlite::SqlitePool;
 use tracing::debug;
 
+pub mod conf;
 mod moonlight;
 mod sqlite;
 mod transformer;
 
 pub use moonlight::{MoonlightGroup, Order};
+pub use sqlite::data::ContractEventRecord;
 
 // Archive folder containing the sqlite database and the moonlight database
 const ARCHIVE_FOLDER_NAME: &str = "archive";
@@ -36,6 +38,8 @@ pub struct Archive {
     moonlight_db: Arc<OptimisticTransactionDB>,
     // last finalized block height known to the archive
     last_finalized_block_height: u64,
+    // The configuration of the archive
+    conf: conf::Params,
 }
 
 impl Archive {
@@ -65,6 +69,7 @@ impl Archive {
             sqlite_archive,
             moonlight_db,
             last_finalized_block_height: 0,
+            conf: conf::Params::default(),
         };
 
         let last_finalized_block_height = match self_archive
@@ -85,6 +90,12 @@ impl Archive {
         self_archive
     }
 
+    /// Sets the configuration of the archive
+    pub fn with_conf(mut self, conf: conf::Params) -> Self {
+        self.conf = conf;
+        self
+    }
+
     /// Returns the last finalized block height cached in the archive.
     ///
     /// # Note
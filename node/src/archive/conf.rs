@@ -0,0 +1,29 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+/// Archive configuration parameters
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct Params {
+    /// Retention of the finalized events, per contract. The events of the
+    /// contracts not listed are kept forever
+    pub event_retention: Vec<EventRetention>,
+}
+
+/// How long the finalized events of a contract are kept
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct EventRetention {
+    /// Hex encoded id of the contract
+    pub contract: String,
+
+    /// Minimum age of the events to remove
+    #[serde(with = "humantime_serde")]
+    pub keep: Duration,
+}
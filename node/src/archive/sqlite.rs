@@ -7,10 +7,11 @@
 use std::path::Path;
 
 use anyhow::Result;
+use dusk_consensus::config::MINIMUM_BLOCK_TIME;
 use node_data::events::contract::ContractTxEvent;
 use node_data::ledger::Hash;
 use sqlx::sqlite::{SqliteConnectOptions, SqlitePool};
-use sqlx::{Pool, Sqlite};
+use sqlx::{Pool, QueryBuilder, Sqlite};
 use tracing::{error, info, warn};
 
 use crate::archive::transformer;
@@ -19,6 +20,9 @@ use crate::archive::Archive;
 /// The name of the archive SQLite database.
 const SQLITEARCHIVE_DB_NAME: &str = "archive.sqlite3";
 
+/// Maximum number of events returned by `get_contract_events`.
+pub const MAX_CONTRACT_EVENTS: usize = 10_000;
+
 impl Archive {
     /// Create or open the SQLite database.
     ///
@@ -148,6 +152,85 @@ impl Archive {
         Ok(records)
     }
 
+    /// Get the finalized events of a contract with a block height in
+    /// `from..=to`, in the order they were emitted.
+    ///
+    /// If `topics` is not empty, only the events with one of the given topics
+    /// are returned. At most [`MAX_CONTRACT_EVENTS`] events are returned.
+    pub async fn get_contract_events(
+        &self,
+        contract_id: &str,
+        from: u64,
+        to: u64,
+        topics: &[String],
+    ) -> Result<Vec<data::ContractEventRecord>> {
+        let mut conn = self.sqlite_archive.acquire().await?;
+
+        let mut query = QueryBuilder::<Sqlite>::new(
+            "SELECT block_height, origin, topic, source, data FROM finalized_events WHERE source = ",
+        );
+        query
+            .push_bind(contract_id.to_lowercase())
+            .push(" AND block_height BETWEEN ")
+            .push_bind(from as i64)
+            .push(" AND ")
+            .push_bind(to as i64);
+
+        if !topics.is_empty() {
+            query.push(" AND topic IN (");
+            let mut separated = query.separated(", ");
+            for topic in topics {
+                separated.push_bind(topic);
+            }
+            separated.push_unseparated(")");
+        }
+
+        query
+            .push(" ORDER BY id LIMIT ")
+            .push_bind(MAX_CONTRACT_EVENTS as i64);
+
+        let records = query
+            .build_query_as::<data::ContractEventRecord>()
+            .fetch_all(&mut *conn)
+            .await?;
+
+        Ok(records)
+    }
+
+    /// Remove the finalized events past the retention of their contract.
+    ///
+    /// The retention is converted to a number of blocks with the minimum block
+    /// time, so that the events are kept at least as long as configured.
+    async fn prune_contract_events(&self, finalized_height: u64) -> Result<()> {
+        let mut tx = self.sqlite_archive.begin().await?;
+
+        for retention in &self.conf.event_retention {
+            let keep_blocks = retention.keep.as_secs() / *MINIMUM_BLOCK_TIME;
+            let Some(below) = finalized_height.checked_sub(keep_blocks) else {
+                continue;
+            };
+
+            let pruned = sqlx::query(
+                "DELETE FROM finalized_events WHERE source = ? AND block_height < ?",
+            )
+            .bind(retention.contract.to_lowercase())
+            .bind(below as i64)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+
+            if pruned > 0 {
+                info!(
+                    "Pruned {pruned} events of contract {} below height {below}",
+                    util::truncate_string(&retention.contract)
+                );
+            }
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
     /// Fetch all unfinalized vm events from a given block hash
     pub async fn fetch_unfinalized_events_by_hash(
         &self,
@@ -305,7 +388,7 @@ impl Archive {
         1. Any PhoenixTransactionEvent (through notes & refund_note)
         */
         let phoenix_event_present = events.iter().any(|event| {
-            event.event.target == dusk_core::transfer::TRANSFER_CONTRACT
+            dusk_core::abi::is_transfer_contract(&event.event.target)
                 && event.event.topic == dusk_core::transfer::PHOENIX_TOPIC
         });
 
@@ -370,6 +453,13 @@ impl Archive {
 
         self.last_finalized_block_height = finalized_block_height as u64;
 
+        if let Err(e) = self
+            .prune_contract_events(finalized_block_height as u64)
+            .await
+        {
+            warn!("Failed to prune contract events: {e}");
+        }
+
         Ok(())
     }
 
@@ -420,7 +510,7 @@ impl Archive {
     }
 }
 
-mod data {
+pub(super) mod data {
     use dusk_core::abi::{ContractId, CONTRACT_ID_BYTES};
     use node_data::events::contract::{
         ContractEvent, ContractTxEvent, ORIGIN_HASH_BYTES,
@@ -474,6 +564,18 @@ mod data {
         pub data: Vec<u8>,
     }
 
+    /// Archived ContractTxEvent, along with the height of its block
+    #[serde_with::serde_as]
+    #[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+    pub struct ContractEventRecord {
+        pub block_height: i64,
+        pub origin: String,
+        pub topic: String,
+        pub source: String,
+        #[serde_as(as = "serde_with::hex::Hex")]
+        pub data: Vec<u8>,
+    }
+
     impl TryFrom<ArchivedEvent> for ContractTxEvent {
         type Error = anyhow::Error;
 
@@ -528,6 +630,7 @@ mod util {
 mod tests {
     use std::env;
     use std::path::PathBuf;
+    use std::time::Duration;
 
     use dusk_core::abi::ContractId;
     use node_data::events::contract::ContractEvent;
@@ -536,6 +639,7 @@ mod tests {
     use util::truncate_string;
 
     use super::*;
+    use crate::archive::conf;
 
     #[test]
     fn test_truncate_string() {
@@ -669,4 +773,74 @@ mod tests {
         assert_eq!(blk_height, 1);
         assert_eq!(blk_hash, hex_blk_hash);
     }
+
+    #[tokio::test]
+    async fn test_contract_events() {
+        let pruned = ContractId::from_bytes([0; 32]).to_string();
+        let contract = ContractId::from_bytes([1; 32]).to_string();
+        let conf = conf::Params {
+            event_retention: vec![conf::EventRetention {
+                contract: pruned.clone(),
+                keep: Duration::ZERO,
+            }],
+        };
+        let mut archive =
+            Archive::create_or_open(test_dir()).await.with_conf(conf);
+
+        for height in 1..=2 {
+            archive
+                .store_unfinalized_events(
+                    height,
+                    [height as u8; 32],
+                    dummy_data(),
+                )
+                .await
+                .unwrap();
+        }
+        archive
+            .finalize_archive_data(1, &hex::encode([1u8; 32]))
+            .await
+            .unwrap();
+
+        let events = archive
+            .get_contract_events(&contract, 0, 10, &[])
+            .await
+            .unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].block_height, 1);
+        assert_eq!(events[0].topic, "contract2");
+        assert!(archive
+            .get_contract_events(&contract, 0, 10, &["other".to_string()])
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(archive
+            .get_contract_events(&contract, 2, 10, &[])
+            .await
+            .unwrap()
+            .is_empty());
+
+        // The events of the first block are past the retention once the
+        // second one is finalized
+        archive
+            .finalize_archive_data(2, &hex::encode([2u8; 32]))
+            .await
+            .unwrap();
+
+        let topics = ["contract1".to_string()];
+        let events = archive
+            .get_contract_events(&pruned, 0, 10, &topics)
+            .await
+            .unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].block_height, 2);
+        assert_eq!(
+            archive
+                .get_contract_events(&contract, 0, 10, &[])
+                .await
+                .unwrap()
+                .len(),
+            2
+        );
+    }
 }
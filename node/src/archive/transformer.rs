@@ -6,12 +6,14 @@
 
 use std::collections::BTreeMap;
 
+use dusk_core::abi::is_transfer_contract;
 use dusk_core::signatures::bls::PublicKey as AccountPublicKey;
+use dusk_core::transfer::sponsor::SponsorEvent;
 use dusk_core::transfer::withdraw::WithdrawReceiver;
 use dusk_core::transfer::{
     ContractToAccountEvent, ConvertEvent, MoonlightTransactionEvent,
     WithdrawEvent, CONTRACT_TO_ACCOUNT_TOPIC, CONVERT_TOPIC, MINT_TOPIC,
-    MOONLIGHT_TOPIC, TRANSFER_CONTRACT, WITHDRAW_TOPIC,
+    MOONLIGHT_TOPIC, SPONSOR_TOPIC, WITHDRAW_TOPIC,
 };
 use node_data::events::contract::{ContractEvent, ContractTxEvent, OriginHash};
 use serde::{Deserialize, Serialize};
@@ -162,6 +164,10 @@ pub(super) fn filter_and_convert(
 ///   - `CONVERT_TOPIC` with `ConvertEvent`: Receiver recorded in inflow
 ///     mapping.
 ///
+/// - **Sponsored Moonlight Transaction**
+///   - `SPONSOR_TOPIC` with `SponsorEvent`: Sponsor recorded in outflow
+///     mapping.
+///
 /// - **Moonlight or Phoenix Transaction**
 ///   - `WITHDRAW_TOPIC` with `WithdrawEvent`: Receiver recorded in inflow
 ///     mapping.
@@ -201,7 +207,7 @@ fn record_flows(
         .filter(|event| {
             // Make sure that the events originate from the transfer
             // contract.
-            if event.target != TRANSFER_CONTRACT {
+            if !is_transfer_contract(&event.target) {
                 return false;
             }
 
@@ -267,6 +273,17 @@ fn record_flows(
 
                     true
                 }
+                SPONSOR_TOPIC => {
+                    let Ok(sponsor_event) =
+                        rkyv::from_bytes::<SponsorEvent>(&event.data)
+                    else {
+                        return false;
+                    };
+
+                    handle_outflow(sponsor_event.sponsor);
+
+                    true
+                }
                 CONVERT_TOPIC => {
                     let Ok(convert_event) =
                         rkyv::from_bytes::<ConvertEvent>(&event.data)
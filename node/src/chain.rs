@@ -5,39 +5,82 @@
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
 mod acceptor;
+mod admin;
+mod anti_entropy;
+mod candidate_pins;
+pub mod conf;
 mod consensus;
+pub mod consensus_events;
+mod divergence;
 mod fallback;
+pub mod fast_sync;
+mod fee_estimator;
+mod finalization;
+mod fork_choice;
 mod fsm;
+mod future_blocks;
 mod genesis;
+mod hash_chain;
 
 mod header_validation;
+mod health;
+mod keystore;
+mod last_iter;
 mod metrics;
+mod participation;
+mod provisioners_cache;
+pub mod replay;
+mod seats;
+mod stale_tip;
 
 use std::ops::Deref;
 use std::sync::Arc;
 use std::time::Duration;
 
+pub use admin::LogReload;
 use anyhow::Result;
 use async_trait::async_trait;
+pub use divergence::{ExecutedTx, StateDivergence};
 use dusk_consensus::config::is_emergency_block;
 use dusk_consensus::errors::ConsensusError;
 use dusk_core::signatures::bls::PublicKey as BlsPublicKey;
-pub use header_validation::verify_att;
+pub use fee_estimator::estimate_gas_price;
+pub use hash_chain::{verify_hash_chain, verify_headers, HashChainError};
+pub use header_validation::{
+    median_time_past, verify_att, MEDIAN_TIME_PAST_SPAN,
+};
+pub use health::{consensus_health, ConsensusHealth};
 use node_data::events::Event;
+use node_data::get_current_timestamp;
 use node_data::ledger::{to_str, BlockWithLabel, Label};
-use node_data::message::payload::RatificationResult;
-use node_data::message::{AsyncQueue, Payload, Topics};
+use node_data::message::payload::{
+    AdminAction, AdminCommand, RatificationResult,
+};
+use node_data::message::{self, AsyncQueue, OverflowPolicy, Payload, Topics};
+pub use participation::{
+    participation, participations, withholding_evidence, Participation,
+    WithholdingEvidence,
+};
+pub use seats::{epoch_seats, round_seats, EpochSeats, StepSeats, StepTally};
+use tokio::signal::unix::{signal, SignalKind};
 use tokio::sync::mpsc::Sender;
 use tokio::sync::RwLock;
 use tokio::time::{sleep_until, Instant};
 use tracing::{debug, error, info, warn};
 
 use self::acceptor::Acceptor;
+use self::admin::AdminGuard;
+use self::anti_entropy::AntiEntropy;
+use self::consensus_events::ConsensusEvents;
 use self::fsm::SimpleFSM;
+use self::keystore::ConsensusKeys;
+use self::stale_tip::StaleTipRecovery;
 #[cfg(feature = "archive")]
 use crate::archive::Archive;
-use crate::database::rocksdb::MD_HASH_KEY;
+use crate::database::rocksdb::{MD_ADMIN_TIMESTAMP, MD_HASH_KEY};
 use crate::database::{Ledger, Metadata};
+use crate::mempool::policy::TxPolicy;
+use crate::network::reputation;
 use crate::{database, vm, LongLivedService, Message, Network};
 
 const TOPICS: &[u8] = &[
@@ -47,6 +90,7 @@ const TOPICS: &[u8] = &[
     Topics::Ratification as u8,
     Topics::Quorum as u8,
     Topics::ValidationQuorum as u8,
+    Topics::Admin as u8,
 ];
 
 const HEARTBEAT_SEC: Duration = Duration::from_secs(3);
@@ -62,6 +106,10 @@ pub struct ChainSrv<N: Network, DB: database::DB, VM: vm::VMExecution> {
     genesis_timestamp: u64,
     dusk_key: BlsPublicKey,
     finality_activation: u64,
+    conf: conf::Params,
+    tx_policy: TxPolicy,
+    log_reload: Option<LogReload>,
+    consensus_events: ConsensusEvents,
     #[cfg(feature = "archive")]
     archive: Archive,
 }
@@ -83,9 +131,14 @@ impl<N: Network, DB: database::DB, VM: vm::VMExecution>
         )
         .await?;
 
+        db.read().await.view(|t| reputation::load(t))?;
+        db.read().await.view(|t| participation::load(t))?;
+
+        let keys = ConsensusKeys::unlock_from_env(&self.keys_path)?;
+
         // Initialize Acceptor
         let acc = Acceptor::init_consensus(
-            &self.keys_path,
+            keys,
             tip,
             db,
             network,
@@ -96,6 +149,10 @@ impl<N: Network, DB: database::DB, VM: vm::VMExecution>
             self.event_sender.clone(),
             self.dusk_key,
             self.finality_activation,
+            self.tx_policy.clone(),
+            self.conf.extra_data(),
+            self.conf.memory,
+            self.consensus_events.clone(),
         )
         .await?;
 
@@ -107,7 +164,7 @@ impl<N: Network, DB: database::DB, VM: vm::VMExecution>
     async fn execute(
         &mut self,
         network: Arc<RwLock<N>>,
-        _db: Arc<RwLock<DB>>,
+        db: Arc<RwLock<DB>>,
         _vm: Arc<RwLock<VM>>,
     ) -> anyhow::Result<usize> {
         // Register routes
@@ -124,10 +181,34 @@ impl<N: Network, DB: database::DB, VM: vm::VMExecution>
 
         // Start-up FSM instance
         let mut fsm = SimpleFSM::new(acc.clone(), network.clone()).await;
+        let mut anti_entropy = AntiEntropy::new(
+            acc.clone(),
+            network.clone(),
+            self.conf.anti_entropy,
+        );
+        let mut stale_tip = StaleTipRecovery::new(
+            acc.clone(),
+            network.clone(),
+            self.conf.stale_tip,
+        )
+        .await;
 
         let outbound_chan = acc.read().await.get_outbound_chan().await;
         let result_chan = acc.read().await.get_result_chan().await;
 
+        let last_admin_timestamp = db
+            .read()
+            .await
+            .view(|t| t.op_read(MD_ADMIN_TIMESTAMP))?
+            .and_then(|bytes| bytes.try_into().ok())
+            .map(u64::from_le_bytes)
+            .unwrap_or_default();
+        let mut admin =
+            AdminGuard::new(self.conf.admin.clone(), last_admin_timestamp);
+
+        // SIGHUP being the network restart, the keys are reloaded on SIGUSR1
+        let mut reload_keys = signal(SignalKind::user_defined1())?;
+
         let mut heartbeat = Instant::now().checked_add(HEARTBEAT_SEC).unwrap();
 
         // Message loop for Chain context
@@ -161,6 +242,7 @@ impl<N: Network, DB: database::DB, VM: vm::VMExecution>
                         }
 
                         Payload::Quorum(ref q) => {
+                            stale_tip.on_remote_quorum(q).await;
                             fsm.on_quorum(q, msg.metadata.as_ref()).await;
                             self.reroute_acceptor(msg).await;
 
@@ -175,6 +257,7 @@ impl<N: Network, DB: database::DB, VM: vm::VMExecution>
                                 hash = to_str(&blk.header().hash),
                                 metadata = ?msg.metadata,
                             );
+                            stale_tip.on_remote_block(blk.header()).await;
 
                             // Handle a block that originates from a network peer.
                             // By disabling block broadcast, a block may be received
@@ -202,6 +285,10 @@ impl<N: Network, DB: database::DB, VM: vm::VMExecution>
                             }
                         }
 
+                        Payload::AdminCommand(cmd) => {
+                            self.on_admin_command(&db, &mut admin, &cmd, msg.metadata.as_ref()).await;
+                        }
+
                         _ => {
                             warn!("invalid inbound message");
                         },
@@ -229,12 +316,31 @@ impl<N: Network, DB: database::DB, VM: vm::VMExecution>
                             warn!("Unable to broadcast message {e}");
                     }
 
+                },
+                // Reloads the consensus keys on SIGUSR1
+                Some(_) = reload_keys.recv() => {
+                    self.reload_consensus_keys().await;
                 },
                  // Handles heartbeat event
                 _ = sleep_until(heartbeat) => {
                     if let Err(err) = fsm.on_heartbeat_event().await {
                         error!(event = "heartbeat_failed", ?err);
                     }
+                    anti_entropy.on_heartbeat().await;
+                    stale_tip.on_heartbeat(&anti_entropy).await;
+                    if let Err(err) =
+                        db.read().await.update(|t| reputation::persist(t))
+                    {
+                        warn!(event = "peer reputation persist failed", ?err);
+                    }
+                    if let Err(err) =
+                        db.read().await.update(|t| participation::persist(t))
+                    {
+                        warn!(
+                            event = "withholding evidence persist failed",
+                            ?err
+                        );
+                    }
 
                     heartbeat = Instant::now().checked_add(HEARTBEAT_SEC).unwrap();
                 },
@@ -249,6 +355,7 @@ impl<N: Network, DB: database::DB, VM: vm::VMExecution>
 }
 
 impl<N: Network, DB: database::DB, VM: vm::VMExecution> ChainSrv<N, DB, VM> {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         keys_path: String,
         max_inbound_size: usize,
@@ -256,6 +363,7 @@ impl<N: Network, DB: database::DB, VM: vm::VMExecution> ChainSrv<N, DB, VM> {
         genesis_timestamp: u64,
         dusk_key: BlsPublicKey,
         finality_activation: u64,
+        conf: conf::Params,
         #[cfg(feature = "archive")] archive: Archive,
     ) -> Self {
         info!(
@@ -264,7 +372,11 @@ impl<N: Network, DB: database::DB, VM: vm::VMExecution> ChainSrv<N, DB, VM> {
         );
 
         Self {
-            inbound: AsyncQueue::bounded(max_inbound_size, "chain_inbound"),
+            inbound: AsyncQueue::bounded(max_inbound_size, "chain_inbound")
+                .with_memory_budget(
+                    conf.memory.chain_inbound,
+                    OverflowPolicy::DropFarthest,
+                ),
             keys_path,
             acceptor: None,
             max_consensus_queue_size: max_inbound_size,
@@ -272,11 +384,34 @@ impl<N: Network, DB: database::DB, VM: vm::VMExecution> ChainSrv<N, DB, VM> {
             genesis_timestamp,
             dusk_key,
             finality_activation,
+            conf,
+            tx_policy: TxPolicy::default(),
+            log_reload: None,
+            consensus_events: ConsensusEvents::default(),
             #[cfg(feature = "archive")]
             archive,
         }
     }
 
+    /// Sets the policy of the transactions excluded from the candidates
+    pub fn with_tx_policy(mut self, tx_policy: TxPolicy) -> Self {
+        self.tx_policy = tx_policy;
+        self
+    }
+
+    /// Sets the reload of the log filter, enabling the admin command
+    /// replacing it
+    pub fn with_log_reload(mut self, log_reload: LogReload) -> Self {
+        self.log_reload = Some(log_reload);
+        self
+    }
+
+    /// Sets the channel the consensus step events are published on
+    pub fn with_consensus_events(mut self, events: ConsensusEvents) -> Self {
+        self.consensus_events = events;
+        self
+    }
+
     /// Load both the chain tip and last finalized block from persisted ledger.
     ///
     /// Panics
@@ -345,6 +480,86 @@ impl<N: Network, DB: database::DB, VM: vm::VMExecution> ChainSrv<N, DB, VM> {
             .await
     }
 
+    /// Dispatches an admin command, if authenticated by `admin`.
+    ///
+    /// Rejected commands are not penalized, since the peer they are received
+    /// from may only be relaying them.
+    async fn on_admin_command(
+        &self,
+        db: &Arc<RwLock<DB>>,
+        admin: &mut AdminGuard,
+        cmd: &AdminCommand,
+        metadata: Option<&message::Metadata>,
+    ) {
+        let src = metadata.map(|md| md.src_addr);
+        if let Err(err) = admin.check(cmd, get_current_timestamp()) {
+            warn!(event = "admin command rejected", ?cmd.action, ?src, %err);
+            return;
+        }
+
+        // Persist the timestamp of the command before dispatching it, so that
+        // it can't be replayed after a restart
+        let timestamp = admin.last_timestamp().to_le_bytes();
+        if let Err(err) = db
+            .read()
+            .await
+            .update(|t| t.op_write(MD_ADMIN_TIMESTAMP, timestamp))
+        {
+            error!(event = "admin command not persisted", ?err);
+            return;
+        }
+
+        info!(event = "admin command accepted", ?cmd.action, ?src);
+        match &cmd.action {
+            AdminAction::Revert => {
+                if let Err(err) = self.revert_last_final().await {
+                    error!(event = "admin revert failed", ?err);
+                }
+            }
+            AdminAction::Ban(ip) => reputation::penalize(
+                *ip,
+                reputation::BAN_THRESHOLD,
+                "banned by the operator",
+            ),
+            AdminAction::Unban(ip) => reputation::forgive(ip),
+            AdminAction::ReloadConsensusKeys => {
+                self.reload_consensus_keys().await
+            }
+            AdminAction::SetLogFilter(filter) => match &self.log_reload {
+                Some(reload) => match reload(filter) {
+                    Ok(()) => info!(event = "log filter replaced", %filter),
+                    Err(err) => {
+                        error!(event = "log filter rejected", %filter, ?err)
+                    }
+                },
+                None => warn!(event = "log filter not reloadable", %filter),
+            },
+        }
+    }
+
+    /// Reloads the consensus keys, signing with them from the next round on.
+    ///
+    /// The keys are unlocked on a blocking task, their key derivation being
+    /// expensive. The loaded keys are kept if the reloaded ones can't be
+    /// loaded.
+    async fn reload_consensus_keys(&self) {
+        info!(event = "consensus keys reload requested");
+        let keys_path = self.keys_path.clone();
+        let unlocked = tokio::task::spawn_blocking(move || {
+            ConsensusKeys::unlock_from_env(&keys_path)
+        })
+        .await
+        .map_err(anyhow::Error::from)
+        .and_then(|keys| keys);
+        match unlocked {
+            Ok(keys) => {
+                let acc = self.acceptor.as_ref().expect("initialize is called");
+                acc.read().await.reload_consensus_keys(keys).await;
+            }
+            Err(err) => error!(event = "consensus keys reload failed", ?err),
+        }
+    }
+
     async fn reroute_acceptor(&self, msg: Message) {
         debug!(
             event = "Consensus message received",
@@ -13,43 +13,67 @@ use std::{cmp, env};
 use anyhow::{anyhow, Result};
 use dusk_consensus::commons::TimeoutSet;
 use dusk_consensus::config::{
-    is_emergency_block, CONSENSUS_MAX_ITER, MAX_ROUND_DISTANCE,
+    is_emergency_block, CONSENSUS_MAX_ITER, MAX_BLOCK_SIZE, MAX_ROUND_DISTANCE,
     MAX_STEP_TIMEOUT, MIN_STEP_TIMEOUT,
 };
 use dusk_consensus::errors::{ConsensusError, HeaderError};
-use dusk_consensus::operations::Voter;
+use dusk_consensus::operations::{VerificationOutput, Voter};
 use dusk_consensus::user::provisioners::{ContextProvisioners, Provisioners};
 use dusk_consensus::user::stake::Stake;
+use dusk_core::abi::is_stake_contract;
 use dusk_core::signatures::bls;
-use dusk_core::stake::STAKE_CONTRACT;
-use dusk_core::stake::{SlashEvent, StakeAmount, StakeEvent};
+use dusk_core::stake::{
+    parse_stake_call, KeyRotation, KeyRotationEvent, SlashEvent, StakeAmount,
+    StakeCall, StakeEvent,
+};
 use metrics::{counter, gauge, histogram};
 use node_data::bls::PublicKey;
 use node_data::events::contract::ContractEvent;
 use node_data::events::{BlockEvent, BlockState, Event, TransactionEvent};
 use node_data::ledger::{
-    self, to_str, Block, BlockWithLabel, Label, Seed, Slash,
+    self, to_str, Block, BlockWithLabel, Label, Seed, Slash, SpentTransaction,
 };
 use node_data::message::payload::{GetBlocks, Vote};
 use node_data::message::{AsyncQueue, Payload, Status};
 use node_data::{get_current_timestamp, Serializable, StepName};
 use rkyv::{check_archived_root, Deserialize, Infallible};
 use tokio::sync::mpsc::Sender;
-use tokio::sync::{RwLock, RwLockReadGuard};
+use tokio::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
 use tracing::{debug, error, info, trace, warn};
 
 use super::consensus::Task;
+use super::keystore::ConsensusKeys;
 #[cfg(feature = "archive")]
 use crate::archive::Archive;
-use crate::chain::header_validation::{verify_att, verify_faults, Validator};
+use crate::chain::candidate_pins::CandidatePins;
+use crate::chain::conf::MemoryParams;
+use crate::chain::consensus_events::ConsensusEvents;
+use crate::chain::divergence::{
+    self, DivergenceRetries, ProvisionerDivergence, StateDivergence,
+    MAX_STATE_DIVERGENCES,
+};
+use crate::chain::fee_estimator;
+use crate::chain::finalization::Finalization;
+use crate::chain::hash_chain::verify_hash_chain;
+use crate::chain::header_validation::{
+    verify_att, verify_extra_data, verify_faults, verify_provisioners_root,
+    verify_tx_order, Validator,
+};
 use crate::chain::metrics::AverageElapsedTime;
+use crate::chain::participation;
+use crate::chain::provisioners_cache::ProvisionersCache;
 use crate::database::rocksdb::{
     Backend, MD_AVG_PROPOSAL, MD_AVG_RATIFICATION, MD_AVG_VALIDATION,
     MD_HASH_KEY, MD_STATE_ROOT_KEY,
 };
+use crate::database::round_history::ROUND_HISTORY_RETENTION;
 use crate::database::{
     self, ConsensusStorage, DatabaseOptions, Ledger, Mempool, Metadata, DB,
 };
+use crate::disk_guard::{self, DiskStatus};
+use crate::mempool::inclusion;
+use crate::mempool::policy::TxPolicy;
+use crate::network::contribution;
 use crate::{vm, Message, Network};
 
 const CANDIDATES_DELETION_OFFSET: u64 = 10;
@@ -88,6 +112,8 @@ pub(crate) struct Acceptor<N: Network, DB: database::DB, VM: vm::VMExecution> {
 
     /// Provisioners needed to verify next block
     pub(crate) provisioners_list: RwLock<ContextProvisioners>,
+    /// Provisioners of the recent state roots
+    provisioners_cache: ProvisionersCache,
 
     /// Upper layer consensus task
     task: RwLock<super::consensus::Task>,
@@ -97,6 +123,12 @@ pub(crate) struct Acceptor<N: Network, DB: database::DB, VM: vm::VMExecution> {
     pub(crate) network: Arc<RwLock<N>>,
     #[cfg(feature = "archive")]
     pub(crate) archive: Archive,
+    /// Pending finalization of the VM state
+    finalization: Finalization,
+    /// Consecutive state divergences, halting the acceptance once bounded
+    state_divergences: RwLock<DivergenceRetries>,
+    /// Candidates referenced by in-flight quorum processing
+    pub(crate) candidate_pins: CandidatePins,
     /// Sender channel for sending out RUES events
     event_sender: Sender<Event>,
 
@@ -121,6 +153,7 @@ enum ProvisionerChange {
     Unstake(StakeEvent),
     Slash(SlashEvent),
     HardSlash(SlashEvent),
+    Rotate(KeyRotationEvent),
 }
 
 fn stake_event(data: &[u8]) -> StakeEvent {
@@ -141,6 +174,68 @@ fn slash_event(data: &[u8]) -> SlashEvent {
     staking_event_data
 }
 
+fn rotation_event(data: &[u8]) -> KeyRotationEvent {
+    let rotation_event_data = check_archived_root::<KeyRotationEvent>(data)
+        .expect("Key rotation event data should deserialize correctly");
+    rotation_event_data
+        .deserialize(&mut Infallible)
+        .expect("Infallible")
+}
+
+/// Moves the stake of the provisioner `account` to its `next` account key
+fn rotate_member(
+    provisioners: &mut Provisioners,
+    account: &PublicKey,
+    next: PublicKey,
+) -> std::result::Result<(), &'static str> {
+    if provisioners.get_member(&next).is_some() {
+        return Err("Rotating to an existing stake");
+    }
+    let stake = provisioners
+        .remove_stake(account)
+        .ok_or("Rotating a not existing stake")?;
+    provisioners.add_member_with_stake(next, stake);
+    Ok(())
+}
+
+/// Returns the key rotations registered by the successful transactions of a
+/// block
+fn key_rotations(txs: &[SpentTransaction]) -> Vec<KeyRotation> {
+    txs.iter()
+        .filter(|tx| tx.err.is_none())
+        .filter_map(|tx| parse_stake_call(tx.inner.inner.call()?).ok()?)
+        .filter_map(|call| match call {
+            StakeCall::RotateKey(rotation) => Some(rotation),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Stores the Merkle root of the block hashes of the epoch finalized at
+/// `height`.
+///
+/// Epochs partially missing from the ledger are skipped.
+fn store_epoch_root<L: Ledger>(db: &mut L, height: u64) -> Result<()> {
+    let epoch = ledger::epoch_of(height);
+
+    let mut hashes = vec![];
+    for height in ledger::epoch_heights(epoch) {
+        match db.block_hash_by_height(height)? {
+            Some(hash) => hashes.push(hash),
+            None => {
+                debug!(event = "epoch root skipped", epoch, height);
+                return Ok(());
+            }
+        }
+    }
+
+    let root = ledger::epoch_root(&hashes);
+    db.store_epoch_root(epoch, &root)?;
+    info!(event = "epoch root stored", epoch, root = to_str(&root));
+
+    Ok(())
+}
+
 impl ProvisionerChange {
     pub fn from_event(event: &ContractEvent) -> Option<ProvisionerChange> {
         let event = match event.topic.as_str() {
@@ -150,6 +245,9 @@ impl ProvisionerChange {
             "hard_slash" => {
                 ProvisionerChange::HardSlash(slash_event(&event.data))
             }
+            "key_rotated" => {
+                ProvisionerChange::Rotate(rotation_event(&event.data))
+            }
             _ => return None,
         };
         Some(event)
@@ -161,6 +259,7 @@ impl ProvisionerChange {
             ProvisionerChange::Unstake(_) => "unstake",
             ProvisionerChange::Slash(_) => "slash",
             ProvisionerChange::HardSlash(_) => "hard_slash",
+            ProvisionerChange::Rotate(_) => "key_rotated",
         }
     }
 
@@ -170,6 +269,7 @@ impl ProvisionerChange {
             ProvisionerChange::Unstake(e) => &e.keys.account,
             ProvisionerChange::Slash(e) => &e.account,
             ProvisionerChange::HardSlash(e) => &e.account,
+            ProvisionerChange::Rotate(e) => &e.account,
         }
     }
 
@@ -177,12 +277,24 @@ impl ProvisionerChange {
         PublicKey::new(*self.key())
     }
 
+    /// Returns the keys of the provisioners changed, i.e. the next account
+    /// key along with the rotated one
+    fn changed_keys(&self) -> Vec<PublicKey> {
+        match &self {
+            ProvisionerChange::Rotate(e) => {
+                vec![PublicKey::new(e.account), PublicKey::new(e.next_account)]
+            }
+            _ => vec![self.to_public_key()],
+        }
+    }
+
     fn value(&self) -> u64 {
         match &self {
             ProvisionerChange::Stake(e) => e.value,
             ProvisionerChange::Unstake(e) => e.value,
             ProvisionerChange::Slash(e) => e.value,
             ProvisionerChange::HardSlash(e) => e.value,
+            ProvisionerChange::Rotate(_) => 0,
         }
     }
 }
@@ -196,7 +308,7 @@ impl<DB: database::DB, VM: vm::VMExecution, N: Network> Acceptor<N, DB, VM> {
     /// [Task].
     #[allow(clippy::too_many_arguments)]
     pub async fn init_consensus(
-        keys_path: &str,
+        keys: ConsensusKeys,
         tip: BlockWithLabel,
         db: Arc<RwLock<DB>>,
         network: Arc<RwLock<N>>,
@@ -206,35 +318,48 @@ impl<DB: database::DB, VM: vm::VMExecution, N: Network> Acceptor<N, DB, VM> {
         event_sender: Sender<Event>,
         dusk_key: bls::PublicKey,
         finality_activation: u64,
+        tx_policy: TxPolicy,
+        extra_data: Vec<u8>,
+        memory: MemoryParams,
+        consensus_events: ConsensusEvents,
     ) -> anyhow::Result<Self> {
         let tip_height = tip.inner().header().height;
         let tip_state_hash = tip.inner().header().state_hash;
-        let provisioners_list =
-            vm.read().await.get_provisioners(tip_state_hash)?;
-
-        let mut provisioners_list = ContextProvisioners::new(provisioners_list);
-
-        if tip.inner().header().height > 0 {
-            let changed_provisioners =
-                vm.read().await.get_changed_provisioners(tip_state_hash)?;
-            provisioners_list.apply_changes(changed_provisioners);
-        }
+        let provisioners_cache = ProvisionersCache::default();
+        let provisioners_list = {
+            let vm = vm.read().await;
+            if tip_height > 0 {
+                provisioners_cache.context(&*vm, tip_state_hash, None)?
+            } else {
+                ContextProvisioners::new(
+                    provisioners_cache.provisioners(&*vm, tip_state_hash)?,
+                )
+            }
+        };
 
         let mut acc = Self {
             tip: RwLock::new(tip),
             provisioners_list: RwLock::new(provisioners_list),
+            provisioners_cache,
             db: db.clone(),
             vm: vm.clone(),
             network: network.clone(),
             #[cfg(feature = "archive")]
             archive,
             task: RwLock::new(Task::new_with_keys(
-                keys_path.to_string(),
+                keys,
                 max_queue_size,
-            )?),
+                tx_policy,
+                extra_data,
+                memory,
+                consensus_events,
+            )),
             event_sender,
             dusk_key,
             finality_activation,
+            finalization: Finalization::default(),
+            state_divergences: RwLock::default(),
+            candidate_pins: CandidatePins::default(),
         };
 
         // NB. After restart, state_root returned by VM is always the last
@@ -286,6 +411,17 @@ impl<DB: database::DB, VM: vm::VMExecution, N: Network> Acceptor<N, DB, VM> {
             if tip_ext > tip_height {
                 info!("detected ext db at height {tip_ext}. Syncing local db starting from {tip_height}" );
 
+                db_ext.view(|t| {
+                    verify_hash_chain(t, tip_height, tip_ext, |height| {
+                        if height % 1000 == 0 || height == tip_ext {
+                            info!(
+                                event = "ext db hash chain verified",
+                                height, tip_ext
+                            );
+                        }
+                    })
+                })?;
+
                 for height in tip_height + 1..=tip_ext {
                     let blk = db_ext
                         .view(|db| db.block_by_height(height))?
@@ -570,23 +706,119 @@ impl<DB: database::DB, VM: vm::VMExecution, N: Network> Acceptor<N, DB, VM> {
         Ok(())
     }
 
+    /// Returns the keys of the provisioners changed by `stake_events`
+    fn changed_keys(stake_events: &[ContractEvent]) -> Vec<PublicKey> {
+        let mut keys: Vec<PublicKey> = stake_events
+            .iter()
+            .filter_map(ProvisionerChange::from_event)
+            .flat_map(|change| change.changed_keys())
+            .collect();
+        keys.sort();
+        keys.dedup();
+        keys
+    }
+
+    /// Reports a block whose execution diverged from the state committed to
+    /// by its header, and reverts to the last finalized state to recover
+    /// from any divergence of the blocks before it.
+    ///
+    /// The block acceptance is halted instead once the same block diverged
+    /// [`MAX_STATE_DIVERGENCES`] times in a row.
+    async fn on_state_divergence(&self, divergence: &StateDivergence) {
+        match divergence.report() {
+            Ok(path) => error!(
+                event = "state divergence",
+                %divergence,
+                report = %path.display()
+            ),
+            Err(e) => error!(
+                event = "state divergence",
+                %divergence,
+                "Unable to write divergence report: {e}"
+            ),
+        }
+
+        let height = divergence.block_height;
+        if self.state_divergences.write().await.record(height) {
+            error!(
+                event = "block acceptance halted",
+                height,
+                divergences = MAX_STATE_DIVERGENCES,
+            );
+            return;
+        }
+
+        if let Err(e) = self.try_revert(RevertTarget::LastFinalizedState).await
+        {
+            error!(event = "state divergence revert failed", ?e);
+        }
+    }
+
+    /// Recovers the provisioner list after a failed selective update.
+    ///
+    /// A [`ProvisionerDivergence`] is reported and only the divergent keys are
+    /// reconciled against the VM. Any other error, or a failed repair, results
+    /// in a full resync.
+    ///
+    /// Returns the recovered provisioners.
+    fn recover_provisioners(
+        vm: &VM,
+        state_hash: [u8; 32],
+        error: anyhow::Error,
+        current: &Provisioners,
+    ) -> Result<Provisioners> {
+        if let Some(divergence) = error.downcast_ref::<ProvisionerDivergence>()
+        {
+            match divergence.report() {
+                Ok(path) => warn!(
+                    event = "provisioners divergence",
+                    %divergence,
+                    report = %path.display()
+                ),
+                Err(e) => warn!(
+                    event = "provisioners divergence",
+                    %divergence,
+                    "Unable to write divergence report: {e}"
+                ),
+            }
+
+            let repaired =
+                divergence::repair(current, &divergence.changed_keys, |pk| {
+                    vm.get_provisioner(pk.inner())
+                });
+            match repaired {
+                Ok(new_prov) => {
+                    info!(
+                        event = "provisioners repaired",
+                        keys = divergence.changed_keys.len()
+                    );
+                    return Ok(new_prov);
+                }
+                Err(e) => warn!("Unable to repair provisioners: {e}"),
+            }
+        }
+
+        warn!("Resync provisioners due to {error:?}");
+        vm.get_provisioners(state_hash)
+    }
+
+    /// Applies the provisioner changes of `stake_events` to `current`.
+    ///
+    /// Returns the updated provisioners, or `None` if none changed.
     fn selective_update(
         block_height: u64,
         stake_events: &[ContractEvent],
-        provisioners_list: &mut tokio::sync::RwLockWriteGuard<
-            '_,
-            ContextProvisioners,
-        >,
-    ) -> Result<()> {
+        current: &Provisioners,
+    ) -> Result<Option<Provisioners>> {
         let src = "selective";
         let changed_prov: Vec<_> = stake_events
             .iter()
             .filter_map(ProvisionerChange::from_event)
             .collect();
         if changed_prov.is_empty() {
-            provisioners_list.remove_previous();
+            Ok(None)
         } else {
-            let mut new_prov = provisioners_list.current().clone();
+            let mut new_prov = current.clone();
             for change in changed_prov {
                 let account = change.to_public_key();
                 let value = change.value();
@@ -597,13 +829,16 @@ impl<DB: database::DB, VM: vm::VMExecution, N: Network> Acceptor<N, DB, VM> {
                     account = account.to_bs58(),
                     value
                 );
-                match &change {
+                let applied = match &change {
                     ProvisionerChange::Stake(stake_event) => {
                         match new_prov.get_member_mut(&account) {
-                            Some(stake) if stake.value() == 0 => anyhow::bail!(
-                                "Found an active stake with 0 amount"
-                            ),
-                            Some(stake) => stake.add(stake_event.value),
+                            Some(stake) if stake.value() == 0 => {
+                                Err("Found an active stake with 0 amount")
+                            }
+                            Some(stake) => {
+                                stake.add(stake_event.value);
+                                Ok(())
+                            }
                             None => {
                                 let amount = StakeAmount::new(
                                     stake_event.value,
@@ -614,32 +849,51 @@ impl<DB: database::DB, VM: vm::VMExecution, N: Network> Acceptor<N, DB, VM> {
                                     amount.eligibility,
                                 );
                                 new_prov.add_member_with_stake(account, stake);
+                                Ok(())
                             }
                         }
                     }
                     ProvisionerChange::Unstake(unstake_event) => {
                         let unstaked = unstake_event.value;
-                        new_prov.sub_stake(&account, unstaked).ok_or(
-                            anyhow::anyhow!("Unstake a not existing stake"),
-                        )?;
+                        new_prov
+                            .sub_stake(&account, unstaked)
+                            .map(|_| ())
+                            .ok_or("Unstake a not existing stake")
                     }
                     ProvisionerChange::Slash(slash_event)
                     | ProvisionerChange::HardSlash(slash_event) => {
-                        let to_slash = new_prov
-                            .get_member_mut(&account)
-                            .ok_or(anyhow::anyhow!(
-                                "Slashing a not existing stake"
-                            ))?;
-                        to_slash.subtract(slash_event.value);
-                        to_slash
-                            .change_eligibility(slash_event.next_eligibility);
+                        match new_prov.get_member_mut(&account) {
+                            Some(to_slash) => {
+                                to_slash.subtract(slash_event.value);
+                                to_slash.change_eligibility(
+                                    slash_event.next_eligibility,
+                                );
+                                Ok(())
+                            }
+                            None => Err("Slashing a not existing stake"),
+                        }
+                    }
+                    ProvisionerChange::Rotate(rotation) => {
+                        let next = PublicKey::new(rotation.next_account);
+                        rotate_member(&mut new_prov, &account, next)
                     }
+                };
+
+                if let Err(reason) = applied {
+                    let divergence = ProvisionerDivergence {
+                        block_height,
+                        reason,
+                        change: format!("{change:?}"),
+                        account: change.to_public_key(),
+                        changed_keys: Self::changed_keys(stake_events),
+                        before: current.clone(),
+                        partial: new_prov,
+                    };
+                    return Err(divergence.into());
                 }
             }
-            // Update new prov
-            provisioners_list.update_and_swap(new_prov);
+            Ok(Some(new_prov))
         }
-        Ok(())
     }
 
     /// Updates tip together with provisioners list.
@@ -677,13 +931,18 @@ impl<DB: database::DB, VM: vm::VMExecution, N: Network> Acceptor<N, DB, VM> {
             db.op_write(MD_STATE_ROOT_KEY, blk.header().state_hash)
         })?;
 
+        let prev_state_root = self
+            .db
+            .read()
+            .await
+            .view(|t| t.block_header(&blk.header().prev_block_hash))?
+            .map(|prev| prev.state_hash);
         let vm = self.vm.read().await;
-        let current_prov = vm.get_provisioners(blk.header().state_hash)?;
-        provisioners_list.update(current_prov);
-
-        let changed_provisioners =
-            vm.get_changed_provisioners(blk.header().state_hash)?;
-        provisioners_list.apply_changes(changed_provisioners);
+        *provisioners_list = self.provisioners_cache.context(
+            &*vm,
+            blk.header().state_hash,
+            prev_state_root,
+        )?;
 
         *tip = BlockWithLabel::new_with_label(blk.clone(), label);
 
@@ -713,19 +972,60 @@ impl<DB: database::DB, VM: vm::VMExecution, N: Network> Acceptor<N, DB, VM> {
     }
 
     /// Return true if the accepted blocks triggered a rolling finality
+    ///
+    /// A block diverging from the state committed to by its header is
+    /// reported, and reverted, once its acceptance returned, so that the revert
+    /// doesn't wait for the locks taken by the acceptance.
     pub(crate) async fn try_accept_block(
         &mut self,
         blk: &Block,
         enable_consensus: bool,
     ) -> anyhow::Result<bool> {
+        let accepted = self.accept_block(blk, enable_consensus).await;
+        if let Err(err) = &accepted {
+            if let Some(divergence) = err.downcast_ref() {
+                self.on_state_divergence(divergence).await;
+            }
+        }
+        accepted
+    }
+
+    async fn accept_block(
+        &mut self,
+        blk: &Block,
+        enable_consensus: bool,
+    ) -> anyhow::Result<bool> {
+        if disk_guard::status() >= DiskStatus::AcceptanceHalted {
+            anyhow::bail!(
+                "block acceptance halted: available disk space too low"
+            );
+        }
+        if self.state_divergences.read().await.is_halted() {
+            anyhow::bail!(
+                "block acceptance halted: block diverged {} times",
+                MAX_STATE_DIVERGENCES
+            );
+        }
+
+        let block_size = blk.size()?;
+        if block_size > *MAX_BLOCK_SIZE {
+            counter!("dusk_oversize_blocks_rejected").increment(1);
+            anyhow::bail!(
+                "block size {block_size} exceeds the maximum {}",
+                *MAX_BLOCK_SIZE
+            );
+        }
+
         let mut events = vec![];
-        let mut task = self.task.write().await;
 
-        let mut tip = self.tip.write().await;
-        let prev_header = tip.inner().header().clone();
-        let mut provisioners_list = self.provisioners_list.write().await;
-        let block_time =
-            blk.header().timestamp - tip.inner().header().timestamp;
+        // The block is accepted against the current tip and provisioners,
+        // read-locked only, and the changes are swapped in once the acceptance
+        // succeeds, so that readers are not blocked for the whole acceptance
+        let prev_header =
+            read_timed(&self.tip, "tip").await.inner().header().clone();
+        let provisioners_list =
+            read_timed(&self.provisioners_list, "provisioners").await;
+        let block_time = blk.header().timestamp - prev_header.timestamp;
 
         let header_verification_start = std::time::Instant::now();
         // Verify Block Header
@@ -737,6 +1037,18 @@ impl<DB: database::DB, VM: vm::VMExecution, N: Network> Acceptor<N, DB, VM> {
             &self.dusk_key,
         )
         .await?;
+        let vm = self.vm.read().await;
+        verify_provisioners_root(&*vm, &prev_header, blk.header())?;
+        verify_extra_data(&*vm, blk.header())?;
+        let ctx = vm::BlockContext::new(blk.header(), &*vm);
+        verify_tx_order(&ctx, blk)?;
+        drop(vm);
+
+        let presence = participation::attestation_presence(
+            provisioners_list.current(),
+            blk.header(),
+            prev_header.seed,
+        );
 
         // Elapsed time header verification
         histogram!("dusk_block_header_elapsed")
@@ -747,51 +1059,70 @@ impl<DB: database::DB, VM: vm::VMExecution, N: Network> Acceptor<N, DB, VM> {
         let mut block_size_on_disk = 0;
         let mut slashed_count: usize = 0;
         // Persist block in consistency with the VM state update
-        let (label, finalized) = {
+        let (label, finalized, finalization, rotations, new_provisioners) = {
             let header = blk.header();
             verify_faults(self.db.clone(), header.height, blk.faults()).await?;
 
             let vm = self.vm.write().await;
 
-            let (contract_events, finality) =
-                self.db.read().await.update(|db| {
-                    let (txs, verification_output, contract_events) = vm
-                        .accept(
-                            prev_header.state_hash,
-                            blk,
-                            &prev_block_voters[..],
-                        )?;
-
-                    for spent_tx in txs.iter() {
-                        events
-                            .push(TransactionEvent::Executed(spent_tx).into());
-                    }
-                    est_elapsed_time = start.elapsed();
+            let accepted = self.db.read().await.update(|db| {
+                let (txs, verification_output, contract_events, contract_gas) =
+                    vm.accept(
+                        prev_header.state_hash,
+                        blk,
+                        &ctx,
+                        &prev_block_voters[..],
+                    )?;
+
+                for spent_tx in txs.iter() {
+                    events.push(TransactionEvent::Executed(spent_tx).into());
+                }
+                est_elapsed_time = start.elapsed();
 
-                    assert_eq!(
-                        header.state_hash,
-                        verification_output.state_root
-                    );
-                    assert_eq!(
-                        header.event_bloom,
-                        verification_output.event_bloom
+                if header.state_hash != verification_output.state_root
+                    || header.event_bloom != verification_output.event_bloom
+                {
+                    let expected = VerificationOutput {
+                        state_root: header.state_hash,
+                        event_bloom: header.event_bloom,
+                    };
+                    let divergence = StateDivergence::new(
+                        header.height,
+                        header.hash,
+                        expected,
+                        verification_output,
+                        &txs,
+                        &contract_events,
                     );
+                    return Err(divergence.into());
+                }
 
-                    let finality =
-                        self.rolling_finality::<DB>(pni, blk, db, &mut events)?;
+                let finality =
+                    self.rolling_finality::<DB>(pni, blk, db, &mut events)?;
 
-                    let label = finality.0;
-                    // Store block with updated transactions with Error and
-                    // GasSpent
-                    block_size_on_disk =
-                        db.store_block(header, &txs, blk.faults(), label)?;
+                let label = finality.0;
+                // Store block with updated transactions with Error and
+                // GasSpent
+                block_size_on_disk =
+                    db.store_block(header, &txs, blk.faults(), label)?;
 
-                    Ok((contract_events, finality))
-                })?;
+                // The contract events can't be indexed afterwards, even if
+                // indexes are paused
+                db.store_events(header.height, &contract_events)?;
+
+                // Index the gas spent per contract of the block, unless
+                // indexes are paused to spare the disk
+                if disk_guard::status() < DiskStatus::IndexesPaused {
+                    db.store_contract_gas(header.height, &contract_gas)?;
+                }
+
+                Ok((contract_events, finality, key_rotations(&txs)))
+            });
+            let (contract_events, finality, rotations) = accepted?;
 
             // use rolling_finality_events for archive
             #[cfg(feature = "archive")]
-            {
+            if disk_guard::status() < DiskStatus::IndexesPaused {
                 if let Some(RollingFinalityResult { new_finals, .. }) =
                     &finality.1
                 {
@@ -826,7 +1157,7 @@ impl<DB: database::DB, VM: vm::VMExecution, N: Network> Acceptor<N, DB, VM> {
 
             let mut stakes = vec![];
             for event in contract_events {
-                if event.event.target == STAKE_CONTRACT {
+                if is_stake_contract(&event.event.target) {
                     stakes.push(event.event);
                 }
             }
@@ -834,7 +1165,7 @@ impl<DB: database::DB, VM: vm::VMExecution, N: Network> Acceptor<N, DB, VM> {
             self.log_missing_iterations(
                 provisioners_list.current(),
                 header.iteration,
-                tip.inner().header().seed,
+                prev_header.seed,
                 header.height,
             );
 
@@ -851,54 +1182,108 @@ impl<DB: database::DB, VM: vm::VMExecution, N: Network> Acceptor<N, DB, VM> {
             let selective_update = Self::selective_update(
                 header.height,
                 &stakes,
-                &mut provisioners_list,
+                provisioners_list.current(),
             );
 
-            if let Err(e) = selective_update {
-                warn!("Resync provisioners due to {e:?}");
-                let state_hash = blk.header().state_hash;
-                let new_prov = vm.get_provisioners(state_hash)?;
-                provisioners_list.update_and_swap(new_prov)
-            }
+            // The provisioners are only cloned when the block changes them
+            let new_provisioners = match selective_update {
+                Ok(new_provisioners) => new_provisioners,
+                Err(e) => Some(Self::recover_provisioners(
+                    &vm,
+                    blk.header().state_hash,
+                    e,
+                    provisioners_list.current(),
+                )?),
+            };
+            let current = new_provisioners
+                .as_ref()
+                .unwrap_or(provisioners_list.current());
+            self.provisioners_cache
+                .insert(header.state_hash, current.clone());
 
             let (label, final_results) = finality;
-            // Update tip
-            *tip = BlockWithLabel::new_with_label(blk.clone(), label);
 
             let finalized = final_results.is_some();
 
-            if let Some(RollingFinalityResult {
-                prev_final_state_root,
-                mut new_finals,
-            }) = final_results
-            {
-                let legacy = blk.header().height < self.finality_activation;
-
-                let (_, new_final_state) =
-                    new_finals.pop_last().expect("new_finals to be not empty");
-                let new_final_state_root = new_final_state.state_root;
-                // old final state roots to merge too
-                let new_finals = new_finals
-                    .into_values()
-                    .map(|finalized_info| finalized_info.state_root)
-                    .collect::<Vec<_>>();
-
-                let old_final_state_roots = if legacy {
-                    [new_finals, vec![prev_final_state_root]].concat()
-                } else {
-                    [vec![prev_final_state_root], new_finals].concat()
-                };
+            let finalization = final_results.map(
+                |RollingFinalityResult {
+                     prev_final_state_root,
+                     mut new_finals,
+                 }| {
+                    let legacy = blk.header().height < self.finality_activation;
+
+                    let (_, new_final_state) = new_finals
+                        .pop_last()
+                        .expect("new_finals to be not empty");
+                    let new_final_state_root = new_final_state.state_root;
+                    // old final state roots to merge too
+                    let new_finals = new_finals
+                        .into_values()
+                        .map(|finalized_info| finalized_info.state_root)
+                        .collect::<Vec<_>>();
+
+                    let old_final_state_roots = if legacy {
+                        [new_finals, vec![prev_final_state_root]].concat()
+                    } else {
+                        [vec![prev_final_state_root], new_finals].concat()
+                    };
 
-                vm.finalize_state(new_final_state_root, old_final_state_roots)?;
-            }
+                    (new_final_state_root, old_final_state_roots)
+                },
+            );
 
-            anyhow::Ok((label, finalized))
+            anyhow::Ok((
+                label,
+                finalized,
+                finalization,
+                rotations,
+                new_provisioners,
+            ))
         }?;
+        drop(provisioners_list);
+
+        // Swap in the new tip and provisioners
+        let tip = BlockWithLabel::new_with_label(blk.clone(), label);
+        {
+            let swap_start = std::time::Instant::now();
+            let mut curr_tip = write_timed(&self.tip, "tip").await;
+            let mut curr_provisioners =
+                write_timed(&self.provisioners_list, "provisioners").await;
+            *curr_tip = tip.clone();
+            apply_provisioners(&mut curr_provisioners, new_provisioners);
+            histogram!("dusk_acceptor_swap_elapsed")
+                .record(swap_start.elapsed());
+        }
+
+        // Finalize the VM state off the acceptance path
+        if let Some((new_final_state_root, old_final_state_roots)) =
+            finalization
+        {
+            self.finalization
+                .spawn(
+                    &self.vm,
+                    tip.inner().header().height,
+                    new_final_state_root,
+                    old_final_state_roots,
+                )
+                .await;
+        }
 
         // Abort consensus.
         // A fully valid block is accepted, consensus task must be aborted.
+        let mut task = write_timed(&self.task, "task").await;
         task.abort_with_wait().await;
 
+        for rotation in &rotations {
+            info!(
+                event = "key rotation registered",
+                account = PublicKey::new(*rotation.account()).to_bs58(),
+                next = PublicKey::new(*rotation.next_account()).to_bs58(),
+                activation = rotation.activation()
+            );
+            task.keys.schedule(rotation);
+        }
+
         Self::emit_metrics(
             tip.inner(),
             &label,
@@ -907,6 +1292,13 @@ impl<DB: database::DB, VM: vm::VMExecution, N: Network> Acceptor<N, DB, VM> {
             block_size_on_disk,
             slashed_count,
         );
+        inclusion::mark_included(tip.inner().txs().iter().map(|tx| tx.id()));
+        contribution::on_accepted(&tip.inner().header().hash);
+        fee_estimator::record(
+            tip.inner().header().height,
+            tip.inner().txs().iter().map(|tx| tx.gas_price()),
+        );
+        participation::record(tip.inner().header().height, presence);
 
         // Clean up the database
         let count = self
@@ -914,14 +1306,27 @@ impl<DB: database::DB, VM: vm::VMExecution, N: Network> Acceptor<N, DB, VM> {
             .read()
             .await
             .update(|db| {
-                // Delete any candidate block older than TIP - OFFSET
+                // Delete any candidate block older than TIP - OFFSET, except
+                // for the ones pinned by an in-flight quorum processing
                 let threshold = tip
                     .inner()
                     .header()
                     .height
                     .saturating_sub(CANDIDATES_DELETION_OFFSET);
 
-                db.delete_candidate(|height| height <= threshold)?;
+                if let Some(threshold) =
+                    self.candidate_pins.deletion_threshold(threshold)
+                {
+                    db.delete_candidate(|height| height <= threshold)?;
+                }
+
+                // Keep the step outcomes of the recent rounds only
+                db.delete_round_history(
+                    tip.inner()
+                        .header()
+                        .height
+                        .saturating_sub(ROUND_HISTORY_RETENTION),
+                )?;
 
                 // Delete from mempool any transaction already included in the
                 // block
@@ -945,6 +1350,7 @@ impl<DB: database::DB, VM: vm::VMExecution, N: Network> Acceptor<N, DB, VM> {
                             })
                             .unwrap_or_default()
                         {
+                            inclusion::mark_dropped(&deleted_tx);
                             events.push(
                                 TransactionEvent::Removed(deleted_tx).into(),
                             );
@@ -964,6 +1370,7 @@ impl<DB: database::DB, VM: vm::VMExecution, N: Network> Acceptor<N, DB, VM> {
             let mut f = task.future_msg.lock().await;
             f.remove_msgs_out_of_range(round + 1, OFFSET_FUTURE_MSGS);
             histogram!("dusk_future_msg_count").record(f.msg_count() as f64);
+            gauge!("dusk_future_msg_bytes").set(f.memory_usage() as f64);
         }
 
         let fsv_bitset = tip.inner().header().att.validation.bitset;
@@ -996,9 +1403,10 @@ impl<DB: database::DB, VM: vm::VMExecution, N: Network> Acceptor<N, DB, VM> {
         // Restart Consensus.
         if enable_consensus {
             let base_timeouts = self.adjust_round_base_timeouts().await;
+            let provisioners_list = self.provisioners_list.read().await.clone();
             task.spawn(
                 tip.inner(),
-                provisioners_list.clone(),
+                provisioners_list,
                 &self.db,
                 &self.vm,
                 base_timeouts,
@@ -1146,6 +1554,12 @@ impl<DB: database::DB, VM: vm::VMExecution, N: Network> Acceptor<N, DB, VM> {
                     );
 
                     finalized_blocks.insert(height, finalized);
+
+                    if ledger::epoch_heights(ledger::epoch_of(height)).end
+                        == height + 1
+                    {
+                        store_epoch_root(db, height)?;
+                    }
                 }
             }
         }
@@ -1166,6 +1580,9 @@ impl<DB: database::DB, VM: vm::VMExecution, N: Network> Acceptor<N, DB, VM> {
     ///
     /// This incorporates both VM state revert and Ledger state revert.
     pub async fn try_revert(&self, target: RevertTarget) -> Result<()> {
+        // The VM can't be reverted during a finalization
+        self.finalization.wait().await?;
+
         let curr_height = self.get_curr_height().await;
 
         let target_state_hash = match target {
@@ -1321,12 +1738,12 @@ impl<DB: database::DB, VM: vm::VMExecution, N: Network> Acceptor<N, DB, VM> {
     }
 
     pub(crate) async fn get_curr_height(&self) -> u64 {
-        self.tip.read().await.inner().header().height
+        read_timed(&self.tip, "tip").await.inner().header().height
     }
 
     /// Returns chain tip header
     pub(crate) async fn tip_header(&self) -> ledger::Header {
-        self.tip.read().await.inner().header().clone()
+        read_timed(&self.tip, "tip").await.inner().header().clone()
     }
 
     pub(crate) async fn get_last_final_block(&self) -> Result<Block> {
@@ -1362,7 +1779,7 @@ impl<DB: database::DB, VM: vm::VMExecution, N: Network> Acceptor<N, DB, VM> {
     }
 
     pub(crate) async fn get_curr_tip(&self) -> BlockWithLabel {
-        self.tip.read().await.clone()
+        read_timed(&self.tip, "tip").await.clone()
     }
 
     pub(crate) async fn get_result_chan(
@@ -1375,6 +1792,12 @@ impl<DB: database::DB, VM: vm::VMExecution, N: Network> Acceptor<N, DB, VM> {
         self.task.read().await.outbound.clone()
     }
 
+    /// Replaces the consensus keys with `keys`, signing with them from the
+    /// consensus task of the next round on
+    pub(crate) async fn reload_consensus_keys(&self, keys: ConsensusKeys) {
+        self.task.write().await.reload_keys(keys);
+    }
+
     async fn adjust_round_base_timeouts(&self) -> TimeoutSet {
         let mut base_timeout_set = TimeoutSet::new();
 
@@ -1485,20 +1908,11 @@ impl<DB: database::DB, VM: vm::VMExecution, N: Network> Acceptor<N, DB, VM> {
             ))
         })?;
 
-        let provisioners_list = self
-            .vm
-            .read()
-            .await
-            .get_provisioners(prev_header.state_hash)?;
-
-        let mut provisioners_list = ContextProvisioners::new(provisioners_list);
-
-        let changed_provisioners = self
-            .vm
-            .read()
-            .await
-            .get_changed_provisioners(prev_header.state_hash)?;
-        provisioners_list.apply_changes(changed_provisioners);
+        let provisioners_list = self.provisioners_cache.context(
+            &*self.vm.read().await,
+            prev_header.state_hash,
+            None,
+        )?;
 
         // Ensure header of the new block is valid according to prev_block
         // header
@@ -1558,3 +1972,418 @@ pub(crate) async fn verify_block_header<DB: database::DB>(
         .execute_checks(header, &expected_generator, check_att)
         .await
 }
+
+/// Applies the provisioners changed by an accepted block, if any, to the
+/// `current` ones
+fn apply_provisioners(
+    current: &mut ContextProvisioners,
+    changed: Option<Provisioners>,
+) {
+    match changed {
+        Some(provisioners) => current.update_and_swap(provisioners),
+        None => current.remove_previous(),
+    }
+}
+
+/// Acquires a read lock on `lock`, recording the time spent waiting for it
+async fn read_timed<'a, T>(
+    lock: &'a RwLock<T>,
+    name: &'static str,
+) -> RwLockReadGuard<'a, T> {
+    let start = std::time::Instant::now();
+    let guard = lock.read().await;
+    histogram!(format!("dusk_acceptor_{name}_read_wait"))
+        .record(start.elapsed());
+    guard
+}
+
+/// Acquires a write lock on `lock`, recording the time spent waiting for it
+async fn write_timed<'a, T>(
+    lock: &'a RwLock<T>,
+    name: &'static str,
+) -> RwLockWriteGuard<'a, T> {
+    let start = std::time::Instant::now();
+    let guard = lock.write().await;
+    histogram!(format!("dusk_acceptor_{name}_write_wait"))
+        .record(start.elapsed());
+    guard
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+    use std::net::SocketAddr;
+
+    use dusk_consensus::config::{
+        EMERGENCY_BLOCK_ITERATION, MIN_EMERGENCY_BLOCK_TIME,
+    };
+    use dusk_consensus::errors::VstError;
+    use dusk_consensus::merkle::merkle_root;
+    use dusk_consensus::operations::CallParams;
+    use dusk_consensus::user::provisioners::DUSK;
+    use dusk_core::abi::ContractId;
+    use dusk_core::signatures::bls::{
+        PublicKey as BlsPublicKey, SecretKey as BlsSecretKey,
+    };
+    use node_data::events::contract::ContractTxEvent;
+    use node_data::ledger::{Header, Transaction};
+    use node_data::message::BLOCK_HEADER_VERSION;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::*;
+    use crate::network::mock::MockHub;
+
+    fn provisioners(rng: &mut StdRng, count: usize) -> Provisioners {
+        let mut provisioners = Provisioners::empty();
+        for _ in 0..count {
+            let sk = BlsSecretKey::random(rng);
+            let pk = PublicKey::new(BlsPublicKey::from(&sk));
+            provisioners.add_member_with_value(pk, 1000);
+        }
+        provisioners
+    }
+
+    #[test]
+    fn apply_changed_provisioners() {
+        let rng = &mut StdRng::seed_from_u64(0xbeef);
+        let first = provisioners(rng, 2);
+        let second = provisioners(rng, 3);
+
+        let mut list = ContextProvisioners::new(first);
+        apply_provisioners(&mut list, Some(second));
+        assert_eq!(list.current().iter().count(), 3);
+        assert_eq!(list.prev().iter().count(), 2);
+
+        // A block not changing the provisioners drops the previous ones
+        apply_provisioners(&mut list, None);
+        assert_eq!(list.current().iter().count(), 3);
+        assert_eq!(list.prev().iter().count(), 3);
+    }
+
+    fn rotation(account: &PublicKey, next: &PublicKey) -> ContractEvent {
+        let event = KeyRotationEvent {
+            account: *account.inner(),
+            next_account: *next.inner(),
+            activation: 0,
+        };
+        ContractEvent {
+            target: dusk_core::stake::STAKE_CONTRACT,
+            topic: "key_rotated".into(),
+            data: rkyv::to_bytes::<_, 256>(&event)
+                .expect("event to serialize")
+                .to_vec(),
+        }
+    }
+
+    #[test]
+    fn rotated_provisioner() {
+        let rng = &mut StdRng::seed_from_u64(0xbeef);
+        let current = provisioners(rng, 2);
+        let mut keys = current.iter().map(|(pk, _)| pk.clone());
+        let (first, second) = (keys.next().unwrap(), keys.next().unwrap());
+        let next_sk = BlsSecretKey::random(rng);
+        let next = PublicKey::new(BlsPublicKey::from(&next_sk));
+
+        // Both keys are changed by the rotation
+        let change = ProvisionerChange::from_event(&rotation(&first, &next))
+            .expect("rotation to change the provisioners");
+        assert_eq!(change.changed_keys(), vec![first.clone(), next.clone()]);
+
+        // The stake is moved to the next account key
+        let mut rotated = current.clone();
+        rotate_member(&mut rotated, &first, next.clone())
+            .expect("rotation to apply");
+        assert!(rotated.get_member(&first).is_none());
+        assert_eq!(
+            rotated.get_member(&next).map(|s| s.value()),
+            current.get_member(&first).map(|s| s.value())
+        );
+
+        // Rotations to a provisioner key, or of an unknown key, diverge
+        let mut rotated = current.clone();
+        rotate_member(&mut rotated, &first, second)
+            .expect_err("rotation to an existing stake");
+        rotate_member(&mut rotated, &next, first)
+            .expect_err("rotation of a not existing stake");
+    }
+
+    /// VM accepting any block into a state diverging from the one committed
+    /// to by its header
+    #[derive(Clone)]
+    struct DivergingVm {
+        state_root: [u8; 32],
+        provisioners: Provisioners,
+    }
+
+    impl vm::VMExecution for DivergingVm {
+        fn execute_state_transition<I: Iterator<Item = Transaction>>(
+            &self,
+            _: &CallParams,
+            _: &vm::BlockContext,
+            _: I,
+        ) -> anyhow::Result<(
+            Vec<SpentTransaction>,
+            Vec<Transaction>,
+            VerificationOutput,
+        )> {
+            unimplemented!()
+        }
+
+        fn verify_state_transition(
+            &self,
+            _: [u8; 32],
+            _: &Block,
+            _: &vm::BlockContext,
+            _: &[Voter],
+        ) -> Result<VerificationOutput, VstError> {
+            unimplemented!()
+        }
+
+        fn accept(
+            &self,
+            _: [u8; 32],
+            _: &Block,
+            _: &vm::BlockContext,
+            _: &[Voter],
+        ) -> anyhow::Result<(
+            Vec<SpentTransaction>,
+            VerificationOutput,
+            Vec<ContractTxEvent>,
+            vm::ContractGas,
+        )> {
+            let output = VerificationOutput {
+                state_root: [0xff; 32],
+                event_bloom: [0; 256],
+            };
+            Ok((vec![], output, vec![], vec![]))
+        }
+
+        fn replay(
+            &self,
+            _: [u8; 32],
+            _: &Block,
+            _: &vm::BlockContext,
+            _: &[Voter],
+        ) -> anyhow::Result<(Vec<SpentTransaction>, VerificationOutput, bool)>
+        {
+            unimplemented!()
+        }
+
+        fn delete_side_commit(&self, _: [u8; 32]) -> anyhow::Result<()> {
+            unimplemented!()
+        }
+
+        fn finalize_state(
+            &self,
+            _: [u8; 32],
+            _: Vec<[u8; 32]>,
+        ) -> anyhow::Result<()> {
+            unimplemented!()
+        }
+
+        fn preverify(
+            &self,
+            _: &Transaction,
+            _: u64,
+        ) -> anyhow::Result<vm::PreverificationResult> {
+            unimplemented!()
+        }
+
+        fn get_provisioners(
+            &self,
+            _: [u8; 32],
+        ) -> anyhow::Result<Provisioners> {
+            Ok(self.provisioners.clone())
+        }
+
+        fn get_changed_provisioners(
+            &self,
+            _: [u8; 32],
+        ) -> anyhow::Result<Vec<(PublicKey, Option<Stake>)>> {
+            Ok(vec![])
+        }
+
+        fn get_provisioners_root(
+            &self,
+            _: u64,
+            _: [u8; 32],
+        ) -> anyhow::Result<Option<[u8; 32]>> {
+            Ok(None)
+        }
+
+        fn with_header_extra_data(&self, _: u64) -> bool {
+            false
+        }
+
+        fn active_features(&self, _: u64) -> BTreeSet<String> {
+            BTreeSet::new()
+        }
+
+        fn economic_params(&self, _: u64) -> vm::EconomicParams {
+            vm::EconomicParams::default()
+        }
+
+        fn get_provisioner(
+            &self,
+            _: &BlsPublicKey,
+        ) -> anyhow::Result<Option<Stake>> {
+            unimplemented!()
+        }
+
+        fn get_state_root(&self) -> anyhow::Result<[u8; 32]> {
+            Ok(self.state_root)
+        }
+
+        fn move_to_commit(&self, _: [u8; 32]) -> anyhow::Result<()> {
+            unimplemented!()
+        }
+
+        fn get_finalized_state_root(&self) -> anyhow::Result<[u8; 32]> {
+            Ok(self.state_root)
+        }
+
+        fn query_at(
+            &self,
+            _: [u8; 32],
+            _: ContractId,
+            _: &str,
+            _: Vec<u8>,
+        ) -> anyhow::Result<Vec<u8>> {
+            unimplemented!()
+        }
+
+        fn get_block_gas_limit(&self) -> u64 {
+            unimplemented!()
+        }
+
+        fn revert(&self, _: [u8; 32]) -> anyhow::Result<[u8; 32]> {
+            unimplemented!()
+        }
+
+        fn revert_to_finalized(&self) -> anyhow::Result<[u8; 32]> {
+            Ok(self.state_root)
+        }
+
+        fn gas_per_deploy_byte(&self) -> u64 {
+            unimplemented!()
+        }
+
+        fn min_deployment_gas_price(&self) -> u64 {
+            unimplemented!()
+        }
+
+        fn min_gas_limit(&self) -> u64 {
+            unimplemented!()
+        }
+
+        fn min_deploy_points(&self) -> u64 {
+            unimplemented!()
+        }
+    }
+
+    /// Returns the emergency block signed by `sk` on top of `prev`
+    fn emergency_block(
+        prev: &Header,
+        pk: &BlsPublicKey,
+        sk: &BlsSecretKey,
+    ) -> Block {
+        let seed = sk.sign_multisig(pk, prev.seed.inner());
+        let mut header = Header {
+            version: BLOCK_HEADER_VERSION,
+            height: prev.height + 1,
+            timestamp: get_current_timestamp(),
+            prev_block_hash: prev.hash,
+            seed: Seed::from(seed.to_bytes()),
+            state_hash: [2; 32],
+            generator_bls_pubkey: *PublicKey::new(*pk).bytes(),
+            iteration: EMERGENCY_BLOCK_ITERATION,
+            txroot: merkle_root::<[u8; 32]>(&[]),
+            faultroot: merkle_root::<[u8; 32]>(&[]),
+            ..Default::default()
+        };
+        header.hash = header.compute_hash().unwrap();
+        header.signature = sk.sign_multisig(pk, &header.hash).to_bytes().into();
+        Block::new(header, vec![], vec![]).unwrap()
+    }
+
+    #[cfg(not(feature = "archive"))]
+    #[tokio::test]
+    async fn diverging_block() {
+        let rng = &mut StdRng::seed_from_u64(0xbeef);
+        let dusk_sk = BlsSecretKey::random(rng);
+        let dusk_key = BlsPublicKey::from(&dusk_sk);
+        let sk = BlsSecretKey::random(rng);
+        let pk = PublicKey::new(BlsPublicKey::from(&sk));
+        let mut provisioners = Provisioners::empty();
+        provisioners.add_member_with_value(pk.clone(), 1000 * DUSK);
+
+        let genesis = Block::new(
+            Header {
+                timestamp: get_current_timestamp()
+                    - MIN_EMERGENCY_BLOCK_TIME.as_secs()
+                    - 60,
+                state_hash: [1; 32],
+                ..Default::default()
+            },
+            vec![],
+            vec![],
+        )
+        .unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let db =
+            Backend::create_or_open(dir.path(), DatabaseOptions::default());
+        db.update(|t| {
+            t.store_block(genesis.header(), &[], &[], Label::Final(0))
+        })
+        .unwrap();
+
+        let vm = DivergingVm {
+            state_root: genesis.header().state_hash,
+            provisioners,
+        };
+        let network = MockHub::new(0).connect(SocketAddr::from(([0; 4], 0)));
+        let (event_sender, _events) = tokio::sync::mpsc::channel(100);
+        let mut acc = Acceptor::init_consensus(
+            ConsensusKeys::new((sk, pk), None),
+            BlockWithLabel::new_with_label(genesis.clone(), Label::Final(0)),
+            Arc::new(RwLock::new(db)),
+            Arc::new(RwLock::new(network)),
+            Arc::new(RwLock::new(vm)),
+            100,
+            event_sender,
+            dusk_key,
+            0,
+            TxPolicy::default(),
+            vec![],
+            MemoryParams::default(),
+            ConsensusEvents::default(),
+        )
+        .await
+        .unwrap();
+
+        // The diverging block is reverted, without waiting for the locks
+        // taken by its acceptance
+        let blk = emergency_block(genesis.header(), &dusk_key, &dusk_sk);
+        for _ in 0..MAX_STATE_DIVERGENCES {
+            let accepted = tokio::time::timeout(
+                Duration::from_secs(10),
+                acc.try_accept_block(&blk, false),
+            )
+            .await
+            .expect("diverging block to be reverted");
+            let err = accepted.expect_err("diverging block to be rejected");
+            assert!(err.downcast_ref::<StateDivergence>().is_some());
+            assert_eq!(acc.get_curr_height().await, 0);
+        }
+
+        // The acceptance is halted once the block diverged too many times
+        let err = acc
+            .try_accept_block(&blk, false)
+            .await
+            .expect_err("acceptance to be halted");
+        assert!(err.downcast_ref::<StateDivergence>().is_none());
+        assert_eq!(acc.get_curr_height().await, 0);
+    }
+}
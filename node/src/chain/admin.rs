@@ -0,0 +1,209 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Authentication of the admin commands received over the network.
+//!
+//! A command is dispatched only if it's signed by the operator key configured
+//! on the node, addressed to the node id configured on it, and fresh: its
+//! timestamp must be within the allowed clock skew of the local clock, and
+//! later than the one of the last accepted command, so that a captured command
+//! can't be replayed. The timestamp of the last accepted command is persisted,
+//! so that commands can't be replayed after a restart either.
+//!
+//! Every command is rejected when no operator key or node id is configured.
+
+use node_data::message::payload::AdminCommand;
+use thiserror::Error;
+
+use super::conf::AdminParams;
+
+/// Replaces the log filter directives of the node
+pub type LogReload = Box<dyn Fn(&str) -> anyhow::Result<()> + Send + Sync>;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum AdminError {
+    #[error("no operator key or node id configured")]
+    Disabled,
+    #[error("invalid signature")]
+    InvalidSignature,
+    #[error("addressed to node {0}")]
+    WrongTarget(String),
+    #[error("timestamp {0} out of the allowed clock skew")]
+    ClockSkew(u64),
+    #[error("timestamp {0} not later than the last command")]
+    Replayed(u64),
+}
+
+pub(crate) struct AdminGuard {
+    conf: AdminParams,
+    /// Timestamp of the last accepted command
+    last_timestamp: u64,
+}
+
+impl AdminGuard {
+    /// Creates a guard accepting the commands issued after `last_timestamp`
+    pub fn new(conf: AdminParams, last_timestamp: u64) -> Self {
+        Self {
+            conf,
+            last_timestamp,
+        }
+    }
+
+    /// Returns the timestamp of the last accepted command
+    pub fn last_timestamp(&self) -> u64 {
+        self.last_timestamp
+    }
+
+    /// Checks `cmd` can be dispatched at time `now` (in seconds)
+    pub fn check(
+        &mut self,
+        cmd: &AdminCommand,
+        now: u64,
+    ) -> Result<(), AdminError> {
+        let operator = self
+            .conf
+            .operator_key
+            .as_ref()
+            .ok_or(AdminError::Disabled)?;
+        let node_id = self.conf.node_id.as_ref().ok_or(AdminError::Disabled)?;
+
+        cmd.verify_signature(operator.inner())
+            .map_err(|_| AdminError::InvalidSignature)?;
+
+        if &cmd.target != node_id {
+            return Err(AdminError::WrongTarget(cmd.target.clone()));
+        }
+
+        if cmd.timestamp.abs_diff(now) > self.conf.max_clock_skew.as_secs() {
+            return Err(AdminError::ClockSkew(cmd.timestamp));
+        }
+        if cmd.timestamp <= self.last_timestamp {
+            return Err(AdminError::Replayed(cmd.timestamp));
+        }
+
+        self.last_timestamp = cmd.timestamp;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use dusk_core::signatures::bls::{
+        PublicKey as BlsPublicKey, SecretKey as BlsSecretKey,
+    };
+    use node_data::bls::PublicKey;
+    use node_data::message::payload::AdminAction;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::*;
+
+    fn signed(sk: &BlsSecretKey, timestamp: u64) -> AdminCommand {
+        addressed(sk, "node-1", timestamp)
+    }
+
+    fn addressed(
+        sk: &BlsSecretKey,
+        target: &str,
+        timestamp: u64,
+    ) -> AdminCommand {
+        let mut cmd = AdminCommand::new(target, AdminAction::Revert, timestamp);
+        cmd.sign(sk, &BlsPublicKey::from(sk));
+        cmd
+    }
+
+    #[test]
+    fn admin_commands_are_authenticated() {
+        let rng = &mut StdRng::seed_from_u64(1);
+        let sk = BlsSecretKey::random(rng);
+        let other = BlsSecretKey::random(rng);
+        let now = 1_000;
+
+        let operator_key = Some(PublicKey::new(BlsPublicKey::from(&sk)));
+        let conf = AdminParams {
+            operator_key,
+            node_id: Some("node-1".to_string()),
+            max_clock_skew: Duration::from_secs(60),
+        };
+
+        let mut disabled = AdminGuard::new(AdminParams::default(), 0);
+        assert_eq!(
+            disabled.check(&signed(&sk, now), now),
+            Err(AdminError::Disabled)
+        );
+        let no_node_id = AdminParams {
+            node_id: None,
+            ..conf.clone()
+        };
+        let mut disabled = AdminGuard::new(no_node_id, 0);
+        assert_eq!(
+            disabled.check(&signed(&sk, now), now),
+            Err(AdminError::Disabled)
+        );
+
+        let mut guard = AdminGuard::new(conf.clone(), 0);
+        assert_eq!(
+            guard.check(&signed(&other, now), now),
+            Err(AdminError::InvalidSignature)
+        );
+        assert_eq!(
+            guard.check(&signed(&sk, now - 61), now),
+            Err(AdminError::ClockSkew(now - 61))
+        );
+        assert_eq!(
+            guard.check(&addressed(&sk, "node-2", now), now),
+            Err(AdminError::WrongTarget("node-2".to_string()))
+        );
+
+        let cmd = signed(&sk, now);
+        assert_eq!(guard.check(&cmd, now), Ok(()));
+        assert_eq!(guard.check(&cmd, now), Err(AdminError::Replayed(now)));
+        assert_eq!(guard.check(&signed(&sk, now + 1), now), Ok(()));
+        assert_eq!(guard.last_timestamp(), now + 1);
+
+        // The last accepted timestamp survives a restart
+        let mut restarted = AdminGuard::new(conf, guard.last_timestamp());
+        assert_eq!(restarted.check(&cmd, now), Err(AdminError::Replayed(now)));
+    }
+
+    #[test]
+    fn log_filter_commands_are_not_replayed() {
+        let rng = &mut StdRng::seed_from_u64(2);
+        let sk = BlsSecretKey::random(rng);
+        let now = 1_000;
+
+        let operator_key = Some(PublicKey::new(BlsPublicKey::from(&sk)));
+        let conf = AdminParams {
+            operator_key,
+            node_id: Some("node-1".to_string()),
+            max_clock_skew: Duration::from_secs(60),
+        };
+        let set_log_filter = |filter: &str, timestamp| {
+            let action = AdminAction::SetLogFilter(filter.to_string());
+            let mut cmd = AdminCommand::new("node-1", action, timestamp);
+            cmd.sign(&sk, &BlsPublicKey::from(&sk));
+            cmd
+        };
+
+        let mut guard = AdminGuard::new(conf.clone(), 0);
+        let debug = set_log_filter("debug", now);
+        assert_eq!(guard.check(&debug, now), Ok(()));
+        let info = set_log_filter("info", now + 1);
+        assert_eq!(guard.check(&info, now), Ok(()));
+
+        // Neither the earlier filter nor the last one can be set again
+        assert_eq!(guard.check(&debug, now), Err(AdminError::Replayed(now)));
+        assert_eq!(guard.check(&info, now), Err(AdminError::Replayed(now + 1)));
+
+        let mut restarted = AdminGuard::new(conf, guard.last_timestamp());
+        assert_eq!(
+            restarted.check(&debug, now),
+            Err(AdminError::Replayed(now))
+        );
+    }
+}
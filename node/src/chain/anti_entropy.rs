@@ -0,0 +1,131 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Inventory-based anti-entropy.
+//!
+//! Gossip may drop messages, which can make two nodes slowly diverge without
+//! either of them noticing. To prevent this, every node periodically sends the
+//! hashes of its last `depth` blocks (as a plain `Inv` message) to a few
+//! random alive peers.
+//!
+//! A receiver looks up each hash in its own ledger. Any hash it does not
+//! know, either because the block is missing or because it has a different
+//! block at that height, is requested back via `GetResource`. The returned
+//! blocks then go through the regular block-event handling (sync or fallback).
+//!
+//! Since every node runs the same loop, each pair of nodes repairs in both
+//! directions.
+//!
+//! Before being advertised, the local range is checked with
+//! [`verify_hash_chain`], so that a corrupted ledger is not spread around.
+
+use std::sync::Arc;
+
+use metrics::counter;
+use node_data::message::payload::Inv;
+use tokio::sync::RwLock;
+use tokio::time::Instant;
+use tracing::{debug, warn};
+
+use super::acceptor::Acceptor;
+use super::conf::AntiEntropyParams;
+use super::hash_chain::verify_hash_chain;
+use crate::database::Ledger;
+use crate::{database, vm, Network};
+
+pub(crate) struct AntiEntropy<N: Network, DB: database::DB, VM: vm::VMExecution>
+{
+    acc: Arc<RwLock<Acceptor<N, DB, VM>>>,
+    network: Arc<RwLock<N>>,
+    conf: AntiEntropyParams,
+
+    /// Instant of the next repair round
+    next_run: Option<Instant>,
+}
+
+impl<N: Network, DB: database::DB, VM: vm::VMExecution> AntiEntropy<N, DB, VM> {
+    pub(crate) fn new(
+        acc: Arc<RwLock<Acceptor<N, DB, VM>>>,
+        network: Arc<RwLock<N>>,
+        conf: AntiEntropyParams,
+    ) -> Self {
+        let next_run =
+            conf.interval.and_then(|i| Instant::now().checked_add(i));
+        Self {
+            acc,
+            network,
+            conf,
+            next_run,
+        }
+    }
+
+    /// Handles heartbeat event.
+    ///
+    /// Runs a repair round if the configured interval has elapsed.
+    pub(crate) async fn on_heartbeat(&mut self) {
+        let (Some(interval), Some(next_run)) =
+            (self.conf.interval, self.next_run)
+        else {
+            return;
+        };
+
+        if Instant::now() < next_run {
+            return;
+        }
+        self.next_run = Instant::now().checked_add(interval);
+
+        if let Err(err) = self.run_round().await {
+            warn!(event = "anti_entropy round failed", ?err);
+        }
+    }
+
+    /// Advertises the hashes of the most recent blocks to random peers
+    pub(crate) async fn run_round(&self) -> anyhow::Result<()> {
+        if self.conf.depth == 0 || self.conf.fanout == 0 {
+            return Ok(());
+        }
+
+        let inv = {
+            let acc = self.acc.read().await;
+            let tip_height = acc.get_curr_height().await;
+            let from = tip_height.saturating_sub(self.conf.depth as u64 - 1);
+
+            acc.db.read().await.view(|t| {
+                // Never advertise a range we can't vouch for
+                verify_hash_chain(t, from, tip_height, |_| {}).map_err(
+                    |e| {
+                        counter!("dusk_anti_entropy_chain_errors").increment(1);
+                        e
+                    },
+                )?;
+
+                let mut inv = Inv::new(self.conf.depth);
+                for height in from..=tip_height {
+                    if let Some(hash) = t.block_hash_by_height(height)? {
+                        inv.add_block_from_hash(hash);
+                    }
+                }
+                anyhow::Ok(inv)
+            })?
+        };
+
+        debug!(
+            event = "anti_entropy round",
+            entries = inv.inv_list.len(),
+            fanout = self.conf.fanout,
+        );
+
+        self.network
+            .read()
+            .await
+            .send_to_alive_peers(inv.into(), self.conf.fanout)
+            .await?;
+
+        counter!("dusk_anti_entropy_rounds").increment(1);
+
+        Ok(())
+    }
+}
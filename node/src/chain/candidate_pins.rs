@@ -0,0 +1,93 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Pinning of the candidates referenced by in-flight quorum processing.
+//!
+//! A Quorum is processed in two phases: the candidate is first fetched from
+//! the DB, then accepted. A candidate is pinned for the whole processing, so
+//! that the candidates cleanup, run on block acceptance, can't delete it in
+//! between.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Reference count of the pinned candidates, by hash
+#[derive(Clone, Default)]
+pub(crate) struct CandidatePins {
+    pins: Arc<Mutex<HashMap<[u8; 32], (u64, usize)>>>,
+}
+
+impl CandidatePins {
+    /// Pins the candidate `hash` at `height` until the returned guard is
+    /// dropped.
+    pub fn pin(&self, hash: [u8; 32], height: u64) -> CandidatePin {
+        let mut pins = self.pins.lock().expect("pins lock to be acquired");
+        pins.entry(hash).or_insert((height, 0)).1 += 1;
+        CandidatePin {
+            pins: self.clone(),
+            hash,
+        }
+    }
+
+    /// Returns the highest height the candidates cleanup can delete up to,
+    /// given the `threshold` it would otherwise use.
+    ///
+    /// The threshold is lowered below the height of every pinned candidate.
+    pub fn deletion_threshold(&self, threshold: u64) -> Option<u64> {
+        let pins = self.pins.lock().expect("pins lock to be acquired");
+        match pins.values().map(|(height, _)| *height).min() {
+            Some(pinned) if pinned <= threshold => pinned.checked_sub(1),
+            _ => Some(threshold),
+        }
+    }
+
+    fn unpin(&self, hash: &[u8; 32]) {
+        let mut pins = self.pins.lock().expect("pins lock to be acquired");
+        if let Some((_, count)) = pins.get_mut(hash) {
+            *count -= 1;
+            if *count == 0 {
+                pins.remove(hash);
+            }
+        }
+    }
+}
+
+/// Guard of a pinned candidate, unpinning it on drop
+pub(crate) struct CandidatePin {
+    pins: CandidatePins,
+    hash: [u8; 32],
+}
+
+impl Drop for CandidatePin {
+    fn drop(&mut self) {
+        self.pins.unpin(&self.hash);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pinned_candidates_are_kept() {
+        let pins = CandidatePins::default();
+        assert_eq!(pins.deletion_threshold(10), Some(10));
+
+        let pin = pins.pin([1; 32], 8);
+        let again = pins.pin([1; 32], 8);
+        let later = pins.pin([2; 32], 12);
+        assert_eq!(pins.deletion_threshold(10), Some(7));
+
+        drop(pin);
+        assert_eq!(pins.deletion_threshold(10), Some(7));
+        drop(again);
+        assert_eq!(pins.deletion_threshold(10), Some(10));
+        drop(later);
+
+        let _genesis = pins.pin([0; 32], 0);
+        assert_eq!(pins.deletion_threshold(10), None);
+    }
+}
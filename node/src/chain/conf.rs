@@ -0,0 +1,226 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::time::Duration;
+
+use node_data::bls::PublicKey;
+use node_data::message::MAX_HEADER_EXTRA_DATA_SIZE;
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_EXTRA_DATA: &str =
+    concat!("dusk-node/", env!("CARGO_PKG_VERSION"));
+
+/// Chain service configuration parameters
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct Params {
+    /// Inventory-based anti-entropy repair loop
+    pub anti_entropy: AntiEntropyParams,
+
+    /// Authenticated admin commands
+    pub admin: AdminParams,
+
+    /// State sync from remote peers at startup
+    pub fast_sync: FastSyncParams,
+
+    /// Identifier included in the extra-data of the generated blocks, once
+    /// supported by the header version
+    pub extra_data: String,
+
+    /// Memory budgets of the consensus queues
+    pub memory: MemoryParams,
+
+    /// Detection of a stale tip and its recovery
+    pub stale_tip: StaleTipParams,
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Self {
+            anti_entropy: AntiEntropyParams::default(),
+            admin: AdminParams::default(),
+            fast_sync: FastSyncParams::default(),
+            extra_data: DEFAULT_EXTRA_DATA.to_string(),
+            memory: MemoryParams::default(),
+            stale_tip: StaleTipParams::default(),
+        }
+    }
+}
+
+impl Params {
+    /// Returns the extra-data of the generated blocks, truncated to
+    /// [`MAX_HEADER_EXTRA_DATA_SIZE`] bytes on a char boundary
+    pub fn extra_data(&self) -> Vec<u8> {
+        let mut len = self.extra_data.len().min(MAX_HEADER_EXTRA_DATA_SIZE);
+        while !self.extra_data.is_char_boundary(len) {
+            len -= 1;
+        }
+        self.extra_data.as_bytes()[..len].to_vec()
+    }
+}
+
+pub const DEFAULT_CHAIN_INBOUND_BUDGET: usize = 256 * 1024 * 1024;
+pub const DEFAULT_CONSENSUS_INBOUND_BUDGET: usize = 64 * 1024 * 1024;
+pub const DEFAULT_CONSENSUS_OUTBOUND_BUDGET: usize = 64 * 1024 * 1024;
+pub const DEFAULT_FUTURE_MSGS_BUDGET: usize = 32 * 1024 * 1024;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+#[serde(default)]
+pub struct MemoryParams {
+    /// Maximum number of bytes held by the messages waiting in the chain
+    /// inbound queue. The ones of the farthest heights are dropped past it
+    pub chain_inbound: usize,
+
+    /// Maximum number of bytes held by the messages waiting in the consensus
+    /// inbound queue. The ones of the farthest rounds are dropped past it
+    pub consensus_inbound: usize,
+
+    /// Maximum number of bytes held by the messages waiting to be broadcast
+    /// by the consensus. The oldest ones are dropped past it
+    pub consensus_outbound: usize,
+
+    /// Maximum number of bytes held by the messages of the future rounds.
+    /// The ones of the farthest rounds are dropped past it
+    pub future_msgs: usize,
+}
+
+impl Default for MemoryParams {
+    fn default() -> Self {
+        Self {
+            chain_inbound: DEFAULT_CHAIN_INBOUND_BUDGET,
+            consensus_inbound: DEFAULT_CONSENSUS_INBOUND_BUDGET,
+            consensus_outbound: DEFAULT_CONSENSUS_OUTBOUND_BUDGET,
+            future_msgs: DEFAULT_FUTURE_MSGS_BUDGET,
+        }
+    }
+}
+
+pub const DEFAULT_ANTI_ENTROPY_INTERVAL: Duration = Duration::from_secs(30);
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+#[serde(default)]
+pub struct AntiEntropyParams {
+    /// Interval between two repair rounds. Set to `None` to disable the loop
+    #[serde(with = "humantime_serde")]
+    pub interval: Option<Duration>,
+
+    /// Number of most recent heights advertised in each round
+    pub depth: u16,
+
+    /// Number of random alive peers the inventory is sent to
+    pub fanout: usize,
+}
+
+impl Default for AntiEntropyParams {
+    fn default() -> Self {
+        Self {
+            interval: Some(DEFAULT_ANTI_ENTROPY_INTERVAL),
+            depth: 10,
+            fanout: 3,
+        }
+    }
+}
+
+pub const DEFAULT_STALE_TIP_COOLDOWN: Duration = Duration::from_secs(60);
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+#[serde(default)]
+pub struct StaleTipParams {
+    /// Number of minimum block times the tip must not advance for, while
+    /// verified payloads of the peers report higher heights or conflicting
+    /// blocks, to be considered stale. Set to `None` to disable the recovery
+    pub threshold: Option<u32>,
+
+    /// Minimum time between two recovery steps
+    #[serde(with = "humantime_serde")]
+    pub cooldown: Duration,
+}
+
+impl Default for StaleTipParams {
+    fn default() -> Self {
+        Self {
+            threshold: Some(10),
+            cooldown: DEFAULT_STALE_TIP_COOLDOWN,
+        }
+    }
+}
+
+pub const DEFAULT_ADMIN_MAX_CLOCK_SKEW: Duration = Duration::from_secs(60);
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct AdminParams {
+    /// Key the admin commands must be signed with. Set to `None` to reject
+    /// every admin command
+    pub operator_key: Option<PublicKey>,
+
+    /// Identifier of the node the admin commands must be addressed to. Set to
+    /// `None` to reject every admin command
+    pub node_id: Option<String>,
+
+    /// Maximum difference between the timestamp of a command and the local
+    /// clock
+    #[serde(with = "humantime_serde")]
+    pub max_clock_skew: Duration,
+}
+
+impl Default for AdminParams {
+    fn default() -> Self {
+        Self {
+            operator_key: None,
+            node_id: None,
+            max_clock_skew: DEFAULT_ADMIN_MAX_CLOCK_SKEW,
+        }
+    }
+}
+
+pub const DEFAULT_FAST_SYNC_TIMEOUT: Duration = Duration::from_secs(600);
+pub const DEFAULT_MAX_STATE_SIZE: u64 = 8 * 1024 * 1024 * 1024;
+pub const DEFAULT_STATE_EXPORT_INTERVAL: Duration = Duration::from_secs(3600);
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct FastSyncParams {
+    /// HTTP addresses of the peers the state is synced from. Set to an empty
+    /// list to disable the fast sync
+    pub peers: Vec<String>,
+
+    /// Number of peers that must agree on the synced block, the serving one
+    /// included
+    pub quorum: usize,
+
+    /// Minimum number of blocks the local tip must be behind the peers for
+    /// the state to be synced instead of the blocks
+    pub min_gap: u64,
+
+    /// Timeout of each request to a peer
+    #[serde(with = "humantime_serde")]
+    pub timeout: Duration,
+
+    /// Serve the finalized state to the fast sync of other nodes
+    pub serve_state: bool,
+
+    /// Maximum size in bytes of a state archive, either served or downloaded
+    pub max_state_size: u64,
+
+    /// Minimum interval between two exports of the served state
+    #[serde(with = "humantime_serde")]
+    pub state_export_interval: Duration,
+}
+
+impl Default for FastSyncParams {
+    fn default() -> Self {
+        Self {
+            peers: vec![],
+            quorum: 2,
+            min_gap: 10_000,
+            timeout: DEFAULT_FAST_SYNC_TIMEOUT,
+            serve_state: false,
+            max_state_size: DEFAULT_MAX_STATE_SIZE,
+            state_export_interval: DEFAULT_STATE_EXPORT_INTERVAL,
+        }
+    }
+}
@@ -14,25 +14,38 @@ use dusk_consensus::errors::{
     ConsensusError, HeaderError, OperationError, VstError,
 };
 use dusk_consensus::operations::{
-    CallParams, Operations, Output, VerificationOutput, Voter,
+    CallParams, Operations, Output, StepEvent, VerificationOutput, Voter,
 };
 use dusk_consensus::queue::MsgRegistry;
 use dusk_consensus::user::provisioners::ContextProvisioners;
 use metrics::gauge;
 use node_data::bls::PublicKeyBytes;
 use node_data::ledger::{to_str, Block, Fault, Hash, Header};
-use node_data::message::{payload, AsyncQueue, ConsensusHeader};
+use node_data::message::{
+    payload, AsyncQueue, ConsensusHeader, OverflowPolicy, Payload,
+};
 use node_data::{ledger, Serializable, StepName};
 use tokio::sync::{oneshot, Mutex, RwLock};
 use tokio::task::JoinHandle;
 use tracing::{debug, info, trace, warn};
 
-use crate::chain::header_validation::Validator;
+use crate::chain::conf::MemoryParams;
+use crate::chain::consensus_events::{ConsensusEvent, ConsensusEvents};
+use crate::chain::header_validation::{
+    verify_extra_data, verify_provisioners_root, verify_tx_order, Validator,
+};
+use crate::chain::keystore::ConsensusKeys;
+use crate::chain::last_iter;
 use crate::chain::metrics::AverageElapsedTime;
+use crate::chain::{health, seats};
 use crate::database::rocksdb::{
-    MD_AVG_PROPOSAL, MD_AVG_RATIFICATION, MD_AVG_VALIDATION, MD_LAST_ITER,
+    MD_AVG_PROPOSAL, MD_AVG_RATIFICATION, MD_AVG_VALIDATION,
 };
+use crate::database::round_history::StepRecord;
 use crate::database::{self, ConsensusStorage, Mempool, Metadata};
+use crate::disk_guard::{self, DiskStatus};
+use crate::mempool::policy::TxPolicy;
+use crate::network::reputation;
 use crate::{vm, Message};
 
 /// Consensus Service Task is responsible for running the consensus layer.
@@ -55,44 +68,70 @@ pub(crate) struct Task {
     task_id: u64,
 
     /// Loaded Consensus keys
-    pub keys: (
-        dusk_core::signatures::bls::SecretKey,
-        node_data::bls::PublicKey,
-    ),
+    pub keys: ConsensusKeys,
+
+    /// Reloaded consensus keys, replacing the loaded ones when the next task
+    /// is spawned
+    reloaded_keys: Option<ConsensusKeys>,
+
+    /// Policy of the transactions excluded from the candidates
+    tx_policy: TxPolicy,
+
+    /// Extra-data of the generated blocks
+    extra_data: Vec<u8>,
+
+    /// Channel the step events are published on
+    events: ConsensusEvents,
 }
 
 impl Task {
-    /// Creates a new consensus task with the given keys encrypted with password
-    /// from env var DUSK_CONSENSUS_KEYS_PASS.
+    /// Creates a new consensus task signing with the given unlocked keys.
     pub(crate) fn new_with_keys(
-        path: String,
+        keys: ConsensusKeys,
         max_inbound_size: usize,
-    ) -> anyhow::Result<Self> {
-        let pwd = std::env::var("DUSK_CONSENSUS_KEYS_PASS")
-            .map_err(|_| anyhow::anyhow!("DUSK_CONSENSUS_KEYS_PASS not set"))?;
-        info!(event = "loading consensus keys", path = path);
-        let keys = node_data::bls::load_keys(path, pwd)?;
-
-        info!(
-            event = "loaded consensus keys",
-            pubkey = format!("{:?}", keys.1)
-        );
-
-        Ok(Self {
+        tx_policy: TxPolicy,
+        extra_data: Vec<u8>,
+        memory: MemoryParams,
+        events: ConsensusEvents,
+    ) -> Self {
+        let task = Self {
             main_inbound: AsyncQueue::bounded(
                 max_inbound_size,
                 "consensus_inbound",
+            )
+            .with_memory_budget(
+                memory.consensus_inbound,
+                OverflowPolicy::DropFarthest,
             ),
             outbound: AsyncQueue::bounded(
                 max_inbound_size,
                 "consensus_outbound",
+            )
+            .with_memory_budget(
+                memory.consensus_outbound,
+                OverflowPolicy::DropOldest,
             ),
-            future_msg: Arc::new(Mutex::new(MsgRegistry::default())),
+            future_msg: Arc::new(Mutex::new(MsgRegistry::with_budget(
+                memory.future_msgs,
+            ))),
             result: AsyncQueue::bounded(1, "consensus_result"),
             running_task: None,
             task_id: 0,
             keys,
-        })
+            reloaded_keys: None,
+            tx_policy,
+            extra_data,
+            events,
+        };
+        health::register(&task);
+
+        task
+    }
+
+    /// Replaces the keys with `keys` from the next spawned task on, leaving
+    /// the running one unaffected
+    pub(crate) fn reload_keys(&mut self, keys: ConsensusKeys) {
+        self.reloaded_keys = Some(keys);
     }
 
     pub(crate) fn spawn<D: database::DB, VM: vm::VMExecution>(
@@ -105,6 +144,11 @@ impl Task {
         voters: Vec<Voter>,
     ) {
         let current = provisioners_list.to_current();
+        let round = tip.header().height + 1;
+        if let Some(keys) = self.reloaded_keys.take() {
+            self.keys.replace(keys);
+        }
+        let (sk, pk) = self.keys.for_round(round, &current).clone();
         let consensus_task = Consensus::new(
             self.main_inbound.clone(),
             self.outbound.clone(),
@@ -114,13 +158,16 @@ impl Task {
                 vm,
                 tip.header().clone(),
                 provisioners_list, // TODO: Avoid cloning
+                self.tx_policy.clone(),
+                self.extra_data.clone(),
+                self.events.clone(),
             )),
             Arc::new(Mutex::new(CandidateDB::new(db.clone()))),
         );
 
         let ru = RoundUpdate::new(
-            self.keys.1.clone(),
-            self.keys.0.clone(),
+            pk,
+            sk,
             tip.header(),
             base_timeout.clone(),
             voters,
@@ -142,6 +189,8 @@ impl Task {
         gauge!("dusk_provisioners_eligible").set(eligible_num as f64);
         gauge!("dusk_provisioners_all").set(all_num as f64);
 
+        health::task_spawned(ru.round);
+
         let id = self.task_id;
         let resp = self.result.clone();
         let (cancel_tx, cancel_rx) = oneshot::channel::<i32>();
@@ -171,6 +220,7 @@ impl Task {
             if let Err(e) = handle.await {
                 warn!("Unable to wait for abort {e}")
             }
+            health::task_stopped();
         }
     }
 
@@ -179,6 +229,7 @@ impl Task {
             if cancel_chan.send(0).is_err() {
                 warn!("Unable to send cancel for abort")
             };
+            health::task_stopped();
         }
     }
 
@@ -211,53 +262,84 @@ impl<DB: database::DB> dusk_consensus::commons::Database for CandidateDB<DB> {
             event = "store candidate block",
             height, iter, hash, prev_hash
         );
-        let _ = self.db.read().await.update(|txn| txn.store_candidate(b));
+        if disk_guard::status() >= DiskStatus::CandidatesPaused {
+            warn!(event = "candidate storage paused", height, iter);
+            return;
+        }
+        let prev_block_hash = b.header().prev_block_hash;
+        if let Err(e) = self.db.read().await.update(|txn| {
+            txn.store_candidate(b)?;
+            last_iter::raise(txn, prev_block_hash, iter)
+        }) {
+            warn!("Cannot write candidate to database {e:?}");
+        }
     }
     async fn store_validation_result(
         &mut self,
         consensus_header: &ConsensusHeader,
         validation_result: &payload::ValidationResult,
     ) {
-        let _ = self.db.read().await.update(|db| {
-            db.store_validation_result(consensus_header, validation_result)
-        });
+        if let Err(e) = self.db.read().await.update(|db| {
+            db.store_validation_result(consensus_header, validation_result)?;
+            last_iter::raise(
+                db,
+                consensus_header.prev_block_hash,
+                consensus_header.iteration,
+            )
+        }) {
+            warn!("Cannot write validation result to database {e:?}");
+        }
     }
     async fn get_last_iter(&self) -> (Hash, u8) {
-        let data = self
-            .db
+        self.db
             .read()
             .await
-            .view(|t| t.op_read(MD_LAST_ITER))
+            .view(|t| last_iter::read(t))
             .unwrap_or_else(|e| {
                 warn!("Cannot read last_iter from database {e:?}");
                 None
             })
-            .filter(|v| v.len() == 33)
             .unwrap_or_else(|| {
                 warn!("No last_iter saved, falling back to default");
-                [0u8; 33].to_vec()
-            });
-
-        let mut hash = [0u8; 32];
-        hash.copy_from_slice(&data[0..32]);
-
-        let iter = data[32];
-
-        (hash, iter)
+                ([0u8; 32], 0)
+            })
     }
     async fn store_last_iter(&mut self, (hash, iter): (Hash, u8)) {
-        let mut to_store = hash.to_vec();
-        to_store.push(iter);
-
         if let Err(e) = self
             .db
             .read()
             .await
-            .update(|t| t.op_write(MD_LAST_ITER, to_store))
+            .update(|t| last_iter::write(t, hash, iter))
         {
             warn!("Cannot write last_iter to database {e:?}");
         }
     }
+    async fn recover_last_iter(&mut self, prev_block_hash: Hash) {
+        if let Err(e) = self
+            .db
+            .read()
+            .await
+            .update(|t| last_iter::recover(t, prev_block_hash))
+        {
+            warn!("Cannot recover last_iter from database {e:?}");
+        }
+    }
+    async fn store_step_outcome(
+        &mut self,
+        ch: &ConsensusHeader,
+        step: StepName,
+        outcome: &Payload,
+    ) {
+        let record = StepRecord::new(*ch, step, outcome);
+        if let Err(e) = self
+            .db
+            .read()
+            .await
+            .update(|t| t.store_step_record(&record))
+        {
+            warn!("Cannot write step outcome to database {e:?}");
+        }
+    }
 }
 
 /// Implements Executor trait to mock Contract Storage calls.
@@ -266,6 +348,9 @@ pub struct Executor<DB: database::DB, VM: vm::VMExecution> {
     vm: Arc<RwLock<VM>>,
     tip_header: ledger::Header,
     provisioners: ContextProvisioners,
+    tx_policy: TxPolicy,
+    extra_data: Vec<u8>,
+    events: ConsensusEvents,
 }
 
 impl<DB: database::DB, VM: vm::VMExecution> Executor<DB, VM> {
@@ -274,12 +359,18 @@ impl<DB: database::DB, VM: vm::VMExecution> Executor<DB, VM> {
         vm: &Arc<RwLock<VM>>,
         tip_header: ledger::Header,
         provisioners: ContextProvisioners,
+        tx_policy: TxPolicy,
+        extra_data: Vec<u8>,
+        events: ConsensusEvents,
     ) -> Self {
         Executor {
             db: db.clone(),
             vm: vm.clone(),
             tip_header,
             provisioners,
+            tx_policy,
+            extra_data,
+            events,
         }
     }
 }
@@ -297,9 +388,15 @@ impl<DB: database::DB, VM: vm::VMExecution> Operations for Executor<DB, VM> {
             &self.provisioners,
         );
 
-        validator
+        let res = validator
             .execute_checks(candidate_header, expected_generator, false)
-            .await
+            .await?;
+
+        let vm = self.vm.read().await;
+        verify_provisioners_root(&*vm, &self.tip_header, candidate_header)?;
+        verify_extra_data(&*vm, candidate_header)?;
+
+        Ok(res)
     }
 
     async fn verify_faults(
@@ -324,8 +421,10 @@ impl<DB: database::DB, VM: vm::VMExecution> Operations for Executor<DB, VM> {
         info!("verifying state");
 
         let vm = self.vm.read().await;
+        let ctx = vm::BlockContext::new(blk.header(), &*vm);
 
-        vm.verify_state_transition(prev_root, blk, voters)
+        verify_tx_order(&ctx, blk)?;
+        vm.verify_state_transition(prev_root, blk, &ctx, voters)
     }
 
     async fn execute_state_transition(
@@ -334,19 +433,48 @@ impl<DB: database::DB, VM: vm::VMExecution> Operations for Executor<DB, VM> {
     ) -> Result<Output, OperationError> {
         info!("executing state transition");
         let vm = self.vm.read().await;
+        let ctx = vm::BlockContext::candidate(
+            params.round,
+            *params.generator_pubkey.bytes(),
+            &*vm,
+        );
+        let canonical = ctx.with_canonical_tx_order();
 
         let db = self.db.read().await;
         let (executed_txs, discarded_txs, verification_output) = db
             .view(|view| {
-                let txs = view.mempool_txs_sorted_by_fee().map_err(|err| {
+                // The VM stops including transactions once the block is full,
+                // so only the ones that can't fit at all are skipped here
+                let max_gas = ctx.gas_limit;
+                let txs = if canonical {
+                    view.txs_in_canonical_order(max_gas)
+                } else {
+                    view.txs_sorted_by_fee(usize::MAX, max_gas)
+                }
+                .map_err(|err| {
                     anyhow::anyhow!("failed to get mempool txs: {}", err)
-                })?;
-                let ret = vm.execute_state_transition(&params, txs).map_err(
-                    |err| anyhow::anyhow!("failed to call EST {}", err),
-                )?;
+                })?
+                .filter(|tx| !self.tx_policy.denies(tx, "candidate"));
+                let ret =
+                    vm.execute_state_transition(&params, &ctx, txs).map_err(
+                        |err| anyhow::anyhow!("failed to call EST {}", err),
+                    )?;
                 Ok(ret)
             })
             .map_err(OperationError::InvalidEST)?;
+
+        // The bundle positions are only part of the blocks in canonical order
+        let executed_txs = if canonical {
+            executed_txs
+        } else {
+            executed_txs
+                .into_iter()
+                .map(|mut tx| {
+                    tx.inner = tx.inner.without_bundle();
+                    tx
+                })
+                .collect()
+        };
         let _ = db.update(|m| {
             for t in &discarded_txs {
                 if let Ok(_removed) = m.delete_mempool_tx(t.id(), true) {
@@ -365,6 +493,32 @@ impl<DB: database::DB, VM: vm::VMExecution> Operations for Executor<DB, VM> {
         })
     }
 
+    async fn step_started(
+        &self,
+        round: u64,
+        iteration: u8,
+        step_name: StepName,
+    ) {
+        health::step_started(round, iteration, step_name);
+        self.events
+            .publish(ConsensusEvent::step_started(round, iteration, step_name));
+    }
+
+    async fn committee_seats(
+        &self,
+        round: u64,
+        iteration: u8,
+        step_name: StepName,
+        seats: usize,
+        credits: usize,
+    ) {
+        seats::record(round, iteration, step_name, seats, credits);
+    }
+
+    async fn step_event(&self, event: StepEvent) {
+        self.events.publish(event.into());
+    }
+
     async fn add_step_elapsed_time(
         &self,
         _round: u64,
@@ -402,4 +556,35 @@ impl<DB: database::DB, VM: vm::VMExecution> Operations for Executor<DB, VM> {
     async fn get_block_gas_limit(&self) -> u64 {
         self.vm.read().await.get_block_gas_limit()
     }
+
+    async fn get_provisioners_root(
+        &self,
+        round: u64,
+        prev_state_root: [u8; 32],
+    ) -> Result<Option<[u8; 32]>, OperationError> {
+        self.vm
+            .read()
+            .await
+            .get_provisioners_root(round, prev_state_root)
+            .map_err(OperationError::InvalidEST)
+    }
+
+    async fn get_header_extra_data(&self, round: u64) -> Option<Vec<u8>> {
+        self.vm
+            .read()
+            .await
+            .with_header_extra_data(round)
+            .then(|| self.extra_data.clone())
+    }
+
+    async fn report_invalid_signature(&self, msg: &Message) {
+        if let Some(md) = &msg.metadata {
+            // The sender may be relaying a message forged by another peer
+            reputation::penalize_relayed(
+                md.src_addr.ip(),
+                reputation::PENALTY_INVALID_SIGNATURE,
+                "invalid signature",
+            );
+        }
+    }
 }
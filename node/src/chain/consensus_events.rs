@@ -0,0 +1,230 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Stream of the consensus step events, for observability tooling.
+//!
+//! The events notified by the consensus task are published on a broadcast
+//! channel holding the last [`CAPACITY`] ones. A subscriber lagging behind
+//! misses the oldest events instead of slowing the consensus down.
+//!
+//! The channel is shared, through [`ConsensusEvents`], by the chain service
+//! publishing the events and the services streaming them.
+
+use dusk_consensus::operations::StepEvent;
+use node_data::message::payload::Vote;
+use node_data::StepName;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+
+/// Number of events kept for the lagging subscribers
+const CAPACITY: usize = 1024;
+
+/// Channel of the consensus events
+#[derive(Debug, Clone)]
+pub struct ConsensusEvents(broadcast::Sender<ConsensusEvent>);
+
+impl Default for ConsensusEvents {
+    fn default() -> Self {
+        Self(broadcast::channel(CAPACITY).0)
+    }
+}
+
+impl ConsensusEvents {
+    /// Publishes `event` to the subscribers
+    pub(super) fn publish(&self, event: ConsensusEvent) {
+        // Sending only fails without subscribers
+        let _ = self.0.send(event);
+    }
+
+    /// Subscribes to the events published from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<ConsensusEvent> {
+        self.0.subscribe()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum ConsensusEvent {
+    StepStarted {
+        round: u64,
+        iteration: u8,
+        step: String,
+    },
+    QuorumReached {
+        round: u64,
+        iteration: u8,
+        vote: Vote,
+    },
+    TimeoutIncreased {
+        round: u64,
+        iteration: u8,
+        step: String,
+        timeout_ms: u64,
+    },
+    VotesReceived {
+        round: u64,
+        iteration: u8,
+        step: String,
+        count: usize,
+    },
+}
+
+impl ConsensusEvent {
+    pub(super) fn step_started(
+        round: u64,
+        iteration: u8,
+        step_name: StepName,
+    ) -> Self {
+        Self::StepStarted {
+            round,
+            iteration,
+            step: step(step_name),
+        }
+    }
+
+    pub fn round(&self) -> u64 {
+        match self {
+            Self::StepStarted { round, .. }
+            | Self::QuorumReached { round, .. }
+            | Self::TimeoutIncreased { round, .. }
+            | Self::VotesReceived { round, .. } => *round,
+        }
+    }
+}
+
+fn step(step_name: StepName) -> String {
+    format!("{step_name:?}").to_lowercase()
+}
+
+impl From<StepEvent> for ConsensusEvent {
+    fn from(event: StepEvent) -> Self {
+        match event {
+            StepEvent::QuorumReached {
+                round,
+                iteration,
+                vote,
+            } => Self::QuorumReached {
+                round,
+                iteration,
+                vote,
+            },
+            StepEvent::TimeoutIncreased {
+                round,
+                iteration,
+                step_name,
+                timeout,
+            } => Self::TimeoutIncreased {
+                round,
+                iteration,
+                step: step(step_name),
+                timeout_ms: timeout.as_millis() as u64,
+            },
+            StepEvent::VotesReceived {
+                round,
+                iteration,
+                step_name,
+                count,
+            } => Self::VotesReceived {
+                round,
+                iteration,
+                step: step(step_name),
+                count,
+            },
+        }
+    }
+}
+
+/// Range of the rounds whose events are streamed, unbounded on the unset
+/// sides
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+pub struct RoundRange {
+    #[serde(default)]
+    pub from_round: Option<u64>,
+    #[serde(default)]
+    pub to_round: Option<u64>,
+}
+
+impl RoundRange {
+    pub fn contains(&self, round: u64) -> bool {
+        self.from_round.map_or(true, |from| round >= from)
+            && self.to_round.map_or(true, |to| round <= to)
+    }
+
+    /// Returns whether no event of `round`, or of the rounds after it, is
+    /// in the range
+    pub fn is_over(&self, round: u64) -> bool {
+        self.to_round.is_some_and(|to| round > to)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_consensus_events() {
+        let channel = ConsensusEvents::default();
+        let mut events = channel.subscribe();
+
+        channel.publish(ConsensusEvent::step_started(
+            7,
+            1,
+            StepName::Validation,
+        ));
+        channel.publish(
+            StepEvent::TimeoutIncreased {
+                round: 7,
+                iteration: 1,
+                step_name: StepName::Validation,
+                timeout: Duration::from_millis(1500),
+            }
+            .into(),
+        );
+
+        let event = events.try_recv().expect("step started");
+        assert_eq!(
+            serde_json::to_value(&event).unwrap(),
+            json!({
+                "event": "step_started",
+                "round": 7,
+                "iteration": 1,
+                "step": "validation",
+            })
+        );
+        let event = events.try_recv().expect("timeout increased");
+        assert_eq!(
+            event,
+            ConsensusEvent::TimeoutIncreased {
+                round: 7,
+                iteration: 1,
+                step: "validation".into(),
+                timeout_ms: 1500,
+            }
+        );
+        assert!(events.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_round_range() {
+        let range = RoundRange {
+            from_round: Some(10),
+            to_round: Some(12),
+        };
+        assert!(!range.contains(9));
+        assert!(range.contains(10) && range.contains(12));
+        assert!(!range.contains(13));
+        assert!(!range.is_over(12));
+        assert!(range.is_over(13));
+
+        let range = RoundRange::default();
+        assert!(range.contains(0) && range.contains(u64::MAX));
+        assert!(!range.is_over(u64::MAX));
+    }
+}
@@ -0,0 +1,406 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Diagnostics of provisioner list and state divergences.
+//!
+//! The provisioner list is incrementally updated with the stake events of each
+//! accepted block. When a change can't be applied to the in-memory list, a
+//! [`ProvisionerDivergence`] is raised. It can be dumped to a diagnostic file
+//! with [`ProvisionerDivergence::report`], and the divergent keys reconciled
+//! against the VM with [`repair`].
+//!
+//! When the execution of a block results in a state other than the one its
+//! header commits to, a [`StateDivergence`] is raised instead, listing the
+//! outcome of every executed transaction so that it can be compared with the
+//! one of another node. A block diverging again after the node recovered from
+//! its divergence halts the block acceptance, see [`DivergenceRetries`].
+
+use std::fmt::{self, Write};
+use std::path::PathBuf;
+use std::{env, fs, io};
+
+use dusk_consensus::operations::VerificationOutput;
+use dusk_consensus::user::provisioners::Provisioners;
+use dusk_consensus::user::stake::Stake;
+use node_data::bls::PublicKey;
+use node_data::events::contract::ContractTxEvent;
+use node_data::ledger::{to_str, SpentTransaction};
+
+/// Directory where divergence reports are written.
+///
+/// Defaults to the system temporary directory.
+const DIVERGENCE_DIR_ENV: &str = "RUSK_DIVERGENCE_DIR";
+
+/// A provisioner change that could not be applied to the provisioner list
+#[derive(Debug)]
+pub(crate) struct ProvisionerDivergence {
+    /// Height of the block emitting the change
+    pub block_height: u64,
+    pub reason: &'static str,
+    /// The conflicting change
+    pub change: String,
+    /// The affected key
+    pub account: PublicKey,
+    /// Keys changed by the block
+    pub changed_keys: Vec<PublicKey>,
+    /// The provisioner list before the block
+    pub before: Provisioners,
+    /// The provisioner list at the moment the change failed
+    pub partial: Provisioners,
+}
+
+impl fmt::Display for ProvisionerDivergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} at block {} (account: {})",
+            self.reason,
+            self.block_height,
+            self.account.to_bs58()
+        )
+    }
+}
+
+impl std::error::Error for ProvisionerDivergence {}
+
+impl ProvisionerDivergence {
+    /// Writes the divergence to a diagnostic file, returning its path
+    pub fn report(&self) -> io::Result<PathBuf> {
+        let name = format!("provisioners_divergence_{}.log", self.block_height);
+        write_report(&name, &self.to_report())
+    }
+
+    fn to_report(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "reason: {}", self.reason);
+        let _ = writeln!(out, "block_height: {}", self.block_height);
+        let _ = writeln!(out, "account: {}", self.account.to_bs58());
+        let _ = writeln!(out, "change: {}", self.change);
+
+        for (name, list) in
+            [("before", &self.before), ("partial", &self.partial)]
+        {
+            let _ = writeln!(out, "\n[{name}]");
+            for (pk, stake) in list.iter() {
+                let _ = writeln!(
+                    out,
+                    "{} value={} eligible_since={}",
+                    pk.to_bs58(),
+                    stake.value(),
+                    stake.eligible_since
+                );
+            }
+        }
+        out
+    }
+}
+
+/// Times the same block can diverge before the block acceptance is halted
+pub(crate) const MAX_STATE_DIVERGENCES: u32 = 3;
+
+/// Consecutive divergences of the block at the same height.
+///
+/// A divergence caused by the local state is deterministic: reverting and
+/// syncing the block again only diverges again. The acceptance is therefore
+/// halted once the same height diverged [`MAX_STATE_DIVERGENCES`] times in a
+/// row, so that the node stops instead of looping.
+#[derive(Debug, Default)]
+pub(crate) struct DivergenceRetries {
+    /// Height of the last divergence, and how many times it diverged
+    last: Option<(u64, u32)>,
+}
+
+impl DivergenceRetries {
+    /// Records a divergence of the block at `height`, returning true if the
+    /// acceptance must be halted
+    pub fn record(&mut self, height: u64) -> bool {
+        let count = match self.last {
+            Some((last, count)) if last == height => count + 1,
+            _ => 1,
+        };
+        self.last = Some((height, count));
+        self.is_halted()
+    }
+
+    /// Returns true if the acceptance is halted
+    pub fn is_halted(&self) -> bool {
+        self.last
+            .is_some_and(|(_, count)| count >= MAX_STATE_DIVERGENCES)
+    }
+}
+
+/// Outcome of a transaction of a block whose state diverged
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExecutedTx {
+    pub id: [u8; 32],
+    pub gas_spent: u64,
+    pub err: Option<String>,
+    /// Digests of the events emitted by the transaction, in order
+    pub events: Vec<[u8; 32]>,
+}
+
+/// A block whose execution resulted in a state other than the one committed
+/// to by its header
+#[derive(Debug)]
+pub struct StateDivergence {
+    pub block_height: u64,
+    pub block_hash: [u8; 32],
+    /// Outcome committed to by the block header
+    pub expected: VerificationOutput,
+    /// Outcome of the local execution
+    pub computed: VerificationOutput,
+    pub txs: Vec<ExecutedTx>,
+    /// Digests of the events not emitted by a transaction, such as the
+    /// rewards and slashes of the block
+    pub block_events: Vec<[u8; 32]>,
+}
+
+impl fmt::Display for StateDivergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "state root {} diverges from {} at block {}",
+            to_str(&self.computed.state_root),
+            to_str(&self.expected.state_root),
+            self.block_height,
+        )
+    }
+}
+
+impl std::error::Error for StateDivergence {}
+
+impl StateDivergence {
+    /// Collects the outcome of the execution of a block, from the spent
+    /// transactions and the contract events it emitted
+    pub fn new(
+        block_height: u64,
+        block_hash: [u8; 32],
+        expected: VerificationOutput,
+        computed: VerificationOutput,
+        txs: &[SpentTransaction],
+        events: &[ContractTxEvent],
+    ) -> Self {
+        let txs: Vec<_> = txs
+            .iter()
+            .map(|spent| {
+                let id = spent.inner.id();
+                ExecutedTx {
+                    id,
+                    gas_spent: spent.gas_spent,
+                    err: spent.err.as_ref().map(ToString::to_string),
+                    events: events
+                        .iter()
+                        .filter(|e| e.origin == id)
+                        .map(|e| e.event.digest())
+                        .collect(),
+                }
+            })
+            .collect();
+        let block_events = events
+            .iter()
+            .filter(|e| !txs.iter().any(|tx| tx.id == e.origin))
+            .map(|e| e.event.digest())
+            .collect();
+
+        Self {
+            block_height,
+            block_hash,
+            expected,
+            computed,
+            txs,
+            block_events,
+        }
+    }
+
+    /// Writes the divergence to a diagnostic file, returning its path
+    pub fn report(&self) -> io::Result<PathBuf> {
+        let name = format!("state_divergence_{}.log", self.block_height);
+        write_report(&name, &self.to_report())
+    }
+
+    fn to_report(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "block_height: {}", self.block_height);
+        let _ = writeln!(out, "block_hash: {}", hex::encode(self.block_hash));
+        let _ = writeln!(out, "expected: {}", self.expected);
+        let _ = writeln!(out, "computed: {}", self.computed);
+
+        let _ = writeln!(out, "\n[txs]");
+        for tx in &self.txs {
+            let _ = writeln!(
+                out,
+                "{} gas_spent={} err={}",
+                hex::encode(tx.id),
+                tx.gas_spent,
+                tx.err.as_deref().unwrap_or("none"),
+            );
+            for event in &tx.events {
+                let _ = writeln!(out, "  event {}", hex::encode(event));
+            }
+        }
+
+        let _ = writeln!(out, "\n[block_events]");
+        for event in &self.block_events {
+            let _ = writeln!(out, "event {}", hex::encode(event));
+        }
+        out
+    }
+}
+
+/// Writes a diagnostic file named `name` in the divergence reports directory
+fn write_report(name: &str, report: &str) -> io::Result<PathBuf> {
+    let dir = env::var(DIVERGENCE_DIR_ENV)
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| env::temp_dir());
+    fs::create_dir_all(&dir)?;
+
+    let path = dir.join(name);
+    fs::write(&path, report)?;
+    Ok(path)
+}
+
+/// Reconciles `keys` of the `current` provisioner list with the stakes
+/// returned by `fetch`, leaving all the other members untouched.
+///
+/// Members with no stake, or a stake of 0, are removed.
+pub(crate) fn repair<F>(
+    current: &Provisioners,
+    keys: &[PublicKey],
+    fetch: F,
+) -> anyhow::Result<Provisioners>
+where
+    F: Fn(&PublicKey) -> anyhow::Result<Option<Stake>>,
+{
+    let mut repaired = current.clone();
+    for pk in keys {
+        match fetch(pk)? {
+            Some(stake) if stake.value() > 0 => {
+                repaired.replace_stake(pk.clone(), stake);
+            }
+            _ => {
+                repaired.remove_stake(pk);
+            }
+        }
+    }
+    Ok(repaired)
+}
+
+#[cfg(test)]
+mod tests {
+    use dusk_core::signatures::bls::{
+        PublicKey as BlsPublicKey, SecretKey as BlsSecretKey,
+    };
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use dusk_core::abi::{ContractId, CONTRACT_ID_BYTES};
+    use node_data::events::contract::ContractEvent;
+    use node_data::ledger::faker::gen_dummy_tx;
+
+    use super::*;
+
+    fn public_key(rng: &mut StdRng) -> PublicKey {
+        let sk = BlsSecretKey::random(rng);
+        PublicKey::new(BlsPublicKey::from(&sk))
+    }
+
+    #[test]
+    fn repair_divergent_keys() {
+        let rng = &mut StdRng::seed_from_u64(0xdead);
+        let untouched = public_key(rng);
+        let updated = public_key(rng);
+        let removed = public_key(rng);
+        let added = public_key(rng);
+
+        let mut current = Provisioners::empty();
+        current.add_member_with_value(untouched.clone(), 10);
+        current.add_member_with_value(updated.clone(), 20);
+        current.add_member_with_value(removed.clone(), 30);
+
+        let keys = [updated.clone(), removed.clone(), added.clone()];
+        let repaired = repair(&current, &keys, |pk| {
+            let stake = if *pk == updated {
+                Some(Stake::new(25, 2))
+            } else if *pk == added {
+                Some(Stake::new(40, 3))
+            } else {
+                None
+            };
+            Ok(stake)
+        })
+        .expect("repair to succeed");
+
+        let members: Vec<_> = repaired
+            .iter()
+            .map(|(pk, stake)| (pk.clone(), stake.value()))
+            .collect();
+        assert_eq!(members.len(), 3);
+        assert!(members.contains(&(untouched, 10)));
+        assert!(members.contains(&(updated, 25)));
+        assert!(members.contains(&(added, 40)));
+    }
+
+    #[test]
+    fn state_divergence_retries() {
+        let mut retries = DivergenceRetries::default();
+        for _ in 1..MAX_STATE_DIVERGENCES {
+            assert!(!retries.record(10));
+        }
+
+        // Another height starts the count over
+        assert!(!retries.record(11));
+        for _ in 2..MAX_STATE_DIVERGENCES {
+            assert!(!retries.record(11));
+        }
+        assert!(!retries.is_halted());
+
+        assert!(retries.record(11));
+        assert!(retries.is_halted());
+    }
+
+    #[test]
+    fn state_divergence_events() {
+        let txs: Vec<_> = [1, 2]
+            .into_iter()
+            .map(|price| {
+                SpentTransaction::new(gen_dummy_tx(price), 7, 10, None)
+            })
+            .collect();
+
+        let event = |origin: [u8; 32], data: u8| ContractTxEvent {
+            event: ContractEvent {
+                target: ContractId::from_bytes([0; CONTRACT_ID_BYTES]),
+                topic: "topic".to_string(),
+                data: vec![data],
+            },
+            origin,
+        };
+        let (first, second) = (txs[0].inner.id(), txs[1].inner.id());
+        let events = [
+            event(first, 1),
+            event(second, 2),
+            event(first, 3),
+            event([9; 32], 4),
+        ];
+
+        let divergence = StateDivergence::new(
+            7,
+            [9; 32],
+            VerificationOutput::default(),
+            VerificationOutput::default(),
+            &txs,
+            &events,
+        );
+        assert_eq!(divergence.block_height, 7);
+        assert_eq!(divergence.txs.len(), 2);
+        assert_eq!(
+            divergence.txs[0].events,
+            vec![events[0].event.digest(), events[2].event.digest()]
+        );
+        assert_eq!(divergence.txs[1].events, vec![events[1].event.digest()]);
+        assert_eq!(divergence.block_events, vec![events[3].event.digest()]);
+    }
+}
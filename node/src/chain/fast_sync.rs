@@ -0,0 +1,793 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Fast sync of the chain state from remote peers.
+//!
+//! Executing every block since the genesis makes the initial sync of a node
+//! slow. When the local tip is far enough behind, the node instead downloads
+//! the finalized VM state of a peer, along with the blocks preceding it:
+//! 1. the last finalized block of the peer is confirmed by a quorum of peers
+//! 2. the state archive is staged, once its root is recomputed and checked
+//!    against the state hash of the synced block
+//! 3. the block following the synced one must be attested by the committees
+//!    extracted from the provisioners of the staged state
+//! 4. the blocks from the local tip up to the synced one are downloaded, their
+//!    hash chain and roots are verified, and they are stored with the labels of
+//!    the peer, which must be final
+//! 5. the staged state replaces the local one, and the synced block becomes the
+//!    tip the normal block acceptance continues from
+//!
+//! The attestations of the downloaded blocks are only checked to vote for
+//! their block, as for [`verify_hash_chain`]: verifying them against their
+//! provisioners would require executing the blocks. The synced block is
+//! trusted because a quorum of peers agrees on it, and because the
+//! provisioners of its state reached a quorum on its successor: a forged
+//! state would need the keys of the committees it elects.
+//!
+//! Peers serve the sync through the `fast-sync` RUES topics: `info` returns
+//! the header of their last finalized block, `headers` and `blocks` return
+//! the items of a [`HeightRange`] one after the other, and `state` returns a
+//! [`encode_snapshot`] of their finalized state. The state is only served if
+//! enabled, see [`FastSyncParams::serve_state`].
+//!
+//! [`verify_hash_chain`]: super::verify_hash_chain
+
+use std::io::{self, Read, Write};
+
+use dusk_consensus::merkle::merkle_root;
+use dusk_consensus::quorum::bundle::QuorumBundle;
+use dusk_consensus::user::provisioners::Provisioners;
+use node_data::ledger::{Fault, Header, Label, SpentTransaction};
+use node_data::Serializable;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use super::conf::FastSyncParams;
+use super::hash_chain::{self, HashChainError};
+use crate::database::rocksdb::{MD_HASH_KEY, MD_STATE_ROOT_KEY};
+use crate::database::{self, Ledger, Metadata};
+
+/// Maximum number of headers returned by a `headers` request
+pub const MAX_HEADERS_PER_REQUEST: u64 = 1000;
+
+/// Maximum number of blocks returned by a `blocks` request
+pub const MAX_BLOCKS_PER_REQUEST: u64 = 100;
+
+/// Range of heights requested to a peer, both included
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HeightRange {
+    pub from: u64,
+    pub to: u64,
+}
+
+impl HeightRange {
+    /// Returns the heights of the range, failing if the range is empty or
+    /// longer than `max`
+    pub fn heights(
+        &self,
+        max: u64,
+    ) -> anyhow::Result<impl Iterator<Item = u64>> {
+        if self.to < self.from || self.to - self.from >= max {
+            anyhow::bail!("Invalid range {}..={}", self.from, self.to);
+        }
+        Ok(self.from..=self.to)
+    }
+}
+
+/// A block as stored in the ledger, along with its label and the outcome of
+/// its transactions
+#[derive(Debug, Clone)]
+pub struct LedgerBlock {
+    pub header: Header,
+    pub label: Label,
+    pub txs: Vec<SpentTransaction>,
+    pub faults: Vec<Fault>,
+}
+
+impl LedgerBlock {
+    /// Checks the transactions and faults against the roots of the header,
+    /// as they aren't covered by the block hash
+    fn verify_roots(&self) -> anyhow::Result<()> {
+        let height = self.header.height;
+
+        let tx_digests: Vec<_> =
+            self.txs.iter().map(|t| t.inner.digest()).collect();
+        if merkle_root(&tx_digests[..]) != self.header.txroot {
+            anyhow::bail!("block at height {height} has invalid transactions");
+        }
+
+        let fault_digests: Vec<_> =
+            self.faults.iter().map(|f| f.digest()).collect();
+        if merkle_root(&fault_digests[..]) != self.header.faultroot {
+            anyhow::bail!("block at height {height} has invalid faults");
+        }
+
+        Ok(())
+    }
+}
+
+impl Serializable for LedgerBlock {
+    fn write<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        self.header.write(w)?;
+        self.label.write(w)?;
+
+        w.write_all(&(self.txs.len() as u32).to_le_bytes())?;
+        for t in &self.txs {
+            t.write(w)?;
+        }
+
+        w.write_all(&(self.faults.len() as u32).to_le_bytes())?;
+        for f in &self.faults {
+            f.write(w)?;
+        }
+        Ok(())
+    }
+
+    fn read<R: Read>(r: &mut R) -> io::Result<Self>
+    where
+        Self: Sized,
+    {
+        let header = Header::read(r)?;
+        let label = Label::read(r)?;
+
+        let txs_len = Self::read_u32_le(r)?;
+        let txs = (0..txs_len)
+            .map(|_| SpentTransaction::read(r))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let faults_len = Self::read_u32_le(r)?;
+        let faults = (0..faults_len)
+            .map(|_| Fault::read(r))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self {
+            header,
+            label,
+            txs,
+            faults,
+        })
+    }
+}
+
+/// Serializes items one after the other
+pub fn encode_all<'a, T: Serializable + 'a>(
+    items: impl IntoIterator<Item = &'a T>,
+) -> io::Result<Vec<u8>> {
+    let mut buf = vec![];
+    for item in items {
+        item.write(&mut buf)?;
+    }
+    Ok(buf)
+}
+
+/// Deserializes the items serialized one after the other in `buf`
+pub fn decode_all<T: Serializable>(mut buf: &[u8]) -> io::Result<Vec<T>> {
+    let mut items = vec![];
+    while !buf.is_empty() {
+        items.push(T::read(&mut buf)?);
+    }
+    Ok(items)
+}
+
+/// Encodes a state snapshot: the header of the finalized block, followed by
+/// the archive of the state whose root is the block state hash
+pub fn encode_snapshot(header: &Header, archive: &[u8]) -> io::Result<Vec<u8>> {
+    let mut buf = vec![];
+    header.write(&mut buf)?;
+    buf.extend_from_slice(archive);
+    Ok(buf)
+}
+
+fn decode_snapshot(mut buf: &[u8]) -> io::Result<(Header, &[u8])> {
+    let header = Header::read(&mut buf)?;
+    Ok((header, buf))
+}
+
+/// Returns the header of the last finalized block of `ledger`, as served by
+/// the `info` topic
+pub fn last_final_header<L: Ledger + Metadata>(
+    ledger: &L,
+) -> anyhow::Result<Header> {
+    let tip = ledger
+        .op_read(MD_HASH_KEY)?
+        .and_then(|hash| ledger.block_header(&hash).transpose())
+        .ok_or_else(|| anyhow::anyhow!("Cannot read tip"))??;
+
+    for height in (0..=tip.height).rev() {
+        if let Some((hash, Label::Final(_))) =
+            ledger.block_label_by_height(height)?
+        {
+            return ledger.block_header(&hash)?.ok_or_else(|| {
+                anyhow::anyhow!("Cannot read block at {height}")
+            });
+        }
+    }
+    anyhow::bail!("No final block found")
+}
+
+/// Returns the headers of `range` stored in `ledger`, up to the first missing
+/// one, as served by the `headers` topic
+pub fn range_headers<L: Ledger>(
+    ledger: &L,
+    range: HeightRange,
+) -> anyhow::Result<Vec<Header>> {
+    let mut headers = vec![];
+    for height in range.heights(MAX_HEADERS_PER_REQUEST)? {
+        let Some(hash) = ledger.block_hash_by_height(height)? else {
+            break;
+        };
+        match ledger.block_header(&hash)? {
+            Some(header) => headers.push(header),
+            None => break,
+        }
+    }
+    Ok(headers)
+}
+
+/// Returns the blocks of `range` stored in `ledger`, up to the first missing
+/// one, as served by the `blocks` topic
+pub fn range_blocks<L: Ledger>(
+    ledger: &L,
+    range: HeightRange,
+) -> anyhow::Result<Vec<LedgerBlock>> {
+    let mut blocks = vec![];
+    for height in range.heights(MAX_BLOCKS_PER_REQUEST)? {
+        let Some((hash, label)) = ledger.block_label_by_height(height)? else {
+            break;
+        };
+        let Some(block) = ledger.light_block(&hash)? else {
+            break;
+        };
+        blocks.push(LedgerBlock {
+            txs: ledger.ledger_txs(block.transactions_ids.iter().collect())?,
+            faults: ledger.faults(&block.faults_ids)?,
+            header: block.header,
+            label,
+        });
+    }
+    Ok(blocks)
+}
+
+/// Verifies `header` as the successor of `prev`, or as the genesis if there's
+/// no previous header
+fn verify_next(
+    prev: Option<&Header>,
+    header: &Header,
+) -> Result<(), HashChainError> {
+    match prev {
+        Some(prev) => hash_chain::verify_next(prev, header),
+        None => hash_chain::verify_header(header, 0),
+    }
+}
+
+/// State downloaded from a peer, staged until the synced block is verified
+pub trait StagedState {
+    /// Returns the provisioners of the staged state, which are the ones
+    /// eligible for the block following the synced one
+    fn provisioners(&self) -> anyhow::Result<Provisioners>;
+
+    /// Replaces the local state with the staged one
+    fn commit(self) -> anyhow::Result<()>;
+}
+
+pub struct FastSync {
+    conf: FastSyncParams,
+    client: reqwest::Client,
+}
+
+impl FastSync {
+    pub fn new(conf: FastSyncParams) -> Self {
+        info!("FastSync::new with conf {:?}", conf);
+        let client = reqwest::Client::builder()
+            .timeout(conf.timeout)
+            .build()
+            .unwrap_or_default();
+        Self { conf, client }
+    }
+
+    /// Syncs the state from the configured peers, if the local tip is at
+    /// least `min_gap` blocks behind them.
+    ///
+    /// `stage` is called with the downloaded state archive and its expected
+    /// root. It must unpack the archive aside the local state, failing if its
+    /// root doesn't match. The staged state is only committed once the synced
+    /// block is verified and the blocks preceding it are stored.
+    ///
+    /// Returns the header of the new tip, if the state was synced. Failures
+    /// to download or verify the state of a peer only skip to the next peer.
+    pub async fn run<DB, S, F>(
+        &self,
+        db: &DB,
+        stage: F,
+    ) -> anyhow::Result<Option<Header>>
+    where
+        DB: database::DB,
+        S: StagedState,
+        F: Fn(&[u8], [u8; 32]) -> anyhow::Result<S>,
+    {
+        if self.conf.peers.is_empty() {
+            return Ok(None);
+        }
+
+        let local = db.view(|t| match t.op_read(MD_HASH_KEY)? {
+            Some(hash) => t.block_header(&hash),
+            None => Ok(None),
+        })?;
+
+        for peer in &self.conf.peers {
+            match self.sync_from(db, peer, local.as_ref(), &stage).await {
+                Ok(Some(header)) => return Ok(Some(header)),
+                Ok(None) => {
+                    info!(event = "fast sync not needed", peer);
+                    return Ok(None);
+                }
+                Err(err) => warn!(event = "fast sync failed", peer, %err),
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Syncs the state of `peer`, unless `local` is less than `min_gap`
+    /// blocks behind it
+    async fn sync_from<DB, S, F>(
+        &self,
+        db: &DB,
+        peer: &str,
+        local: Option<&Header>,
+        stage: &F,
+    ) -> anyhow::Result<Option<Header>>
+    where
+        DB: database::DB,
+        S: StagedState,
+        F: Fn(&[u8], [u8; 32]) -> anyhow::Result<S>,
+    {
+        let next_height = local.map_or(0, |h| h.height + 1);
+
+        let info = self.request(peer, "info", &()).await?;
+        let info = Header::read(&mut &info[..])?;
+        if info.height < next_height + self.conf.min_gap {
+            return Ok(None);
+        }
+
+        // The state is downloaded first, the peer finalizing new blocks in
+        // the meantime
+        let state = self.request(peer, "state", &()).await?;
+        let (header, archive) = decode_snapshot(&state)?;
+        info!(
+            event = "fast sync state downloaded",
+            peer,
+            height = header.height,
+            size = archive.len(),
+        );
+
+        let confirmations = self.confirmations(peer, &header).await;
+        if confirmations < self.conf.quorum {
+            anyhow::bail!(
+                "block at height {} confirmed by {confirmations} peers only",
+                header.height
+            );
+        }
+
+        let staged = stage(archive, header.state_hash)?;
+        self.verify_successor(peer, &header, &staged).await?;
+        info!(event = "fast sync state verified", height = header.height);
+
+        self.store_blocks(db, peer, local, &header).await?;
+
+        staged.commit()?;
+        db.update(|t| {
+            t.op_write(MD_HASH_KEY, header.hash)?;
+            t.op_write(MD_STATE_ROOT_KEY, header.state_hash)
+        })?;
+
+        info!(
+            event = "fast sync completed",
+            height = header.height,
+            hash = hex::encode(header.hash),
+            state_root = hex::encode(header.state_hash),
+        );
+        Ok(Some(header))
+    }
+
+    /// Verifies that the block following `header` is attested by the
+    /// committees of the provisioners of the `staged` state
+    async fn verify_successor<S: StagedState>(
+        &self,
+        peer: &str,
+        header: &Header,
+        staged: &S,
+    ) -> anyhow::Result<()> {
+        let height = header.height + 1;
+        let range = HeightRange {
+            from: height,
+            to: height,
+        };
+        let next = self.fetch::<Header>(peer, "headers", range).await?;
+        let next = &next[0];
+        hash_chain::verify_next(header, next)?;
+
+        let provisioners = staged.provisioners()?;
+        QuorumBundle::export(next, header.seed, &provisioners).map_err(
+            |err| {
+                anyhow::anyhow!(
+                    "block at height {height} not attested by the synced \
+                     provisioners: {err}"
+                )
+            },
+        )?;
+        Ok(())
+    }
+
+    /// Downloads, verifies and stores the blocks from `local` up to `synced`.
+    ///
+    /// The tip is kept at `local`, or at the synced genesis if there's none,
+    /// until the synced state is committed: the stored blocks are only
+    /// overwritten by a later sync if this one fails.
+    async fn store_blocks<DB: database::DB>(
+        &self,
+        db: &DB,
+        peer: &str,
+        local: Option<&Header>,
+        synced: &Header,
+    ) -> anyhow::Result<()> {
+        let mut tip = local.cloned();
+        let mut prev = local.cloned();
+        let mut from = local.map_or(0, |h| h.height + 1);
+        while from <= synced.height {
+            let to = (from + MAX_BLOCKS_PER_REQUEST - 1).min(synced.height);
+            let blocks: Vec<LedgerBlock> =
+                self.fetch(peer, "blocks", HeightRange { from, to }).await?;
+            for b in &blocks {
+                verify_next(prev.as_ref(), &b.header)?;
+                b.verify_roots()?;
+                let height = b.header.height;
+                if !matches!(b.label, Label::Final(_)) {
+                    anyhow::bail!("block at height {height} is not final");
+                }
+                from = height + 1;
+                prev = Some(b.header.clone());
+                tip.get_or_insert_with(|| b.header.clone());
+            }
+
+            db.update(|t| {
+                for b in &blocks {
+                    t.store_block(&b.header, &b.txs, &b.faults, b.label)?;
+                }
+                if let Some(tip) = &tip {
+                    t.op_write(MD_HASH_KEY, tip.hash)?;
+                    t.op_write(MD_STATE_ROOT_KEY, tip.state_hash)?;
+                }
+                Ok(())
+            })?;
+            info!(event = "fast sync blocks stored", height = from - 1);
+        }
+
+        match prev {
+            Some(prev) if prev.hash == synced.hash => Ok(()),
+            _ => anyhow::bail!("blocks do not link to the state block"),
+        }
+    }
+
+    /// Returns the number of peers whose block at the height of `header` is
+    /// the same, `peer` included
+    async fn confirmations(&self, peer: &str, header: &Header) -> usize {
+        let range = HeightRange {
+            from: header.height,
+            to: header.height,
+        };
+
+        let mut confirmations = 1;
+        for other in self.conf.peers.iter().filter(|p| *p != peer) {
+            match self.fetch::<Header>(other, "headers", range).await {
+                Ok(headers) if headers.first() == Some(header) => {
+                    confirmations += 1
+                }
+                Ok(_) => warn!(event = "fast sync block not confirmed", other),
+                Err(err) => warn!(event = "fast sync peer failed", other, %err),
+            }
+        }
+        confirmations
+    }
+
+    /// Fetches the items of `range` from `peer`, failing if none is returned
+    async fn fetch<T: Serializable>(
+        &self,
+        peer: &str,
+        topic: &str,
+        range: HeightRange,
+    ) -> anyhow::Result<Vec<T>> {
+        let items = decode_all(&self.request(peer, topic, &range).await?)?;
+        if items.is_empty() {
+            anyhow::bail!("no {topic} returned from height {}", range.from);
+        }
+        Ok(items)
+    }
+
+    /// Requests `topic` to `peer`, failing if the response is larger than
+    /// `max_state_size`
+    async fn request<B: Serialize>(
+        &self,
+        peer: &str,
+        topic: &str,
+        body: &B,
+    ) -> anyhow::Result<Vec<u8>> {
+        let max_size = self.conf.max_state_size;
+        let too_large = || anyhow::anyhow!("{topic} above {max_size} bytes");
+
+        let url =
+            format!("{}/on/fast-sync/{topic}", peer.trim_end_matches('/'));
+        let mut rsp = self
+            .client
+            .post(url)
+            .body(serde_json::to_vec(body)?)
+            .send()
+            .await?
+            .error_for_status()?;
+        if rsp.content_length().is_some_and(|len| len > max_size) {
+            return Err(too_large());
+        }
+
+        let mut buf = vec![];
+        while let Some(chunk) = rsp.chunk().await? {
+            if (buf.len() + chunk.len()) as u64 > max_size {
+                return Err(too_large());
+            }
+            buf.extend_from_slice(&chunk);
+        }
+        Ok(buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    use fake::{Fake, Faker};
+    use node_data::message::PROVISIONERS_ROOT_HEADER_VERSION;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+
+    use super::*;
+    use crate::chain::hash_chain::tests::{
+        attest, child, new_provisioners, seal,
+    };
+    use crate::database::rocksdb::Backend;
+    use crate::database::{DatabaseOptions, DB};
+
+    fn ledger_block() -> LedgerBlock {
+        let mut block = LedgerBlock {
+            header: Faker.fake(),
+            label: Label::Final(3),
+            txs: vec![Faker.fake(), Faker.fake()],
+            faults: vec![],
+        };
+        let tx_digests: Vec<_> =
+            block.txs.iter().map(|t| t.inner.digest()).collect();
+        block.header.txroot = merkle_root(&tx_digests[..]);
+        block.header.faultroot = merkle_root::<[u8; 32]>(&[]);
+        block
+    }
+
+    #[test]
+    fn test_ledger_blocks_encoding() {
+        let blocks = vec![ledger_block(), ledger_block()];
+
+        let buf = encode_all(&blocks).unwrap();
+        let decoded: Vec<LedgerBlock> = decode_all(&buf).unwrap();
+
+        assert_eq!(decoded.len(), 2);
+        for (b, decoded) in blocks.iter().zip(&decoded) {
+            assert_eq!(b.header, decoded.header);
+            assert_eq!(b.label, decoded.label);
+            let ids = |b: &LedgerBlock| {
+                b.txs.iter().map(|t| t.inner.id()).collect::<Vec<_>>()
+            };
+            assert_eq!(ids(b), ids(decoded));
+            decoded.verify_roots().expect("roots to match");
+        }
+
+        let mut tampered = decoded[0].clone();
+        tampered.txs.clear();
+        assert!(tampered.verify_roots().is_err());
+
+        let header: Header = Faker.fake();
+        let snapshot = encode_snapshot(&header, b"archive").unwrap();
+        let (decoded, archive) = decode_snapshot(&snapshot).unwrap();
+        assert_eq!(decoded, header);
+        assert_eq!(archive, b"archive");
+    }
+
+    #[test]
+    fn test_height_range() {
+        let range = HeightRange { from: 5, to: 7 };
+        assert_eq!(range.heights(3).unwrap().collect::<Vec<_>>(), [5, 6, 7]);
+        assert!(range.heights(2).is_err());
+        assert!(HeightRange { from: 7, to: 5 }.heights(10).is_err());
+    }
+
+    fn store(db: &Backend, header: &Header, label: Label) {
+        db.update(|t| t.store_block(header, &[], &[], label))
+            .expect("block to be stored");
+    }
+
+    /// Reads the topic and the body of an HTTP request
+    async fn read_request(stream: &mut TcpStream) -> (String, Vec<u8>) {
+        let mut buf = vec![];
+        let mut chunk = [0; 1024];
+        loop {
+            let n = stream.read(&mut chunk).await.unwrap();
+            assert!(n > 0, "request to be complete");
+            buf.extend_from_slice(&chunk[..n]);
+
+            let Some(end) = buf.windows(4).position(|w| w == b"\r\n\r\n")
+            else {
+                continue;
+            };
+            let head = String::from_utf8_lossy(&buf[..end]).to_string();
+            let len = head
+                .lines()
+                .find_map(|line| {
+                    let line = line.to_ascii_lowercase();
+                    let len = line.strip_prefix("content-length:")?;
+                    len.trim().parse::<usize>().ok()
+                })
+                .unwrap_or_default();
+            let body = end + 4;
+            if buf.len() >= body + len {
+                let path = head.split_whitespace().nth(1).unwrap();
+                let topic = path.rsplit('/').next().unwrap().to_string();
+                return (topic, buf[body..body + len].to_vec());
+            }
+        }
+    }
+
+    /// Serves the fast sync topics of `db` over HTTP, returning its address
+    async fn serve(db: Arc<Backend>, snapshot: Vec<u8>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let (topic, body) = read_request(&mut stream).await;
+                let range = || serde_json::from_slice(&body).unwrap();
+                let rsp = match topic.as_str() {
+                    "info" => db
+                        .view(|t| encode_all([&last_final_header(t).unwrap()])),
+                    "headers" => db.view(|t| {
+                        encode_all(&range_headers(t, range()).unwrap())
+                    }),
+                    "blocks" => db.view(|t| {
+                        encode_all(&range_blocks(t, range()).unwrap())
+                    }),
+                    _ => Ok(snapshot.clone()),
+                }
+                .unwrap();
+
+                let head = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\
+                     Connection: close\r\n\r\n",
+                    rsp.len()
+                );
+                stream.write_all(head.as_bytes()).await.unwrap();
+                stream.write_all(&rsp).await.unwrap();
+            }
+        });
+        format!("http://{addr}")
+    }
+
+    struct TestState {
+        provisioners: Provisioners,
+        committed: Arc<AtomicBool>,
+    }
+
+    impl StagedState for TestState {
+        fn provisioners(&self) -> anyhow::Result<Provisioners> {
+            Ok(self.provisioners.clone())
+        }
+
+        fn commit(self) -> anyhow::Result<()> {
+            self.committed.store(true, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_fast_sync() {
+        const SYNCED_HEIGHT: u64 = 5;
+
+        let mut rng = StdRng::seed_from_u64(0xbeef);
+        let (provisioners, sks) = new_provisioners(&mut rng, 10);
+        let (others, _) = new_provisioners(&mut rng, 10);
+        let (pk, sk) = sks.iter().next().unwrap();
+
+        let peer_dir = tempfile::tempdir().unwrap();
+        let peer_db = Backend::create_or_open(
+            peer_dir.path(),
+            DatabaseOptions::default(),
+        );
+        let local_dir = tempfile::tempdir().unwrap();
+        let local_db = Backend::create_or_open(
+            local_dir.path(),
+            DatabaseOptions::default(),
+        );
+
+        let mut genesis = Header {
+            txroot: merkle_root::<[u8; 32]>(&[]),
+            faultroot: merkle_root::<[u8; 32]>(&[]),
+            ..Default::default()
+        };
+        seal(&mut genesis);
+        store(&peer_db, &genesis, Label::Final(0));
+        store(&local_db, &genesis, Label::Final(0));
+
+        // The peer finalized the blocks up to the synced one, and accepted
+        // the next one, attested by the provisioners of the synced state
+        let mut synced = genesis.clone();
+        for height in 1..=SYNCED_HEIGHT {
+            synced = child(&synced, pk, sk);
+            store(&peer_db, &synced, Label::Final(height));
+        }
+        let mut next = child(&synced, pk, sk);
+        next.version = PROVISIONERS_ROOT_HEADER_VERSION;
+        next.provisioners_root = provisioners.commitment();
+        seal(&mut next);
+        attest(&mut next, synced.seed, &provisioners, &sks);
+        store(&peer_db, &next, Label::Accepted(SYNCED_HEIGHT + 1));
+
+        let snapshot = encode_snapshot(&synced, b"archive").unwrap();
+        let peer = serve(Arc::new(peer_db), snapshot).await;
+        let fast_sync = FastSync::new(FastSyncParams {
+            peers: vec![peer],
+            quorum: 1,
+            min_gap: 1,
+            ..Default::default()
+        });
+
+        let committed = Arc::new(AtomicBool::new(false));
+        let stage = |provisioners: &Provisioners| {
+            let provisioners = provisioners.clone();
+            let committed = committed.clone();
+            let state_hash = synced.state_hash;
+            move |archive: &[u8], state_root| {
+                assert_eq!(archive, b"archive");
+                assert_eq!(state_root, state_hash);
+                anyhow::Ok(TestState {
+                    provisioners: provisioners.clone(),
+                    committed: committed.clone(),
+                })
+            }
+        };
+        let tip =
+            |db: &Backend| db.view(|t| last_final_header(t).unwrap().height);
+
+        // A state whose provisioners don't attest the next block is dropped
+        let synced_tip = fast_sync.run(&local_db, stage(&others)).await;
+        assert!(synced_tip.unwrap().is_none());
+        assert!(!committed.load(Ordering::SeqCst));
+        assert_eq!(tip(&local_db), 0);
+
+        let synced_tip = fast_sync.run(&local_db, stage(&provisioners)).await;
+        assert_eq!(synced_tip.unwrap(), Some(synced.clone()));
+        assert!(committed.load(Ordering::SeqCst));
+        assert_eq!(tip(&local_db), SYNCED_HEIGHT);
+
+        // The blocks are stored with the labels of the peer
+        local_db
+            .view(|t| {
+                for height in 1..=SYNCED_HEIGHT {
+                    let (_, label) = t.block_label_by_height(height)?.unwrap();
+                    assert_eq!(label, Label::Final(height));
+                }
+                let tip_hash = t.op_read(MD_HASH_KEY)?.unwrap();
+                assert_eq!(tip_hash, synced.hash);
+                anyhow::Ok(())
+            })
+            .unwrap();
+    }
+}
@@ -0,0 +1,100 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Gas price estimation over the recently accepted blocks.
+//!
+//! The acceptor records the gas prices of the transactions included in every
+//! accepted block. The prices of the last [`BLOCK_WINDOW`] blocks are kept to
+//! suggest a gas price at a given percentile.
+//!
+//! The records start when the node starts, and are not persisted.
+
+use std::collections::VecDeque;
+use std::sync::{LazyLock, Mutex};
+
+/// Number of blocks the gas prices are kept for
+pub const BLOCK_WINDOW: usize = 100;
+
+static FEES: LazyLock<Mutex<FeeEstimator>> =
+    LazyLock::new(|| Mutex::new(FeeEstimator::default()));
+
+#[derive(Default)]
+struct FeeEstimator {
+    /// Gas prices of the transactions included in each block, by height
+    blocks: VecDeque<(u64, Vec<u64>)>,
+}
+
+impl FeeEstimator {
+    fn record(&mut self, height: u64, gas_prices: Vec<u64>) {
+        // Blocks at or above `height` have been reverted
+        while self.blocks.back().is_some_and(|(h, _)| *h >= height) {
+            self.blocks.pop_back();
+        }
+        if self.blocks.len() >= BLOCK_WINDOW {
+            self.blocks.pop_front();
+        }
+        self.blocks.push_back((height, gas_prices));
+    }
+
+    fn estimate(&self, percentile: u8) -> Option<u64> {
+        let mut prices: Vec<_> = self
+            .blocks
+            .iter()
+            .flat_map(|(_, prices)| prices.iter().copied())
+            .collect();
+        prices.sort_unstable();
+
+        let percentile = usize::from(percentile.min(100));
+        let rank = (prices.len() * percentile).div_ceil(100);
+        prices.get(rank.saturating_sub(1)).copied()
+    }
+}
+
+/// Records the gas prices of the transactions included in an accepted block
+pub(crate) fn record(height: u64, gas_prices: impl Iterator<Item = u64>) {
+    let mut fees = FEES.lock().expect("lock not to be poisoned");
+    fees.record(height, gas_prices.collect());
+}
+
+/// Returns the gas price at the given `percentile` (capped at 100) of the
+/// transactions included in the last [`BLOCK_WINDOW`] blocks, or `None` if
+/// they included none
+pub fn estimate_gas_price(percentile: u8) -> Option<u64> {
+    let fees = FEES.lock().expect("lock not to be poisoned");
+    fees.estimate(percentile)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate() {
+        let mut fees = FeeEstimator::default();
+        assert_eq!(fees.estimate(50), None);
+
+        fees.record(1, vec![10, 1]);
+        fees.record(2, vec![]);
+        fees.record(3, vec![5, 3, 7]);
+        assert_eq!(fees.estimate(0), Some(1));
+        assert_eq!(fees.estimate(50), Some(5));
+        assert_eq!(fees.estimate(90), Some(10));
+        assert_eq!(fees.estimate(200), Some(10));
+
+        // A block replacing a reverted one replaces its prices
+        fees.record(3, vec![100]);
+        assert_eq!(fees.blocks.len(), 3);
+        assert_eq!(fees.estimate(100), Some(100));
+        assert_eq!(fees.estimate(50), Some(10));
+
+        // Only the last blocks are kept
+        for height in 4..4 + BLOCK_WINDOW as u64 {
+            fees.record(height, vec![2]);
+        }
+        assert_eq!(fees.blocks.len(), BLOCK_WINDOW);
+        assert_eq!(fees.estimate(100), Some(2));
+    }
+}
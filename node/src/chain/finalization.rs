@@ -0,0 +1,158 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Background finalization of the VM state.
+//!
+//! Merging the finalized states can be slow, so it's run in a blocking task
+//! instead of within the acceptance of the block that makes them final. At
+//! most one finalization is pending at any time: [`Finalization::wait`] is the
+//! barrier to consult before starting a new finalization or reverting the VM.
+
+use std::sync::Arc;
+use std::time::Instant;
+
+use anyhow::anyhow;
+use metrics::{gauge, histogram};
+use tokio::sync::{Mutex, RwLock};
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+
+use crate::vm;
+
+#[derive(Default)]
+pub(crate) struct Finalization {
+    pending: Mutex<Option<JoinHandle<anyhow::Result<()>>>>,
+}
+
+impl Finalization {
+    /// Waits for the pending finalization, if any, to complete.
+    ///
+    /// Returns the error of the pending finalization, if it failed.
+    pub async fn wait(&self) -> anyhow::Result<()> {
+        let mut pending = self.pending.lock().await;
+        Self::join(pending.take()).await
+    }
+
+    /// Finalizes `commit` in the background, merging `to_merge` into it.
+    ///
+    /// The VM lock is only held to clone the VM, so that the merge doesn't
+    /// stall the acceptance of the next blocks.
+    pub async fn spawn<VM: vm::VMExecution>(
+        &self,
+        vm: &RwLock<VM>,
+        height: u64,
+        commit: [u8; 32],
+        to_merge: Vec<[u8; 32]>,
+    ) {
+        let vm = vm.read().await.clone();
+        self.spawn_with(height, commit, move || {
+            vm.finalize_state(commit, to_merge)
+        })
+        .await
+    }
+
+    /// Runs `finalize` in the background, as the finalization of `commit`.
+    ///
+    /// The previous finalization is waited for before starting the new one.
+    /// Its failure is only logged, since it doesn't concern the block being
+    /// accepted.
+    async fn spawn_with<F>(&self, height: u64, commit: [u8; 32], finalize: F)
+    where
+        F: FnOnce() -> anyhow::Result<()> + Send + 'static,
+    {
+        let mut pending = self.pending.lock().await;
+        if let Err(err) = Self::join(pending.take()).await {
+            warn!(event = "previous finalization failed", ?err);
+        }
+
+        let queued = Instant::now();
+        gauge!("dusk_finalization_pending").set(1.0);
+
+        let handle = tokio::task::spawn_blocking(move || {
+            let start = Instant::now();
+            let res = finalize();
+            histogram!("dusk_finalization_elapsed").record(start.elapsed());
+            histogram!("dusk_finalization_lag").record(queued.elapsed());
+            gauge!("dusk_finalization_pending").set(0.0);
+
+            match &res {
+                Ok(_) => info!(
+                    event = "state finalized",
+                    height,
+                    state_root = hex::encode(commit),
+                    elapsed = ?start.elapsed(),
+                ),
+                Err(err) => error!(
+                    event = "state finalization failed",
+                    height,
+                    state_root = hex::encode(commit),
+                    ?err
+                ),
+            }
+            res
+        });
+
+        *pending = Some(handle);
+    }
+
+    async fn join(
+        handle: Option<JoinHandle<anyhow::Result<()>>>,
+    ) -> anyhow::Result<()> {
+        match handle {
+            Some(handle) => handle
+                .await
+                .map_err(|e| anyhow!("finalization task failed: {e}"))?,
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::time::Duration;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_background_finalization() {
+        let finalization = Finalization::default();
+        let done = Arc::new(AtomicBool::new(false));
+
+        let finalized = done.clone();
+        finalization
+            .spawn_with(1, [1; 32], move || {
+                std::thread::sleep(Duration::from_millis(50));
+                finalized.store(true, Ordering::SeqCst);
+                Ok(())
+            })
+            .await;
+        assert!(!done.load(Ordering::SeqCst));
+
+        // The barrier returns once the finalization is complete
+        finalization.wait().await.unwrap();
+        assert!(done.load(Ordering::SeqCst));
+        finalization.wait().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_failed_finalization() {
+        let finalization = Finalization::default();
+        finalization
+            .spawn_with(1, [1; 32], || Err(anyhow!("merge failed")))
+            .await;
+
+        // A failure doesn't prevent the next finalization...
+        finalization.spawn_with(2, [2; 32], || Ok(())).await;
+        finalization.wait().await.unwrap();
+
+        // ...but is returned by the barrier
+        finalization
+            .spawn_with(3, [3; 32], || Err(anyhow!("merge failed")))
+            .await;
+        assert!(finalization.wait().await.is_err());
+    }
+}
@@ -0,0 +1,194 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Fork choice among the competing chains above the last finalized block.
+//!
+//! The blocks received at or below the tip, which aren't part of the local
+//! chain, are recorded by height. A competing block whose parent isn't local
+//! either can't be compared with the local chain on its own: once its
+//! ancestors are recorded too, the [`branch`] leading to it from the local
+//! chain is compared with the local block at the height it forks from.
+//!
+//! As per protocol, the chain whose block at the fork height has the lowest
+//! iteration is preferred.
+//!
+//! Blocks at or below the last finalized height are discarded, and at most
+//! [`MAX_PER_HEIGHT`] blocks are recorded per height, within [`MAX_HEIGHTS`]
+//! heights.
+//!
+//! [`branch`]: ForkChoice::branch
+
+use std::collections::BTreeMap;
+
+use node_data::ledger::{Block, Hash, Header};
+
+/// Maximum number of competing blocks recorded per height
+const MAX_PER_HEIGHT: usize = 4;
+
+/// Maximum number of heights competing blocks are recorded for
+const MAX_HEIGHTS: usize = 64;
+
+#[derive(Default)]
+pub(crate) struct ForkChoice {
+    /// Competing blocks by height
+    blocks: BTreeMap<u64, Vec<Block>>,
+}
+
+impl ForkChoice {
+    /// Records the competing block `blk`.
+    ///
+    /// Returns whether the block is recorded.
+    pub fn insert(&mut self, blk: &Block) -> bool {
+        let height = blk.header().height;
+        if !self.blocks.contains_key(&height)
+            && self.blocks.len() == MAX_HEIGHTS
+        {
+            // Keep the lowest heights, closer to the fork points
+            match self.blocks.last_key_value() {
+                Some((last, _)) if *last > height => {
+                    self.blocks.pop_last();
+                }
+                _ => return false,
+            }
+        }
+
+        let blocks = self.blocks.entry(height).or_default();
+        let hash = blk.header().hash;
+        if blocks.len() == MAX_PER_HEIGHT
+            || blocks.iter().any(|b| b.header().hash == hash)
+        {
+            return false;
+        }
+        blocks.push(blk.clone());
+        true
+    }
+
+    fn get(&self, height: u64, hash: &Hash) -> Option<&Block> {
+        self.blocks
+            .get(&height)?
+            .iter()
+            .find(|b| b.header().hash == *hash)
+    }
+
+    /// Returns the recorded chain of blocks ending at `blk`, from the oldest
+    /// ancestor recorded to `blk` itself
+    pub fn branch(&self, blk: &Block) -> Vec<Block> {
+        let mut branch = vec![blk.clone()];
+        let mut header = blk.header();
+        while let Some(height) = header.height.checked_sub(1) {
+            let Some(parent) = self.get(height, &header.prev_block_hash) else {
+                break;
+            };
+            branch.push(parent.clone());
+            header = parent.header();
+        }
+        branch.reverse();
+        branch
+    }
+
+    /// Discards the blocks at or below the last finalized height
+    pub fn prune(&mut self, final_height: u64) {
+        self.blocks.retain(|height, _| *height > final_height);
+    }
+
+    /// Takes a recorded block child of `tip` out, to be accepted on top of
+    /// it
+    pub fn take_child(&mut self, tip: &Header) -> Option<Block> {
+        let height = tip.height + 1;
+        let blocks = self.blocks.get_mut(&height)?;
+        let pos = blocks
+            .iter()
+            .position(|b| b.header().prev_block_hash == tip.hash)?;
+        let child = blocks.remove(pos);
+        if blocks.is_empty() {
+            self.blocks.remove(&height);
+        }
+        Some(child)
+    }
+}
+
+/// Returns whether the chain forking with `remote` is preferred over the
+/// local one, forking with `local` at the same height
+pub(crate) fn is_preferred(local: &Header, remote: &Header) -> bool {
+    local.height == remote.height && remote.iteration < local.iteration
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(height: u64, hash: u8, prev: u8, iteration: u8) -> Block {
+        let header = Header {
+            height,
+            iteration,
+            hash: [hash; 32],
+            prev_block_hash: [prev; 32],
+            ..Default::default()
+        };
+        Block::new(header, vec![], vec![]).expect("block to be created")
+    }
+
+    fn hashes(branch: &[Block]) -> Vec<u8> {
+        branch.iter().map(|b| b.header().hash[0]).collect()
+    }
+
+    #[test]
+    fn test_branch() {
+        let mut fork_choice = ForkChoice::default();
+
+        // Local chain: 10 <- 11 (iter 2) <- 12
+        // Fork: 10 <- 21 (iter 0) <- 22 <- 23
+        let b23 = block(13, 23, 22, 0);
+        assert!(fork_choice.insert(&b23));
+        assert_eq!(hashes(&fork_choice.branch(&b23)), vec![23]);
+
+        assert!(fork_choice.insert(&block(11, 21, 10, 0)));
+        assert!(fork_choice.insert(&block(12, 22, 21, 1)));
+        assert!(!fork_choice.insert(&block(12, 22, 21, 1)));
+
+        let branch = fork_choice.branch(&b23);
+        assert_eq!(hashes(&branch), vec![21, 22, 23]);
+        assert_eq!(branch[0].header().prev_block_hash, [10; 32]);
+
+        let local = block(11, 11, 10, 2);
+        assert!(is_preferred(local.header(), branch[0].header()));
+        assert!(!is_preferred(branch[0].header(), local.header()));
+        assert!(!is_preferred(local.header(), branch[1].header()));
+
+        // Once reverted to the parent of the branch, it's taken in order
+        let tip = block(10, 10, 9, 0);
+        let child = fork_choice.take_child(tip.header()).expect("child");
+        assert_eq!(child.header().hash, [21; 32]);
+        assert!(fork_choice.take_child(tip.header()).is_none());
+        let child = fork_choice.take_child(child.header()).expect("child");
+        let child = fork_choice.take_child(child.header()).expect("child");
+        assert_eq!(child.header().hash, [23; 32]);
+        assert!(fork_choice.blocks.is_empty());
+    }
+
+    #[test]
+    fn test_bounds() {
+        let mut fork_choice = ForkChoice::default();
+        for i in 0..MAX_PER_HEIGHT as u8 {
+            assert!(fork_choice.insert(&block(11, i, 10, i)));
+        }
+        assert!(!fork_choice.insert(&block(11, 99, 10, 0)));
+
+        for height in 12..11 + MAX_HEIGHTS as u64 {
+            assert!(fork_choice.insert(&block(height, 1, 0, 0)));
+        }
+        assert_eq!(fork_choice.blocks.len(), MAX_HEIGHTS);
+
+        // A higher height isn't recorded, a lower one evicts the highest
+        assert!(!fork_choice.insert(&block(100, 1, 0, 0)));
+        assert!(fork_choice.insert(&block(10, 1, 0, 0)));
+        assert_eq!(fork_choice.blocks.len(), MAX_HEIGHTS);
+
+        fork_choice.prune(11);
+        assert!(!fork_choice.blocks.contains_key(&11));
+        assert_eq!(fork_choice.blocks.len(), MAX_HEIGHTS - 2);
+    }
+}
@@ -371,6 +371,7 @@ impl<N: Network, DB: database::DB, VM: vm::VMExecution> SimpleFSM<N, DB, VM> {
                 }
             };
 
+            let mut pin = None;
             let quorum_blk = if quorum_height > tip_height + 1 {
                 // Quorum from future
 
@@ -381,7 +382,18 @@ impl<N: Network, DB: database::DB, VM: vm::VMExecution> SimpleFSM<N, DB, VM> {
                 || (quorum_height == tip_height && tip_header.hash != candidate)
             {
                 // If Quorum is for at height tip+1 or tip (but for a different
-                // candidate) we try to fetch the candidate from the DB
+                // candidate) we try to fetch the candidate from the DB.
+                //
+                // The candidate is pinned until the Quorum is processed, so
+                // that it can't be deleted between its fetching and its
+                // acceptance
+                pin = Some(
+                    self.acc
+                        .read()
+                        .await
+                        .candidate_pins
+                        .pin(candidate, quorum_height),
+                );
                 let res = db.read().await.view(|t| t.candidate(&candidate));
 
                 match res {
@@ -416,6 +428,7 @@ impl<N: Network, DB: database::DB, VM: vm::VMExecution> SimpleFSM<N, DB, VM> {
                         error!("Error on block handling: {e}");
                     }
                 }
+                drop(pin);
             } else {
                 // Candidate block not found
                 debug!(
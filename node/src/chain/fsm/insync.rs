@@ -12,6 +12,8 @@ use node_data::message::Message;
 
 use super::*;
 use crate::chain::fallback;
+use crate::chain::fork_choice::{self, ForkChoice};
+use crate::chain::future_blocks::FutureBlocks;
 
 pub(super) struct InSyncImpl<DB: database::DB, VM: vm::VMExecution, N: Network>
 {
@@ -20,6 +22,10 @@ pub(super) struct InSyncImpl<DB: database::DB, VM: vm::VMExecution, N: Network>
 
     blacklisted_blocks: SharedHashSet,
     presync: Option<PresyncInfo>,
+    /// Blocks received ahead of the tip
+    future_blocks: FutureBlocks,
+    /// Competing blocks at or below the tip
+    fork_choice: ForkChoice,
 }
 
 impl<DB: database::DB, VM: vm::VMExecution, N: Network> InSyncImpl<DB, VM, N> {
@@ -33,6 +39,8 @@ impl<DB: database::DB, VM: vm::VMExecution, N: Network> InSyncImpl<DB, VM, N> {
             network,
             blacklisted_blocks,
             presync: None,
+            future_blocks: FutureBlocks::default(),
+            fork_choice: ForkChoice::default(),
         }
     }
 
@@ -134,11 +142,12 @@ impl<DB: database::DB, VM: vm::VMExecution, N: Network> InSyncImpl<DB, VM, N> {
             // Ensure remote_blk is higher than the last finalized
             // We do this check after the previous one because
             // get_last_final_block if heavy
-            if remote_height
-                <= acc.get_last_final_block().await?.header().height
-            {
+            let final_height =
+                acc.get_last_final_block().await?.header().height;
+            if remote_height <= final_height {
                 return Ok(None);
             }
+            self.fork_choice.prune(final_height);
 
             // Check if prev_blk is in our chain
             // If not, remote_blk is on a fork
@@ -153,6 +162,22 @@ impl<DB: database::DB, VM: vm::VMExecution, N: Network> InSyncImpl<DB, VM, N> {
                     "received block from fork at height {remote_height}: {}",
                     to_str(&remote_header.hash)
                 );
+                // Switch to the fork if its branch is known and preferred
+                if self.fork_choice.insert(remote_blk) {
+                    let switch = Self::try_switch_fork(
+                        &mut acc,
+                        &mut self.fork_choice,
+                        &self.blacklisted_blocks,
+                        remote_blk,
+                    );
+                    if let Err(e) = switch.await {
+                        error!(
+                            event = "fork switch failed",
+                            height = remote_height,
+                            err = format!("{:?}", e)
+                        );
+                    }
+                }
                 return Ok(None);
             }
 
@@ -216,8 +241,14 @@ impl<DB: database::DB, VM: vm::VMExecution, N: Network> InSyncImpl<DB, VM, N> {
                                 .insert(local_header.hash);
 
                             // After reverting we can accept `remote_blk` as the
-                            // new tip
+                            // new tip, followed by the competing blocks
+                            // recorded on top of it
                             acc.try_accept_block(remote_blk, true).await?;
+                            Self::accept_fork_children(
+                                &mut acc,
+                                &mut self.fork_choice,
+                            )
+                            .await?;
                             return Ok(None);
                         }
                         Err(e) => {
@@ -233,6 +264,10 @@ impl<DB: database::DB, VM: vm::VMExecution, N: Network> InSyncImpl<DB, VM, N> {
                 }
 
                 Ordering::Greater => {
+                    // Keep remote_blk in case a preferred block is built on
+                    // top of it
+                    self.fork_choice.insert(remote_blk);
+
                     // If remote_blk.iteration > local_blk.iteration, we send
                     // the sender our local block. This
                     // behavior is intended to make the peer
@@ -251,6 +286,8 @@ impl<DB: database::DB, VM: vm::VMExecution, N: Network> InSyncImpl<DB, VM, N> {
                     }
                 }
                 Ordering::Equal => {
+                    self.fork_choice.insert(remote_blk);
+
                     // If remote_blk and local_blk have the same iteration, it
                     // means two conflicting candidates have been generated
                     let local_hash = to_str(&local_header.hash);
@@ -264,7 +301,28 @@ impl<DB: database::DB, VM: vm::VMExecution, N: Network> InSyncImpl<DB, VM, N> {
 
         // If remote_blk is a successor of our tip, we try to accept it
         if remote_height == tip_height + 1 {
-            let finalized = acc.try_accept_block(remote_blk, true).await?;
+            let mut finalized = acc.try_accept_block(remote_blk, true).await?;
+
+            // Accept the blocks received ahead of it, as long as they follow
+            // the tip
+            loop {
+                let tip = acc.tip_header().await;
+                let Some(blk) = self.future_blocks.take_child(&tip) else {
+                    break;
+                };
+                match acc.try_accept_block(&blk, true).await {
+                    Ok(blk_finalized) => finalized |= blk_finalized,
+                    Err(err) => {
+                        warn!(
+                            event = "future block not accepted",
+                            height = blk.header().height,
+                            hash = to_str(&blk.header().hash),
+                            ?err,
+                        );
+                        break;
+                    }
+                }
+            }
 
             // On first final block accepted while we're inSync, clear
             // blacklisted blocks
@@ -291,6 +349,10 @@ impl<DB: database::DB, VM: vm::VMExecution, N: Network> InSyncImpl<DB, VM, N> {
             return Ok(None);
         }
 
+        // Keep remote_blk to accept it once its parent is, instead of
+        // requesting it again
+        self.future_blocks.insert(remote_blk, tip_height);
+
         // If remote_blk.height > tip.height+1, we might be out of sync.
         // Before switching to outOfSync mode and download missing blocks,
         // we ensure that the peer has a valid successor of tip
@@ -321,6 +383,79 @@ impl<DB: database::DB, VM: vm::VMExecution, N: Network> InSyncImpl<DB, VM, N> {
         Ok(None)
     }
 
+    /// Switches to the branch of the competing block `remote_blk`, if it
+    /// forks from the local chain with a block preferred over the local one.
+    ///
+    /// The local chain is reverted to the parent of the branch, on which the
+    /// blocks of the branch, and the ones recorded after them, are accepted
+    /// in order.
+    async fn try_switch_fork(
+        acc: &mut Acceptor<N, DB, VM>,
+        fork_choice: &mut ForkChoice,
+        blacklisted_blocks: &SharedHashSet,
+        remote_blk: &Block,
+    ) -> anyhow::Result<()> {
+        let branch = fork_choice.branch(remote_blk);
+        let root = branch[0].header();
+
+        let (prev_state, local_blk) = acc.db.read().await.view(|t| {
+            let prev_state = t
+                .block_header(&root.prev_block_hash)?
+                .map(|prev| prev.state_hash);
+            let local_blk = t.block_by_height(root.height)?;
+            anyhow::Ok((prev_state, local_blk))
+        })?;
+
+        // The branch doesn't fork from the local chain yet
+        let (Some(prev_state), Some(local_blk)) = (prev_state, local_blk)
+        else {
+            return Ok(());
+        };
+        let local_header = local_blk.header();
+        if !fork_choice::is_preferred(local_header, root) {
+            return Ok(());
+        }
+
+        info!(
+            event = "switching to fork",
+            height = root.height,
+            iter = local_header.iteration,
+            new_iter = root.iteration,
+            len = branch.len(),
+        );
+
+        fallback::WithContext::new(acc)
+            .try_revert(local_header, root, RevertTarget::Commit(prev_state))
+            .await?;
+        counter!("dusk_fallback_count").increment(1);
+
+        // Blacklist the local block so we discard it if we receive it again
+        blacklisted_blocks.write().await.insert(local_header.hash);
+
+        if Self::accept_fork_children(acc, fork_choice).await? {
+            blacklisted_blocks.write().await.clear();
+        }
+        Ok(())
+    }
+
+    /// Accepts the recorded competing blocks following the tip, in order.
+    ///
+    /// Returns whether any of them is finalized.
+    async fn accept_fork_children(
+        acc: &mut Acceptor<N, DB, VM>,
+        fork_choice: &mut ForkChoice,
+    ) -> anyhow::Result<bool> {
+        let mut finalized = false;
+        loop {
+            let tip = acc.tip_header().await;
+            let Some(blk) = fork_choice.take_child(&tip) else {
+                break;
+            };
+            finalized |= acc.try_accept_block(&blk, true).await?;
+        }
+        Ok(finalized)
+    }
+
     /// Requests a block by height from a `peer_addr`
     async fn request_block(&self, height: u64, peer_addr: SocketAddr) {
         let network = self.network.read().await;
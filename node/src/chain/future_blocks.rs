@@ -0,0 +1,136 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Pool of the blocks received ahead of the tip while in sync.
+//!
+//! A block whose parent isn't accepted yet is kept, instead of being dropped
+//! and requested again, as long as its height is within [`HEIGHT_WINDOW`] of
+//! the tip. Once its parent becomes the tip, it's taken out of the pool to be
+//! accepted in turn.
+//!
+//! The pool holds at most [`MAX_BLOCKS`], the blocks the farthest from the
+//! tip being evicted first.
+
+use std::collections::BTreeMap;
+
+use metrics::gauge;
+use node_data::ledger::{Block, Hash, Header};
+
+/// Number of heights above the tip the blocks are kept for
+const HEIGHT_WINDOW: u64 = 50;
+
+/// Maximum number of blocks in the pool
+const MAX_BLOCKS: usize = 100;
+
+#[derive(Default)]
+pub(crate) struct FutureBlocks {
+    /// Blocks by height and hash
+    blocks: BTreeMap<(u64, Hash), Block>,
+}
+
+impl FutureBlocks {
+    /// Adds `blk` to the pool, if within the window above `tip_height`.
+    ///
+    /// Returns whether the block is kept.
+    pub fn insert(&mut self, blk: &Block, tip_height: u64) -> bool {
+        let height = blk.header().height;
+        if height <= tip_height + 1 || height > tip_height + HEIGHT_WINDOW {
+            return false;
+        }
+
+        let key = (height, blk.header().hash);
+        if self.blocks.len() == MAX_BLOCKS && !self.blocks.contains_key(&key) {
+            match self.blocks.last_key_value() {
+                Some((last, _)) if *last > key => {
+                    self.blocks.pop_last();
+                }
+                _ => return false,
+            }
+        }
+        self.blocks.insert(key, blk.clone());
+        gauge!("dusk_future_blocks").set(self.blocks.len() as f64);
+        true
+    }
+
+    /// Takes a block child of `tip` out of the pool, discarding the blocks
+    /// at or below its height.
+    pub fn take_child(&mut self, tip: &Header) -> Option<Block> {
+        self.blocks.retain(|(height, _), _| *height > tip.height);
+
+        let next = tip.height + 1;
+        let key = self
+            .blocks
+            .range((next, Hash::default())..=(next, [u8::MAX; 32]))
+            .find(|(_, blk)| blk.header().prev_block_hash == tip.hash)
+            .map(|(key, _)| *key);
+        let child = key.and_then(|key| self.blocks.remove(&key));
+
+        gauge!("dusk_future_blocks").set(self.blocks.len() as f64);
+        child
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(height: u64, hash: u8, prev: u8) -> Block {
+        let header = Header {
+            height,
+            hash: [hash; 32],
+            prev_block_hash: [prev; 32],
+            ..Default::default()
+        };
+        Block::new(header, vec![], vec![]).expect("block to be created")
+    }
+
+    fn tip(height: u64, hash: u8) -> Header {
+        block(height, hash, 0).header().clone()
+    }
+
+    #[test]
+    fn test_future_blocks() {
+        let mut pool = FutureBlocks::default();
+
+        // Only the blocks ahead of the tip successor, within the window
+        assert!(!pool.insert(&block(11, 11, 10), 10));
+        assert!(!pool.insert(&block(10 + HEIGHT_WINDOW + 1, 1, 1), 10));
+        assert!(pool.insert(&block(12, 12, 11), 10));
+        assert!(pool.insert(&block(13, 13, 12), 10));
+        assert!(pool.insert(&block(13, 14, 99), 10));
+        assert_eq!(pool.blocks.len(), 3);
+
+        // No child of the tip yet
+        assert!(pool.take_child(&tip(10, 10)).is_none());
+
+        let child = pool.take_child(&tip(11, 11)).expect("child of 11");
+        assert_eq!(child.header().hash, [12; 32]);
+        let child = pool.take_child(&tip(12, 12)).expect("child of 12");
+        assert_eq!(child.header().hash, [13; 32]);
+
+        // The sibling left at the height of the tip is discarded
+        assert!(pool.take_child(&tip(13, 13)).is_none());
+        assert_eq!(pool.blocks.len(), 0);
+    }
+
+    #[test]
+    fn test_future_blocks_eviction() {
+        let mut pool = FutureBlocks::default();
+        for i in 0..MAX_BLOCKS as u64 {
+            assert!(pool.insert(&block(12 + i % 10, i as u8, 0), 10));
+        }
+        assert_eq!(pool.blocks.len(), MAX_BLOCKS);
+
+        // A farther block isn't kept, a closer one evicts the farthest
+        assert!(!pool.insert(&block(40, 200, 0), 10));
+        assert!(pool.insert(&block(12, 201, 0), 10));
+        assert_eq!(pool.blocks.len(), MAX_BLOCKS);
+        assert!(!pool
+            .blocks
+            .keys()
+            .any(|(h, hash)| *h == 21 && hash[0] == 99));
+    }
+}
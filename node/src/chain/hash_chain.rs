@@ -0,0 +1,336 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Hash-chain verification of a range of stored blocks.
+//!
+//! This is a cheap first-stage check, meant to be run before the full
+//! acceptance of a range of blocks. No transaction is executed and no vote is
+//! verified against the provisioners, only the self-contained links between
+//! consecutive headers are checked:
+//! - the header hash matches its hashable fields
+//! - heights are consecutive and `prev_block_hash` links to the previous block
+//! - the seed is signed by the generator over the previous seed
+//! - the attestations vote for the block they attest
+//!
+//! The headers served to light clients are further checked to be attested by
+//! the committees of a provisioner set, see [`verify_headers`].
+
+use dusk_bytes::Serializable;
+use dusk_consensus::config::is_emergency_block;
+use dusk_consensus::errors::BundleError;
+use dusk_consensus::quorum::bundle::QuorumBundle;
+use dusk_consensus::user::provisioners::Provisioners;
+use dusk_core::signatures::bls::{
+    MultisigPublicKey, MultisigSignature, PublicKey as BlsPublicKey,
+};
+use node_data::ledger::{to_str, Attestation, Hash, Header};
+use node_data::message::payload::{RatificationResult, Vote};
+use thiserror::Error;
+
+use crate::database::Ledger;
+
+#[derive(Debug, Error)]
+pub enum HashChainError {
+    #[error("block at height {0} not found")]
+    MissingBlock(u64),
+    #[error("block at height {0} has height {1}")]
+    InvalidHeight(u64, u64),
+    #[error("block at height {0} has invalid hash {1}")]
+    InvalidHash(u64, String),
+    #[error("block at height {0} does not link to the previous block")]
+    InvalidPrevBlockHash(u64),
+    #[error("block at height {0} has invalid seed: {1}")]
+    InvalidSeed(u64, String),
+    #[error("block at height {0} has invalid attestation: {1}")]
+    InvalidAttestation(u64, &'static str),
+    #[error("block at height {0} is not attested by the provisioners: {1}")]
+    NotAttested(u64, BundleError),
+    #[error("storage error: {0}")]
+    Storage(#[from] anyhow::Error),
+}
+
+/// Verifies the hash chain of the blocks stored in `ledger` from height `from`
+/// to height `to`, both included.
+///
+/// If `from` is not the genesis, its links are checked against the block at
+/// height `from - 1`, which must be stored too.
+///
+/// `progress` is called with the height of every verified block.
+pub fn verify_hash_chain<L: Ledger, F: FnMut(u64)>(
+    ledger: &L,
+    from: u64,
+    to: u64,
+    mut progress: F,
+) -> Result<(), HashChainError> {
+    let mut prev = match from.checked_sub(1) {
+        Some(height) => Some(header_by_height(ledger, height)?),
+        None => None,
+    };
+
+    for height in from..=to {
+        let header = header_by_height(ledger, height)?;
+        verify_header(&header, height)?;
+        if let Some(prev) = &prev {
+            verify_link(prev, &header)?;
+        }
+
+        progress(height);
+        prev = Some(header);
+    }
+
+    Ok(())
+}
+
+/// Verifies that `header` is self-consistent and links to `prev`, the header
+/// of the block at the previous height.
+pub(crate) fn verify_next(
+    prev: &Header,
+    header: &Header,
+) -> Result<(), HashChainError> {
+    verify_header(header, prev.height + 1)?;
+    verify_link(prev, header)
+}
+
+/// Verifies the hash chain of `headers`, which must follow `trusted`, the
+/// header of a block already known to be final, as served to light clients.
+///
+/// The attestation of every header but the emergency ones is verified against
+/// the committees extracted from `provisioners`, the set the headers are
+/// attested by. The headers committing to their provisioner set must commit
+/// to this one.
+pub fn verify_headers(
+    trusted: &Header,
+    headers: &[Header],
+    provisioners: &Provisioners,
+) -> Result<(), HashChainError> {
+    let mut prev = trusted;
+    for header in headers {
+        verify_next(prev, header)?;
+        if !is_emergency_block(header.iteration) {
+            QuorumBundle::export(header, prev.seed, provisioners)
+                .map_err(|e| HashChainError::NotAttested(header.height, e))?;
+        }
+        prev = header;
+    }
+    Ok(())
+}
+
+fn header_by_height<L: Ledger>(
+    ledger: &L,
+    height: u64,
+) -> Result<Header, HashChainError> {
+    let hash = ledger
+        .block_hash_by_height(height)?
+        .ok_or(HashChainError::MissingBlock(height))?;
+    ledger
+        .block_header(&hash)?
+        .ok_or(HashChainError::MissingBlock(height))
+}
+
+/// Verifies the fields of `header` that don't depend on the previous block
+pub(crate) fn verify_header(
+    header: &Header,
+    height: u64,
+) -> Result<(), HashChainError> {
+    if header.height != height {
+        return Err(HashChainError::InvalidHeight(height, header.height));
+    }
+
+    let hash = header.compute_hash().map_err(anyhow::Error::from)?;
+    if hash != header.hash {
+        return Err(HashChainError::InvalidHash(height, to_str(&header.hash)));
+    }
+
+    // Neither the genesis nor emergency blocks carry a quorum attestation
+    let attested = height == 0
+        || is_emergency_block(header.iteration)
+        || attests(&header.att, header.hash);
+    if !attested {
+        return Err(HashChainError::InvalidAttestation(
+            height,
+            "attestation does not vote for the block",
+        ));
+    }
+
+    Ok(())
+}
+
+/// Verifies the links of `header` to the previous block
+fn verify_link(prev: &Header, header: &Header) -> Result<(), HashChainError> {
+    let height = header.height;
+    if header.prev_block_hash != prev.hash {
+        return Err(HashChainError::InvalidPrevBlockHash(height));
+    }
+
+    verify_seed(prev, header)
+        .map_err(|e| HashChainError::InvalidSeed(height, e))?;
+
+    let attested = prev.height == 0
+        || is_emergency_block(prev.iteration)
+        || attests(&header.prev_block_cert, prev.hash);
+    if !attested {
+        return Err(HashChainError::InvalidAttestation(
+            height,
+            "prev_block_cert does not vote for the previous block",
+        ));
+    }
+
+    Ok(())
+}
+
+fn verify_seed(prev: &Header, header: &Header) -> Result<(), String> {
+    let generator =
+        BlsPublicKey::from_bytes(header.generator_bls_pubkey.inner())
+            .map_err(|e| format!("invalid generator: {e:?}"))?;
+    let generator = MultisigPublicKey::aggregate(&[generator])
+        .map_err(|e| format!("invalid generator: {e:?}"))?;
+    let seed = MultisigSignature::from_bytes(header.seed.inner())
+        .map_err(|e| format!("invalid seed bytes: {e:?}"))?;
+
+    generator
+        .verify(&seed, prev.seed.inner())
+        .map_err(|e| format!("{e:?}"))
+}
+
+fn attests(att: &Attestation, hash: Hash) -> bool {
+    att.result == RatificationResult::Success(Vote::Valid(hash))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::collections::BTreeMap;
+
+    use dusk_consensus::merkle::merkle_root;
+    use dusk_consensus::quorum::verifiers::step_committee;
+    use dusk_consensus::user::provisioners::DUSK;
+    use dusk_core::signatures::bls::SecretKey as BlsSecretKey;
+    use node_data::bls::PublicKey;
+    use node_data::ledger::{Seed, StepVotes};
+    use node_data::message::payload;
+    use node_data::message::SignedStepMessage;
+    use node_data::{Serializable as NodeSerializable, StepName};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::*;
+
+    pub(crate) type Keys = BTreeMap<PublicKey, BlsSecretKey>;
+
+    pub(crate) fn new_provisioners(
+        rng: &mut StdRng,
+        n: usize,
+    ) -> (Provisioners, Keys) {
+        let mut provisioners = Provisioners::empty();
+        let mut sks = BTreeMap::new();
+        for _ in 0..n {
+            let sk = BlsSecretKey::random(rng);
+            let pk = PublicKey::new(BlsPublicKey::from(&sk));
+            provisioners.add_member_with_value(pk.clone(), 1000 * DUSK);
+            sks.insert(pk, sk);
+        }
+        (provisioners, sks)
+    }
+
+    /// Returns the header of the block generated by `sk` on top of `prev`,
+    /// with an attestation voting for it but no votes
+    pub(crate) fn child(
+        prev: &Header,
+        pk: &PublicKey,
+        sk: &BlsSecretKey,
+    ) -> Header {
+        let seed = sk.sign_multisig(pk.inner(), prev.seed.inner());
+        let mut header = Header {
+            height: prev.height + 1,
+            prev_block_hash: prev.hash,
+            prev_block_cert: prev.att,
+            seed: Seed::from(seed.to_bytes()),
+            generator_bls_pubkey: *pk.bytes(),
+            state_hash: [prev.height as u8 + 1; 32],
+            txroot: merkle_root::<[u8; 32]>(&[]),
+            faultroot: merkle_root::<[u8; 32]>(&[]),
+            ..Default::default()
+        };
+        seal(&mut header);
+        header
+    }
+
+    pub(crate) fn seal(header: &mut Header) {
+        header.hash = header.compute_hash().unwrap();
+        header.att.result =
+            RatificationResult::Success(Vote::Valid(header.hash));
+    }
+
+    /// Signs the attestation of `header` with all the members of the
+    /// committees extracted from `provisioners`
+    pub(crate) fn attest(
+        header: &mut Header,
+        prev_seed: Seed,
+        provisioners: &Provisioners,
+        sks: &Keys,
+    ) {
+        let consensus_header = header.to_consensus_header();
+        let vote = Vote::Valid(header.hash);
+        for step in [StepName::Validation, StepName::Ratification] {
+            let committee = step_committee(
+                provisioners,
+                header.height,
+                header.iteration,
+                prev_seed,
+                step,
+            );
+
+            let mut msg = consensus_header.signable();
+            msg.extend_from_slice(match step {
+                StepName::Validation => payload::Validation::SIGN_SEED,
+                _ => payload::Ratification::SIGN_SEED,
+            });
+            vote.write(&mut msg).unwrap();
+
+            let sigs: Vec<_> = committee
+                .iter()
+                .map(|pk| sks[pk].sign_multisig(pk.inner(), &msg))
+                .collect();
+            let signature = sigs[0].aggregate(&sigs[1..]);
+            let bitset = (1u64 << committee.size()) - 1;
+            let step_votes = StepVotes::new(signature.to_bytes(), bitset);
+            match step {
+                StepName::Validation => header.att.validation = step_votes,
+                _ => header.att.ratification = step_votes,
+            }
+        }
+    }
+
+    #[test]
+    fn test_verify_headers() {
+        let mut rng = StdRng::seed_from_u64(0xbeef);
+        let (provisioners, sks) = new_provisioners(&mut rng, 10);
+        let (others, _) = new_provisioners(&mut rng, 10);
+        let (pk, sk) = sks.iter().next().unwrap();
+
+        let mut trusted = Header::default();
+        seal(&mut trusted);
+
+        let mut headers = vec![];
+        let mut prev = trusted.clone();
+        for _ in 0..3 {
+            let mut header = child(&prev, pk, sk);
+            attest(&mut header, prev.seed, &provisioners, &sks);
+            headers.push(header.clone());
+            prev = header;
+        }
+        assert!(verify_headers(&trusted, &headers, &provisioners).is_ok());
+
+        // The headers aren't attested by another provisioner set
+        let res = verify_headers(&trusted, &headers, &others);
+        assert!(matches!(res, Err(HashChainError::NotAttested(1, _))));
+
+        // A header voting for itself without votes isn't attested
+        let unattested = child(&prev, pk, sk);
+        headers.push(unattested);
+        let res = verify_headers(&trusted, &headers, &provisioners);
+        assert!(matches!(res, Err(HashChainError::NotAttested(4, _))));
+    }
+}
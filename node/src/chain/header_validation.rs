@@ -14,11 +14,11 @@ use dusk_consensus::config::{
     MINIMUM_BLOCK_TIME, MIN_EMERGENCY_BLOCK_TIME, RELAX_ITERATION_THRESHOLD,
 };
 use dusk_consensus::errors::{
-    AttestationError, FailedIterationError, HeaderError,
+    AttestationError, FailedIterationError, HeaderError, VstError,
 };
 use dusk_consensus::operations::Voter;
 use dusk_consensus::quorum::verifiers;
-use dusk_consensus::quorum::verifiers::QuorumResult;
+use dusk_consensus::quorum::verifiers::{DeferredSignatures, QuorumResult};
 use dusk_consensus::user::committee::CommitteeSet;
 use dusk_consensus::user::provisioners::{ContextProvisioners, Provisioners};
 use dusk_core::signatures::bls::{
@@ -29,7 +29,10 @@ use hex;
 use node_data::bls::PublicKeyBytes;
 use node_data::ledger::{Fault, InvalidFault, Seed, Signature};
 use node_data::message::payload::{RatificationResult, Vote};
-use node_data::message::{ConsensusHeader, BLOCK_HEADER_VERSION};
+use node_data::message::{
+    ConsensusHeader, BLOCK_HEADER_VERSION, EXTRA_DATA_HEADER_VERSION,
+    MAX_HEADER_EXTRA_DATA_SIZE, PROVISIONERS_ROOT_HEADER_VERSION,
+};
 use node_data::{get_current_timestamp, ledger, StepName};
 use thiserror::Error;
 use tokio::sync::RwLock;
@@ -37,9 +40,13 @@ use tracing::{debug, error};
 
 use crate::database;
 use crate::database::Ledger;
+use crate::vm::{BlockContext, VMExecution};
 
 const MARGIN_TIMESTAMP: u64 = 3;
 
+/// Number of headers the median time past is computed over
+pub const MEDIAN_TIME_PAST_SPAN: usize = 11;
+
 // TODO: Use thiserror instead of anyhow
 
 #[derive(Debug, Error)]
@@ -150,7 +157,9 @@ impl<'a, DB: database::DB> Validator<'a, DB> {
         candidate_block: &'a ledger::Header,
         generator: &MultisigPublicKey,
     ) -> Result<(), HeaderError> {
-        if candidate_block.version != BLOCK_HEADER_VERSION {
+        if !(BLOCK_HEADER_VERSION..=EXTRA_DATA_HEADER_VERSION)
+            .contains(&candidate_block.version)
+        {
             return Err(HeaderError::UnsupportedVersion);
         }
 
@@ -241,7 +250,7 @@ impl<'a, DB: database::DB> Validator<'a, DB> {
         Ok(())
     }
 
-    async fn verify_prev_block_cert(
+    pub(crate) async fn verify_prev_block_cert(
         &self,
         candidate_block: &'a ledger::Header,
     ) -> Result<Vec<Voter>, HeaderError> {
@@ -288,6 +297,9 @@ impl<'a, DB: database::DB> Validator<'a, DB> {
         candidate_block: &'a ledger::Header,
     ) -> Result<u8, FailedIterationError> {
         let mut failed_atts = 0u8;
+        // The signatures of all the failed attestations are verified in
+        // parallel, once all their quorums are checked
+        let mut deferred = DeferredSignatures::default();
 
         let att_list = &candidate_block.failed_iterations.att_list;
 
@@ -315,12 +327,13 @@ impl<'a, DB: database::DB> Validator<'a, DB> {
                     candidate_block.to_consensus_header();
                 consensus_header.iteration = iter as u8;
 
-                verify_att(
+                verify_att_deferred(
                     att,
                     consensus_header,
                     self.prev_header.seed,
                     self.provisioners.current(),
                     Some(RatificationResult::Fail(Vote::default())),
+                    &mut deferred,
                 )
                 .await?;
 
@@ -328,6 +341,10 @@ impl<'a, DB: database::DB> Validator<'a, DB> {
             }
         }
 
+        deferred
+            .verify()
+            .map_err(|(step, e)| AttestationError::InvalidVotes(step, e))?;
+
         // In case of Emergency Block, which iteration number is u8::MAX, we
         // count failed iterations up to CONSENSUS_MAX_ITER
         let last_iter = cmp::min(candidate_block.iteration, CONSENSUS_MAX_ITER);
@@ -424,6 +441,33 @@ pub async fn verify_att(
     curr_seed: Signature,
     curr_eligible_provisioners: &Provisioners,
     expected_result: Option<RatificationResult>,
+) -> Result<(QuorumResult, QuorumResult, Vec<Voter>), AttestationError> {
+    let mut deferred = DeferredSignatures::default();
+    let result = verify_att_deferred(
+        att,
+        consensus_header,
+        curr_seed,
+        curr_eligible_provisioners,
+        expected_result,
+        &mut deferred,
+    )
+    .await?;
+    deferred
+        .verify()
+        .map_err(|(step, e)| AttestationError::InvalidVotes(step, e))?;
+
+    Ok(result)
+}
+
+/// Verifies an attestation, deferring the verification of the step
+/// signatures to `deferred`
+pub async fn verify_att_deferred(
+    att: &ledger::Attestation,
+    consensus_header: ConsensusHeader,
+    curr_seed: Signature,
+    curr_eligible_provisioners: &Provisioners,
+    expected_result: Option<RatificationResult>,
+    deferred: &mut DeferredSignatures,
 ) -> Result<(QuorumResult, QuorumResult, Vec<Voter>), AttestationError> {
     // Check expected result
     if let Some(expected) = expected_result {
@@ -457,33 +501,144 @@ pub async fn verify_att(
     let vote = att.result.vote();
 
     // Verify validation
-    let (val_result, validation_voters) = verifiers::verify_step_votes(
-        &consensus_header,
-        vote,
-        &att.validation,
-        &committee,
-        curr_seed,
-        StepName::Validation,
-    )
-    .await
-    .map_err(|s| AttestationError::InvalidVotes(StepName::Validation, s))?;
+    let (val_result, validation_voters) =
+        verifiers::verify_step_votes_deferred(
+            &consensus_header,
+            vote,
+            &att.validation,
+            &committee,
+            curr_seed,
+            StepName::Validation,
+            deferred,
+        )
+        .await
+        .map_err(|s| AttestationError::InvalidVotes(StepName::Validation, s))?;
 
     // Verify ratification
-    let (rat_result, ratification_voters) = verifiers::verify_step_votes(
-        &consensus_header,
-        vote,
-        &att.ratification,
-        &committee,
-        curr_seed,
-        StepName::Ratification,
-    )
-    .await
-    .map_err(|s| AttestationError::InvalidVotes(StepName::Ratification, s))?;
+    let (rat_result, ratification_voters) =
+        verifiers::verify_step_votes_deferred(
+            &consensus_header,
+            vote,
+            &att.ratification,
+            &committee,
+            curr_seed,
+            StepName::Ratification,
+            deferred,
+        )
+        .await
+        .map_err(|s| {
+            AttestationError::InvalidVotes(StepName::Ratification, s)
+        })?;
 
     let voters = merge_voters(validation_voters, ratification_voters);
     Ok((val_result, rat_result, voters))
 }
 
+/// Verifies the provisioner set commitment of a candidate header against the
+/// one computed by the VM on top of `prev_header`.
+///
+/// Headers must commit to the provisioner set if and only if the VM expects
+/// one for their height.
+pub fn verify_provisioners_root<VM: VMExecution>(
+    vm: &VM,
+    prev_header: &ledger::Header,
+    candidate: &ledger::Header,
+) -> Result<(), HeaderError> {
+    let expected = vm
+        .get_provisioners_root(candidate.height, prev_header.state_hash)
+        .map_err(|e| {
+            HeaderError::Storage("error computing provisioners root", e)
+        })?;
+
+    let has_root = candidate.version >= PROVISIONERS_ROOT_HEADER_VERSION;
+    match expected {
+        None if has_root => Err(HeaderError::UnsupportedVersion),
+        None => Ok(()),
+        Some(_) if !has_root => Err(HeaderError::UnsupportedVersion),
+        Some(root) if root != candidate.provisioners_root => {
+            Err(HeaderError::InvalidProvisionersRoot(
+                root,
+                candidate.provisioners_root,
+            ))
+        }
+        Some(_) => Ok(()),
+    }
+}
+
+/// Verifies the extra-data of a candidate header.
+///
+/// Headers must carry the extra-data if and only if the VM expects it for
+/// their height, and it must not exceed [`MAX_HEADER_EXTRA_DATA_SIZE`] bytes.
+pub fn verify_extra_data<VM: VMExecution>(
+    vm: &VM,
+    candidate: &ledger::Header,
+) -> Result<(), HeaderError> {
+    let expected = vm.with_header_extra_data(candidate.height);
+    let has_extra_data = candidate.version >= EXTRA_DATA_HEADER_VERSION;
+    if expected != has_extra_data {
+        return Err(HeaderError::UnsupportedVersion);
+    }
+    if candidate.extra_data.len() > MAX_HEADER_EXTRA_DATA_SIZE {
+        return Err(HeaderError::InvalidExtraData(candidate.extra_data.len()));
+    }
+    Ok(())
+}
+
+/// Verifies the transactions of a block are in canonical order, if its
+/// context expects it.
+///
+/// The transactions of other blocks must not carry a bundle position.
+pub fn verify_tx_order(
+    ctx: &BlockContext,
+    blk: &ledger::Block,
+) -> Result<(), VstError> {
+    let ordered = if ctx.with_canonical_tx_order() {
+        ledger::is_canonically_ordered(blk.txs())
+    } else {
+        blk.txs().iter().all(|tx| tx.bundle().is_none())
+    };
+    if !ordered {
+        return Err(VstError::InvalidTxOrder);
+    }
+    Ok(())
+}
+
+/// Returns the median timestamp of the last `span` headers, `tip` included.
+///
+/// Fewer headers are considered if the chain is shorter than `span`.
+///
+/// Block timestamps are not checked against it, since the minimum block time
+/// already keeps each of them above the previous ones, hence above the median.
+pub fn median_time_past<L: Ledger>(
+    ledger: &L,
+    tip: &ledger::Header,
+    span: usize,
+) -> anyhow::Result<u64> {
+    let mut timestamps = Vec::with_capacity(span);
+    timestamps.push(tip.timestamp);
+
+    let mut height = tip.height;
+    let mut prev_hash = tip.prev_block_hash;
+    while timestamps.len() < span && height > 0 {
+        let header = ledger.block_header(&prev_hash)?.ok_or_else(|| {
+            anyhow::anyhow!("missing header {}", hex::encode(prev_hash))
+        })?;
+        timestamps.push(header.timestamp);
+        height = header.height;
+        prev_hash = header.prev_block_hash;
+    }
+
+    Ok(median(&mut timestamps))
+}
+
+/// Returns the median of a non-empty list of timestamps.
+///
+/// For an even number of timestamps the lower median is returned.
+fn median(timestamps: &mut [u64]) -> u64 {
+    timestamps.sort_unstable();
+    timestamps[(timestamps.len() - 1) / 2]
+}
+
 /// Merges two Vec<Voter>, summing up the usize values if the PublicKey is
 /// repeated
 fn merge_voters(v1: Vec<Voter>, v2: Vec<Voter>) -> Vec<Voter> {
@@ -496,3 +651,16 @@ fn merge_voters(v1: Vec<Voter>, v2: Vec<Voter>) -> Vec<Voter> {
 
     voter_map.into_iter().collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_median() {
+        assert_eq!(median(&mut [5]), 5);
+        assert_eq!(median(&mut [30, 10, 20]), 20);
+        assert_eq!(median(&mut [40, 10, 30, 20]), 20);
+        assert_eq!(median(&mut [10, 10, 1000, 10, 11]), 10);
+    }
+}
@@ -0,0 +1,155 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Introspection of the consensus task.
+//!
+//! The consensus [`Task`] registers its queues once its keys are loaded, and
+//! reports every lifecycle and step transition. A snapshot of the resulting
+//! state can be taken at any time with [`consensus_health`].
+
+use std::sync::{Arc, OnceLock, RwLock};
+
+use dusk_consensus::queue::MsgRegistry;
+use node_data::message::AsyncQueue;
+use node_data::{get_current_timestamp, StepName};
+use serde::Serialize;
+use tokio::sync::Mutex;
+use tracing::{debug, info};
+
+use super::consensus::Task;
+use crate::Message;
+
+static QUEUES: OnceLock<TaskQueues> = OnceLock::new();
+
+static STATE: RwLock<TaskState> = RwLock::new(TaskState {
+    running: false,
+    round: 0,
+    iteration: 0,
+    step: None,
+    last_transition: None,
+    restarts: 0,
+});
+
+struct TaskQueues {
+    main_inbound: AsyncQueue<Message>,
+    outbound: AsyncQueue<Message>,
+    future_msg: Arc<Mutex<MsgRegistry<Message>>>,
+}
+
+#[derive(Clone, Copy)]
+struct TaskState {
+    running: bool,
+    round: u64,
+    iteration: u8,
+    step: Option<StepName>,
+    last_transition: Option<u64>,
+    restarts: u64,
+}
+
+/// Snapshot of the consensus task state
+#[derive(Debug, Clone, Serialize)]
+pub struct ConsensusHealth {
+    /// Whether the consensus keys were loaded
+    pub keys_loaded: bool,
+    /// Whether a consensus task is running
+    pub running: bool,
+    pub round: u64,
+    pub iteration: u8,
+    /// The step currently executed, if any
+    pub step: Option<String>,
+    /// Timestamp (in seconds) of the last step transition
+    pub last_transition: Option<u64>,
+    /// Number of tasks spawned again for a round already started
+    pub restarts: u64,
+    pub main_inbound_len: usize,
+    pub outbound_len: usize,
+    pub future_msg_len: usize,
+    /// Number of bytes held by the messages of the queues
+    pub main_inbound_bytes: usize,
+    pub future_msg_bytes: usize,
+}
+
+/// Returns a snapshot of the consensus task state
+pub async fn consensus_health() -> ConsensusHealth {
+    let state = *STATE.read().expect("lock not to be poisoned");
+
+    let mut health = ConsensusHealth {
+        keys_loaded: QUEUES.get().is_some(),
+        running: state.running,
+        round: state.round,
+        iteration: state.iteration,
+        step: state.step.map(|s| format!("{s:?}")),
+        last_transition: state.last_transition,
+        restarts: state.restarts,
+        main_inbound_len: 0,
+        outbound_len: 0,
+        future_msg_len: 0,
+        main_inbound_bytes: 0,
+        future_msg_bytes: 0,
+    };
+
+    if let Some(q) = QUEUES.get() {
+        let future_msg = q.future_msg.lock().await;
+        health.main_inbound_len = q.main_inbound.len();
+        health.outbound_len = q.outbound.len();
+        health.future_msg_len = future_msg.msg_count();
+        health.main_inbound_bytes = q.main_inbound.memory_usage();
+        health.future_msg_bytes = future_msg.memory_usage();
+    }
+
+    health
+}
+
+/// Registers the queues of a task whose keys were loaded
+pub(super) fn register(task: &Task) {
+    let queues = TaskQueues {
+        main_inbound: task.main_inbound.clone(),
+        outbound: task.outbound.clone(),
+        future_msg: task.future_msg.clone(),
+    };
+    let _ = QUEUES.set(queues);
+}
+
+/// Records a task being spawned for `round`
+pub(super) fn task_spawned(round: u64) {
+    info!(event = "consensus task spawned", round);
+
+    let mut state = STATE.write().expect("lock not to be poisoned");
+    // A task spawned for a round not higher than the previous one is a restart
+    let restarted = state.last_transition.is_some() && round <= state.round;
+    *state = TaskState {
+        running: true,
+        round,
+        iteration: 0,
+        step: None,
+        last_transition: Some(get_current_timestamp()),
+        restarts: state.restarts + u64::from(restarted),
+    };
+}
+
+/// Records the running task being stopped
+pub(super) fn task_stopped() {
+    let mut state = STATE.write().expect("lock not to be poisoned");
+    if !state.running {
+        return;
+    }
+
+    info!(event = "consensus task stopped", round = state.round);
+    state.running = false;
+    state.step = None;
+    state.last_transition = Some(get_current_timestamp());
+}
+
+/// Records the start of a step
+pub(super) fn step_started(round: u64, iteration: u8, step: StepName) {
+    debug!(event = "consensus step started", round, iteration, ?step);
+
+    let mut state = STATE.write().expect("lock not to be poisoned");
+    state.round = round;
+    state.iteration = iteration;
+    state.step = Some(step);
+    state.last_transition = Some(get_current_timestamp());
+}
@@ -0,0 +1,252 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Consensus keys of the node, and their rotation.
+//!
+//! A provisioner rotates its account key by registering the next one with a
+//! `rotate_key` call to the stake contract, which moves the stake to the next
+//! key at the start of the following epoch. The next keys are loaded from a
+//! file next to the current ones, with the `next.keys` extension and the same
+//! password.
+//!
+//! The acceptor schedules the rotations of the node keys registered in the
+//! accepted blocks. The consensus signs with the next keys from the first
+//! round whose provisioners include them in place of the current ones, after
+//! which the messages signed with the current keys are rejected, since they
+//! are no longer provisioners.
+//!
+//! The keys are stored in encrypted keystores, see [`node_data::keystore`].
+//!
+//! The keys can also be reloaded from their files while the node is running,
+//! e.g. to load the next keys ahead of a rotation. The reloaded keys replace
+//! the loaded ones when the consensus task of the next round is spawned.
+
+use std::path::Path;
+
+use dusk_consensus::user::provisioners::Provisioners;
+use dusk_core::signatures::bls::SecretKey as BlsSecretKey;
+use dusk_core::stake::KeyRotation;
+use node_data::bls::PublicKey;
+use node_data::keystore::{next_keys_path, unlock_file};
+use tracing::{info, warn};
+
+pub(crate) type KeyPair = (BlsSecretKey, PublicKey);
+
+/// Keys signing the consensus messages, along with the ones replacing them
+pub(crate) struct ConsensusKeys {
+    current: KeyPair,
+    next: Option<KeyPair>,
+    /// Height the rotation to the next keys takes effect at, once registered
+    activation: Option<u64>,
+}
+
+impl ConsensusKeys {
+    pub fn new(current: KeyPair, next: Option<KeyPair>) -> Self {
+        Self {
+            current,
+            next,
+            activation: None,
+        }
+    }
+
+    /// Unlocks the keys at `path` with `pwd`, and the next ones if their
+    /// file exists
+    pub fn unlock(path: &str, pwd: &str) -> anyhow::Result<Self> {
+        let current = unlock_file(Path::new(path), pwd)?.into_keys();
+
+        let next_path = next_keys_path(Path::new(path));
+        let next = match next_path.exists() {
+            true => {
+                let next = unlock_file(&next_path, pwd)?.into_keys();
+                info!(
+                    event = "loaded next consensus keys",
+                    path = next_path.display().to_string(),
+                    pubkey = format!("{:?}", next.1)
+                );
+                Some(next)
+            }
+            false => None,
+        };
+
+        Ok(Self::new(current, next))
+    }
+
+    /// Unlocks the keys at `path` with the password from env var
+    /// DUSK_CONSENSUS_KEYS_PASS
+    pub fn unlock_from_env(path: &str) -> anyhow::Result<Self> {
+        let pwd = std::env::var("DUSK_CONSENSUS_KEYS_PASS")
+            .map_err(|_| anyhow::anyhow!("DUSK_CONSENSUS_KEYS_PASS not set"))?;
+        info!(event = "loading consensus keys", path = path);
+        let keys = Self::unlock(path, &pwd)?;
+
+        info!(
+            event = "loaded consensus keys",
+            pubkey = format!("{:?}", keys.current().1)
+        );
+
+        Ok(keys)
+    }
+
+    /// Replaces the keys with the `reloaded` ones, keeping the scheduled
+    /// rotation if it still rotates to the same next keys
+    pub fn replace(&mut self, reloaded: Self) {
+        let same_rotation = self.current.1 == reloaded.current.1
+            && matches!(
+                (&self.next, &reloaded.next),
+                (Some((_, next)), Some((_, reloaded))) if next == reloaded
+            );
+        let activation = self.activation.filter(|_| same_rotation);
+
+        info!(
+            event = "consensus keys replaced",
+            prev = self.current.1.to_bs58(),
+            current = reloaded.current.1.to_bs58(),
+            next = ?reloaded.next.as_ref().map(|(_, pk)| pk.to_bs58()),
+            activation
+        );
+        *self = Self {
+            activation,
+            ..reloaded
+        };
+    }
+
+    /// Returns the keys currently signing the consensus messages
+    pub fn current(&self) -> &KeyPair {
+        &self.current
+    }
+
+    /// Schedules the rotation registered in an accepted block, if it rotates
+    /// the current keys
+    pub fn schedule(&mut self, rotation: &KeyRotation) {
+        if rotation.account() != self.current.1.inner() {
+            return;
+        }
+
+        let activation = rotation.activation();
+        match &self.next {
+            Some((_, next)) if next.inner() == rotation.next_account() => {
+                self.activation = Some(activation);
+                info!(
+                    event = "consensus keys rotation scheduled",
+                    activation,
+                    next = next.to_bs58()
+                );
+            }
+            _ => warn!(
+                event = "consensus keys rotation to unknown keys",
+                activation,
+                next = PublicKey::new(*rotation.next_account()).to_bs58(),
+                "the next keys should be loaded before the rotation"
+            ),
+        }
+    }
+
+    /// Returns the keys signing the messages of `round`, switching to the
+    /// next ones once the provisioners of the round include them in place of
+    /// the current ones.
+    ///
+    /// The switch happens at the first round of the epoch the rotation takes
+    /// effect at, or right away for rotations that took effect while the node
+    /// was stopped.
+    pub fn for_round(
+        &mut self,
+        round: u64,
+        provisioners: &Provisioners,
+    ) -> &KeyPair {
+        let rotated = self.next.as_ref().is_some_and(|(_, next)| {
+            provisioners.get_member(next).is_some()
+                && provisioners.get_member(&self.current.1).is_none()
+        });
+
+        if rotated {
+            let next = self.next.take().expect("next keys to be loaded");
+            info!(
+                event = "consensus keys rotated",
+                round,
+                scheduled = self.activation,
+                prev = self.current.1.to_bs58(),
+                current = next.1.to_bs58()
+            );
+            self.current = next;
+            self.activation = None;
+        }
+
+        &self.current
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use dusk_consensus::user::stake::Stake;
+    use dusk_core::signatures::bls::PublicKey as BlsPublicKey;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::*;
+
+    fn key_pair(rng: &mut StdRng) -> KeyPair {
+        let sk = BlsSecretKey::random(rng);
+        let pk = PublicKey::new(BlsPublicKey::from(&sk));
+        (sk, pk)
+    }
+
+    fn provisioners(keys: &[&KeyPair]) -> Provisioners {
+        let mut provisioners = Provisioners::empty();
+        for (_, pk) in keys {
+            provisioners.add_member_with_stake(pk.clone(), Stake::new(1, 0));
+        }
+        provisioners
+    }
+
+    #[test]
+    fn test_rotation() {
+        let rng = &mut StdRng::seed_from_u64(0xbeef);
+        let current = key_pair(rng);
+        let next = key_pair(rng);
+        let mut keys = ConsensusKeys::new(current.clone(), Some(next.clone()));
+
+        // Rotations of other keys are ignored
+        let other = key_pair(rng);
+        keys.schedule(&KeyRotation::new(&other.0, &next.0, 2160, 0));
+        assert_eq!(keys.activation, None);
+
+        keys.schedule(&KeyRotation::new(&current.0, &next.0, 2160, 0));
+        assert_eq!(keys.activation, Some(2160));
+
+        // The current keys sign until the stake is moved to the next ones
+        let before = provisioners(&[&current]);
+        assert_eq!(keys.for_round(2160, &before).1, current.1);
+
+        let after = provisioners(&[&next]);
+        assert_eq!(keys.for_round(2161, &after).1, next.1);
+        assert_eq!(keys.activation, None);
+
+        // The rotation is not undone
+        assert_eq!(keys.for_round(2162, &before).1, next.1);
+    }
+
+    #[test]
+    fn test_replace() {
+        let rng = &mut StdRng::seed_from_u64(0xcafe);
+        let current = key_pair(rng);
+        let next = key_pair(rng);
+        let mut keys = ConsensusKeys::new(current.clone(), Some(next.clone()));
+        keys.schedule(&KeyRotation::new(&current.0, &next.0, 2160, 0));
+
+        // Reloading the same keys keeps the scheduled rotation
+        keys.replace(ConsensusKeys::new(current.clone(), Some(next.clone())));
+        assert_eq!(keys.activation, Some(2160));
+
+        // Other next keys don't rotate as scheduled
+        let other = key_pair(rng);
+        keys.replace(ConsensusKeys::new(current.clone(), Some(other.clone())));
+        assert_eq!(keys.activation, None);
+
+        let after = provisioners(&[&other]);
+        keys.replace(ConsensusKeys::new(other.clone(), None));
+        assert_eq!(keys.for_round(2161, &after).1, other.1);
+    }
+}
@@ -0,0 +1,139 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Record of the last iteration run by the consensus.
+//!
+//! A restarted round is resumed after the recorded iteration, so that the
+//! node doesn't take part again in an iteration it may have already voted or
+//! generated a candidate in. The record is raised in the same transaction
+//! storing a candidate or a validation result, so that it's never behind
+//! them.
+//!
+//! A record written otherwise, e.g. by a former version, can still be behind
+//! the stored ones after a crash. It's then repaired when the round is
+//! resumed, raising it to the last iteration something is stored for.
+
+use anyhow::Result;
+use node_data::ledger::Hash;
+use tracing::warn;
+
+use crate::database::rocksdb::MD_LAST_ITER;
+use crate::database::{ConsensusStorage, Metadata};
+
+/// Returns the recorded tip hash and iteration, if any
+pub(crate) fn read<M: Metadata>(t: &M) -> Result<Option<(Hash, u8)>> {
+    let record = t.op_read(MD_LAST_ITER)?.filter(|v| v.len() == 33).map(|v| {
+        let mut hash = [0u8; 32];
+        hash.copy_from_slice(&v[0..32]);
+        (hash, v[32])
+    });
+    Ok(record)
+}
+
+/// Records `iter` as the last iteration run on top of `hash`
+pub(crate) fn write<M: Metadata>(
+    t: &mut M,
+    hash: Hash,
+    iter: u8,
+) -> Result<()> {
+    let mut record = hash.to_vec();
+    record.push(iter);
+    t.op_write(MD_LAST_ITER, record)
+}
+
+/// Raises the record to `iter`, unless it's already ahead for `hash`
+pub(crate) fn raise<M: Metadata>(
+    t: &mut M,
+    hash: Hash,
+    iter: u8,
+) -> Result<()> {
+    match read(t)? {
+        Some((recorded, last)) if recorded == hash && last >= iter => Ok(()),
+        _ => write(t, hash, iter),
+    }
+}
+
+/// Repairs the record of the round following `hash` if it's behind the
+/// candidates and validation results stored for it.
+///
+/// Returns the iteration the record is repaired to.
+pub(crate) fn recover<T: Metadata + ConsensusStorage>(
+    t: &mut T,
+    hash: Hash,
+) -> Result<Option<u8>> {
+    let Some(stored) = t.last_stored_iteration(&hash)? else {
+        return Ok(None);
+    };
+
+    let recorded = read(t)?;
+    match recorded {
+        Some((recorded, last)) if recorded == hash && last >= stored => {
+            Ok(None)
+        }
+        _ => {
+            warn!(
+                event = "last iteration behind the stored ones",
+                recorded = ?recorded.map(|(_, last)| last),
+                stored
+            );
+            write(t, hash, stored)?;
+            Ok(Some(stored))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fake::{Fake, Faker};
+    use node_data::ledger::{Block, Header};
+    use node_data::message::ConsensusHeader;
+
+    use super::*;
+    use crate::database::rocksdb::Backend;
+    use crate::database::{DatabaseOptions, DB};
+
+    #[test]
+    fn test_recover() {
+        let dir = tempfile::tempdir().unwrap();
+        let db =
+            Backend::create_or_open(dir.path(), DatabaseOptions::default());
+        let tip = [1; 32];
+
+        db.update(|t| {
+            assert_eq!(recover(t, tip)?, None);
+
+            // Stored on top of another tip
+            write(t, [2; 32], 5)?;
+            let header = Header {
+                prev_block_hash: tip,
+                iteration: 3,
+                hash: [3; 32],
+                ..Default::default()
+            };
+            t.store_candidate(Block::new(header, vec![], vec![])?)?;
+            assert_eq!(recover(t, tip)?, Some(3));
+            assert_eq!(read(t)?, Some((tip, 3)));
+
+            // Behind the stored ones
+            let ch = ConsensusHeader {
+                prev_block_hash: tip,
+                round: 1,
+                iteration: 4,
+            };
+            t.store_validation_result(&ch, &Faker.fake())?;
+            assert_eq!(recover(t, tip)?, Some(4));
+
+            // Ahead of the stored ones
+            raise(t, tip, 6)?;
+            raise(t, tip, 2)?;
+            assert_eq!(recover(t, tip)?, None);
+            assert_eq!(read(t)?, Some((tip, 6)));
+
+            Ok(())
+        })
+        .unwrap();
+    }
+}
@@ -0,0 +1,448 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Participation of the provisioners in the committees of the accepted blocks.
+//!
+//! The members of the validation and ratification committees of the
+//! attestation of every accepted block are compared with the voters in its
+//! bitsets. The steps each provisioner was in the committee of, and the ones
+//! its vote is missing from, are kept for the last [`BLOCK_WINDOW`] blocks to
+//! compute its participation score.
+//!
+//! Provisioners whose score drops below [`WITHHOLDING_SCORE`] over at least
+//! [`MIN_APPEARANCES`] steps are reported as withholding their votes. The last
+//! [`EVIDENCE_RETENTION`] reports are kept as evidence.
+//!
+//! A vote reaching the generator after the quorum is missing from the bitsets
+//! as well, so a low score alone doesn't prove any misbehavior.
+//!
+//! The reports are stored in the Metadata CF with [`persist`], along with the
+//! peer reputation, so that they can be reloaded with [`load`] on restart. The
+//! scores themselves start over when the node starts.
+
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::io::{self, Read, Write};
+use std::sync::{LazyLock, Mutex};
+
+use dusk_consensus::quorum::verifiers;
+use dusk_consensus::user::provisioners::Provisioners;
+use metrics::{counter, gauge};
+use node_data::bls::PublicKey;
+use node_data::ledger::{Header, Seed};
+use node_data::{Serializable, StepName};
+use serde::Serialize;
+use tracing::warn;
+
+use crate::database::rocksdb::MD_WITHHOLDING_EVIDENCE;
+use crate::database::Metadata;
+
+/// Number of blocks the participation is computed over
+pub const BLOCK_WINDOW: usize = 2_160;
+
+/// Minimum number of steps in a committee for a provisioner to be reported as
+/// withholding its votes
+pub const MIN_APPEARANCES: u64 = 50;
+
+/// Score (in percent) under which a provisioner is reported as withholding
+/// its votes
+pub const WITHHOLDING_SCORE: u8 = 20;
+
+/// Number of withholding reports kept
+pub const EVIDENCE_RETENTION: usize = 100;
+
+static PARTICIPATION: LazyLock<Mutex<ParticipationTracker>> =
+    LazyLock::new(|| Mutex::new(ParticipationTracker::default()));
+
+/// Steps a provisioner was in the committee of, and absent from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct Tally {
+    appearances: u64,
+    absences: u64,
+}
+
+impl Tally {
+    fn add(&mut self, other: &Tally) {
+        self.appearances += other.appearances;
+        self.absences += other.absences;
+    }
+
+    fn sub(&mut self, other: &Tally) {
+        self.appearances = self.appearances.saturating_sub(other.appearances);
+        self.absences = self.absences.saturating_sub(other.absences);
+    }
+
+    /// Percentage of the steps the vote is in the bitset of
+    fn score(&self) -> u8 {
+        if self.appearances == 0 {
+            return 100;
+        }
+        let present = self.appearances - self.absences;
+        (present * 100 / self.appearances) as u8
+    }
+
+    fn is_withholding(&self) -> bool {
+        self.appearances >= MIN_APPEARANCES && self.score() < WITHHOLDING_SCORE
+    }
+}
+
+/// Participation of a provisioner in the committees of the last blocks
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Participation {
+    /// Public key of the provisioner, base58 encoded
+    pub provisioner: String,
+    /// Number of steps the provisioner was in the committee of
+    pub appearances: u64,
+    /// Number of those steps its vote is missing from
+    pub absences: u64,
+    /// Percentage of those steps its vote is in
+    pub score: u8,
+}
+
+impl Participation {
+    fn new(provisioner: &PublicKey, tally: &Tally) -> Self {
+        Self {
+            provisioner: provisioner.to_base58(),
+            appearances: tally.appearances,
+            absences: tally.absences,
+            score: tally.score(),
+        }
+    }
+}
+
+/// Report of a provisioner withholding its votes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WithholdingEvidence {
+    /// Height of the block the withholding was detected at
+    pub height: u64,
+    #[serde(flatten)]
+    pub participation: Participation,
+}
+
+impl Serializable for WithholdingEvidence {
+    fn write<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        let participation = &self.participation;
+        w.write_all(&self.height.to_le_bytes())?;
+        Self::write_var_le_bytes32(w, participation.provisioner.as_bytes())?;
+        w.write_all(&participation.appearances.to_le_bytes())?;
+        w.write_all(&participation.absences.to_le_bytes())?;
+        w.write_all(&[participation.score])
+    }
+
+    fn read<R: Read>(r: &mut R) -> io::Result<Self>
+    where
+        Self: Sized,
+    {
+        let height = Self::read_u64_le(r)?;
+        let provisioner = String::from_utf8(Self::read_var_le_bytes32(r)?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let appearances = Self::read_u64_le(r)?;
+        let absences = Self::read_u64_le(r)?;
+        let score = Self::read_u8(r)?;
+
+        Ok(Self {
+            height,
+            participation: Participation {
+                provisioner,
+                appearances,
+                absences,
+                score,
+            },
+        })
+    }
+}
+
+#[derive(Default)]
+struct ParticipationTracker {
+    /// Presence of the committee members of each block, by height
+    blocks: VecDeque<(u64, Vec<(PublicKey, Tally)>)>,
+    totals: BTreeMap<PublicKey, Tally>,
+    /// Provisioners currently reported as withholding their votes
+    withholding: BTreeSet<PublicKey>,
+    evidence: VecDeque<WithholdingEvidence>,
+    /// Whether reports were added since the last persist
+    dirty: bool,
+}
+
+impl ParticipationTracker {
+    /// Records the presence of the committee members of the block at
+    /// `height`, returning the provisioners newly detected as withholding
+    /// their votes
+    fn record(
+        &mut self,
+        height: u64,
+        presence: Vec<(PublicKey, Tally)>,
+    ) -> Vec<WithholdingEvidence> {
+        // Blocks at or above `height` have been reverted
+        while self.blocks.back().is_some_and(|(h, _)| *h >= height) {
+            if let Some((_, reverted)) = self.blocks.pop_back() {
+                self.remove(&reverted);
+            }
+        }
+        if self.blocks.len() >= BLOCK_WINDOW {
+            if let Some((_, oldest)) = self.blocks.pop_front() {
+                self.remove(&oldest);
+            }
+        }
+
+        let mut detected = vec![];
+        for (pk, tally) in &presence {
+            let total = self.totals.entry(pk.clone()).or_default();
+            total.add(tally);
+
+            if !total.is_withholding() {
+                self.withholding.remove(pk);
+            } else if self.withholding.insert(pk.clone()) {
+                let participation = Participation::new(pk, total);
+                detected.push(WithholdingEvidence {
+                    height,
+                    participation,
+                });
+            }
+        }
+
+        for evidence in &detected {
+            self.add_evidence(evidence.clone());
+        }
+
+        self.blocks.push_back((height, presence));
+        detected
+    }
+
+    fn add_evidence(&mut self, evidence: WithholdingEvidence) {
+        if self.evidence.len() >= EVIDENCE_RETENTION {
+            self.evidence.pop_front();
+        }
+        self.evidence.push_back(evidence);
+        self.dirty = true;
+    }
+
+    fn write_evidence<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        w.write_all(&(self.evidence.len() as u32).to_le_bytes())?;
+        for evidence in &self.evidence {
+            evidence.write(w)?;
+        }
+        Ok(())
+    }
+
+    fn read_evidence<R: Read>(
+        r: &mut R,
+    ) -> io::Result<Vec<WithholdingEvidence>> {
+        let len = WithholdingEvidence::read_u32_le(r)?;
+        (0..len).map(|_| WithholdingEvidence::read(r)).collect()
+    }
+
+    fn remove(&mut self, presence: &[(PublicKey, Tally)]) {
+        for (pk, tally) in presence {
+            if let Some(total) = self.totals.get_mut(pk) {
+                total.sub(tally);
+                if total.appearances == 0 {
+                    self.totals.remove(pk);
+                    self.withholding.remove(pk);
+                }
+            }
+        }
+    }
+}
+
+/// Returns the steps of the attestation of `header` each committee member
+/// was in the committee of, and absent from the bitset of.
+///
+/// `provisioners` and `seed` are the ones the attestation is verified with.
+pub(crate) fn attestation_presence(
+    provisioners: &Provisioners,
+    header: &Header,
+    seed: Seed,
+) -> Vec<(PublicKey, Tally)> {
+    let mut presence: BTreeMap<PublicKey, Tally> = BTreeMap::new();
+    let att = &header.att;
+    let steps = [
+        (StepName::Validation, &att.validation),
+        (StepName::Ratification, &att.ratification),
+    ];
+
+    for (step, step_votes) in steps {
+        let committee = verifiers::step_committee(
+            provisioners,
+            header.height,
+            header.iteration,
+            seed,
+            step,
+        );
+        let voters = committee.intersect(step_votes.bitset);
+        for pk in committee.iter() {
+            let tally = presence.entry(pk.clone()).or_default();
+            tally.appearances += 1;
+            tally.absences += u64::from(!voters.contains_key(pk));
+        }
+    }
+
+    presence.into_iter().collect()
+}
+
+/// Records the presence of the committee members of an accepted block
+pub(crate) fn record(height: u64, presence: Vec<(PublicKey, Tally)>) {
+    let absences: u64 = presence.iter().map(|(_, t)| t.absences).sum();
+    counter!("dusk_committee_absences").increment(absences);
+
+    let mut tracker = PARTICIPATION.lock().expect("lock not to be poisoned");
+    for evidence in tracker.record(height, presence) {
+        let participation = &evidence.participation;
+        warn!(
+            event = "vote withholding",
+            height,
+            provisioner = participation.provisioner,
+            appearances = participation.appearances,
+            absences = participation.absences,
+            score = participation.score
+        );
+        counter!("dusk_vote_withholding_detected").increment(1);
+    }
+    gauge!("dusk_withholding_provisioners")
+        .set(tracker.withholding.len() as f64);
+}
+
+/// Returns the participation of `provisioner` in the committees of the last
+/// [`BLOCK_WINDOW`] blocks, if it was in any
+pub fn participation(provisioner: &PublicKey) -> Option<Participation> {
+    let tracker = PARTICIPATION.lock().expect("lock not to be poisoned");
+    let tally = tracker.totals.get(provisioner)?;
+    Some(Participation::new(provisioner, tally))
+}
+
+/// Returns the participation of all the provisioners in the committees of
+/// the last [`BLOCK_WINDOW`] blocks, lowest score first
+pub fn participations() -> Vec<Participation> {
+    let tracker = PARTICIPATION.lock().expect("lock not to be poisoned");
+    let mut participations: Vec<_> = tracker
+        .totals
+        .iter()
+        .map(|(pk, tally)| Participation::new(pk, tally))
+        .collect();
+    participations.sort_by_key(|p| p.score);
+    participations
+}
+
+/// Returns the last reports of provisioners withholding their votes, oldest
+/// first
+pub fn withholding_evidence() -> Vec<WithholdingEvidence> {
+    let tracker = PARTICIPATION.lock().expect("lock not to be poisoned");
+    tracker.evidence.iter().cloned().collect()
+}
+
+/// Loads the reports stored in the Metadata CF, placing them before the ones
+/// made since the node started
+pub fn load<M: Metadata>(db: &M) -> anyhow::Result<()> {
+    let Some(bytes) = db.op_read(MD_WITHHOLDING_EVIDENCE)? else {
+        return Ok(());
+    };
+    let stored = ParticipationTracker::read_evidence(&mut &bytes[..])?;
+
+    let mut tracker = PARTICIPATION.lock().expect("lock not to be poisoned");
+    let recent: Vec<_> = tracker.evidence.drain(..).collect();
+    let dirty = !recent.is_empty();
+    for evidence in stored.into_iter().chain(recent) {
+        tracker.add_evidence(evidence);
+    }
+    tracker.dirty = dirty;
+    Ok(())
+}
+
+/// Stores the reports in the Metadata CF, if any was made since the last call
+pub fn persist<M: Metadata>(db: &mut M) -> anyhow::Result<()> {
+    let bytes = {
+        let mut tracker =
+            PARTICIPATION.lock().expect("lock not to be poisoned");
+        if !tracker.dirty {
+            return Ok(());
+        }
+        tracker.dirty = false;
+
+        let mut bytes = vec![];
+        tracker.write_evidence(&mut bytes)?;
+        bytes
+    };
+
+    db.op_write(MD_WITHHOLDING_EVIDENCE, bytes)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tally(appearances: u64, absences: u64) -> Tally {
+        Tally {
+            appearances,
+            absences,
+        }
+    }
+
+    #[test]
+    fn test_withholding() {
+        let mut tracker = ParticipationTracker::default();
+        let honest = PublicKey::from_sk_seed_u64(1);
+        let withholding = PublicKey::from_sk_seed_u64(2);
+
+        let block = |absences| {
+            vec![
+                (honest.clone(), tally(2, 0)),
+                (withholding.clone(), tally(2, absences)),
+            ]
+        };
+
+        // Nothing is reported before the minimum number of appearances
+        for height in 1..MIN_APPEARANCES / 2 {
+            assert!(tracker.record(height, block(2)).is_empty());
+        }
+        let detected = tracker.record(MIN_APPEARANCES / 2, block(2));
+        assert_eq!(detected.len(), 1);
+        let reported = &detected[0].participation;
+        assert_eq!(reported.provisioner, withholding.to_base58());
+        assert_eq!(reported.score, 0);
+
+        // A provisioner is reported once while withholding
+        let height = MIN_APPEARANCES / 2 + 1;
+        assert!(tracker.record(height, block(2)).is_empty());
+        assert_eq!(tracker.evidence.len(), 1);
+        assert_eq!(tracker.totals[&honest].score(), 100);
+
+        // A block replacing a reverted one replaces its presence
+        tracker.record(height, block(0));
+        assert_eq!(tracker.blocks.len() as u64, height);
+        assert_eq!(tracker.totals[&withholding].absences, MIN_APPEARANCES);
+
+        // Participating again clears the report
+        for height in height + 1..height + 2 * MIN_APPEARANCES {
+            tracker.record(height, block(0));
+        }
+        assert!(tracker.withholding.is_empty());
+        assert!(tracker.totals[&withholding].score() >= WITHHOLDING_SCORE);
+    }
+
+    #[test]
+    fn test_evidence_encoding() {
+        let mut tracker = ParticipationTracker::default();
+        let provisioner = PublicKey::from_sk_seed_u64(1);
+        for height in 0..EVIDENCE_RETENTION as u64 + 1 {
+            tracker.add_evidence(WithholdingEvidence {
+                height,
+                participation: Participation::new(
+                    &provisioner,
+                    &tally(height, height / 2),
+                ),
+            });
+        }
+        assert!(tracker.dirty);
+        assert_eq!(tracker.evidence.len(), EVIDENCE_RETENTION);
+        assert_eq!(tracker.evidence[0].height, 1);
+
+        let mut bytes = vec![];
+        tracker.write_evidence(&mut bytes).unwrap();
+        let decoded =
+            ParticipationTracker::read_evidence(&mut &bytes[..]).unwrap();
+        assert!(decoded.iter().eq(tracker.evidence.iter()));
+    }
+}
@@ -0,0 +1,137 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Cache of the provisioners of the recent state roots.
+//!
+//! The provisioners being a function of the state root, the ones cached for
+//! a root stay valid across reverts. The acceptor caches the provisioners
+//! resulting from the selective update of every accepted block, so that
+//! moving the tip back to a recent block doesn't fetch the whole provisioner
+//! set from the VM.
+//!
+//! The provisioners of the parent of a block are derived from its own by
+//! applying the changes of the block, see
+//! [`ContextProvisioners::apply_changes`], and are cached too.
+//!
+//! A selective update diverging from the VM is never cached: the acceptor
+//! recovers from it with a full fetch, whose result is cached instead.
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use dusk_consensus::user::provisioners::{ContextProvisioners, Provisioners};
+use metrics::counter;
+
+use crate::vm::VMExecution;
+
+/// Number of state roots whose provisioners are cached
+const CAPACITY: usize = 16;
+
+#[derive(Default)]
+pub(crate) struct ProvisionersCache {
+    entries: Mutex<VecDeque<([u8; 32], Provisioners)>>,
+}
+
+impl ProvisionersCache {
+    /// Caches `provisioners` as the ones at `state_root`, evicting the
+    /// oldest entry if full
+    pub fn insert(&self, state_root: [u8; 32], provisioners: Provisioners) {
+        let mut entries = self.entries.lock().expect("cache lock");
+        entries.retain(|(root, _)| *root != state_root);
+        if entries.len() == CAPACITY {
+            entries.pop_front();
+        }
+        entries.push_back((state_root, provisioners));
+    }
+
+    fn get(&self, state_root: &[u8; 32]) -> Option<Provisioners> {
+        let entries = self.entries.lock().expect("cache lock");
+        entries
+            .iter()
+            .find(|(root, _)| root == state_root)
+            .map(|(_, provisioners)| provisioners.clone())
+    }
+
+    /// Returns the provisioners at `state_root`, fetching them from the VM
+    /// if not cached
+    pub fn provisioners<VM: VMExecution>(
+        &self,
+        vm: &VM,
+        state_root: [u8; 32],
+    ) -> anyhow::Result<Provisioners> {
+        if let Some(provisioners) = self.get(&state_root) {
+            counter!("dusk_provisioners_cache_hits").increment(1);
+            return Ok(provisioners);
+        }
+        counter!("dusk_provisioners_cache_misses").increment(1);
+
+        let provisioners = vm.get_provisioners(state_root)?;
+        self.insert(state_root, provisioners.clone());
+        Ok(provisioners)
+    }
+
+    /// Returns the provisioners at `state_root`, along with the ones at the
+    /// state root of the parent block, `prev_state_root`, derived from the
+    /// changes of the block.
+    ///
+    /// The derived provisioners are cached if `prev_state_root` is given.
+    pub fn context<VM: VMExecution>(
+        &self,
+        vm: &VM,
+        state_root: [u8; 32],
+        prev_state_root: Option<[u8; 32]>,
+    ) -> anyhow::Result<ContextProvisioners> {
+        let mut provisioners =
+            ContextProvisioners::new(self.provisioners(vm, state_root)?);
+        provisioners.apply_changes(vm.get_changed_provisioners(state_root)?);
+
+        if let Some(prev_state_root) = prev_state_root {
+            self.insert(prev_state_root, provisioners.prev().clone());
+        }
+        Ok(provisioners)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use dusk_consensus::user::stake::Stake;
+    use dusk_core::signatures::bls::{
+        PublicKey as BlsPublicKey, SecretKey as BlsSecretKey,
+    };
+    use node_data::bls::PublicKey;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::*;
+
+    #[test]
+    fn test_provisioners_cache() {
+        let mut rng = StdRng::seed_from_u64(0xbeef);
+        let cache = ProvisionersCache::default();
+
+        let mut provisioners = Provisioners::empty();
+        let pk = BlsPublicKey::from(&BlsSecretKey::random(&mut rng));
+        provisioners
+            .add_member_with_stake(PublicKey::new(pk), Stake::new(1_000, 0));
+
+        cache.insert([0; 32], provisioners.clone());
+        let cached = cache.get(&[0; 32]).expect("cached provisioners");
+        assert_eq!(cached.commitment(), provisioners.commitment());
+
+        // Inserting a root again replaces its provisioners
+        cache.insert([0; 32], Provisioners::empty());
+        let cached = cache.get(&[0; 32]).expect("cached provisioners");
+        assert_eq!(cached.commitment(), Provisioners::empty().commitment());
+
+        // The oldest roots are evicted first
+        for i in 1..=CAPACITY as u8 {
+            cache.insert([i; 32], Provisioners::empty());
+        }
+        assert!(cache.get(&[0; 32]).is_none());
+        assert!(cache.get(&[1; 32]).is_some());
+        assert!(cache.get(&[CAPACITY as u8; 32]).is_some());
+    }
+}
@@ -0,0 +1,301 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Deterministic replay of historical blocks.
+//!
+//! A range of blocks of the local ledger is re-executed on top of the state
+//! preceding the first block, and the outcome of every block is compared with
+//! the one recorded in the ledger: the result of each transaction first, then
+//! the state root and the event bloom. The first divergence is reported.
+//!
+//! The state preceding the first block must be available in the VM, e.g.
+//! because it's not finalized yet or it was restored from a snapshot. The
+//! replayed states are stored in side commits, which are deleted once the
+//! replay is over.
+
+use std::fmt;
+use std::sync::Arc;
+
+use anyhow::{anyhow, bail};
+use dusk_consensus::operations::VerificationOutput;
+use dusk_consensus::user::provisioners::ContextProvisioners;
+use node_data::ledger::{to_str, Block, Header, SpentTransaction};
+use tokio::sync::RwLock;
+use tracing::{info, warn};
+
+use crate::chain::header_validation::Validator;
+use crate::database::Ledger;
+use crate::{database, vm};
+
+/// First difference between a replayed block and the recorded one
+#[derive(Debug)]
+pub struct Divergence {
+    pub height: u64,
+    /// Index and id of the diverging transaction, if any
+    pub tx: Option<(usize, [u8; 32])>,
+    pub reason: String,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "block {}", self.height)?;
+        if let Some((index, id)) = &self.tx {
+            write!(f, ", tx {index} ({})", hex::encode(id))?;
+        }
+        write!(f, ": {}", self.reason)
+    }
+}
+
+/// Replays the blocks from height `from` to `to` (included).
+///
+/// Returns the first divergence found, if any.
+pub async fn replay_blocks<DB: database::DB, VM: vm::VMExecution>(
+    db: Arc<RwLock<DB>>,
+    vm: Arc<RwLock<VM>>,
+    from: u64,
+    to: u64,
+) -> anyhow::Result<Option<Divergence>> {
+    if from == 0 || from > to {
+        bail!("invalid replay range {from}..={to}");
+    }
+
+    let mut side_commits = vec![];
+    let res = replay_range(&db, &vm, from, to, &mut side_commits).await;
+
+    for commit in side_commits {
+        if let Err(err) = vm.read().await.delete_side_commit(commit) {
+            warn!(event = "cannot delete side commit", ?err);
+        }
+    }
+
+    res
+}
+
+async fn replay_range<DB: database::DB, VM: vm::VMExecution>(
+    db: &Arc<RwLock<DB>>,
+    vm: &Arc<RwLock<VM>>,
+    from: u64,
+    to: u64,
+    side_commits: &mut Vec<[u8; 32]>,
+) -> anyhow::Result<Option<Divergence>> {
+    let mut prev_header = block_at(db, from - 1).await?.header().clone();
+
+    info!(
+        event = "replay started",
+        from,
+        to,
+        state_root = to_str(&prev_header.state_hash),
+    );
+
+    for height in from..=to {
+        let blk = block_at(db, height).await?;
+
+        let provisioners =
+            context_provisioners(&*vm.read().await, &prev_header).map_err(
+                |err| {
+                    anyhow!(
+                        "state {} not available: {err}",
+                        to_str(&prev_header.state_hash)
+                    )
+                },
+            )?;
+        let voters = Validator::new(db.clone(), &prev_header, &provisioners)
+            .verify_prev_block_cert(blk.header())
+            .await?;
+
+        let (txs, output, new_commit) = {
+            let vm = vm.read().await;
+            let ctx = vm::BlockContext::new(blk.header(), &*vm);
+            vm.replay(prev_header.state_hash, &blk, &ctx, &voters)?
+        };
+        if new_commit {
+            side_commits.push(output.state_root);
+        }
+
+        let recorded = db.read().await.view(|t| {
+            blk.txs()
+                .iter()
+                .map(|tx| t.ledger_tx(&tx.id()))
+                .collect::<anyhow::Result<Vec<_>>>()
+        })?;
+
+        if let Some(divergence) = compare(&blk, &recorded, &txs, &output) {
+            warn!(event = "replay diverged", %divergence);
+            return Ok(Some(divergence));
+        }
+
+        info!(
+            event = "block replayed",
+            height,
+            txs = txs.len(),
+            state_root = to_str(&output.state_root),
+        );
+        prev_header = blk.header().clone();
+    }
+
+    info!(event = "replay completed", from, to);
+    Ok(None)
+}
+
+async fn block_at<DB: database::DB>(
+    db: &Arc<RwLock<DB>>,
+    height: u64,
+) -> anyhow::Result<Block> {
+    db.read()
+        .await
+        .view(|t| t.block_by_height(height))?
+        .ok_or_else(|| anyhow!("block {height} not found in the ledger"))
+}
+
+/// Returns the provisioners needed to verify the successor of `header`
+fn context_provisioners<VM: vm::VMExecution>(
+    vm: &VM,
+    header: &Header,
+) -> anyhow::Result<ContextProvisioners> {
+    let mut provisioners =
+        ContextProvisioners::new(vm.get_provisioners(header.state_hash)?);
+    if header.height > 0 {
+        let changed = vm.get_changed_provisioners(header.state_hash)?;
+        provisioners.apply_changes(changed);
+    }
+    Ok(provisioners)
+}
+
+/// Compares the outcome of the replay of `blk` with the recorded one
+fn compare(
+    blk: &Block,
+    recorded: &[Option<SpentTransaction>],
+    replayed: &[SpentTransaction],
+    output: &VerificationOutput,
+) -> Option<Divergence> {
+    let header = blk.header();
+    let divergence = |tx, reason| {
+        Some(Divergence {
+            height: header.height,
+            tx,
+            reason,
+        })
+    };
+
+    for (index, tx) in blk.txs().iter().enumerate() {
+        let tx_id = Some((index, tx.id()));
+        let Some(recorded) = &recorded[index] else {
+            return divergence(tx_id, "missing from the ledger".into());
+        };
+        let Some(replayed) = replayed.get(index) else {
+            return divergence(tx_id, "not executed".into());
+        };
+        if replayed.gas_spent != recorded.gas_spent {
+            return divergence(
+                tx_id,
+                format!(
+                    "gas spent {}, recorded {}",
+                    replayed.gas_spent, recorded.gas_spent
+                ),
+            );
+        }
+        if replayed.err != recorded.err {
+            return divergence(
+                tx_id,
+                format!(
+                    "error {:?}, recorded {:?}",
+                    replayed.err, recorded.err
+                ),
+            );
+        }
+    }
+
+    if output.state_root != header.state_hash {
+        return divergence(
+            None,
+            format!(
+                "state root {}, recorded {}",
+                hex::encode(output.state_root),
+                hex::encode(header.state_hash)
+            ),
+        );
+    }
+    if output.event_bloom != header.event_bloom {
+        return divergence(None, "event bloom mismatch".into());
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use dusk_core::abi::ExecutionError;
+    use fake::{Fake, Faker};
+    use node_data::ledger::faker::gen_dummy_tx;
+
+    use super::*;
+
+    fn block() -> Block {
+        let mut header: Header = Faker.fake();
+        header.state_hash = [1; 32];
+        header.event_bloom = [2; 256];
+        let txs = vec![gen_dummy_tx(1), gen_dummy_tx(2)];
+        Block::new(header, txs, vec![]).expect("block to be created")
+    }
+
+    fn spent(blk: &Block) -> Vec<SpentTransaction> {
+        blk.txs()
+            .iter()
+            .map(|tx| SpentTransaction::new(tx.clone(), 1, 10, None))
+            .collect()
+    }
+
+    fn output(blk: &Block) -> VerificationOutput {
+        VerificationOutput {
+            state_root: blk.header().state_hash,
+            event_bloom: blk.header().event_bloom,
+        }
+    }
+
+    #[test]
+    fn compare_replayed_blocks() {
+        let blk = block();
+        let recorded: Vec<_> = spent(&blk).into_iter().map(Some).collect();
+        let replayed = spent(&blk);
+        assert!(compare(&blk, &recorded, &replayed, &output(&blk)).is_none());
+
+        // The first diverging transaction is reported
+        let mut diverging = replayed.clone();
+        diverging[1].gas_spent = 11;
+        let divergence =
+            compare(&blk, &recorded, &diverging, &output(&blk)).unwrap();
+        assert_eq!(divergence.height, blk.header().height);
+        assert_eq!(divergence.tx, Some((1, blk.txs()[1].id())));
+
+        let mut diverging = replayed.clone();
+        diverging[0].err = Some(ExecutionError::OutOfGas);
+        let divergence =
+            compare(&blk, &recorded, &diverging, &output(&blk)).unwrap();
+        assert_eq!(divergence.tx, Some((0, blk.txs()[0].id())));
+
+        let divergence =
+            compare(&blk, &recorded, &replayed[..1], &output(&blk)).unwrap();
+        assert_eq!(divergence.tx, Some((1, blk.txs()[1].id())));
+
+        let mut missing = recorded.clone();
+        missing[0] = None;
+        let divergence =
+            compare(&blk, &missing, &replayed, &output(&blk)).unwrap();
+        assert_eq!(divergence.tx, Some((0, blk.txs()[0].id())));
+
+        // Block-level divergences are reported with no transaction
+        let mut root = output(&blk);
+        root.state_root = [3; 32];
+        let divergence = compare(&blk, &recorded, &replayed, &root).unwrap();
+        assert!(divergence.tx.is_none());
+
+        let mut bloom = output(&blk);
+        bloom.event_bloom = [0; 256];
+        let divergence = compare(&blk, &recorded, &replayed, &bloom).unwrap();
+        assert!(divergence.tx.is_none());
+        assert_eq!(divergence.reason, "event bloom mismatch");
+    }
+}
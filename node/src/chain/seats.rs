@@ -0,0 +1,209 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Committee seats of the local provisioner.
+//!
+//! The consensus reports the seats the local provisioner has in the committee
+//! of every step it runs. The steps it had seats in are kept for the last
+//! [`ROUND_RETENTION`] rounds, and the seats are summed up per epoch for the
+//! last [`EPOCH_RETENTION`] epochs.
+//!
+//! The records start when the node starts, and are not persisted.
+
+use std::collections::BTreeMap;
+use std::sync::{LazyLock, Mutex};
+
+use node_data::ledger::epoch_of;
+use node_data::StepName;
+use serde::Serialize;
+
+/// Number of rounds whose seats are kept
+pub const ROUND_RETENTION: u64 = 1_000;
+
+/// Number of epochs whose seats are kept
+pub const EPOCH_RETENTION: u64 = 16;
+
+static SEATS: LazyLock<Mutex<Seats>> =
+    LazyLock::new(|| Mutex::new(Seats::default()));
+
+/// Seats of the local provisioner in the committee of a step
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StepSeats {
+    pub round: u64,
+    pub iteration: u8,
+    pub step: String,
+    /// Credits assigned to the local provisioner
+    pub seats: usize,
+    /// Credits assigned to the whole committee
+    pub credits: usize,
+}
+
+/// Seats of the local provisioner in the committees of a kind of step
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct StepTally {
+    /// Number of steps run
+    pub steps: u64,
+    /// Number of steps the local provisioner had seats in
+    pub assigned: u64,
+    /// Credits assigned to the local provisioner
+    pub seats: u64,
+    /// Credits assigned to the committees
+    pub credits: u64,
+}
+
+impl StepTally {
+    fn add(&mut self, seats: usize, credits: usize) {
+        self.steps += 1;
+        self.assigned += u64::from(seats > 0);
+        self.seats += seats as u64;
+        self.credits += credits as u64;
+    }
+}
+
+/// Seats of the local provisioner over an epoch
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct EpochSeats {
+    pub epoch: u64,
+    pub proposal: StepTally,
+    pub validation: StepTally,
+    pub ratification: StepTally,
+}
+
+#[derive(Default)]
+struct Seats {
+    /// Steps run per round, whether the local provisioner had seats or not
+    rounds: BTreeMap<u64, Vec<StepSeats>>,
+    epochs: BTreeMap<u64, EpochSeats>,
+}
+
+impl Seats {
+    fn record(
+        &mut self,
+        round: u64,
+        iteration: u8,
+        step: StepName,
+        seats: usize,
+        credits: usize,
+    ) {
+        let step_name = format!("{step:?}");
+        let steps = self.rounds.entry(round).or_default();
+
+        // A step run again after a restart of the consensus is counted once
+        if steps
+            .iter()
+            .any(|s| s.iteration == iteration && s.step == step_name)
+        {
+            return;
+        }
+        steps.push(StepSeats {
+            round,
+            iteration,
+            step: step_name,
+            seats,
+            credits,
+        });
+
+        let epoch = epoch_of(round);
+        let epoch_seats = self.epochs.entry(epoch).or_insert(EpochSeats {
+            epoch,
+            ..Default::default()
+        });
+        let tally = match step {
+            StepName::Proposal => &mut epoch_seats.proposal,
+            StepName::Validation => &mut epoch_seats.validation,
+            StepName::Ratification => &mut epoch_seats.ratification,
+        };
+        tally.add(seats, credits);
+
+        let min_round = round.saturating_sub(ROUND_RETENTION - 1);
+        self.rounds = self.rounds.split_off(&min_round);
+        let min_epoch = epoch.saturating_sub(EPOCH_RETENTION - 1);
+        self.epochs = self.epochs.split_off(&min_epoch);
+    }
+}
+
+/// Records the seats of the local provisioner in the committee of a step
+pub(super) fn record(
+    round: u64,
+    iteration: u8,
+    step: StepName,
+    seats: usize,
+    credits: usize,
+) {
+    let mut stats = SEATS.lock().expect("lock not to be poisoned");
+    stats.record(round, iteration, step, seats, credits);
+}
+
+/// Returns the steps of `round` the local provisioner had seats in
+pub fn round_seats(round: u64) -> Vec<StepSeats> {
+    let stats = SEATS.lock().expect("lock not to be poisoned");
+    stats
+        .rounds
+        .get(&round)
+        .map(|steps| steps.iter().filter(|s| s.seats > 0).cloned().collect())
+        .unwrap_or_default()
+}
+
+/// Returns the seats of the local provisioner over `epoch`, if any step of
+/// the epoch was run
+pub fn epoch_seats(epoch: u64) -> Option<EpochSeats> {
+    let stats = SEATS.lock().expect("lock not to be poisoned");
+    stats.epochs.get(&epoch).cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use dusk_core::stake::EPOCH;
+
+    use super::*;
+
+    #[test]
+    fn test_seats() {
+        let mut seats = Seats::default();
+        let round = 3 * EPOCH + 1;
+
+        seats.record(round, 0, StepName::Proposal, 0, 1);
+        seats.record(round, 0, StepName::Validation, 3, 64);
+        seats.record(round, 0, StepName::Ratification, 0, 64);
+        // Steps run again are ignored
+        seats.record(round, 0, StepName::Validation, 3, 64);
+        seats.record(round, 1, StepName::Proposal, 1, 1);
+
+        let assigned: Vec<_> = seats.rounds[&round]
+            .iter()
+            .filter(|s| s.seats > 0)
+            .map(|s| (s.iteration, s.step.as_str(), s.seats))
+            .collect();
+        assert_eq!(assigned, vec![(0, "Validation", 3), (1, "Proposal", 1)]);
+
+        let epoch = &seats.epochs[&3];
+        assert_eq!(
+            epoch.proposal,
+            StepTally {
+                steps: 2,
+                assigned: 1,
+                seats: 1,
+                credits: 2
+            }
+        );
+        assert_eq!(
+            epoch.validation,
+            StepTally {
+                steps: 1,
+                assigned: 1,
+                seats: 3,
+                credits: 64
+            }
+        );
+        assert_eq!(epoch.ratification.assigned, 0);
+
+        // Old rounds and epochs are pruned
+        let later = round + EPOCH_RETENTION * EPOCH;
+        seats.record(later, 0, StepName::Proposal, 0, 1);
+        assert!(!seats.rounds.contains_key(&round));
+        assert!(!seats.epochs.contains_key(&3));
+    }
+}
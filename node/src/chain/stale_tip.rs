@@ -0,0 +1,461 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Detection of a stale tip, and its recovery with the help of the peers.
+//!
+//! The tip is stale when it hasn't advanced for `threshold` times the minimum
+//! block time, while the peers prove to be ahead of it or on another branch:
+//!
+//! - a quorum for the round following the tip, whose attestation is verified
+//!   against the current provisioners, reports a higher height;
+//! - a block at or below the tip, whose header is verified against its parent
+//!   in the local chain, conflicts with the local chain.
+//!
+//! Only the payloads received once the tip is idle for `threshold` are
+//! verified, and the evidence they provide expires after `threshold` too, so
+//! that neither unverified nor outdated payloads drive the recovery.
+//!
+//! As long as the tip stays stale, the recovery escalates one step at a time,
+//! at most once per `cooldown`:
+//!
+//! 1. [`RecoveryStep::Sync`] requests the blocks following the tip from random
+//!    peers, and runs an anti-entropy round.
+//! 2. [`RecoveryStep::RestartConsensus`] restarts the consensus task.
+//! 3. [`RecoveryStep::RevertToFinalized`] reverts to the last finalized block,
+//!    only if a peer sent a block conflicting with the local chain. Otherwise,
+//!    the recovery starts over from the first step.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use dusk_consensus::config::MINIMUM_BLOCK_TIME;
+use metrics::counter;
+use node_data::ledger::{to_str, Header};
+use node_data::message::payload::{
+    GetBlocks, Quorum, RatificationResult, Vote,
+};
+use tokio::sync::RwLock;
+use tokio::time::Instant;
+use tracing::{info, warn};
+
+use super::acceptor::{Acceptor, RevertTarget};
+use super::anti_entropy::AntiEntropy;
+use super::conf::StaleTipParams;
+use super::header_validation::verify_att;
+use crate::database::Ledger;
+use crate::{database, vm, Network};
+
+/// Number of random alive peers the blocks following the tip are requested to
+const SYNC_FANOUT: usize = 8;
+
+/// Step of the recovery of a stale tip
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum RecoveryStep {
+    Sync,
+    RestartConsensus,
+    RevertToFinalized,
+}
+
+impl RecoveryStep {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Sync => "sync",
+            Self::RestartConsensus => "restart_consensus",
+            Self::RevertToFinalized => "revert_to_finalized",
+        }
+    }
+}
+
+/// Tracks the progress of the tip against the heights reported by the peers
+#[derive(Debug)]
+struct Detector {
+    threshold: Duration,
+    cooldown: Duration,
+
+    /// Height of the tip, and instant it was reached at
+    tip: (u64, Instant),
+    /// Highest verified height reported by the peers, and instant it was
+    /// reported at
+    remote_height: Option<(u64, Instant)>,
+    /// Instant a peer last sent a verified block conflicting with the local
+    /// chain, since the tip last advanced
+    conflict: Option<Instant>,
+
+    /// Number of steps taken since the tip last advanced
+    steps: usize,
+    /// Instant of the last step
+    last_step: Option<Instant>,
+}
+
+impl Detector {
+    fn new(threshold: Duration, cooldown: Duration, tip: u64) -> Self {
+        Self {
+            threshold,
+            cooldown,
+            tip: (tip, Instant::now()),
+            remote_height: None,
+            conflict: None,
+            steps: 0,
+            last_step: None,
+        }
+    }
+
+    fn on_tip(&mut self, height: u64, now: Instant) {
+        if height != self.tip.0 {
+            self.tip = (height, now);
+            self.conflict = None;
+            self.steps = 0;
+        }
+    }
+
+    /// Whether the evidence reported at `at` is still valid at `now`
+    fn is_fresh(&self, at: Instant, now: Instant) -> bool {
+        now.duration_since(at) < self.threshold
+    }
+
+    /// Whether the tip hasn't advanced for the threshold
+    fn is_idle(&self, now: Instant) -> bool {
+        now.duration_since(self.tip.1) >= self.threshold
+    }
+
+    /// Returns the verified height reported by the peers, if not expired
+    fn remote_height(&self, now: Instant) -> Option<u64> {
+        self.remote_height
+            .filter(|(_, at)| self.is_fresh(*at, now))
+            .map(|(height, _)| height)
+    }
+
+    fn has_conflict(&self, now: Instant) -> bool {
+        self.conflict.is_some_and(|at| self.is_fresh(at, now))
+    }
+
+    /// Whether a payload reporting a higher height is worth verifying
+    fn wants_remote_height(&self, now: Instant) -> bool {
+        self.is_idle(now)
+            && self.remote_height(now).map_or(true, |h| h <= self.tip.0)
+    }
+
+    /// Whether a payload conflicting with the local chain is worth verifying
+    fn wants_conflict(&self, now: Instant) -> bool {
+        self.is_idle(now) && !self.has_conflict(now)
+    }
+
+    fn on_remote_height(&mut self, height: u64, now: Instant) {
+        if self.remote_height(now).map_or(true, |h| h <= height) {
+            self.remote_height = Some((height, now));
+        }
+    }
+
+    fn on_conflict(&mut self, now: Instant) {
+        self.conflict = Some(now);
+    }
+
+    fn is_stale(&self, now: Instant) -> bool {
+        self.is_idle(now)
+            && (self.remote_height(now).is_some_and(|h| h > self.tip.0)
+                || self.has_conflict(now))
+    }
+
+    /// Returns the step to take at `now`, if any
+    fn next_step(&mut self, now: Instant) -> Option<RecoveryStep> {
+        if !self.is_stale(now) {
+            return None;
+        }
+        if self
+            .last_step
+            .is_some_and(|last| now.duration_since(last) < self.cooldown)
+        {
+            return None;
+        }
+
+        let step = match self.steps % 2 {
+            0 if self.steps > 0 && self.has_conflict(now) => {
+                RecoveryStep::RevertToFinalized
+            }
+            0 => RecoveryStep::Sync,
+            _ => RecoveryStep::RestartConsensus,
+        };
+
+        self.last_step = Some(now);
+        self.steps += 1;
+        if step == RecoveryStep::RevertToFinalized {
+            self.conflict = None;
+            self.steps = 0;
+        }
+        Some(step)
+    }
+}
+
+pub(crate) struct StaleTipRecovery<
+    N: Network,
+    DB: database::DB,
+    VM: vm::VMExecution,
+> {
+    acc: Arc<RwLock<Acceptor<N, DB, VM>>>,
+    network: Arc<RwLock<N>>,
+    detector: Option<Detector>,
+}
+
+impl<N: Network, DB: database::DB, VM: vm::VMExecution>
+    StaleTipRecovery<N, DB, VM>
+{
+    pub(crate) async fn new(
+        acc: Arc<RwLock<Acceptor<N, DB, VM>>>,
+        network: Arc<RwLock<N>>,
+        conf: StaleTipParams,
+    ) -> Self {
+        let tip = acc.read().await.get_curr_height().await;
+        let detector = conf.threshold.map(|threshold| {
+            let block_time = Duration::from_secs(*MINIMUM_BLOCK_TIME);
+            Detector::new(block_time * threshold, conf.cooldown, tip)
+        });
+        Self {
+            acc,
+            network,
+            detector,
+        }
+    }
+
+    /// Records the height of a quorum received from a peer, once its
+    /// attestation is verified.
+    ///
+    /// Only the successful quorums for the round following the tip can be
+    /// verified, against the current provisioners.
+    pub(crate) async fn on_remote_quorum(&mut self, quorum: &Quorum) {
+        let Some(detector) = &mut self.detector else {
+            return;
+        };
+        if !detector.wants_remote_height(Instant::now()) {
+            return;
+        }
+        if !matches!(
+            quorum.att.result,
+            RatificationResult::Success(Vote::Valid(_))
+        ) {
+            return;
+        }
+
+        let acc = self.acc.read().await;
+        let tip = acc.tip_header().await;
+        if quorum.header.round != tip.height + 1
+            || quorum.header.prev_block_hash != tip.hash
+        {
+            return;
+        }
+        let provisioners = acc.provisioners_list.read().await.current().clone();
+        drop(acc);
+
+        let res = verify_att(
+            &quorum.att,
+            quorum.header,
+            tip.seed,
+            &provisioners,
+            None,
+        )
+        .await;
+        match res {
+            Ok(_) => {
+                detector.on_remote_height(quorum.header.round, Instant::now())
+            }
+            Err(err) => warn!(event = "unverified remote quorum", ?err),
+        }
+    }
+
+    /// Checks the header of a block received from a peer against the local
+    /// chain, recording a conflict once the header is verified against its
+    /// parent
+    pub(crate) async fn on_remote_block(&mut self, header: &Header) {
+        let Some(detector) = &mut self.detector else {
+            return;
+        };
+        if header.height > detector.tip.0
+            || !detector.wants_conflict(Instant::now())
+        {
+            return;
+        }
+
+        let acc = self.acc.read().await;
+        let local = acc.db.read().await.view(|t| {
+            match t.block_hash_by_height(header.height)? {
+                Some(hash) => t.block_header(&hash),
+                None => Ok(None),
+            }
+        });
+        let Ok(Some(local)) = local else {
+            return;
+        };
+        if local.hash == header.hash {
+            return;
+        }
+
+        if let Err(err) = acc.verify_header_against_local(&local, header).await
+        {
+            warn!(event = "unverified conflicting block", ?err);
+            return;
+        }
+        info!(
+            event = "conflicting block",
+            height = header.height,
+            hash = to_str(&header.hash),
+            local_hash = to_str(&local.hash),
+        );
+        detector.on_conflict(Instant::now());
+    }
+
+    /// Handles heartbeat event.
+    ///
+    /// Takes the next recovery step if the tip is stale.
+    pub(crate) async fn on_heartbeat(
+        &mut self,
+        anti_entropy: &AntiEntropy<N, DB, VM>,
+    ) {
+        let Some(detector) = &mut self.detector else {
+            return;
+        };
+
+        let now = Instant::now();
+        let tip = self.acc.read().await.tip_header().await;
+        detector.on_tip(tip.height, now);
+
+        let Some(step) = detector.next_step(now) else {
+            return;
+        };
+        warn!(
+            event = "stale tip",
+            step = step.name(),
+            height = tip.height,
+            remote_height = ?detector.remote_height(now),
+            stale_for = ?now.duration_since(detector.tip.1),
+        );
+        counter!("dusk_stale_tip_recovery_steps", "step" => step.name())
+            .increment(1);
+
+        if let Err(err) = self.run_step(step, &tip, anti_entropy).await {
+            warn!(
+                event = "stale tip recovery failed",
+                step = step.name(),
+                ?err
+            );
+        }
+    }
+
+    async fn run_step(
+        &self,
+        step: RecoveryStep,
+        tip: &Header,
+        anti_entropy: &AntiEntropy<N, DB, VM>,
+    ) -> anyhow::Result<()> {
+        match step {
+            RecoveryStep::Sync => {
+                self.network
+                    .read()
+                    .await
+                    .send_to_alive_peers(
+                        GetBlocks::new(tip.hash).into(),
+                        SYNC_FANOUT,
+                    )
+                    .await?;
+                anti_entropy.run_round().await
+            }
+            RecoveryStep::RestartConsensus => {
+                self.acc.write().await.restart_consensus().await;
+                Ok(())
+            }
+            RecoveryStep::RevertToFinalized => {
+                let mut acc = self.acc.write().await;
+                acc.try_revert(RevertTarget::LastFinalizedState).await?;
+                acc.restart_consensus().await;
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const THRESHOLD: Duration = Duration::from_secs(100);
+    const COOLDOWN: Duration = Duration::from_secs(60);
+
+    #[test]
+    fn test_recovery_steps() {
+        let mut detector = Detector::new(THRESHOLD, COOLDOWN, 10);
+        let start = detector.tip.1;
+        let at = |secs| start + Duration::from_secs(secs);
+
+        // A tip not advancing is not stale while no peer is ahead of it
+        assert_eq!(detector.next_step(at(200)), None);
+        detector.on_remote_height(10, at(200));
+        assert_eq!(detector.next_step(at(200)), None);
+
+        detector.on_remote_height(12, at(200));
+        assert_eq!(detector.next_step(at(200)), Some(RecoveryStep::Sync));
+
+        // One step per cooldown
+        assert_eq!(detector.next_step(at(259)), None);
+        assert_eq!(
+            detector.next_step(at(260)),
+            Some(RecoveryStep::RestartConsensus)
+        );
+
+        // The reported height expires
+        assert_eq!(detector.next_step(at(320)), None);
+
+        // No revert without conflicting blocks
+        detector.on_remote_height(12, at(320));
+        assert_eq!(detector.next_step(at(320)), Some(RecoveryStep::Sync));
+        assert_eq!(
+            detector.next_step(at(380)),
+            Some(RecoveryStep::RestartConsensus)
+        );
+        detector.on_remote_height(12, at(380));
+        detector.on_conflict(at(380));
+        assert_eq!(
+            detector.next_step(at(440)),
+            Some(RecoveryStep::RevertToFinalized)
+        );
+        assert_eq!(detector.next_step(at(500)), None);
+
+        // Advancing the tip resets the recovery
+        detector.on_tip(11, at(510));
+        detector.on_remote_height(12, at(510));
+        assert_eq!(detector.next_step(at(600)), None);
+        detector.on_remote_height(12, at(610));
+        assert_eq!(detector.next_step(at(610)), Some(RecoveryStep::Sync));
+    }
+
+    #[test]
+    fn test_verified_evidence() {
+        let mut detector = Detector::new(THRESHOLD, COOLDOWN, 10);
+        let start = detector.tip.1;
+        let at = |secs| start + Duration::from_secs(secs);
+
+        // Nothing is verified while the tip advances
+        assert!(!detector.wants_remote_height(at(99)));
+        assert!(!detector.wants_conflict(at(99)));
+        assert!(detector.wants_remote_height(at(100)));
+        assert!(detector.wants_conflict(at(100)));
+
+        // Nothing is verified again while the evidence holds
+        detector.on_remote_height(11, at(100));
+        detector.on_conflict(at(100));
+        assert!(!detector.wants_remote_height(at(199)));
+        assert!(!detector.wants_conflict(at(199)));
+        assert!(detector.wants_remote_height(at(200)));
+        assert!(detector.wants_conflict(at(200)));
+
+        // A conflict alone makes the tip stale, until it expires
+        detector.remote_height = None;
+        detector.on_conflict(at(150));
+        assert_eq!(detector.next_step(at(150)), Some(RecoveryStep::Sync));
+        assert_eq!(
+            detector.next_step(at(210)),
+            Some(RecoveryStep::RestartConsensus)
+        );
+        assert_eq!(detector.next_step(at(270)), None);
+    }
+}
@@ -0,0 +1,542 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Typed configuration of the node services.
+//!
+//! Each configuration section implements [`Section`], validating its own
+//! fields and documenting them. The dependencies between the sections are
+//! validated by [`validate`], and [`schema`] describes every field along
+//! with its default value, for tooling.
+//!
+//! The configuration is versioned with [`CONFIG_VERSION`]. A configuration
+//! of a newer version than the supported one is rejected, since its fields
+//! may not mean what the node expects.
+
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use thiserror::Error;
+
+use crate::chain::conf::Params as ChainParams;
+use crate::databroker::conf::Params as DataBrokerParams;
+use crate::mempool::conf::Params as MempoolParams;
+
+/// Version of the configuration supported
+pub const CONFIG_VERSION: u32 = 1;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ConfigError {
+    #[error("{field} is {value}: {reason}")]
+    Invalid {
+        field: String,
+        value: String,
+        reason: String,
+    },
+    #[error("version {0} is newer than the supported {CONFIG_VERSION}")]
+    UnsupportedVersion(u32),
+}
+
+impl ConfigError {
+    fn invalid(
+        field: &str,
+        value: impl std::fmt::Debug,
+        reason: impl Into<String>,
+    ) -> Self {
+        Self::Invalid {
+            field: field.to_string(),
+            value: format!("{value:?}"),
+            reason: reason.into(),
+        }
+    }
+}
+
+/// Documentation of a configuration field, nested ones being named with a
+/// dot, e.g. `anti_entropy.depth`
+pub struct FieldDoc {
+    pub name: &'static str,
+    pub description: &'static str,
+}
+
+/// A section of the configuration
+pub trait Section: Serialize + Default {
+    /// Name of the section in the configuration file
+    const NAME: &'static str;
+
+    /// Documentation of the fields of the section
+    const FIELDS: &'static [FieldDoc];
+
+    /// Appends the errors of the fields of the section to `errors`
+    fn validate(&self, errors: &mut Vec<ConfigError>);
+}
+
+/// Description of a configuration field
+#[derive(Debug, Serialize)]
+pub struct FieldSchema {
+    pub name: String,
+    /// JSON type of the default value, `null` for the optional fields
+    /// unset by default
+    pub kind: &'static str,
+    pub default: Value,
+    pub description: &'static str,
+}
+
+/// Returns the schema of the fields of the section `S`
+pub fn section_schema<S: Section>() -> Vec<FieldSchema> {
+    let mut fields = vec![];
+    let defaults = serde_json::to_value(S::default()).unwrap_or_default();
+    flatten(None, defaults, &mut fields);
+
+    for field in fields.iter_mut() {
+        if let Some(doc) = S::FIELDS.iter().find(|f| f.name == field.name) {
+            field.description = doc.description;
+        }
+    }
+    fields
+}
+
+fn flatten(prefix: Option<&str>, value: Value, fields: &mut Vec<FieldSchema>) {
+    let Value::Object(map) = value else {
+        return;
+    };
+    for (key, value) in map {
+        let name = match prefix {
+            Some(prefix) => format!("{prefix}.{key}"),
+            None => key,
+        };
+        if value.is_object() {
+            flatten(Some(&name), value, fields);
+            continue;
+        }
+        let kind = match &value {
+            Value::Object(_) => "object",
+            Value::Null => "null",
+            Value::Bool(_) => "boolean",
+            Value::Number(_) => "number",
+            Value::String(_) => "string",
+            Value::Array(_) => "array",
+        };
+        fields.push(FieldSchema {
+            name,
+            kind,
+            default: value,
+            description: "",
+        });
+    }
+}
+
+/// Returns the schema of the configuration of the node services
+pub fn schema() -> Value {
+    let mut sections = Map::new();
+    let mut add = |name: &str, fields: Vec<FieldSchema>| {
+        let fields = serde_json::to_value(fields).unwrap_or_default();
+        sections.insert(name.to_string(), fields);
+    };
+    add(ChainParams::NAME, section_schema::<ChainParams>());
+    add(MempoolParams::NAME, section_schema::<MempoolParams>());
+    add(DataBrokerParams::NAME, section_schema::<DataBrokerParams>());
+
+    json!({
+        "version": CONFIG_VERSION,
+        "sections": sections,
+    })
+}
+
+/// Returns the effective configuration of the node services, to be printed
+/// at startup
+pub fn effective(
+    chain: &ChainParams,
+    mempool: &MempoolParams,
+    databroker: &DataBrokerParams,
+) -> Value {
+    json!({
+        "version": CONFIG_VERSION,
+        "chain": chain,
+        "mempool": mempool,
+        "databroker": databroker,
+    })
+}
+
+/// Checks that the configuration `version` is supported, the current one
+/// being assumed if not set
+pub fn check_version(version: Option<u32>) -> Result<u32, ConfigError> {
+    match version {
+        Some(version) if version > CONFIG_VERSION => {
+            Err(ConfigError::UnsupportedVersion(version))
+        }
+        Some(version) => Ok(version),
+        None => Ok(CONFIG_VERSION),
+    }
+}
+
+/// Validates the configuration of the node services, along with the
+/// dependencies between them
+pub fn validate(
+    chain: &ChainParams,
+    mempool: &MempoolParams,
+    databroker: &DataBrokerParams,
+) -> Result<(), Vec<ConfigError>> {
+    let mut errors = vec![];
+    chain.validate(&mut errors);
+    mempool.validate(&mut errors);
+    databroker.validate(&mut errors);
+
+    // The heights advertised by the anti-entropy loop must fit in the
+    // inventories accepted by the peers
+    let depth = chain.anti_entropy.depth;
+    if chain.anti_entropy.interval.is_some()
+        && usize::from(depth) > databroker.max_inv_entries
+    {
+        errors.push(ConfigError::invalid(
+            "chain.anti_entropy.depth",
+            depth,
+            format!(
+                "above databroker.max_inv_entries ({})",
+                databroker.max_inv_entries
+            ),
+        ));
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// Appends an error to `errors` if `value` is zero
+fn non_zero<T>(field: &str, value: T, errors: &mut Vec<ConfigError>)
+where
+    T: Default + PartialEq + std::fmt::Debug,
+{
+    if value == T::default() {
+        errors.push(ConfigError::invalid(field, value, "must not be zero"));
+    }
+}
+
+impl Section for ChainParams {
+    const NAME: &'static str = "chain";
+
+    const FIELDS: &'static [FieldDoc] = &[
+        FieldDoc {
+            name: "anti_entropy.interval",
+            description: "Interval between two anti-entropy repair rounds, \
+                          `null` to disable them",
+        },
+        FieldDoc {
+            name: "anti_entropy.depth",
+            description: "Number of most recent heights advertised in each \
+                          repair round",
+        },
+        FieldDoc {
+            name: "anti_entropy.fanout",
+            description: "Number of random alive peers the inventory is sent \
+                          to",
+        },
+        FieldDoc {
+            name: "admin.operator_key",
+            description: "Key the admin commands must be signed with",
+        },
+        FieldDoc {
+            name: "admin.max_clock_skew",
+            description: "Maximum difference between the timestamp of an \
+                          admin command and the local clock",
+        },
+        FieldDoc {
+            name: "fast_sync.peers",
+            description: "HTTP addresses of the peers the state is synced \
+                          from, empty to disable the fast sync",
+        },
+        FieldDoc {
+            name: "fast_sync.quorum",
+            description: "Number of peers that must agree on the synced \
+                          block",
+        },
+        FieldDoc {
+            name: "fast_sync.min_gap",
+            description: "Minimum number of blocks behind the peers for the \
+                          state to be synced",
+        },
+        FieldDoc {
+            name: "fast_sync.timeout",
+            description: "Timeout of each fast sync request",
+        },
+        FieldDoc {
+            name: "fast_sync.serve_state",
+            description: "Serve the finalized state to the fast sync of \
+                          other nodes",
+        },
+        FieldDoc {
+            name: "fast_sync.max_state_size",
+            description: "Maximum size in bytes of a state archive, served \
+                          or downloaded",
+        },
+        FieldDoc {
+            name: "fast_sync.state_export_interval",
+            description: "Minimum interval between two exports of the \
+                          served state",
+        },
+        FieldDoc {
+            name: "extra_data",
+            description: "Identifier included in the extra-data of the \
+                          generated blocks",
+        },
+        FieldDoc {
+            name: "memory.consensus_inbound",
+            description: "Maximum number of bytes held by the consensus \
+                          inbound queue",
+        },
+        FieldDoc {
+            name: "memory.future_msgs",
+            description: "Maximum number of bytes held by the messages of \
+                          the future rounds",
+        },
+        FieldDoc {
+            name: "stale_tip.threshold",
+            description: "Number of minimum block times without progress for \
+                          the tip to be stale, `null` to disable the recovery",
+        },
+        FieldDoc {
+            name: "stale_tip.cooldown",
+            description: "Minimum time between two stale tip recovery steps",
+        },
+    ];
+
+    fn validate(&self, errors: &mut Vec<ConfigError>) {
+        if self.anti_entropy.interval.is_some() {
+            let depth = self.anti_entropy.depth;
+            non_zero("chain.anti_entropy.depth", depth, errors);
+            let fanout = self.anti_entropy.fanout;
+            non_zero("chain.anti_entropy.fanout", fanout, errors);
+        }
+
+        let fast_sync = &self.fast_sync;
+        if !fast_sync.peers.is_empty() {
+            non_zero("chain.fast_sync.quorum", fast_sync.quorum, errors);
+            if fast_sync.quorum > fast_sync.peers.len() {
+                errors.push(ConfigError::invalid(
+                    "chain.fast_sync.quorum",
+                    fast_sync.quorum,
+                    format!(
+                        "above the number of fast_sync.peers ({})",
+                        fast_sync.peers.len()
+                    ),
+                ));
+            }
+            let timeout = fast_sync.timeout;
+            non_zero("chain.fast_sync.timeout", timeout, errors);
+        }
+        if !fast_sync.peers.is_empty() || fast_sync.serve_state {
+            let size = fast_sync.max_state_size;
+            non_zero("chain.fast_sync.max_state_size", size, errors);
+        }
+
+        let memory = &self.memory;
+        let inbound = memory.consensus_inbound;
+        non_zero("chain.memory.consensus_inbound", inbound, errors);
+        non_zero("chain.memory.future_msgs", memory.future_msgs, errors);
+
+        if let Some(threshold) = self.stale_tip.threshold {
+            non_zero("chain.stale_tip.threshold", threshold, errors);
+        }
+    }
+}
+
+impl Section for MempoolParams {
+    const NAME: &'static str = "mempool";
+
+    const FIELDS: &'static [FieldDoc] = &[
+        FieldDoc {
+            name: "max_queue_size",
+            description: "Number of transactions pending to be processed",
+        },
+        FieldDoc {
+            name: "max_mempool_txn_count",
+            description: "Maximum number of transactions in the mempool",
+        },
+        FieldDoc {
+            name: "idle_interval",
+            description: "Interval between two checks of the expired \
+                          transactions",
+        },
+        FieldDoc {
+            name: "mempool_expiry",
+            description: "Duration after which a transaction is removed from \
+                          the mempool",
+        },
+        FieldDoc {
+            name: "max_mempool_size",
+            description: "Maximum size in bytes of the mempool transactions, \
+                          `null` for no limit",
+        },
+        FieldDoc {
+            name: "mempool_download_redundancy",
+            description: "Number of peers the mempool is requested from",
+        },
+        FieldDoc {
+            name: "tx_policy",
+            description: "Path to the policy of the forbidden transactions",
+        },
+        FieldDoc {
+            name: "min_replacement_bump",
+            description: "Minimum gas price increase, in percent, for a \
+                          transaction to replace another",
+        },
+        FieldDoc {
+            name: "admission",
+            description: "Admission rules stacked on top of the ones of the \
+                          chain",
+        },
+    ];
+
+    fn validate(&self, errors: &mut Vec<ConfigError>) {
+        non_zero("mempool.max_queue_size", self.max_queue_size, errors);
+        let count = self.max_mempool_txn_count;
+        non_zero("mempool.max_mempool_txn_count", count, errors);
+        if let Some(size) = self.max_mempool_size {
+            non_zero("mempool.max_mempool_size", size, errors);
+        }
+
+        // Expired transactions are only removed on idle
+        if let (Some(interval), Some(expiry)) =
+            (self.idle_interval, self.mempool_expiry)
+        {
+            if expiry < interval {
+                errors.push(ConfigError::invalid(
+                    "mempool.mempool_expiry",
+                    expiry,
+                    format!("below mempool.idle_interval ({interval:?})"),
+                ));
+            }
+        }
+    }
+}
+
+impl Section for DataBrokerParams {
+    const NAME: &'static str = "databroker";
+
+    const FIELDS: &'static [FieldDoc] = &[
+        FieldDoc {
+            name: "max_inv_entries",
+            description: "Maximum number of entries of an inventory",
+        },
+        FieldDoc {
+            name: "max_ongoing_requests",
+            description: "Maximum number of requests handled concurrently",
+        },
+        FieldDoc {
+            name: "max_queue_size",
+            description: "Number of requests pending to be handled",
+        },
+        FieldDoc {
+            name: "delay_on_resp_msg",
+            description: "Delay in milliseconds before each response, on \
+                          local networks only",
+        },
+    ];
+
+    fn validate(&self, errors: &mut Vec<ConfigError>) {
+        let entries = self.max_inv_entries;
+        non_zero("databroker.max_inv_entries", entries, errors);
+        let requests = self.max_ongoing_requests;
+        non_zero("databroker.max_ongoing_requests", requests, errors);
+        non_zero("databroker.max_queue_size", self.max_queue_size, errors);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn test_default_config() {
+        let chain = ChainParams::default();
+        let mempool = MempoolParams::default();
+        let databroker = DataBrokerParams::default();
+        assert_eq!(validate(&chain, &mempool, &databroker), Ok(()));
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        let mut chain = ChainParams::default();
+        chain.anti_entropy.depth = 200;
+        chain.fast_sync.peers = vec!["http://127.0.0.1:8080".into()];
+        let mut mempool = MempoolParams::default();
+        mempool.mempool_expiry = Some(Duration::from_secs(1));
+        let databroker = DataBrokerParams {
+            max_queue_size: 0,
+            ..Default::default()
+        };
+
+        let errors = validate(&chain, &mempool, &databroker).unwrap_err();
+        let fields: Vec<_> = errors
+            .iter()
+            .map(|e| match e {
+                ConfigError::Invalid { field, .. } => field.as_str(),
+                _ => unreachable!(),
+            })
+            .collect();
+        assert_eq!(
+            fields,
+            vec![
+                "chain.fast_sync.quorum",
+                "mempool.mempool_expiry",
+                "databroker.max_queue_size",
+                "chain.anti_entropy.depth",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_version() {
+        assert_eq!(check_version(None), Ok(CONFIG_VERSION));
+        assert_eq!(check_version(Some(CONFIG_VERSION)), Ok(CONFIG_VERSION));
+        assert_eq!(
+            check_version(Some(CONFIG_VERSION + 1)),
+            Err(ConfigError::UnsupportedVersion(CONFIG_VERSION + 1))
+        );
+    }
+
+    #[test]
+    fn test_effective() {
+        let chain = ChainParams::default();
+        let mempool = MempoolParams::default();
+        let databroker = DataBrokerParams::default();
+        let effective = effective(&chain, &mempool, &databroker);
+
+        for name in [ChainParams::NAME, MempoolParams::NAME] {
+            assert!(effective[name].is_object(), "{name}");
+        }
+        let entries = &effective[DataBrokerParams::NAME]["max_inv_entries"];
+        assert_eq!(entries, &json!(databroker.max_inv_entries));
+    }
+
+    #[test]
+    fn test_schema() {
+        let fields = section_schema::<ChainParams>();
+        let depth = fields
+            .iter()
+            .find(|f| f.name == "anti_entropy.depth")
+            .expect("nested field");
+        assert_eq!(depth.kind, "number");
+        assert_eq!(depth.default, json!(10));
+        assert!(!depth.description.is_empty());
+
+        let key = fields
+            .iter()
+            .find(|f| f.name == "admin.operator_key")
+            .expect("optional field");
+        assert_eq!(key.kind, "null");
+
+        // Every documented field exists
+        for doc in ChainParams::FIELDS {
+            assert!(fields.iter().any(|f| f.name == doc.name), "{}", doc.name);
+        }
+        let schema = schema();
+        assert_eq!(schema["version"], json!(CONFIG_VERSION));
+        assert!(schema["sections"]["mempool"].is_array());
+    }
+}
@@ -4,24 +4,58 @@
 //
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
 
 pub mod rocksdb;
+pub mod round_history;
 
 use anyhow::Result;
+use dusk_core::abi::ContractId;
+use node_data::events::contract::ContractTxEvent;
 use node_data::ledger::{
-    Block, Fault, Header, Label, SpendingId, SpentTransaction, Transaction,
+    Block, BundlePosition, Fault, Header, Label, SpendingId, SpentTransaction,
+    Transaction,
 };
 use node_data::message::{payload, ConsensusHeader};
+use round_history::StepRecord;
 use serde::{Deserialize, Serialize};
 
+/// Outcome of the replace-by-fee check of a transaction against the mempool
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Replacement {
+    /// The transaction shares no spendable id with the mempool transactions
+    None,
+    /// The transaction replaces the mempool transactions with these ids
+    Replaces(Vec<[u8; 32]>),
+    /// The transaction doesn't pay enough to replace the mempool ones
+    Underpriced {
+        /// Minimum gas price required to replace them
+        min_gas_price: u64,
+    },
+}
+
+/// Returns the minimum gas price a transaction must pay to replace one paying
+/// `gas_price`, given the minimum bump percentage
+pub fn min_replacement_gas_price(gas_price: u64, min_bump_percent: u64) -> u64 {
+    let bump = (gas_price as u128 * min_bump_percent as u128).div_ceil(100);
+    let min = (gas_price as u128 + bump).max(gas_price as u128 + 1);
+    min.min(u64::MAX as u128) as u64
+}
+
 pub struct LightBlock {
     pub header: Header,
     pub transactions_ids: Vec<[u8; 32]>,
     pub faults_ids: Vec<[u8; 32]>,
 }
 
+/// A contract event emitted by an accepted block, as indexed in the ledger
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LedgerEvent {
+    pub height: u64,
+    pub event: ContractTxEvent,
+}
+
 pub trait DB: Send + Sync + 'static {
     type P<'a>: Persist;
 
@@ -87,6 +121,12 @@ pub trait Ledger {
 
     fn ledger_tx_exists(&self, tx_id: &[u8]) -> Result<bool>;
 
+    /// Returns the id of the ledger transaction spending `nullifier`, if any
+    fn ledger_tx_by_nullifier(
+        &self,
+        nullifier: &[u8; 32],
+    ) -> Result<Option<[u8; 32]>>;
+
     fn block_label_by_height(
         &self,
         height: u64,
@@ -101,6 +141,66 @@ pub trait Ledger {
 
     fn faults_by_block(&self, start_height: u64) -> Result<Vec<Fault>>;
     fn faults(&self, faults_ids: &[[u8; 32]]) -> Result<Vec<Fault>>;
+
+    /// Stores the Merkle root of the block hashes of a finalized epoch
+    fn store_epoch_root(&mut self, epoch: u64, root: &[u8; 32]) -> Result<()>;
+    fn epoch_root(&self, epoch: u64) -> Result<Option<[u8; 32]>>;
+
+    /// Indexes the contract events emitted by the block at `height`.
+    ///
+    /// The events are deleted along with the block.
+    fn store_events(
+        &mut self,
+        height: u64,
+        events: &[ContractTxEvent],
+    ) -> Result<()>;
+
+    /// Returns the height of the first block whose events are indexed.
+    ///
+    /// The events of the blocks accepted before the index was introduced are
+    /// not indexed.
+    fn events_indexed_from(&self) -> Result<u64>;
+
+    /// Returns the events emitted by the blocks from `from` to `to`, both
+    /// included, in emission order.
+    ///
+    /// Fails if `from` is below [`Self::events_indexed_from`].
+    fn events_by_height_range(
+        &self,
+        from: u64,
+        to: u64,
+    ) -> Result<Vec<LedgerEvent>>;
+
+    /// Returns the events emitted by `contract` in the blocks from `from` to
+    /// `to`, both included, in emission order.
+    ///
+    /// Fails if `from` is below [`Self::events_indexed_from`].
+    fn events_by_contract(
+        &self,
+        contract: &ContractId,
+        from: u64,
+        to: u64,
+    ) -> Result<Vec<LedgerEvent>>;
+
+    /// Indexes the gas spent calling each contract by the transactions of the
+    /// block at `height`, as returned by [`VMExecution::accept`].
+    ///
+    /// The record is deleted along with the block.
+    ///
+    /// [`VMExecution::accept`]: crate::vm::VMExecution::accept
+    fn store_contract_gas(
+        &mut self,
+        height: u64,
+        gas: &[(ContractId, u64)],
+    ) -> Result<()>;
+
+    /// Returns the gas spent calling each contract in the blocks from `from`
+    /// to `to`, both included, the most consuming contracts first
+    fn gas_by_contract(
+        &self,
+        from: u64,
+        to: u64,
+    ) -> Result<Vec<(ContractId, u64)>>;
 }
 
 pub trait ConsensusStorage {
@@ -141,6 +241,23 @@ pub trait ConsensusStorage {
         F: FnOnce([u8; 32]) -> bool + std::marker::Copy;
 
     fn count_validation_results(&self) -> usize;
+
+    /// Returns the highest iteration a candidate or a ValidationResult is
+    /// stored for, among the ones following the block `prev_block_hash`
+    fn last_stored_iteration(
+        &self,
+        prev_block_hash: &[u8; 32],
+    ) -> Result<Option<u8>>;
+
+    /// Round history Storage
+    fn store_step_record(&mut self, record: &StepRecord) -> Result<()>;
+
+    /// Returns the records of all the steps run for `round`, sorted by
+    /// branch, iteration and step
+    fn round_history(&self, round: u64) -> Result<Vec<StepRecord>>;
+
+    /// Deletes the history of all the rounds lower than `below_round`
+    fn delete_round_history(&mut self, below_round: u64) -> Result<()>;
 }
 
 pub trait Mempool {
@@ -159,7 +276,8 @@ pub trait Mempool {
 
     /// Deletes a transaction from the mempool.
     ///
-    /// If `cascade` is true, all dependant transactions are deleted
+    /// If `cascade` is true, all dependant transactions are deleted, along
+    /// with the rest of its bundle
     ///
     /// Return a vector with all the deleted tx_id
     fn delete_mempool_tx(
@@ -168,17 +286,68 @@ pub trait Mempool {
         cascade: bool,
     ) -> Result<Vec<[u8; 32]>>;
 
+    /// Links the mempool transactions of a bundle, so that they are yielded
+    /// together, in order, and deleted together.
+    fn store_mempool_bundle(
+        &mut self,
+        bundle_id: [u8; 32],
+        tx_ids: &[[u8; 32]],
+    ) -> Result<()>;
+
+    /// Gets the position of a mempool transaction in its bundle, along with
+    /// the hashes of all the bundle transactions, if it is part of one.
+    fn mempool_bundle(
+        &self,
+        tx_id: [u8; 32],
+    ) -> Result<Option<(BundlePosition, Vec<[u8; 32]>)>>;
+
     /// Get transactions hash from the mempool, searching by spendable ids
     fn mempool_txs_by_spendable_ids(
         &self,
         n: &[SpendingId],
     ) -> HashSet<[u8; 32]>;
 
+    /// Checks whether a transaction can replace by fee the mempool
+    /// transactions sharing any of its spendable ids.
+    ///
+    /// A transaction replaces them only if its gas price exceeds each of
+    /// theirs by at least `min_bump_percent` percent, and is strictly higher.
+    fn mempool_replacement(
+        &self,
+        tx: &Transaction,
+        min_bump_percent: u64,
+    ) -> Result<Replacement>;
+
     /// Get an iterator over the mempool transactions sorted by gas price
     fn mempool_txs_sorted_by_fee(
         &self,
     ) -> Result<Box<dyn Iterator<Item = Transaction> + '_>>;
 
+    /// Get an iterator over at most `limit` mempool transactions sorted by
+    /// gas price, the oldest first for the same price.
+    ///
+    /// Transactions with a gas limit above `max_gas` are skipped, since they
+    /// can't fit in a block. The transactions of a bundle are yielded all
+    /// together, in order.
+    fn txs_sorted_by_fee(
+        &self,
+        limit: usize,
+        max_gas: u64,
+    ) -> Result<Box<dyn Iterator<Item = Transaction> + '_>>;
+
+    /// Get an iterator over the mempool transactions in the canonical order
+    /// of the block transactions, see [`canonical_order`].
+    ///
+    /// Transactions with a gas limit above `max_gas` are skipped. The
+    /// transactions of a bundle are yielded all together, in order, along
+    /// with their position in the bundle.
+    ///
+    /// [`canonical_order`]: node_data::ledger::canonical_order
+    fn txs_in_canonical_order(
+        &self,
+        max_gas: u64,
+    ) -> Result<Box<dyn Iterator<Item = Transaction> + '_>>;
+
     /// Get an iterator over the mempool transactions hash by gas price
     fn mempool_txs_ids_sorted_by_fee(
         &self,
@@ -197,6 +366,9 @@ pub trait Mempool {
 
     /// Number of persisted transactions
     fn mempool_txs_count(&self) -> usize;
+
+    /// Size in bytes of each persisted transaction, by id
+    fn mempool_txs_sizes(&self) -> HashMap<[u8; 32], usize>;
 }
 
 pub trait Metadata {
@@ -5,44 +5,84 @@
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
 use std::cell::RefCell;
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::io::{Read, Write};
 use std::path::Path;
 use std::sync::Arc;
 use std::{io, vec};
 
 use anyhow::Result;
+use dusk_core::abi::ContractId;
+use node_data::events::contract::{ContractEvent, ContractTxEvent};
 use node_data::ledger::{
-    Block, Fault, Header, Label, SpendingId, SpentTransaction, Transaction,
+    canonical_order, Block, BundlePosition, Fault, Header, Label, SpendingId,
+    SpentTransaction, Transaction,
 };
 use node_data::message::{payload, ConsensusHeader};
 use node_data::Serializable;
 use rocksdb::{
     AsColumnFamilyRef, BlockBasedOptions, ColumnFamily, ColumnFamilyDescriptor,
-    DBAccess, DBRawIteratorWithThreadMode, IteratorMode, LogLevel,
-    OptimisticTransactionDB, OptimisticTransactionOptions, Options,
+    DBAccess, DBIteratorWithThreadMode, DBRawIteratorWithThreadMode, Direction,
+    IteratorMode, LogLevel, OptimisticTransactionDB,
+    OptimisticTransactionOptions, Options, ReadOptions, SnapshotWithThreadMode,
     WriteOptions,
 };
 use tracing::info;
 
+use super::round_history::StepRecord;
 use super::{
-    ConsensusStorage, DatabaseOptions, Ledger, LightBlock, Metadata, Persist,
-    DB,
+    into_array, min_replacement_gas_price, ConsensusStorage, DatabaseOptions,
+    Ledger, LedgerEvent, LightBlock, Metadata, Persist, Replacement, DB,
 };
 use crate::database::Mempool;
 
+mod migration;
+
 const CF_LEDGER_HEADER: &str = "cf_ledger_header";
 const CF_LEDGER_TXS: &str = "cf_ledger_txs";
 const CF_LEDGER_FAULTS: &str = "cf_ledger_faults";
 const CF_LEDGER_HEIGHT: &str = "cf_ledger_height";
+const CF_LEDGER_EPOCH_ROOTS: &str = "cf_ledger_epoch_roots";
+const CF_LEDGER_EVENTS: &str = "cf_ledger_events";
+const CF_LEDGER_CONTRACT_EVENTS: &str = "cf_ledger_contract_events";
+const CF_LEDGER_NULLIFIERS: &str = "cf_ledger_nullifiers";
+const CF_LEDGER_CONTRACT_GAS: &str = "cf_ledger_contract_gas";
 const CF_CANDIDATES: &str = "cf_candidates";
 const CF_CANDIDATES_HEIGHT: &str = "cf_candidates_height";
+const CF_CANDIDATES_ITERATION: &str = "cf_candidates_iteration";
 const CF_VALIDATION_RESULTS: &str = "cf_validation_results";
+const CF_ROUND_HISTORY: &str = "cf_round_history";
 const CF_MEMPOOL: &str = "cf_mempool";
 const CF_MEMPOOL_SPENDING_ID: &str = "cf_mempool_spending_id";
 const CF_MEMPOOL_FEES: &str = "cf_mempool_fees";
+const CF_MEMPOOL_PRIORITY: &str = "cf_mempool_priority";
+const CF_MEMPOOL_BUNDLES: &str = "cf_mempool_bundles";
 const CF_METADATA: &str = "cf_metadata";
 
+/// Column families of the database, as created by [`Backend::create_or_open`]
+const COLUMN_FAMILIES: [&str; 20] = [
+    CF_LEDGER_HEADER,
+    CF_LEDGER_TXS,
+    CF_LEDGER_FAULTS,
+    CF_LEDGER_HEIGHT,
+    CF_LEDGER_EPOCH_ROOTS,
+    CF_LEDGER_EVENTS,
+    CF_LEDGER_CONTRACT_EVENTS,
+    CF_LEDGER_NULLIFIERS,
+    CF_LEDGER_CONTRACT_GAS,
+    CF_CANDIDATES,
+    CF_CANDIDATES_HEIGHT,
+    CF_CANDIDATES_ITERATION,
+    CF_VALIDATION_RESULTS,
+    CF_ROUND_HISTORY,
+    CF_METADATA,
+    CF_MEMPOOL,
+    CF_MEMPOOL_SPENDING_ID,
+    CF_MEMPOOL_FEES,
+    CF_MEMPOOL_PRIORITY,
+    CF_MEMPOOL_BUNDLES,
+];
+
 const DB_FOLDER_NAME: &str = "chain.db";
 
 // List of supported metadata keys
@@ -52,13 +92,76 @@ pub const MD_AVG_VALIDATION: &[u8] = b"avg_validation_time";
 pub const MD_AVG_RATIFICATION: &[u8] = b"avg_ratification_time";
 pub const MD_AVG_PROPOSAL: &[u8] = b"avg_proposal_time";
 pub const MD_LAST_ITER: &[u8] = b"consensus_last_iter";
+pub const MD_PEER_REPUTATION: &[u8] = b"peer_reputation";
+pub const MD_WITHHOLDING_EVIDENCE: &[u8] = b"withholding_evidence";
+pub const MD_ADMIN_TIMESTAMP: &[u8] = b"admin_last_timestamp";
+pub const MD_EVENTS_INDEXED_FROM: &[u8] = b"events_indexed_from";
 
 #[derive(Clone)]
 pub struct Backend {
     rocksdb: Arc<OptimisticTransactionDB>,
+    /// Snapshot the reads are served from, if the backend is pinned
+    snapshot: Option<Arc<Snapshot>>,
+}
+
+/// Snapshot of the database, keeping it open for as long as it's alive
+struct Snapshot {
+    // Declared before the database, so that it's released first
+    inner: SnapshotWithThreadMode<'static, OptimisticTransactionDB>,
+    _rocksdb: Arc<OptimisticTransactionDB>,
+}
+
+impl Snapshot {
+    fn new(rocksdb: &Arc<OptimisticTransactionDB>) -> Self {
+        let inner = rocksdb.snapshot();
+        // SAFETY: the snapshot only borrows the database, which is kept open
+        // by `_rocksdb` for as long as the snapshot is alive
+        let inner = unsafe {
+            std::mem::transmute::<
+                SnapshotWithThreadMode<'_, OptimisticTransactionDB>,
+                SnapshotWithThreadMode<'static, OptimisticTransactionDB>,
+            >(inner)
+        };
+        Self {
+            inner,
+            _rocksdb: rocksdb.clone(),
+        }
+    }
 }
 
 impl Backend {
+    /// Returns a backend whose views all read the database as it is now,
+    /// ignoring any later write.
+    ///
+    /// A pinned backend can't be updated.
+    pub fn pinned(&self) -> Self {
+        Self {
+            rocksdb: self.rocksdb.clone(),
+            snapshot: Some(Arc::new(Snapshot::new(&self.rocksdb))),
+        }
+    }
+
+    /// Returns the column families missing from the database stored in
+    /// `path`, without opening it.
+    ///
+    /// Returns `None` if no database is stored in `path` yet.
+    pub fn missing_column_families<T: AsRef<Path>>(
+        path: T,
+    ) -> Result<Option<Vec<&'static str>>> {
+        let path = path.as_ref().join(DB_FOLDER_NAME);
+        if !path.join("CURRENT").exists() {
+            return Ok(None);
+        }
+
+        let existing = rocksdb::DB::list_cf(&Options::default(), &path)?;
+        let missing = COLUMN_FAMILIES
+            .into_iter()
+            .filter(|cf| !existing.iter().any(|e| e == cf))
+            .collect();
+
+        Ok(Some(missing))
+    }
+
     fn begin_tx(&self) -> DBTransaction<'_, OptimisticTransactionDB> {
         // Create a new RocksDB transaction
         let write_options = WriteOptions::default();
@@ -92,11 +195,21 @@ impl Backend {
             .cf_handle(CF_CANDIDATES_HEIGHT)
             .expect("candidates_height column family must exist");
 
+        let candidates_iteration_cf = self
+            .rocksdb
+            .cf_handle(CF_CANDIDATES_ITERATION)
+            .expect("candidates_iteration column family must exist");
+
         let validation_results_cf = self
             .rocksdb
             .cf_handle(CF_VALIDATION_RESULTS)
             .expect("validation result column family must exist");
 
+        let round_history_cf = self
+            .rocksdb
+            .cf_handle(CF_ROUND_HISTORY)
+            .expect("round history column family must exist");
+
         let mempool_cf = self
             .rocksdb
             .cf_handle(CF_MEMPOOL)
@@ -112,11 +225,46 @@ impl Backend {
             .cf_handle(CF_MEMPOOL_FEES)
             .expect("CF_MEMPOOL_FEES column family must exist");
 
+        let priority_cf = self
+            .rocksdb
+            .cf_handle(CF_MEMPOOL_PRIORITY)
+            .expect("CF_MEMPOOL_PRIORITY column family must exist");
+
+        let bundles_cf = self
+            .rocksdb
+            .cf_handle(CF_MEMPOOL_BUNDLES)
+            .expect("CF_MEMPOOL_BUNDLES column family must exist");
+
         let ledger_height_cf = self
             .rocksdb
             .cf_handle(CF_LEDGER_HEIGHT)
             .expect("CF_LEDGER_HEIGHT column family must exist");
 
+        let ledger_epoch_roots_cf = self
+            .rocksdb
+            .cf_handle(CF_LEDGER_EPOCH_ROOTS)
+            .expect("CF_LEDGER_EPOCH_ROOTS column family must exist");
+
+        let ledger_events_cf = self
+            .rocksdb
+            .cf_handle(CF_LEDGER_EVENTS)
+            .expect("CF_LEDGER_EVENTS column family must exist");
+
+        let ledger_contract_events_cf = self
+            .rocksdb
+            .cf_handle(CF_LEDGER_CONTRACT_EVENTS)
+            .expect("CF_LEDGER_CONTRACT_EVENTS column family must exist");
+
+        let ledger_nullifiers_cf = self
+            .rocksdb
+            .cf_handle(CF_LEDGER_NULLIFIERS)
+            .expect("CF_LEDGER_NULLIFIERS column family must exist");
+
+        let ledger_contract_gas_cf = self
+            .rocksdb
+            .cf_handle(CF_LEDGER_CONTRACT_GAS)
+            .expect("CF_LEDGER_CONTRACT_GAS column family must exist");
+
         let metadata_cf = self
             .rocksdb
             .cf_handle(CF_METADATA)
@@ -126,15 +274,25 @@ impl Backend {
             inner,
             candidates_cf,
             candidates_height_cf,
+            candidates_iteration_cf,
             validation_results_cf,
+            round_history_cf,
             ledger_cf,
             ledger_txs_cf,
             ledger_faults_cf,
             mempool_cf,
             spending_id_cf,
             fees_cf,
+            priority_cf,
+            bundles_cf,
             ledger_height_cf,
+            ledger_epoch_roots_cf,
+            ledger_events_cf,
+            ledger_contract_events_cf,
+            ledger_nullifiers_cf,
+            ledger_contract_gas_cf,
             metadata_cf,
+            snapshot: self.snapshot.as_deref().map(|s| &s.inner),
             cumulative_inner_size: RefCell::new(0),
         }
     }
@@ -200,15 +358,43 @@ impl DB for Backend {
                 CF_LEDGER_HEIGHT,
                 blocks_cf_opts.clone(),
             ),
+            ColumnFamilyDescriptor::new(
+                CF_LEDGER_EPOCH_ROOTS,
+                blocks_cf_opts.clone(),
+            ),
+            ColumnFamilyDescriptor::new(
+                CF_LEDGER_EVENTS,
+                blocks_cf_opts.clone(),
+            ),
+            ColumnFamilyDescriptor::new(
+                CF_LEDGER_CONTRACT_EVENTS,
+                blocks_cf_opts.clone(),
+            ),
+            ColumnFamilyDescriptor::new(
+                CF_LEDGER_NULLIFIERS,
+                blocks_cf_opts.clone(),
+            ),
+            ColumnFamilyDescriptor::new(
+                CF_LEDGER_CONTRACT_GAS,
+                blocks_cf_opts.clone(),
+            ),
             ColumnFamilyDescriptor::new(CF_CANDIDATES, blocks_cf_opts.clone()),
             ColumnFamilyDescriptor::new(
                 CF_CANDIDATES_HEIGHT,
                 blocks_cf_opts.clone(),
             ),
+            ColumnFamilyDescriptor::new(
+                CF_CANDIDATES_ITERATION,
+                blocks_cf_opts.clone(),
+            ),
             ColumnFamilyDescriptor::new(
                 CF_VALIDATION_RESULTS,
                 blocks_cf_opts.clone(),
             ),
+            ColumnFamilyDescriptor::new(
+                CF_ROUND_HISTORY,
+                blocks_cf_opts.clone(),
+            ),
             ColumnFamilyDescriptor::new(CF_METADATA, blocks_cf_opts.clone()),
             ColumnFamilyDescriptor::new(CF_MEMPOOL, mp_opts.clone()),
             ColumnFamilyDescriptor::new(
@@ -216,17 +402,24 @@ impl DB for Backend {
                 mp_opts.clone(),
             ),
             ColumnFamilyDescriptor::new(CF_MEMPOOL_FEES, mp_opts.clone()),
+            ColumnFamilyDescriptor::new(CF_MEMPOOL_PRIORITY, mp_opts.clone()),
+            ColumnFamilyDescriptor::new(CF_MEMPOOL_BUNDLES, mp_opts.clone()),
         ];
 
+        migration::restore_interrupted(&path)
+            .expect("interrupted database migration to be restored");
+        let rocksdb = OptimisticTransactionDB::open_cf_descriptors(
+            &blocks_cf_opts,
+            &path,
+            cfs,
+        )
+        .expect("should be a valid database in {path}");
+        let rocksdb = migration::migrate(rocksdb, &path, migration::MIGRATIONS)
+            .expect("database migrations to succeed");
+
         Self {
-            rocksdb: Arc::new(
-                OptimisticTransactionDB::open_cf_descriptors(
-                    &blocks_cf_opts,
-                    path,
-                    cfs,
-                )
-                .expect("should be a valid database in {path}"),
-            ),
+            rocksdb: Arc::new(rocksdb),
+            snapshot: None,
         }
     }
 
@@ -254,6 +447,10 @@ impl DB for Backend {
     where
         F: for<'a> FnOnce(&mut Self::P<'a>) -> Result<T>,
     {
+        if self.snapshot.is_some() {
+            anyhow::bail!("Cannot update a pinned database");
+        }
+
         // Create read-write transaction
         let mut tx = self.begin_tx();
 
@@ -283,21 +480,40 @@ pub struct DBTransaction<'db, DB: DBAccess> {
     // Candidates column family
     candidates_cf: &'db ColumnFamily,
     candidates_height_cf: &'db ColumnFamily,
+    // Index of the candidates, keyed by (prev_block_hash, iteration, hash)
+    candidates_iteration_cf: &'db ColumnFamily,
     // ValidationResults column family
     validation_results_cf: &'db ColumnFamily,
+    // Round history column family
+    round_history_cf: &'db ColumnFamily,
 
     // Ledger column families
     ledger_cf: &'db ColumnFamily,
     ledger_faults_cf: &'db ColumnFamily,
     ledger_txs_cf: &'db ColumnFamily,
     ledger_height_cf: &'db ColumnFamily,
+    ledger_epoch_roots_cf: &'db ColumnFamily,
+    // Contract events, keyed by (height, index)
+    ledger_events_cf: &'db ColumnFamily,
+    // Index of the contract events, keyed by (contract, height, index)
+    ledger_contract_events_cf: &'db ColumnFamily,
+    // Index of the spent nullifiers, mapped to the spending transaction
+    ledger_nullifiers_cf: &'db ColumnFamily,
+    // Gas spent per called contract, keyed by (height, contract)
+    ledger_contract_gas_cf: &'db ColumnFamily,
 
     // Mempool column families
     mempool_cf: &'db ColumnFamily,
     spending_id_cf: &'db ColumnFamily,
     fees_cf: &'db ColumnFamily,
+    priority_cf: &'db ColumnFamily,
+    bundles_cf: &'db ColumnFamily,
 
     metadata_cf: &'db ColumnFamily,
+
+    /// Snapshot the reads are served from, if any
+    snapshot:
+        Option<&'db SnapshotWithThreadMode<'static, OptimisticTransactionDB>>,
 }
 
 impl<'db, DB: DBAccess> Ledger for DBTransaction<'db, DB> {
@@ -341,6 +557,18 @@ impl<'db, DB: DBAccess> Ledger for DBTransaction<'db, DB> {
             }
         }
 
+        // COLUMN FAMILY: CF_LEDGER_NULLIFIERS
+        {
+            let cf = self.ledger_nullifiers_cf;
+
+            for tx in txs {
+                let tx_id = tx.inner.id();
+                for n in ledger_nullifiers(&tx.inner) {
+                    self.put_cf(cf, n, tx_id)?;
+                }
+            }
+        }
+
         // COLUMN FAMILY: CF_LEDGER_FAULTS
         {
             let cf = self.ledger_faults_cf;
@@ -408,10 +636,15 @@ impl<'db, DB: DBAccess> Ledger for DBTransaction<'db, DB> {
 
         for tx in b.txs() {
             self.inner.delete_cf(self.ledger_txs_cf, tx.id())?;
+            for n in ledger_nullifiers(tx) {
+                self.inner.delete_cf(self.ledger_nullifiers_cf, n)?;
+            }
         }
         for f in b.faults() {
             self.inner.delete_cf(self.ledger_faults_cf, f.id())?;
         }
+        self.delete_events(b.header().height)?;
+        self.delete_contract_gas(b.header().height)?;
 
         self.inner.delete_cf(self.ledger_cf, b.header().hash)?;
 
@@ -419,7 +652,7 @@ impl<'db, DB: DBAccess> Ledger for DBTransaction<'db, DB> {
     }
 
     fn block_exists(&self, hash: &[u8]) -> Result<bool> {
-        Ok(self.inner.get_cf(self.ledger_cf, hash)?.is_some())
+        Ok(self.get_cf(self.ledger_cf, hash)?.is_some())
     }
 
     fn faults(&self, faults_ids: &[[u8; 32]]) -> Result<Vec<Fault>> {
@@ -432,7 +665,7 @@ impl<'db, DB: DBAccess> Ledger for DBTransaction<'db, DB> {
             .collect::<Vec<_>>();
 
         // Retrieve all faults ID with single call
-        let faults_buffer = self.inner.multi_get_cf(ids);
+        let faults_buffer = self.multi_get_cf(ids);
 
         let mut faults = vec![];
         for buf in faults_buffer {
@@ -445,12 +678,12 @@ impl<'db, DB: DBAccess> Ledger for DBTransaction<'db, DB> {
     }
 
     fn block(&self, hash: &[u8]) -> Result<Option<Block>> {
-        match self.inner.get_cf(self.ledger_cf, hash)? {
+        match self.get_cf(self.ledger_cf, hash)? {
             Some(blob) => {
                 let record = LightBlock::read(&mut &blob[..])?;
 
                 // Retrieve all transactions buffers with single call
-                let txs_buffers = self.inner.multi_get_cf(
+                let txs_buffers = self.multi_get_cf(
                     record
                         .transactions_ids
                         .iter()
@@ -466,7 +699,7 @@ impl<'db, DB: DBAccess> Ledger for DBTransaction<'db, DB> {
                 }
 
                 // Retrieve all faults ID with single call
-                let faults_buffer = self.inner.multi_get_cf(
+                let faults_buffer = self.multi_get_cf(
                     record
                         .faults_ids
                         .iter()
@@ -490,7 +723,7 @@ impl<'db, DB: DBAccess> Ledger for DBTransaction<'db, DB> {
     }
 
     fn light_block(&self, hash: &[u8]) -> Result<Option<LightBlock>> {
-        match self.inner.get_cf(self.ledger_cf, hash)? {
+        match self.get_cf(self.ledger_cf, hash)? {
             Some(blob) => {
                 let record = LightBlock::read(&mut &blob[..])?;
                 Ok(Some(record))
@@ -500,7 +733,7 @@ impl<'db, DB: DBAccess> Ledger for DBTransaction<'db, DB> {
     }
 
     fn block_header(&self, hash: &[u8]) -> Result<Option<Header>> {
-        match self.inner.get_cf(self.ledger_cf, hash)? {
+        match self.get_cf(self.ledger_cf, hash)? {
             Some(blob) => {
                 let record = Header::read(&mut &blob[..])?;
                 Ok(Some(record))
@@ -511,7 +744,6 @@ impl<'db, DB: DBAccess> Ledger for DBTransaction<'db, DB> {
 
     fn block_hash_by_height(&self, height: u64) -> Result<Option<[u8; 32]>> {
         Ok(self
-            .inner
             .get_cf(self.ledger_height_cf, height.to_le_bytes())?
             .map(|h| {
                 const LEN: usize = 32;
@@ -523,7 +755,6 @@ impl<'db, DB: DBAccess> Ledger for DBTransaction<'db, DB> {
 
     fn ledger_tx(&self, tx_id: &[u8]) -> Result<Option<SpentTransaction>> {
         let tx = self
-            .inner
             .get_cf(self.ledger_txs_cf, tx_id)?
             .map(|blob| SpentTransaction::read(&mut &blob[..]))
             .transpose()?;
@@ -545,7 +776,7 @@ impl<'db, DB: DBAccess> Ledger for DBTransaction<'db, DB> {
 
         let ids = tx_ids.into_iter().map(|id| (cf, id)).collect::<Vec<_>>();
 
-        let multi_get_results = self.inner.multi_get_cf(ids);
+        let multi_get_results = self.multi_get_cf(ids);
 
         let mut spent_transactions =
             Vec::with_capacity(multi_get_results.len());
@@ -574,7 +805,17 @@ impl<'db, DB: DBAccess> Ledger for DBTransaction<'db, DB> {
     /// This is a convenience method that checks if a transaction exists in the
     /// ledger without unmarshalling the transaction
     fn ledger_tx_exists(&self, tx_id: &[u8]) -> Result<bool> {
-        Ok(self.inner.get_cf(self.ledger_txs_cf, tx_id)?.is_some())
+        Ok(self.get_cf(self.ledger_txs_cf, tx_id)?.is_some())
+    }
+
+    fn ledger_tx_by_nullifier(
+        &self,
+        nullifier: &[u8; 32],
+    ) -> Result<Option<[u8; 32]>> {
+        let tx_id = self
+            .get_cf(self.ledger_nullifiers_cf, nullifier)?
+            .map(|id| into_array(&id));
+        Ok(tx_id)
     }
 
     fn block_by_height(&self, height: u64) -> Result<Option<Block>> {
@@ -592,7 +833,6 @@ impl<'db, DB: DBAccess> Ledger for DBTransaction<'db, DB> {
     ) -> Result<Option<([u8; 32], Label)>> {
         const HASH_LEN: usize = 32;
         Ok(self
-            .inner
             .get_cf(self.ledger_height_cf, height.to_le_bytes())?
             .map(|h| {
                 let mut hash = [0u8; HASH_LEN];
@@ -603,6 +843,180 @@ impl<'db, DB: DBAccess> Ledger for DBTransaction<'db, DB> {
             })
             .transpose()?)
     }
+
+    fn store_epoch_root(&mut self, epoch: u64, root: &[u8; 32]) -> Result<()> {
+        self.put_cf(self.ledger_epoch_roots_cf, epoch.to_be_bytes(), root)?;
+        Ok(())
+    }
+
+    fn epoch_root(&self, epoch: u64) -> Result<Option<[u8; 32]>> {
+        Ok(self
+            .get_cf(self.ledger_epoch_roots_cf, epoch.to_be_bytes())?
+            .map(|root| into_array(&root)))
+    }
+
+    fn store_events(
+        &mut self,
+        height: u64,
+        events: &[ContractTxEvent],
+    ) -> Result<()> {
+        for (index, event) in events.iter().enumerate() {
+            let key = event_key(height, index as u32);
+
+            let mut buf = vec![];
+            LedgerEvent {
+                height,
+                event: event.clone(),
+            }
+            .write(&mut buf)?;
+            self.put_cf(self.ledger_events_cf, key, buf)?;
+
+            let contract = event.event.target.as_bytes();
+            self.put_cf(
+                self.ledger_contract_events_cf,
+                [contract, &key[..]].concat(),
+                b"",
+            )?;
+        }
+        Ok(())
+    }
+
+    fn events_indexed_from(&self) -> Result<u64> {
+        let Some(height) = self.op_read(MD_EVENTS_INDEXED_FROM)? else {
+            return Ok(0);
+        };
+        let height = height
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("Invalid events index height"))?;
+        Ok(u64::from_le_bytes(height))
+    }
+
+    fn events_by_height_range(
+        &self,
+        from: u64,
+        to: u64,
+    ) -> Result<Vec<LedgerEvent>> {
+        self.check_events_indexed(from)?;
+        let start = from.to_be_bytes();
+        let iter = self.iterator_cf(
+            self.ledger_events_cf,
+            IteratorMode::From(&start, Direction::Forward),
+        );
+
+        let mut events = vec![];
+        for (_, value) in iter.map(Result::unwrap) {
+            let event = LedgerEvent::read(&mut &value[..])?;
+            if event.height > to {
+                break;
+            }
+            events.push(event);
+        }
+        Ok(events)
+    }
+
+    fn events_by_contract(
+        &self,
+        contract: &ContractId,
+        from: u64,
+        to: u64,
+    ) -> Result<Vec<LedgerEvent>> {
+        self.check_events_indexed(from)?;
+        let prefix = contract.as_bytes();
+        let start = [prefix, &from.to_be_bytes()[..]].concat();
+        let iter = self.iterator_cf(
+            self.ledger_contract_events_cf,
+            IteratorMode::From(&start, Direction::Forward),
+        );
+
+        let mut keys = vec![];
+        for (key, _) in iter.map(Result::unwrap) {
+            if !key.starts_with(prefix) {
+                break;
+            }
+            let event_key = key[prefix.len()..].to_vec();
+            let height = u64::from_be_bytes(into_array(&event_key[..8]));
+            if height > to {
+                break;
+            }
+            keys.push(event_key);
+        }
+
+        let mut events = Vec::with_capacity(keys.len());
+        let values = self
+            .multi_get_cf(keys.iter().map(|key| (self.ledger_events_cf, key)));
+        for value in values {
+            let value = value?
+                .ok_or_else(|| anyhow::anyhow!("Indexed event not found"))?;
+            events.push(LedgerEvent::read(&mut &value[..])?);
+        }
+        Ok(events)
+    }
+
+    fn store_contract_gas(
+        &mut self,
+        height: u64,
+        gas: &[(ContractId, u64)],
+    ) -> Result<()> {
+        for (contract, gas) in gas {
+            let key = contract_gas_key(height, contract);
+            self.put_cf(self.ledger_contract_gas_cf, key, gas.to_be_bytes())?;
+        }
+        Ok(())
+    }
+
+    fn gas_by_contract(
+        &self,
+        from: u64,
+        to: u64,
+    ) -> Result<Vec<(ContractId, u64)>> {
+        let start = from.to_be_bytes();
+        let iter = self.iterator_cf(
+            self.ledger_contract_gas_cf,
+            IteratorMode::From(&start, Direction::Forward),
+        );
+
+        let mut gas_by_contract = BTreeMap::<[u8; 32], u64>::new();
+        for (key, value) in iter.map(Result::unwrap) {
+            let height = u64::from_be_bytes(into_array(&key[..8]));
+            if height > to {
+                break;
+            }
+            let gas = gas_by_contract.entry(into_array(&key[8..])).or_default();
+            *gas = gas.saturating_add(u64::from_be_bytes(into_array(&value)));
+        }
+
+        let mut gas_by_contract: Vec<_> = gas_by_contract
+            .into_iter()
+            .map(|(contract, gas)| (ContractId::from_bytes(contract), gas))
+            .collect();
+        gas_by_contract.sort_by(|(_, a), (_, b)| b.cmp(a));
+        Ok(gas_by_contract)
+    }
+}
+
+/// Returns the key of the event emitted at `index` by the block at `height`
+fn event_key(height: u64, index: u32) -> [u8; 12] {
+    let mut key = [0u8; 12];
+    key[..8].copy_from_slice(&height.to_be_bytes());
+    key[8..].copy_from_slice(&index.to_be_bytes());
+    key
+}
+
+/// Returns the key of the gas spent calling `contract` by the block at
+/// `height`
+fn contract_gas_key(height: u64, contract: &ContractId) -> [u8; 40] {
+    let mut key = [0u8; 40];
+    key[..8].copy_from_slice(&height.to_be_bytes());
+    key[8..].copy_from_slice(contract.as_bytes());
+    key
+}
+
+/// Returns the nullifiers spent by `tx`, as indexed in the ledger
+fn ledger_nullifiers(tx: &Transaction) -> impl Iterator<Item = [u8; 32]> {
+    tx.to_spend_ids().into_iter().filter_map(|id| match id {
+        SpendingId::Nullifier(n) => Some(n),
+        SpendingId::AccountNonce(..) => None,
+    })
 }
 
 /// Implementation of the `Candidate` trait for `DBTransaction<'db, DB>`.
@@ -628,6 +1042,12 @@ impl<'db, DB: DBAccess> ConsensusStorage for DBTransaction<'db, DB> {
         self.inner
             .put_cf(self.candidates_height_cf, key, b.header().hash)?;
 
+        self.inner.put_cf(
+            self.candidates_iteration_cf,
+            candidate_iteration_key(b.header()),
+            [],
+        )?;
+
         Ok(())
     }
 
@@ -642,7 +1062,7 @@ impl<'db, DB: DBAccess> ConsensusStorage for DBTransaction<'db, DB> {
     /// Returns `Ok(Some(block))` if the block is found, `Ok(None)` if the block
     /// is not found, or an error if the operation fails.
     fn candidate(&self, hash: &[u8]) -> Result<Option<Block>> {
-        if let Some(blob) = self.inner.get_cf(self.candidates_cf, hash)? {
+        if let Some(blob) = self.get_cf(self.candidates_cf, hash)? {
             let b = Block::read(&mut &blob[..])?;
             return Ok(Some(b));
         }
@@ -655,22 +1075,18 @@ impl<'db, DB: DBAccess> ConsensusStorage for DBTransaction<'db, DB> {
         &self,
         consensus_header: &ConsensusHeader,
     ) -> Result<Option<Block>> {
-        let iter = self
-            .inner
-            .iterator_cf(self.candidates_cf, IteratorMode::Start);
-
-        for (_, blob) in iter.map(Result::unwrap) {
-            let b = Block::read(&mut &blob[..])?;
-
-            let header = b.header();
-            if header.prev_block_hash == consensus_header.prev_block_hash
-                && header.iteration == consensus_header.iteration
-            {
-                return Ok(Some(b));
+        let prefix = serialize_iter_key(consensus_header)?;
+        let mut iter = self.iterator_cf(
+            self.candidates_iteration_cf,
+            IteratorMode::From(&prefix, Direction::Forward),
+        );
+
+        match iter.next().transpose()? {
+            Some((key, _)) if key.starts_with(&prefix) => {
+                self.candidate(&key[prefix.len()..])
             }
+            _ => Ok(None),
         }
-
-        Ok(None)
     }
 
     /// Deletes candidate-related items from the database based on a closure.
@@ -687,13 +1103,20 @@ impl<'db, DB: DBAccess> ConsensusStorage for DBTransaction<'db, DB> {
     where
         F: FnOnce(u64) -> bool + std::marker::Copy,
     {
-        let iter = self
-            .inner
-            .iterator_cf(self.candidates_height_cf, IteratorMode::Start);
+        let iter =
+            self.iterator_cf(self.candidates_height_cf, IteratorMode::Start);
 
         for (key, hash) in iter.map(Result::unwrap) {
             let (height, _) = deserialize_key(&mut &key.to_vec()[..])?;
             if closure(height) {
+                // Only the header is read to find the iteration index entry
+                if let Some(blob) = self.get_cf(self.candidates_cf, &hash)? {
+                    let header = Header::read(&mut &blob[..])?;
+                    self.inner.delete_cf(
+                        self.candidates_iteration_cf,
+                        candidate_iteration_key(&header),
+                    )?;
+                }
                 self.inner.delete_cf(self.candidates_cf, hash)?;
                 self.inner.delete_cf(self.candidates_height_cf, key)?;
             }
@@ -703,9 +1126,8 @@ impl<'db, DB: DBAccess> ConsensusStorage for DBTransaction<'db, DB> {
     }
 
     fn count_candidates(&self) -> usize {
-        let iter = self
-            .inner
-            .iterator_cf(self.candidates_height_cf, IteratorMode::Start);
+        let iter =
+            self.iterator_cf(self.candidates_height_cf, IteratorMode::Start);
 
         iter.count()
     }
@@ -761,9 +1183,7 @@ impl<'db, DB: DBAccess> ConsensusStorage for DBTransaction<'db, DB> {
         consensus_header: &ConsensusHeader,
     ) -> Result<Option<payload::ValidationResult>> {
         let key = serialize_iter_key(consensus_header)?;
-        if let Some(blob) =
-            self.inner.get_cf(self.validation_results_cf, key)?
-        {
+        if let Some(blob) = self.get_cf(self.validation_results_cf, key)? {
             let validation_result =
                 payload::ValidationResult::read(&mut &blob[..])?;
             return Ok(Some(validation_result));
@@ -788,9 +1208,8 @@ impl<'db, DB: DBAccess> ConsensusStorage for DBTransaction<'db, DB> {
     where
         F: FnOnce([u8; 32]) -> bool + std::marker::Copy,
     {
-        let iter = self
-            .inner
-            .iterator_cf(self.validation_results_cf, IteratorMode::Start);
+        let iter =
+            self.iterator_cf(self.validation_results_cf, IteratorMode::Start);
 
         for (key, _) in iter.map(Result::unwrap) {
             let (prev_block_hash, _) =
@@ -804,13 +1223,36 @@ impl<'db, DB: DBAccess> ConsensusStorage for DBTransaction<'db, DB> {
     }
 
     fn count_validation_results(&self) -> usize {
-        let iter = self
-            .inner
-            .iterator_cf(self.validation_results_cf, IteratorMode::Start);
+        let iter =
+            self.iterator_cf(self.validation_results_cf, IteratorMode::Start);
 
         iter.count()
     }
 
+    fn last_stored_iteration(
+        &self,
+        prev_block_hash: &[u8; 32],
+    ) -> Result<Option<u8>> {
+        // Both column families are keyed by (prev_block_hash, iteration, ..),
+        // only the keys following `prev_block_hash` are read
+        let mut last = None;
+        for cf in [self.candidates_iteration_cf, self.validation_results_cf] {
+            let iter = self.iterator_cf(
+                cf,
+                IteratorMode::From(prev_block_hash, Direction::Forward),
+            );
+            for (key, _) in iter.map(Result::unwrap) {
+                if !key.starts_with(prev_block_hash) {
+                    break;
+                }
+                let (_, iteration) = deserialize_iter_key(&mut &key[..])?;
+                last = last.max(Some(iteration));
+            }
+        }
+
+        Ok(last)
+    }
+
     /// Deletes all items from the `CF_VALIDATION_RESULTS` column family.
     ///
     /// # Returns
@@ -820,13 +1262,65 @@ impl<'db, DB: DBAccess> ConsensusStorage for DBTransaction<'db, DB> {
     fn clear_validation_results(&mut self) -> Result<()> {
         self.delete_validation_results(|_| true)
     }
+
+    fn store_step_record(&mut self, record: &StepRecord) -> Result<()> {
+        let mut serialized = vec![];
+        record.write(&mut serialized)?;
+
+        self.inner
+            .put_cf(self.round_history_cf, record.key(), serialized)?;
+
+        Ok(())
+    }
+
+    fn round_history(&self, round: u64) -> Result<Vec<StepRecord>> {
+        // Keys are prefixed by the big-endian round, so the records of a
+        // round are the ones bounded by the prefixes of it and the next one
+        let iter = self.inner.iterator_cf_opt(
+            self.round_history_cf,
+            round_bounds(self.read_opts(), round, round.saturating_add(1)),
+            IteratorMode::Start,
+        );
+
+        let mut records = vec![];
+        for (_, value) in iter.map(Result::unwrap) {
+            records.push(StepRecord::read(&mut &value[..])?);
+        }
+
+        Ok(records)
+    }
+
+    fn delete_round_history(&mut self, below_round: u64) -> Result<()> {
+        let mut iter = self.inner.raw_iterator_cf_opt(
+            self.round_history_cf,
+            round_bounds(self.read_opts(), 0, below_round),
+        );
+        iter.seek_to_first();
+
+        // Only the keys of the rounds to delete are read
+        while let Some(key) = iter.key() {
+            self.inner.delete_cf(self.round_history_cf, key)?;
+            iter.next();
+        }
+        iter.status()?;
+
+        Ok(())
+    }
+}
+
+/// Bounds the read options `opts` of an iteration over the round history to
+/// the records of the rounds from `from` (included) to `to` (excluded)
+fn round_bounds(mut opts: ReadOptions, from: u64, to: u64) -> ReadOptions {
+    opts.set_iterate_lower_bound(from.to_be_bytes());
+    opts.set_iterate_upper_bound(to.to_be_bytes());
+    opts
 }
 
 impl<'db, DB: DBAccess> Persist for DBTransaction<'db, DB> {
     /// Deletes all items from both CF_LEDGER and CF_CANDIDATES column families
     fn clear_database(&mut self) -> Result<()> {
         // Create an iterator over the column family CF_LEDGER
-        let iter = self.inner.iterator_cf(self.ledger_cf, IteratorMode::Start);
+        let iter = self.iterator_cf(self.ledger_cf, IteratorMode::Start);
 
         // Iterate through the CF_LEDGER column family and delete all items
         for (key, _) in iter.map(Result::unwrap) {
@@ -875,6 +1369,14 @@ impl<'db, DB: DBAccess> Mempool for DBTransaction<'db, DB> {
             self.put_cf(self.spending_id_cf, key, hash)?;
         }
 
+        // Map Fee_Timestamp_Hash to nothing
+        // Key is used to sort by fee, the oldest first for the same fee
+        self.put_cf(
+            self.priority_cf,
+            serialize_priority_key(tx.gas_price(), timestamp, hash)?,
+            [],
+        )?;
+
         let timestamp = timestamp.to_be_bytes();
 
         // Map Fee_Hash to Timestamp
@@ -890,17 +1392,23 @@ impl<'db, DB: DBAccess> Mempool for DBTransaction<'db, DB> {
     }
 
     fn mempool_tx(&self, hash: [u8; 32]) -> Result<Option<Transaction>> {
-        let data = self.inner.get_cf(self.mempool_cf, hash)?;
+        let data = self.get_cf(self.mempool_cf, hash)?;
 
         match data {
             // None has a meaning key not found
             None => Ok(None),
-            Some(blob) => Ok(Some(Transaction::read(&mut &blob.to_vec()[..])?)),
+            Some(blob) => {
+                let tx = Transaction::read(&mut &blob.to_vec()[..])?;
+                match self.mempool_bundle(hash)? {
+                    Some((position, _)) => Ok(Some(tx.with_bundle(position))),
+                    None => Ok(Some(tx)),
+                }
+            }
         }
     }
 
     fn mempool_tx_exists(&self, h: [u8; 32]) -> Result<bool> {
-        Ok(self.inner.get_cf(self.mempool_cf, h)?.is_some())
+        Ok(self.get_cf(self.mempool_cf, h)?.is_some())
     }
 
     fn delete_mempool_tx(
@@ -922,14 +1430,32 @@ impl<'db, DB: DBAccess> Mempool for DBTransaction<'db, DB> {
                 self.inner.delete_cf(self.spending_id_cf, key)?;
             }
 
-            // Delete Fee_Hash
-            self.inner.delete_cf(
-                self.fees_cf,
-                serialize_key(tx.gas_price(), hash)?,
-            )?;
+            // Delete Fee_Hash, along with Fee_Timestamp_Hash
+            let fee_key = serialize_key(tx.gas_price(), hash)?;
+            if let Some(timestamp) = self.get_cf(self.fees_cf, &fee_key)? {
+                let timestamp = u64::from_be_bytes(into_array(&timestamp));
+                self.inner.delete_cf(
+                    self.priority_cf,
+                    serialize_priority_key(tx.gas_price(), timestamp, hash)?,
+                )?;
+            }
+            self.inner.delete_cf(self.fees_cf, fee_key)?;
 
             deleted.push(h);
 
+            // Unlink the bundle of the transaction, deleting the rest of it
+            // along with their dependants if `cascade` is true
+            if let Some((_, bundle_txs)) = self.mempool_bundle(hash)? {
+                for tx_id in &bundle_txs {
+                    self.inner.delete_cf(self.bundles_cf, tx_id)?;
+                }
+                if cascade {
+                    for tx_id in bundle_txs.into_iter().filter(|id| *id != h) {
+                        deleted.extend(self.delete_mempool_tx(tx_id, true)?);
+                    }
+                }
+            }
+
             if cascade {
                 let mut dependants = vec![];
                 // Get the next spending id (aka next nonce tx)
@@ -957,13 +1483,59 @@ impl<'db, DB: DBAccess> Mempool for DBTransaction<'db, DB> {
         Ok(deleted)
     }
 
+    fn store_mempool_bundle(
+        &mut self,
+        bundle_id: [u8; 32],
+        tx_ids: &[[u8; 32]],
+    ) -> Result<()> {
+        // Map each Hash to the bundle Id, followed by the bundle tx hashes
+        let mut bundle_data = bundle_id.to_vec();
+        for tx_id in tx_ids {
+            bundle_data.extend_from_slice(tx_id);
+        }
+
+        for tx_id in tx_ids {
+            self.put_cf(self.bundles_cf, tx_id, &bundle_data)?;
+        }
+
+        Ok(())
+    }
+
+    fn mempool_bundle(
+        &self,
+        tx_id: [u8; 32],
+    ) -> Result<Option<(BundlePosition, Vec<[u8; 32]>)>> {
+        let Some(data) = self.get_cf(self.bundles_cf, tx_id)? else {
+            return Ok(None);
+        };
+
+        let mut chunks = data.chunks_exact(32);
+        let id = chunks
+            .next()
+            .map(into_array)
+            .ok_or_else(|| anyhow::anyhow!("invalid bundle data"))?;
+        let bundle_txs: Vec<[u8; 32]> = chunks.map(into_array).collect();
+
+        let index = bundle_txs
+            .iter()
+            .position(|id| *id == tx_id)
+            .ok_or_else(|| anyhow::anyhow!("tx missing from its bundle"))?;
+        let position = BundlePosition {
+            id,
+            index: index as u32,
+            len: bundle_txs.len() as u32,
+        };
+
+        Ok(Some((position, bundle_txs)))
+    }
+
     fn mempool_txs_by_spendable_ids(
         &self,
         n: &[SpendingId],
     ) -> HashSet<[u8; 32]> {
         n.iter()
             .filter_map(|n| {
-                match self.inner.get_cf(self.spending_id_cf, n.to_bytes()) {
+                match self.get_cf(self.spending_id_cf, n.to_bytes()) {
                     Ok(Some(tx_id)) => tx_id.try_into().ok(),
                     _ => None,
                 }
@@ -971,18 +1543,88 @@ impl<'db, DB: DBAccess> Mempool for DBTransaction<'db, DB> {
             .collect()
     }
 
+    fn mempool_replacement(
+        &self,
+        tx: &Transaction,
+        min_bump_percent: u64,
+    ) -> Result<Replacement> {
+        let mut replaced = vec![];
+        let mut min_gas_price = 0;
+        for m_tx_id in self.mempool_txs_by_spendable_ids(&tx.to_spend_ids()) {
+            if let Some(m_tx) = self.mempool_tx(m_tx_id)? {
+                min_gas_price = min_gas_price.max(min_replacement_gas_price(
+                    m_tx.gas_price(),
+                    min_bump_percent,
+                ));
+                replaced.push(m_tx_id);
+            }
+        }
+
+        if replaced.is_empty() {
+            Ok(Replacement::None)
+        } else if tx.gas_price() < min_gas_price {
+            Ok(Replacement::Underpriced { min_gas_price })
+        } else {
+            replaced.sort();
+            Ok(Replacement::Replaces(replaced))
+        }
+    }
+
     fn mempool_txs_sorted_by_fee(
         &self,
     ) -> Result<Box<dyn Iterator<Item = Transaction> + '_>> {
-        let iter = MemPoolIterator::new(&self.inner, self.fees_cf, self);
+        let iter =
+            MemPoolIterator::new(self.raw_iterator_cf(self.fees_cf), self);
 
         Ok(Box::new(iter))
     }
 
+    fn txs_sorted_by_fee(
+        &self,
+        limit: usize,
+        max_gas: u64,
+    ) -> Result<Box<dyn Iterator<Item = Transaction> + '_>> {
+        let mut iter = self.raw_iterator_cf(self.priority_cf);
+        iter.seek_to_last();
+
+        // Iterate all keys from the end in reverse lexicographic order
+        let ids = std::iter::from_fn(move || {
+            let (_, _, tx_id) =
+                deserialize_priority_key(&mut iter.key()?).ok()?;
+            iter.prev();
+            Some(tx_id)
+        });
+        let txs = self.bundled_mempool_txs(ids, max_gas).take(limit);
+
+        Ok(Box::new(txs))
+    }
+
+    fn txs_in_canonical_order(
+        &self,
+        max_gas: u64,
+    ) -> Result<Box<dyn Iterator<Item = Transaction> + '_>> {
+        let mut iter = self.raw_iterator_cf(self.priority_cf);
+        iter.seek_to_first();
+
+        let mut txs = vec![];
+        while let Some(key) = iter.key() {
+            let (_, _, tx_id) = deserialize_priority_key(&mut &key[..])?;
+            if let Some(tx) = self.mempool_tx(tx_id)? {
+                if tx.inner.gas_limit() <= max_gas {
+                    txs.push(tx);
+                }
+            }
+            iter.next();
+        }
+
+        Ok(Box::new(canonical_order(txs).into_iter()))
+    }
+
     fn mempool_txs_ids_sorted_by_fee(
         &self,
     ) -> Result<Box<dyn Iterator<Item = (u64, [u8; 32])> + '_>> {
-        let iter = MemPoolFeeIterator::new(&self.inner, self.fees_cf, true);
+        let iter =
+            MemPoolFeeIterator::new(self.raw_iterator_cf(self.fees_cf), true);
 
         Ok(Box::new(iter))
     }
@@ -990,14 +1632,15 @@ impl<'db, DB: DBAccess> Mempool for DBTransaction<'db, DB> {
     fn mempool_txs_ids_sorted_by_low_fee(
         &self,
     ) -> Result<Box<dyn Iterator<Item = (u64, [u8; 32])> + '_>> {
-        let iter = MemPoolFeeIterator::new(&self.inner, self.fees_cf, false);
+        let iter =
+            MemPoolFeeIterator::new(self.raw_iterator_cf(self.fees_cf), false);
 
         Ok(Box::new(iter))
     }
 
     /// Get all expired transactions hashes.
     fn mempool_expired_txs(&self, timestamp: u64) -> Result<Vec<[u8; 32]>> {
-        let mut iter = self.inner.raw_iterator_cf(self.fees_cf);
+        let mut iter = self.raw_iterator_cf(self.fees_cf);
         iter.seek_to_first();
         let mut txs_list = vec![];
 
@@ -1034,7 +1677,7 @@ impl<'db, DB: DBAccess> Mempool for DBTransaction<'db, DB> {
     }
 
     fn mempool_txs_ids(&self) -> Result<Vec<[u8; 32]>> {
-        let mut iter = self.inner.raw_iterator_cf(self.fees_cf);
+        let mut iter = self.raw_iterator_cf(self.fees_cf);
         iter.seek_to_last();
 
         let mut txs_list = vec![];
@@ -1054,10 +1697,16 @@ impl<'db, DB: DBAccess> Mempool for DBTransaction<'db, DB> {
     }
 
     fn mempool_txs_count(&self) -> usize {
-        self.inner
-            .iterator_cf(self.mempool_cf, IteratorMode::Start)
+        self.iterator_cf(self.mempool_cf, IteratorMode::Start)
             .count()
     }
+
+    fn mempool_txs_sizes(&self) -> HashMap<[u8; 32], usize> {
+        self.iterator_cf(self.mempool_cf, IteratorMode::Start)
+            .filter_map(|entry| entry.ok())
+            .filter_map(|(id, tx)| Some((id[..].try_into().ok()?, tx.len())))
+            .collect()
+    }
 }
 
 pub struct MemPoolIterator<'db, DB: DBAccess, M: Mempool> {
@@ -1067,11 +1716,10 @@ pub struct MemPoolIterator<'db, DB: DBAccess, M: Mempool> {
 
 impl<'db, DB: DBAccess, M: Mempool> MemPoolIterator<'db, DB, M> {
     fn new(
-        db: &'db rocksdb::Transaction<DB>,
-        fees_cf: &ColumnFamily,
+        iter: DBRawIteratorWithThreadMode<'db, rocksdb::Transaction<'db, DB>>,
         mempool: &'db M,
     ) -> Self {
-        let iter = MemPoolFeeIterator::new(db, fees_cf, true);
+        let iter = MemPoolFeeIterator::new(iter, true);
         MemPoolIterator { iter, mempool }
     }
 }
@@ -1092,11 +1740,12 @@ pub struct MemPoolFeeIterator<'db, DB: DBAccess> {
 
 impl<'db, DB: DBAccess> MemPoolFeeIterator<'db, DB> {
     fn new(
-        db: &'db rocksdb::Transaction<DB>,
-        fees_cf: &ColumnFamily,
+        mut iter: DBRawIteratorWithThreadMode<
+            'db,
+            rocksdb::Transaction<'db, DB>,
+        >,
         fee_desc: bool,
     ) -> Self {
-        let mut iter = db.raw_iterator_cf(fees_cf);
         if fee_desc {
             iter.seek_to_last();
         };
@@ -1130,11 +1779,10 @@ impl<DB: DBAccess> Iterator for MemPoolFeeIterator<'_, DB> {
 impl<'db, DB: DBAccess> std::fmt::Debug for DBTransaction<'db, DB> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         //  Print ledger blocks
-        let iter = self.inner.iterator_cf(self.ledger_cf, IteratorMode::Start);
+        let iter = self.iterator_cf(self.ledger_cf, IteratorMode::Start);
 
         iter.map(Result::unwrap).try_for_each(|(hash, _)| {
-            if let Ok(Some(blob)) = self.inner.get_cf(self.ledger_cf, &hash[..])
-            {
+            if let Ok(Some(blob)) = self.get_cf(self.ledger_cf, &hash[..]) {
                 let b = Block::read(&mut &blob[..]).unwrap_or_default();
                 writeln!(f, "ledger_block [{}]: {:#?}", b.header().height, b)
             } else {
@@ -1143,14 +1791,12 @@ impl<'db, DB: DBAccess> std::fmt::Debug for DBTransaction<'db, DB> {
         })?;
 
         // Print candidate blocks
-        let iter = self
-            .inner
-            .iterator_cf(self.candidates_cf, IteratorMode::Start);
+        let iter = self.iterator_cf(self.candidates_cf, IteratorMode::Start);
 
         let results: std::fmt::Result =
             iter.map(Result::unwrap).try_for_each(|(hash, _)| {
                 if let Ok(Some(blob)) =
-                    self.inner.get_cf(self.candidates_cf, &hash[..])
+                    self.get_cf(self.candidates_cf, &hash[..])
                 {
                     let b = Block::read(&mut &blob[..]).unwrap_or_default();
                     writeln!(
@@ -1175,7 +1821,7 @@ impl<'db, DB: DBAccess> Metadata for DBTransaction<'db, DB> {
     }
 
     fn op_read(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
-        self.inner.get_cf(self.metadata_cf, key).map_err(Into::into)
+        self.get_cf(self.metadata_cf, key).map_err(Into::into)
     }
 }
 
@@ -1194,19 +1840,146 @@ impl<'db, DB: DBAccess> DBTransaction<'db, DB> {
         Ok(())
     }
 
-    pub fn get_size(&self) -> usize {
-        *self.cumulative_inner_size.borrow()
+    /// Returns the options of the reads, served from the snapshot of the
+    /// transaction, if any
+    fn read_opts(&self) -> ReadOptions {
+        let mut opts = ReadOptions::default();
+        if let Some(snapshot) = self.snapshot {
+            opts.set_snapshot(snapshot);
+        }
+        opts
     }
-}
 
-fn serialize_key(value: u64, hash: [u8; 32]) -> std::io::Result<Vec<u8>> {
-    let mut w = vec![];
-    std::io::Write::write_all(&mut w, &value.to_be_bytes())?;
-    std::io::Write::write_all(&mut w, &hash)?;
-    Ok(w)
-}
-
-fn deserialize_key<R: Read>(r: &mut R) -> Result<(u64, [u8; 32])> {
+    /// A thin wrapper around inner.get_cf reading from the snapshot of the
+    /// transaction, if any
+    fn get_cf<K: AsRef<[u8]>>(
+        &self,
+        cf: &impl AsColumnFamilyRef,
+        key: K,
+    ) -> std::result::Result<Option<Vec<u8>>, rocksdb::Error> {
+        self.inner.get_cf_opt(cf, key, &self.read_opts())
+    }
+
+    /// A thin wrapper around inner.multi_get_cf reading from the snapshot of
+    /// the transaction, if any
+    fn multi_get_cf<'a, 'b: 'a, K, I, W>(
+        &'a self,
+        keys: I,
+    ) -> Vec<std::result::Result<Option<Vec<u8>>, rocksdb::Error>>
+    where
+        K: AsRef<[u8]>,
+        I: IntoIterator<Item = (&'b W, K)>,
+        W: 'b + AsColumnFamilyRef,
+    {
+        self.inner.multi_get_cf_opt(keys, &self.read_opts())
+    }
+
+    /// A thin wrapper around inner.iterator_cf reading from the snapshot of
+    /// the transaction, if any
+    fn iterator_cf(
+        &self,
+        cf: &impl AsColumnFamilyRef,
+        mode: IteratorMode,
+    ) -> DBIteratorWithThreadMode<'_, rocksdb::Transaction<'db, DB>> {
+        self.inner.iterator_cf_opt(cf, self.read_opts(), mode)
+    }
+
+    /// A thin wrapper around inner.raw_iterator_cf reading from the snapshot
+    /// of the transaction, if any
+    fn raw_iterator_cf(
+        &self,
+        cf: &impl AsColumnFamilyRef,
+    ) -> DBRawIteratorWithThreadMode<'_, rocksdb::Transaction<'db, DB>> {
+        self.inner.raw_iterator_cf_opt(cf, self.read_opts())
+    }
+
+    pub fn get_size(&self) -> usize {
+        *self.cumulative_inner_size.borrow()
+    }
+
+    /// Fetches the mempool transactions of `ids` with a gas limit of at most
+    /// `max_gas`.
+    ///
+    /// The transactions of a bundle are yielded all together, in order,
+    /// where the first of them is found.
+    fn bundled_mempool_txs<'a>(
+        &'a self,
+        ids: impl Iterator<Item = [u8; 32]> + 'a,
+        max_gas: u64,
+    ) -> impl Iterator<Item = Transaction> + 'a {
+        let mut yielded = HashSet::new();
+        ids.flat_map(move |tx_id| {
+            let tx_ids = match self.mempool_bundle(tx_id) {
+                Ok(Some((_, bundle_txs))) => bundle_txs,
+                _ => vec![tx_id],
+            };
+            tx_ids
+                .into_iter()
+                .filter(|tx_id| yielded.insert(*tx_id))
+                .collect::<Vec<_>>()
+        })
+        .filter_map(move |tx_id| self.mempool_tx(tx_id).ok().flatten())
+        .filter(move |tx| tx.inner.gas_limit() <= max_gas)
+    }
+
+    /// Deletes the gas spent per contract by the block at `height`
+    fn delete_contract_gas(&self, height: u64) -> Result<()> {
+        let prefix = height.to_be_bytes();
+        let iter = self.iterator_cf(
+            self.ledger_contract_gas_cf,
+            IteratorMode::From(&prefix, Direction::Forward),
+        );
+
+        for (key, _) in iter.map(Result::unwrap) {
+            if !key.starts_with(&prefix) {
+                break;
+            }
+            self.inner.delete_cf(self.ledger_contract_gas_cf, key)?;
+        }
+        Ok(())
+    }
+
+    /// Fails if the events of the block at `height` are not indexed
+    fn check_events_indexed(&self, height: u64) -> Result<()> {
+        let indexed_from = self.events_indexed_from()?;
+        if height < indexed_from {
+            anyhow::bail!("Events below height {indexed_from} are not indexed");
+        }
+        Ok(())
+    }
+
+    /// Deletes the contract events emitted by the block at `height`
+    fn delete_events(&self, height: u64) -> Result<()> {
+        let prefix = height.to_be_bytes();
+        let iter = self.iterator_cf(
+            self.ledger_events_cf,
+            IteratorMode::From(&prefix, Direction::Forward),
+        );
+
+        for (key, value) in iter.map(Result::unwrap) {
+            if !key.starts_with(&prefix) {
+                break;
+            }
+            let event = LedgerEvent::read(&mut &value[..])?;
+            let contract = event.event.event.target.as_bytes();
+            self.inner.delete_cf(
+                self.ledger_contract_events_cf,
+                [contract, &key[..]].concat(),
+            )?;
+            self.inner.delete_cf(self.ledger_events_cf, key)?;
+        }
+        Ok(())
+    }
+}
+
+fn serialize_key(value: u64, hash: [u8; 32]) -> std::io::Result<Vec<u8>> {
+    let mut w = vec![];
+    std::io::Write::write_all(&mut w, &value.to_be_bytes())?;
+    std::io::Write::write_all(&mut w, &hash)?;
+    Ok(w)
+}
+
+fn deserialize_key<R: Read>(r: &mut R) -> Result<(u64, [u8; 32])> {
     let mut buf = [0u8; 8];
     r.read_exact(&mut buf)?;
     let value = u64::from_be_bytes(buf);
@@ -1216,6 +1989,34 @@ fn deserialize_key<R: Read>(r: &mut R) -> Result<(u64, [u8; 32])> {
     Ok((value, hash))
 }
 
+/// The timestamp is inverted, for the older transactions to come last in
+/// lexicographic order among the ones of the same fee
+fn serialize_priority_key(
+    fee: u64,
+    timestamp: u64,
+    hash: [u8; 32],
+) -> std::io::Result<Vec<u8>> {
+    let mut w = vec![];
+    std::io::Write::write_all(&mut w, &fee.to_be_bytes())?;
+    std::io::Write::write_all(&mut w, &(u64::MAX - timestamp).to_be_bytes())?;
+    std::io::Write::write_all(&mut w, &hash)?;
+    Ok(w)
+}
+
+fn deserialize_priority_key<R: Read>(
+    r: &mut R,
+) -> Result<(u64, u64, [u8; 32])> {
+    let mut buf = [0u8; 8];
+    r.read_exact(&mut buf)?;
+    let fee = u64::from_be_bytes(buf);
+    r.read_exact(&mut buf)?;
+    let timestamp = u64::MAX - u64::from_be_bytes(buf);
+    let mut hash = [0u8; 32];
+    r.read_exact(&mut hash[..])?;
+
+    Ok((fee, timestamp, hash))
+}
+
 fn serialize_iter_key(ch: &ConsensusHeader) -> std::io::Result<Vec<u8>> {
     let mut w = vec![];
     std::io::Write::write_all(&mut w, &ch.prev_block_hash)?;
@@ -1223,6 +2024,16 @@ fn serialize_iter_key(ch: &ConsensusHeader) -> std::io::Result<Vec<u8>> {
     Ok(w)
 }
 
+/// Returns the key of a candidate in the `CF_CANDIDATES_ITERATION` index
+fn candidate_iteration_key(header: &Header) -> Vec<u8> {
+    [
+        &header.prev_block_hash[..],
+        &[header.iteration],
+        &header.hash[..],
+    ]
+    .concat()
+}
+
 fn deserialize_iter_key<R: Read>(r: &mut R) -> Result<([u8; 32], u8)> {
     let mut prev_block_hash = [0u8; 32];
     r.read_exact(&mut prev_block_hash)?;
@@ -1299,12 +2110,49 @@ impl node_data::Serializable for LightBlock {
     }
 }
 
+impl node_data::Serializable for LedgerEvent {
+    fn write<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        w.write_all(&self.height.to_le_bytes())?;
+        w.write_all(&self.event.origin)?;
+        w.write_all(self.event.event.target.as_bytes())?;
+        Self::write_var_le_bytes32(w, self.event.event.topic.as_bytes())?;
+        Self::write_var_le_bytes32(w, &self.event.event.data)?;
+
+        Ok(())
+    }
+
+    fn read<R: Read>(r: &mut R) -> io::Result<Self>
+    where
+        Self: Sized,
+    {
+        let height = Self::read_u64_le(r)?;
+        let origin = Self::read_bytes(r)?;
+        let target = ContractId::from_bytes(Self::read_bytes(r)?);
+        let topic = String::from_utf8(Self::read_var_le_bytes32(r)?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let data = Self::read_var_le_bytes32(r)?;
+
+        Ok(Self {
+            height,
+            event: ContractTxEvent {
+                event: ContractEvent {
+                    target,
+                    topic,
+                    data,
+                },
+                origin,
+            },
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use fake::{Fake, Faker};
-    use node_data::ledger;
+    use node_data::{ledger, StepName};
 
     use super::*;
+    use crate::database::round_history::StepOutcome;
 
     #[test]
     fn test_store_block() {
@@ -1439,6 +2287,55 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_pinned_view() {
+        TestWrapper::new("test_pinned_view").run(|path| {
+            let db = Backend::create_or_open(path, DatabaseOptions::default());
+            let b: Block = Faker.fake();
+            let hash = b.header().hash;
+
+            let pinned = db.pinned();
+            db.update(|txn| {
+                txn.store_block(
+                    b.header(),
+                    &to_spent_txs(b.txs()),
+                    b.faults(),
+                    Label::Final(3),
+                )?;
+                Ok(())
+            })
+            .expect("block to be stored");
+
+            // The views of the pinned backend ignore the writes committed
+            // after it was pinned
+            pinned.view(|txn| {
+                assert!(txn
+                    .block(&hash)
+                    .expect("block to be fetched")
+                    .is_none());
+                assert!(txn.op_read(MD_HASH_KEY).unwrap().is_none());
+                assert!(!txn.block_exists(&hash).unwrap());
+            });
+
+            // A pinned backend can't be updated
+            assert!(pinned
+                .update(|txn| txn.op_write(MD_HASH_KEY, [0; 32]))
+                .is_err());
+
+            // A backend pinned after the writes reads them
+            db.pinned().view(|txn| {
+                assert!(txn
+                    .block(&hash)
+                    .expect("block to be fetched")
+                    .is_some());
+                assert_eq!(
+                    txn.op_read(MD_HASH_KEY).unwrap(),
+                    Some(hash.to_vec())
+                );
+            });
+        });
+    }
+
     fn assert_blocks_eq(a: &Block, b: &Block) {
         assert!(a.header().hash != [0u8; 32]);
         assert!(a.header().hash.eq(&b.header().hash));
@@ -1478,6 +2375,42 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_mempool_replacement() {
+        TestWrapper::new("test_mempool_replacement").run(|path| {
+            let db = Backend::create_or_open(path, DatabaseOptions::default());
+            // Dummy transactions spend the same nullifiers
+            let t = ledger::faker::gen_dummy_tx(100);
+
+            db.view(|txn| {
+                let replacement = txn.mempool_replacement(&t, 10).unwrap();
+                assert_eq!(replacement, Replacement::None);
+            });
+
+            db.update(|txn| txn.store_mempool_tx(&t, 0)).unwrap();
+
+            db.view(|txn| {
+                let bumped = ledger::faker::gen_dummy_tx(109);
+                assert_eq!(
+                    txn.mempool_replacement(&bumped, 10).unwrap(),
+                    Replacement::Underpriced { min_gas_price: 110 }
+                );
+
+                let bumped = ledger::faker::gen_dummy_tx(110);
+                assert_eq!(
+                    txn.mempool_replacement(&bumped, 10).unwrap(),
+                    Replacement::Replaces(vec![t.id()])
+                );
+
+                // The gas price must be strictly higher, whatever the bump
+                assert_eq!(
+                    txn.mempool_replacement(&t, 0).unwrap(),
+                    Replacement::Underpriced { min_gas_price: 101 }
+                );
+            });
+        });
+    }
+
     #[test]
     fn test_mempool_txs_sorted_by_fee() {
         TestWrapper::new("test_mempool_txs_sorted_by_fee").run(|path| {
@@ -1512,6 +2445,112 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_txs_sorted_by_fee() {
+        TestWrapper::new("test_txs_sorted_by_fee").run(|path| {
+            let db = Backend::create_or_open(path, DatabaseOptions::default());
+            let txs: Vec<_> = [3, 1, 4, 2, 5]
+                .into_iter()
+                .map(ledger::faker::gen_dummy_tx)
+                .collect();
+            db.update(|txn| {
+                for (i, t) in txs.iter().enumerate() {
+                    txn.store_mempool_tx(t, i as u64)?;
+                }
+                txn.delete_mempool_tx(txs[1].id(), false)?;
+                Ok(())
+            })
+            .unwrap();
+
+            db.view(|txn| {
+                let fees: Vec<_> = txn
+                    .txs_sorted_by_fee(usize::MAX, u64::MAX)
+                    .unwrap()
+                    .map(|t| t.gas_price())
+                    .collect();
+                assert_eq!(fees, [5, 4, 3, 2]);
+
+                let fees: Vec<_> = txn
+                    .txs_sorted_by_fee(2, u64::MAX)
+                    .unwrap()
+                    .map(|t| t.gas_price())
+                    .collect();
+                assert_eq!(fees, [5, 4]);
+
+                // The dummy transactions have a gas limit of 1
+                assert_eq!(
+                    txn.txs_sorted_by_fee(usize::MAX, 0).unwrap().count(),
+                    0
+                );
+
+                let canonical: Vec<_> =
+                    txn.txs_in_canonical_order(u64::MAX).unwrap().collect();
+                assert_eq!(canonical.len(), 4);
+                assert!(ledger::is_canonically_ordered(&canonical));
+                assert_eq!(txn.txs_in_canonical_order(0).unwrap().count(), 0);
+            });
+        });
+
+        // The oldest transaction comes last among the ones of the same fee,
+        // since the keys are iterated in reverse order
+        let older = serialize_priority_key(5, 1, [9; 32]).unwrap();
+        let newer = serialize_priority_key(5, 2, [0; 32]).unwrap();
+        let higher = serialize_priority_key(6, 9, [0; 32]).unwrap();
+        assert!(newer < older && older < higher);
+        assert_eq!(
+            deserialize_priority_key(&mut &older[..]).unwrap(),
+            (5, 1, [9; 32])
+        );
+    }
+
+    #[test]
+    fn test_mempool_bundles() {
+        TestWrapper::new("test_mempool_bundles").run(|path| {
+            let db = Backend::create_or_open(path, DatabaseOptions::default());
+            let txs: Vec<_> = [3, 1, 4, 2, 5]
+                .into_iter()
+                .map(ledger::faker::gen_dummy_tx)
+                .collect();
+            let bundle = [txs[1].id(), txs[2].id()];
+            db.update(|txn| {
+                for (i, t) in txs.iter().enumerate() {
+                    txn.store_mempool_tx(t, i as u64)?;
+                }
+                txn.store_mempool_bundle([7; 32], &bundle)
+            })
+            .unwrap();
+
+            db.view(|txn| {
+                // The bundle is yielded where its highest fee tx is found
+                let fees: Vec<_> = txn
+                    .txs_sorted_by_fee(usize::MAX, u64::MAX)
+                    .unwrap()
+                    .map(|t| t.gas_price())
+                    .collect();
+                assert_eq!(fees, [5, 1, 4, 3, 2]);
+
+                let tx = txn.mempool_tx(txs[2].id()).unwrap().unwrap();
+                let position = tx.bundle().expect("tx to be bundled");
+                assert_eq!((position.id, position.index), ([7; 32], 1));
+                assert!(position.is_last());
+                assert!(txn.mempool_bundle(txs[0].id()).unwrap().is_none());
+            });
+
+            // Deleting a bundled tx deletes the whole bundle
+            db.update(|txn| {
+                let deleted = txn.delete_mempool_tx(txs[2].id(), true)?;
+                assert_eq!(deleted, [txs[2].id(), txs[1].id()]);
+                Ok(())
+            })
+            .unwrap();
+
+            db.view(|txn| {
+                assert!(txn.mempool_bundle(txs[1].id()).unwrap().is_none());
+                assert_eq!(txn.mempool_txs_count(), 3);
+            });
+        });
+    }
+
     #[test]
     fn test_txs_count() {
         TestWrapper::new("test_txs_count").run(|path| {
@@ -1557,6 +2596,32 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_txs_size() {
+        TestWrapper::new("test_txs_size").run(|path| {
+            let db = Backend::create_or_open(path, DatabaseOptions::default());
+
+            let txs: Vec<_> =
+                (0..10).map(|i| ledger::faker::gen_dummy_tx(i)).collect();
+            let sizes = |txs: &[Transaction]| -> HashMap<_, _> {
+                txs.iter().map(|t| (t.id(), t.size().unwrap())).collect()
+            };
+
+            db.update(|db| {
+                assert!(db.mempool_txs_sizes().is_empty());
+                txs.iter().for_each(|t| {
+                    db.store_mempool_tx(&t, 0).expect("tx should be added")
+                });
+                assert_eq!(db.mempool_txs_sizes(), sizes(&txs));
+
+                db.delete_mempool_tx(txs[0].id(), false)?;
+                assert_eq!(db.mempool_txs_sizes(), sizes(&txs[1..]));
+                Ok(())
+            })
+            .unwrap();
+        });
+    }
+
     #[test]
     fn test_max_gas_limit() {
         TestWrapper::new("test_block_size_limit").run(|path| {
@@ -1620,12 +2685,7 @@ mod tests {
 
     fn to_spent_txs(txs: &Vec<Transaction>) -> Vec<SpentTransaction> {
         txs.iter()
-            .map(|t| SpentTransaction {
-                inner: t.clone(),
-                block_height: 0,
-                gas_spent: 0,
-                err: None,
-            })
+            .map(|t| SpentTransaction::new(t.clone(), 0, 0, None))
             .collect()
     }
 
@@ -1659,7 +2719,16 @@ mod tests {
                         .expect("should find a transaction")
                         .inner
                         .eq(t));
+
+                    // The nullifiers it spends are indexed
+                    for n in ledger_nullifiers(t) {
+                        assert_eq!(
+                            v.ledger_tx_by_nullifier(&n).unwrap(),
+                            Some(t.id())
+                        );
+                    }
                 }
+                assert_eq!(v.ledger_tx_by_nullifier(&[0; 32]).unwrap(), None);
             });
         });
     }
@@ -1694,6 +2763,120 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_epoch_root() {
+        TestWrapper::new("test_epoch_root").run(|path| {
+            let db = Backend::create_or_open(path, DatabaseOptions::default());
+
+            db.update(|txn| txn.store_epoch_root(3, &[7; 32])).unwrap();
+
+            db.view(|v| {
+                assert_eq!(v.epoch_root(3).unwrap(), Some([7; 32]));
+                assert_eq!(v.epoch_root(4).unwrap(), None);
+            });
+        });
+    }
+
+    #[test]
+    fn test_events() {
+        TestWrapper::new("test_events").run(|path| {
+            let db = Backend::create_or_open(path, DatabaseOptions::default());
+
+            let event = |contract: u8, topic: &str| ContractTxEvent {
+                event: ContractEvent {
+                    target: ContractId::from_bytes([contract; 32]),
+                    topic: topic.to_string(),
+                    data: vec![contract; 3],
+                },
+                origin: [9; 32],
+            };
+            let a = ContractId::from_bytes([1; 32]);
+            let b = ContractId::from_bytes([2; 32]);
+
+            db.update(|txn| {
+                txn.store_events(10, &[event(1, "x"), event(2, "y")])?;
+                txn.store_events(11, &[event(2, "z"), event(1, "w")])?;
+                txn.store_events(12, &[event(1, "v")])
+            })
+            .unwrap();
+
+            let topics = |events: Vec<LedgerEvent>| {
+                events
+                    .into_iter()
+                    .map(|e| format!("{}:{}", e.height, e.event.event.topic))
+                    .collect::<Vec<_>>()
+            };
+
+            db.view(|v| {
+                let events = v.events_by_height_range(11, 12).unwrap();
+                assert_eq!(topics(events), ["11:z", "11:w", "12:v"]);
+
+                let events = v.events_by_contract(&a, 0, 11).unwrap();
+                assert_eq!(topics(events), ["10:x", "11:w"]);
+                assert_eq!(
+                    v.events_by_contract(&a, 12, 20).unwrap()[0].event,
+                    event(1, "v")
+                );
+            });
+
+            // The events of the blocks accepted before the index are missing
+            db.update(|txn| {
+                txn.op_write(MD_EVENTS_INDEXED_FROM, 11u64.to_le_bytes())
+            })
+            .unwrap();
+            db.view(|v| {
+                assert_eq!(v.events_indexed_from().unwrap(), 11);
+                assert!(v.events_by_height_range(10, 12).is_err());
+                assert!(v.events_by_contract(&a, 10, 12).is_err());
+                assert_eq!(v.events_by_contract(&a, 11, 12).unwrap().len(), 2);
+            });
+
+            // Deleting a block deletes its events from both column families
+            db.update(|txn| {
+                txn.op_write(MD_EVENTS_INDEXED_FROM, 0u64.to_le_bytes())?;
+                txn.delete_events(11)
+            })
+            .unwrap();
+            db.view(|v| {
+                let events = v.events_by_height_range(0, 20).unwrap();
+                assert_eq!(topics(events), ["10:x", "10:y", "12:v"]);
+
+                let events = v.events_by_contract(&b, 0, 20).unwrap();
+                assert_eq!(topics(events), ["10:y"]);
+            });
+        });
+    }
+
+    #[test]
+    fn test_contract_gas() {
+        TestWrapper::new("test_contract_gas").run(|path| {
+            let db = Backend::create_or_open(path, DatabaseOptions::default());
+            let a = ContractId::from_bytes([1; 32]);
+            let b = ContractId::from_bytes([2; 32]);
+
+            db.update(|txn| {
+                txn.store_contract_gas(10, &[(a, 10), (b, 3)])?;
+                txn.store_contract_gas(11, &[(b, 5)])
+            })
+            .unwrap();
+
+            db.view(|v| {
+                assert_eq!(
+                    v.gas_by_contract(10, 11).unwrap(),
+                    [(a, 10), (b, 8)]
+                );
+                assert_eq!(v.gas_by_contract(11, 20).unwrap(), [(b, 5)]);
+                assert!(v.gas_by_contract(12, 20).unwrap().is_empty());
+            });
+
+            // Deleting a block deletes its gas records
+            db.update(|txn| txn.delete_contract_gas(10)).unwrap();
+            db.view(|v| {
+                assert_eq!(v.gas_by_contract(0, 20).unwrap(), [(b, 5)]);
+            });
+        });
+    }
+
     #[test]
     fn test_fetch_block_label_by_height() {
         TestWrapper::new("test_fetch_block_hash_by_height").run(|path| {
@@ -1725,6 +2908,111 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_round_history() {
+        TestWrapper::new("test_round_history").run(|path| {
+            let db = Backend::create_or_open(path, DatabaseOptions::default());
+
+            let record = |round, iteration, step| StepRecord {
+                header: ConsensusHeader {
+                    prev_block_hash: [1; 32],
+                    round,
+                    iteration,
+                },
+                step,
+                outcome: StepOutcome::Candidate([round as u8; 32]),
+            };
+
+            db.update(|txn| {
+                txn.store_step_record(&record(11, 1, StepName::Validation))?;
+                txn.store_step_record(&record(10, 0, StepName::Proposal))?;
+                txn.store_step_record(&record(11, 0, StepName::Proposal))?;
+                txn.store_step_record(&record(12, 0, StepName::Proposal))?;
+                Ok(())
+            })
+            .unwrap();
+
+            db.view(|txn| {
+                let history = txn.round_history(11).unwrap();
+                let steps: Vec<_> = history
+                    .iter()
+                    .map(|r| (r.header.iteration, r.step))
+                    .collect();
+                assert_eq!(
+                    steps,
+                    vec![(0, StepName::Proposal), (1, StepName::Validation)]
+                );
+                assert!(matches!(
+                    history[0].outcome,
+                    StepOutcome::Candidate(hash) if hash == [11; 32]
+                ));
+            });
+
+            db.update(|txn| txn.delete_round_history(12)).unwrap();
+
+            db.view(|txn| {
+                assert!(txn.round_history(10).unwrap().is_empty());
+                assert!(txn.round_history(11).unwrap().is_empty());
+                assert_eq!(txn.round_history(12).unwrap().len(), 1);
+            });
+        });
+    }
+
+    #[test]
+    fn test_last_stored_iteration() {
+        TestWrapper::new("test_last_stored_iteration").run(|path| {
+            let db = Backend::create_or_open(path, DatabaseOptions::default());
+
+            let candidate = |prev: u8, iteration: u8| {
+                let mut hash = [prev; 32];
+                hash[0] = iteration;
+                let header = ledger::Header {
+                    prev_block_hash: [prev; 32],
+                    iteration,
+                    hash,
+                    ..Default::default()
+                };
+                Block::new(header, vec![], vec![]).expect("valid block")
+            };
+            let ch = |prev: u8, iteration| ConsensusHeader {
+                prev_block_hash: [prev; 32],
+                round: 1,
+                iteration,
+            };
+
+            db.update(|txn| {
+                txn.store_candidate(candidate(1, 2))?;
+                txn.store_candidate(candidate(1, 0))?;
+                txn.store_candidate(candidate(2, 7))?;
+                txn.store_validation_result(&ch(1, 4), &Faker.fake())?;
+                Ok(())
+            })
+            .unwrap();
+
+            db.view(|txn| {
+                assert_eq!(
+                    txn.last_stored_iteration(&[1; 32]).unwrap(),
+                    Some(4)
+                );
+                assert_eq!(
+                    txn.last_stored_iteration(&[2; 32]).unwrap(),
+                    Some(7)
+                );
+                assert_eq!(txn.last_stored_iteration(&[3; 32]).unwrap(), None);
+            });
+
+            // Deleting the candidates removes them from the index
+            db.update(|txn| txn.delete_candidate(|_| true)).unwrap();
+            db.view(|txn| {
+                assert_eq!(
+                    txn.last_stored_iteration(&[1; 32]).unwrap(),
+                    Some(4)
+                );
+                assert_eq!(txn.last_stored_iteration(&[2; 32]).unwrap(), None);
+            });
+        });
+    }
+
     #[test]
     /// Ensures delete_block fn removes all keys of a single block
     fn test_delete_block() {
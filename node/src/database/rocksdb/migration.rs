@@ -0,0 +1,827 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Versioning of the database schema, and its migrations.
+//!
+//! The version of the schema is stored in the metadata column family. When
+//! the database is opened, the migrations to the versions above it are run
+//! in order, each one storing its version once done.
+//!
+//! The database is backed up with a checkpoint before the first migration.
+//! If any migration fails, the database is restored from the backup, which
+//! is removed once all migrations succeed. A backup left by a migration
+//! interrupted by a crash is restored before the database is opened again,
+//! see [`restore_interrupted`], so that the migrations always start over from
+//! the backed up version.
+//!
+//! A database without version is either new, and then marked with the latest
+//! version, or created before the versioning, i.e. at version 0.
+//!
+//! Changing the layout of the stored data, e.g. a new field of
+//! [`SpentTransaction`] or a new index, is done by registering a migration
+//! in [`MIGRATIONS`].
+//!
+//! [`SpentTransaction`]: node_data::ledger::SpentTransaction
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+use dusk_core::transfer::TRANSFER_CONTRACT;
+use node_data::ledger::{self, Header, Label, SpentTransaction};
+use node_data::Serializable;
+use rocksdb::checkpoint::Checkpoint;
+use rocksdb::{ColumnFamily, IteratorMode, OptimisticTransactionDB};
+use tracing::{info, warn};
+
+use super::{
+    candidate_iteration_key, contract_gas_key, deserialize_key,
+    ledger_nullifiers, serialize_priority_key, CF_CANDIDATES,
+    CF_CANDIDATES_ITERATION, CF_LEDGER_CONTRACT_GAS, CF_LEDGER_EPOCH_ROOTS,
+    CF_LEDGER_HEIGHT, CF_LEDGER_NULLIFIERS, CF_LEDGER_TXS, CF_MEMPOOL_FEES,
+    CF_MEMPOOL_PRIORITY, CF_METADATA, MD_EVENTS_INDEXED_FROM, MD_HASH_KEY,
+};
+
+/// Metadata key of the schema version
+const MD_SCHEMA_VERSION: &[u8] = b"schema_version";
+
+/// A migration of the database to a schema version
+#[derive(Clone, Copy)]
+pub(super) struct Migration {
+    /// Version the database is at after the migration
+    pub version: u32,
+    pub description: &'static str,
+    pub run: fn(&OptimisticTransactionDB) -> Result<()>,
+}
+
+/// Migrations of the database, in increasing order of version
+pub(super) const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "versioned schema",
+        run: |_| Ok(()),
+    },
+    Migration {
+        version: 2,
+        description: "ledger nullifiers index",
+        run: index_ledger_nullifiers,
+    },
+    Migration {
+        version: 3,
+        description: "ledger epoch roots",
+        run: store_epoch_roots,
+    },
+    Migration {
+        version: 4,
+        description: "mempool priority index",
+        run: index_mempool_priority,
+    },
+    Migration {
+        version: 5,
+        description: "ledger events index start",
+        run: store_events_indexed_from,
+    },
+    Migration {
+        version: 6,
+        description: "candidates iteration index",
+        run: index_candidates_iteration,
+    },
+    Migration {
+        version: 7,
+        description: "ledger contract gas",
+        run: store_contract_gas,
+    },
+];
+
+/// Returns the version of the latest schema
+fn latest_version(migrations: &[Migration]) -> u32 {
+    migrations.last().map_or(0, |m| m.version)
+}
+
+/// Returns the schema version stored in `db`, if any
+fn schema_version(db: &OptimisticTransactionDB) -> Result<Option<u32>> {
+    let cf = metadata_cf(db)?;
+    let Some(version) = db.get_cf(cf, MD_SCHEMA_VERSION)? else {
+        return Ok(None);
+    };
+    let version = version
+        .try_into()
+        .map_err(|_| anyhow!("invalid schema version"))?;
+    Ok(Some(u32::from_le_bytes(version)))
+}
+
+fn set_schema_version(
+    db: &OptimisticTransactionDB,
+    version: u32,
+) -> Result<()> {
+    let cf = metadata_cf(db)?;
+    db.put_cf(cf, MD_SCHEMA_VERSION, version.to_le_bytes())?;
+    Ok(())
+}
+
+fn metadata_cf(db: &OptimisticTransactionDB) -> Result<&ColumnFamily> {
+    db.cf_handle(CF_METADATA)
+        .ok_or_else(|| anyhow!("CF_METADATA column family must exist"))
+}
+
+/// Indexes the nullifiers spent by the transactions already in the ledger
+fn index_ledger_nullifiers(db: &OptimisticTransactionDB) -> Result<()> {
+    let txs_cf = db
+        .cf_handle(CF_LEDGER_TXS)
+        .ok_or_else(|| anyhow!("CF_LEDGER_TXS column family must exist"))?;
+    let nullifiers_cf =
+        db.cf_handle(CF_LEDGER_NULLIFIERS).ok_or_else(|| {
+            anyhow!("CF_LEDGER_NULLIFIERS column family must exist")
+        })?;
+
+    for entry in db.iterator_cf(txs_cf, IteratorMode::Start) {
+        let (tx_id, blob) = entry?;
+        let tx = SpentTransaction::read(&mut &blob[..])?;
+        for n in ledger_nullifiers(&tx.inner) {
+            db.put_cf(nullifiers_cf, n, &tx_id)?;
+        }
+    }
+    Ok(())
+}
+
+/// Returns the height of the highest block of the ledger, if any
+fn ledger_tip(
+    db: &OptimisticTransactionDB,
+    height_cf: &ColumnFamily,
+) -> Result<Option<u64>> {
+    // Heights are not keyed in order, the tip is the highest one
+    let mut tip = None;
+    for entry in db.iterator_cf(height_cf, IteratorMode::Start) {
+        let (height, _) = entry?;
+        let height = height
+            .as_ref()
+            .try_into()
+            .map_err(|_| anyhow!("invalid block height"))?;
+        tip = tip.max(Some(u64::from_le_bytes(height)));
+    }
+    Ok(tip)
+}
+
+/// Stores the roots of the epochs finalized before they were recorded.
+///
+/// As on acceptance, epochs partially missing from the ledger are skipped.
+fn store_epoch_roots(db: &OptimisticTransactionDB) -> Result<()> {
+    let height_cf = db
+        .cf_handle(CF_LEDGER_HEIGHT)
+        .ok_or_else(|| anyhow!("CF_LEDGER_HEIGHT column family must exist"))?;
+    let roots_cf = db.cf_handle(CF_LEDGER_EPOCH_ROOTS).ok_or_else(|| {
+        anyhow!("CF_LEDGER_EPOCH_ROOTS column family must exist")
+    })?;
+
+    let Some(tip) = ledger_tip(db, height_cf)? else {
+        return Ok(());
+    };
+
+    'epochs: for epoch in 0..ledger::epoch_of(tip + 1) {
+        let epoch_key = epoch.to_be_bytes();
+        if db.get_cf(roots_cf, epoch_key)?.is_some() {
+            continue;
+        }
+
+        let mut hashes = vec![];
+        let mut finalized = false;
+        for height in ledger::epoch_heights(epoch) {
+            let Some(value) = db.get_cf(height_cf, height.to_le_bytes())?
+            else {
+                continue 'epochs;
+            };
+            if value.len() < 32 {
+                return Err(anyhow!("invalid block at height {height}"));
+            }
+            let (hash, mut label) = value.split_at(32);
+            hashes.push(hash.try_into()?);
+            finalized = matches!(Label::read(&mut label)?, Label::Final(_));
+        }
+        if finalized {
+            db.put_cf(roots_cf, epoch_key, ledger::epoch_root(&hashes))?;
+        }
+    }
+    Ok(())
+}
+
+/// Indexes by priority the transactions already in the mempool
+fn index_mempool_priority(db: &OptimisticTransactionDB) -> Result<()> {
+    let fees_cf = db
+        .cf_handle(CF_MEMPOOL_FEES)
+        .ok_or_else(|| anyhow!("CF_MEMPOOL_FEES column family must exist"))?;
+    let priority_cf = db.cf_handle(CF_MEMPOOL_PRIORITY).ok_or_else(|| {
+        anyhow!("CF_MEMPOOL_PRIORITY column family must exist")
+    })?;
+
+    for entry in db.iterator_cf(fees_cf, IteratorMode::Start) {
+        let (key, timestamp) = entry?;
+        let (fee, hash) = deserialize_key(&mut &key[..])?;
+        let timestamp = timestamp
+            .as_ref()
+            .try_into()
+            .map_err(|_| anyhow!("invalid mempool tx timestamp"))?;
+        let timestamp = u64::from_be_bytes(timestamp);
+        db.put_cf(
+            priority_cf,
+            serialize_priority_key(fee, timestamp, hash)?,
+            [],
+        )?;
+    }
+    Ok(())
+}
+
+/// Stores the height from which the contract events are indexed.
+///
+/// The events of the blocks already in the ledger are not stored, hence can't
+/// be indexed: the index starts after the tip.
+fn store_events_indexed_from(db: &OptimisticTransactionDB) -> Result<()> {
+    let height_cf = db
+        .cf_handle(CF_LEDGER_HEIGHT)
+        .ok_or_else(|| anyhow!("CF_LEDGER_HEIGHT column family must exist"))?;
+
+    let Some(tip) = ledger_tip(db, height_cf)? else {
+        return Ok(());
+    };
+    let from = tip + 1;
+    db.put_cf(metadata_cf(db)?, MD_EVENTS_INDEXED_FROM, from.to_le_bytes())?;
+    Ok(())
+}
+
+/// Indexes by iteration the candidates already stored
+fn index_candidates_iteration(db: &OptimisticTransactionDB) -> Result<()> {
+    let candidates_cf = db
+        .cf_handle(CF_CANDIDATES)
+        .ok_or_else(|| anyhow!("CF_CANDIDATES column family must exist"))?;
+    let iteration_cf =
+        db.cf_handle(CF_CANDIDATES_ITERATION).ok_or_else(|| {
+            anyhow!("CF_CANDIDATES_ITERATION column family must exist")
+        })?;
+
+    for entry in db.iterator_cf(candidates_cf, IteratorMode::Start) {
+        let (_, blob) = entry?;
+        let header = Header::read(&mut &blob[..])?;
+        db.put_cf(iteration_cf, candidate_iteration_key(&header), [])?;
+    }
+    Ok(())
+}
+
+/// Stores the gas spent per contract by the blocks already in the ledger.
+///
+/// The nested calls of their transactions being unknown, the gas spent by
+/// each transaction is billed to the contract it calls, the transfer contract
+/// for the transactions not calling any. The blocks already indexed are
+/// skipped.
+fn store_contract_gas(db: &OptimisticTransactionDB) -> Result<()> {
+    let txs_cf = db
+        .cf_handle(CF_LEDGER_TXS)
+        .ok_or_else(|| anyhow!("CF_LEDGER_TXS column family must exist"))?;
+    let gas_cf = db.cf_handle(CF_LEDGER_CONTRACT_GAS).ok_or_else(|| {
+        anyhow!("CF_LEDGER_CONTRACT_GAS column family must exist")
+    })?;
+
+    let mut indexed = BTreeSet::new();
+    for entry in db.iterator_cf(gas_cf, IteratorMode::Start) {
+        let (key, _) = entry?;
+        let height = key
+            .get(..8)
+            .and_then(|height| height.try_into().ok())
+            .ok_or_else(|| anyhow!("invalid contract gas key"))?;
+        indexed.insert(u64::from_be_bytes(height));
+    }
+
+    for entry in db.iterator_cf(txs_cf, IteratorMode::Start) {
+        let (_, blob) = entry?;
+        let tx = SpentTransaction::read(&mut &blob[..])?;
+        if indexed.contains(&tx.block_height) {
+            continue;
+        }
+
+        let contract = tx
+            .inner
+            .inner
+            .call()
+            .map_or(TRANSFER_CONTRACT, |call| call.contract);
+        let key = contract_gas_key(tx.block_height, &contract);
+        let gas = match db.get_cf(gas_cf, key)? {
+            Some(gas) => u64::from_be_bytes(
+                gas.try_into()
+                    .map_err(|_| anyhow!("invalid contract gas"))?,
+            ),
+            None => 0,
+        };
+        db.put_cf(gas_cf, key, gas.saturating_add(tx.gas_spent).to_be_bytes())?;
+    }
+    Ok(())
+}
+
+/// Runs the `migrations` of the database `db`, stored in `path`, to the
+/// versions above its own.
+///
+/// On failure, the database is closed and restored from its backup.
+pub(super) fn migrate(
+    db: OptimisticTransactionDB,
+    path: &Path,
+    migrations: &[Migration],
+) -> Result<OptimisticTransactionDB> {
+    let latest = latest_version(migrations);
+
+    let version = match schema_version(&db)? {
+        Some(version) => version,
+        None if db.get_cf(metadata_cf(&db)?, MD_HASH_KEY)?.is_none() => {
+            set_schema_version(&db, latest)?;
+            return Ok(db);
+        }
+        None => 0,
+    };
+    if version > latest {
+        return Err(anyhow!(
+            "database schema v{version} is newer than the supported v{latest}"
+        ));
+    }
+    if version == latest {
+        return Ok(db);
+    }
+
+    // The checkpoint is only moved to the backup once complete, so that an
+    // interrupted checkpoint is never restored
+    let backup = backup_path(path);
+    let staging = staging_path(&backup);
+    if staging.exists() {
+        fs::remove_dir_all(&staging)?;
+    }
+    Checkpoint::new(&db)?
+        .create_checkpoint(&staging)
+        .context("cannot back the database up")?;
+    fs::rename(&staging, &backup).context("cannot back the database up")?;
+    info!(
+        event = "database backed up",
+        path = %backup.display(),
+        version,
+    );
+
+    for migration in migrations.iter().filter(|m| m.version > version) {
+        info!(
+            event = "migrating database",
+            version = migration.version,
+            description = migration.description,
+        );
+        let migrated = (migration.run)(&db)
+            .and_then(|_| set_schema_version(&db, migration.version));
+        if let Err(err) = migrated {
+            drop(db);
+            restore(path, &backup)?;
+            warn!(event = "database migration failed, restored", version);
+
+            let target = migration.version;
+            return Err(err.context(format!("migration to v{target} failed")));
+        }
+    }
+
+    fs::remove_dir_all(&backup)?;
+    info!(event = "database migrated", from = version, to = latest);
+    Ok(db)
+}
+
+/// Restores the database stored in `path` from the backup left by an
+/// interrupted migration, if any.
+///
+/// Must be called before the database is opened.
+pub(super) fn restore_interrupted(path: &Path) -> Result<()> {
+    let backup = backup_path(path);
+    let staging = staging_path(&backup);
+    if staging.exists() {
+        fs::remove_dir_all(&staging)?;
+    }
+    if backup.exists() {
+        restore(path, &backup)?;
+        warn!(
+            event = "interrupted database migration, restored",
+            path = %path.display(),
+        );
+    }
+    Ok(())
+}
+
+fn backup_path(path: &Path) -> PathBuf {
+    let mut backup = path.as_os_str().to_owned();
+    backup.push(".backup");
+    PathBuf::from(backup)
+}
+
+fn staging_path(backup: &Path) -> PathBuf {
+    let mut staging = backup.as_os_str().to_owned();
+    staging.push(".tmp");
+    PathBuf::from(staging)
+}
+
+/// Replaces the database stored in `path` with its `backup`.
+///
+/// The steps are idempotent, so that an interrupted restore can be resumed.
+fn restore(path: &Path, backup: &Path) -> Result<()> {
+    let mut failed = path.as_os_str().to_owned();
+    failed.push(".failed");
+    let failed = PathBuf::from(failed);
+
+    if failed.exists() {
+        fs::remove_dir_all(&failed)?;
+    }
+    if path.exists() {
+        fs::rename(path, &failed)?;
+    }
+    fs::rename(backup, path)?;
+    if failed.exists() {
+        fs::remove_dir_all(&failed)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use rocksdb::Options;
+
+    use super::*;
+
+    const MARKER: &[u8] = b"marker";
+
+    fn open(path: &Path) -> OptimisticTransactionDB {
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        opts.create_missing_column_families(true);
+        OptimisticTransactionDB::open_cf(&opts, path, [CF_METADATA])
+            .expect("database to open")
+    }
+
+    fn marked(db: &OptimisticTransactionDB) -> bool {
+        let cf = metadata_cf(db).unwrap();
+        db.get_cf(cf, MARKER).unwrap().is_some()
+    }
+
+    const MIGRATIONS: &[Migration] = &[
+        Migration {
+            version: 1,
+            description: "mark",
+            run: |db| {
+                db.put_cf(metadata_cf(db)?, MARKER, [1])?;
+                Ok(())
+            },
+        },
+        Migration {
+            version: 2,
+            description: "noop",
+            run: |_| Ok(()),
+        },
+    ];
+
+    const FAILING: &[Migration] = &[
+        MIGRATIONS[0],
+        Migration {
+            version: 2,
+            description: "fail",
+            run: |_| Err(anyhow!("failing migration")),
+        },
+    ];
+
+    #[test]
+    fn test_new_database() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("chain.db");
+
+        let db = migrate(open(&path), &path, MIGRATIONS).unwrap();
+        assert_eq!(schema_version(&db).unwrap(), Some(2));
+        assert!(!marked(&db));
+    }
+
+    #[test]
+    fn test_legacy_database() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("chain.db");
+
+        let db = open(&path);
+        db.put_cf(metadata_cf(&db).unwrap(), MD_HASH_KEY, [0; 32])
+            .unwrap();
+
+        let db = migrate(db, &path, MIGRATIONS).unwrap();
+        assert_eq!(schema_version(&db).unwrap(), Some(2));
+        assert!(marked(&db));
+        assert!(!backup_path(&path).exists());
+
+        // A migrated database is left untouched
+        let db = migrate(db, &path, MIGRATIONS).unwrap();
+        assert_eq!(schema_version(&db).unwrap(), Some(2));
+
+        // Older nodes can't open it
+        assert!(migrate(db, &path, &MIGRATIONS[..1]).is_err());
+    }
+
+    #[test]
+    fn test_index_ledger_nullifiers() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        opts.create_missing_column_families(true);
+        let db = OptimisticTransactionDB::open_cf(
+            &opts,
+            dir.path(),
+            [CF_LEDGER_TXS, CF_LEDGER_NULLIFIERS],
+        )
+        .unwrap();
+        let txs_cf = db.cf_handle(CF_LEDGER_TXS).unwrap();
+        let nullifiers_cf = db.cf_handle(CF_LEDGER_NULLIFIERS).unwrap();
+
+        let tx = SpentTransaction {
+            inner: ledger::faker::gen_dummy_tx(100),
+            block_height: 1,
+            gas_spent: 1,
+            err: None,
+            gas_refunded: 0,
+            fee_charged: 100,
+        };
+        let tx_id = tx.inner.id();
+        let mut blob = vec![];
+        tx.write(&mut blob).unwrap();
+        db.put_cf(txs_cf, tx_id, blob).unwrap();
+        assert!(db
+            .iterator_cf(nullifiers_cf, IteratorMode::Start)
+            .next()
+            .is_none());
+
+        index_ledger_nullifiers(&db).unwrap();
+
+        let nullifiers: Vec<_> = ledger_nullifiers(&tx.inner).collect();
+        assert!(!nullifiers.is_empty());
+        for n in &nullifiers {
+            assert_eq!(
+                db.get_cf(nullifiers_cf, n).unwrap(),
+                Some(tx_id.to_vec())
+            );
+        }
+        let indexed = db.iterator_cf(nullifiers_cf, IteratorMode::Start);
+        assert_eq!(indexed.count(), nullifiers.len());
+    }
+
+    #[test]
+    fn test_store_epoch_roots() {
+        use dusk_core::stake::EPOCH;
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        opts.create_missing_column_families(true);
+        let db = OptimisticTransactionDB::open_cf(
+            &opts,
+            dir.path(),
+            [CF_LEDGER_HEIGHT, CF_LEDGER_EPOCH_ROOTS],
+        )
+        .unwrap();
+        let height_cf = db.cf_handle(CF_LEDGER_HEIGHT).unwrap();
+        let roots_cf = db.cf_handle(CF_LEDGER_EPOCH_ROOTS).unwrap();
+
+        // Epoch 0 finalized, epoch 1 only accepted, epoch 2 incomplete
+        let hash = |height: u64| [height as u8; 32];
+        for height in 0..2 * EPOCH + 1 {
+            let label = match height < EPOCH {
+                true => Label::Final(0),
+                false => Label::Accepted(0),
+            };
+            let mut value = hash(height).to_vec();
+            label.write(&mut value).unwrap();
+            db.put_cf(height_cf, height.to_le_bytes(), value).unwrap();
+        }
+
+        store_epoch_roots(&db).unwrap();
+
+        let hashes: Vec<_> = ledger::epoch_heights(0).map(hash).collect();
+        let root = db.get_cf(roots_cf, 0u64.to_be_bytes()).unwrap();
+        assert_eq!(root, Some(ledger::epoch_root(&hashes).to_vec()));
+        assert_eq!(db.get_cf(roots_cf, 1u64.to_be_bytes()).unwrap(), None);
+        assert_eq!(db.get_cf(roots_cf, 2u64.to_be_bytes()).unwrap(), None);
+    }
+
+    #[test]
+    fn test_index_mempool_priority() {
+        use super::super::{deserialize_priority_key, serialize_key};
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        opts.create_missing_column_families(true);
+        let db = OptimisticTransactionDB::open_cf(
+            &opts,
+            dir.path(),
+            [CF_MEMPOOL_FEES, CF_MEMPOOL_PRIORITY],
+        )
+        .unwrap();
+        let fees_cf = db.cf_handle(CF_MEMPOOL_FEES).unwrap();
+        let priority_cf = db.cf_handle(CF_MEMPOOL_PRIORITY).unwrap();
+
+        let txs = [(1, 10, [1; 32]), (5, 20, [2; 32]), (5, 30, [3; 32])];
+        for (fee, timestamp, hash) in txs {
+            let key = serialize_key(fee, hash).unwrap();
+            db.put_cf(fees_cf, key, u64::to_be_bytes(timestamp))
+                .unwrap();
+        }
+
+        index_mempool_priority(&db).unwrap();
+
+        // Highest fee first, the oldest first for the same fee
+        let indexed: Vec<_> = db
+            .iterator_cf(priority_cf, IteratorMode::End)
+            .map(|entry| {
+                let (key, _) = entry.unwrap();
+                deserialize_priority_key(&mut &key[..]).unwrap()
+            })
+            .collect();
+        assert_eq!(indexed, [txs[1], txs[2], txs[0]]);
+    }
+
+    #[test]
+    fn test_index_candidates_iteration() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        opts.create_missing_column_families(true);
+        let db = OptimisticTransactionDB::open_cf(
+            &opts,
+            dir.path(),
+            [CF_CANDIDATES, CF_CANDIDATES_ITERATION],
+        )
+        .unwrap();
+        let candidates_cf = db.cf_handle(CF_CANDIDATES).unwrap();
+        let iteration_cf = db.cf_handle(CF_CANDIDATES_ITERATION).unwrap();
+
+        let mut headers = vec![];
+        for (prev, iteration) in [(1, 2), (1, 0), (2, 7)] {
+            let header = Header {
+                prev_block_hash: [prev; 32],
+                iteration,
+                ..Default::default()
+            };
+            let block = ledger::Block::new(header, vec![], vec![])
+                .expect("valid block");
+            let mut blob = vec![];
+            block.write(&mut blob).unwrap();
+            db.put_cf(candidates_cf, block.header().hash, blob).unwrap();
+            headers.push(block.header().clone());
+        }
+
+        index_candidates_iteration(&db).unwrap();
+
+        let mut indexed: Vec<_> = db
+            .iterator_cf(iteration_cf, IteratorMode::Start)
+            .map(|entry| entry.unwrap().0.to_vec())
+            .collect();
+        let mut expected: Vec<_> =
+            headers.iter().map(candidate_iteration_key).collect();
+        indexed.sort();
+        expected.sort();
+        assert_eq!(indexed, expected);
+    }
+
+    #[test]
+    fn test_store_contract_gas() {
+        use dusk_core::abi::ContractId;
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        opts.create_missing_column_families(true);
+        let db = OptimisticTransactionDB::open_cf(
+            &opts,
+            dir.path(),
+            [CF_LEDGER_TXS, CF_LEDGER_CONTRACT_GAS],
+        )
+        .unwrap();
+        let txs_cf = db.cf_handle(CF_LEDGER_TXS).unwrap();
+        let gas_cf = db.cf_handle(CF_LEDGER_CONTRACT_GAS).unwrap();
+
+        for (gas_price, block_height, gas_spent) in
+            [(100, 1, 3), (200, 1, 4), (300, 2, 5)]
+        {
+            let tx = SpentTransaction {
+                inner: ledger::faker::gen_dummy_tx(gas_price),
+                block_height,
+                gas_spent,
+                err: None,
+                gas_refunded: 0,
+                fee_charged: gas_spent * gas_price,
+            };
+            let mut blob = vec![];
+            tx.write(&mut blob).unwrap();
+            db.put_cf(txs_cf, tx.inner.id(), blob).unwrap();
+        }
+
+        // The block at height 2 is already indexed
+        let indexed = contract_gas_key(2, &TRANSFER_CONTRACT);
+        db.put_cf(gas_cf, indexed, 9u64.to_be_bytes()).unwrap();
+
+        store_contract_gas(&db).unwrap();
+
+        // The dummy transactions call the same contract
+        let called = ContractId::from_bytes([21; 32]);
+        let gas: Vec<_> = db
+            .iterator_cf(gas_cf, IteratorMode::Start)
+            .map(|entry| {
+                let (key, gas) = entry.unwrap();
+                (key.to_vec(), gas.to_vec())
+            })
+            .collect();
+        assert_eq!(
+            gas,
+            [
+                (contract_gas_key(1, &called).to_vec(), 7u64.to_be_bytes()),
+                (indexed.to_vec(), 9u64.to_be_bytes()),
+            ]
+            .map(|(key, gas)| (key, gas.to_vec()))
+        );
+    }
+
+    #[test]
+    fn test_store_events_indexed_from() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        opts.create_missing_column_families(true);
+        let db = OptimisticTransactionDB::open_cf(
+            &opts,
+            dir.path(),
+            [CF_LEDGER_HEIGHT, CF_METADATA],
+        )
+        .unwrap();
+        let height_cf = db.cf_handle(CF_LEDGER_HEIGHT).unwrap();
+        let md_cf = metadata_cf(&db).unwrap();
+
+        // An empty ledger indexes the events of all its blocks
+        store_events_indexed_from(&db).unwrap();
+        assert_eq!(db.get_cf(md_cf, MD_EVENTS_INDEXED_FROM).unwrap(), None);
+
+        for height in [0u64, 7, 3] {
+            db.put_cf(height_cf, height.to_le_bytes(), [height as u8; 32])
+                .unwrap();
+        }
+        store_events_indexed_from(&db).unwrap();
+        assert_eq!(
+            db.get_cf(md_cf, MD_EVENTS_INDEXED_FROM).unwrap(),
+            Some(8u64.to_le_bytes().to_vec())
+        );
+    }
+
+    #[test]
+    fn test_failed_migration() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("chain.db");
+
+        let db = open(&path);
+        db.put_cf(metadata_cf(&db).unwrap(), MD_HASH_KEY, [0; 32])
+            .unwrap();
+
+        assert!(migrate(db, &path, FAILING).is_err());
+
+        // The database is restored as before the first migration
+        let db = open(&path);
+        assert_eq!(schema_version(&db).unwrap(), None);
+        assert!(!marked(&db));
+        assert!(!backup_path(&path).exists());
+    }
+
+    #[test]
+    fn test_interrupted_migration() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("chain.db");
+
+        let db = open(&path);
+        db.put_cf(metadata_cf(&db).unwrap(), MD_HASH_KEY, [0; 32])
+            .unwrap();
+
+        // Crash after the first migration, leaving the backup behind
+        let backup = backup_path(&path);
+        Checkpoint::new(&db)
+            .unwrap()
+            .create_checkpoint(&backup)
+            .unwrap();
+        (MIGRATIONS[0].run)(&db).unwrap();
+        set_schema_version(&db, 1).unwrap();
+        drop(db);
+
+        // The database is restored as before the first migration
+        restore_interrupted(&path).unwrap();
+        assert!(!backup.exists());
+        let db = open(&path);
+        assert_eq!(schema_version(&db).unwrap(), None);
+        assert!(!marked(&db));
+
+        let db = migrate(db, &path, MIGRATIONS).unwrap();
+        assert_eq!(schema_version(&db).unwrap(), Some(2));
+        assert!(marked(&db));
+        assert!(!backup.exists());
+
+        // Nothing is restored without backup
+        drop(db);
+        restore_interrupted(&path).unwrap();
+        let db = open(&path);
+        assert_eq!(schema_version(&db).unwrap(), Some(2));
+    }
+}
@@ -0,0 +1,130 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Outcomes of the consensus steps of the recent rounds.
+//!
+//! Each step the node runs is recorded along with the result it computed, so
+//! that the quorum missing from a failed round can be found afterwards. Only
+//! the last [`ROUND_HISTORY_RETENTION`] rounds are kept.
+
+use std::io::{self, Read, Write};
+
+use node_data::ledger::{Attestation, Hash};
+use node_data::message::payload::ValidationResult;
+use node_data::message::{ConsensusHeader, Payload};
+use node_data::{Serializable, StepName};
+
+/// Number of rounds below the tip whose history is kept
+pub const ROUND_HISTORY_RETENTION: u64 = 100;
+
+/// Result computed by a consensus step
+#[derive(Debug, Clone)]
+pub enum StepOutcome {
+    /// The step ended without result, e.g. because it timed out
+    NoResult,
+    /// The proposal step produced the candidate with this hash
+    Candidate(Hash),
+    /// The validation step produced this result
+    Validation(ValidationResult),
+    /// The iteration ended with this quorum
+    Quorum(Attestation),
+}
+
+/// Outcome of a step of a round iteration
+#[derive(Debug, Clone)]
+pub struct StepRecord {
+    pub header: ConsensusHeader,
+    pub step: StepName,
+    pub outcome: StepOutcome,
+}
+
+impl StepRecord {
+    /// Creates the record of a step from the message it ended with
+    pub fn new(header: ConsensusHeader, step: StepName, msg: &Payload) -> Self {
+        let outcome = match msg {
+            Payload::Candidate(c) => {
+                StepOutcome::Candidate(c.candidate.header().hash)
+            }
+            Payload::ValidationResult(vr) => {
+                StepOutcome::Validation(vr.as_ref().clone())
+            }
+            Payload::Quorum(q) => StepOutcome::Quorum(q.att),
+            _ => StepOutcome::NoResult,
+        };
+        Self {
+            header,
+            step,
+            outcome,
+        }
+    }
+
+    /// Returns the storage key of the record, the records of a round being
+    /// sorted by branch, iteration and step
+    pub(crate) fn key(&self) -> Vec<u8> {
+        let mut key = self.header.round.to_be_bytes().to_vec();
+        key.extend_from_slice(&self.header.prev_block_hash);
+        key.push(self.header.iteration);
+        key.push(self.step as u8);
+        key
+    }
+}
+
+fn step_from_u8(step: u8) -> io::Result<StepName> {
+    match step {
+        0 => Ok(StepName::Proposal),
+        1 => Ok(StepName::Validation),
+        2 => Ok(StepName::Ratification),
+        _ => Err(io::Error::new(io::ErrorKind::InvalidData, "Invalid step")),
+    }
+}
+
+impl Serializable for StepRecord {
+    fn write<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        self.header.write(w)?;
+        w.write_all(&[self.step as u8])?;
+        match &self.outcome {
+            StepOutcome::NoResult => w.write_all(&[0])?,
+            StepOutcome::Candidate(hash) => {
+                w.write_all(&[1])?;
+                w.write_all(hash)?;
+            }
+            StepOutcome::Validation(vr) => {
+                w.write_all(&[2])?;
+                vr.write(w)?;
+            }
+            StepOutcome::Quorum(att) => {
+                w.write_all(&[3])?;
+                att.write(w)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn read<R: Read>(r: &mut R) -> io::Result<Self>
+    where
+        Self: Sized,
+    {
+        let header = ConsensusHeader::read(r)?;
+        let step = step_from_u8(Self::read_u8(r)?)?;
+        let outcome = match Self::read_u8(r)? {
+            0 => StepOutcome::NoResult,
+            1 => StepOutcome::Candidate(Self::read_bytes(r)?),
+            2 => StepOutcome::Validation(ValidationResult::read(r)?),
+            3 => StepOutcome::Quorum(Attestation::read(r)?),
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "Invalid step outcome",
+                ))
+            }
+        };
+        Ok(Self {
+            header,
+            step,
+            outcome,
+        })
+    }
+}
@@ -13,7 +13,7 @@ use std::sync::Arc;
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use node_data::message::payload::{self, GetResource, InvParam, InvType};
-use node_data::message::{AsyncQueue, Payload, Topics};
+use node_data::message::{AsyncQueue, OverflowPolicy, Payload, Topics};
 use smallvec::SmallVec;
 use tokio::sync::{RwLock, Semaphore};
 use tracing::{debug, info, warn};
@@ -23,6 +23,7 @@ use crate::{database, vm, LongLivedService, Message, Network};
 
 const TOPICS: &[u8] = &[
     Topics::GetBlocks as u8,
+    Topics::GetHeaders as u8,
     Topics::GetMempool as u8,
     Topics::Inv as u8,
     Topics::GetResource as u8,
@@ -83,6 +84,10 @@ impl DataBrokerSrv {
             inbound: AsyncQueue::bounded(
                 conf.max_queue_size,
                 "databroker_inbound",
+            )
+            .with_memory_budget(
+                conf.max_queue_bytes,
+                OverflowPolicy::DropNewest,
             ),
             limit_ongoing_requests: Arc::new(Semaphore::new(
                 conf.max_ongoing_requests,
@@ -196,6 +201,12 @@ impl DataBrokerSrv {
                     .await?;
                 Ok(Response::new_from_msg(msg, recv_peer))
             }
+            // Handle GetHeaders requests
+            Payload::GetHeaders(m) => {
+                let msg = Self::handle_get_headers(db, m, conf.max_inv_entries)
+                    .await?;
+                Ok(Response::new_from_msg(msg, recv_peer))
+            }
             // Handle GetMempool requests
             Payload::GetMempool(_) => {
                 let msg = Self::handle_get_mempool(db).await?;
@@ -333,6 +344,53 @@ impl DataBrokerSrv {
         Ok(inv.into())
     }
 
+    /// Handles GetHeaders message request.
+    ///
+    /// Replies with the headers of the blocks following the locator, each
+    /// carrying its attestation, up to the number requested.
+    ///
+    /// Message flow: GetHeaders -> Headers
+    async fn handle_get_headers<DB: database::DB>(
+        db: &Arc<RwLock<DB>>,
+        m: &payload::GetHeaders,
+        max_entries: usize,
+    ) -> Result<Message> {
+        let max_headers = min(usize::from(m.max_headers), max_entries)
+            .min(payload::Headers::MAX_HEADERS);
+
+        let headers = db.read().await.view(|t| {
+            let locator = t
+                .block_header(&m.locator)?
+                .ok_or_else(|| anyhow!("could not find locator block"))?;
+
+            let mut headers = vec![];
+            let mut prev_block_hash = m.locator;
+            let mut height = locator.height;
+            while headers.len() < max_headers {
+                height += 1;
+                let Some(hash) = t.block_hash_by_height(height)? else {
+                    break;
+                };
+                let header = t
+                    .block_header(&hash)?
+                    .ok_or_else(|| anyhow!("block header not found"))?;
+
+                if header.prev_block_hash != prev_block_hash {
+                    return Err(anyhow!("inconsistent chain"));
+                }
+                prev_block_hash = hash;
+                headers.push(header);
+            }
+
+            if headers.is_empty() {
+                return Err(anyhow!("no headers found"));
+            }
+            Ok(headers)
+        })?;
+
+        Ok(payload::Headers::new(headers).into())
+    }
+
     /// Handles inventory message request.
     ///
     /// This takes an inventory message (topics.Inv), checks it for any
@@ -468,10 +526,12 @@ impl DataBrokerSrv {
                     }
                     InvType::MempoolTx => {
                         if let InvParam::Hash(tx_id) = &i.param {
+                            // Bundle positions are not propagated along
+                            // with single transactions
                             db.mempool_tx(*tx_id)
                                 .ok()
                                 .flatten()
-                                .map(Message::from)
+                                .map(|tx| Message::from(tx.without_bundle()))
                         } else {
                             None
                         }
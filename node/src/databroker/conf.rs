@@ -14,6 +14,10 @@ pub struct Params {
     pub max_ongoing_requests: usize,
     #[serde(default = "default_max_queue_size")]
     pub max_queue_size: usize,
+    /// Maximum number of bytes held by the pending requests. The incoming
+    /// ones are dropped past it
+    #[serde(default = "default_max_queue_bytes")]
+    pub max_queue_bytes: usize,
 
     /// delay_on_resp_msg is in milliseconds. It mitigates stress on UDP
     /// buffers when network latency is 0 (localnet network only)
@@ -29,6 +33,9 @@ const fn default_max_ongoing_requests() -> usize {
 const fn default_max_queue_size() -> usize {
     1000
 }
+const fn default_max_queue_bytes() -> usize {
+    16 * 1024 * 1024
+}
 
 impl Default for Params {
     fn default() -> Self {
@@ -37,6 +44,7 @@ impl Default for Params {
             max_ongoing_requests: default_max_ongoing_requests(),
             delay_on_resp_msg: None,
             max_queue_size: default_max_queue_size(),
+            max_queue_bytes: default_max_queue_bytes(),
         }
     }
 }
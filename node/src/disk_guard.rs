@@ -0,0 +1,216 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Disk-quota guard.
+//!
+//! Running out of disk space in the middle of a RocksDB write may leave the
+//! database in an unrecoverable state. This service periodically checks the
+//! space available on the volume hosting the database and degrades the node
+//! gracefully as it shrinks:
+//!
+//! 1. optional indexes (e.g. the archive) stop being written
+//! 2. candidate blocks stop being stored
+//! 3. block acceptance is halted
+//!
+//! The current level is exposed through [`status`] and the
+//! `dusk_disk_guard_status` gauge. Every level is lifted automatically as soon
+//! as enough space is freed.
+
+pub mod conf;
+
+use std::ffi::CString;
+use std::io;
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use metrics::gauge;
+use tokio::sync::RwLock;
+use tokio::time::sleep;
+use tracing::{error, info, warn};
+
+use crate::disk_guard::conf::Params;
+use crate::{database, vm, LongLivedService, Network};
+
+static STATUS: AtomicU8 = AtomicU8::new(DiskStatus::Healthy as u8);
+
+/// Degradation level, ordered from the least to the most restrictive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[repr(u8)]
+pub enum DiskStatus {
+    Healthy = 0,
+    IndexesPaused = 1,
+    CandidatesPaused = 2,
+    AcceptanceHalted = 3,
+}
+
+impl DiskStatus {
+    /// Returns the status matching the given amount of available bytes
+    pub fn from_available(available: u64, conf: &Params) -> Self {
+        if available < conf.halt_acceptance_below {
+            Self::AcceptanceHalted
+        } else if available < conf.pause_candidates_below {
+            Self::CandidatesPaused
+        } else if available < conf.pause_indexes_below {
+            Self::IndexesPaused
+        } else {
+            Self::Healthy
+        }
+    }
+
+    fn from_u8(v: u8) -> Self {
+        match v {
+            0 => Self::Healthy,
+            1 => Self::IndexesPaused,
+            2 => Self::CandidatesPaused,
+            _ => Self::AcceptanceHalted,
+        }
+    }
+}
+
+impl std::fmt::Display for DiskStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Self::Healthy => "healthy",
+            Self::IndexesPaused => "indexes_paused",
+            Self::CandidatesPaused => "candidates_paused",
+            Self::AcceptanceHalted => "acceptance_halted",
+        };
+        f.write_str(s)
+    }
+}
+
+/// Returns the current disk status
+pub fn status() -> DiskStatus {
+    DiskStatus::from_u8(STATUS.load(Ordering::Relaxed))
+}
+
+/// Returns the number of bytes available to unprivileged users on the volume
+/// hosting `path`
+pub fn available_space(path: &Path) -> io::Result<u64> {
+    let c_path = CString::new(path.as_os_str().as_bytes())?;
+
+    // SAFETY: `statvfs` is plain old data and `c_path` is a valid
+    // nul-terminated string that outlives the call
+    let stat = unsafe {
+        let mut stat: libc::statvfs = std::mem::zeroed();
+        if libc::statvfs(c_path.as_ptr(), &mut stat) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        stat
+    };
+
+    #[allow(clippy::unnecessary_cast)]
+    Ok(stat.f_bavail as u64 * stat.f_frsize as u64)
+}
+
+pub struct DiskGuardSrv {
+    path: PathBuf,
+    conf: Params,
+}
+
+impl DiskGuardSrv {
+    pub fn new(path: PathBuf, conf: Params) -> Self {
+        info!("DiskGuardSrv::new with conf {:?}", conf);
+        Self { path, conf }
+    }
+
+    fn check(&self) {
+        let available = match available_space(&self.path) {
+            Ok(available) => available,
+            Err(err) => {
+                error!(event = "disk space check failed", ?err);
+                return;
+            }
+        };
+        gauge!("dusk_disk_available_bytes").set(available as f64);
+
+        let new_status = DiskStatus::from_available(available, &self.conf);
+        let old_status = DiskStatus::from_u8(
+            STATUS.swap(new_status as u8, Ordering::Relaxed),
+        );
+        gauge!("dusk_disk_guard_status").set(new_status as u8 as f64);
+
+        if new_status > old_status {
+            warn!(
+                event = "disk space low",
+                status = %new_status,
+                available,
+            );
+        } else if new_status < old_status {
+            info!(
+                event = "disk space recovered",
+                status = %new_status,
+                available,
+            );
+        }
+    }
+}
+
+#[async_trait]
+impl<N: Network, DB: database::DB, VM: vm::VMExecution>
+    LongLivedService<N, DB, VM> for DiskGuardSrv
+{
+    async fn execute(
+        &mut self,
+        _: Arc<RwLock<N>>,
+        _: Arc<RwLock<DB>>,
+        _: Arc<RwLock<VM>>,
+    ) -> anyhow::Result<usize> {
+        let Some(interval) = self.conf.check_interval else {
+            return Ok(0);
+        };
+
+        loop {
+            self.check();
+            sleep(interval).await;
+        }
+    }
+
+    /// Returns service name.
+    fn name(&self) -> &'static str {
+        "disk_guard"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_from_available() {
+        let conf = Params {
+            check_interval: None,
+            pause_indexes_below: 300,
+            pause_candidates_below: 200,
+            halt_acceptance_below: 100,
+        };
+
+        assert_eq!(DiskStatus::from_available(400, &conf), DiskStatus::Healthy);
+        assert_eq!(DiskStatus::from_available(300, &conf), DiskStatus::Healthy);
+        assert_eq!(
+            DiskStatus::from_available(299, &conf),
+            DiskStatus::IndexesPaused
+        );
+        assert_eq!(
+            DiskStatus::from_available(150, &conf),
+            DiskStatus::CandidatesPaused
+        );
+        assert_eq!(
+            DiskStatus::from_available(0, &conf),
+            DiskStatus::AcceptanceHalted
+        );
+    }
+
+    #[test]
+    fn test_available_space() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(available_space(dir.path()).is_ok());
+        assert!(available_space(&dir.path().join("missing")).is_err());
+    }
+}
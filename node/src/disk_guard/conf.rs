@@ -0,0 +1,44 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+/// Disk guard configuration parameters
+pub const DEFAULT_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+const MIB: u64 = 1024 * 1024;
+
+#[derive(Serialize, Deserialize, Copy, Clone, Debug)]
+#[serde(default)]
+pub struct Params {
+    /// Interval between two disk space checks. Set to `None` to disable the
+    /// guard
+    #[serde(with = "humantime_serde")]
+    pub check_interval: Option<Duration>,
+
+    /// Available bytes below which optional indexes (e.g. the archive) stop
+    /// being written
+    pub pause_indexes_below: u64,
+
+    /// Available bytes below which candidate blocks stop being stored
+    pub pause_candidates_below: u64,
+
+    /// Available bytes below which block acceptance is halted
+    pub halt_acceptance_below: u64,
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Self {
+            check_interval: Some(DEFAULT_CHECK_INTERVAL),
+            pause_indexes_below: 4096 * MIB,
+            pause_candidates_below: 2048 * MIB,
+            halt_acceptance_below: 1024 * MIB,
+        }
+    }
+}
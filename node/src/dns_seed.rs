@@ -0,0 +1,204 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! DNS seeding of the bootstrapping nodes.
+//!
+//! The bootstrapping nodes are resolved from the configured seed domains, so
+//! that the bootstrap infrastructure can be rotated without a node release.
+//! Both the A and AAAA records of a seed are resolved, and its TXT records may
+//! list `ip:port` entries of nodes listening on other ports.
+//!
+//! The nodes resolved at startup are added to the Kadcast bootstrapping nodes.
+//! Seeds are then resolved again as their records expire, and the nodes not
+//! resolved before are contacted with a mempool request: the exchange makes
+//! both ends add each other to their routing table.
+
+pub mod conf;
+
+use std::collections::BTreeSet;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use hickory_resolver::config::LookupIpStrategy;
+use hickory_resolver::TokioAsyncResolver;
+use node_data::message::payload;
+use tokio::sync::RwLock;
+use tokio::time::sleep;
+use tracing::{info, warn};
+
+use crate::dns_seed::conf::Params;
+use crate::{database, vm, LongLivedService, Network};
+
+/// Parses the `ip:port` entries of a TXT record, separated by whitespaces or
+/// commas
+pub fn parse_txt(record: &str) -> Vec<SocketAddr> {
+    record
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|entry| !entry.is_empty())
+        .filter_map(|entry| match entry.parse() {
+            Ok(addr) => Some(addr),
+            Err(_) => {
+                warn!(event = "invalid dns seed entry", entry);
+                None
+            }
+        })
+        .collect()
+}
+
+pub struct DnsSeedSrv {
+    conf: Params,
+    /// Nodes resolved so far
+    known: BTreeSet<SocketAddr>,
+    /// Time the seeds have to be resolved again at
+    next_refresh: Instant,
+}
+
+impl DnsSeedSrv {
+    pub fn new(conf: Params) -> Self {
+        info!("DnsSeedSrv::new with conf {:?}", conf);
+        Self {
+            conf,
+            known: BTreeSet::new(),
+            next_refresh: Instant::now(),
+        }
+    }
+
+    /// Resolves the seeds, returning the bootstrapping nodes to add to the
+    /// Kadcast configuration
+    pub async fn bootstrap(&mut self) -> Vec<String> {
+        if self.conf.domains.is_empty() {
+            return vec![];
+        }
+        let resolved = self.refresh().await;
+        info!(event = "dns seeds resolved", nodes = resolved.len());
+        resolved.iter().map(SocketAddr::to_string).collect()
+    }
+
+    /// Resolves the seeds, returning the nodes not resolved before
+    async fn refresh(&mut self) -> Vec<SocketAddr> {
+        let now = Instant::now();
+        let mut valid_until = now + self.conf.max_refresh;
+
+        let resolver = match resolver() {
+            Ok(resolver) => resolver,
+            Err(err) => {
+                warn!(event = "cannot read the system dns config", %err);
+                self.next_refresh = now + self.conf.min_refresh;
+                return vec![];
+            }
+        };
+
+        let mut resolved = BTreeSet::<SocketAddr>::new();
+        for domain in &self.conf.domains {
+            match resolver.lookup_ip(domain.as_str()).await {
+                Ok(lookup) => {
+                    valid_until = valid_until.min(lookup.valid_until());
+                    let port = self.conf.port;
+                    resolved.extend(
+                        lookup.iter().map(|ip| SocketAddr::new(ip, port)),
+                    );
+                }
+                Err(err) => {
+                    warn!(event = "dns seed lookup failed", domain, %err);
+                    valid_until = now;
+                }
+            }
+
+            if !self.conf.txt_records {
+                continue;
+            }
+            match resolver.txt_lookup(domain.as_str()).await {
+                Ok(lookup) => {
+                    valid_until = valid_until.min(lookup.valid_until());
+                    for txt in lookup.iter() {
+                        let record: Vec<u8> = txt.txt_data().concat();
+                        resolved.extend(parse_txt(&String::from_utf8_lossy(
+                            &record,
+                        )));
+                    }
+                }
+                Err(err) => {
+                    warn!(event = "dns seed txt lookup failed", domain, %err)
+                }
+            }
+        }
+
+        let ttl = valid_until.saturating_duration_since(now);
+        let ttl = ttl.clamp(self.conf.min_refresh, self.conf.max_refresh);
+        self.next_refresh = now + ttl;
+
+        let new: Vec<_> = resolved.difference(&self.known).copied().collect();
+        self.known.extend(new.iter().copied());
+        new
+    }
+}
+
+fn resolver() -> anyhow::Result<TokioAsyncResolver> {
+    let (config, mut opts) = hickory_resolver::system_conf::read_system_conf()?;
+    opts.ip_strategy = LookupIpStrategy::Ipv4AndIpv6;
+    Ok(TokioAsyncResolver::tokio(config, opts))
+}
+
+#[async_trait]
+impl<N: Network, DB: database::DB, VM: vm::VMExecution>
+    LongLivedService<N, DB, VM> for DnsSeedSrv
+{
+    async fn execute(
+        &mut self,
+        network: Arc<RwLock<N>>,
+        _: Arc<RwLock<DB>>,
+        _: Arc<RwLock<VM>>,
+    ) -> anyhow::Result<usize> {
+        if self.conf.domains.is_empty() {
+            return Ok(0);
+        }
+
+        loop {
+            let wait =
+                self.next_refresh.saturating_duration_since(Instant::now());
+            sleep(wait.max(Duration::from_secs(1))).await;
+
+            let new = self.refresh().await;
+            if new.is_empty() {
+                continue;
+            }
+
+            let net = network.read().await;
+            let public_addr = *net.public_addr();
+            for addr in new.into_iter().filter(|addr| *addr != public_addr) {
+                info!(event = "dns seed node discovered", %addr);
+                let msg = payload::GetMempool::default().into();
+                if let Err(err) = net.send_to_peer(msg, addr).await {
+                    warn!(event = "cannot contact dns seed node", %addr, %err);
+                }
+            }
+        }
+    }
+
+    /// Returns service name.
+    fn name(&self) -> &'static str {
+        "dns_seed"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn txt_entries_are_parsed() {
+        let addrs = parse_txt("1.2.3.4:9000, [2001:db8::1]:9001 invalid  ");
+        assert_eq!(
+            addrs,
+            vec![
+                "1.2.3.4:9000".parse::<SocketAddr>().unwrap(),
+                "[2001:db8::1]:9001".parse().unwrap(),
+            ]
+        );
+    }
+}
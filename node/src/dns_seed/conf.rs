@@ -0,0 +1,48 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+/// DNS seed configuration parameters
+pub const DEFAULT_SEED_PORT: u16 = 9000;
+pub const DEFAULT_MIN_REFRESH: Duration = Duration::from_secs(60);
+pub const DEFAULT_MAX_REFRESH: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct Params {
+    /// Seed domains to resolve the bootstrapping nodes from. Set to empty to
+    /// disable the DNS seeding
+    pub domains: Vec<String>,
+
+    /// Kadcast port of the nodes resolved from A/AAAA records
+    pub port: u16,
+
+    /// Resolve the TXT records of the seed domains, listing `ip:port` entries
+    pub txt_records: bool,
+
+    /// Lower bound of the refresh interval, whatever the records TTL
+    #[serde(with = "humantime_serde")]
+    pub min_refresh: Duration,
+
+    /// Upper bound of the refresh interval, whatever the records TTL
+    #[serde(with = "humantime_serde")]
+    pub max_refresh: Duration,
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Self {
+            domains: vec![],
+            port: DEFAULT_SEED_PORT,
+            txt_records: true,
+            min_refresh: DEFAULT_MIN_REFRESH,
+            max_refresh: DEFAULT_MAX_REFRESH,
+        }
+    }
+}
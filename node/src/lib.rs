@@ -8,17 +8,23 @@
 #![deny(unused_extern_crates)]
 #![feature(lazy_cell)]
 
+pub mod alerts;
 #[cfg(feature = "archive")]
 pub mod archive;
 pub mod chain;
+pub mod config;
 pub mod database;
 pub mod databroker;
+pub mod disk_guard;
+pub mod dns_seed;
 pub mod mempool;
 pub mod network;
+pub mod rpc;
+pub mod self_check;
 pub mod telemetry;
 pub mod vm;
 
-use std::net::SocketAddr;
+use std::net::{IpAddr, SocketAddr};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
@@ -94,6 +100,17 @@ pub trait Network: Send + Sync + 'static {
     /// Retrieves number of alive nodes
     async fn alive_nodes_count(&self) -> usize;
 
+    /// Bans the peer at `addr`, dropping all of its messages until the ban
+    /// expires.
+    fn ban_peer(&self, addr: IpAddr, reason: &str);
+
+    /// Lifts the ban of the peer at `addr`, clearing its misbehavior points.
+    fn unban_peer(&self, addr: &IpAddr);
+
+    /// Returns the peers currently banned, along with the timestamp (in
+    /// seconds) their ban expires at.
+    fn banned_peers(&self) -> Vec<(IpAddr, u64)>;
+
     async fn wait_for_alive_nodes(&self, amount: usize, timeout: Duration) {
         let start = Instant::now();
         while self.alive_nodes_count().await < amount {
@@ -4,7 +4,10 @@
 //
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
+pub mod admission;
 pub mod conf;
+pub mod inclusion;
+pub mod policy;
 
 use std::sync::Arc;
 use std::time::Duration;
@@ -15,19 +18,26 @@ use conf::{
 };
 use node_data::events::{Event, TransactionEvent};
 use node_data::get_current_timestamp;
-use node_data::ledger::{SpendingId, Transaction};
-use node_data::message::{payload, AsyncQueue, Payload, Topics};
+use node_data::ledger::{SpendingId, Transaction, TransactionBundle};
+use node_data::message::{
+    payload, AsyncQueue, OverflowPolicy, Payload, Topics,
+};
 use thiserror::Error;
 use tokio::sync::mpsc::Sender;
 use tokio::sync::RwLock;
 use tracing::{error, info, warn};
 
-use crate::database::{Ledger, Mempool};
+use crate::database::rocksdb::MD_HASH_KEY;
+use crate::database::{Ledger, Mempool, Metadata, Replacement};
+use crate::mempool::admission::{
+    AdmissionContext, AdmissionPolicy, PolicyStack,
+};
 use crate::mempool::conf::Params;
+use crate::mempool::policy::TxPolicy;
 use crate::vm::PreverificationResult;
 use crate::{database, vm, LongLivedService, Message, Network};
 
-const TOPICS: &[u8] = &[Topics::Tx as u8];
+const TOPICS: &[u8] = &[Topics::Tx as u8, Topics::TxBundle as u8];
 
 #[derive(Debug, Error)]
 pub enum TxAcceptanceError {
@@ -35,23 +45,38 @@ pub enum TxAcceptanceError {
     AlreadyExistsInMempool,
     #[error("this transaction exists in the ledger")]
     AlreadyExistsInLedger,
-    #[error("this transaction's spendId exists in the mempool")]
-    SpendIdExistsInMempool,
+    #[error("a nullifier of this transaction is spent by the ledger tx {0}")]
+    NullifierSpentInLedger(String),
+    #[error("gas price lower than minimum {0} to replace mempool tx")]
+    ReplacementUnderpriced(u64),
     #[error("this transaction is invalid {0}")]
     VerificationFailed(String),
     #[error("gas price lower than minimum {0}")]
     GasPriceTooLow(u64),
     #[error("gas limit lower than minimum {0}")]
     GasLimitTooLow(u64),
+    #[error("gas limit higher than maximum {0}")]
+    GasLimitTooHigh(u64),
+    #[error("transaction size higher than maximum {0} bytes")]
+    TooLarge(usize),
     #[error("Maximum count of transactions exceeded {0}")]
     MaxTxnCountExceeded(usize),
+    #[error("this transaction is forbidden by the local policy")]
+    ForbiddenByPolicy,
+    #[error("bundle gas limit higher than block gas limit {0}")]
+    BundleGasLimitExceeded(u64),
+    #[error("bundle transaction {0} refused: {1}")]
+    BundleTxRefused(String, Box<TxAcceptanceError>),
+    #[error("bundled transaction propagated out of its bundle")]
+    UnbundledTx,
     #[error("A generic error occurred {0}")]
     Generic(anyhow::Error),
 }
 
 impl From<anyhow::Error> for TxAcceptanceError {
     fn from(err: anyhow::Error) -> Self {
-        Self::Generic(err)
+        // Refusals raised within a database transaction are kept as they are
+        err.downcast().unwrap_or_else(Self::Generic)
     }
 }
 
@@ -60,20 +85,47 @@ pub struct MempoolSrv {
     conf: Params,
     /// Sender channel for sending out RUES events
     event_sender: Sender<Event>,
+    /// Policies the transactions must pass to be admitted
+    admission: PolicyStack,
 }
 
 impl MempoolSrv {
     pub fn new(conf: Params, event_sender: Sender<Event>) -> Self {
         info!("MempoolSrv::new with conf {}", conf);
+        let admission = conf
+            .admission
+            .iter()
+            .cloned()
+            .fold(PolicyStack::chain(), PolicyStack::with);
         Self {
             inbound: AsyncQueue::bounded(
                 conf.max_queue_size,
                 "mempool_inbound",
+            )
+            .with_memory_budget(
+                conf.max_queue_bytes,
+                OverflowPolicy::DropNewest,
             ),
             conf,
             event_sender,
+            admission,
         }
     }
+
+    /// Sets the policy of the transactions refused by the mempool
+    pub fn with_tx_policy(mut self, tx_policy: TxPolicy) -> Self {
+        self.admission = self.admission.with(tx_policy);
+        self
+    }
+
+    /// Stacks a custom admission policy on top of the configured ones
+    pub fn with_admission_policy(
+        mut self,
+        policy: impl AdmissionPolicy + 'static,
+    ) -> Self {
+        self.admission = self.admission.with(policy);
+        self
+    }
 }
 
 #[async_trait]
@@ -130,12 +182,32 @@ impl<N: Network, DB: database::DB, VM: vm::VMExecution>
                                 error!("cannot delete expired tx: {e}");
                                 vec![]
                             });
-                            for deleted_tx_id in deleted_txs{
-                                let event = TransactionEvent::Removed(deleted_tx_id);
-                                info!(event = "mempool_deleted", hash = hex::encode(deleted_tx_id));
-                                if let Err(e) = self.event_sender.try_send(event.into()) {
-                                    warn!("cannot notify mempool removed transaction {e}")
-                                };
+                            self.notify_removed(deleted_txs);
+                        }
+
+                        // Evict the lowest fee transactions while the mempool
+                        // exceeds its maximum size
+                        if let Some(max_size) = self.conf.max_mempool_size {
+                            let mut sizes = db.mempool_txs_sizes();
+                            let mut size: usize = sizes.values().sum();
+                            if size > max_size {
+                                let by_low_fee: Vec<_> = db.mempool_txs_ids_sorted_by_low_fee()?.map(|(_, tx_id)| tx_id).collect();
+                                for tx_id in by_low_fee {
+                                    if size <= max_size {
+                                        break;
+                                    }
+                                    // Skip the ones deleted along with an
+                                    // evicted transaction
+                                    if !sizes.contains_key(&tx_id) {
+                                        continue;
+                                    }
+                                    info!(event = "evicted_tx", hash = hex::encode(tx_id));
+                                    let deleted_txs = db.delete_mempool_tx(tx_id, true)?;
+                                    for deleted in &deleted_txs {
+                                        size -= sizes.remove(deleted).unwrap_or_default();
+                                    }
+                                    self.notify_removed(deleted_txs);
+                                }
                             }
                         }
                         Ok(())
@@ -152,11 +224,34 @@ impl<N: Network, DB: database::DB, VM: vm::VMExecution>
                                     continue;
                                 }
 
+                                // Messages without metadata are local submissions
+                                if msg.metadata.is_none() {
+                                    inclusion::mark_admitted(tx.id());
+                                }
+
                                 let network = network.read().await;
                                 if let Err(e) = network.broadcast(&msg).await {
                                     warn!("Unable to broadcast accepted tx: {e}")
                                 };
                             }
+                            Payload::TransactionBundle(bundle) => {
+                                let accept = self.accept_bundle(&db, &vm, bundle);
+                                if let Err(e) = accept.await {
+                                    error!("Bundle {} not accepted: {e}", hex::encode(bundle.id()));
+                                    continue;
+                                }
+
+                                if msg.metadata.is_none() {
+                                    for tx in bundle.txs() {
+                                        inclusion::mark_admitted(tx.id());
+                                    }
+                                }
+
+                                let network = network.read().await;
+                                if let Err(e) = network.broadcast(&msg).await {
+                                    warn!("Unable to broadcast accepted bundle: {e}")
+                                };
+                            }
                             _ => error!("invalid inbound message payload"),
                         }
                     }
@@ -172,107 +267,223 @@ impl<N: Network, DB: database::DB, VM: vm::VMExecution>
 }
 
 impl MempoolSrv {
+    /// Notifies the removal of transactions from the mempool
+    fn notify_removed(&self, deleted_txs: Vec<[u8; 32]>) {
+        for deleted_tx_id in deleted_txs {
+            inclusion::mark_dropped(&deleted_tx_id);
+            let event = TransactionEvent::Removed(deleted_tx_id);
+            info!(event = "mempool_deleted", hash = hex::encode(deleted_tx_id));
+            if let Err(e) = self.event_sender.try_send(event.into()) {
+                warn!("cannot notify mempool removed transaction {e}")
+            };
+        }
+    }
+
+    /// Notifies the inclusions and removals of an acceptance in the mempool
+    fn notify_accepted(&self, events: Vec<TransactionEvent<'_>>) {
+        for tx_event in events {
+            if let TransactionEvent::Removed(replaced) = &tx_event {
+                inclusion::mark_dropped(replaced);
+            }
+            let node_event = tx_event.into();
+            if let Err(e) = self.event_sender.try_send(node_event) {
+                warn!("cannot notify mempool accepted transaction {e}")
+            };
+        }
+    }
+
     async fn accept_tx<DB: database::DB, VM: vm::VMExecution>(
         &mut self,
         db: &Arc<RwLock<DB>>,
         vm: &Arc<RwLock<VM>>,
         tx: &Transaction,
     ) -> Result<(), TxAcceptanceError> {
-        let max_mempool_txn_count = self.conf.max_mempool_txn_count;
+        // The transactions of a bundle are only accepted along with it
+        if tx.bundle().is_some() {
+            return Err(TxAcceptanceError::UnbundledTx);
+        }
 
-        let events =
-            MempoolSrv::check_tx(db, vm, tx, false, max_mempool_txn_count)
-                .await?;
+        let max_mempool_txn_count = self.conf.max_mempool_txn_count;
+        let min_replacement_bump = self.conf.min_replacement_bump;
+
+        let events = MempoolSrv::check_tx(
+            db,
+            vm,
+            tx,
+            false,
+            max_mempool_txn_count,
+            min_replacement_bump,
+            &self.admission,
+        )
+        .await?;
 
         tracing::info!(
             event = "transaction accepted",
             hash = hex::encode(tx.id())
         );
 
-        for tx_event in events {
-            let node_event = tx_event.into();
-            if let Err(e) = self.event_sender.try_send(node_event) {
-                warn!("cannot notify mempool accepted transaction {e}")
-            };
+        self.notify_accepted(events);
+
+        Ok(())
+    }
+
+    /// Accepts all the transactions of a bundle, or none of them.
+    ///
+    /// All the transactions are checked before any of them is stored, then
+    /// they are stored in a single database transaction, along with the
+    /// replacements and evictions they make.
+    async fn accept_bundle<DB: database::DB, VM: vm::VMExecution>(
+        &mut self,
+        db: &Arc<RwLock<DB>>,
+        vm: &Arc<RwLock<VM>>,
+        bundle: &TransactionBundle,
+    ) -> Result<(), TxAcceptanceError> {
+        let block_gas_limit = vm.read().await.get_block_gas_limit();
+        if bundle.gas_limit() > block_gas_limit {
+            return Err(TxAcceptanceError::BundleGasLimitExceeded(
+                block_gas_limit,
+            ));
         }
 
+        let txs = bundle.txs();
+        let mut admitted = vec![];
+        let mut pending = vec![];
+        for tx in &txs {
+            let ctx = MempoolSrv::admit_tx(
+                db,
+                vm,
+                tx,
+                self.conf.max_mempool_txn_count,
+                self.conf.min_replacement_bump,
+                &self.admission,
+                &pending,
+            )
+            .await
+            .map_err(|e| {
+                let tx_id = hex::encode(tx.id());
+                TxAcceptanceError::BundleTxRefused(tx_id, Box::new(e))
+            })?;
+            pending.extend(tx.to_spend_ids());
+            admitted.push(ctx);
+        }
+
+        let tx_ids: Vec<_> = txs.iter().map(|tx| tx.id()).collect();
+        let mut events = vec![];
+        db.read().await.update(|db| {
+            for (tx, ctx) in txs.iter().zip(admitted) {
+                // The mempool grows with each transaction of the bundle, whose
+                // transactions are never evicted in favor of each other
+                let ctx = readmit_tx(
+                    db,
+                    tx,
+                    ctx,
+                    self.conf.min_replacement_bump,
+                    &self.admission,
+                    &tx_ids,
+                )
+                .map_err(|e| {
+                    let tx_id = hex::encode(tx.id());
+                    let e = TxAcceptanceError::from(e);
+                    TxAcceptanceError::BundleTxRefused(tx_id, Box::new(e))
+                })?;
+                store_tx(db, tx, &ctx, &mut events)?;
+            }
+            db.store_mempool_bundle(bundle.id(), &tx_ids)
+        })?;
+
+        tracing::info!(
+            event = "bundle accepted",
+            hash = hex::encode(bundle.id()),
+            txs = tx_ids.len()
+        );
+
+        self.notify_accepted(events);
+
         Ok(())
     }
 
+    /// Checks the admission of `tx` in the mempool against `policy`, then
+    /// preverifies it and stores it, unless `dry_run` is set.
     pub async fn check_tx<'t, DB: database::DB, VM: vm::VMExecution>(
         db: &Arc<RwLock<DB>>,
         vm: &Arc<RwLock<VM>>,
         tx: &'t Transaction,
         dry_run: bool,
         max_mempool_txn_count: usize,
+        min_replacement_bump: u64,
+        policy: &impl AdmissionPolicy,
     ) -> Result<Vec<TransactionEvent<'t>>, TxAcceptanceError> {
-        let tx_id = tx.id();
-
-        if tx.gas_price() < 1 {
-            return Err(TxAcceptanceError::GasPriceTooLow(1));
-        }
-
-        if tx.inner.deploy().is_some() {
-            let vm = vm.read().await;
-            let min_deployment_gas_price = vm.min_deployment_gas_price();
-            if tx.gas_price() < min_deployment_gas_price {
-                return Err(TxAcceptanceError::GasPriceTooLow(
-                    min_deployment_gas_price,
-                ));
-            }
+        let ctx = MempoolSrv::admit_tx(
+            db,
+            vm,
+            tx,
+            max_mempool_txn_count,
+            min_replacement_bump,
+            policy,
+            &[],
+        )
+        .await?;
 
-            let gas_per_deploy_byte = vm.gas_per_deploy_byte();
-            let deploy_charge = tx
-                .inner
-                .deploy_charge(gas_per_deploy_byte, vm.min_deploy_points());
-            if tx.inner.gas_limit() < deploy_charge {
-                return Err(TxAcceptanceError::GasLimitTooLow(deploy_charge));
-            }
-        } else {
-            let vm = vm.read().await;
-            let min_gas_limit = vm.min_gas_limit();
-            if tx.inner.gas_limit() < min_gas_limit {
-                return Err(TxAcceptanceError::GasLimitTooLow(min_gas_limit));
-            }
-        }
+        let mut events = vec![];
 
-        // Perform basic checks on the transaction
-        let tx_to_delete = db.read().await.view(|view| {
-            // ensure transaction does not exist in the mempool
-            if view.mempool_tx_exists(tx_id)? {
-                return Err(TxAcceptanceError::AlreadyExistsInMempool);
-            }
+        // Try to add the transaction to the mempool
+        db.read().await.update_dry_run(dry_run, |db| {
+            let ctx =
+                readmit_tx(db, tx, ctx, min_replacement_bump, policy, &[])?;
+            store_tx(db, tx, &ctx, &mut events)
+        })?;
+        Ok(events)
+    }
 
-            // ensure transaction does not exist in the blockchain
-            if view.ledger_tx_exists(&tx_id)? {
-                return Err(TxAcceptanceError::AlreadyExistsInLedger);
-            }
+    /// Checks the admission of `tx` in the mempool against `policy`, then
+    /// preverifies it, returning the context it is admitted with.
+    ///
+    /// The intermediate nonces of a transaction with a future nonce are looked
+    /// up in the mempool, and in the `pending` spending ids.
+    async fn admit_tx<DB: database::DB, VM: vm::VMExecution>(
+        db: &Arc<RwLock<DB>>,
+        vm: &Arc<RwLock<VM>>,
+        tx: &Transaction,
+        max_mempool_txn_count: usize,
+        min_replacement_bump: u64,
+        policy: &impl AdmissionPolicy,
+        pending: &[SpendingId],
+    ) -> Result<AdmissionContext, TxAcceptanceError> {
+        let tx_id = tx.id();
 
-            let txs_count = view.mempool_txs_count();
-            if txs_count >= max_mempool_txn_count {
-                // Get the lowest fee transaction to delete
-                let (lowest_price, to_delete) = view
-                    .mempool_txs_ids_sorted_by_low_fee()?
-                    .next()
-                    .ok_or(anyhow::anyhow!("Cannot get lowest fee tx"))?;
-
-                if tx.gas_price() < lowest_price {
-                    // Or error if the gas price proposed is the lowest of all
-                    // the transactions in the mempool
-                    Err(TxAcceptanceError::MaxTxnCountExceeded(
-                        max_mempool_txn_count,
-                    ))
-                } else {
-                    Ok(Some(to_delete))
-                }
-            } else {
-                Ok(None)
+        let ctx = {
+            let vm = vm.read().await;
+            AdmissionContext {
+                max_mempool_txs_count: max_mempool_txn_count,
+                min_gas_limit: vm.min_gas_limit(),
+                min_deployment_gas_price: vm.min_deployment_gas_price(),
+                gas_per_deploy_byte: vm.gas_per_deploy_byte(),
+                min_deploy_points: vm.min_deploy_points(),
+                ..Default::default()
             }
+        };
+        let ctx = db.read().await.view(|view| {
+            anyhow::Ok(AdmissionContext {
+                in_mempool: view.mempool_tx_exists(tx_id)?,
+                in_ledger: view.ledger_tx_exists(&tx_id)?,
+                spent_in_ledger: spent_in_ledger(view, tx)?,
+                mempool_txs_count: view.mempool_txs_count(),
+                lowest_fee: view.mempool_txs_ids_sorted_by_low_fee()?.next(),
+                replacement: view
+                    .mempool_replacement(tx, min_replacement_bump)?,
+                block_height: next_block_height(view)?,
+                ..ctx
+            })
         })?;
 
+        policy.check(tx, &ctx)?;
+
         // VM Preverify call
-        let preverification_data =
-            vm.read().await.preverify(tx).map_err(|e| {
+        let preverification_data = vm
+            .read()
+            .await
+            .preverify(tx, ctx.block_height)
+            .map_err(|e| {
                 TxAcceptanceError::VerificationFailed(format!("{e:?}"))
             })?;
 
@@ -285,9 +496,10 @@ impl MempoolSrv {
             db.read().await.view(|db| {
                 for nonce in state.nonce + 1..nonce_used {
                     let spending_id = SpendingId::AccountNonce(account, nonce);
-                    if db
-                        .mempool_txs_by_spendable_ids(&[spending_id])
-                        .is_empty()
+                    if !pending.contains(&spending_id)
+                        && db
+                            .mempool_txs_by_spendable_ids(&[spending_id])
+                            .is_empty()
                     {
                         return Err(TxAcceptanceError::VerificationFailed(
                             format!("Missing intermediate nonce {nonce}"),
@@ -298,45 +510,7 @@ impl MempoolSrv {
             })?;
         }
 
-        let mut events = vec![];
-
-        // Try to add the transaction to the mempool
-        db.read().await.update_dry_run(dry_run, |db| {
-            let spend_ids = tx.to_spend_ids();
-
-            let mut replaced = false;
-            // ensure spend_ids do not exist in the mempool
-            for m_tx_id in db.mempool_txs_by_spendable_ids(&spend_ids) {
-                if let Some(m_tx) = db.mempool_tx(m_tx_id)? {
-                    if m_tx.inner.gas_price() < tx.inner.gas_price() {
-                        for deleted in db.delete_mempool_tx(m_tx_id, false)? {
-                            events.push(TransactionEvent::Removed(deleted));
-                            replaced = true;
-                        }
-                    } else {
-                        return Err(
-                            TxAcceptanceError::SpendIdExistsInMempool.into()
-                        );
-                    }
-                }
-            }
-
-            events.push(TransactionEvent::Included(tx));
-
-            if !replaced {
-                if let Some(to_delete) = tx_to_delete {
-                    for deleted in db.delete_mempool_tx(to_delete, true)? {
-                        events.push(TransactionEvent::Removed(deleted));
-                    }
-                }
-            }
-            // Persist transaction in mempool storage
-
-            let now = get_current_timestamp();
-
-            db.store_mempool_tx(tx, now)
-        })?;
-        Ok(events)
+        Ok(ctx)
     }
 
     /// Requests full mempool data from N alive peers
@@ -359,3 +533,100 @@ impl MempoolSrv {
         }
     }
 }
+
+/// Checks `tx` against `policy` again, with the parts of `ctx` depending on
+/// the mempool refreshed from `db`, returning the refreshed context.
+///
+/// The mempool may have changed since `tx` was admitted, e.g. the transactions
+/// it replaces may have been replaced in turn. The transactions in `own` are
+/// not evicted in favor of `tx`, nor replaced by it.
+fn readmit_tx<M: Mempool>(
+    db: &M,
+    tx: &Transaction,
+    ctx: AdmissionContext,
+    min_replacement_bump: u64,
+    policy: &impl AdmissionPolicy,
+    own: &[[u8; 32]],
+) -> anyhow::Result<AdmissionContext> {
+    let replacement = db.mempool_replacement(tx, min_replacement_bump)?;
+    if let Replacement::Replaces(m_tx_ids) = &replacement {
+        if m_tx_ids.iter().any(|m_tx_id| own.contains(m_tx_id)) {
+            anyhow::bail!("conflicting with a transaction of its bundle");
+        }
+    }
+
+    let ctx = AdmissionContext {
+        in_mempool: db.mempool_tx_exists(tx.id())?,
+        mempool_txs_count: db.mempool_txs_count(),
+        lowest_fee: db
+            .mempool_txs_ids_sorted_by_low_fee()?
+            .find(|(_, tx_id)| !own.contains(tx_id)),
+        replacement,
+        ..ctx
+    };
+    policy.check(tx, &ctx)?;
+    Ok(ctx)
+}
+
+/// Stores `tx` in the mempool, deleting the transactions it replaces by fee
+/// or the one evicted to make room for it, as checked in `ctx`
+fn store_tx<'t, M: Mempool>(
+    db: &mut M,
+    tx: &'t Transaction,
+    ctx: &AdmissionContext,
+    events: &mut Vec<TransactionEvent<'t>>,
+) -> anyhow::Result<()> {
+    let mut replaced = false;
+    // Replace by fee the mempool transactions sharing spend_ids
+    if let Replacement::Replaces(m_tx_ids) = &ctx.replacement {
+        for m_tx_id in m_tx_ids {
+            for deleted in db.delete_mempool_tx(*m_tx_id, false)? {
+                events.push(TransactionEvent::Removed(deleted));
+                replaced = true;
+            }
+        }
+    }
+
+    events.push(TransactionEvent::Included(tx));
+
+    if !replaced {
+        if let Some(to_delete) = ctx.to_evict() {
+            for deleted in db.delete_mempool_tx(to_delete, true)? {
+                events.push(TransactionEvent::Removed(deleted));
+            }
+        }
+    }
+    // Persist transaction in mempool storage
+
+    let now = get_current_timestamp();
+
+    db.store_mempool_tx(tx, now)
+}
+
+/// Returns the id of a ledger transaction spending a nullifier of `tx`, if
+/// any, looking each nullifier up in the ledger index.
+///
+/// This rejects double spends before the costlier VM preverification, which
+/// remains the authoritative check.
+fn spent_in_ledger<L: Ledger>(
+    ledger: &L,
+    tx: &Transaction,
+) -> anyhow::Result<Option<[u8; 32]>> {
+    for id in tx.to_spend_ids() {
+        if let SpendingId::Nullifier(n) = id {
+            if let Some(tx_id) = ledger.ledger_tx_by_nullifier(&n)? {
+                return Ok(Some(tx_id));
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Returns the height of the block following the tip of `db`
+fn next_block_height<T: Ledger + Metadata>(db: &T) -> anyhow::Result<u64> {
+    let tip_height = match db.op_read(MD_HASH_KEY)? {
+        Some(hash) => db.block_header(&hash)?.map(|h| h.height),
+        None => None,
+    };
+    Ok(tip_height.map_or(0, |h| h + 1))
+}
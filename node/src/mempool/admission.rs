@@ -0,0 +1,367 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Admission policies of the mempool.
+//!
+//! Before a transaction is preverified by the VM, the mempool gathers the
+//! state it is checked against in an [`AdmissionContext`] and runs it through
+//! a stack of [`AdmissionPolicy`]s, the first refusing it rejecting the
+//! transaction. Policies don't access the database, so that they can be
+//! tested in isolation.
+//!
+//! The [`PolicyStack::chain`] stack enforces the rules of the chain: fees,
+//! mempool size and conflicts with the known transactions. Operators can
+//! stack stricter [`AdmissionRule`]s on top of it from the configuration:
+//!
+//! ```toml
+//! [[mempool.admission]]
+//! rule = "min_gas_price"
+//! gas_price = 5
+//!
+//! [[mempool.admission]]
+//! rule = "max_tx_size"
+//! bytes = 65536
+//! ```
+
+use std::sync::Arc;
+
+use node_data::ledger::Transaction;
+use serde::{Deserialize, Serialize};
+
+use super::policy::TxPolicy;
+use super::TxAcceptanceError;
+use crate::database::Replacement;
+
+/// State a transaction is checked against for admission in the mempool
+#[derive(Debug, Clone)]
+pub struct AdmissionContext {
+    /// Whether the transaction is already in the mempool
+    pub in_mempool: bool,
+    /// Whether the transaction is already in the ledger
+    pub in_ledger: bool,
+    /// Id of a ledger transaction spending a nullifier of the transaction
+    pub spent_in_ledger: Option<[u8; 32]>,
+    /// Number of transactions in the mempool
+    pub mempool_txs_count: usize,
+    /// Maximum number of transactions in the mempool
+    pub max_mempool_txs_count: usize,
+    /// Gas price and id of the lowest fee transaction in the mempool
+    pub lowest_fee: Option<(u64, [u8; 32])>,
+    /// Outcome of the replace-by-fee check against the mempool
+    pub replacement: Replacement,
+    /// Height of the next block, the transaction is admitted for
+    pub block_height: u64,
+    pub min_gas_limit: u64,
+    pub min_deployment_gas_price: u64,
+    pub gas_per_deploy_byte: u64,
+    pub min_deploy_points: u64,
+}
+
+impl Default for AdmissionContext {
+    fn default() -> Self {
+        Self {
+            in_mempool: false,
+            in_ledger: false,
+            spent_in_ledger: None,
+            mempool_txs_count: 0,
+            max_mempool_txs_count: usize::MAX,
+            lowest_fee: None,
+            replacement: Replacement::None,
+            block_height: 0,
+            min_gas_limit: 0,
+            min_deployment_gas_price: 0,
+            gas_per_deploy_byte: 0,
+            min_deploy_points: 0,
+        }
+    }
+}
+
+impl AdmissionContext {
+    /// Returns the id of the transaction to evict to make room for a new
+    /// one, if the mempool is full
+    pub fn to_evict(&self) -> Option<[u8; 32]> {
+        if self.mempool_txs_count < self.max_mempool_txs_count {
+            return None;
+        }
+        self.lowest_fee.map(|(_, tx_id)| tx_id)
+    }
+}
+
+/// Check a transaction must pass to be admitted in the mempool
+pub trait AdmissionPolicy: Send + Sync {
+    fn check(
+        &self,
+        tx: &Transaction,
+        ctx: &AdmissionContext,
+    ) -> Result<(), TxAcceptanceError>;
+}
+
+/// Minimum gas prices and gas limits of the chain
+pub struct FeePolicy;
+
+impl AdmissionPolicy for FeePolicy {
+    fn check(
+        &self,
+        tx: &Transaction,
+        ctx: &AdmissionContext,
+    ) -> Result<(), TxAcceptanceError> {
+        if tx.gas_price() < 1 {
+            return Err(TxAcceptanceError::GasPriceTooLow(1));
+        }
+
+        if tx.inner.deploy().is_some() {
+            if tx.gas_price() < ctx.min_deployment_gas_price {
+                return Err(TxAcceptanceError::GasPriceTooLow(
+                    ctx.min_deployment_gas_price,
+                ));
+            }
+
+            let deploy_charge = tx
+                .inner
+                .deploy_charge(ctx.gas_per_deploy_byte, ctx.min_deploy_points);
+            if tx.inner.gas_limit() < deploy_charge {
+                return Err(TxAcceptanceError::GasLimitTooLow(deploy_charge));
+            }
+        } else if tx.inner.gas_limit() < ctx.min_gas_limit {
+            return Err(TxAcceptanceError::GasLimitTooLow(ctx.min_gas_limit));
+        }
+
+        Ok(())
+    }
+}
+
+/// Maximum number of transactions in the mempool. Once reached, only
+/// transactions paying at least the lowest fee one are admitted, evicting it.
+pub struct SizePolicy;
+
+impl AdmissionPolicy for SizePolicy {
+    fn check(
+        &self,
+        tx: &Transaction,
+        ctx: &AdmissionContext,
+    ) -> Result<(), TxAcceptanceError> {
+        if ctx.mempool_txs_count < ctx.max_mempool_txs_count {
+            return Ok(());
+        }
+
+        let (lowest_price, _) = ctx
+            .lowest_fee
+            .ok_or(anyhow::anyhow!("Cannot get lowest fee tx"))?;
+        if tx.gas_price() < lowest_price {
+            return Err(TxAcceptanceError::MaxTxnCountExceeded(
+                ctx.max_mempool_txs_count,
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Conflicts with the known transactions: duplicates, nullifiers already
+/// spent in the ledger, and mempool transactions spending the same
+/// nullifiers or nonces without being replaceable by fee
+pub struct ConflictPolicy;
+
+impl AdmissionPolicy for ConflictPolicy {
+    fn check(
+        &self,
+        _tx: &Transaction,
+        ctx: &AdmissionContext,
+    ) -> Result<(), TxAcceptanceError> {
+        if ctx.in_mempool {
+            return Err(TxAcceptanceError::AlreadyExistsInMempool);
+        }
+        if ctx.in_ledger {
+            return Err(TxAcceptanceError::AlreadyExistsInLedger);
+        }
+        if let Some(tx_id) = ctx.spent_in_ledger {
+            return Err(TxAcceptanceError::NullifierSpentInLedger(
+                hex::encode(tx_id),
+            ));
+        }
+        if let Replacement::Underpriced { min_gas_price } = ctx.replacement {
+            return Err(TxAcceptanceError::ReplacementUnderpriced(
+                min_gas_price,
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// The local policy of the forbidden transactions
+impl AdmissionPolicy for TxPolicy {
+    fn check(
+        &self,
+        tx: &Transaction,
+        _ctx: &AdmissionContext,
+    ) -> Result<(), TxAcceptanceError> {
+        if self.denies(tx, "mempool") {
+            return Err(TxAcceptanceError::ForbiddenByPolicy);
+        }
+        Ok(())
+    }
+}
+
+/// Admission rule configured by the operator
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "rule", rename_all = "snake_case")]
+pub enum AdmissionRule {
+    /// Refuse transactions paying less than `gas_price`
+    MinGasPrice { gas_price: u64 },
+    /// Refuse transactions with a gas limit above `gas_limit`
+    MaxGasLimit { gas_limit: u64 },
+    /// Refuse transactions larger than `bytes` once serialized
+    MaxTxSize { bytes: usize },
+}
+
+impl AdmissionPolicy for AdmissionRule {
+    fn check(
+        &self,
+        tx: &Transaction,
+        _ctx: &AdmissionContext,
+    ) -> Result<(), TxAcceptanceError> {
+        match *self {
+            Self::MinGasPrice { gas_price } => {
+                if tx.gas_price() < gas_price {
+                    return Err(TxAcceptanceError::GasPriceTooLow(gas_price));
+                }
+            }
+            Self::MaxGasLimit { gas_limit } => {
+                if tx.inner.gas_limit() > gas_limit {
+                    return Err(TxAcceptanceError::GasLimitTooHigh(gas_limit));
+                }
+            }
+            Self::MaxTxSize { bytes } => {
+                if tx.size().map_err(anyhow::Error::from)? > bytes {
+                    return Err(TxAcceptanceError::TooLarge(bytes));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Policies run in order, the first refusing a transaction rejecting it
+#[derive(Clone, Default)]
+pub struct PolicyStack {
+    policies: Vec<Arc<dyn AdmissionPolicy>>,
+}
+
+impl PolicyStack {
+    /// Returns the stack of the rules of the chain
+    pub fn chain() -> Self {
+        Self::default()
+            .with(FeePolicy)
+            .with(ConflictPolicy)
+            .with(SizePolicy)
+    }
+
+    /// Stacks `policy` on top of the current ones
+    pub fn with(mut self, policy: impl AdmissionPolicy + 'static) -> Self {
+        self.policies.push(Arc::new(policy));
+        self
+    }
+}
+
+impl AdmissionPolicy for PolicyStack {
+    fn check(
+        &self,
+        tx: &Transaction,
+        ctx: &AdmissionContext,
+    ) -> Result<(), TxAcceptanceError> {
+        self.policies
+            .iter()
+            .try_for_each(|policy| policy.check(tx, ctx))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use node_data::ledger::faker::gen_dummy_tx;
+
+    use super::*;
+
+    fn refused(policy: &impl AdmissionPolicy, ctx: &AdmissionContext) -> bool {
+        policy.check(&gen_dummy_tx(10), ctx).is_err()
+    }
+
+    #[test]
+    fn test_chain_policy() {
+        let chain = PolicyStack::chain();
+        let ctx = AdmissionContext::default();
+        assert!(!refused(&chain, &ctx));
+
+        let high_min_gas = AdmissionContext {
+            min_gas_limit: u64::MAX,
+            ..ctx.clone()
+        };
+        assert!(refused(&chain, &high_min_gas));
+
+        let duplicated = AdmissionContext {
+            in_ledger: true,
+            ..ctx.clone()
+        };
+        assert!(refused(&chain, &duplicated));
+
+        let double_spent = AdmissionContext {
+            spent_in_ledger: Some([2; 32]),
+            ..ctx.clone()
+        };
+        assert!(refused(&chain, &double_spent));
+
+        let underpriced = AdmissionContext {
+            replacement: Replacement::Underpriced { min_gas_price: 11 },
+            ..ctx.clone()
+        };
+        assert!(refused(&chain, &underpriced));
+
+        // A full mempool only admits transactions paying at least the lowest
+        // fee one, evicting it
+        let mut full = AdmissionContext {
+            mempool_txs_count: 5,
+            max_mempool_txs_count: 5,
+            lowest_fee: Some((10, [1; 32])),
+            ..ctx.clone()
+        };
+        assert!(!refused(&chain, &full));
+        assert_eq!(full.to_evict(), Some([1; 32]));
+        full.lowest_fee = Some((11, [1; 32]));
+        assert!(refused(&chain, &full));
+        assert_eq!(ctx.to_evict(), None);
+    }
+
+    #[test]
+    fn test_admission_rules() {
+        let ctx = AdmissionContext::default();
+        let size = gen_dummy_tx(10).size().unwrap();
+
+        let stack = PolicyStack::chain()
+            .with(AdmissionRule::MinGasPrice { gas_price: 10 })
+            .with(AdmissionRule::MaxTxSize { bytes: size });
+        assert!(!refused(&stack, &ctx));
+
+        let rules = [
+            AdmissionRule::MinGasPrice { gas_price: 11 },
+            AdmissionRule::MaxGasLimit { gas_limit: 0 },
+            AdmissionRule::MaxTxSize { bytes: size - 1 },
+        ];
+        for rule in rules {
+            assert!(refused(&stack.clone().with(rule), &ctx));
+        }
+
+        #[derive(Deserialize)]
+        struct Conf {
+            admission: Vec<AdmissionRule>,
+        }
+        let conf: Conf = toml::from_str(
+            "[[admission]]\nrule = \"max_gas_limit\"\ngas_limit = 5",
+        )
+        .unwrap();
+        assert_eq!(
+            conf.admission,
+            vec![AdmissionRule::MaxGasLimit { gas_limit: 5 }]
+        );
+    }
+}
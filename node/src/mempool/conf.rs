@@ -5,20 +5,30 @@
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
 use std::fmt::Formatter;
+use std::path::PathBuf;
 use std::time::Duration;
 
 use serde::{Deserialize, Serialize};
 
+use super::admission::AdmissionRule;
+
 /// Mempool configuration parameters
 pub const DEFAULT_EXPIRY_TIME: Duration = Duration::from_secs(3 * 60 * 60 * 24); /* 3 days */
 pub const DEFAULT_IDLE_INTERVAL: Duration = Duration::from_secs(60 * 60); /* 1 hour */
 pub const DEFAULT_DOWNLOAD_REDUNDANCY: usize = 5;
+pub const DEFAULT_MIN_REPLACEMENT_BUMP: u64 = 10;
+pub const DEFAULT_MAX_QUEUE_BYTES: usize = 64 * 1024 * 1024;
 
-#[derive(Serialize, Deserialize, Copy, Clone)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct Params {
     /// Number of pending to be processed transactions
     pub max_queue_size: usize,
 
+    /// Maximum number of bytes held by the pending to be processed
+    /// transactions. The incoming ones are dropped past it
+    #[serde(default = "default_max_queue_bytes")]
+    pub max_queue_bytes: usize,
+
     /// Maximum number of transactions that can be accepted/stored in mempool
     pub max_mempool_txn_count: usize,
 
@@ -30,18 +40,49 @@ pub struct Params {
     #[serde(with = "humantime_serde")]
     pub mempool_expiry: Option<Duration>,
 
+    /// Maximum size in bytes of the mempool transactions, above which the
+    /// lowest fee ones are evicted on idle. Set to `None` for no limit
+    #[serde(default)]
+    pub max_mempool_size: Option<usize>,
+
     /// max number of peers to request mempool from
     pub mempool_download_redundancy: Option<usize>,
+
+    /// Path to the policy of the forbidden transactions
+    #[serde(default)]
+    pub tx_policy: Option<PathBuf>,
+
+    /// Minimum gas price increase, in percent, for a transaction to replace
+    /// the mempool ones spending the same nullifiers or nonces
+    #[serde(default = "default_min_replacement_bump")]
+    pub min_replacement_bump: u64,
+
+    /// Admission rules stacked on top of the ones of the chain
+    #[serde(default)]
+    pub admission: Vec<AdmissionRule>,
+}
+
+const fn default_min_replacement_bump() -> u64 {
+    DEFAULT_MIN_REPLACEMENT_BUMP
+}
+
+const fn default_max_queue_bytes() -> usize {
+    DEFAULT_MAX_QUEUE_BYTES
 }
 
 impl Default for Params {
     fn default() -> Self {
         Self {
             max_queue_size: 1000,
+            max_queue_bytes: DEFAULT_MAX_QUEUE_BYTES,
             max_mempool_txn_count: 10_000,
             idle_interval: Some(DEFAULT_IDLE_INTERVAL),
             mempool_expiry: Some(DEFAULT_EXPIRY_TIME),
+            max_mempool_size: None,
             mempool_download_redundancy: Some(DEFAULT_DOWNLOAD_REDUNDANCY),
+            tx_policy: None,
+            min_replacement_bump: DEFAULT_MIN_REPLACEMENT_BUMP,
+            admission: vec![],
         }
     }
 }
@@ -50,13 +91,21 @@ impl std::fmt::Display for Params {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "max_queue_size: {}, max_mempool_txn_count: {},
-         idle_interval: {:?}, mempool_expiry: {:?}, mempool_download_redundancy: {:?}",
+            "max_queue_size: {}, max_queue_bytes: {},
+         max_mempool_txn_count: {}, idle_interval: {:?},
+         mempool_expiry: {:?}, max_mempool_size: {:?},
+         mempool_download_redundancy: {:?}, tx_policy: {:?},
+         min_replacement_bump: {}%, admission: {:?}",
             self.max_queue_size,
+            self.max_queue_bytes,
             self.max_mempool_txn_count,
             self.idle_interval,
             self.mempool_expiry,
-            self.mempool_download_redundancy
+            self.max_mempool_size,
+            self.mempool_download_redundancy,
+            self.tx_policy,
+            self.min_replacement_bump,
+            self.admission
         )
     }
 }
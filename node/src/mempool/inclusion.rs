@@ -0,0 +1,183 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Inclusion latency of the transactions submitted locally.
+//!
+//! The mempool marks the admission of the transactions submitted through the
+//! local RPC endpoints, and the acceptor marks their inclusion in a block.
+//! The latencies of the last [`LATENCY_WINDOW`] inclusions are kept to
+//! compute the percentiles exposed as metrics and checked by the alerts.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+
+use metrics::{gauge, histogram};
+
+/// Number of inclusion latencies the percentiles are computed over
+pub const LATENCY_WINDOW: usize = 1_000;
+
+/// Maximum number of tracked transactions waiting for inclusion. Once
+/// reached, the oldest one stops being tracked.
+pub const MAX_PENDING: usize = 10_000;
+
+static INCLUSION: LazyLock<Mutex<Inclusion>> =
+    LazyLock::new(|| Mutex::new(Inclusion::default()));
+
+/// Inclusion latency of the transactions submitted locally
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct InclusionStats {
+    /// Number of transactions waiting for inclusion
+    pub pending: usize,
+    /// Time the oldest pending transaction has been waiting for
+    pub oldest_pending: Option<Duration>,
+    /// Number of latencies the percentiles are computed over
+    pub samples: usize,
+    pub p50: Option<Duration>,
+    pub p90: Option<Duration>,
+    pub p99: Option<Duration>,
+}
+
+#[derive(Default)]
+struct Inclusion {
+    pending: HashMap<[u8; 32], Instant>,
+    latencies: VecDeque<Duration>,
+}
+
+impl Inclusion {
+    fn admitted(&mut self, tx_id: [u8; 32], now: Instant) {
+        if self.pending.len() >= MAX_PENDING {
+            let oldest = self
+                .pending
+                .iter()
+                .min_by_key(|(_, admitted)| **admitted)
+                .map(|(id, _)| *id);
+            if let Some(oldest) = oldest {
+                self.pending.remove(&oldest);
+            }
+        }
+        self.pending.entry(tx_id).or_insert(now);
+    }
+
+    /// Records the inclusion of `tx_id`, returning its latency if it was
+    /// tracked
+    fn included(&mut self, tx_id: &[u8; 32], now: Instant) -> Option<Duration> {
+        let admitted = self.pending.remove(tx_id)?;
+        let latency = now.duration_since(admitted);
+
+        if self.latencies.len() >= LATENCY_WINDOW {
+            self.latencies.pop_front();
+        }
+        self.latencies.push_back(latency);
+        Some(latency)
+    }
+
+    fn stats(&self, now: Instant) -> InclusionStats {
+        let mut sorted: Vec<_> = self.latencies.iter().copied().collect();
+        sorted.sort_unstable();
+        let percentile = |p: usize| {
+            let rank = (sorted.len() * p).div_ceil(100);
+            sorted.get(rank.saturating_sub(1)).copied()
+        };
+
+        InclusionStats {
+            pending: self.pending.len(),
+            oldest_pending: self
+                .pending
+                .values()
+                .min()
+                .map(|admitted| now.duration_since(*admitted)),
+            samples: sorted.len(),
+            p50: percentile(50),
+            p90: percentile(90),
+            p99: percentile(99),
+        }
+    }
+}
+
+/// Marks the admission in the mempool of a transaction submitted locally
+pub(crate) fn mark_admitted(tx_id: [u8; 32]) {
+    let mut inclusion = INCLUSION.lock().expect("lock not to be poisoned");
+    inclusion.admitted(tx_id, Instant::now());
+    gauge!("dusk_local_tx_pending").set(inclusion.pending.len() as f64);
+}
+
+/// Marks the inclusion in an accepted block of the given transactions,
+/// recording the latency of the ones submitted locally
+pub(crate) fn mark_included(tx_ids: impl Iterator<Item = [u8; 32]>) {
+    let now = Instant::now();
+    let mut inclusion = INCLUSION.lock().expect("lock not to be poisoned");
+
+    let mut recorded = false;
+    for tx_id in tx_ids {
+        if let Some(latency) = inclusion.included(&tx_id, now) {
+            histogram!("dusk_local_tx_inclusion_time").record(latency);
+            recorded = true;
+        }
+    }
+    if !recorded {
+        return;
+    }
+
+    let stats = inclusion.stats(now);
+    let secs = |d: Option<Duration>| d.unwrap_or_default().as_secs_f64();
+    gauge!("dusk_local_tx_pending").set(stats.pending as f64);
+    gauge!("dusk_local_tx_inclusion_p50").set(secs(stats.p50));
+    gauge!("dusk_local_tx_inclusion_p90").set(secs(stats.p90));
+    gauge!("dusk_local_tx_inclusion_p99").set(secs(stats.p99));
+}
+
+/// Stops tracking a transaction removed from the mempool without being
+/// included
+pub(crate) fn mark_dropped(tx_id: &[u8; 32]) {
+    let mut inclusion = INCLUSION.lock().expect("lock not to be poisoned");
+    if inclusion.pending.remove(tx_id).is_some() {
+        gauge!("dusk_local_tx_pending").set(inclusion.pending.len() as f64);
+    }
+}
+
+/// Returns the inclusion latency of the transactions submitted locally
+pub fn inclusion_stats() -> InclusionStats {
+    let inclusion = INCLUSION.lock().expect("lock not to be poisoned");
+    inclusion.stats(Instant::now())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inclusion() {
+        let mut inclusion = Inclusion::default();
+        let start = Instant::now();
+        let secs = Duration::from_secs;
+
+        for i in 0..10u8 {
+            inclusion.admitted([i; 32], start);
+        }
+        // Transactions admitted again keep their first admission time
+        inclusion.admitted([0; 32], start + secs(5));
+
+        for i in 0..9u8 {
+            let latency = inclusion.included(&[i; 32], start + secs(i as u64));
+            assert_eq!(latency, Some(secs(i as u64)));
+        }
+        // Transactions not submitted locally are ignored
+        assert_eq!(inclusion.included(&[42; 32], start), None);
+
+        let stats = inclusion.stats(start + secs(20));
+        assert_eq!(stats.pending, 1);
+        assert_eq!(stats.oldest_pending, Some(secs(20)));
+        assert_eq!(stats.samples, 9);
+        assert_eq!(stats.p50, Some(secs(4)));
+        assert_eq!(stats.p90, Some(secs(8)));
+        assert_eq!(stats.p99, Some(secs(8)));
+
+        let empty = Inclusion::default().stats(start);
+        assert_eq!(empty.oldest_pending, None);
+        assert_eq!(empty.p50, None);
+    }
+}
@@ -0,0 +1,236 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Local transaction policy.
+//!
+//! The policy lists the transactions the node refuses to handle, e.g. to
+//! comply with an emergency advisory: specific transaction ids, nullifiers,
+//! or calls to a contract (optionally restricted to one of its functions).
+//!
+//! A forbidden transaction is neither admitted in the mempool nor included in
+//! the candidates generated by the node. Blocks generated by others are still
+//! accepted, whatever transactions they include.
+//!
+//! The policy is read from a TOML file:
+//!
+//! ```toml
+//! tx_ids = ["<hex>"]
+//! nullifiers = ["<hex>"]
+//!
+//! [[calls]]
+//! contract = "<hex>"
+//! fn_name = "transfer" # optional, every function if omitted
+//! ```
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use dusk_core::abi::ContractId;
+use node_data::ledger::{SpendingId, Transaction};
+use serde::Deserialize;
+use tracing::warn;
+
+#[derive(Deserialize)]
+struct CallRule {
+    contract: String,
+    fn_name: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct PolicyFile {
+    #[serde(default)]
+    tx_ids: Vec<String>,
+    #[serde(default)]
+    nullifiers: Vec<String>,
+    #[serde(default)]
+    calls: Vec<CallRule>,
+    /// Fields unknown to this version, e.g. rules added by a later one, which
+    /// are ignored
+    #[serde(flatten)]
+    unknown: HashMap<String, toml::Value>,
+}
+
+#[derive(Default)]
+struct Rules {
+    tx_ids: HashSet<[u8; 32]>,
+    nullifiers: HashSet<[u8; 32]>,
+    /// Forbidden functions of each contract, `None` forbidding them all
+    calls: HashMap<ContractId, Option<HashSet<String>>>,
+}
+
+/// Rule of the policy matched by a transaction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PolicyHit {
+    TxId,
+    Nullifier([u8; 32]),
+    Call(ContractId, String),
+}
+
+impl fmt::Display for PolicyHit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TxId => write!(f, "forbidden tx id"),
+            Self::Nullifier(n) => {
+                write!(f, "forbidden nullifier {}", hex::encode(n))
+            }
+            Self::Call(contract, fn_name) => {
+                write!(f, "forbidden call {contract}::{fn_name}")
+            }
+        }
+    }
+}
+
+/// Transaction policy, cheap to clone and share among services
+#[derive(Clone, Default)]
+pub struct TxPolicy {
+    rules: Arc<Rules>,
+}
+
+fn parse_hash(hex_str: &str) -> anyhow::Result<[u8; 32]> {
+    hex::decode(hex_str)?
+        .try_into()
+        .map_err(|_| anyhow!("{hex_str} is not 32 bytes long"))
+}
+
+impl TxPolicy {
+    /// Loads the policy from the TOML file at `path`
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        Self::from_toml(&content)
+            .map_err(|e| anyhow!("invalid tx policy {path:?}: {e}"))
+    }
+
+    fn from_toml(content: &str) -> anyhow::Result<Self> {
+        let file: PolicyFile = toml::from_str(content)?;
+        for field in file.unknown.keys() {
+            warn!(event = "unknown tx policy field ignored", field);
+        }
+
+        let mut rules = Rules::default();
+        for tx_id in &file.tx_ids {
+            rules.tx_ids.insert(parse_hash(tx_id)?);
+        }
+        for nullifier in &file.nullifiers {
+            rules.nullifiers.insert(parse_hash(nullifier)?);
+        }
+        for call in file.calls {
+            let contract = ContractId::from_bytes(parse_hash(&call.contract)?);
+            let fn_names = rules
+                .calls
+                .entry(contract)
+                .or_insert_with(|| Some(HashSet::new()));
+            match (fn_names, call.fn_name) {
+                (Some(fn_names), Some(fn_name)) => {
+                    fn_names.insert(fn_name);
+                }
+                (fn_names, _) => *fn_names = None,
+            }
+        }
+
+        Ok(Self {
+            rules: Arc::new(rules),
+        })
+    }
+
+    /// Returns the rule forbidding `tx`, if any
+    pub fn check(&self, tx: &Transaction) -> Option<PolicyHit> {
+        let rules = &self.rules;
+
+        if rules.tx_ids.contains(&tx.id()) {
+            return Some(PolicyHit::TxId);
+        }
+
+        if !rules.nullifiers.is_empty() {
+            for spend_id in tx.to_spend_ids() {
+                if let SpendingId::Nullifier(n) = spend_id {
+                    if rules.nullifiers.contains(&n) {
+                        return Some(PolicyHit::Nullifier(n));
+                    }
+                }
+            }
+        }
+
+        let call = tx.inner.call()?;
+        let forbidden = match rules.calls.get(&call.contract)? {
+            None => true,
+            Some(fn_names) => fn_names.contains(&call.fn_name),
+        };
+        forbidden.then(|| PolicyHit::Call(call.contract, call.fn_name.clone()))
+    }
+
+    /// Returns `true` if `tx` is forbidden, auditing the hit.
+    ///
+    /// `stage` is where the policy was enforced.
+    pub fn denies(&self, tx: &Transaction, stage: &'static str) -> bool {
+        match self.check(tx) {
+            Some(hit) => {
+                warn!(
+                    event = "tx policy hit",
+                    stage,
+                    tx_id = hex::encode(tx.id()),
+                    rule = %hit,
+                );
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use node_data::ledger::faker::gen_dummy_tx;
+
+    use super::*;
+
+    #[test]
+    fn forbidden_txs_are_matched() {
+        let tx = gen_dummy_tx(1);
+        let SpendingId::Nullifier(nullifier) = tx.to_spend_ids()[1] else {
+            panic!("phoenix tx spends nullifiers");
+        };
+
+        assert_eq!(TxPolicy::default().check(&tx), None);
+
+        let policy = format!(r#"tx_ids = ["{}"]"#, hex::encode(tx.id()));
+        let policy = TxPolicy::from_toml(&policy).unwrap();
+        assert_eq!(policy.check(&tx), Some(PolicyHit::TxId));
+
+        let policy = format!(r#"nullifiers = ["{}"]"#, hex::encode(nullifier));
+        let policy = TxPolicy::from_toml(&policy).unwrap();
+        assert_eq!(policy.check(&tx), Some(PolicyHit::Nullifier(nullifier)));
+
+        let contract = hex::encode([21; 32]);
+        let policy = format!(
+            "[[calls]]\ncontract = \"{contract}\"\nfn_name = \"other_method\""
+        );
+        let policy = TxPolicy::from_toml(&policy).unwrap();
+        assert_eq!(policy.check(&tx), None);
+
+        let policy = format!("[[calls]]\ncontract = \"{contract}\"");
+        let policy = TxPolicy::from_toml(&policy).unwrap();
+        assert_eq!(
+            policy.check(&tx),
+            Some(PolicyHit::Call(
+                ContractId::from_bytes([21; 32]),
+                "some_method".into()
+            ))
+        );
+
+        assert!(TxPolicy::from_toml(r#"tx_ids = ["00"]"#).is_err());
+
+        // Unknown fields are ignored, for forward compatibility
+        let policy = format!(
+            "tx_ids = [\"{}\"]\nsenders = [\"00\"]",
+            hex::encode(tx.id())
+        );
+        let policy = TxPolicy::from_toml(&policy).unwrap();
+        assert_eq!(policy.check(&tx), Some(PolicyHit::TxId));
+    }
+}
@@ -4,24 +4,43 @@
 //
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
-use std::net::{AddrParseError, SocketAddr};
-use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::Arc;
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+use std::ops::Deref;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use async_trait::async_trait;
+use dusk_consensus::config::MAX_BLOCK_SIZE;
 use kadcast::config::Config;
 use kadcast::{MessageInfo, Peer};
-use metrics::counter;
+use metrics::{counter, gauge};
 use node_data::message::payload::{GetResource, Inv, Nonce};
-use node_data::message::{AsyncQueue, Metadata, PROTOCOL_VERSION};
+use node_data::message::{
+    AsyncQueue, Metadata, Payload, VoteMsgRef, PROTOCOL_VERSION,
+};
 use node_data::{get_current_timestamp, Serializable};
+use tokio::runtime::Runtime;
 use tokio::sync::RwLock;
 use tracing::{debug, error, info, trace, warn};
 
 use crate::{BoxedFilter, Message};
 
-/// Number of alive peers randomly selected which a `flood_request` is sent to
-const REDUNDANCY_PEER_COUNT: usize = 8;
+pub mod contribution;
+#[cfg(any(test, feature = "mock-network"))]
+pub mod mock;
+pub mod redundancy;
+pub mod reputation;
+pub mod signers;
+
+use redundancy::Redundancy;
+
+/// Number of spawned jobs rerouting a message to its queue
+static REROUTE_JOBS: AtomicU64 = AtomicU64::new(0);
+
+/// Time given to the tasks of a replaced peer to release its sockets
+const PEER_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
 
 type RoutesList<const N: usize> = [Option<AsyncQueue<Message>>; N];
 type FilterList<const N: usize> = [Option<BoxedFilter>; N];
@@ -29,18 +48,55 @@ type FilterList<const N: usize> = [Option<BoxedFilter>; N];
 pub struct Listener<const N: usize> {
     routes: Arc<RwLock<RoutesList<N>>>,
     filters: Arc<RwLock<FilterList<N>>>,
+    redundancy: Arc<Mutex<Redundancy>>,
+    /// Set once the peer of the listener is replaced
+    muted: Arc<AtomicBool>,
 }
 
 impl<const N: usize> Listener<N> {
     fn reroute(&self, topic: u8, msg: Message) {
         let routes = self.routes.clone();
+        let topic_name = format!("{:?}", msg.topic());
+
+        let jobs = REROUTE_JOBS.fetch_add(1, Ordering::Relaxed) + 1;
+        gauge!("dusk_reroute_jobs").set(jobs as f64);
+
         tokio::spawn(async move {
             if let Some(Some(queue)) = routes.read().await.get(topic as usize) {
                 queue.try_send(msg);
+                Self::record_queue_metrics(&topic_name, queue);
             };
+
+            let jobs = REROUTE_JOBS.fetch_sub(1, Ordering::Relaxed) - 1;
+            gauge!("dusk_reroute_jobs").set(jobs as f64);
         });
     }
 
+    /// Records depth, capacity, drops, consumer lag and memory usage of the
+    /// queue consuming `topic`
+    fn record_queue_metrics(topic: &str, queue: &AsyncQueue<Message>) {
+        gauge!(format!("dusk_queue_{topic}_depth")).set(queue.len() as f64);
+        gauge!(format!("dusk_queue_{topic}_capacity"))
+            .set(queue.capacity() as f64);
+        counter!(format!("dusk_queue_{topic}_dropped"))
+            .absolute(queue.dropped());
+        gauge!(format!("dusk_queue_{topic}_lag"))
+            .set(queue.consumer_lag().as_secs_f64());
+        gauge!(format!("dusk_queue_{topic}_bytes"))
+            .set(queue.memory_usage() as f64);
+        if let Some(budget) = queue.memory_budget() {
+            gauge!(format!("dusk_queue_{topic}_budget")).set(budget as f64);
+        }
+    }
+
+    /// Decodes a validation or a ratification in place, reusing the key of
+    /// its signer if already known
+    fn decode_vote(vote: VoteMsgRef<'_>) -> io::Result<Message> {
+        let signer = signers::signer_key(vote.signer())
+            .map_err(|_| io::Error::from(io::ErrorKind::InvalidData))?;
+        vote.decode(signer)
+    }
+
     fn call_filters(
         &self,
         topic: impl Into<u8>,
@@ -57,8 +113,26 @@ impl<const N: usize> Listener<N> {
 
 impl<const N: usize> kadcast::NetworkListen for Listener<N> {
     fn on_message(&self, blob: Vec<u8>, md: MessageInfo) {
+        if self.muted.load(Ordering::Relaxed) {
+            return;
+        }
+
+        if reputation::is_banned(&md.src().ip()) {
+            counter!("dusk_banned_msgs_dropped").increment(1);
+            return;
+        }
+
+        if reputation::is_flooding(md.src().ip()) {
+            counter!("dusk_flood_msgs_dropped").increment(1);
+            return;
+        }
+
         let msg_size = blob.len();
-        match Message::read(&mut &blob.to_vec()[..]) {
+        let msg = match VoteMsgRef::new(&blob) {
+            Some(vote) => Self::decode_vote(vote),
+            None => Message::read(&mut &blob[..]),
+        };
+        match msg {
             Ok(mut msg) => {
                 counter!("dusk_bytes_recv").increment(msg_size as u64);
                 counter!(format!("dusk_inbound_{:?}_size", msg.topic()))
@@ -82,6 +156,30 @@ impl<const N: usize> kadcast::NetworkListen for Listener<N> {
                     iteration = msg.get_iteration(),
                 );
 
+                // Early reject oversize candidates before queueing them
+                if let Payload::Candidate(c) = &msg.payload {
+                    let size = c.candidate.size().unwrap_or(usize::MAX);
+                    if size > *MAX_BLOCK_SIZE {
+                        counter!("dusk_oversize_blocks_rejected").increment(1);
+                        reputation::penalize(
+                            md.src().ip(),
+                            reputation::PENALTY_OVERSIZE,
+                            "oversize candidate",
+                        );
+                        info!(
+                            event = "discard oversize candidate",
+                            src = ?md.src(),
+                            size,
+                        );
+                        return;
+                    }
+                }
+
+                if let Ok(mut redundancy) = self.redundancy.lock() {
+                    redundancy.on_received(&msg.payload, Instant::now());
+                }
+                contribution::on_received(md.src(), &msg.payload);
+
                 // Update Transport Data
                 msg.metadata = Some(Metadata {
                     height: md.height(),
@@ -102,16 +200,82 @@ impl<const N: usize> kadcast::NetworkListen for Listener<N> {
                 // Dump message blob and topic number
                 let topic = blob.get(node_data::message::TOPIC_FIELD_POS);
                 error!("err: {err}, msg_topic: {topic:?}",);
+                reputation::penalize(
+                    md.src().ip(),
+                    reputation::PENALTY_MALFORMED,
+                    "malformed message",
+                );
             }
         };
     }
 }
 
-pub struct Kadcast<const N: usize> {
+/// Kadcast peer running on a dedicated runtime.
+///
+/// Kadcast can't stop a peer: shutting its runtime down cancels the tasks of
+/// the peer, releasing its sockets.
+struct PeerRuntime {
     peer: Peer,
+    runtime: Option<Runtime>,
+}
+
+impl PeerRuntime {
+    fn new<const N: usize>(
+        conf: Config,
+        listener: Listener<N>,
+    ) -> anyhow::Result<Self> {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .thread_name("kadcast")
+            .enable_all()
+            .build()?;
+        let peer = {
+            let _guard = runtime.enter();
+            Peer::new(conf, listener)?
+        };
+
+        Ok(Self {
+            peer,
+            runtime: Some(runtime),
+        })
+    }
+
+    /// Stops the tasks of the peer, waiting for them to release its sockets
+    async fn shutdown(&mut self) {
+        if let Some(runtime) = self.runtime.take() {
+            let shutdown = tokio::task::spawn_blocking(move || {
+                runtime.shutdown_timeout(PEER_SHUTDOWN_TIMEOUT)
+            });
+            if let Err(err) = shutdown.await {
+                warn!(event = "peer shutdown failed", %err);
+            }
+        }
+    }
+}
+
+impl Deref for PeerRuntime {
+    type Target = Peer;
+
+    fn deref(&self) -> &Peer {
+        &self.peer
+    }
+}
+
+impl Drop for PeerRuntime {
+    fn drop(&mut self) {
+        if let Some(runtime) = self.runtime.take() {
+            runtime.shutdown_background();
+        }
+    }
+}
+
+pub struct Kadcast<const N: usize> {
+    peer: PeerRuntime,
     routes: Arc<RwLock<RoutesList<N>>>,
     filters: Arc<RwLock<FilterList<N>>>,
     conf: Config,
+    redundancy: Arc<Mutex<Redundancy>>,
+    /// Muting flag of the listener of `peer`
+    muted: Arc<AtomicBool>,
 
     /// Represents a parsed conf.public_addr
     public_addr: SocketAddr,
@@ -120,24 +284,29 @@ pub struct Kadcast<const N: usize> {
 }
 
 impl<const N: usize> Kadcast<N> {
-    pub fn new(mut conf: Config) -> Result<Self, AddrParseError> {
+    pub fn new(mut conf: Config) -> anyhow::Result<Self> {
         const INIT: Option<AsyncQueue<Message>> = None;
         let routes = Arc::new(RwLock::new([INIT; N]));
 
         const INIT_FN: Option<BoxedFilter> = None;
         let filters = Arc::new(RwLock::new([INIT_FN; N]));
 
+        let redundancy = Arc::new(Mutex::new(Redundancy::default()));
+
         info!(
             "Loading network with public_address {} and private_address {:?}",
             &conf.public_address, &conf.listen_address
         );
+        let muted = Arc::new(AtomicBool::new(false));
         let listener = Listener {
             routes: routes.clone(),
             filters: filters.clone(),
+            redundancy: redundancy.clone(),
+            muted: muted.clone(),
         };
         conf.version = format!("{PROTOCOL_VERSION}");
         conf.version_match = format!("{PROTOCOL_VERSION}");
-        let peer = Peer::new(conf.clone(), listener)?;
+        let peer = PeerRuntime::new(conf.clone(), listener)?;
         let public_addr = conf
             .public_address
             .parse::<SocketAddr>()
@@ -150,11 +319,73 @@ impl<const N: usize> Kadcast<N> {
             filters,
             peer,
             conf,
+            redundancy,
+            muted,
             public_addr,
             counter: AtomicU64::new(nonce.into()),
         })
     }
 
+    /// Replaces the peer with one built from `conf`, e.g. after a change of
+    /// public address or of bootstrapping nodes.
+    ///
+    /// The routes and filters registered by the upper layers are kept, so
+    /// that the services keep running untouched.
+    ///
+    /// The previous peer is muted and shut down before the new one binds its
+    /// sockets, so that the listen address can be kept. If the new peer can't
+    /// be built, the previous configuration is restored.
+    pub async fn restart(&mut self, mut conf: Config) -> anyhow::Result<()> {
+        let public_addr = conf.public_address.parse::<SocketAddr>()?;
+        conf.version = format!("{PROTOCOL_VERSION}");
+        conf.version_match = format!("{PROTOCOL_VERSION}");
+
+        info!(
+            "Restarting network with public_address {} and private_address {:?}",
+            &conf.public_address, &conf.listen_address
+        );
+        self.muted.store(true, Ordering::Relaxed);
+        self.peer.shutdown().await;
+
+        match self.start_peer(conf.clone()) {
+            Ok(()) => {
+                self.conf = conf;
+                self.public_addr = public_addr;
+                Ok(())
+            }
+            Err(err) => {
+                warn!(event = "restoring previous network", %err);
+                self.start_peer(self.conf.clone())?;
+                Err(err)
+            }
+        }
+    }
+
+    /// Starts a peer built from `conf`, with a listener feeding the current
+    /// routes and filters
+    fn start_peer(&mut self, conf: Config) -> anyhow::Result<()> {
+        let muted = Arc::new(AtomicBool::new(false));
+        let listener = Listener {
+            routes: self.routes.clone(),
+            filters: self.filters.clone(),
+            redundancy: self.redundancy.clone(),
+            muted: muted.clone(),
+        };
+
+        self.peer = PeerRuntime::new(conf.clone(), listener)?;
+        self.muted = muted;
+        Ok(())
+    }
+
+    /// Sets the parameters of the resource discovery redundancy
+    pub fn with_redundancy(self, params: redundancy::Params) -> Self {
+        info!("Loading network with redundancy {params:?}");
+        if let Ok(mut redundancy) = self.redundancy.lock() {
+            *redundancy = Redundancy::new(params);
+        }
+        self
+    }
+
     pub fn route_internal(&self, msg: Message) {
         let topic = msg.topic() as usize;
         let routes = self.routes.clone();
@@ -243,6 +474,10 @@ impl<const N: usize> crate::Network for Kadcast<N> {
     /// * `ttl_as_sec` - Defines the lifespan of the request in seconds
     ///
     /// * `hops_limit` - Defines maximum number of hops to receive the request
+    ///
+    /// The number of peers the request is sent to is adapted to the outcome
+    /// of the previous requests and to the number of alive nodes, see
+    /// [`redundancy`].
     async fn flood_request(
         &self,
         msg_inv: &Inv,
@@ -258,8 +493,26 @@ impl<const N: usize> crate::Network for Kadcast<N> {
             ttl_as_sec,
             hops_limit,
         );
-        self.send_to_alive_peers(msg.into(), REDUNDANCY_PEER_COUNT)
-            .await
+        let now = Instant::now();
+        let stale = self
+            .redundancy
+            .lock()
+            .map_or(false, |redundancy| redundancy.alive_nodes_stale(now));
+        if stale {
+            let count = self.alive_nodes_count().await;
+            if let Ok(mut redundancy) = self.redundancy.lock() {
+                redundancy.on_alive_nodes(count, now);
+            }
+        }
+
+        let peer_count = match self.redundancy.lock() {
+            Ok(mut redundancy) => {
+                redundancy.on_request(msg_inv, now);
+                redundancy.peer_count()
+            }
+            Err(_) => redundancy::DEFAULT_INITIAL_PEERS,
+        };
+        self.send_to_alive_peers(msg.into(), peer_count).await
     }
 
     /// Sends an encoded message to a given peer.
@@ -385,4 +638,81 @@ impl<const N: usize> crate::Network for Kadcast<N> {
         // TODO: This call should be replaced with no-copy Kadcast API
         self.peer.alive_nodes(u16::MAX as usize).await.len()
     }
+
+    fn ban_peer(&self, addr: IpAddr, reason: &str) {
+        reputation::penalize(addr, reputation::BAN_THRESHOLD, reason);
+    }
+
+    fn unban_peer(&self, addr: &IpAddr) {
+        reputation::forgive(addr);
+    }
+
+    fn banned_peers(&self) -> Vec<(IpAddr, u64)> {
+        reputation::banned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::UdpSocket;
+
+    use super::*;
+
+    /// Time given to a peer to bind its sockets
+    const BIND_DELAY: Duration = Duration::from_millis(200);
+
+    fn free_address() -> String {
+        let socket = UdpSocket::bind("127.0.0.1:0").expect("socket to bind");
+        socket.local_addr().expect("socket address").to_string()
+    }
+
+    fn config(public_address: &str) -> Config {
+        Config {
+            public_address: public_address.to_string(),
+            listen_address: None,
+            bootstrapping_nodes: vec![],
+            ..Config::default()
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_restart() {
+        let first = free_address();
+        let mut network =
+            Kadcast::<255>::new(config(&first)).expect("network to start");
+        tokio::time::sleep(BIND_DELAY).await;
+        assert!(UdpSocket::bind(&first).is_err());
+
+        // The same address is bound again by the new peer
+        let muted = network.muted.clone();
+        network
+            .restart(config(&first))
+            .await
+            .expect("restart on the same address");
+        tokio::time::sleep(BIND_DELAY).await;
+        assert!(muted.load(Ordering::Relaxed));
+        assert!(!network.muted.load(Ordering::Relaxed));
+        assert!(UdpSocket::bind(&first).is_err());
+
+        // The replaced peer releases its address
+        let second = free_address();
+        network
+            .restart(config(&second))
+            .await
+            .expect("restart on another address");
+        tokio::time::sleep(BIND_DELAY).await;
+        assert_eq!(network.conf().public_address, second);
+        UdpSocket::bind(&first).expect("replaced peer to release its address");
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_restart_invalid_address() {
+        let address = free_address();
+        let mut network =
+            Kadcast::<255>::new(config(&address)).expect("network to start");
+
+        assert!(network.restart(config("invalid")).await.is_err());
+        assert_eq!(network.conf().public_address, address);
+        assert!(!network.muted.load(Ordering::Relaxed));
+    }
 }
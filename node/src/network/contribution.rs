@@ -0,0 +1,244 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Contribution of the peers to the gossip.
+//!
+//! For each block, candidate and quorum received, the peer delivering it
+//! first is credited with a first delivery, and the peers delivering it again
+//! with a duplicate. The first deliverer of a block is credited again once
+//! the block is accepted.
+//!
+//! The usefulness of a peer is the ratio of its first deliveries over all of
+//! its deliveries. Peers delivering little, or mostly duplicates, are
+//! candidates for removal from the bootstrapping nodes, or misconfigured.
+//!
+//! The [`MAX_TRACKED`] last messages are tracked, and the statistics of at
+//! most [`MAX_PEERS`] peers kept, the least recently seen being evicted
+//! first.
+
+use std::collections::{HashMap, VecDeque};
+use std::net::SocketAddr;
+use std::sync::{LazyLock, Mutex};
+
+use node_data::get_current_timestamp;
+use node_data::ledger::Hash;
+use node_data::message::Payload;
+use serde::Serialize;
+
+/// Number of messages whose first deliverer is tracked
+const MAX_TRACKED: usize = 10_000;
+
+/// Maximum number of peers whose statistics are kept
+const MAX_PEERS: usize = 1_000;
+
+static CONTRIBUTION: LazyLock<Mutex<Contribution>> =
+    LazyLock::new(|| Mutex::new(Contribution::default()));
+
+/// Identity of a gossiped message
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Gossip {
+    Block(Hash),
+    Candidate(Hash),
+    Quorum(u64, u8, Hash),
+}
+
+impl Gossip {
+    fn from_payload(payload: &Payload) -> Option<Self> {
+        match payload {
+            Payload::Block(b) => Some(Self::Block(b.header().hash)),
+            Payload::Candidate(c) => {
+                Some(Self::Candidate(c.candidate.header().hash))
+            }
+            Payload::Quorum(q) => Some(Self::Quorum(
+                q.header.round,
+                q.header.iteration,
+                q.header.prev_block_hash,
+            )),
+            _ => None,
+        }
+    }
+}
+
+/// Gossip statistics of a peer
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct PeerContribution {
+    pub peer: String,
+    /// Blocks first delivered by the peer
+    pub blocks: u64,
+    /// Blocks first delivered by the peer, and accepted
+    pub accepted_blocks: u64,
+    /// Candidates first delivered by the peer
+    pub candidates: u64,
+    /// Quorums first delivered by the peer
+    pub quorums: u64,
+    /// Messages delivered by the peer after another one
+    pub duplicates: u64,
+    /// Ratio of the first deliveries over all deliveries
+    pub usefulness: f64,
+    /// Timestamp of the last delivery
+    pub last_seen: u64,
+}
+
+impl PeerContribution {
+    fn first_deliveries(&self) -> u64 {
+        self.blocks + self.candidates + self.quorums
+    }
+
+    fn update_usefulness(&mut self) {
+        let first = self.first_deliveries();
+        let total = first + self.duplicates;
+        self.usefulness = if total == 0 {
+            0.0
+        } else {
+            first as f64 / total as f64
+        };
+    }
+}
+
+#[derive(Debug, Default)]
+struct Contribution {
+    peers: HashMap<SocketAddr, PeerContribution>,
+    /// First deliverer of the tracked messages
+    first: HashMap<Gossip, SocketAddr>,
+    /// Tracked messages, oldest first
+    tracked: VecDeque<Gossip>,
+}
+
+impl Contribution {
+    fn on_received(&mut self, src: SocketAddr, payload: &Payload, now: u64) {
+        let Some(gossip) = Gossip::from_payload(payload) else {
+            return;
+        };
+        let first = !self.first.contains_key(&gossip);
+        if first {
+            if self.tracked.len() == MAX_TRACKED {
+                if let Some(oldest) = self.tracked.pop_front() {
+                    self.first.remove(&oldest);
+                }
+            }
+            self.first.insert(gossip, src);
+            self.tracked.push_back(gossip);
+        }
+
+        if !self.peers.contains_key(&src) && self.peers.len() == MAX_PEERS {
+            let least_recent = self
+                .peers
+                .iter()
+                .min_by_key(|(_, p)| p.last_seen)
+                .map(|(addr, _)| *addr);
+            if let Some(addr) = least_recent {
+                self.peers.remove(&addr);
+            }
+        }
+        let peer = self.peers.entry(src).or_insert_with(|| PeerContribution {
+            peer: src.to_string(),
+            ..Default::default()
+        });
+        match (first, gossip) {
+            (false, _) => peer.duplicates += 1,
+            (true, Gossip::Block(_)) => peer.blocks += 1,
+            (true, Gossip::Candidate(_)) => peer.candidates += 1,
+            (true, Gossip::Quorum(..)) => peer.quorums += 1,
+        }
+        peer.last_seen = now;
+        peer.update_usefulness();
+    }
+
+    fn on_accepted(&mut self, hash: &Hash) {
+        let Some(src) = self.first.get(&Gossip::Block(*hash)) else {
+            return;
+        };
+        if let Some(peer) = self.peers.get_mut(src) {
+            peer.accepted_blocks += 1;
+        }
+    }
+
+    fn contributions(&self) -> Vec<PeerContribution> {
+        let mut contributions: Vec<_> = self.peers.values().cloned().collect();
+        contributions.sort_by(|a, b| {
+            b.first_deliveries()
+                .cmp(&a.first_deliveries())
+                .then_with(|| a.peer.cmp(&b.peer))
+        });
+        contributions
+    }
+}
+
+/// Credits `src` with the delivery of `payload`, if it's a block, a
+/// candidate or a quorum
+pub fn on_received(src: SocketAddr, payload: &Payload) {
+    if let Ok(mut contribution) = CONTRIBUTION.lock() {
+        contribution.on_received(src, payload, get_current_timestamp());
+    }
+}
+
+/// Credits the first deliverer of the block `hash` with its acceptance
+pub fn on_accepted(hash: &Hash) {
+    if let Ok(mut contribution) = CONTRIBUTION.lock() {
+        contribution.on_accepted(hash);
+    }
+}
+
+/// Returns the gossip statistics of the peers, the most contributing first
+pub fn contributions() -> Vec<PeerContribution> {
+    CONTRIBUTION
+        .lock()
+        .map(|contribution| contribution.contributions())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use fake::{Fake, Faker};
+    use node_data::ledger::Block;
+    use node_data::message::payload::Quorum;
+
+    use super::*;
+
+    fn addr(ip: u8) -> SocketAddr {
+        SocketAddr::from(([10, 0, 0, ip], 9000))
+    }
+
+    #[test]
+    fn test_contribution() {
+        let mut contribution = Contribution::default();
+        let block: Block = Faker.fake();
+        let block = Payload::Block(Box::new(block));
+        let quorum = Payload::Quorum(Quorum {
+            header: Faker.fake(),
+            att: Faker.fake(),
+        });
+
+        contribution.on_received(addr(1), &block, 1);
+        contribution.on_received(addr(2), &block, 2);
+        contribution.on_received(addr(2), &quorum, 3);
+        contribution.on_received(addr(1), &quorum, 4);
+        contribution.on_received(addr(3), &quorum, 5);
+
+        let Payload::Block(b) = &block else {
+            unreachable!()
+        };
+        contribution.on_accepted(&b.header().hash);
+
+        let stats = contribution.contributions();
+        assert_eq!(stats.len(), 3);
+
+        let first = &stats[0];
+        assert_eq!(first.peer, addr(1).to_string());
+        assert_eq!((first.blocks, first.accepted_blocks), (1, 1));
+        assert_eq!((first.quorums, first.duplicates), (0, 1));
+        assert_eq!(first.usefulness, 0.5);
+
+        let second = &stats[1];
+        assert_eq!(second.peer, addr(2).to_string());
+        assert_eq!((second.blocks, second.accepted_blocks), (0, 0));
+        assert_eq!((second.quorums, second.duplicates), (1, 1));
+
+        let third = &stats[2];
+        assert_eq!((third.duplicates, third.usefulness), (1, 0.0));
+        assert_eq!(third.last_seen, 5);
+    }
+}
@@ -0,0 +1,459 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! In-process implementation of [`Network`], for tests running several
+//! nodes without Kadcast sockets.
+//!
+//! The nodes connected to the same [`MockHub`] exchange their messages
+//! through it. A broadcast reaches every other node in a single hop, which
+//! doesn't propagate it further. The hub delays every message by its latency
+//! and drops a share of them according to its drop rate, using a seeded rng
+//! so that the drops are reproducible.
+//!
+//! Every node records the messages it sends, see [`MockNetwork::history`].
+
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use node_data::get_current_timestamp;
+use node_data::message::payload::{GetResource, Inv};
+use node_data::message::{AsyncQueue, Message, Metadata};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+use super::reputation::BAN_DURATION;
+use crate::{BoxedFilter, Network};
+
+/// Number of topics a node can route
+const TOPICS: usize = u8::MAX as usize + 1;
+
+/// A message sent by a node
+#[derive(Debug, Clone)]
+pub enum Sent {
+    Broadcast(Message),
+    ToPeer(SocketAddr, Message),
+    ToAlivePeers(usize, Message),
+}
+
+impl Sent {
+    pub fn msg(&self) -> &Message {
+        match self {
+            Self::Broadcast(msg)
+            | Self::ToPeer(_, msg)
+            | Self::ToAlivePeers(_, msg) => msg,
+        }
+    }
+}
+
+/// Receiving end of a node connected to the hub
+struct Endpoint {
+    routes: Mutex<Vec<Option<AsyncQueue<Message>>>>,
+    filters: Mutex<Vec<Option<BoxedFilter>>>,
+    /// Banned peers, along with the timestamp their ban expires at
+    banned: Mutex<HashMap<IpAddr, u64>>,
+}
+
+impl Endpoint {
+    fn new() -> Self {
+        Self {
+            routes: Mutex::new((0..TOPICS).map(|_| None).collect()),
+            filters: Mutex::new((0..TOPICS).map(|_| None).collect()),
+            banned: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn is_banned(&self, addr: &IpAddr) -> bool {
+        let banned = self.banned.lock().expect("banned lock");
+        banned
+            .get(addr)
+            .is_some_and(|expiry| *expiry > get_current_timestamp())
+    }
+
+    /// Routes `msg` received from `src` to its queue, as the Kadcast
+    /// listener does.
+    ///
+    /// Having reached every node, the message is left no hop to be
+    /// propagated further.
+    fn deliver(&self, src: SocketAddr, mut msg: Message) {
+        if self.is_banned(&src.ip()) {
+            return;
+        }
+
+        msg.metadata = Some(Metadata {
+            height: 0,
+            src_addr: src,
+            ray_id: String::new(),
+        });
+
+        let topic = msg.topic() as usize;
+        if let Some(Some(filter)) =
+            self.filters.lock().expect("filters lock").get_mut(topic)
+        {
+            if filter.filter(&msg).is_err() {
+                return;
+            }
+        }
+        if let Some(Some(queue)) =
+            self.routes.lock().expect("routes lock").get(topic)
+        {
+            queue.try_send(msg);
+        }
+    }
+}
+
+struct HubState {
+    endpoints: HashMap<SocketAddr, Arc<Endpoint>>,
+    latency: Duration,
+    drop_rate: f64,
+    dropped: u64,
+    rng: StdRng,
+}
+
+/// Loopback network between in-process nodes
+#[derive(Clone)]
+pub struct MockHub {
+    state: Arc<Mutex<HubState>>,
+}
+
+impl MockHub {
+    /// Creates a hub without latency nor drops, seeding the rng of the drops
+    /// and of the selection of alive peers with `seed`
+    pub fn new(seed: u64) -> Self {
+        let state = HubState {
+            endpoints: HashMap::new(),
+            latency: Duration::ZERO,
+            drop_rate: 0.0,
+            dropped: 0,
+            rng: StdRng::seed_from_u64(seed),
+        };
+        Self {
+            state: Arc::new(Mutex::new(state)),
+        }
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, HubState> {
+        self.state.lock().expect("hub lock")
+    }
+
+    /// Sets the delay of every message
+    pub fn set_latency(&self, latency: Duration) {
+        self.state().latency = latency;
+    }
+
+    /// Sets the probability of every message to be dropped, between 0 and 1
+    pub fn set_drop_rate(&self, drop_rate: f64) {
+        self.state().drop_rate = drop_rate.clamp(0.0, 1.0);
+    }
+
+    /// Returns the number of messages dropped so far
+    pub fn dropped(&self) -> u64 {
+        self.state().dropped
+    }
+
+    /// Connects a node at `addr` to the hub.
+    ///
+    /// Bans being by ip, nodes meant to be banned separately must have
+    /// different ips, e.g. 127.0.0.1 and 127.0.0.2.
+    pub fn connect(&self, addr: SocketAddr) -> MockNetwork {
+        let endpoint = Arc::new(Endpoint::new());
+        self.state().endpoints.insert(addr, endpoint.clone());
+        MockNetwork {
+            hub: self.clone(),
+            addr,
+            endpoint,
+            history: Arc::new(Mutex::new(vec![])),
+        }
+    }
+
+    /// Disconnects the node at `addr`, which neither sends nor receives any
+    /// message until connected again
+    pub fn disconnect(&self, addr: &SocketAddr) {
+        self.state().endpoints.remove(addr);
+    }
+
+    /// Returns the addresses of the nodes connected, other than `addr`
+    fn peers(&self, addr: &SocketAddr) -> Vec<SocketAddr> {
+        let mut peers: Vec<_> = self
+            .state()
+            .endpoints
+            .keys()
+            .filter(|peer| *peer != addr)
+            .copied()
+            .collect();
+        peers.sort();
+        peers
+    }
+
+    /// Returns `amount` random nodes connected, other than `addr`
+    fn random_peers(
+        &self,
+        addr: &SocketAddr,
+        amount: usize,
+    ) -> Vec<SocketAddr> {
+        let peers = self.peers(addr);
+        let mut state = self.state();
+        peers
+            .choose_multiple(&mut state.rng, amount)
+            .copied()
+            .collect()
+    }
+
+    /// Sends `msg` from `src` to the nodes at `dst`
+    fn send(&self, src: SocketAddr, msg: &Message, dst: &[SocketAddr]) {
+        let (endpoints, latency) = {
+            let mut state = self.state();
+            if !state.endpoints.contains_key(&src) {
+                return;
+            }
+
+            let mut endpoints = vec![];
+            for addr in dst {
+                let Some(endpoint) = state.endpoints.get(addr).cloned() else {
+                    continue;
+                };
+                let drop_rate = state.drop_rate;
+                if state.rng.gen_bool(drop_rate) {
+                    state.dropped += 1;
+                    continue;
+                }
+                endpoints.push(endpoint);
+            }
+            (endpoints, state.latency)
+        };
+
+        for endpoint in endpoints {
+            let msg = msg.clone();
+            if latency.is_zero() {
+                endpoint.deliver(src, msg);
+            } else {
+                tokio::spawn(async move {
+                    tokio::time::sleep(latency).await;
+                    endpoint.deliver(src, msg);
+                });
+            }
+        }
+    }
+}
+
+/// A node connected to a [`MockHub`]
+pub struct MockNetwork {
+    hub: MockHub,
+    addr: SocketAddr,
+    endpoint: Arc<Endpoint>,
+    history: Arc<Mutex<Vec<Sent>>>,
+}
+
+impl MockNetwork {
+    /// Returns the messages sent by the node so far
+    pub fn history(&self) -> Vec<Sent> {
+        self.history.lock().expect("history lock").clone()
+    }
+
+    /// Returns the messages broadcast by the node so far
+    pub fn broadcasts(&self) -> Vec<Message> {
+        self.history()
+            .into_iter()
+            .filter_map(|sent| match sent {
+                Sent::Broadcast(msg) => Some(msg),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn record(&self, sent: Sent) {
+        self.history.lock().expect("history lock").push(sent);
+    }
+}
+
+#[async_trait]
+impl Network for MockNetwork {
+    async fn broadcast(&self, msg: &Message) -> anyhow::Result<()> {
+        // A broadcast reaches every node at once, leaving nothing to
+        // propagate
+        if msg.metadata.is_some() {
+            return Ok(());
+        }
+
+        self.record(Sent::Broadcast(msg.clone()));
+        let peers = self.hub.peers(&self.addr);
+        self.hub.send(self.addr, msg, &peers);
+        Ok(())
+    }
+
+    async fn flood_request(
+        &self,
+        msg_inv: &Inv,
+        ttl_as_sec: Option<u64>,
+        hops_limit: u16,
+    ) -> anyhow::Result<()> {
+        let ttl_as_sec = ttl_as_sec
+            .map_or_else(|| u64::MAX, |v| get_current_timestamp() + v);
+
+        let msg = GetResource::new(
+            msg_inv.clone(),
+            Some(self.addr),
+            ttl_as_sec,
+            hops_limit,
+        );
+        let amount = self.alive_nodes_count().await;
+        self.send_to_alive_peers(msg.into(), amount).await
+    }
+
+    async fn send_to_peer(
+        &self,
+        msg: Message,
+        peer_addr: SocketAddr,
+    ) -> anyhow::Result<()> {
+        self.hub.send(self.addr, &msg, &[peer_addr]);
+        self.record(Sent::ToPeer(peer_addr, msg));
+        Ok(())
+    }
+
+    async fn send_to_alive_peers(
+        &self,
+        msg: Message,
+        amount: usize,
+    ) -> anyhow::Result<()> {
+        let peers = self.hub.random_peers(&self.addr, amount);
+        self.hub.send(self.addr, &msg, &peers);
+        self.record(Sent::ToAlivePeers(amount, msg));
+        Ok(())
+    }
+
+    async fn add_route(
+        &mut self,
+        msg_type: u8,
+        queue: AsyncQueue<Message>,
+    ) -> anyhow::Result<()> {
+        let mut routes = self.endpoint.routes.lock().expect("routes lock");
+        routes[msg_type as usize] = Some(queue);
+        Ok(())
+    }
+
+    async fn add_filter(
+        &mut self,
+        msg_type: u8,
+        filter: BoxedFilter,
+    ) -> anyhow::Result<()> {
+        let mut filters = self.endpoint.filters.lock().expect("filters lock");
+        filters[msg_type as usize] = Some(filter);
+        Ok(())
+    }
+
+    fn get_info(&self) -> anyhow::Result<String> {
+        Ok(self.addr.to_string())
+    }
+
+    fn public_addr(&self) -> &SocketAddr {
+        &self.addr
+    }
+
+    async fn alive_nodes_count(&self) -> usize {
+        self.hub.peers(&self.addr).len()
+    }
+
+    fn ban_peer(&self, addr: IpAddr, _reason: &str) {
+        let expiry = get_current_timestamp() + BAN_DURATION.as_secs();
+        let mut banned = self.endpoint.banned.lock().expect("banned lock");
+        banned.insert(addr, expiry);
+    }
+
+    fn unban_peer(&self, addr: &IpAddr) {
+        let mut banned = self.endpoint.banned.lock().expect("banned lock");
+        banned.remove(addr);
+    }
+
+    fn banned_peers(&self) -> Vec<(IpAddr, u64)> {
+        let now = get_current_timestamp();
+        let banned = self.endpoint.banned.lock().expect("banned lock");
+        banned
+            .iter()
+            .filter(|(_, expiry)| **expiry > now)
+            .map(|(addr, expiry)| (*addr, *expiry))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use node_data::message::payload::GetMempool;
+    use node_data::message::Topics;
+
+    use super::*;
+
+    fn addr(ip: u8) -> SocketAddr {
+        SocketAddr::from(([127, 0, 0, ip], 9000))
+    }
+
+    async fn node(hub: &MockHub, ip: u8) -> (MockNetwork, AsyncQueue<Message>) {
+        let mut network = hub.connect(addr(ip));
+        let queue = AsyncQueue::bounded(100, "mock");
+        network
+            .add_route(Topics::GetMempool.into(), queue.clone())
+            .await
+            .unwrap();
+        (network, queue)
+    }
+
+    fn msg() -> Message {
+        GetMempool::default().into()
+    }
+
+    #[tokio::test]
+    async fn test_mock_network() {
+        let hub = MockHub::new(0);
+        let (a, _) = node(&hub, 1).await;
+        let (b, b_queue) = node(&hub, 2).await;
+        let (_c, c_queue) = node(&hub, 3).await;
+
+        a.broadcast(&msg()).await.unwrap();
+        for queue in [&b_queue, &c_queue] {
+            let received = queue.recv().await.unwrap();
+            let metadata = received.metadata.expect("metadata");
+            assert_eq!(metadata.src_addr, addr(1));
+            assert_eq!(metadata.height, 0);
+
+            // A received broadcast isn't propagated further
+            b.broadcast(&received).await.unwrap();
+        }
+        assert_eq!(a.broadcasts().len(), 1);
+        assert!(b.broadcasts().is_empty());
+
+        a.send_to_peer(msg(), addr(3)).await.unwrap();
+        assert!(c_queue.recv().await.is_ok());
+        assert!(b_queue.is_empty());
+
+        // Banned peers are ignored
+        b.ban_peer(addr(1).ip(), "test");
+        a.send_to_alive_peers(msg(), 2).await.unwrap();
+        assert!(c_queue.recv().await.is_ok());
+        assert!(b_queue.is_empty());
+        b.unban_peer(&addr(1).ip());
+
+        hub.set_drop_rate(1.0);
+        a.broadcast(&msg()).await.unwrap();
+        assert_eq!(hub.dropped(), 2);
+        assert!(b_queue.is_empty() && c_queue.is_empty());
+        assert_eq!(a.history().len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_mock_network_latency() {
+        let hub = MockHub::new(0);
+        hub.set_latency(Duration::from_millis(100));
+        let (a, _) = node(&hub, 1).await;
+        let (_b, b_queue) = node(&hub, 2).await;
+
+        a.broadcast(&msg()).await.unwrap();
+        assert!(b_queue.is_empty());
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert_eq!(b_queue.len(), 1);
+    }
+}
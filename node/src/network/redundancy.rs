@@ -0,0 +1,401 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Adaptive redundancy of the resource discovery.
+//!
+//! Each resource requested with a `flood_request` is tracked until it's
+//! received, whatever the peer it's received from, or until
+//! [`Params::request_timeout`] elapses. Once [`Params::window`] outcomes are
+//! collected, the number of peers a request is sent to is adjusted within the
+//! configured bounds:
+//! - it's increased if fewer resources than [`Params::target_success_rate`]
+//!   were found, or if they were found slower than [`Params::target_latency`]
+//!   on average;
+//! - it's decreased if the targets were met and the resources were found in
+//!   less than half the target latency.
+//!
+//! The nodes known alive are counted again every [`ALIVE_NODES_REFRESH`],
+//! and a request is sent to at most [`Params::max_alive_share`] of them, so
+//! that small networks aren't flooded with every request.
+//!
+//! The current count is exposed as the `dusk_redundancy_peer_count` gauge.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use metrics::gauge;
+use node_data::message::payload::{Inv, InvParam, InvType};
+use node_data::message::{ConsensusHeader, Payload};
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_MIN_PEERS: usize = 4;
+pub const DEFAULT_MAX_PEERS: usize = 16;
+pub const DEFAULT_INITIAL_PEERS: usize = 8;
+pub const DEFAULT_WINDOW: usize = 32;
+pub const DEFAULT_TARGET_SUCCESS_RATE: f64 = 0.9;
+pub const DEFAULT_TARGET_LATENCY: Duration = Duration::from_secs(2);
+pub const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+pub const DEFAULT_MAX_ALIVE_SHARE: f64 = 0.5;
+
+/// Interval the alive nodes are counted again at
+pub const ALIVE_NODES_REFRESH: Duration = Duration::from_secs(10);
+
+/// Maximum number of resources tracked at once
+const MAX_PENDING: usize = 10_000;
+
+/// Resource discovery redundancy parameters
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct Params {
+    /// Lower bound of the number of peers a request is sent to
+    pub min_peers: usize,
+
+    /// Upper bound of the number of peers a request is sent to
+    pub max_peers: usize,
+
+    /// Number of peers a request is sent to at startup
+    pub initial_peers: usize,
+
+    /// Number of request outcomes the peer count is adjusted after
+    pub window: usize,
+
+    /// Ratio of requested resources expected to be found
+    pub target_success_rate: f64,
+
+    /// Average time the requested resources are expected to be found in
+    #[serde(with = "humantime_serde")]
+    pub target_latency: Duration,
+
+    /// Time after which a requested resource is deemed not found
+    #[serde(with = "humantime_serde")]
+    pub request_timeout: Duration,
+
+    /// Maximum share of the alive nodes a request is sent to
+    pub max_alive_share: f64,
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Self {
+            min_peers: DEFAULT_MIN_PEERS,
+            max_peers: DEFAULT_MAX_PEERS,
+            initial_peers: DEFAULT_INITIAL_PEERS,
+            window: DEFAULT_WINDOW,
+            target_success_rate: DEFAULT_TARGET_SUCCESS_RATE,
+            target_latency: DEFAULT_TARGET_LATENCY,
+            request_timeout: DEFAULT_REQUEST_TIMEOUT,
+            max_alive_share: DEFAULT_MAX_ALIVE_SHARE,
+        }
+    }
+}
+
+/// A requested resource, as identified by both its request and its response
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Resource {
+    Tx([u8; 32]),
+    BlockFromHash([u8; 32]),
+    BlockFromHeight(u64),
+    CandidateFromHash([u8; 32]),
+    CandidateFromIteration([u8; 32], u64, u8),
+    ValidationResult([u8; 32], u64, u8),
+}
+
+fn iteration(ch: &ConsensusHeader) -> ([u8; 32], u64, u8) {
+    (ch.prev_block_hash, ch.round, ch.iteration)
+}
+
+impl Resource {
+    /// Returns the resources requested by `inv`
+    fn requested(inv: &Inv) -> impl Iterator<Item = Resource> + '_ {
+        inv.inv_list.iter().filter_map(|i| {
+            let resource = match (i.inv_type, &i.param) {
+                (InvType::MempoolTx, InvParam::Hash(id)) => Self::Tx(*id),
+                (InvType::BlockFromHash, InvParam::Hash(hash)) => {
+                    Self::BlockFromHash(*hash)
+                }
+                (InvType::BlockFromHeight, InvParam::Height(height)) => {
+                    Self::BlockFromHeight(*height)
+                }
+                (InvType::CandidateFromHash, InvParam::Hash(hash)) => {
+                    Self::CandidateFromHash(*hash)
+                }
+                (InvType::CandidateFromIteration, InvParam::Iteration(ch)) => {
+                    let (prev, round, iter) = iteration(ch);
+                    Self::CandidateFromIteration(prev, round, iter)
+                }
+                (InvType::ValidationResult, InvParam::Iteration(ch)) => {
+                    let (prev, round, iter) = iteration(ch);
+                    Self::ValidationResult(prev, round, iter)
+                }
+                _ => return None,
+            };
+            Some(resource)
+        })
+    }
+
+    /// Returns the resources a received payload may be the response for
+    fn received(payload: &Payload) -> Vec<Resource> {
+        match payload {
+            Payload::Transaction(tx) => vec![Self::Tx(tx.id())],
+            Payload::Block(b) => vec![
+                Self::BlockFromHash(b.header().hash),
+                Self::BlockFromHeight(b.header().height),
+            ],
+            Payload::Candidate(c) => {
+                let header = c.candidate.header();
+                vec![
+                    Self::CandidateFromHash(header.hash),
+                    Self::CandidateFromIteration(
+                        header.prev_block_hash,
+                        header.height,
+                        header.iteration,
+                    ),
+                ]
+            }
+            Payload::ValidationQuorum(vq) => {
+                let (prev, round, iter) = iteration(&vq.header);
+                vec![Self::ValidationResult(prev, round, iter)]
+            }
+            _ => vec![],
+        }
+    }
+}
+
+/// Tracks the outcome of the requested resources, adjusting the number of
+/// peers a request is sent to
+#[derive(Debug)]
+pub struct Redundancy {
+    params: Params,
+    peer_count: usize,
+    /// Requested resources along with the time they were requested at
+    pending: HashMap<Resource, Instant>,
+    found: usize,
+    missed: usize,
+    latency: Duration,
+    /// Number of alive nodes along with the time they were counted at
+    alive_nodes: Option<(usize, Instant)>,
+}
+
+impl Default for Redundancy {
+    fn default() -> Self {
+        Self::new(Params::default())
+    }
+}
+
+impl Redundancy {
+    pub fn new(mut params: Params) -> Self {
+        params.min_peers = params.min_peers.max(1);
+        params.max_peers = params.max_peers.max(params.min_peers);
+        params.window = params.window.max(1);
+        params.max_alive_share = params.max_alive_share.clamp(0.0, 1.0);
+        let peer_count = params
+            .initial_peers
+            .clamp(params.min_peers, params.max_peers);
+        gauge!("dusk_redundancy_peer_count").set(peer_count as f64);
+
+        Self {
+            params,
+            peer_count,
+            pending: HashMap::new(),
+            found: 0,
+            missed: 0,
+            latency: Duration::ZERO,
+            alive_nodes: None,
+        }
+    }
+
+    /// Returns the number of peers a request is currently sent to, at most
+    /// the share of the alive nodes
+    pub fn peer_count(&self) -> usize {
+        match self.alive_nodes {
+            Some((alive, _)) => {
+                let share = alive as f64 * self.params.max_alive_share;
+                self.peer_count.min((share.ceil() as usize).max(1))
+            }
+            None => self.peer_count,
+        }
+    }
+
+    /// Returns whether the alive nodes are to be counted again at `now`
+    pub fn alive_nodes_stale(&self, now: Instant) -> bool {
+        self.alive_nodes.map_or(true, |(_, counted_at)| {
+            now.saturating_duration_since(counted_at) >= ALIVE_NODES_REFRESH
+        })
+    }
+
+    /// Records the `count` of alive nodes at `now`
+    pub fn on_alive_nodes(&mut self, count: usize, now: Instant) {
+        self.alive_nodes = Some((count, now));
+        gauge!("dusk_redundancy_alive_nodes").set(count as f64);
+    }
+
+    /// Tracks the resources requested by `inv` at `now`, expiring the ones
+    /// requested too long ago
+    pub fn on_request(&mut self, inv: &Inv, now: Instant) {
+        self.expire(now);
+        for resource in Resource::requested(inv) {
+            if self.pending.len() >= MAX_PENDING {
+                break;
+            }
+            self.pending.entry(resource).or_insert(now);
+        }
+    }
+
+    /// Marks the resources `payload` is the response for as found at `now`
+    pub fn on_received(&mut self, payload: &Payload, now: Instant) {
+        if self.pending.is_empty() {
+            return;
+        }
+        for resource in Resource::received(payload) {
+            if let Some(requested_at) = self.pending.remove(&resource) {
+                let latency = now.saturating_duration_since(requested_at);
+                if latency > self.params.request_timeout {
+                    self.record(false);
+                } else {
+                    self.latency += latency;
+                    self.record(true);
+                }
+            }
+        }
+    }
+
+    fn expire(&mut self, now: Instant) {
+        let timeout = self.params.request_timeout;
+        let mut missed = 0;
+        self.pending.retain(|_, requested_at| {
+            let expired =
+                now.saturating_duration_since(*requested_at) > timeout;
+            missed += expired as usize;
+            !expired
+        });
+        for _ in 0..missed {
+            self.record(false);
+        }
+    }
+
+    fn record(&mut self, found: bool) {
+        if found {
+            self.found += 1;
+        } else {
+            self.missed += 1;
+        }
+        if self.found + self.missed >= self.params.window {
+            self.adjust();
+        }
+    }
+
+    fn adjust(&mut self) {
+        let outcomes = self.found + self.missed;
+        let success_rate = self.found as f64 / outcomes as f64;
+        let avg_latency = if self.found > 0 {
+            self.latency / self.found as u32
+        } else {
+            Duration::ZERO
+        };
+
+        let target_met = success_rate >= self.params.target_success_rate
+            && avg_latency <= self.params.target_latency;
+        if !target_met {
+            self.peer_count += 1;
+        } else if avg_latency <= self.params.target_latency / 2 {
+            self.peer_count = self.peer_count.saturating_sub(1);
+        }
+        self.peer_count = self
+            .peer_count
+            .clamp(self.params.min_peers, self.params.max_peers);
+
+        gauge!("dusk_redundancy_peer_count").set(self.peer_count as f64);
+        gauge!("dusk_redundancy_success_rate").set(success_rate);
+        gauge!("dusk_redundancy_latency_ms")
+            .set(avg_latency.as_millis() as f64);
+
+        self.found = 0;
+        self.missed = 0;
+        self.latency = Duration::ZERO;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params() -> Params {
+        Params {
+            min_peers: 2,
+            max_peers: 4,
+            initial_peers: 3,
+            window: 2,
+            ..Default::default()
+        }
+    }
+
+    fn height_inv(height: u64) -> Inv {
+        let mut inv = Inv::new(0);
+        inv.add_block_from_height(height);
+        inv
+    }
+
+    fn block_at(height: u64) -> Payload {
+        let header = node_data::ledger::Header {
+            height,
+            ..Default::default()
+        };
+        let block = node_data::ledger::Block::new(header, vec![], vec![])
+            .expect("block to be created");
+        Payload::Block(Box::new(block))
+    }
+
+    #[test]
+    fn peer_count_adapts_within_bounds() {
+        let mut redundancy = Redundancy::new(params());
+        assert_eq!(redundancy.peer_count(), 3);
+
+        let start = Instant::now();
+        let timeout = redundancy.params.request_timeout;
+
+        // Resources never received raise the peer count up to the bound
+        for round in 0..4 {
+            let now = start + (timeout + Duration::from_secs(1)) * round;
+            redundancy.on_request(&height_inv(2 * round as u64), now);
+            redundancy.on_request(&height_inv(2 * round as u64 + 1), now);
+        }
+        let now = start + (timeout + Duration::from_secs(1)) * 4;
+        redundancy.on_request(&Inv::new(0), now);
+        assert_eq!(redundancy.peer_count(), 4);
+
+        // Resources quickly received lower it down to the bound
+        for height in 100..110 {
+            redundancy.on_request(&height_inv(height), now);
+            redundancy.on_received(
+                &block_at(height),
+                now + Duration::from_millis(10),
+            );
+        }
+        assert_eq!(redundancy.peer_count(), 2);
+
+        // Unrequested resources are not accounted
+        redundancy.on_received(&block_at(200), now);
+        assert_eq!(redundancy.found + redundancy.missed, 0);
+    }
+
+    #[test]
+    fn peer_count_bounded_by_alive_nodes() {
+        let mut redundancy = Redundancy::new(params());
+        let now = Instant::now();
+        assert!(redundancy.alive_nodes_stale(now));
+
+        // At most half of the alive nodes are requested
+        redundancy.on_alive_nodes(4, now);
+        assert_eq!(redundancy.peer_count(), 2);
+        redundancy.on_alive_nodes(1, now);
+        assert_eq!(redundancy.peer_count(), 1);
+        redundancy.on_alive_nodes(100, now);
+        assert_eq!(redundancy.peer_count(), 3);
+
+        assert!(!redundancy.alive_nodes_stale(now));
+        assert!(redundancy.alive_nodes_stale(now + ALIVE_NODES_REFRESH));
+    }
+}
@@ -0,0 +1,400 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Peer reputation.
+//!
+//! Peers sending malformed or abusive messages are given misbehavior points.
+//! Once a peer reaches [`BAN_THRESHOLD`] points it is banned for
+//! [`BAN_DURATION`], and every message it sends is dropped. Points expire
+//! [`SCORE_TTL`] after the last penalty.
+//!
+//! With Kadcast propagation, the sender of a message isn't necessarily its
+//! author. Penalties for messages that honest peers relay, such as a forged
+//! signature, are given with [`penalize_relayed`]: they add to the score of
+//! the sender without banning it.
+//!
+//! Peers sending more than [`FLOOD_MAX_MSGS`] messages within a second are
+//! penalized once per second, and their excess messages are dropped.
+//!
+//! Scores and bans are kept in memory and stored in the Metadata CF with
+//! [`persist`], so that they can be reloaded with [`load`] on restart.
+
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+use std::net::{IpAddr, Ipv6Addr};
+use std::sync::{LazyLock, Mutex};
+use std::time::Duration;
+
+use metrics::counter;
+use node_data::{get_current_timestamp, Serializable};
+use tracing::warn;
+
+use crate::database::rocksdb::MD_PEER_REPUTATION;
+use crate::database::Metadata;
+
+/// Points a peer is banned at
+pub const BAN_THRESHOLD: u32 = 100;
+
+/// Duration of a ban
+pub const BAN_DURATION: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Time after which the points of a peer expire
+pub const SCORE_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// Points given for a message that can't be decoded
+pub const PENALTY_MALFORMED: u32 = 10;
+
+/// Points given for a block exceeding the maximum block size
+pub const PENALTY_OVERSIZE: u32 = 50;
+
+/// Points given for a consensus message with an invalid signature
+pub const PENALTY_INVALID_SIGNATURE: u32 = 20;
+
+/// Points given for exceeding [`FLOOD_MAX_MSGS`] within a second
+pub const PENALTY_FLOOD: u32 = 10;
+
+/// Maximum number of messages accepted from a peer within a second
+pub const FLOOD_MAX_MSGS: u32 = 2_000;
+
+/// Number of peers after which the flood counters of past seconds are pruned
+const FLOOD_PRUNE_LEN: usize = 1_000;
+
+static REPUTATION: LazyLock<Mutex<Reputation>> =
+    LazyLock::new(|| Mutex::new(Reputation::default()));
+
+static FLOOD: LazyLock<Mutex<FloodMeter>> =
+    LazyLock::new(|| Mutex::new(FloodMeter::default()));
+
+/// Counts the messages received from each peer in the current second
+#[derive(Debug, Default)]
+struct FloodMeter {
+    /// Second of the count, along with the count itself
+    counts: HashMap<IpAddr, (u64, u32)>,
+}
+
+impl FloodMeter {
+    /// Counts a message from `addr`, returning the number of messages received
+    /// from it within the second `now`
+    fn record(&mut self, addr: IpAddr, now: u64) -> u32 {
+        if self.counts.len() > FLOOD_PRUNE_LEN {
+            self.counts.retain(|_, (second, _)| *second == now);
+        }
+
+        let (second, count) = self.counts.entry(addr).or_insert((now, 0));
+        if *second != now {
+            *second = now;
+            *count = 0;
+        }
+        *count = count.saturating_add(1);
+        *count
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Entry {
+    score: u32,
+    /// Timestamp (in seconds) of the last penalty
+    last_penalty: u64,
+    /// Timestamp (in seconds) the ban expires at, if banned
+    banned_until: Option<u64>,
+}
+
+impl Entry {
+    fn is_banned(&self, now: u64) -> bool {
+        self.banned_until.is_some_and(|until| now < until)
+    }
+
+    fn is_expired(&self, now: u64) -> bool {
+        !self.is_banned(now) && now >= self.last_penalty + SCORE_TTL.as_secs()
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Reputation {
+    entries: HashMap<IpAddr, Entry>,
+    /// Whether entries changed since the last persist
+    dirty: bool,
+}
+
+impl Reputation {
+    /// Gives `points` to the peer at `addr`, returning true if it gets banned.
+    ///
+    /// The peer is never banned if `ban` is false.
+    fn penalize(
+        &mut self,
+        addr: IpAddr,
+        points: u32,
+        now: u64,
+        ban: bool,
+    ) -> bool {
+        let entry = self.entries.entry(addr).or_insert(Entry {
+            score: 0,
+            last_penalty: now,
+            banned_until: None,
+        });
+        if entry.is_expired(now) {
+            entry.score = 0;
+            entry.banned_until = None;
+        }
+
+        entry.score = entry.score.saturating_add(points);
+        entry.last_penalty = now;
+        self.dirty = true;
+
+        if ban && entry.score >= BAN_THRESHOLD && !entry.is_banned(now) {
+            entry.banned_until = Some(now + BAN_DURATION.as_secs());
+            return true;
+        }
+        false
+    }
+
+    fn forgive(&mut self, addr: &IpAddr) -> bool {
+        let forgiven = self.entries.remove(addr).is_some();
+        self.dirty |= forgiven;
+        forgiven
+    }
+
+    fn is_banned(&self, addr: &IpAddr, now: u64) -> bool {
+        self.entries.get(addr).is_some_and(|e| e.is_banned(now))
+    }
+
+    fn banned(&self, now: u64) -> Vec<(IpAddr, u64)> {
+        self.entries
+            .iter()
+            .filter(|(_, e)| e.is_banned(now))
+            .filter_map(|(addr, e)| Some((*addr, e.banned_until?)))
+            .collect()
+    }
+
+    fn prune(&mut self, now: u64) {
+        let len = self.entries.len();
+        self.entries.retain(|_, e| !e.is_expired(now));
+        self.dirty |= len != self.entries.len();
+    }
+}
+
+impl Serializable for Reputation {
+    fn write<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        w.write_all(&(self.entries.len() as u32).to_le_bytes())?;
+        for (addr, entry) in &self.entries {
+            let addr = match addr {
+                IpAddr::V4(ip) => ip.to_ipv6_mapped(),
+                IpAddr::V6(ip) => *ip,
+            };
+            w.write_all(&addr.octets())?;
+            w.write_all(&entry.score.to_le_bytes())?;
+            w.write_all(&entry.last_penalty.to_le_bytes())?;
+            w.write_all(&entry.banned_until.unwrap_or(0).to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    fn read<R: Read>(r: &mut R) -> io::Result<Self>
+    where
+        Self: Sized,
+    {
+        let len = Self::read_u32_le(r)?;
+        let mut entries = HashMap::new();
+        for _ in 0..len {
+            let addr = Ipv6Addr::from(Self::read_bytes::<_, 16>(r)?);
+            let addr = match addr.to_ipv4_mapped() {
+                Some(ip) => IpAddr::V4(ip),
+                None => IpAddr::V6(addr),
+            };
+            let score = Self::read_u32_le(r)?;
+            let last_penalty = Self::read_u64_le(r)?;
+            let banned_until = Some(Self::read_u64_le(r)?).filter(|&t| t > 0);
+            entries.insert(
+                addr,
+                Entry {
+                    score,
+                    last_penalty,
+                    banned_until,
+                },
+            );
+        }
+
+        Ok(Self {
+            entries,
+            dirty: false,
+        })
+    }
+}
+
+/// Gives `points` misbehavior points to the peer at `addr`, banning it if the
+/// [`BAN_THRESHOLD`] is reached.
+pub fn penalize(addr: IpAddr, points: u32, reason: &str) {
+    let mut reputation = REPUTATION.lock().expect("lock not to be poisoned");
+    if reputation.penalize(addr, points, get_current_timestamp(), true) {
+        warn!(event = "peer banned", %addr, reason);
+        counter!("dusk_peers_banned").increment(1);
+    }
+}
+
+/// Gives `points` misbehavior points to the peer at `addr` for a message it
+/// may only be relaying, without ever banning it
+pub fn penalize_relayed(addr: IpAddr, points: u32, reason: &str) {
+    let mut reputation = REPUTATION.lock().expect("lock not to be poisoned");
+    reputation.penalize(addr, points, get_current_timestamp(), false);
+    counter!("dusk_relayed_penalties", "reason" => reason.to_string())
+        .increment(1);
+}
+
+/// Clears the points and the ban of the peer at `addr`
+pub fn forgive(addr: &IpAddr) {
+    let mut reputation = REPUTATION.lock().expect("lock not to be poisoned");
+    if reputation.forgive(addr) {
+        warn!(event = "peer forgiven", %addr);
+    }
+}
+
+/// Returns true if the peer at `addr` is currently banned
+pub fn is_banned(addr: &IpAddr) -> bool {
+    let reputation = REPUTATION.lock().expect("lock not to be poisoned");
+    reputation.is_banned(addr, get_current_timestamp())
+}
+
+/// Returns the peers currently banned, along with the timestamp (in seconds)
+/// their ban expires at
+pub fn banned() -> Vec<(IpAddr, u64)> {
+    let reputation = REPUTATION.lock().expect("lock not to be poisoned");
+    reputation.banned(get_current_timestamp())
+}
+
+/// Counts a message received from the peer at `addr`, returning true if it
+/// exceeds the [`FLOOD_MAX_MSGS`] of the current second and must be dropped.
+///
+/// The peer is penalized on the first message exceeding the limit, without
+/// being banned, since it may be relaying the messages of others.
+pub fn is_flooding(addr: IpAddr) -> bool {
+    let count = FLOOD
+        .lock()
+        .expect("lock not to be poisoned")
+        .record(addr, get_current_timestamp());
+
+    if count == FLOOD_MAX_MSGS + 1 {
+        penalize_relayed(addr, PENALTY_FLOOD, "message flood");
+    }
+    count > FLOOD_MAX_MSGS
+}
+
+/// Loads the scores and bans stored in the Metadata CF, merging them with the
+/// in-memory ones
+pub fn load<M: Metadata>(db: &M) -> anyhow::Result<()> {
+    let Some(bytes) = db.op_read(MD_PEER_REPUTATION)? else {
+        return Ok(());
+    };
+    let mut stored = Reputation::read(&mut &bytes[..])?;
+    stored.prune(get_current_timestamp());
+
+    let mut reputation = REPUTATION.lock().expect("lock not to be poisoned");
+    for (addr, entry) in stored.entries {
+        reputation.entries.entry(addr).or_insert(entry);
+    }
+    Ok(())
+}
+
+/// Stores the scores and bans in the Metadata CF, if they changed since the
+/// last call
+pub fn persist<M: Metadata>(db: &mut M) -> anyhow::Result<()> {
+    let bytes = {
+        let mut reputation =
+            REPUTATION.lock().expect("lock not to be poisoned");
+        reputation.prune(get_current_timestamp());
+        if !reputation.dirty {
+            return Ok(());
+        }
+        reputation.dirty = false;
+
+        let mut bytes = vec![];
+        reputation.write(&mut bytes)?;
+        bytes
+    };
+
+    db.op_write(MD_PEER_REPUTATION, bytes)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use super::*;
+
+    #[test]
+    fn test_ban_and_expiry() {
+        let mut reputation = Reputation::default();
+        let addr = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+        let now = 1_000;
+
+        for _ in 0..9 {
+            assert!(!reputation.penalize(addr, PENALTY_MALFORMED, now, true));
+        }
+        assert!(!reputation.is_banned(&addr, now));
+        assert!(reputation.penalize(addr, PENALTY_MALFORMED, now, true));
+        assert!(reputation.is_banned(&addr, now));
+
+        let unbanned = now + BAN_DURATION.as_secs();
+        assert!(!reputation.is_banned(&addr, unbanned));
+
+        reputation.prune(unbanned);
+        assert!(reputation.entries.is_empty());
+    }
+
+    #[test]
+    fn test_relayed_penalties() {
+        let mut reputation = Reputation::default();
+        let addr = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+        let now = 1_000;
+
+        // Forged messages relayed by a peer don't get it banned
+        let points = PENALTY_INVALID_SIGNATURE;
+        for _ in 0..10 {
+            assert!(!reputation.penalize(addr, points, now, false));
+        }
+        assert!(!reputation.is_banned(&addr, now));
+        assert_eq!(reputation.entries[&addr].score, 10 * points);
+    }
+
+    #[test]
+    fn test_flood_meter() {
+        let mut flood = FloodMeter::default();
+        let addr = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+        let now = 1_000;
+
+        for expected in 1..=FLOOD_MAX_MSGS + 1 {
+            assert_eq!(flood.record(addr, now), expected);
+        }
+        // The count restarts every second
+        assert_eq!(flood.record(addr, now + 1), 1);
+
+        let mut reputation = Reputation::default();
+        reputation.penalize(addr, BAN_THRESHOLD, now, true);
+        assert_eq!(
+            reputation.banned(now),
+            vec![(addr, now + BAN_DURATION.as_secs())]
+        );
+    }
+
+    #[test]
+    fn test_reputation_encoding() {
+        let mut reputation = Reputation::default();
+        let now = 1_000;
+        reputation.penalize(
+            IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)),
+            BAN_THRESHOLD,
+            now,
+            true,
+        );
+        reputation.penalize(IpAddr::V6(Ipv6Addr::LOCALHOST), 1, now, true);
+        reputation.dirty = false;
+
+        let mut bytes = vec![];
+        reputation.write(&mut bytes).unwrap();
+        let decoded = Reputation::read(&mut &bytes[..]).unwrap();
+        assert_eq!(decoded, reputation);
+    }
+}
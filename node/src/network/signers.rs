@@ -0,0 +1,77 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Decoded keys of the signers of the received votes.
+//!
+//! Validations and ratifications are signed by the members of the committees,
+//! which are drawn out of the same provisioners round after round. Their keys
+//! are decompressed once and reused for the following votes.
+
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex};
+
+use node_data::bls::{PublicKey, PUBLIC_BLS_SIZE};
+
+/// Maximum number of keys kept. Once reached, the keys are dropped all
+/// together.
+pub const MAX_SIGNERS: usize = 4_096;
+
+static SIGNERS: LazyLock<Mutex<Signers>> =
+    LazyLock::new(|| Mutex::new(Signers::default()));
+
+#[derive(Default)]
+struct Signers {
+    keys: HashMap<[u8; PUBLIC_BLS_SIZE], PublicKey>,
+}
+
+impl Signers {
+    fn get_or_decode(
+        &mut self,
+        bytes: &[u8; PUBLIC_BLS_SIZE],
+    ) -> Result<PublicKey, dusk_bytes::Error> {
+        if let Some(key) = self.keys.get(bytes) {
+            return Ok(key.clone());
+        }
+
+        let key = PublicKey::try_from(*bytes)?;
+        if self.keys.len() >= MAX_SIGNERS {
+            self.keys.clear();
+        }
+        self.keys.insert(*bytes, key.clone());
+        Ok(key)
+    }
+}
+
+/// Returns the decoded key of a signer, decompressing it only if it's not
+/// known yet
+pub fn signer_key(
+    bytes: &[u8; PUBLIC_BLS_SIZE],
+) -> Result<PublicKey, dusk_bytes::Error> {
+    let mut signers = SIGNERS.lock().expect("lock not to be poisoned");
+    signers.get_or_decode(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signers() {
+        let mut signers = Signers::default();
+        let key = PublicKey::from_sk_seed_u64(1);
+        let bytes = *key.bytes().inner();
+
+        for _ in 0..2 {
+            let decoded = signers.get_or_decode(&bytes).expect("valid key");
+            assert_eq!(decoded, key);
+            assert_eq!(signers.keys.len(), 1);
+        }
+
+        // Invalid keys are not kept
+        assert!(signers.get_or_decode(&[0xff; PUBLIC_BLS_SIZE]).is_err());
+        assert_eq!(signers.keys.len(), 1);
+    }
+}
@@ -0,0 +1,385 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! JSON-RPC subscription service.
+//!
+//! Streams the consensus events of the node, which are not served by the
+//! GraphQL queries of the HTTP server. Blocks, transactions and the mempool
+//! are looked up through the `block`, `tx`, `mempoolTx` and `mempoolPage`
+//! GraphQL queries instead. Requests and responses are JSON-RPC 2.0 objects,
+//! one per line, exchanged over plain TCP connections.
+//!
+//! The supported methods are:
+//! - `subscribe_consensus_events` with optional params `{"from_round": u64,
+//!   "to_round": u64}`
+//!
+//! Once subscribed to the consensus events, the connection only streams
+//! `consensus_event` notifications of the rounds in range, until the last
+//! one is over. A subscription no longer counts as a connection, but the
+//! subscriptions streamed at once are capped on their own.
+
+pub mod conf;
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::tcp::OwnedWriteHalf;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{OwnedSemaphorePermit, RwLock, Semaphore};
+use tracing::{debug, info, warn};
+
+use crate::chain::consensus_events::{ConsensusEvents, RoundRange};
+use crate::rpc::conf::Params;
+use crate::{database, vm, LongLivedService, Network};
+
+/// Maximum size of a request line
+const MAX_REQUEST_SIZE: u64 = 64 * 1024;
+
+/// JSON-RPC 2.0 error codes
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const TOO_MANY_SUBSCRIPTIONS: i64 = -32000;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Request {
+    jsonrpc: String,
+    #[serde(default)]
+    id: Value,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+/// A method call, with its parameters
+#[derive(Debug, PartialEq, Eq)]
+enum Call {
+    ConsensusEvents(RoundRange),
+}
+
+fn params<P: DeserializeOwned>(params: Value) -> Result<P, RpcError> {
+    serde_json::from_value(params)
+        .map_err(|e| RpcError::new(INVALID_PARAMS, e.to_string()))
+}
+
+/// Parses a request line, returning the id of the request along with the
+/// call or the error to reply with
+fn parse_request(line: &str) -> (Value, Result<Call, RpcError>) {
+    let request: Request = match serde_json::from_str(line) {
+        Ok(request) => request,
+        Err(e) => {
+            let code = if e.is_data() {
+                INVALID_REQUEST
+            } else {
+                PARSE_ERROR
+            };
+            return (Value::Null, Err(RpcError::new(code, e.to_string())));
+        }
+    };
+
+    let id = request.id;
+    if request.jsonrpc != "2.0" {
+        let err = RpcError::new(INVALID_REQUEST, "Unsupported version");
+        return (id, Err(err));
+    }
+
+    let call = match request.method.as_str() {
+        "subscribe_consensus_events" if request.params.is_null() => {
+            Ok(Call::ConsensusEvents(RoundRange::default()))
+        }
+        "subscribe_consensus_events" => {
+            params::<RoundRange>(request.params).map(Call::ConsensusEvents)
+        }
+        method => Err(RpcError::new(
+            METHOD_NOT_FOUND,
+            format!("Unknown method {method}"),
+        )),
+    };
+
+    (id, call)
+}
+
+fn response(id: Value, result: Result<Value, RpcError>) -> Value {
+    match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(error) => json!({ "jsonrpc": "2.0", "id": id, "error": error }),
+    }
+}
+
+pub struct RpcSrv {
+    conf: Params,
+    events: ConsensusEvents,
+}
+
+impl RpcSrv {
+    pub fn new(conf: Params, events: ConsensusEvents) -> Self {
+        info!("RpcSrv::new with conf {:?}", conf);
+        Self { conf, events }
+    }
+}
+
+#[async_trait]
+impl<N: Network, DB: database::DB, VM: vm::VMExecution>
+    LongLivedService<N, DB, VM> for RpcSrv
+{
+    async fn execute(
+        &mut self,
+        _: Arc<RwLock<N>>,
+        _: Arc<RwLock<DB>>,
+        _: Arc<RwLock<VM>>,
+    ) -> anyhow::Result<usize> {
+        let Some(addr) = self.conf.listen_address else {
+            return Ok(0);
+        };
+
+        let listener = TcpListener::bind(addr).await?;
+        info!(event = "rpc listening", %addr);
+
+        let permits = Arc::new(Semaphore::new(self.conf.max_connections));
+        let subscriptions =
+            Arc::new(Semaphore::new(self.conf.max_subscriptions));
+        loop {
+            let (stream, remote) = listener.accept().await?;
+            let Ok(permit) = permits.clone().try_acquire_owned() else {
+                warn!(event = "rpc connection refused", %remote);
+                continue;
+            };
+
+            let subscriptions = subscriptions.clone();
+            let events = self.events.clone();
+            tokio::spawn(async move {
+                if let Err(e) =
+                    serve(stream, permit, &subscriptions, &events).await
+                {
+                    debug!(event = "rpc connection closed", %remote, err = ?e);
+                }
+            });
+        }
+    }
+
+    /// Returns service name.
+    fn name(&self) -> &'static str {
+        "rpc"
+    }
+}
+
+/// Serves the requests of a connection until it is closed.
+///
+/// The connection `permit` is released once the connection turns into a
+/// subscription, which holds one of the `subscriptions` permits instead.
+async fn serve(
+    stream: TcpStream,
+    permit: OwnedSemaphorePermit,
+    subscriptions: &Arc<Semaphore>,
+    events: &ConsensusEvents,
+) -> anyhow::Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut reader = BufReader::new(reader);
+    let mut line = String::new();
+
+    loop {
+        line.clear();
+        let read = (&mut reader)
+            .take(MAX_REQUEST_SIZE)
+            .read_line(&mut line)
+            .await?;
+        if read == 0 {
+            return Ok(());
+        }
+        if !line.ends_with('\n') && read as u64 == MAX_REQUEST_SIZE {
+            anyhow::bail!("Request too large");
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        match parse_request(&line) {
+            (id, Ok(Call::ConsensusEvents(range))) => {
+                let Ok(subscription) =
+                    subscriptions.clone().try_acquire_owned()
+                else {
+                    let err = RpcError::new(
+                        TOO_MANY_SUBSCRIPTIONS,
+                        "Too many subscriptions",
+                    );
+                    write_line(&mut writer, response(id, Err(err))).await?;
+                    continue;
+                };
+                drop(permit);
+
+                let res = stream_events(writer, events, id, range).await;
+                drop(subscription);
+                return res;
+            }
+            (id, Err(e)) => {
+                write_line(&mut writer, response(id, Err(e))).await?
+            }
+        }
+    }
+}
+
+async fn write_line(
+    writer: &mut OwnedWriteHalf,
+    value: Value,
+) -> anyhow::Result<()> {
+    let mut line = value.to_string();
+    line.push('\n');
+    writer.write_all(line.as_bytes()).await?;
+    Ok(())
+}
+
+/// Streams the consensus events of the rounds in `range` as notifications,
+/// until the range is over or the connection is closed
+async fn stream_events(
+    mut writer: OwnedWriteHalf,
+    events: &ConsensusEvents,
+    id: Value,
+    range: RoundRange,
+) -> anyhow::Result<()> {
+    let mut events = events.subscribe();
+    write_line(&mut writer, response(id, Ok(json!(true)))).await?;
+
+    loop {
+        let event = match events.recv().await {
+            Ok(event) => event,
+            Err(RecvError::Lagged(missed)) => {
+                debug!(event = "rpc consensus events lagged", missed);
+                continue;
+            }
+            Err(RecvError::Closed) => return Ok(()),
+        };
+
+        let round = event.round();
+        if range.is_over(round) {
+            return Ok(());
+        }
+        if !range.contains(round) {
+            continue;
+        }
+
+        let notification = json!({
+            "jsonrpc": "2.0",
+            "method": "consensus_event",
+            "params": event,
+        });
+        write_line(&mut writer, notification).await?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_request() {
+        let line = r#"{"jsonrpc":"2.0","id":3,"method":"subscribe_consensus_events","params":{"from_round":5}}"#;
+        let range = RoundRange {
+            from_round: Some(5),
+            to_round: None,
+        };
+        assert_eq!(
+            parse_request(line),
+            (json!(3), Ok(Call::ConsensusEvents(range)))
+        );
+
+        let line = r#"{"jsonrpc":"2.0","id":"a","method":"subscribe_consensus_events"}"#;
+        assert_eq!(
+            parse_request(line),
+            (json!("a"), Ok(Call::ConsensusEvents(RoundRange::default())))
+        );
+    }
+
+    #[test]
+    fn test_parse_request_errors() {
+        let code = |line: &str| parse_request(line).1.unwrap_err().code;
+
+        assert_eq!(code("{"), PARSE_ERROR);
+        assert_eq!(code(r#"{"id":1}"#), INVALID_REQUEST);
+        assert_eq!(
+            code(
+                r#"{"jsonrpc":"1.0","id":1,"method":"subscribe_consensus_events"}"#
+            ),
+            INVALID_REQUEST
+        );
+        // Lookups are served by the GraphQL queries
+        assert_eq!(
+            code(
+                r#"{"jsonrpc":"2.0","id":1,"method":"get_block_by_height","params":{"height":7}}"#
+            ),
+            METHOD_NOT_FOUND
+        );
+        assert_eq!(
+            code(
+                r#"{"jsonrpc":"2.0","id":1,"method":"subscribe_consensus_events","params":{"from_round":"a"}}"#
+            ),
+            INVALID_PARAMS
+        );
+    }
+
+    #[tokio::test]
+    async fn test_subscriptions_cap() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let connections = Arc::new(Semaphore::new(2));
+        let subscriptions = Arc::new(Semaphore::new(1));
+        let events = ConsensusEvents::default();
+
+        let request =
+            r#"{"jsonrpc":"2.0","id":1,"method":"subscribe_consensus_events"}"#;
+        let mut clients = vec![];
+        let mut replies = vec![];
+        for _ in 0..2 {
+            let client = TcpStream::connect(addr).await.unwrap();
+            let (stream, _) = listener.accept().await.unwrap();
+            let permit = connections.clone().try_acquire_owned().unwrap();
+            let subscriptions = subscriptions.clone();
+            let events = events.clone();
+            tokio::spawn(async move {
+                serve(stream, permit, &subscriptions, &events).await
+            });
+
+            let (reader, mut writer) = client.into_split();
+            writer
+                .write_all(format!("{request}\n").as_bytes())
+                .await
+                .unwrap();
+            let mut reader = BufReader::new(reader);
+            let mut reply = String::new();
+            reader.read_line(&mut reply).await.unwrap();
+            replies.push(serde_json::from_str::<Value>(&reply).unwrap());
+            clients.push((reader, writer));
+        }
+
+        // The subscription releases its connection permit
+        assert_eq!(replies[0]["result"], json!(true));
+        assert_eq!(subscriptions.available_permits(), 0);
+        assert_eq!(connections.available_permits(), 1);
+
+        // The connection subscribing over the cap is kept
+        assert_eq!(replies[1]["error"]["code"], json!(TOO_MANY_SUBSCRIPTIONS));
+    }
+}
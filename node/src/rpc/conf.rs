@@ -0,0 +1,38 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::net::SocketAddr;
+
+use serde::{Deserialize, Serialize};
+
+/// RPC service configuration parameters
+pub const DEFAULT_MAX_CONNECTIONS: usize = 64;
+pub const DEFAULT_MAX_SUBSCRIPTIONS: usize = 16;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct Params {
+    /// Address to listen for the JSON-RPC connections on. Unset to disable
+    /// the RPC service
+    pub listen_address: Option<SocketAddr>,
+
+    /// Maximum number of connections served at once, not counting the ones
+    /// turned into subscriptions
+    pub max_connections: usize,
+
+    /// Maximum number of subscriptions streamed at once
+    pub max_subscriptions: usize,
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Self {
+            listen_address: None,
+            max_connections: DEFAULT_MAX_CONNECTIONS,
+            max_subscriptions: DEFAULT_MAX_SUBSCRIPTIONS,
+        }
+    }
+}
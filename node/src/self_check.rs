@@ -0,0 +1,473 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Startup self-check.
+//!
+//! Before the services are started, the node checks that its environment is
+//! sane: the database and its column families, the consistency of the tip with
+//! its label and the metadata, the VM state root, the consensus keys, the
+//! local clock if an SNTP server is configured, the listening ports and the
+//! disk space.
+//!
+//! The outcome is emitted as a JSON [`Report`], and the node refuses to start
+//! if any [`CheckStatus::Critical`] check fails, unless configured otherwise.
+
+pub mod conf;
+
+use std::net::{TcpListener, UdpSocket};
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use node_data::get_current_timestamp;
+use node_data::keystore::unlock_file;
+use node_data::ledger::{Header, Label};
+use serde::Serialize;
+use tracing::{error, info, warn};
+
+use crate::database::rocksdb::{Backend, MD_HASH_KEY, MD_STATE_ROOT_KEY};
+use crate::database::{self, Ledger, Metadata};
+use crate::disk_guard::conf::Params as DiskGuardParams;
+use crate::disk_guard::{available_space, DiskStatus};
+use crate::self_check::conf::Params;
+use crate::vm;
+
+/// Seconds between the NTP epoch (1900) and the UNIX epoch (1970)
+const NTP_UNIX_OFFSET: u64 = 2_208_988_800;
+
+/// Size of an SNTP packet
+const SNTP_PACKET_SIZE: usize = 48;
+
+/// Outcome of a single check, ordered from the best to the worst
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Passed,
+    Warning,
+    Critical,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckResult {
+    pub name: &'static str,
+    pub status: CheckStatus,
+    pub detail: String,
+}
+
+/// Machine-readable outcome of the self-check
+#[derive(Debug, Clone, Serialize)]
+pub struct Report {
+    /// Timestamp of the report, in seconds
+    pub timestamp: u64,
+    /// The worst status among the checks
+    pub status: CheckStatus,
+    pub checks: Vec<CheckResult>,
+}
+
+impl Report {
+    fn push(
+        &mut self,
+        name: &'static str,
+        status: CheckStatus,
+        detail: String,
+    ) {
+        self.status = self.status.max(status);
+        self.checks.push(CheckResult {
+            name,
+            status,
+            detail,
+        });
+    }
+
+    /// Returns true if any critical check failed
+    pub fn has_critical(&self) -> bool {
+        self.status == CheckStatus::Critical
+    }
+}
+
+/// Protocol of a listening port
+#[derive(Debug, Clone, Copy)]
+pub enum Transport {
+    Tcp,
+    Udp,
+}
+
+/// Runs the startup checks, collecting their outcome in a [`Report`]
+pub struct SelfCheck {
+    conf: Params,
+    report: Report,
+}
+
+impl SelfCheck {
+    pub fn new(conf: Params) -> Self {
+        Self {
+            conf,
+            report: Report {
+                timestamp: get_current_timestamp(),
+                status: CheckStatus::Passed,
+                checks: vec![],
+            },
+        }
+    }
+
+    /// Checks that the database stored in `db_path` can be read and has all
+    /// of its column families.
+    ///
+    /// This is to be run before the database is opened, since opening it
+    /// creates any missing column family.
+    pub fn check_database(&mut self, db_path: &Path) {
+        let (status, detail) = match Backend::missing_column_families(db_path) {
+            Ok(None) => (CheckStatus::Passed, "new database".to_string()),
+            Ok(Some(missing)) if missing.is_empty() => {
+                (CheckStatus::Passed, "all column families found".into())
+            }
+            Ok(Some(missing)) => (
+                CheckStatus::Warning,
+                format!("column families to be created: {missing:?}"),
+            ),
+            Err(err) => (
+                CheckStatus::Critical,
+                format!("cannot read the database: {err}"),
+            ),
+        };
+        self.report.push("database", status, detail);
+    }
+
+    /// Checks the tip against its label and the metadata, and the VM state
+    /// root against the tip
+    pub fn check_chain<DB: database::DB, VM: vm::VMExecution>(
+        &mut self,
+        db: &DB,
+        vm: &VM,
+    ) {
+        let tip = match db.view(|t| load_tip(t)) {
+            Ok(tip) => tip,
+            Err(err) => {
+                self.report
+                    .push("tip", CheckStatus::Critical, err.to_string());
+                return;
+            }
+        };
+
+        let Some(tip) = tip else {
+            self.report
+                .push("tip", CheckStatus::Passed, "no tip yet".into());
+            return;
+        };
+        self.report.push(
+            "tip",
+            CheckStatus::Passed,
+            format!("height {} hash {}", tip.height, hex::encode(tip.hash)),
+        );
+
+        let (status, detail) = match vm.get_state_root() {
+            Ok(root) => match db.view(|t| state_root_in_use(t, &tip, root)) {
+                Ok(true) => (CheckStatus::Passed, hex::encode(root)),
+                Ok(false) => (
+                    CheckStatus::Critical,
+                    format!(
+                        "{} doesn't match any block since the last final one",
+                        hex::encode(root)
+                    ),
+                ),
+                Err(err) => (CheckStatus::Critical, err.to_string()),
+            },
+            Err(err) => (CheckStatus::Critical, err.to_string()),
+        };
+        self.report.push("vm_state_root", status, detail);
+    }
+
+    /// Checks that the consensus keys in `path` can be decrypted with the
+    /// password in the `DUSK_CONSENSUS_KEYS_PASS` env var
+    pub fn check_consensus_keys(&mut self, path: &str) {
+        let loaded = std::env::var("DUSK_CONSENSUS_KEYS_PASS")
+            .map_err(|_| anyhow::anyhow!("DUSK_CONSENSUS_KEYS_PASS not set"))
+            .and_then(|pwd| unlock_file(Path::new(path), &pwd));
+
+        let (status, detail) = match loaded {
+            Ok(keys) => {
+                (CheckStatus::Passed, format!("{:?}", keys.public_key()))
+            }
+            Err(err) => (CheckStatus::Critical, err.to_string()),
+        };
+        self.report.push("consensus_keys", status, detail);
+    }
+
+    /// Checks the local clock against the configured SNTP server, if any.
+    ///
+    /// The clock check never prevents the node from starting: both a drift
+    /// and an unreachable server only result in a warning.
+    pub async fn check_clock(&mut self) {
+        let Some(server) = self.conf.ntp_server.clone() else {
+            return;
+        };
+
+        let (status, detail) =
+            match sntp_drift(&server, self.conf.ntp_timeout).await {
+                Ok(drift) if drift > self.conf.max_clock_drift => (
+                    CheckStatus::Warning,
+                    format!("drift of {drift:?} from {server}"),
+                ),
+                Ok(drift) => (
+                    CheckStatus::Passed,
+                    format!("drift of {drift:?} from {server}"),
+                ),
+                Err(err) => (
+                    CheckStatus::Warning,
+                    format!("cannot query {server}: {err}"),
+                ),
+            };
+        self.report.push("clock", status, detail);
+    }
+
+    /// Checks that `addr` can be bound, releasing it right after
+    pub fn check_listener(
+        &mut self,
+        name: &'static str,
+        addr: &str,
+        transport: Transport,
+    ) {
+        let bound = match transport {
+            Transport::Tcp => TcpListener::bind(addr).map(drop),
+            Transport::Udp => UdpSocket::bind(addr).map(drop),
+        };
+
+        let (status, detail) = match bound {
+            Ok(()) => (CheckStatus::Passed, format!("{addr} ({transport:?})")),
+            Err(err) => (
+                CheckStatus::Critical,
+                format!("cannot bind {addr} ({transport:?}): {err}"),
+            ),
+        };
+        self.report.push(name, status, detail);
+    }
+
+    /// Checks the space available on the volume hosting `db_path` against
+    /// the disk guard thresholds
+    pub fn check_disk(&mut self, db_path: &Path, conf: &DiskGuardParams) {
+        // The database folder doesn't exist before the first start
+        let path = db_path.ancestors().find(|p| p.exists()).unwrap_or(db_path);
+
+        let (status, detail) = match available_space(path) {
+            Ok(available) => {
+                let status = match DiskStatus::from_available(available, conf) {
+                    DiskStatus::Healthy => CheckStatus::Passed,
+                    DiskStatus::AcceptanceHalted => CheckStatus::Critical,
+                    _ => CheckStatus::Warning,
+                };
+                (status, format!("{available} bytes available"))
+            }
+            Err(err) => (CheckStatus::Warning, err.to_string()),
+        };
+        self.report.push("disk_space", status, detail);
+    }
+
+    /// Emits the report, returning an error if a critical check failed and
+    /// the node must not start
+    pub fn finish(self) -> anyhow::Result<Report> {
+        let report = self.report;
+
+        for check in &report.checks {
+            match check.status {
+                CheckStatus::Passed => {
+                    info!(event = "self-check", name = check.name, detail = %check.detail)
+                }
+                CheckStatus::Warning => {
+                    warn!(event = "self-check", name = check.name, detail = %check.detail)
+                }
+                CheckStatus::Critical => {
+                    error!(event = "self-check", name = check.name, detail = %check.detail)
+                }
+            }
+        }
+
+        let json = serde_json::to_string(&report)?;
+        info!(event = "self-check report", report = %json);
+        if let Some(path) = &self.conf.report_path {
+            if let Err(err) = std::fs::write(path, &json) {
+                warn!(event = "cannot write self-check report", ?path, ?err);
+            }
+        }
+
+        if report.has_critical() && self.conf.refuse_on_critical {
+            let failed: Vec<_> = report
+                .checks
+                .iter()
+                .filter(|c| c.status == CheckStatus::Critical)
+                .map(|c| c.name)
+                .collect();
+            anyhow::bail!("startup self-check failed: {failed:?}");
+        }
+
+        Ok(report)
+    }
+}
+
+/// Returns the tip header, after checking it against its label and the stored
+/// state root
+fn load_tip<T: Ledger + Metadata>(t: &T) -> anyhow::Result<Option<Header>> {
+    let Some(tip_hash) = t.op_read(MD_HASH_KEY)? else {
+        return Ok(None);
+    };
+
+    let header = t.block_header(&tip_hash)?.ok_or_else(|| {
+        anyhow::anyhow!("tip {} not found", hex::encode(&tip_hash))
+    })?;
+
+    let (hash, _) =
+        t.block_label_by_height(header.height)?.ok_or_else(|| {
+            anyhow::anyhow!("no label at tip height {}", header.height)
+        })?;
+    if hash != header.hash {
+        anyhow::bail!(
+            "tip {} doesn't match {} at height {}",
+            hex::encode(header.hash),
+            hex::encode(hash),
+            header.height
+        );
+    }
+
+    if let Some(state_root) = t.op_read(MD_STATE_ROOT_KEY)? {
+        if state_root[..] != header.state_hash[..] {
+            anyhow::bail!(
+                "stored state root {} doesn't match the tip one {}",
+                hex::encode(state_root),
+                hex::encode(header.state_hash)
+            );
+        }
+    }
+
+    Ok(Some(header))
+}
+
+/// Returns true if `root` is the state root of `tip` or of one of its
+/// ancestors down to the last final block, which the VM restarts from
+fn state_root_in_use<T: Ledger>(
+    t: &T,
+    tip: &Header,
+    root: [u8; 32],
+) -> anyhow::Result<bool> {
+    let mut header = tip.clone();
+    loop {
+        if header.state_hash == root {
+            return Ok(true);
+        }
+
+        let label = t.block_label_by_height(header.height)?;
+        if header.height == 0
+            || matches!(label, None | Some((_, Label::Final(_))))
+        {
+            return Ok(false);
+        }
+
+        header = match t.block_header(&header.prev_block_hash)? {
+            Some(prev) => prev,
+            None => return Ok(false),
+        };
+    }
+}
+
+/// Returns the UNIX time carried in the transmit timestamp of an SNTP reply
+fn parse_sntp_reply(reply: &[u8]) -> Option<Duration> {
+    let secs = u32::from_be_bytes(reply.get(40..44)?.try_into().ok()?);
+    let frac = u32::from_be_bytes(reply.get(44..48)?.try_into().ok()?);
+
+    let secs = (secs as u64).checked_sub(NTP_UNIX_OFFSET)?;
+    let nanos = ((frac as u64) * 1_000_000_000) >> 32;
+    Some(Duration::new(secs, nanos as u32))
+}
+
+/// Returns the drift of the local clock from the SNTP `server`
+async fn sntp_drift(
+    server: &str,
+    timeout: Duration,
+) -> anyhow::Result<Duration> {
+    let socket = tokio::net::UdpSocket::bind("0.0.0.0:0").await?;
+    socket.connect(server).await?;
+
+    // LI = 0, VN = 3, Mode = 3 (client)
+    let mut request = [0u8; SNTP_PACKET_SIZE];
+    request[0] = 0x1b;
+
+    let sent_at = SystemTime::now().duration_since(UNIX_EPOCH)?;
+    socket.send(&request).await?;
+
+    let mut reply = [0u8; SNTP_PACKET_SIZE];
+    let len = tokio::time::timeout(timeout, socket.recv(&mut reply)).await??;
+    let received_at = SystemTime::now().duration_since(UNIX_EPOCH)?;
+
+    let server_time = parse_sntp_reply(&reply[..len])
+        .ok_or_else(|| anyhow::anyhow!("invalid SNTP reply"))?;
+
+    // Assume the reply was sent halfway through the round trip
+    let local_time = sent_at + (received_at.saturating_sub(sent_at)) / 2;
+
+    Ok(if server_time > local_time {
+        server_time - local_time
+    } else {
+        local_time - server_time
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{DatabaseOptions, DB};
+
+    #[test]
+    fn test_parse_sntp_reply() {
+        let mut reply = [0u8; SNTP_PACKET_SIZE];
+        let secs = NTP_UNIX_OFFSET + 1_700_000_000;
+        reply[40..44].copy_from_slice(&(secs as u32).to_be_bytes());
+        reply[44..48].copy_from_slice(&(u32::MAX / 2 + 1).to_be_bytes());
+
+        assert_eq!(
+            parse_sntp_reply(&reply),
+            Some(Duration::from_millis(1_700_000_000_500))
+        );
+        assert_eq!(parse_sntp_reply(&reply[..47]), None);
+
+        // Timestamps before the UNIX epoch are invalid
+        assert_eq!(parse_sntp_reply(&[0u8; SNTP_PACKET_SIZE]), None);
+    }
+
+    #[test]
+    fn test_report() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut check = SelfCheck::new(Params {
+            report_path: Some(dir.path().join("report.json")),
+            ..Default::default()
+        });
+
+        check.check_database(dir.path());
+        let db =
+            Backend::create_or_open(dir.path(), DatabaseOptions::default());
+        drop(db);
+        check.check_database(dir.path());
+
+        check.check_listener("tcp_listener", "127.0.0.1:0", Transport::Tcp);
+        let report = check.finish().expect("no critical check to fail");
+        assert_eq!(report.status, CheckStatus::Passed);
+        assert_eq!(report.checks[0].detail, "new database");
+        assert_eq!(report.checks[1].detail, "all column families found");
+        assert!(dir.path().join("report.json").exists());
+
+        // An address already bound can't be bound again
+        let bound = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let addr = bound.local_addr().unwrap().to_string();
+
+        let mut check = SelfCheck::new(Params::default());
+        check.check_listener("udp_listener", &addr, Transport::Udp);
+        assert!(check.finish().is_err());
+
+        let mut check = SelfCheck::new(Params {
+            refuse_on_critical: false,
+            ..Default::default()
+        });
+        check.check_listener("udp_listener", &addr, Transport::Udp);
+        let report = check.finish().expect("critical checks to be tolerated");
+        assert!(report.has_critical());
+    }
+}
@@ -0,0 +1,50 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+/// Startup self-check configuration parameters
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct Params {
+    /// Whether the self-check is run on startup
+    pub enabled: bool,
+
+    /// Whether the node refuses to start when a critical check fails
+    pub refuse_on_critical: bool,
+
+    /// SNTP server the local clock is compared to, if any. The clock isn't
+    /// checked unless it is set
+    pub ntp_server: Option<String>,
+
+    /// Time to wait for the SNTP server to reply
+    #[serde(with = "humantime_serde")]
+    pub ntp_timeout: Duration,
+
+    /// Drift of the local clock from the SNTP server above which a warning is
+    /// reported
+    #[serde(with = "humantime_serde")]
+    pub max_clock_drift: Duration,
+
+    /// File the JSON report is written to, in addition to the logs
+    pub report_path: Option<PathBuf>,
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            refuse_on_critical: true,
+            ntp_server: None,
+            ntp_timeout: Duration::from_secs(2),
+            max_clock_drift: Duration::from_secs(5),
+            report_path: None,
+        }
+    }
+}
@@ -4,22 +4,35 @@
 //
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
+use std::collections::BTreeSet;
+
 use dusk_consensus::errors::VstError;
 use dusk_consensus::operations::{CallParams, VerificationOutput, Voter};
 use dusk_consensus::user::provisioners::Provisioners;
 use dusk_consensus::user::stake::Stake;
+use dusk_core::abi::ContractId;
 use dusk_core::signatures::bls::PublicKey as BlsPublicKey;
 use dusk_core::transfer::moonlight::AccountData;
+use node_data::bls::PublicKeyBytes;
 use node_data::events::contract::ContractTxEvent;
-use node_data::ledger::{Block, SpentTransaction, Transaction};
+use node_data::ledger::{Block, Hash, Header, SpentTransaction, Transaction};
 
 #[derive(Default)]
 pub struct Config {}
 
-pub trait VMExecution: Send + Sync + 'static {
+/// Gas spent calling each contract, see [`VMExecution::accept`]
+pub type ContractGas = Vec<(ContractId, u64)>;
+
+/// Execution of the blocks and transactions.
+///
+/// Cloning a VM is cheap, the clones sharing the same state.
+pub trait VMExecution: Clone + Send + Sync + 'static {
+    /// Executes the transactions of a candidate within `ctx`, see
+    /// [`BlockContext::candidate`]
     fn execute_state_transition<I: Iterator<Item = Transaction>>(
         &self,
         params: &CallParams,
+        ctx: &BlockContext,
         txs: I,
     ) -> anyhow::Result<(
         Vec<SpentTransaction>,
@@ -27,33 +40,66 @@ pub trait VMExecution: Send + Sync + 'static {
         VerificationOutput,
     )>;
 
+    /// Executes `blk` on top of `prev_root` within `ctx`, without moving the
+    /// tip
     fn verify_state_transition(
         &self,
         prev_root: [u8; 32],
         blk: &Block,
+        ctx: &BlockContext,
         voters: &[Voter],
     ) -> Result<VerificationOutput, VstError>;
 
+    /// Executes `blk` on top of `prev_root` within `ctx`, moving the tip to
+    /// the resulting state.
+    ///
+    /// Along with the spent transactions, the verification output and the
+    /// contract events, returns the gas spent calling each contract by the
+    /// transactions, nested calls included.
     fn accept(
         &self,
         prev_root: [u8; 32],
         blk: &Block,
+        ctx: &BlockContext,
         voters: &[Voter],
     ) -> anyhow::Result<(
         Vec<SpentTransaction>,
         VerificationOutput,
         Vec<ContractTxEvent>,
+        ContractGas,
     )>;
 
+    /// Re-executes `blk` on top of `prev_root`, without checking its outcome
+    /// nor moving the tip.
+    ///
+    /// The resulting state is stored in a side commit. Along with the spent
+    /// transactions and the verification output, returns whether such commit
+    /// is new, hence should be deleted with [`Self::delete_side_commit`] once
+    /// done with it.
+    fn replay(
+        &self,
+        prev_root: [u8; 32],
+        blk: &Block,
+        ctx: &BlockContext,
+        voters: &[Voter],
+    ) -> anyhow::Result<(Vec<SpentTransaction>, VerificationOutput, bool)>;
+
+    /// Deletes a side commit created by [`Self::replay`]. Any other commit,
+    /// e.g. the tip or a candidate state still in flight, is kept
+    fn delete_side_commit(&self, commit: [u8; 32]) -> anyhow::Result<()>;
+
     fn finalize_state(
         &self,
         commit: [u8; 32],
         to_merge: Vec<[u8; 32]>,
     ) -> anyhow::Result<()>;
 
+    /// Preverifies `tx` against the current state, to be included in a block
+    /// at `block_height`
     fn preverify(
         &self,
         tx: &Transaction,
+        block_height: u64,
     ) -> anyhow::Result<PreverificationResult>;
 
     fn get_provisioners(
@@ -66,6 +112,25 @@ pub trait VMExecution: Send + Sync + 'static {
         base_commit: [u8; 32],
     ) -> anyhow::Result<Vec<(node_data::bls::PublicKey, Option<Stake>)>>;
 
+    /// Returns the commitment to the provisioner set at `base_commit` that a
+    /// block at `block_height` must include, or `None` if such blocks carry
+    /// no commitment
+    fn get_provisioners_root(
+        &self,
+        block_height: u64,
+        base_commit: [u8; 32],
+    ) -> anyhow::Result<Option<[u8; 32]>>;
+
+    /// Returns true if blocks at `block_height` carry the extra-data of their
+    /// generator
+    fn with_header_extra_data(&self, block_height: u64) -> bool;
+
+    /// Returns the names of the features active at `block_height`
+    fn active_features(&self, block_height: u64) -> BTreeSet<String>;
+
+    /// Returns the economic parameters in force at `block_height`
+    fn economic_params(&self, block_height: u64) -> EconomicParams;
+
     fn get_provisioner(
         &self,
         pk: &BlsPublicKey,
@@ -78,6 +143,19 @@ pub trait VMExecution: Send + Sync + 'static {
     /// Returns last finalized state root
     fn get_finalized_state_root(&self) -> anyhow::Result<[u8; 32]>;
 
+    /// Calls `method` of `contract` with the serialized `args` against the
+    /// state at `state_root`, returning the serialized result.
+    ///
+    /// The states of finalized blocks other than the last one are merged, and
+    /// cannot be queried.
+    fn query_at(
+        &self,
+        state_root: [u8; 32],
+        contract: ContractId,
+        method: &str,
+        args: Vec<u8>,
+    ) -> anyhow::Result<Vec<u8>>;
+
     /// Returns block gas limit
     fn get_block_gas_limit(&self) -> u64;
 
@@ -90,6 +168,84 @@ pub trait VMExecution: Send + Sync + 'static {
     fn min_deploy_points(&self) -> u64;
 }
 
+/// Economic parameters in force at a block height
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EconomicParams {
+    /// Amount of Dusk emitted by the block, on top of the fees it collects
+    pub emission: u64,
+    /// Gas charged for each byte of a deployed contract bytecode
+    pub gas_per_deploy_byte: u64,
+    /// Minimum gas charged for a contract deployment
+    pub min_deploy_points: u64,
+    /// Minimum gas price of a contract deployment
+    pub min_deployment_gas_price: u64,
+}
+
+/// Name of the feature requiring the transactions of a block to be in
+/// canonical order, see [`node_data::ledger::OrderKey`]
+pub const FEATURE_CANONICAL_TX_ORDER: &str = "CANONICAL_TX_ORDER";
+
+/// Everything the execution of a block depends on, besides its transactions
+/// and the state it's executed on top of.
+///
+/// The context is assembled by the chain layer out of the block header and
+/// the parameters in force at its height, so that re-executing a block
+/// within the same context yields the same state.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockContext {
+    pub height: u64,
+    pub hash: Hash,
+    pub generator: PublicKeyBytes,
+    pub gas_limit: u64,
+    /// Names of the features active at the height of the block
+    pub features: BTreeSet<String>,
+    pub economic: EconomicParams,
+}
+
+impl BlockContext {
+    /// Assembles the context of the block with the given `header`, taking
+    /// the parameters in force at its height from `vm`
+    pub fn new<V: VMExecution + ?Sized>(header: &Header, vm: &V) -> Self {
+        Self {
+            height: header.height,
+            hash: header.hash,
+            generator: header.generator_bls_pubkey,
+            gas_limit: header.gas_limit,
+            features: vm.active_features(header.height),
+            economic: vm.economic_params(header.height),
+        }
+    }
+
+    /// Assembles the context of a candidate block at `height` generated by
+    /// `generator`, whose hash is only known once its transactions are
+    /// executed
+    pub fn candidate<V: VMExecution + ?Sized>(
+        height: u64,
+        generator: PublicKeyBytes,
+        vm: &V,
+    ) -> Self {
+        Self {
+            height,
+            hash: Hash::default(),
+            generator,
+            gas_limit: vm.get_block_gas_limit(),
+            features: vm.active_features(height),
+            economic: vm.economic_params(height),
+        }
+    }
+
+    /// Returns true if `feature` is active at the height of the block
+    pub fn is_active(&self, feature: &str) -> bool {
+        self.features.contains(feature)
+    }
+
+    /// Returns true if the transactions of the block must be in canonical
+    /// order
+    pub fn with_canonical_tx_order(&self) -> bool {
+        self.is_active(FEATURE_CANONICAL_TX_ORDER)
+    }
+}
+
 #[allow(clippy::large_enum_variant)]
 pub enum PreverificationResult {
     Valid,
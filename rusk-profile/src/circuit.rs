@@ -14,6 +14,7 @@ use serde::{Deserialize, Serialize};
 use tracing::info;
 use version_check::Version;
 
+use crate::keys::{KeysChecksum, CHECKSUM_EXTENSION};
 use crate::{
     extension, file_name, file_stem, get_rusk_circuits_dir, get_rusk_keys_dir,
     Theme,
@@ -161,10 +162,12 @@ impl Circuit {
     }
 
     /// Fetches the prover key if stored in the keys directory
+    ///
+    /// The key is checked against its checksum, and is considered not found
+    /// when compiled with another plonk version than the circuit's one.
     pub fn get_prover(&self) -> io::Result<Vec<u8>> {
-        let mut file = get_rusk_keys_dir()?;
-        file.push(self.id_str());
-        file.set_extension("pk");
+        let checksum = self.keys_checksum()?;
+        let file = self.keys_file("pk")?;
 
         let pk = match &file.exists() {
             true => read(file),
@@ -172,15 +175,18 @@ impl Circuit {
                 Err(io::Error::new(ErrorKind::NotFound, "ProverKey not found"))
             }
         }?;
+        checksum.check_prover_key(&pk)?;
 
         Ok(pk)
     }
 
     /// Fetches the verifier data if stored in the keys directory
+    ///
+    /// The data is checked against its checksum, and is considered not found
+    /// when compiled with another plonk version than the circuit's one.
     pub fn get_verifier(&self) -> io::Result<Vec<u8>> {
-        let mut file = get_rusk_keys_dir()?;
-        file.push(self.id_str());
-        file.set_extension("vd");
+        let checksum = self.keys_checksum()?;
+        let file = self.keys_file("vd")?;
 
         let vd = match &file.exists() {
             true => read(file),
@@ -189,6 +195,7 @@ impl Circuit {
                 "VerifierData not found",
             )),
         }?;
+        checksum.check_verifier_data(&vd)?;
 
         Ok(vd)
     }
@@ -198,20 +205,38 @@ impl Circuit {
         Ok((self.get_prover()?, self.get_verifier()?))
     }
 
-    /// Stores the given prover key and verifier data
+    /// Stores the given prover key and verifier data, along with their
+    /// checksum
     pub fn add_keys(&self, pk: Vec<u8>, vd: Vec<u8>) -> io::Result<()> {
-        let mut file = get_rusk_keys_dir()?;
-        file.push(self.id_str());
+        let checksum = KeysChecksum::new(self.plonk_version(), &pk, &vd);
 
-        let pk_file = file.with_extension("pk");
-        let vd_file = file.with_extension("vd");
-
-        File::create(pk_file)?.write_all(&pk)?;
-        File::create(vd_file)?.write_all(&vd)?;
+        File::create(self.keys_file("pk")?)?.write_all(&pk)?;
+        File::create(self.keys_file("vd")?)?.write_all(&vd)?;
+        // The checksum is written last, so that keys partially written are
+        // never considered valid
+        checksum.store(&self.keys_file(CHECKSUM_EXTENSION)?)?;
 
         Ok(())
     }
 
+    /// Returns the path of the file with the given extension in the keys
+    /// directory
+    fn keys_file(&self, extension: &str) -> io::Result<PathBuf> {
+        let mut file = get_rusk_keys_dir()?;
+        file.push(self.id_str());
+        file.set_extension(extension);
+        Ok(file)
+    }
+
+    /// Reads the checksum of the stored keys, checking that they were
+    /// compiled with the plonk version of the circuit
+    fn keys_checksum(&self) -> io::Result<KeysChecksum> {
+        let checksum =
+            KeysChecksum::from_file(&self.keys_file(CHECKSUM_EXTENSION)?)?;
+        checksum.check_version(self.plonk_version())?;
+        Ok(checksum)
+    }
+
     /// Cleans all stored files associated with the [`Circuit`]
     pub fn clean(&self) -> io::Result<()> {
         // collect all files with the circuit id as the file stem in circuits
@@ -0,0 +1,118 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Integrity of the keys cached in the keys directory.
+//!
+//! Next to the prover key and verifier data of a circuit, a checksum file
+//! records their hashes and the plonk version they were compiled with. Keys
+//! whose hash doesn't match, or compiled with another plonk version than the
+//! one of the circuit, are treated as missing so that they get compiled
+//! again.
+
+use std::fs::{read, File};
+use std::io::prelude::*;
+use std::io::{self, ErrorKind};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+/// Extension of the checksum files in the keys directory
+pub(crate) const CHECKSUM_EXTENSION: &str = "sum";
+
+/// Checksum of the keys of a circuit
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub(crate) struct KeysChecksum {
+    plonk_version: Option<String>,
+    prover_key: String,
+    verifier_data: String,
+}
+
+impl KeysChecksum {
+    /// Computes the checksum of the given prover key and verifier data
+    pub(crate) fn new(
+        plonk_version: Option<&str>,
+        pk: &[u8],
+        vd: &[u8],
+    ) -> Self {
+        Self {
+            plonk_version: plonk_version.map(String::from),
+            prover_key: hash(pk),
+            verifier_data: hash(vd),
+        }
+    }
+
+    /// Reads the checksum stored in `file`
+    pub(crate) fn from_file(file: &Path) -> io::Result<Self> {
+        if !file.exists() {
+            return Err(io::Error::new(
+                ErrorKind::NotFound,
+                "Keys checksum not found",
+            ));
+        }
+        let content = read(file)?;
+        let content = std::str::from_utf8(&content).map_err(|e| {
+            io::Error::new(
+                ErrorKind::InvalidData,
+                format!("Couldn't read keys checksum: {e}"),
+            )
+        })?;
+        toml::from_str(content).map_err(|e| {
+            io::Error::new(
+                ErrorKind::InvalidData,
+                format!("Couldn't parse keys checksum: {e}"),
+            )
+        })
+    }
+
+    /// Stores the checksum in `file`
+    pub(crate) fn store(&self, file: &Path) -> io::Result<()> {
+        let toml = toml::to_string(self).map_err(|e| {
+            io::Error::new(
+                ErrorKind::InvalidData,
+                format!("Couldn't create string from keys checksum: {e}"),
+            )
+        })?;
+        File::create(file)?.write_all(toml.as_bytes())
+    }
+
+    /// Checks that the keys were compiled with `plonk_version`
+    pub(crate) fn check_version(
+        &self,
+        plonk_version: Option<&str>,
+    ) -> io::Result<()> {
+        if self.plonk_version.as_deref() != plonk_version {
+            return Err(io::Error::new(
+                ErrorKind::NotFound,
+                "Keys compiled with another plonk version",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Checks the integrity of a prover key
+    pub(crate) fn check_prover_key(&self, pk: &[u8]) -> io::Result<()> {
+        check(&self.prover_key, pk, "ProverKey")
+    }
+
+    /// Checks the integrity of a verifier data
+    pub(crate) fn check_verifier_data(&self, vd: &[u8]) -> io::Result<()> {
+        check(&self.verifier_data, vd, "VerifierData")
+    }
+}
+
+fn hash(bytes: &[u8]) -> String {
+    blake3::hash(bytes).to_hex().to_string()
+}
+
+fn check(expected: &str, bytes: &[u8], what: &str) -> io::Result<()> {
+    if hash(bytes) != expected {
+        return Err(io::Error::new(
+            ErrorKind::InvalidData,
+            format!("{what} doesn't match its checksum"),
+        ));
+    }
+    Ok(())
+}
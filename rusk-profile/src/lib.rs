@@ -21,6 +21,8 @@ pub use theme::Theme;
 mod circuit;
 pub use circuit::Circuit;
 
+mod keys;
+
 /// HEX representation of the SHA-256 hash of the CRS uncompressed bytes.
 /// This is the hash of the contribution number 15 of the Dusk Trusted Setup.
 pub static CRS_17_HASH: &str =
@@ -224,7 +226,7 @@ fn clean_outdated_keys(ids: &[&str]) -> io::Result<()> {
         .filter_map(|res| res.ok())
         .filter(|e| e.is_file())
         .filter(|p| match extension(p) {
-            Some("pk" | "vd") => {
+            Some("pk" | "vd" | keys::CHECKSUM_EXTENSION) => {
                 file_stem(p).filter(|id| ids.contains(id)).is_none()
             }
             _ => true,
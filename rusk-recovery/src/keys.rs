@@ -74,17 +74,18 @@ fn check_circuits_cache(
     let theme = Theme::default();
     for circuit in circuit_list {
         info!(
-            "{} {} verifier data from cache",
+            "{} {} keys from cache",
             theme.action("Fetching"),
             circuit.name()
         );
-        match circuit.get_verifier() {
+        match circuit.get_keys() {
             Ok(_) => {
                 info!("{}   {}.vd", theme.info("Found"), circuit.id_str());
+                info!("{}   {}.pk", theme.info("Found"), circuit.id_str());
             }
 
-            _ => {
-                warn!("{} due to cache miss", theme.warn("Compiling"),);
+            Err(e) => {
+                warn!("{} due to cache miss: {e}", theme.warn("Compiling"));
 
                 let compressed = circuit.get_compressed();
                 let (pk, vd) = Compiler::compile_with_compressed(
@@ -4,6 +4,7 @@
 //
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
+use std::collections::BTreeSet;
 use std::error::Error;
 use std::fs;
 use std::path::Path;
@@ -14,6 +15,7 @@ use dusk_core::stake::{StakeAmount, StakeData, StakeKeys, STAKE_CONTRACT};
 use dusk_core::transfer::phoenix::{Note, Sender};
 use dusk_core::transfer::TRANSFER_CONTRACT;
 use dusk_core::JubJubScalar;
+use dusk_vm::proof::verify_memory;
 use dusk_vm::{ContractData, Session, VM};
 use ff::Field;
 use rand::rngs::StdRng;
@@ -287,6 +289,175 @@ pub fn restore_state<P: AsRef<Path>>(
     Ok((vm, commit_id))
 }
 
+/// Exports the state in `state_dir` into the archive `output`, returning the
+/// root of the exported state.
+///
+/// The state is checked against its persisted root before being archived,
+/// see [`verify_state_root`]. The node using the state should be stopped during
+/// the export.
+pub fn export_state<P: AsRef<Path>, Q: AsRef<Path>>(
+    state_dir: P,
+    output: Q,
+) -> Result<[u8; 32], Box<dyn Error>> {
+    let theme = Theme::default();
+    let state_dir = state_dir.as_ref();
+    let output = output.as_ref();
+
+    if output.exists() {
+        return Err(format!("{} already exists", output.display()).into());
+    }
+
+    let (vm, commit_id) = restore_state(state_dir)?;
+    verify_state_root(&vm, state_dir, commit_id)?;
+    drop(vm);
+
+    info!(
+        "{} state into {}",
+        theme.action("Exporting"),
+        output.display()
+    );
+    tar::archive(state_dir, output)?;
+
+    info!(
+        "{} {}",
+        theme.action("Exported Root"),
+        hex::encode(commit_id)
+    );
+    Ok(commit_id)
+}
+
+/// Imports the state exported in the archive `input` into `state_dir`,
+/// returning the root of the imported state.
+///
+/// The archive is unpacked next to `state_dir` first, and only replaces it
+/// once the imported state is checked against its persisted root, see
+/// [`verify_state_root`], matching `expected_root` if given. An existing state
+/// is only replaced if `force` is set.
+pub fn import_state<P: AsRef<Path>, Q: AsRef<Path>>(
+    input: P,
+    state_dir: Q,
+    expected_root: Option<[u8; 32]>,
+    force: bool,
+) -> Result<[u8; 32], Box<dyn Error>> {
+    let theme = Theme::default();
+    let input = input.as_ref();
+    let state_dir = state_dir.as_ref();
+
+    if rusk_profile::to_rusk_state_id_path(state_dir).exists() && !force {
+        return Err(format!(
+            "A state already exists in {}",
+            state_dir.display()
+        )
+        .into());
+    }
+
+    info!(
+        "{} state from {}",
+        theme.action("Importing"),
+        input.display()
+    );
+    let staging = state_dir.with_extension("import");
+    if staging.exists() {
+        fs::remove_dir_all(&staging)?;
+    }
+    fs::create_dir_all(&staging)?;
+
+    let imported = tar::unarchive_file(input, &staging)
+        .and_then(|_| restore_state(&staging))
+        .and_then(|(vm, commit_id)| {
+            verify_state_root(&vm, &staging, commit_id)?;
+            match expected_root {
+                Some(root) if root != commit_id => Err(format!(
+                    "State root mismatch, expected {} found {}",
+                    hex::encode(root),
+                    hex::encode(commit_id)
+                )
+                .into()),
+                _ => Ok(commit_id),
+            }
+        });
+    let commit_id = match imported {
+        Ok(commit_id) => commit_id,
+        Err(err) => {
+            fs::remove_dir_all(&staging)?;
+            return Err(err);
+        }
+    };
+
+    if state_dir.exists() {
+        fs::remove_dir_all(state_dir)?;
+    }
+    fs::rename(&staging, state_dir)?;
+
+    info!(
+        "{} {}",
+        theme.action("Imported Root"),
+        hex::encode(commit_id)
+    );
+    Ok(commit_id)
+}
+
+/// Checks the state of `vm` at `state_root`, by opening the memory of its
+/// contracts against the root.
+///
+/// The contracts are the ones whose identifier names an entry of `state_dir`,
+/// the genesis contracts being required.
+pub fn verify_state_root(
+    vm: &VM,
+    state_dir: &Path,
+    state_root: [u8; 32],
+) -> Result<(), Box<dyn Error>> {
+    // The chain identifier only matters to the execution of transactions
+    let session = vm.session(state_root, GENESIS_CHAIN_ID, 0)?;
+    if session.root() != state_root {
+        return Err("State root mismatch".into());
+    }
+
+    let not_matching = |contract: ContractId| {
+        format!(
+            "Contract {} not matching the state root",
+            hex::encode(contract.as_bytes())
+        )
+    };
+    for contract in [TRANSFER_CONTRACT, STAKE_CONTRACT] {
+        if verify_memory(&session, contract, &state_root) != Some(true) {
+            return Err(not_matching(contract).into());
+        }
+    }
+    for contract in stored_contracts(state_dir)? {
+        let contract = ContractId::from_bytes(contract);
+        if verify_memory(&session, contract, &state_root) == Some(false) {
+            return Err(not_matching(contract).into());
+        }
+    }
+    Ok(())
+}
+
+/// Returns the identifiers naming the entries of `dir`, at any depth
+fn stored_contracts(dir: &Path) -> Result<BTreeSet<[u8; 32]>, Box<dyn Error>> {
+    let mut contracts = BTreeSet::new();
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for entry in fs::read_dir(&dir)? {
+            let entry = entry?;
+            if entry.file_type()?.is_dir() {
+                dirs.push(entry.path());
+            }
+
+            let path = entry.path();
+            let Some(stem) = path.file_stem().and_then(|s| s.to_str()) else {
+                continue;
+            };
+            if let Ok(bytes) = hex::decode(stem) {
+                if let Ok(id) = bytes.try_into() {
+                    contracts.insert(id);
+                }
+            }
+        }
+    }
+    Ok(contracts)
+}
+
 /// Load a state file and save it into the rusk state directory.
 fn load_state<P: AsRef<Path>>(
     state_dir: P,
@@ -357,4 +528,68 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn export_import() -> Result<(), Box<dyn Error>> {
+        let mainnet = mainnet_from_file()?;
+        let tmp = tempfile::TempDir::with_prefix("export")
+            .expect("Should be able to create temporary directory");
+        let state_dir = tmp.path().join("state");
+        let archive = tmp.path().join("state.tar.gz");
+        let imported_dir = tmp.path().join("imported");
+
+        let (vm, root) =
+            deploy(&state_dir, &mainnet, dusk_mainnet_key(), |_| {})?;
+        drop(vm);
+
+        assert_eq!(export_state(&state_dir, &archive)?, root);
+        assert!(export_state(&state_dir, &archive).is_err());
+
+        assert!(import_state(&archive, &imported_dir, Some([0; 32]), false)
+            .is_err());
+        assert!(!imported_dir.exists());
+
+        let imported =
+            import_state(&archive, &imported_dir, Some(root), false)?;
+        assert_eq!(imported, root);
+        assert!(import_state(&archive, &imported_dir, None, false).is_err());
+        assert_eq!(import_state(&archive, &imported_dir, None, true)?, root);
+
+        let (vm, restored) = restore_state(&imported_dir)?;
+        assert_eq!(restored, root);
+        verify_state_root(&vm, &imported_dir, root)?;
+        drop(vm);
+
+        // A corrupted contract memory doesn't match the root anymore
+        corrupt_memory(&imported_dir)?;
+        let (vm, _) = restore_state(&imported_dir)?;
+        assert!(verify_state_root(&vm, &imported_dir, root).is_err());
+        drop(vm);
+        assert!(
+            export_state(&imported_dir, tmp.path().join("corrupted")).is_err()
+        );
+
+        Ok(())
+    }
+
+    /// Flips the first byte of the memory pages stored in `dir`
+    fn corrupt_memory(dir: &Path) -> Result<(), Box<dyn Error>> {
+        let mut dirs = vec![(dir.to_path_buf(), false)];
+        while let Some((dir, in_memory)) = dirs.pop() {
+            for entry in fs::read_dir(&dir)? {
+                let path = entry?.path();
+                if path.is_dir() {
+                    let memory = in_memory || path.ends_with("memory");
+                    dirs.push((path, memory));
+                } else if in_memory {
+                    let mut page = fs::read(&path)?;
+                    if let Some(byte) = page.first_mut() {
+                        *byte ^= 0xff;
+                        fs::write(&path, page)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
 }
@@ -4,7 +4,11 @@
 //
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
+use std::collections::BTreeSet;
+use std::error::Error;
 use std::fmt::Debug;
+use std::fs;
+use std::path::Path;
 
 use dusk_bytes::Serializable;
 use dusk_core::signatures::bls::PublicKey as AccountPublicKey;
@@ -97,6 +101,70 @@ impl Snapshot {
     pub fn base_state(&self) -> Option<&str> {
         self.base_state.as_deref()
     }
+
+    /// Reads the genesis spec in `file`, parsed as JSON if its extension is
+    /// `json` and as TOML otherwise, and validates it.
+    pub fn from_file<P: AsRef<Path>>(file: P) -> Result<Self, Box<dyn Error>> {
+        let file = file.as_ref();
+        let content = fs::read_to_string(file)
+            .map_err(|e| format!("Cannot read {}: {e}", file.display()))?;
+
+        let is_json = file
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+        let snapshot: Self = if is_json {
+            serde_json::from_str(&content)?
+        } else {
+            toml::from_str(&content)?
+        };
+
+        snapshot.validate()?;
+        Ok(snapshot)
+    }
+
+    /// Checks that every entry of the spec changes the genesis state, and
+    /// that no account or provisioner is listed twice.
+    pub fn validate(&self) -> Result<(), String> {
+        for balance in self.phoenix_balances() {
+            if balance.notes.iter().any(|&value| value == 0) {
+                let address = bs58::encode(balance.address.to_bytes());
+                return Err(format!(
+                    "Zero value note for {}",
+                    address.into_string()
+                ));
+            }
+        }
+
+        let mut accounts = BTreeSet::new();
+        for account in self.moonlight_accounts() {
+            if !accounts.insert(account.address.to_bytes()) {
+                let address = bs58::encode(account.address.to_bytes());
+                return Err(format!(
+                    "Duplicate moonlight account {}",
+                    address.into_string()
+                ));
+            }
+        }
+
+        let mut provisioners = BTreeSet::new();
+        for stake in self.stakes() {
+            let address = bs58::encode(stake.address().to_bytes());
+            if stake.amount == 0 && stake.reward.unwrap_or_default() == 0 {
+                return Err(format!(
+                    "Empty stake for provisioner {}",
+                    address.into_string()
+                ));
+            }
+            if !provisioners.insert(stake.address().to_bytes()) {
+                return Err(format!(
+                    "Duplicate provisioner {}",
+                    address.into_string()
+                ));
+            }
+        }
+
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -158,6 +226,44 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn bundled_specs_are_valid() -> Result<(), Box<dyn Error>> {
+        let specs = [
+            include_str!("../../config/example.toml"),
+            include_str!("../../config/localnet.toml"),
+            include_str!("../../config/mainnet.toml"),
+            include_str!("../../config/singlenet.toml"),
+            include_str!("../../config/testnet.toml"),
+        ];
+        for spec in specs {
+            let snapshot: Snapshot = toml::from_str(spec)?;
+            snapshot.validate()?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn json_spec() -> Result<(), Box<dyn Error>> {
+        let toml = include_str!("../../config/example.toml");
+        let snapshot: Snapshot = toml::from_str(toml)?;
+
+        let dir = tempfile::tempdir()?;
+        let file = dir.path().join("genesis.json");
+        fs::write(&file, serde_json::to_string(&snapshot)?)?;
+        assert!(Snapshot::from_file(&file)? == snapshot);
+
+        let mut duplicated: serde_json::Value =
+            serde_json::to_value(&snapshot)?;
+        let stakes = duplicated["stake"].as_array_mut().expect("stakes");
+        let first = stakes[0].clone();
+        stakes.push(first);
+        fs::write(&file, duplicated.to_string())?;
+        let err = Snapshot::from_file(&file).expect_err("duplicate stake");
+        assert!(err.to_string().starts_with("Duplicate provisioner"));
+
+        Ok(())
+    }
+
     #[test]
     fn empty_toml() -> Result<(), Box<dyn Error>> {
         let str = toml::to_string_pretty(&Snapshot::default())?;
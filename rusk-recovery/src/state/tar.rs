@@ -6,6 +6,7 @@
 
 use std::error::Error;
 use std::fs::File;
+use std::io::BufReader;
 use std::path::Path;
 
 use flate2::{read, write, Compression};
@@ -22,6 +23,18 @@ pub fn unarchive(buffer: &[u8], output: &Path) -> Result<(), Box<dyn Error>> {
         .or_else(|_| zip::unzip(buffer, output))
 }
 
+/// Unarchive a file into a destination folder, streaming it from the disk
+pub fn unarchive_file(
+    input: &Path,
+    output: &Path,
+) -> Result<(), Box<dyn Error>> {
+    let tar = read::GzDecoder::new(BufReader::new(File::open(input)?));
+    let mut archive = Archive::new(tar);
+    archive
+        .unpack(output)
+        .or_else(|_| zip::unzip_reader(File::open(input)?, output))
+}
+
 /// Archive a folder into a destination file.
 pub fn archive(src_dir: &Path, dst_file: &Path) -> Result<(), Box<dyn Error>> {
     let tar_gz = File::create(dst_file)?;
@@ -6,14 +6,21 @@
 
 use std::error::Error;
 use std::fs::{self};
-use std::io::{Cursor, Read};
+use std::io::{Cursor, Read, Seek};
 use std::path::Path;
 
 use zip::ZipArchive;
 
 /// Unzip binaries into a destination folder
 pub fn unzip(buffer: &[u8], output: &Path) -> Result<(), Box<dyn Error>> {
-    let reader = Cursor::new(buffer);
+    unzip_reader(Cursor::new(buffer), output)
+}
+
+/// Unzip the binaries read from `reader` into a destination folder
+pub fn unzip_reader<R: Read + Seek>(
+    reader: R,
+    output: &Path,
+) -> Result<(), Box<dyn Error>> {
     let mut zip = ZipArchive::new(reader)?;
 
     for i in 0..zip.len() {
@@ -440,3 +440,16 @@ impl State {
         store.inner_mut().zeroize();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use dusk_core::abi;
+
+    use super::*;
+
+    #[test]
+    fn genesis_contract_ids() {
+        assert_eq!(TRANSFER_CONTRACT, hex::encode(abi::TRANSFER_CONTRACT));
+        assert_eq!(STAKE_CONTRACT, hex::encode(abi::STAKE_CONTRACT));
+    }
+}
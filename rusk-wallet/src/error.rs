@@ -201,6 +201,9 @@ impl From<dusk_core::Error> for Error {
             }
             Rkyv(_) => Self::Rkyv,
             MemoTooLarge(m) => Self::MemoTooLarge(m),
+            InvalidBase58(_) | InvalidChecksum | InvalidVersion(..) => {
+                Self::BadAddress
+            }
         }
     }
 }
@@ -18,6 +18,7 @@ use criterion::{
     criterion_group, criterion_main, BenchmarkGroup, BenchmarkId, Criterion,
 };
 use dusk_core::transfer::Transaction as ProtocolTransaction;
+use node::vm::{BlockContext, VMExecution};
 use node_data::bls::PublicKey;
 use node_data::ledger::Transaction;
 use rand::prelude::StdRng;
@@ -116,7 +117,14 @@ fn bench_accept(
     const BLOCK_GAS_LIMIT: u64 = 1_000_000_000_000;
     const BLOCK_HASH: [u8; 32] = [0u8; 32];
 
-    let generator = PublicKey::new(*DUSK_CONSENSUS_KEY).into_inner();
+    let ctx = BlockContext {
+        height: BLOCK_HEIGHT,
+        hash: BLOCK_HASH,
+        generator: *PublicKey::new(*DUSK_CONSENSUS_KEY).bytes(),
+        gas_limit: BLOCK_GAS_LIMIT,
+        features: rusk.active_features(BLOCK_HEIGHT),
+        economic: rusk.economic_params(BLOCK_HEIGHT),
+    };
 
     let txs = Arc::new(txs);
     let prev_root = rusk.state_root();
@@ -124,6 +132,7 @@ fn bench_accept(
     for n_txs in N_TXS {
         let rusk = rusk.clone();
         let txs = txs.clone();
+        let ctx = ctx.clone();
 
         group.bench_with_input(
             BenchmarkId::new(name, format!("{} TXs", n_txs)),
@@ -134,10 +143,7 @@ fn bench_accept(
 
                     rusk.accept_transactions(
                         prev_root,
-                        BLOCK_HEIGHT,
-                        BLOCK_GAS_LIMIT,
-                        BLOCK_HASH,
-                        generator,
+                        &ctx,
                         txs,
                         None,
                         vec![],
@@ -13,7 +13,7 @@ use std::path::PathBuf;
 use clap::builder::PossibleValuesParser;
 use clap::Parser;
 
-#[derive(Parser, Debug)]
+#[derive(Parser, Debug, Clone)]
 #[command(
     author="Dusk Network B.V. All Rights Reserved.",
     version = &rusk::VERSION_BUILD[..],
@@ -96,6 +96,17 @@ pub struct Args {
     /// Kadcast network id
     pub kadcast_network_id: Option<u8>,
 
+    #[clap(long, env = "RUSK_NETWORK", value_parser = PossibleValuesParser::new(["mainnet", "testnet", "devnet"]))]
+    /// Network to join, setting the Kadcast network id
+    ///
+    /// An explicit network id takes precedence
+    pub network: Option<String>,
+
+    #[cfg(feature = "chain")]
+    #[clap(long)]
+    /// Print the schema of the node configuration, as JSON, and exit
+    pub config_schema: bool,
+
     /// Utility commands
     #[clap(subcommand)]
     pub command: Option<command::Command>,
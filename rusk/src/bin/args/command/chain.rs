@@ -10,4 +10,18 @@ use clap::Subcommand;
 pub enum ChainCommand {
     /// Revert chain state to last final state
     Revert,
+
+    /// Re-execute a range of blocks of the local ledger and report the first
+    /// divergence from the recorded outcome.
+    ///
+    /// The state preceding the first block must be available
+    Replay {
+        /// Height of the first block to replay
+        #[clap(long)]
+        from: u64,
+
+        /// Height of the last block to replay
+        #[clap(long)]
+        to: u64,
+    },
 }
@@ -36,7 +36,8 @@ pub enum RecoveryCommand {
         #[clap(short = 'f', value_parser = BoolishValueParser::new(), long, env = "RUSK_FORCE_STATE")]
         force: bool,
 
-        /// Create a state applying the init config specified in this file.
+        /// Create a state applying the init config specified in this file,
+        /// parsed as JSON if its extension is `json` and as TOML otherwise.
         #[clap(short, long, value_parser, env = "RUSK_RECOVERY_INPUT")]
         init: Option<std::path::PathBuf>,
 
@@ -45,6 +46,36 @@ pub enum RecoveryCommand {
         #[clap(short, long, value_parser, num_args(1))]
         output: Option<std::path::PathBuf>,
     },
+
+    #[cfg(feature = "recovery-state")]
+    /// Export the VM state into a portable archive
+    ExportState {
+        /// Path of the archive to create
+        path: std::path::PathBuf,
+    },
+
+    #[cfg(feature = "recovery-state")]
+    /// Import the VM state from an archive created by `export-state`
+    ImportState {
+        /// Path of the archive to import
+        path: std::path::PathBuf,
+
+        /// Root the imported state is expected to have, hex encoded
+        #[clap(long, value_parser = parse_root)]
+        root: Option<[u8; 32]>,
+
+        /// Replaces the state in the profile path, if any.
+        #[clap(short = 'f', value_parser = BoolishValueParser::new(), long)]
+        force: bool,
+    },
+}
+
+#[cfg(feature = "recovery-state")]
+fn parse_root(root: &str) -> Result<[u8; 32], String> {
+    let bytes = hex::decode(root).map_err(|e| format!("Invalid root: {e}"))?;
+    bytes
+        .try_into()
+        .map_err(|_| "Invalid root: expected 32 bytes".to_string())
 }
 
 impl RecoveryCommand {
@@ -73,6 +104,14 @@ impl RecoveryCommand {
                 init,
                 output,
             } => crate::args::state::recovery_state(init, force, output),
+            #[cfg(feature = "recovery-state")]
+            Self::ExportState { path } => {
+                crate::args::state::export_state(path)
+            }
+            #[cfg(feature = "recovery-state")]
+            Self::ImportState { path, root, force } => {
+                crate::args::state::import_state(path, root, force)
+            }
             #[cfg(feature = "recovery-keys")]
             Self::Keys { keep, crs_url } => {
                 rusk_recovery_tools::keys::exec(keep, crs_url)
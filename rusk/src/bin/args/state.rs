@@ -8,7 +8,7 @@ use super::*;
 
 use std::{env, fs, io};
 
-use rusk_recovery_tools::state::{deploy, restore_state, tar};
+use rusk_recovery_tools::state::{self, deploy, restore_state, tar, Snapshot};
 use rusk_recovery_tools::Theme;
 use tracing::info;
 
@@ -17,12 +17,10 @@ pub fn recovery_state(
     force: bool,
     output_file: Option<PathBuf>,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let config = match &init {
-        Some(path) => fs::read_to_string(path)
-            .map_err(|_| format!("file {path:?} not found"))?,
-        None => rusk_recovery_tools::state::DEFAULT_SNAPSHOT.into(),
+    let init = match &init {
+        Some(path) => Snapshot::from_file(path)?,
+        None => toml::from_str(state::DEFAULT_SNAPSHOT)?,
     };
-    let init = toml::from_str(&config)?;
 
     let theme = Theme::default();
     info!("{} Network state", theme.action("Checking"));
@@ -92,6 +90,34 @@ pub fn recovery_state(
     Ok(())
 }
 
+pub fn export_state(output: PathBuf) -> Result<(), Box<dyn std::error::Error>> {
+    let state_dir = rusk_profile::get_rusk_state_dir()?;
+    state::export_state(&state_dir, &output)?;
+
+    info!(
+        "{} network state at {}",
+        Theme::default().success("Exported"),
+        output.display()
+    );
+    Ok(())
+}
+
+pub fn import_state(
+    input: PathBuf,
+    root: Option<[u8; 32]>,
+    force: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let state_dir = rusk_profile::get_rusk_state_dir()?;
+    state::import_state(&input, &state_dir, root, force)?;
+
+    info!(
+        "{} network state at {}",
+        Theme::default().success("Stored"),
+        state_dir.display()
+    );
+    Ok(())
+}
+
 fn clean_state() -> Result<(), io::Error> {
     let state_path = rusk_profile::get_rusk_state_dir()?;
 
@@ -4,15 +4,29 @@
 //
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
+#[cfg(feature = "chain")]
+pub mod alerts;
+#[cfg(feature = "archive")]
+pub mod archive;
 #[cfg(feature = "chain")]
 pub mod chain;
 #[cfg(feature = "chain")]
 pub mod databroker;
 #[cfg(feature = "chain")]
+pub mod disk_guard;
+#[cfg(feature = "chain")]
+pub mod dns_seed;
+#[cfg(feature = "chain")]
 pub mod kadcast;
 #[cfg(feature = "chain")]
 pub mod mempool;
 #[cfg(feature = "chain")]
+pub mod redundancy;
+#[cfg(feature = "chain")]
+pub mod rpc;
+#[cfg(feature = "chain")]
+pub mod self_check;
+#[cfg(feature = "chain")]
 pub mod telemetry;
 
 pub mod http;
@@ -22,12 +36,20 @@ use std::str::FromStr;
 
 #[cfg(feature = "chain")]
 use self::{
-    chain::ChainConfig, databroker::DataBrokerConfig, kadcast::KadcastConfig,
-    mempool::MempoolConfig, telemetry::TelemetryConfig,
+    alerts::AlertsConfig, chain::ChainConfig, databroker::DataBrokerConfig,
+    disk_guard::DiskGuardConfig, dns_seed::DnsSeedConfig,
+    kadcast::KadcastConfig, mempool::MempoolConfig,
+    redundancy::RedundancyConfig, rpc::RpcConfig, self_check::SelfCheckConfig,
+    telemetry::TelemetryConfig,
 };
 
+#[cfg(feature = "archive")]
+use self::archive::ArchiveConfig;
+
+#[cfg(feature = "chain")]
+use node::config::ConfigError;
 #[cfg(feature = "chain")]
-use rusk::node::RuskVmConfig;
+use rusk::node::{AutoCompoundConfig, RuskVmConfig};
 
 use serde::{Deserialize, Serialize};
 
@@ -37,6 +59,9 @@ use self::http::HttpConfig;
 
 #[derive(Serialize, Deserialize, Clone, Default)]
 pub(crate) struct Config {
+    /// Version of the configuration, the supported one if not set
+    version: Option<u32>,
+
     log_level: Option<String>,
     log_type: Option<String>,
     log_filter: Option<String>,
@@ -49,6 +74,10 @@ pub(crate) struct Config {
     #[serde(default = "KadcastConfig::default")]
     pub(crate) kadcast: KadcastConfig,
 
+    #[cfg(feature = "chain")]
+    #[serde(default = "RedundancyConfig::default")]
+    pub(crate) redundancy: RedundancyConfig,
+
     #[cfg(feature = "chain")]
     #[serde(default = "ChainConfig::default")]
     pub(crate) chain: ChainConfig,
@@ -67,6 +96,34 @@ pub(crate) struct Config {
     #[cfg(feature = "chain")]
     #[serde(default = "MempoolConfig::default")]
     pub(crate) mempool: MempoolConfig,
+
+    #[cfg(feature = "chain")]
+    #[serde(default = "DiskGuardConfig::default")]
+    pub(crate) disk_guard: DiskGuardConfig,
+
+    #[cfg(feature = "chain")]
+    #[serde(default = "DnsSeedConfig::default")]
+    pub(crate) dns_seed: DnsSeedConfig,
+
+    #[cfg(feature = "chain")]
+    #[serde(default = "AlertsConfig::default")]
+    pub(crate) alerts: AlertsConfig,
+
+    #[cfg(feature = "chain")]
+    #[serde(default = "RpcConfig::default")]
+    pub(crate) rpc: RpcConfig,
+
+    #[cfg(feature = "chain")]
+    #[serde(default = "SelfCheckConfig::default")]
+    pub(crate) self_check: SelfCheckConfig,
+
+    #[cfg(feature = "chain")]
+    #[serde(default = "AutoCompoundConfig::default")]
+    pub(crate) auto_compound: AutoCompoundConfig,
+
+    #[cfg(feature = "archive")]
+    #[serde(default = "ArchiveConfig::default")]
+    pub(crate) archive: ArchiveConfig,
 }
 
 /// Default log_level.
@@ -141,3 +198,23 @@ impl Config {
         self.log_filter.clone().unwrap_or_default()
     }
 }
+
+#[cfg(feature = "chain")]
+impl Config {
+    /// Validates the version of the configuration, and the configuration of
+    /// the node services
+    pub(crate) fn validate(&self) -> Result<(), Vec<ConfigError>> {
+        node::config::check_version(self.version).map_err(|e| vec![e])?;
+
+        let mempool = self.mempool.clone().into();
+        let databroker = self.databroker.clone().into();
+        node::config::validate(&self.chain.params(), &mempool, &databroker)
+    }
+
+    /// Returns the effective configuration of the node services
+    pub(crate) fn effective(&self) -> serde_json::Value {
+        let mempool = self.mempool.clone().into();
+        let databroker = self.databroker.clone().into();
+        node::config::effective(&self.chain.params(), &mempool, &databroker)
+    }
+}
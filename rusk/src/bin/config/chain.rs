@@ -9,6 +9,7 @@ use std::{
     time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
+use node::chain::conf::Params as ChainParams;
 use node::database::DatabaseOptions;
 use serde::{Deserialize, Serialize};
 
@@ -42,6 +43,9 @@ pub(crate) struct ChainConfig {
     #[serde(with = "humantime_serde")]
     #[serde(default)]
     genesis_timestamp: Option<SystemTime>,
+
+    #[serde(flatten)]
+    params: ChainParams,
 }
 
 impl ChainConfig {
@@ -113,6 +117,10 @@ impl ChainConfig {
         self.min_gas_limit
     }
 
+    pub(crate) fn params(&self) -> ChainParams {
+        self.params.clone()
+    }
+
     pub(crate) fn max_queue_size(&self) -> usize {
         self.max_queue_size.unwrap_or(10_000)
     }
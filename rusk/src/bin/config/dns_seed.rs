@@ -0,0 +1,16 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub(crate) struct DnsSeedConfig(node::dns_seed::conf::Params);
+
+impl From<DnsSeedConfig> for node::dns_seed::conf::Params {
+    fn from(conf: DnsSeedConfig) -> Self {
+        conf.0
+    }
+}
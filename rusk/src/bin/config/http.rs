@@ -5,8 +5,13 @@
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
 use std::path::PathBuf;
+use std::time::Duration;
 
 use hyper::HeaderMap;
+use rusk::http::gateway::GatewayConfig;
+#[cfg(feature = "prover")]
+use rusk::http::ProvingQueueConfig;
+use rusk::http::{DEFAULT_IDEMPOTENCY_KEYS_CAP, DEFAULT_IDEMPOTENCY_KEY_TTL};
 use serde::de::{self, Unexpected};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
@@ -31,6 +36,15 @@ pub struct HttpConfig {
     pub ws_event_channel_cap: usize,
     #[serde(with = "vec_header_map", default = "default_http_headers")]
     pub headers: HeaderMap,
+    #[serde(with = "humantime_serde", default = "default_idempotency_key_ttl")]
+    pub idempotency_key_ttl: Duration,
+    #[serde(default = "default_idempotency_keys_cap")]
+    pub idempotency_keys_cap: usize,
+    #[serde(default)]
+    pub gateway: Option<GatewayConfig>,
+    #[cfg(feature = "prover")]
+    #[serde(default)]
+    pub prover: ProvingQueueConfig,
 }
 
 // Custom deserialization function for `feeder_call_gas`.
@@ -74,6 +88,11 @@ impl Default for HttpConfig {
             listen_address: None,
             ws_sub_channel_cap: default_ws_sub_channel_cap(),
             ws_event_channel_cap: default_ws_event_channel_cap(),
+            idempotency_key_ttl: default_idempotency_key_ttl(),
+            idempotency_keys_cap: default_idempotency_keys_cap(),
+            gateway: None,
+            #[cfg(feature = "prover")]
+            prover: ProvingQueueConfig::default(),
         }
     }
 }
@@ -94,6 +113,14 @@ const fn default_ws_event_channel_cap() -> usize {
     1024
 }
 
+const fn default_idempotency_key_ttl() -> Duration {
+    DEFAULT_IDEMPOTENCY_KEY_TTL
+}
+
+const fn default_idempotency_keys_cap() -> usize {
+    DEFAULT_IDEMPOTENCY_KEYS_CAP
+}
+
 fn default_http_headers() -> HeaderMap {
     HeaderMap::new()
 }
@@ -223,6 +250,23 @@ mod tests {
             .expect("deserializing config should succeed");
     }
 
+    #[test]
+    fn deserialize_gateway_config() {
+        let config_str = r#"[gateway]
+                            admin_key = "admin"
+                            anonymous = { read_per_minute = 60 }
+                            keys = [{ key = "key", submit_per_minute = 5 }]
+                            method_limits = { "transactions/simulate" = 30 }"#;
+
+        let config: HttpConfig = toml::from_str(config_str)
+            .expect("deserializing config should succeed");
+        let gateway = config.gateway.expect("gateway to be configured");
+        assert_eq!(gateway.admin_key.as_deref(), Some("admin"));
+        assert_eq!(gateway.anonymous.unwrap().read_per_minute, 60);
+        assert_eq!(gateway.keys[0].quota.submit_per_minute, 5);
+        assert_eq!(gateway.method_limits["transactions/simulate"], 30);
+    }
+
     #[test]
     fn deserialize_invalid_feeder_call_gas() {
         let config_str = r#"feeder_call_gas = "invalid_number""#;
@@ -19,6 +19,26 @@ impl From<KadcastConfig> for Config {
 }
 
 impl KadcastConfig {
+    /// Reads the kadcast section of the config file again, overridden by the
+    /// command line arguments as at startup
+    pub(crate) fn reload(args: &Args) -> anyhow::Result<Config> {
+        #[derive(Deserialize)]
+        struct ConfigFile {
+            #[serde(default)]
+            kadcast: KadcastConfig,
+        }
+
+        let mut conf = match &args.config {
+            Some(path) => {
+                let toml = std::fs::read_to_string(path)?;
+                toml::from_str::<ConfigFile>(&toml)?.kadcast
+            }
+            None => KadcastConfig::default(),
+        };
+        conf.merge(args);
+        Ok(conf.into())
+    }
+
     pub(crate) fn merge(&mut self, arg: &Args) {
         if let Some(public_address) = &arg.kadcast_public_address {
             self.0.public_address = public_address.into();
@@ -29,8 +49,21 @@ impl KadcastConfig {
         if let Some(bootstrapping_nodes) = arg.kadcast_bootstrap.clone() {
             self.0.bootstrapping_nodes = bootstrapping_nodes
         };
+        if let Some(network_id) = arg.network.as_deref().and_then(network_id) {
+            self.0.kadcast_id = Some(network_id)
+        };
         if let Some(network_id) = arg.kadcast_network_id {
             self.0.kadcast_id = Some(network_id)
         };
     }
 }
+
+/// Returns the id of a known network
+pub(crate) fn network_id(network: &str) -> Option<u8> {
+    match network {
+        "mainnet" => Some(1),
+        "testnet" => Some(2),
+        "devnet" => Some(3),
+        _ => None,
+    }
+}
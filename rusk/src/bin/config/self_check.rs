@@ -0,0 +1,16 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub(crate) struct SelfCheckConfig(node::self_check::conf::Params);
+
+impl From<SelfCheckConfig> for node::self_check::conf::Params {
+    fn from(conf: SelfCheckConfig) -> Self {
+        conf.0
+    }
+}
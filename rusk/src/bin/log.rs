@@ -10,9 +10,12 @@ use tracing_subscriber::{
         format::{DefaultFields, Format},
         SubscriberBuilder,
     },
-    EnvFilter,
+    reload, EnvFilter,
 };
 
+/// Replaces the log filter of the registered subscriber
+pub type FilterReload = Box<dyn Fn(&str) -> anyhow::Result<()> + Send + Sync>;
+
 pub struct Log {
     level: tracing::Level,
     filter: String,
@@ -44,7 +47,7 @@ impl Log {
         )
     }
 
-    pub fn register(self) -> Result<(), SetGlobalDefaultError> {
+    pub fn register(self) -> Result<FilterReload, SetGlobalDefaultError> {
         match self.format.clone() {
             Some(format) => self.register_format(&format),
             None => self.register_simple(),
@@ -52,20 +55,22 @@ impl Log {
     }
 
     #[allow(dead_code)]
-    fn register_simple(self) -> Result<(), SetGlobalDefaultError> {
+    fn register_simple(self) -> Result<FilterReload, SetGlobalDefaultError> {
         let subscriber = self
             .subscriber()
             .with_level(false)
             .without_time()
             .with_target(false)
-            .finish();
-        tracing::subscriber::set_global_default(subscriber)
+            .with_filter_reloading();
+        let handle = subscriber.reload_handle();
+        tracing::subscriber::set_global_default(subscriber.finish())?;
+        Ok(reloader(handle))
     }
 
     fn register_format(
         self,
         log_format: &str,
-    ) -> Result<(), SetGlobalDefaultError> {
+    ) -> Result<FilterReload, SetGlobalDefaultError> {
         let subscriber = self.subscriber();
         // Set the subscriber as global.
         // so this subscriber will be used as the default in all threads for the
@@ -77,19 +82,34 @@ impl Log {
                     .json()
                     .with_current_span(false)
                     .flatten_event(true)
-                    .finish();
-
-                tracing::subscriber::set_global_default(subscriber)
+                    .with_filter_reloading();
+                let handle = subscriber.reload_handle();
+                tracing::subscriber::set_global_default(subscriber.finish())?;
+                Ok(reloader(handle))
             }
             "plain" => {
-                let subscriber = subscriber.with_ansi(false).finish();
-                tracing::subscriber::set_global_default(subscriber)
+                let subscriber =
+                    subscriber.with_ansi(false).with_filter_reloading();
+                let handle = subscriber.reload_handle();
+                tracing::subscriber::set_global_default(subscriber.finish())?;
+                Ok(reloader(handle))
             }
             "coloured" => {
-                let subscriber = subscriber.finish();
-                tracing::subscriber::set_global_default(subscriber)
+                let subscriber = subscriber.with_filter_reloading();
+                let handle = subscriber.reload_handle();
+                tracing::subscriber::set_global_default(subscriber.finish())?;
+                Ok(reloader(handle))
             }
             _ => unreachable!(),
         }
     }
 }
+
+/// Wraps the reload `handle` of a subscriber filter, parsing the new filter
+/// directives
+fn reloader<S: 'static>(handle: reload::Handle<EnvFilter, S>) -> FilterReload {
+    Box::new(move |filter| {
+        handle.reload(EnvFilter::try_new(filter)?)?;
+        Ok(())
+    })
+}
@@ -30,6 +30,12 @@ use crate::config::Config;
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let args = args::Args::parse();
 
+    #[cfg(feature = "chain")]
+    if args.config_schema {
+        println!("{:#}", node::config::schema());
+        return Ok(());
+    }
+
     let config = Config::from(&args);
 
     let log = Log::new(config.log_level(), config.log_filter());
@@ -39,12 +45,24 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         args.command.clone()
     {
         // Set custom tracing format if subcommand is specified
-        log.register()?;
+        let _ = log.register()?;
         recovery.run()?;
         return Ok(());
     }
 
-    log.with_format(config.log_type()).register()?;
+    #[cfg_attr(not(feature = "chain"), allow(unused_variables))]
+    let log_reload = log.with_format(config.log_type()).register()?;
+
+    #[cfg(feature = "chain")]
+    {
+        if let Err(errors) = config.validate() {
+            for err in &errors {
+                tracing::error!(event = "invalid configuration", %err);
+            }
+            return Err("invalid configuration".into());
+        }
+        info!(event = "effective configuration", config = %config.effective());
+    }
 
     #[cfg(feature = "ephemeral")]
     let tempdir = match args.state_path {
@@ -74,15 +92,33 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             .with_db_path(db_path)
             .with_db_options(config.chain.db_options())
             .with_kadcast(config.kadcast)
+            .with_kadcast_reload({
+                let args = args.clone();
+                move || config::kadcast::KadcastConfig::reload(&args)
+            })
+            .with_log_reload(log_reload)
+            .with_redundancy(config.redundancy.into())
             .with_consensus_keys(config.chain.consensus_keys_path())
             .with_databroker(config.databroker)
             .with_telemetry(config.telemetry.listen_addr())
             .with_chain_queue_size(config.chain.max_queue_size())
+            .with_chain_params(config.chain.params())
             .with_genesis_timestamp(config.chain.genesis_timestamp())
             .with_mempool(config.mempool.into())
+            .with_disk_guard(config.disk_guard.into())
+            .with_self_check(config.self_check.into())
+            .with_dns_seed(config.dns_seed.into())
+            .with_alerts(config.alerts.into())
+            .with_rpc(config.rpc.into())
+            .with_auto_compound(config.auto_compound)
             .with_state_dir(state_dir)
             .with_min_gas_limit(config.chain.min_gas_limit());
 
+        #[cfg(feature = "archive")]
+        {
+            node_builder = node_builder.with_archive(config.archive.into());
+        }
+
         #[allow(deprecated)]
         {
             if let Some(gas_byte) = config.chain.gas_per_deploy_byte() {
@@ -116,16 +152,25 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             key: config.http.key,
             headers: config.http.headers,
             ws_event_channel_cap: config.http.ws_event_channel_cap,
+            idempotency_key_ttl: config.http.idempotency_key_ttl,
+            idempotency_keys_cap: config.http.idempotency_keys_cap,
+            gateway: config.http.gateway,
+            #[cfg(feature = "prover")]
+            prover: config.http.prover,
         };
         node_builder = node_builder.with_http(http_builder)
     }
 
     #[cfg(feature = "chain")]
-    if let Some(args::command::Command::Chain(
-        args::command::chain::ChainCommand::Revert,
-    )) = args.command.as_ref()
+    if let Some(args::command::Command::Chain(command)) = args.command.as_ref()
     {
-        node_builder = node_builder.with_revert();
+        use args::command::chain::ChainCommand;
+        node_builder = match *command {
+            ChainCommand::Revert => node_builder.with_revert(),
+            ChainCommand::Replay { from, to } => {
+                node_builder.with_replay(from, to)
+            }
+        };
     }
 
     if let Err(e) = node_builder.build_and_run().await {
@@ -4,10 +4,15 @@
 //
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
+#[cfg(feature = "prover")]
+use std::sync::Arc;
+
 use tokio::sync::broadcast;
 use tracing::info;
 
 use crate::http::{DataSources, HttpServer, HttpServerConfig};
+#[cfg(feature = "prover")]
+use crate::http::{ProverService, ProvingQueue};
 
 #[derive(Default)]
 pub struct RuskHttpBuilder {
@@ -31,7 +36,11 @@ impl RuskHttpBuilder {
             let mut handler = DataSources::default();
 
             #[cfg(feature = "prover")]
-            handler.sources.push(Box::new(rusk_prover::LocalProver));
+            {
+                let queue = ProvingQueue::local(http.prover)?;
+                let prover = ProverService::new(Arc::new(queue));
+                handler.sources.push(Box::new(prover));
+            }
 
             let cert_and_key = match (http.cert, http.key) {
                 (Some(cert), Some(key)) => Some((cert, key)),
@@ -45,6 +54,7 @@ impl RuskHttpBuilder {
                 http.address,
                 http.headers,
                 cert_and_key,
+                http.gateway,
             )
             .await?;
 
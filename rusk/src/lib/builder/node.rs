@@ -5,27 +5,54 @@
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
 use std::path::PathBuf;
+#[cfg(feature = "prover")]
+use std::sync::Arc;
 use std::time::Duration;
 
 use kadcast::config::Config as KadcastConfig;
-use node::chain::ChainSrv;
+use node::alerts::conf::Params as AlertsParam;
+use node::alerts::AlertsSrv;
+use node::chain::conf::Params as ChainParam;
+use node::chain::consensus_events::ConsensusEvents;
+#[cfg(feature = "recovery-state")]
+use node::chain::fast_sync::FastSync;
+use node::chain::{ChainSrv, LogReload};
 use node::database::rocksdb;
 use node::database::{DatabaseOptions, DB};
 use node::databroker::conf::Params as BrokerParam;
 use node::databroker::DataBrokerSrv;
+use node::disk_guard::conf::Params as DiskGuardParam;
+use node::disk_guard::DiskGuardSrv;
+use node::dns_seed::conf::Params as DnsSeedParam;
+use node::dns_seed::DnsSeedSrv;
 use node::mempool::conf::Params as MempoolParam;
+use node::mempool::policy::TxPolicy;
 use node::mempool::MempoolSrv;
+use node::network::redundancy::Params as RedundancyParam;
 use node::network::Kadcast;
+use node::rpc::conf::Params as RpcParam;
+use node::rpc::RpcSrv;
+use node::self_check::conf::Params as SelfCheckParam;
+use node::self_check::{SelfCheck, Transport};
 use node::telemetry::TelemetrySrv;
 use node::{LongLivedService, Node};
 
+#[cfg(feature = "archive")]
+use node::archive::conf::Params as ArchiveParam;
 #[cfg(feature = "archive")]
 use node::archive::Archive;
 use tokio::sync::{broadcast, mpsc};
 use tracing::info;
 
-use crate::http::{DataSources, HttpServer, HttpServerConfig};
-use crate::node::{ChainEventStreamer, RuskNode, RuskVmConfig, Services};
+use crate::http::{DataSources, HttpServer, HttpServerConfig, IdempotencyKeys};
+#[cfg(feature = "prover")]
+use crate::http::{ProverService, ProvingQueue};
+use crate::node::{
+    node_migrations, AutoCompoundConfig, AutoCompoundSrv, ChainEventStreamer,
+    KadcastReload, NetworkReloadSrv, RuskNode, RuskVmConfig, Services,
+};
+#[cfg(feature = "recovery-state")]
+use crate::node::{stage_state_snapshot, StateExport};
 use crate::{Rusk, VERSION};
 
 #[derive(Default)]
@@ -33,11 +60,23 @@ pub struct RuskNodeBuilder {
     consensus_keys_path: String,
     databroker: BrokerParam,
     kadcast: KadcastConfig,
+    kadcast_reload: Option<KadcastReload>,
+    log_reload: Option<LogReload>,
+    redundancy: RedundancyParam,
     mempool: MempoolParam,
+    disk_guard: DiskGuardParam,
+    self_check: SelfCheckParam,
+    dns_seed: DnsSeedParam,
+    alerts: AlertsParam,
+    rpc: RpcParam,
+    #[cfg(feature = "archive")]
+    archive: ArchiveParam,
+    auto_compound: AutoCompoundConfig,
     telemetry_address: Option<String>,
     db_path: PathBuf,
     db_options: DatabaseOptions,
     max_chain_queue_size: usize,
+    chain: ChainParam,
     genesis_timestamp: u64,
     vm_config: RuskVmConfig,
     min_gas_limit: Option<u64>,
@@ -47,6 +86,7 @@ pub struct RuskNodeBuilder {
     http: Option<HttpServerConfig>,
 
     command_revert: bool,
+    command_replay: Option<(u64, u64)>,
 }
 
 const DEFAULT_MIN_GAS_LIMIT: u64 = 75000;
@@ -73,6 +113,30 @@ impl RuskNodeBuilder {
         self
     }
 
+    /// Restarts the network on SIGHUP, with the Kadcast configuration
+    /// returned by `reload`
+    pub fn with_kadcast_reload<F>(mut self, reload: F) -> Self
+    where
+        F: Fn() -> anyhow::Result<KadcastConfig> + Send + Sync + 'static,
+    {
+        self.kadcast_reload = Some(Box::new(reload));
+        self
+    }
+
+    /// Replaces the log filter with `reload` on the admin commands asking so
+    pub fn with_log_reload<F>(mut self, reload: F) -> Self
+    where
+        F: Fn(&str) -> anyhow::Result<()> + Send + Sync + 'static,
+    {
+        self.log_reload = Some(Box::new(reload));
+        self
+    }
+
+    pub fn with_redundancy(mut self, conf: RedundancyParam) -> Self {
+        self.redundancy = conf;
+        self
+    }
+
     pub fn with_db_path(mut self, db_path: PathBuf) -> Self {
         self.db_path = db_path;
         self
@@ -96,11 +160,52 @@ impl RuskNodeBuilder {
         self
     }
 
+    pub fn with_disk_guard(mut self, conf: DiskGuardParam) -> Self {
+        self.disk_guard = conf;
+        self
+    }
+
+    pub fn with_self_check(mut self, conf: SelfCheckParam) -> Self {
+        self.self_check = conf;
+        self
+    }
+
+    pub fn with_dns_seed(mut self, conf: DnsSeedParam) -> Self {
+        self.dns_seed = conf;
+        self
+    }
+
+    pub fn with_alerts(mut self, conf: AlertsParam) -> Self {
+        self.alerts = conf;
+        self
+    }
+
+    pub fn with_rpc(mut self, conf: RpcParam) -> Self {
+        self.rpc = conf;
+        self
+    }
+
+    #[cfg(feature = "archive")]
+    pub fn with_archive(mut self, conf: ArchiveParam) -> Self {
+        self.archive = conf;
+        self
+    }
+
+    pub fn with_auto_compound(mut self, conf: AutoCompoundConfig) -> Self {
+        self.auto_compound = conf;
+        self
+    }
+
     pub fn with_chain_queue_size(mut self, max_queue_size: usize) -> Self {
         self.max_chain_queue_size = max_queue_size;
         self
     }
 
+    pub fn with_chain_params(mut self, conf: ChainParam) -> Self {
+        self.chain = conf;
+        self
+    }
+
     pub fn with_genesis_timestamp(mut self, genesis_timestamp: u64) -> Self {
         self.genesis_timestamp = genesis_timestamp;
         self
@@ -184,13 +289,47 @@ impl RuskNodeBuilder {
         self
     }
 
+    /// Replays the blocks from height `from` to `to` instead of running the
+    /// node
+    pub fn with_replay(mut self, from: u64, to: u64) -> Self {
+        self.command_replay = Some((from, to));
+        self
+    }
+
     pub fn with_vm_config(mut self, vm_config: RuskVmConfig) -> Self {
         self.vm_config = vm_config;
         self
     }
 
+    /// Syncs the state from the fast sync peers, if the node is far enough
+    /// behind them
+    async fn fast_sync(&self, db: &rocksdb::Backend) -> anyhow::Result<()> {
+        let conf = self.chain.fast_sync.clone();
+
+        #[cfg(feature = "recovery-state")]
+        FastSync::new(conf)
+            .run(db, |archive, state_root| {
+                stage_state_snapshot(
+                    &self.state_dir,
+                    archive,
+                    state_root,
+                    self.kadcast.kadcast_id.unwrap_or_default(),
+                    self.feeder_call_gas,
+                )
+            })
+            .await?;
+
+        #[cfg(not(feature = "recovery-state"))]
+        if !conf.peers.is_empty() {
+            let _ = db;
+            tracing::warn!("Fast sync requires the recovery-state feature");
+        }
+
+        Ok(())
+    }
+
     /// Build the RuskNode and corresponding services
-    pub async fn build_and_run(self) -> anyhow::Result<()> {
+    pub async fn build_and_run(mut self) -> anyhow::Result<()> {
         let channel_cap = self
             .http
             .as_ref()
@@ -200,7 +339,17 @@ impl RuskNodeBuilder {
         let (node_sender, node_receiver) = mpsc::channel(1000);
 
         #[cfg(feature = "archive")]
-        let archive = Archive::create_or_open(self.db_path.clone()).await;
+        let archive = Archive::create_or_open(self.db_path.clone())
+            .await
+            .with_conf(self.archive.clone());
+
+        // The self-check is skipped by the commands not running the node
+        let run_node = !self.command_revert && self.command_replay.is_none();
+        let mut self_check = (run_node && self.self_check.enabled)
+            .then(|| SelfCheck::new(self.self_check.clone()));
+        if let Some(self_check) = &mut self_check {
+            self_check.check_database(&self.db_path);
+        }
 
         let min_gas_limit = self.min_gas_limit.unwrap_or(DEFAULT_MIN_GAS_LIMIT);
         let finality_activation = self
@@ -208,6 +357,18 @@ impl RuskNodeBuilder {
             .feature(crate::node::FEATURE_ABI_PUBLIC_SENDER)
             .unwrap_or(u64::MAX);
 
+        let db = rocksdb::Backend::create_or_open(
+            self.db_path.clone(),
+            self.db_options.clone(),
+        );
+
+        // Syncing the state would leave nothing to revert or replay
+        if run_node {
+            self.fast_sync(&db).await?;
+        }
+
+        let migrations = node_migrations(&self.vm_config)
+            .map_err(|e| anyhow::anyhow!("Cannot load the migrations {e}"))?;
         let rusk = Rusk::new(
             self.state_dir,
             self.kadcast.kadcast_id.unwrap_or_default(),
@@ -218,22 +379,95 @@ impl RuskNodeBuilder {
             #[cfg(feature = "archive")]
             archive.clone(),
         )
-        .map_err(|e| anyhow::anyhow!("Cannot instantiate VM {e}"))?;
+        .map_err(|e| anyhow::anyhow!("Cannot instantiate VM {e}"))?
+        .with_migrations(migrations);
         info!("Rusk VM loaded");
 
-        let node = {
-            let db = rocksdb::Backend::create_or_open(
-                self.db_path.clone(),
-                self.db_options.clone(),
+        if let Some(mut self_check) = self_check {
+            let kadcast_address = self
+                .kadcast
+                .listen_address
+                .clone()
+                .unwrap_or_else(|| self.kadcast.public_address.clone());
+
+            self_check.check_chain(&db, &rusk);
+            self_check.check_consensus_keys(&self.consensus_keys_path);
+            self_check.check_clock().await;
+            self_check.check_listener(
+                "kadcast_listener",
+                &kadcast_address,
+                Transport::Udp,
             );
-            let net = Kadcast::new(self.kadcast)?;
-            RuskNode::new(
+            if let Some(http) = &self.http {
+                self_check.check_listener(
+                    "http_listener",
+                    &http.address,
+                    Transport::Tcp,
+                );
+            }
+            self_check.check_disk(&self.db_path, &self.disk_guard);
+            self_check.finish()?;
+        }
+
+        let tx_policy = match &self.mempool.tx_policy {
+            Some(path) => TxPolicy::load(path)?,
+            None => TxPolicy::default(),
+        };
+
+        let auto_compound = AutoCompoundSrv::new(
+            self.auto_compound,
+            self.consensus_keys_path.clone(),
+        );
+
+        let mut dns_seed = DnsSeedSrv::new(self.dns_seed);
+        for node in dns_seed.bootstrap().await {
+            let bootstrapping_nodes = &mut self.kadcast.bootstrapping_nodes;
+            if node != self.kadcast.public_address
+                && !bootstrapping_nodes.contains(&node)
+            {
+                bootstrapping_nodes.push(node);
+            }
+        }
+
+        let node = {
+            let net =
+                Kadcast::new(self.kadcast)?.with_redundancy(self.redundancy);
+            let node = RuskNode::new(
                 Node::new(net, db, rusk.clone()),
                 #[cfg(feature = "archive")]
                 archive.clone(),
             )
+            .with_auto_compound_status(auto_compound.status())
+            .with_min_replacement_bump(self.mempool.min_replacement_bump);
+            #[cfg(feature = "recovery-state")]
+            let node =
+                node.with_state_export(StateExport::new(&self.chain.fast_sync));
+            match &self.http {
+                Some(http) => node.with_idempotency_keys(IdempotencyKeys::new(
+                    http.idempotency_key_ttl,
+                    http.idempotency_keys_cap,
+                )),
+                None => node,
+            }
         };
 
+        if let Some((from, to)) = self.command_replay {
+            let divergence = node::chain::replay::replay_blocks(
+                node.inner().database(),
+                node.inner().vm_handler(),
+                from,
+                to,
+            )
+            .await?;
+            return match divergence {
+                Some(divergence) => {
+                    Err(anyhow::anyhow!("Replay diverged at {divergence}"))
+                }
+                None => Ok(()),
+            };
+        }
+
+        let consensus_events = ConsensusEvents::default();
         let mut chain_srv = ChainSrv::new(
             self.consensus_keys_path,
             self.max_chain_queue_size,
@@ -241,9 +475,15 @@ impl RuskNodeBuilder {
             self.genesis_timestamp,
             *crate::DUSK_CONSENSUS_KEY,
             finality_activation,
+            self.chain,
             #[cfg(feature = "archive")]
             archive.clone(),
-        );
+        )
+        .with_tx_policy(tx_policy.clone())
+        .with_consensus_events(consensus_events.clone());
+        if let Some(log_reload) = self.log_reload {
+            chain_srv = chain_srv.with_log_reload(log_reload);
+        }
         if self.command_revert {
             chain_srv
                 .initialize(
@@ -256,10 +496,19 @@ impl RuskNodeBuilder {
         }
 
         let mut service_list: Vec<Box<Services>> = vec![
-            Box::new(MempoolSrv::new(self.mempool, node_sender.clone())),
+            Box::new(
+                MempoolSrv::new(self.mempool, node_sender.clone())
+                    .with_tx_policy(tx_policy),
+            ),
             Box::new(chain_srv),
             Box::new(DataBrokerSrv::new(self.databroker)),
             Box::new(TelemetrySrv::new(self.telemetry_address)),
+            Box::new(DiskGuardSrv::new(self.db_path.clone(), self.disk_guard)),
+            Box::new(dns_seed),
+            Box::new(AlertsSrv::new(self.alerts)),
+            Box::new(RpcSrv::new(self.rpc, consensus_events)),
+            Box::new(NetworkReloadSrv::new(self.kadcast_reload)),
+            Box::new(auto_compound),
         ];
 
         let mut _ws_server = None;
@@ -276,7 +525,11 @@ impl RuskNodeBuilder {
             handler.sources.push(Box::new(node.clone()));
 
             #[cfg(feature = "prover")]
-            handler.sources.push(Box::new(rusk_prover::LocalProver));
+            {
+                let queue = ProvingQueue::local(http.prover)?;
+                let prover = ProverService::new(Arc::new(queue));
+                handler.sources.push(Box::new(prover));
+            }
 
             let cert_and_key = match (http.cert, http.key) {
                 (Some(cert), Some(key)) => Some((cert, key)),
@@ -291,6 +544,7 @@ impl RuskNodeBuilder {
                     http.address,
                     http.headers,
                     cert_and_key,
+                    http.gateway,
                 )
                 .await?,
             );
@@ -21,6 +21,8 @@ pub enum Error {
     RestoreFailed,
     /// Proof verification failure
     ProofVerification,
+    /// Signature verification failure
+    SignatureVerification,
     /// Out of gas in block execution
     OutOfGas,
     /// Repeated nullifier in transaction verification
@@ -49,7 +51,7 @@ pub enum Error {
     Io(io::Error),
     /// Failed to produce proper state
     #[cfg(feature = "chain")]
-    InconsistentState(Box<dusk_consensus::operations::VerificationOutput>),
+    InconsistentState(Box<node::chain::StateDivergence>),
     /// Other
     Other(Box<dyn std::error::Error>),
     /// Commit not found amongst existing commits
@@ -60,6 +62,13 @@ pub enum Error {
     MemoTooLarge(usize),
     /// Chain tip different from the expected one
     TipChanged,
+    /// Stake call breaking the stake rules
+    StakeRule(Box<BlsPublicKey>, String),
+    /// Migration not registered
+    UnknownMigration(String),
+    /// Sponsored transaction before the sponsorship activation, or the
+    /// deployment of a transfer contract charging the sponsors
+    InactiveSponsorship,
 }
 
 impl std::error::Error for Error {}
@@ -109,6 +118,15 @@ impl From<dusk_core::Error> for Error {
             }
             ExecErr::Rkyv(e) => Self::Transaction(ExecErr::Rkyv(e)),
             ExecErr::MemoTooLarge(size) => Self::MemoTooLarge(size),
+            ExecErr::InvalidBase58(e) => {
+                Self::Transaction(ExecErr::InvalidBase58(e))
+            }
+            ExecErr::InvalidChecksum => {
+                Self::Transaction(ExecErr::InvalidChecksum)
+            }
+            ExecErr::InvalidVersion(found, expected) => {
+                Self::Transaction(ExecErr::InvalidVersion(found, expected))
+            }
         }
     }
 }
@@ -158,6 +176,9 @@ impl fmt::Display for Error {
             Error::Phoenix(err) => write!(f, "Phoenix error: {err}"),
             Error::Other(err) => write!(f, "Other error: {err}"),
             Error::ProofVerification => write!(f, "Proof verification failure"),
+            Error::SignatureVerification => {
+                write!(f, "Signature verification failure")
+            }
             Error::OutOfGas => write!(f, "Out of gas"),
             Error::RepeatingNullifiers(n) => {
                 write!(f, "Nullifiers already spent: {n:?}")
@@ -172,8 +193,8 @@ impl fmt::Display for Error {
                 write!(f,"Expected: 0 < (inputs: {inputs_len}) < 5, 0 ≤ (outputs: {outputs_len}) < 3")
             }
             #[cfg(feature = "chain")]
-            Error::InconsistentState(vo) => {
-                write!(f, "Inconsistent state verification data {vo}",)
+            Error::InconsistentState(divergence) => {
+                write!(f, "Inconsistent state: {divergence}")
             }
             Error::CommitNotFound(commit_id) => {
                 write!(f, "Commit not found, id = {}", hex::encode(commit_id),)
@@ -181,12 +202,23 @@ impl fmt::Display for Error {
             Error::InvalidCreditsCount(height, credits) => {
                 write!(f, "Invalid credits: H= {height}, credits= {credits}",)
             }
+            Error::StakeRule(account, reason) => {
+                let encoded_account =
+                    bs58::encode(&account.to_bytes()).into_string();
+                write!(f, "Stake rule violation: {encoded_account} {reason}")
+            }
             Error::MemoTooLarge(size) => {
                 write!(f, "The memo size {size} is too large")
             }
             Error::TipChanged => {
                 write!(f, "Chain tip different from the expected one")
             }
+            Error::UnknownMigration(name) => {
+                write!(f, "Unknown migration: {name}")
+            }
+            Error::InactiveSponsorship => {
+                write!(f, "Sponsored transactions are not active yet")
+            }
         }
     }
 }
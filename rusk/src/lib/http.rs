@@ -7,9 +7,12 @@
 #[cfg(feature = "chain")]
 mod chain;
 mod event;
+pub mod gateway;
 #[cfg(feature = "prover")]
 mod prover;
 #[cfg(feature = "chain")]
+mod query;
+#[cfg(feature = "chain")]
 mod rusk;
 mod stream;
 
@@ -29,6 +32,7 @@ use std::path::{Path, PathBuf};
 use std::pin::Pin;
 use std::str::FromStr;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use async_trait::async_trait;
 
@@ -61,14 +65,28 @@ use node_data::events::contract::ContractEvent;
 use crate::http::event::FullOrStreamBody;
 use crate::VERSION;
 
-pub use self::event::{RuesDispatchEvent, RuesEvent, RUES_LOCATION_PREFIX};
+#[cfg(feature = "chain")]
+pub use self::chain::IdempotencyKeys;
+pub use self::event::{
+    RequestClient, RuesDispatchEvent, RuesEvent, RUES_LOCATION_PREFIX,
+};
+#[cfg(feature = "prover")]
+pub use self::prover::{
+    JobInfo, JobStatus, Priority, ProverService, ProvingQueue,
+    ProvingQueueConfig,
+};
 
 use self::event::{ResponseData, RuesEventUri, SessionId};
+use self::gateway::{Gateway, GatewayConfig, Rejection};
 use self::stream::Listener;
 
 const RUSK_VERSION_HEADER: &str = "Rusk-Version";
 const RUSK_VERSION_STRICT_HEADER: &str = "Rusk-Version-Strict";
 
+pub const DEFAULT_IDEMPOTENCY_KEY_TTL: Duration =
+    Duration::from_secs(24 * 60 * 60);
+pub const DEFAULT_IDEMPOTENCY_KEYS_CAP: usize = 100_000;
+
 pub struct HttpServer {
     handle: task::JoinHandle<()>,
     _shutdown: broadcast::Sender<Infallible>,
@@ -80,6 +98,15 @@ pub struct HttpServerConfig {
     pub key: Option<PathBuf>,
     pub headers: HeaderMap,
     pub ws_event_channel_cap: usize,
+    /// Time the idempotency keys of the transaction submissions are kept for
+    pub idempotency_key_ttl: Duration,
+    /// Maximum number of idempotency keys kept
+    pub idempotency_keys_cap: usize,
+    /// Rate-limited public RPC gateway, disabled if `None`
+    pub gateway: Option<GatewayConfig>,
+    /// Queue of the delegated proving jobs
+    #[cfg(feature = "prover")]
+    pub prover: ProvingQueueConfig,
 }
 
 impl HttpServer {
@@ -94,6 +121,7 @@ impl HttpServer {
         addr: A,
         headers: HeaderMap,
         cert_and_key: Option<(P1, P2)>,
+        gateway: Option<GatewayConfig>,
     ) -> io::Result<(Self, SocketAddr)>
     where
         A: ToSocketAddrs,
@@ -111,6 +139,9 @@ impl HttpServer {
         let local_addr = listener.local_addr()?;
 
         info!("Starting HTTP Listener to {local_addr}");
+        if gateway.is_some() {
+            info!("HTTP gateway enabled");
+        }
 
         let handle = task::spawn(listening_loop(
             handler,
@@ -119,6 +150,7 @@ impl HttpServer {
             shutdown_receiver,
             headers,
             ws_event_channel_cap,
+            gateway.map(|config| Arc::new(Gateway::new(config))),
         ));
 
         let server = Self {
@@ -175,6 +207,7 @@ async fn listening_loop<H>(
     mut shutdown: broadcast::Receiver<Infallible>,
     headers: HeaderMap,
     ws_event_channel_cap: usize,
+    gateway: Option<Arc<Gateway>>,
 ) where
     H: HandleRequest,
 {
@@ -188,6 +221,8 @@ async fn listening_loop<H>(
         shutdown: shutdown.resubscribe(),
         headers: Arc::new(headers),
         ws_event_channel_cap,
+        gateway,
+        remote: None,
     };
 
     let runtime = tokio::runtime::Builder::new_multi_thread()
@@ -203,15 +238,16 @@ async fn listening_loop<H>(
                 break;
             }
             r = listener.accept() => {
-                let stream = match r {
-                    Ok(stream) => stream,
+                let (stream, remote) = match r {
+                    Ok(accepted) => accepted,
                     Err(_) => break,
                 };
 
                 let http = HttpBuilder::new(TokioExecutor);
 
                 let stream = TokioIo::new(stream);
-                let service = service.clone();
+                let mut service = service.clone();
+                service.remote = Some(remote);
 
                 runtime.spawn(async move {
                     let conn = http.serve_connection_with_upgrades(stream, service);
@@ -230,6 +266,9 @@ struct ExecutionService<H> {
     shutdown: broadcast::Receiver<Infallible>,
     headers: Arc<HeaderMap>,
     ws_event_channel_cap: usize,
+    gateway: Option<Arc<Gateway>>,
+    /// Address of the connection's peer
+    remote: Option<SocketAddr>,
 }
 
 impl<H> Clone for ExecutionService<H> {
@@ -241,6 +280,8 @@ impl<H> Clone for ExecutionService<H> {
             shutdown: self.shutdown.resubscribe(),
             headers: self.headers.clone(),
             ws_event_channel_cap: self.ws_event_channel_cap,
+            gateway: self.gateway.clone(),
+            remote: self.remote,
         }
     }
 }
@@ -271,6 +312,8 @@ where
         let shutdown = self.shutdown.resubscribe();
         let ws_event_channel_cap = self.ws_event_channel_cap;
         let headers = self.headers.clone();
+        let gateway = self.gateway.clone();
+        let remote = self.remote;
 
         Box::pin(async move {
             let rsp = handle_request(
@@ -280,6 +323,8 @@ where
                 events,
                 shutdown,
                 ws_event_channel_cap,
+                gateway,
+                remote,
             )
             .await;
 
@@ -453,6 +498,70 @@ fn response(
         .expect("Failed to build response"))
 }
 
+/// Checks a RUES request against the gateway, returning the response to reply
+/// with if it's rejected.
+///
+/// Key management requests are only checked against the admin key, while any
+/// other request is charged to the quota of its client.
+fn gateway_check(
+    gateway: &Gateway,
+    req: &Request<Incoming>,
+    remote: Option<SocketAddr>,
+) -> Result<Option<Response<FullOrStreamBody>>, ExecutionError> {
+    let key = req
+        .headers()
+        .get(gateway::API_KEY_HEADER)
+        .and_then(|v| v.to_str().ok());
+    let (target, topic) = RuesEventUri::parse_from_path(req.uri().path())
+        .map(|uri| (uri.component, uri.topic))
+        .unwrap_or_default();
+
+    let rejection = if target == gateway::GATEWAY_TARGET {
+        if gateway.is_admin(key) {
+            return Ok(None);
+        }
+        Rejection::InvalidKey
+    } else {
+        let remote = remote.map(|addr| addr.ip());
+        match gateway.check(key, remote, &target, &topic, Instant::now()) {
+            Ok(()) => return Ok(None),
+            Err(rejection) => rejection,
+        }
+    };
+
+    let body = rejection.body().to_string();
+    let rsp = match &rejection {
+        Rejection::InvalidKey => response(StatusCode::UNAUTHORIZED, body)?,
+        Rejection::RateLimited { retry_after, .. } => {
+            let mut rsp = response(StatusCode::TOO_MANY_REQUESTS, body)?;
+            let retry_after = gateway::retry_after_secs(retry_after);
+            rsp.headers_mut()
+                .insert(hyper::header::RETRY_AFTER, retry_after.into());
+            rsp
+        }
+    };
+    Ok(Some(rsp))
+}
+
+/// Returns the client a request is attributed to.
+///
+/// API keys are only trusted once checked by the gateway, any other request
+/// being attributed to its remote address.
+fn request_client(
+    gateway: Option<&Gateway>,
+    req: &Request<Incoming>,
+    remote: Option<SocketAddr>,
+) -> Option<RequestClient> {
+    let key = gateway
+        .and_then(|_| req.headers().get(gateway::API_KEY_HEADER))
+        .and_then(|key| key.to_str().ok());
+    match key {
+        Some(key) => Some(RequestClient::Key(key.to_string())),
+        None => remote.map(|addr| RequestClient::Address(addr.ip())),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 async fn handle_request_rues<H: HandleRequest>(
     mut req: Request<Incoming>,
     handler: Arc<H>,
@@ -462,7 +571,15 @@ async fn handle_request_rues<H: HandleRequest>(
     events: broadcast::Receiver<RuesEvent>,
     shutdown: broadcast::Receiver<Infallible>,
     ws_event_channel_cap: usize,
+    gateway: Option<Arc<Gateway>>,
+    remote: Option<SocketAddr>,
 ) -> Result<Response<FullOrStreamBody>, ExecutionError> {
+    if let Some(gateway) = &gateway {
+        if let Some(rsp) = gateway_check(gateway, &req, remote)? {
+            return Ok(rsp);
+        }
+    }
+
     if hyper_tungstenite::is_upgrade_request(&req) {
         let (subscription_sender, subscriptions) =
             mpsc::channel(ws_event_channel_cap);
@@ -490,7 +607,26 @@ async fn handle_request_rues<H: HandleRequest>(
 
         Ok(response.map(Into::into))
     } else if req.method() == Method::POST {
-        let (event, binary_resp) = RuesDispatchEvent::from_request(req).await?;
+        let client = request_client(gateway.as_deref(), &req, remote);
+        let (mut event, binary_resp) =
+            RuesDispatchEvent::from_request(req).await?;
+        event.client = client;
+
+        if let Some(gateway) = gateway
+            .as_ref()
+            .filter(|_| event.uri.component == gateway::GATEWAY_TARGET)
+        {
+            return match gateway
+                .handle_admin(&event.uri.topic, event.data.as_bytes())
+            {
+                Ok(data) => response(StatusCode::OK, data.to_string()),
+                Err(e) => response(
+                    StatusCode::BAD_REQUEST,
+                    serde_json::json!({ "error": e.to_string() }).to_string(),
+                ),
+            };
+        }
+
         let _is_binary = event.is_binary();
         let mut resp_headers = event.x_headers();
         let (responder, mut receiver) = mpsc::unbounded_channel();
@@ -567,6 +703,7 @@ async fn handle_request_rues<H: HandleRequest>(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn handle_request<H>(
     req: Request<Incoming>,
     sources: Arc<H>,
@@ -576,6 +713,8 @@ async fn handle_request<H>(
     events: broadcast::Receiver<RuesEvent>,
     shutdown: broadcast::Receiver<Infallible>,
     ws_event_channel_cap: usize,
+    gateway: Option<Arc<Gateway>>,
+    remote: Option<SocketAddr>,
 ) -> Result<Response<FullOrStreamBody>, ExecutionError>
 where
     H: HandleRequest,
@@ -591,6 +730,8 @@ where
             events,
             shutdown,
             ws_event_channel_cap,
+            gateway,
+            remote,
         )
         .await;
     }
@@ -712,6 +853,7 @@ mod tests {
             "localhost:0",
             HeaderMap::new(),
             cert_and_key,
+            None,
         )
         .await
         .expect("Binding the server to the address should succeed");
@@ -759,6 +901,7 @@ mod tests {
             "localhost:0",
             HeaderMap::new(),
             Some((cert_path, key_path)),
+            None,
         )
         .await
         .expect("Binding the server to the address should succeed");
@@ -808,6 +951,7 @@ mod tests {
             "localhost:0",
             HeaderMap::new(),
             cert_and_key,
+            None,
         )
         .await
         .expect("Binding the server to the address should succeed");
@@ -994,4 +1138,26 @@ mod tests {
         let data = data.to_vec().into();
         Ok(RuesEvent { data, headers, uri })
     }
+
+    #[test]
+    fn any_topic_subscription() {
+        let event = RuesEvent::from(ContractTxEvent {
+            event: ContractEvent {
+                target: ContractId::from_bytes([1; 32]),
+                topic: "topic".into(),
+                data: vec![],
+            },
+            origin: [0; 32],
+        });
+        let entity = hex::encode([1; 32]);
+        let sub = |path: &str| {
+            RuesEventUri::parse_from_path(path).expect("path to be valid")
+        };
+
+        assert!(sub("/on/contracts/*").matches(&event));
+        assert!(sub(&format!("/on/contracts:{entity}/*")).matches(&event));
+        assert!(!sub("/on/contracts:00/*").matches(&event));
+        assert!(!sub("/on/transactions/*").matches(&event));
+        assert!(!sub("/on/contracts/other").matches(&event));
+    }
 }
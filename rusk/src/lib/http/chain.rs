@@ -6,32 +6,63 @@
 
 mod geo;
 pub mod graphql;
+mod idempotency;
+
+pub use idempotency::IdempotencyKeys;
 
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::sync::{mpsc, Arc};
+use std::thread;
 
-use dusk_core::transfer::Transaction as ProtocolTransaction;
-use dusk_vm::execute;
-use node::database::rocksdb::MD_HASH_KEY;
-use node::database::{self, Ledger, LightBlock, Mempool, Metadata, DB};
+use dusk_bytes::Serializable;
+use dusk_consensus::quorum::bundle::QuorumBundle;
+use dusk_core::transfer::bundle::TransactionBundle as ProtocolBundle;
+use dusk_core::transfer::phoenix::{BatchedNoteLeaf, NoteLeaf, NoteLeafBatch};
+use dusk_core::transfer::sponsor::SponsoredTransaction;
+use dusk_core::transfer::{
+    Transaction as ProtocolTransaction, TRANSFER_CONTRACT,
+};
+use dusk_core::BlsScalar;
+use dusk_vm::trace::TxTrace;
+use node::chain::fast_sync::{self, HeightRange};
+use node::chain::{median_time_past, MEDIAN_TIME_PAST_SPAN};
+use node::database::rocksdb::{Backend, MD_HASH_KEY};
+use node::database::round_history::{StepOutcome, StepRecord};
+use node::database::{
+    self, ConsensusStorage, Ledger, LightBlock, Mempool, Metadata, DB,
+};
+use node::mempool::admission::PolicyStack;
 use node::mempool::MempoolSrv;
 use node::vm::VMExecution;
-use node_data::ledger::Transaction;
+use node_data::ledger::{
+    self, EpochProof, Header, SpendingId, StepVotes, Transaction,
+    TransactionBundle,
+};
+use node_data::message::payload::RatificationResult;
 
 use async_graphql::{
     EmptyMutation, EmptySubscription, Name, Schema, Variables,
 };
+use futures_util::future::join_all;
 use graphql::Query;
+use serde::Deserialize;
 use serde_json::{json, Map, Value};
-use tracing::error;
+use tracing::{error, info};
 
 use super::event::RequestData;
 use super::*;
-use crate::node::RuskNode;
+use crate::node::{tx_profile_json, RuskNode};
 use crate::{VERSION, VERSION_BUILD};
 
+const RUSK_IDEMPOTENCY_KEY_HEADER: &str = "Rusk-Idempotency-Key";
+const RUSK_STATE_ROOT_HEADER: &str = "Rusk-State-Root";
 const GQL_VAR_PREFIX: &str = "rusk-gqlvar-";
 
+/// Maximum number of queries in a GraphQL batch
+const MAX_GQL_BATCH: usize = 32;
+/// Maximum number of notes in a notes snapshot request
+const MAX_SNAPSHOT_NOTES: usize = 1024;
+
 fn variables_from_headers(headers: &Map<String, Value>) -> Variables {
     let mut var = Variables::default();
     headers
@@ -49,19 +80,72 @@ fn variables_from_headers(headers: &Map<String, Value>) -> Variables {
     var
 }
 
+/// A query of a GraphQL batch
+#[derive(Deserialize)]
+struct GqlBatchQuery {
+    query: String,
+    #[serde(default)]
+    variables: Option<Value>,
+}
+
+impl GqlBatchQuery {
+    /// Parses a batch of at most [`MAX_GQL_BATCH`] queries
+    fn parse_batch(data: &str) -> anyhow::Result<Vec<Self>> {
+        let queries: Vec<Self> = serde_json::from_str(data)
+            .map_err(|e| anyhow::anyhow!("Invalid batch: {e}"))?;
+        if queries.len() > MAX_GQL_BATCH {
+            return Err(anyhow::anyhow!(
+                "Batch too large: {} queries, max {MAX_GQL_BATCH}",
+                queries.len()
+            ));
+        }
+        Ok(queries)
+    }
+
+    fn to_request(&self) -> async_graphql::Request {
+        let variables = self
+            .variables
+            .clone()
+            .map(Variables::from_json)
+            .unwrap_or_default();
+        async_graphql::Request::new(&self.query).variables(variables)
+    }
+}
+
 #[async_trait]
 impl HandleRequest for RuskNode {
     fn can_handle_rues(&self, request: &RuesDispatchEvent) -> bool {
         #[allow(clippy::match_like_matches_macro)]
         match request.uri.inner() {
             ("graphql", _, "query") => true,
+            ("graphql", _, "batch") => true,
             ("transactions", _, "preverify") => true,
             ("transactions", _, "propagate") => true,
+            ("bundles", _, "propagate") => true,
+            ("transactions", _, "propagate-sponsored") => true,
             ("transactions", _, "simulate") => true,
+            ("transactions", Some(_), "trace") => true,
             ("network", _, "peers") => true,
             ("network", _, "peers_location") => true,
+            ("network", _, "contributions") => true,
             ("node", _, "info") => true,
+            ("node", _, "auto-compound") => true,
             ("blocks", _, "gas-price") => true,
+            ("blocks", _, "gas-price-estimate") => true,
+            ("blocks", _, "note-leaves") => true,
+            ("notes", _, "snapshot") => true,
+            ("blocks", Some(_), "epoch-root") => true,
+            ("blocks", Some(_), "epoch-proof") => true,
+            ("consensus", Some(_), "history") => true,
+            ("consensus", Some(_), "seats") => true,
+            ("consensus", Some(_), "epoch-seats") => true,
+            ("consensus", _, "participation") => true,
+            ("consensus", _, "withholding") => true,
+            ("fast-sync", _, "info") => true,
+            ("fast-sync", _, "headers") => true,
+            ("fast-sync", _, "blocks") => true,
+            #[cfg(feature = "recovery-state")]
+            ("fast-sync", _, "state") => true,
             _ => false,
         }
     }
@@ -73,14 +157,32 @@ impl HandleRequest for RuskNode {
             ("graphql", _, "query") => {
                 self.handle_gql(&request.data, &request.headers).await
             }
+            ("graphql", _, "batch") => {
+                self.handle_gql_batch(&request.data).await
+            }
             ("transactions", _, "preverify") => {
                 self.handle_preverify(request.data.as_bytes()).await
             }
             ("transactions", _, "propagate") => {
-                self.propagate_tx(request.data.as_bytes()).await
+                let key = request
+                    .header(RUSK_IDEMPOTENCY_KEY_HEADER)
+                    .and_then(Value::as_str);
+                self.propagate_tx(request.data.as_bytes(), key).await
+            }
+            ("bundles", _, "propagate") => {
+                self.propagate_bundle(request.data.as_bytes()).await
+            }
+            ("transactions", _, "propagate-sponsored") => {
+                self.propagate_sponsored_tx(request.data.as_bytes()).await
             }
             ("transactions", _, "simulate") => {
-                self.simulate_tx(request.data.as_bytes()).await
+                let state_root = request
+                    .header(RUSK_STATE_ROOT_HEADER)
+                    .and_then(Value::as_str);
+                self.simulate_tx(request.data.as_bytes(), state_root).await
+            }
+            ("transactions", Some(tx_id), "trace") => {
+                self.trace_tx(tx_id).await
             }
             ("network", _, "peers") => {
                 let amount = request.data.as_string().trim().parse()?;
@@ -88,7 +190,16 @@ impl HandleRequest for RuskNode {
             }
 
             ("network", _, "peers_location") => self.peers_location().await,
+            ("network", _, "contributions") => {
+                let contributions =
+                    node::network::contribution::contributions();
+                Ok(ResponseData::new(serde_json::to_value(contributions)?))
+            }
             ("node", _, "info") => self.get_info().await,
+            ("node", _, "auto-compound") => {
+                let status = self.auto_compound.read().clone();
+                Ok(ResponseData::new(serde_json::to_value(status)?))
+            }
             ("blocks", _, "gas-price") => {
                 let max_transactions = request
                     .data
@@ -98,6 +209,69 @@ impl HandleRequest for RuskNode {
                     .unwrap_or(usize::MAX);
                 self.get_gas_price(max_transactions).await
             }
+            ("blocks", _, "gas-price-estimate") => {
+                let percentile = request.data.as_string().trim().parse()?;
+                let price = node::chain::estimate_gas_price(percentile)
+                    .ok_or_else(|| {
+                        anyhow::anyhow!("No recent transactions to estimate")
+                    })?;
+                Ok(ResponseData::new(json!({
+                    "percentile": percentile,
+                    "gas_price": price,
+                })))
+            }
+            ("blocks", _, "note-leaves") => {
+                self.note_leaves(request.data.as_bytes()).await
+            }
+            ("notes", _, "snapshot") => {
+                self.notes_snapshot(request.data.as_bytes()).await
+            }
+            ("blocks", Some(epoch), "epoch-root") => {
+                self.get_epoch_root(epoch.parse()?).await
+            }
+            ("blocks", Some(height), "epoch-proof") => {
+                self.get_epoch_proof(height.parse()?).await
+            }
+            ("blocks", Some(height), "quorum-bundle") => {
+                self.get_quorum_bundle(height.parse()?).await
+            }
+            ("consensus", Some(round), "history") => {
+                self.get_round_history(round.parse()?).await
+            }
+            ("consensus", Some(round), "seats") => {
+                let round: u64 = round.parse()?;
+                let steps = node::chain::round_seats(round);
+                Ok(ResponseData::new(json!({
+                    "round": round,
+                    "steps": steps,
+                })))
+            }
+            ("consensus", Some(epoch), "epoch-seats") => {
+                let seats = node::chain::epoch_seats(epoch.parse()?);
+                Ok(ResponseData::new(serde_json::to_value(seats)?))
+            }
+            ("consensus", Some(provisioner), "participation") => {
+                let participation =
+                    node::chain::participation(&provisioner.parse()?);
+                Ok(ResponseData::new(serde_json::to_value(participation)?))
+            }
+            ("consensus", None, "participation") => {
+                let participations = node::chain::participations();
+                Ok(ResponseData::new(serde_json::to_value(participations)?))
+            }
+            ("consensus", _, "withholding") => {
+                let evidence = node::chain::withholding_evidence();
+                Ok(ResponseData::new(serde_json::to_value(evidence)?))
+            }
+            ("fast-sync", _, "info") => self.fast_sync_info().await,
+            ("fast-sync", _, "headers") => {
+                self.fast_sync_headers(request.data.as_bytes()).await
+            }
+            ("fast-sync", _, "blocks") => {
+                self.fast_sync_blocks(request.data.as_bytes()).await
+            }
+            #[cfg(feature = "recovery-state")]
+            ("fast-sync", _, "state") => self.fast_sync_state().await,
             _ => anyhow::bail!("Unsupported"),
         }
     }
@@ -109,15 +283,7 @@ impl RuskNode {
         headers: &serde_json::Map<String, Value>,
     ) -> anyhow::Result<ResponseData> {
         let gql_query = data.as_string();
-
-        #[cfg(feature = "archive")]
-        let schema = Schema::build(Query, EmptyMutation, EmptySubscription)
-            .data((self.db(), self.archive()))
-            .finish();
-        #[cfg(not(feature = "archive"))]
-        let schema = Schema::build(Query, EmptyMutation, EmptySubscription)
-            .data((self.db(), ()))
-            .finish();
+        let schema = self.gql_schema();
 
         if gql_query.trim().is_empty() {
             return Ok(ResponseData::new(schema.sdl()));
@@ -137,36 +303,165 @@ impl RuskNode {
         Ok(ResponseData::new(data))
     }
 
+    /// Executes a batch of GraphQL queries concurrently, returning the result
+    /// of each of them along with the tip they were executed at.
+    ///
+    /// The failure of a query doesn't affect the others. All the queries read
+    /// the database as it was when the batch was received, so that all the
+    /// results reflect the same tip.
+    async fn handle_gql_batch(
+        &self,
+        data: &RequestData,
+    ) -> anyhow::Result<ResponseData> {
+        let queries = GqlBatchQuery::parse_batch(&data.as_string())?;
+
+        let db = Arc::new(RwLock::new(self.db().read().await.pinned()));
+        let schema = self.gql_schema_on(db.clone());
+        let batch = execute_gql_batch(&schema, &db, &queries).await?;
+
+        Ok(ResponseData::new(batch))
+    }
+
+    fn gql_schema(&self) -> Schema<Query, EmptyMutation, EmptySubscription> {
+        self.gql_schema_on(self.db())
+    }
+
+    /// Returns the GraphQL schema reading from `db`
+    fn gql_schema_on(
+        &self,
+        db: Arc<RwLock<Backend>>,
+    ) -> Schema<Query, EmptyMutation, EmptySubscription> {
+        #[cfg(feature = "archive")]
+        let schema = Schema::build(Query, EmptyMutation, EmptySubscription)
+            .data((db, self.archive()))
+            .finish();
+        #[cfg(not(feature = "archive"))]
+        let schema = Schema::build(Query, EmptyMutation, EmptySubscription)
+            .data((db, ()))
+            .finish();
+        schema
+    }
+
     async fn handle_preverify(
         &self,
         data: &[u8],
     ) -> anyhow::Result<ResponseData> {
         let tx = dusk_core::transfer::Transaction::from_slice(data)
             .map_err(|e| anyhow::anyhow!("Invalid Data {e:?}"))?;
+        self.admission_check(&tx.into()).await?;
+
+        Ok(ResponseData::new(DataType::None))
+    }
+
+    /// Checks that `tx` would be admitted to the mempool
+    async fn admission_check(&self, tx: &Transaction) -> anyhow::Result<()> {
         let db = self.inner().database();
         let vm = self.inner().vm_handler();
-        let tx = tx.into();
 
-        MempoolSrv::check_tx(&db, &vm, &tx, true, usize::MAX)
+        let min_bump = self.min_replacement_bump;
+        let policy = PolicyStack::chain();
+        MempoolSrv::check_tx(&db, &vm, tx, true, usize::MAX, min_bump, &policy)
             .await
             .map_err(|e| {
                 error!("Tx {} not accepted: {e}", hex::encode(tx.id()));
                 e
             })?;
 
-        Ok(ResponseData::new(DataType::None))
+        Ok(())
     }
 
-    async fn propagate_tx(&self, tx: &[u8]) -> anyhow::Result<ResponseData> {
+    /// Propagates a transaction.
+    ///
+    /// If an idempotency `key` is given, a submission with an already known
+    /// key isn't propagated again. Instead, the id and status of the
+    /// transaction originally submitted with the key are returned.
+    async fn propagate_tx(
+        &self,
+        tx: &[u8],
+        key: Option<&str>,
+    ) -> anyhow::Result<ResponseData> {
         let tx: Transaction = ProtocolTransaction::from_slice(tx)
             .map_err(|e| anyhow::anyhow!("Invalid Data {e:?}"))?
             .into();
-        let tx_message = tx.into();
+        let tx_id = tx.id();
 
-        let network = self.network();
-        network.read().await.route_internal(tx_message);
+        let Some(key) = key else {
+            self.network().read().await.route_internal(tx.into());
+            return Ok(ResponseData::new(DataType::None));
+        };
+        IdempotencyKeys::validate(key)?;
 
-        Ok(ResponseData::new(DataType::None))
+        // The key is only recorded once the transaction is admitted, so that
+        // a rejected submission doesn't burn it
+        let mut original = self.idempotency_keys.get(key);
+        if original.is_none() {
+            self.admission_check(&tx).await?;
+            original = self.idempotency_keys.get_or_insert(key, tx_id);
+        }
+
+        let status = match original {
+            Some(original) => {
+                let status = self.tx_status(original).await?;
+                info!(
+                    event = "duplicated submission",
+                    key,
+                    tx_id = hex::encode(original),
+                    status
+                );
+                json!({ "id": hex::encode(original), "status": status })
+            }
+            None => {
+                self.network().read().await.route_internal(tx.into());
+                json!({ "id": hex::encode(tx_id), "status": "submitted" })
+            }
+        };
+        Ok(ResponseData::new(status))
+    }
+
+    /// Propagates a bundle of transactions, returning its id.
+    async fn propagate_bundle(
+        &self,
+        bundle: &[u8],
+    ) -> anyhow::Result<ResponseData> {
+        let bundle: TransactionBundle = ProtocolBundle::from_slice(bundle)
+            .map_err(|e| anyhow::anyhow!("Invalid Data {e:?}"))?
+            .into();
+        let bundle_id = bundle.id();
+
+        self.network().read().await.route_internal(bundle.into());
+
+        Ok(ResponseData::new(json!({ "id": hex::encode(bundle_id) })))
+    }
+
+    /// Propagates a transaction along with the sponsorship of its gas,
+    /// returning its id.
+    async fn propagate_sponsored_tx(
+        &self,
+        tx: &[u8],
+    ) -> anyhow::Result<ResponseData> {
+        let tx: Transaction = SponsoredTransaction::from_slice(tx)
+            .map_err(|e| anyhow::anyhow!("Invalid Data {e:?}"))?
+            .into();
+        let tx_id = tx.id();
+
+        self.network().read().await.route_internal(tx.into());
+
+        Ok(ResponseData::new(json!({ "id": hex::encode(tx_id) })))
+    }
+
+    /// Returns the status of a submitted transaction: `executed` once in the
+    /// ledger, `pending` while in the mempool, `submitted` otherwise.
+    async fn tx_status(&self, tx_id: [u8; 32]) -> anyhow::Result<&'static str> {
+        self.db().read().await.view(|t| {
+            let status = if t.ledger_tx_exists(&tx_id)? {
+                "executed"
+            } else if t.mempool_tx_exists(tx_id)? {
+                "pending"
+            } else {
+                "submitted"
+            };
+            Ok(status)
+        })
     }
 
     async fn alive_nodes(&self, amount: usize) -> anyhow::Result<ResponseData> {
@@ -189,6 +484,20 @@ impl RuskNode {
         let vm_conf = serde_json::to_value(vm_conf).unwrap_or_default();
         info.insert("vm_config", vm_conf);
 
+        let disk_status = node::disk_guard::status().to_string();
+        info.insert("disk_status", disk_status.into());
+
+        let consensus = node::chain::consensus_health().await;
+        let consensus = serde_json::to_value(consensus).unwrap_or_default();
+        info.insert("consensus", consensus);
+
+        if let Some(tip) = load_tip(&self.db()).await? {
+            let mtp = self.db().read().await.view(|t| {
+                median_time_past(t, &tip.header, MEDIAN_TIME_PAST_SPAN)
+            })?;
+            info.insert("median_time_past", mtp.into());
+        }
+
         Ok(ResponseData::new(serde_json::to_value(&info)?))
     }
 
@@ -256,41 +565,537 @@ impl RuskNode {
         Ok(ResponseData::new(serde_json::to_value(stats)?))
     }
 
-    async fn simulate_tx(&self, tx: &[u8]) -> anyhow::Result<ResponseData> {
-        let tx = ProtocolTransaction::from_slice(tx)
-            .map_err(|e| anyhow::anyhow!("Invalid transaction: {e:?}"))?;
-        let (config, mut session) = {
-            let vm_handler = self.inner().vm_handler();
-            let vm_handler = vm_handler.read().await;
-            if tx.gas_limit() > vm_handler.get_block_gas_limit() {
-                return Err(anyhow::anyhow!("Gas limit is too high."));
-            }
-            let tip = load_tip(&self.db())
-                .await
-                .map_err(|e| anyhow::anyhow!("Failed to load the tip: {e}"))?
-                .ok_or_else(|| anyhow::anyhow!("Could not find the tip"))?;
-            let height = tip.header.height;
-            let config = vm_handler.vm_config.to_execution_config(height);
-            let session = vm_handler
-                .new_block_session(height, vm_handler.tip.read().current)
-                .map_err(|e| {
-                    anyhow::anyhow!("Failed to initialize a session: {e}")
-                })?;
-            (config, session)
-        };
-        let receipt = execute(&mut session, &tx, &config);
-        let resp = match receipt {
-            Ok(receipt) => json!({
-                "gas-spent": receipt.gas_spent,
-                "error": receipt.data.err().map(|err| format!("{err:?}")),
-            }),
-            Err(err) => json!({
-                "gas-spent": 0,
-                "error": format!("{err:?}")
-            }),
+    /// Simulates the execution of a transaction on top of the given state
+    /// root, or of the tip if none is given, at the height of the tip.
+    async fn simulate_tx(
+        &self,
+        tx: &[u8],
+        state_root: Option<&str>,
+    ) -> anyhow::Result<ResponseData> {
+        let tx: Transaction = ProtocolTransaction::from_slice(tx)
+            .map_err(|e| anyhow::anyhow!("Invalid transaction: {e:?}"))?
+            .into();
+        let state_root = state_root
+            .map(|root| {
+                hex::decode(root)?
+                    .try_into()
+                    .map_err(|_| anyhow::anyhow!("Invalid state root"))
+            })
+            .transpose()?;
+
+        let tip = load_tip(&self.db())
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to load the tip: {e}"))?
+            .ok_or_else(|| anyhow::anyhow!("Could not find the tip"))?;
+        let height = tip.header.height;
+
+        // The transaction is simulated without holding the VM lock
+        let rusk = self.inner().vm_handler().read().await.clone();
+        let gas_limit = tx.inner.gas_limit();
+        let gas_price = tx.inner.gas_price();
+        if gas_limit > rusk.get_block_gas_limit() {
+            return Err(anyhow::anyhow!("Gas limit is too high."));
+        }
+        let state_root = state_root.unwrap_or_else(|| rusk.tip.read().current);
+
+        let (result, profile) = tokio::task::spawn_blocking(move || {
+            let profiled = rusk.vm_config.debug_receipts;
+            rusk.simulate_transaction(state_root, height, &tx, profiled)
+        })
+        .await?
+        .map_err(|e| anyhow::anyhow!("Failed to simulate: {e}"))?;
+
+        // An unspendable transaction is charged nothing, hence refunded nothing
+        let gas_refunded = match result.gas_spent {
+            0 => 0,
+            gas_spent => gas_limit.saturating_sub(gas_spent),
         };
+        let mut resp = json!({
+            "gas-spent": result.gas_spent,
+            "gas-refunded": gas_refunded,
+            "fee-charged": result.gas_spent * gas_price,
+            "events": result.events,
+            "error": result.error,
+        });
+        if let Some(profile) = profile {
+            resp["profile"] = tx_profile_json(&profile);
+        }
+        Ok(ResponseData::new(resp))
+    }
+
+    /// Re-executes a historical transaction against the state it was executed
+    /// on, returning the trace of its contract calls and host queries.
+    ///
+    /// Only served when `debug_receipts` is enabled, and as long as the state
+    /// preceding the transaction's block is still available.
+    async fn trace_tx(&self, tx_id: &str) -> anyhow::Result<ResponseData> {
+        let tx_id: [u8; 32] = hex::decode(tx_id)?
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("Invalid transaction id"))?;
+
+        let (block, prev_state_root) = self.db().read().await.view(|t| {
+            let spent = t
+                .ledger_tx(&tx_id)?
+                .ok_or_else(|| anyhow::anyhow!("Transaction not found"))?;
+            let block = t
+                .block_by_height(spent.block_height)?
+                .ok_or_else(|| anyhow::anyhow!("Block not found"))?;
+            let prev_header = t
+                .block_header(&block.header().prev_block_hash)?
+                .ok_or_else(|| anyhow::anyhow!("Parent block not found"))?;
+            anyhow::Ok((block, prev_header.state_hash))
+        })?;
+        let index = block
+            .txs()
+            .iter()
+            .position(|tx| tx.id() == tx_id)
+            .ok_or_else(|| anyhow::anyhow!("Transaction not in its block"))?;
+
+        // The transactions are re-executed without holding the VM lock
+        let rusk = self.inner().vm_handler().read().await.clone();
+        if !rusk.vm_config.debug_receipts {
+            anyhow::bail!("Tracing requires debug_receipts to be enabled");
+        }
+        let (receipt, trace) = tokio::task::spawn_blocking(move || {
+            rusk.trace_transaction(
+                prev_state_root,
+                block.header().height,
+                block.txs(),
+                index,
+            )
+        })
+        .await?
+        .map_err(|e| anyhow::anyhow!("Cannot trace transaction: {e}"))?;
+
+        let mut resp = json!({
+            "gas-spent": receipt.gas_spent,
+            "error": receipt.data.err().map(|err| format!("{err:?}")),
+        });
+        resp["trace"] = tx_trace_json(&trace);
         Ok(ResponseData::new(resp))
     }
+
+    /// Returns the spending status of the given notes, along with the
+    /// balance they make up.
+    ///
+    /// A note is either spent on-chain, spent by a transaction pending in the
+    /// mempool, or unspent. A snapshot is consistent: the on-chain spends are
+    /// the ones at the tip the mempool is read along with.
+    async fn notes_snapshot(
+        &self,
+        data: &[u8],
+    ) -> anyhow::Result<ResponseData> {
+        let (request, nullifiers) = NotesSnapshotRequest::parse(data)?;
+
+        // The ledger and the mempool are read from the same view, while the
+        // state is read at the commit of the tip of that view
+        let (tip, pending) = self.db().read().await.view(|t| {
+            let tip = t
+                .op_read(MD_HASH_KEY)?
+                .and_then(|hash| t.block_header(&hash).transpose())
+                .transpose()?
+                .ok_or_else(|| anyhow::anyhow!("Tip not found"))?;
+            let pending: Vec<_> = nullifiers
+                .iter()
+                .map(|n| {
+                    let id = SpendingId::Nullifier(n.to_bytes());
+                    t.mempool_txs_by_spendable_ids(&[id]).into_iter().next()
+                })
+                .collect();
+            anyhow::Ok((tip, pending))
+        })?;
+
+        let vm_handler = self.inner().vm_handler();
+        let spent: Vec<BlsScalar> = vm_handler
+            .read()
+            .await
+            .query_at(
+                tip.state_hash,
+                TRANSFER_CONTRACT,
+                "existing_nullifiers",
+                &nullifiers,
+            )
+            .map_err(|e| anyhow::anyhow!("Cannot query the state {e:?}"))?;
+
+        let (notes, spendable, pending_spent) =
+            request.snapshot(&nullifiers, &spent, pending);
+
+        Ok(ResponseData::new(json!({
+            "height": tip.height,
+            "block_hash": hex::encode(tip.hash),
+            "state_root": hex::encode(tip.state_hash),
+            "notes": notes,
+            "balance": {
+                "spendable": spendable,
+                "pending": pending_spent,
+            },
+        })))
+    }
+
+    /// Returns the outcome of every step the node ran for `round`, so that
+    /// the quorum missing from a failed round can be told.
+    async fn get_round_history(
+        &self,
+        round: u64,
+    ) -> anyhow::Result<ResponseData> {
+        let records =
+            self.db().read().await.view(|t| t.round_history(round))?;
+        let steps: Vec<_> = records.iter().map(step_record_json).collect();
+
+        Ok(ResponseData::new(json!({
+            "round": round,
+            "steps": steps,
+        })))
+    }
+
+    async fn get_epoch_root(&self, epoch: u64) -> anyhow::Result<ResponseData> {
+        let root = self.db().read().await.view(|t| t.epoch_root(epoch))?;
+
+        Ok(ResponseData::new(json!({
+            "epoch": epoch,
+            "root": root.map(hex::encode),
+        })))
+    }
+
+    /// Returns the proof that the block at `height` is in the root of its
+    /// epoch
+    async fn get_epoch_proof(
+        &self,
+        height: u64,
+    ) -> anyhow::Result<ResponseData> {
+        let epoch = ledger::epoch_of(height);
+
+        let (root, hashes) = self.db().read().await.view(|t| {
+            let root = t
+                .epoch_root(epoch)?
+                .ok_or_else(|| anyhow::anyhow!("No root for epoch {epoch}"))?;
+            let hashes = ledger::epoch_heights(epoch)
+                .map(|height| {
+                    t.block_hash_by_height(height)?.ok_or_else(|| {
+                        anyhow::anyhow!("Cannot read block at {height}")
+                    })
+                })
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            anyhow::Ok((root, hashes))
+        })?;
+
+        let index = (height - ledger::epoch_heights(epoch).start) as usize;
+        let proof = EpochProof::new(epoch, &hashes, index)
+            .ok_or_else(|| anyhow::anyhow!("Invalid height {height}"))?;
+        let mut encoded = vec![];
+        node_data::Serializable::write(&proof, &mut encoded)?;
+
+        Ok(ResponseData::new(json!({
+            "epoch": epoch,
+            "height": height,
+            "block_hash": hex::encode(hashes[index]),
+            "root": hex::encode(root),
+            "index": proof.index,
+            "len": proof.len,
+            "siblings": proof.siblings.iter().map(hex::encode).collect::<Vec<_>>(),
+            "proof": hex::encode(encoded),
+        })))
+    }
+
+    /// Returns the quorum certificate bundle of the block at `height`, in both
+    /// the canonical binary and the JSON format
+    async fn get_quorum_bundle(
+        &self,
+        height: u64,
+    ) -> anyhow::Result<ResponseData> {
+        if height == 0 {
+            anyhow::bail!("The genesis block has no attestation");
+        }
+
+        let (header, prev_header) = self.db().read().await.view(|t| {
+            let header = |height| -> anyhow::Result<Header> {
+                t.block_hash_by_height(height)?
+                    .and_then(|hash| t.block_header(&hash).transpose())
+                    .ok_or_else(|| {
+                        anyhow::anyhow!("Cannot read block at {height}")
+                    })?
+            };
+            anyhow::Ok((header(height)?, header(height - 1)?))
+        })?;
+
+        // The committees are extracted from the provisioners at the state the
+        // block was built upon
+        let rusk = self.inner().vm_handler().read().await.clone();
+        let provisioners = rusk.get_provisioners(prev_header.state_hash)?;
+        let bundle =
+            QuorumBundle::export(&header, prev_header.seed, &provisioners)?;
+
+        let mut encoded = vec![];
+        node_data::Serializable::write(&bundle, &mut encoded)?;
+
+        Ok(ResponseData::new(json!({
+            "bundle": bundle,
+            "encoded": hex::encode(encoded),
+        })))
+    }
+
+    async fn fast_sync_info(&self) -> anyhow::Result<ResponseData> {
+        let db = self.db();
+        let header =
+            db.read().await.view(|t| fast_sync::last_final_header(t))?;
+        Ok(ResponseData::new(fast_sync::encode_all([&header])?))
+    }
+
+    async fn fast_sync_headers(
+        &self,
+        data: &[u8],
+    ) -> anyhow::Result<ResponseData> {
+        let range: HeightRange = serde_json::from_slice(data)
+            .map_err(|e| anyhow::anyhow!("Invalid request: {e}"))?;
+        let headers = self
+            .db()
+            .read()
+            .await
+            .view(|t| fast_sync::range_headers(t, range))?;
+        Ok(ResponseData::new(fast_sync::encode_all(&headers)?))
+    }
+
+    async fn fast_sync_blocks(
+        &self,
+        data: &[u8],
+    ) -> anyhow::Result<ResponseData> {
+        let range: HeightRange = serde_json::from_slice(data)
+            .map_err(|e| anyhow::anyhow!("Invalid request: {e}"))?;
+        let blocks = self
+            .db()
+            .read()
+            .await
+            .view(|t| fast_sync::range_blocks(t, range))?;
+        Ok(ResponseData::new(fast_sync::encode_all(&blocks)?))
+    }
+
+    /// Returns the archive of the finalized state, preceded by the header of
+    /// the last finalized block.
+    ///
+    /// The state is only exported if enabled, at most once per configured
+    /// interval, and if its archive doesn't exceed the configured size.
+    #[cfg(feature = "recovery-state")]
+    async fn fast_sync_state(&self) -> anyhow::Result<ResponseData> {
+        self.state_export.acquire()?;
+        let max_size = self.state_export.max_size;
+        let rusk = self.inner().vm_handler().read().await.clone();
+        let (state_root, archive) =
+            tokio::task::spawn_blocking(move || rusk.state_snapshot(max_size))
+                .await??;
+
+        let db = self.db();
+        let header =
+            db.read().await.view(|t| fast_sync::last_final_header(t))?;
+        if header.state_hash != state_root {
+            anyhow::bail!("State finalized during the export");
+        }
+
+        info!(
+            event = "fast sync state exported",
+            height = header.height,
+            size = archive.len()
+        );
+        Ok(ResponseData::new(fast_sync::encode_snapshot(
+            &header, &archive,
+        )?))
+    }
+
+    /// Streams the leaves of the transfer tree starting from the given
+    /// height, batched per block.
+    ///
+    /// Each batch is sent as a rkyv-serialized [`NoteLeafBatch`]. The stream
+    /// ends early at the first block missing from the ledger.
+    async fn note_leaves(&self, data: &[u8]) -> anyhow::Result<ResponseData> {
+        let request: NoteLeavesRequest = serde_json::from_slice(data)
+            .map_err(|e| anyhow::anyhow!("Invalid request: {e}"))?;
+
+        let rusk = self.inner().vm_handler().read().await.clone();
+        let db = self.db();
+
+        let (leaves_sender, leaves_receiver) = mpsc::channel();
+        let (sender, receiver) = mpsc::channel();
+
+        thread::spawn(move || {
+            let res = rusk.feeder_query(
+                TRANSFER_CONTRACT,
+                "leaves_from_height",
+                &request.height,
+                leaves_sender,
+                None,
+            );
+            if let Err(err) = res {
+                error!("Note leaves query errored: {err}");
+            }
+        });
+
+        thread::spawn(move || {
+            batch_note_leaves(leaves_receiver, &db, request.notes, &sender)
+        });
+
+        Ok(ResponseData::new(receiver))
+    }
+}
+
+/// Sends the rkyv-serialized `leaves` to `sender`, batched per block along
+/// with the hash of the block, and the full notes if `with_notes` is set.
+///
+/// The batches stop at the first block missing from `db`, rather than being
+/// sent without the hash of their block.
+fn batch_note_leaves<DB: database::DB>(
+    leaves: impl IntoIterator<Item = Vec<u8>>,
+    db: &RwLock<DB>,
+    with_notes: bool,
+    sender: &mpsc::Sender<Vec<u8>>,
+) {
+    let mut batch: Option<NoteLeafBatch> = None;
+    for bytes in leaves {
+        let leaf = rkyv::from_bytes::<NoteLeaf>(&bytes)
+            .expect("The contract should always return valid leaves");
+
+        let height = leaf.block_height;
+        if batch.as_ref().map(|b| b.block_height) != Some(height) {
+            if let Some(batch) = batch.take() {
+                if !send_batch(sender, &batch) {
+                    return;
+                }
+            }
+
+            let block_hash =
+                db.blocking_read().view(|t| t.block_hash_by_height(height));
+            let block_hash = match block_hash {
+                Ok(Some(hash)) => hash,
+                Ok(None) => {
+                    error!(
+                        "Missing block at {height}, note leaves stream ended"
+                    );
+                    return;
+                }
+                Err(err) => {
+                    error!("Cannot read block hash at {height}: {err}");
+                    return;
+                }
+            };
+            batch = Some(NoteLeafBatch {
+                block_height: height,
+                block_hash,
+                leaves: vec![],
+            });
+        }
+
+        if let Some(batch) = batch.as_mut() {
+            batch
+                .leaves
+                .push(BatchedNoteLeaf::new(leaf.note, with_notes));
+        }
+    }
+
+    if let Some(batch) = batch {
+        send_batch(sender, &batch);
+    }
+}
+
+#[derive(Deserialize)]
+struct NoteLeavesRequest {
+    /// Height to start streaming the leaves from
+    #[serde(default)]
+    height: u64,
+    /// Whether to include the full notes in the batches
+    #[serde(default)]
+    notes: bool,
+}
+
+#[derive(Deserialize)]
+struct NotesSnapshotRequest {
+    notes: Vec<SnapshotNote>,
+}
+
+impl NotesSnapshotRequest {
+    /// Parses a request, returning it along with the decoded nullifiers of
+    /// its notes.
+    fn parse(data: &[u8]) -> anyhow::Result<(Self, Vec<BlsScalar>)> {
+        let request: Self = serde_json::from_slice(data)
+            .map_err(|e| anyhow::anyhow!("Invalid request: {e}"))?;
+
+        if request.notes.len() > MAX_SNAPSHOT_NOTES {
+            anyhow::bail!(
+                "Too many notes: {}, max {MAX_SNAPSHOT_NOTES}",
+                request.notes.len()
+            );
+        }
+
+        let nullifiers = request
+            .notes
+            .iter()
+            .map(|note| {
+                let bytes: [u8; 32] = hex::decode(&note.nullifier)?
+                    .try_into()
+                    .map_err(|_| anyhow::anyhow!("Invalid nullifier length"))?;
+                BlsScalar::from_bytes(&bytes).map_err(|_| {
+                    anyhow::anyhow!("Invalid nullifier {}", note.nullifier)
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        Ok((request, nullifiers))
+    }
+
+    /// Classifies the notes of the request given the nullifiers spent in the
+    /// state and the mempool transactions spending each note, returning the
+    /// notes along with the spendable and pending balances.
+    fn snapshot(
+        &self,
+        nullifiers: &[BlsScalar],
+        spent: &[BlsScalar],
+        pending: Vec<Option<[u8; 32]>>,
+    ) -> (Vec<Value>, u64, u64) {
+        let mut spendable = 0u64;
+        let mut pending_spent = 0u64;
+        let notes = self
+            .notes
+            .iter()
+            .zip(nullifiers)
+            .zip(pending)
+            .map(|((note, nullifier), pending_tx)| {
+                let (status, pending_tx) = if spent.contains(nullifier) {
+                    ("spent", None)
+                } else if let Some(tx_id) = pending_tx {
+                    pending_spent = pending_spent.saturating_add(note.value);
+                    ("pending", Some(hex::encode(tx_id)))
+                } else {
+                    spendable = spendable.saturating_add(note.value);
+                    ("unspent", None)
+                };
+                json!({
+                    "nullifier": note.nullifier,
+                    "pos": note.pos,
+                    "status": status,
+                    "pending_tx": pending_tx,
+                })
+            })
+            .collect();
+
+        (notes, spendable, pending_spent)
+    }
+}
+
+#[derive(Deserialize)]
+struct SnapshotNote {
+    /// Hex-encoded nullifier of the note
+    nullifier: String,
+    /// Position of the note in the transfer tree, echoed in the response
+    #[serde(default)]
+    pos: Option<u64>,
+    /// Value of the note, known to the wallet
+    #[serde(default)]
+    value: u64,
+}
+
+/// Serializes and sends a batch, returning false if the receiver is gone.
+fn send_batch(sender: &mpsc::Sender<Vec<u8>>, batch: &NoteLeafBatch) -> bool {
+    let bytes = rkyv::to_bytes::<_, 4096>(batch)
+        .expect("Serializing a batch should succeed")
+        .to_vec();
+    sender.send(bytes).is_ok()
 }
 
 async fn load_tip<DB: database::DB>(
@@ -303,3 +1108,291 @@ async fn load_tip<DB: database::DB>(
         }))
     })
 }
+
+/// Executes the GraphQL `queries` concurrently over `schema`, returning the
+/// result of each of them along with the tip of `db`.
+///
+/// The schema is expected to read from `db`, pinned for the results to
+/// reflect its tip.
+async fn execute_gql_batch<DB: database::DB>(
+    schema: &Schema<Query, EmptyMutation, EmptySubscription>,
+    db: &Arc<RwLock<DB>>,
+    queries: &[GqlBatchQuery],
+) -> anyhow::Result<Value> {
+    let tip = load_tip(db)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Tip not found"))?;
+
+    let responses =
+        join_all(queries.iter().map(|q| schema.execute(q.to_request()))).await;
+    let results = responses
+        .iter()
+        .map(serde_json::to_value)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(json!({
+        "height": tip.header.height,
+        "hash": hex::encode(tip.header.hash),
+        "results": results,
+    }))
+}
+
+/// Returns the JSON representation of the trace of a transaction
+fn tx_trace_json(trace: &TxTrace) -> Value {
+    let calls: Vec<_> = trace
+        .calls
+        .iter()
+        .map(|call| {
+            json!({
+                "contract": call.contract.to_string(),
+                "gas_limit": call.gas_limit,
+                "gas_spent": call.gas_spent,
+                "mem_len": call.mem_len,
+            })
+        })
+        .collect();
+    let host_calls: Vec<_> = trace
+        .host_calls
+        .iter()
+        .map(|call| json!({ "name": call.name, "arg_len": call.arg_len }))
+        .collect();
+
+    json!({
+        "calls": calls,
+        "host_calls": host_calls,
+    })
+}
+
+/// Returns the JSON representation of the outcome of a consensus step
+fn step_record_json(record: &StepRecord) -> Value {
+    let outcome = match &record.outcome {
+        StepOutcome::NoResult => json!({ "type": "none" }),
+        StepOutcome::Candidate(hash) => json!({
+            "type": "candidate",
+            "hash": hex::encode(hash),
+        }),
+        StepOutcome::Validation(vr) => json!({
+            "type": "validation",
+            "quorum": format!("{:?}", vr.quorum()),
+            "vote": vr.vote(),
+            "step_votes": step_votes_json(vr.sv()),
+        }),
+        StepOutcome::Quorum(att) => {
+            let (success, vote) = match att.result {
+                RatificationResult::Success(vote) => (true, vote),
+                RatificationResult::Fail(vote) => (false, vote),
+            };
+            json!({
+                "type": "quorum",
+                "success": success,
+                "vote": vote,
+                "validation": step_votes_json(&att.validation),
+                "ratification": step_votes_json(&att.ratification),
+            })
+        }
+    };
+
+    json!({
+        "prev_block_hash": hex::encode(record.header.prev_block_hash),
+        "iteration": record.header.iteration,
+        "step": format!("{:?}", record.step),
+        "outcome": outcome,
+    })
+}
+
+fn step_votes_json(sv: &StepVotes) -> Value {
+    json!({
+        "bitset": sv.bitset,
+        "votes": sv.bitset.count_ones(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use dusk_core::transfer::phoenix::{
+        Note, PublicKey as PhoenixPublicKey, SecretKey as PhoenixSecretKey,
+    };
+    use dusk_core::JubJubScalar;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::*;
+
+    fn snapshot_request(nullifiers: &[BlsScalar]) -> Vec<u8> {
+        let notes: Vec<_> = nullifiers
+            .iter()
+            .enumerate()
+            .map(|(i, n)| {
+                json!({
+                    "nullifier": hex::encode(n.to_bytes()),
+                    "pos": i,
+                    "value": 10 * (i as u64 + 1),
+                })
+            })
+            .collect();
+        serde_json::to_vec(&json!({ "notes": notes })).unwrap()
+    }
+
+    #[test]
+    fn gql_batch_is_bounded() {
+        let batch = |len| {
+            let query = json!({ "query": "{ block(height: -1) { reward } }" });
+            serde_json::to_string(&vec![query; len]).unwrap()
+        };
+        assert!(GqlBatchQuery::parse_batch(&batch(MAX_GQL_BATCH)).is_ok());
+        assert!(GqlBatchQuery::parse_batch(&batch(MAX_GQL_BATCH + 1)).is_err());
+        assert!(GqlBatchQuery::parse_batch("{}").is_err());
+    }
+
+    #[tokio::test]
+    async fn gql_batch_reads_one_snapshot() {
+        let tmp = tempfile::tempdir().expect("temp dir to be created");
+        let db = Backend::create_or_open(
+            tmp.path(),
+            database::DatabaseOptions::default(),
+        );
+        let store = |height: u64| {
+            let header = Header {
+                height,
+                hash: [height as u8; 32],
+                ..Default::default()
+            };
+            db.update(|t| {
+                t.store_block(&header, &[], &[], ledger::Label::Final(height))?;
+                Ok(())
+            })
+            .expect("block to be stored");
+        };
+
+        store(1);
+        let pinned = Arc::new(RwLock::new(db.pinned()));
+        // A block accepted while the batch is executed
+        store(2);
+
+        #[cfg(feature = "archive")]
+        let archive = node::archive::Archive::create_or_open(tmp.path()).await;
+        #[cfg(not(feature = "archive"))]
+        let archive = ();
+        let schema = Schema::build(Query, EmptyMutation, EmptySubscription)
+            .data((pinned.clone(), archive))
+            .finish();
+
+        let queries = GqlBatchQuery::parse_batch(
+            &json!([
+                { "query": "{ block(height: -1) { header { height } } }" },
+                { "query": "{ block(height: 2) { header { height } } }" },
+                { "query": "{ block(height: \"invalid\") { reward } }" },
+            ])
+            .to_string(),
+        )
+        .expect("batch to parse");
+        let batch = execute_gql_batch(&schema, &pinned, &queries)
+            .await
+            .expect("batch to be executed");
+
+        // All the queries read the tip the batch was pinned to
+        assert_eq!(batch["height"], 1);
+        assert_eq!(batch["hash"], hex::encode([1; 32]));
+        let results = &batch["results"];
+        assert_eq!(results[0]["data"]["block"]["header"]["height"], 1);
+        assert_eq!(results[1]["data"]["block"], Value::Null);
+
+        // The failure of a query doesn't affect the others
+        assert!(results[2]["errors"].is_array());
+    }
+
+    #[test]
+    fn note_leaves_end_at_missing_block() {
+        let tmp = tempfile::tempdir().expect("temp dir to be created");
+        let db = Backend::create_or_open(
+            tmp.path(),
+            database::DatabaseOptions::default(),
+        );
+        for height in [1, 3] {
+            let header = Header {
+                height,
+                hash: [height as u8; 32],
+                ..Default::default()
+            };
+            db.update(|t| {
+                t.store_block(&header, &[], &[], ledger::Label::Final(height))?;
+                Ok(())
+            })
+            .expect("block to be stored");
+        }
+
+        let rng = &mut StdRng::seed_from_u64(0xbeef);
+        let pk = PhoenixPublicKey::from(&PhoenixSecretKey::random(rng));
+        let mut leaf = |block_height| {
+            let blinder = [JubJubScalar::from(1u64); 2];
+            let note = Note::transparent(rng, &pk, &pk, 10, blinder);
+            rkyv::to_bytes::<_, 4096>(&NoteLeaf { block_height, note })
+                .expect("leaf to serialize")
+                .to_vec()
+        };
+        let leaves = vec![leaf(1), leaf(1), leaf(2), leaf(3)];
+
+        let (sender, receiver) = mpsc::channel();
+        batch_note_leaves(leaves, &RwLock::new(db), true, &sender);
+        drop(sender);
+        let batches: Vec<_> = receiver
+            .into_iter()
+            .map(|bytes| rkyv::from_bytes::<NoteLeafBatch>(&bytes).unwrap())
+            .collect();
+
+        // No batch is sent for the missing block, nor for the ones after it
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].block_height, 1);
+        assert_eq!(batches[0].block_hash, [1; 32]);
+        assert_eq!(batches[0].leaves.len(), 2);
+        assert!(batches[0].leaves.iter().all(|leaf| leaf.note.is_some()));
+    }
+
+    #[test]
+    fn notes_snapshot_statuses() {
+        let nullifiers: Vec<_> =
+            (1..=3).map(|i| BlsScalar::from(i as u64)).collect();
+        let (request, parsed) =
+            NotesSnapshotRequest::parse(&snapshot_request(&nullifiers))
+                .expect("request should parse");
+        assert_eq!(parsed, nullifiers);
+
+        let spent = [nullifiers[0]];
+        let pending = vec![Some([9; 32]), Some([7; 32]), None];
+        let (notes, spendable, pending_spent) =
+            request.snapshot(&parsed, &spent, pending);
+
+        // A note spent in the state is not reported as pending
+        assert_eq!(notes[0]["status"], "spent");
+        assert_eq!(notes[0]["pending_tx"], Value::Null);
+        assert_eq!(notes[1]["status"], "pending");
+        assert_eq!(notes[1]["pending_tx"], hex::encode([7; 32]));
+        assert_eq!(notes[2]["status"], "unspent");
+        assert_eq!(notes[2]["pos"], 2);
+        assert_eq!(pending_spent, 20);
+        assert_eq!(spendable, 30);
+    }
+
+    #[test]
+    fn notes_snapshot_is_bounded() {
+        let nullifiers = vec![BlsScalar::one(); MAX_SNAPSHOT_NOTES];
+        assert!(
+            NotesSnapshotRequest::parse(&snapshot_request(&nullifiers)).is_ok()
+        );
+
+        let nullifiers = vec![BlsScalar::one(); MAX_SNAPSHOT_NOTES + 1];
+        assert!(NotesSnapshotRequest::parse(&snapshot_request(&nullifiers))
+            .is_err());
+    }
+
+    #[test]
+    fn notes_snapshot_rejects_invalid_nullifiers() {
+        let short = json!({ "notes": [{ "nullifier": "abcd" }] });
+        let data = serde_json::to_vec(&short).unwrap();
+        assert!(NotesSnapshotRequest::parse(&data).is_err());
+
+        let not_hex = json!({ "notes": [{ "nullifier": "zz" }] });
+        let data = serde_json::to_vec(&not_hex).unwrap();
+        assert!(NotesSnapshotRequest::parse(&data).is_err());
+    }
+}
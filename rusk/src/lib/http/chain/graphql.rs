@@ -20,6 +20,8 @@ use dusk_core::transfer::TRANSFER_CONTRACT;
 use node::database::rocksdb::Backend;
 use node::database::{Ledger, DB};
 use node_data::ledger::Label;
+
+use crate::http::query::PageInput;
 #[cfg(feature = "archive")]
 use {
     archive::data::*, archive::events::*, archive::finalized_block::*,
@@ -79,14 +81,7 @@ impl Query {
     ) -> FieldResult<Vec<SpentTransaction>> {
         let blocks = self.blocks(ctx, last, range).await?;
 
-        let contract = match contract {
-            Some(contract) => {
-                let mut decoded = [0u8; 32];
-                decoded.copy_from_slice(&hex::decode(contract)?[..]);
-                Some(ContractId::from(decoded))
-            }
-            _ => None,
-        };
+        let contract = contract.as_deref().map(parse_contract).transpose()?;
 
         let mut txs = vec![];
         for b in blocks.iter() {
@@ -96,16 +91,7 @@ impl Query {
                 Some(contract) => {
                     let mut txs_to_add = block_txs
                         .into_iter()
-                        .filter(|t| {
-                            let tx_contract =
-                                t.0.inner
-                                    .inner
-                                    .call()
-                                    .map(|c| c.contract)
-                                    .unwrap_or(TRANSFER_CONTRACT);
-
-                            tx_contract == *contract
-                        })
+                        .filter(|t| tx_contract(&t.0.inner) == *contract)
                         .collect();
                     txs.append(&mut txs_to_add);
                 }
@@ -128,6 +114,54 @@ impl Query {
         }
     }
 
+    /// Get the gas spent calling each contract in a range of at most 10000
+    /// block heights, the most consuming contracts first.
+    ///
+    /// Each contract is accounted the gas spent by its calls, including their
+    /// nested calls. The blocks migrated from an older database only account
+    /// each transaction to the contract it calls. Blocks accepted while the
+    /// indexes were paused are not accounted.
+    async fn gas_by_contract(
+        &self,
+        ctx: &Context<'_>,
+        from_height: u64,
+        to_height: u64,
+    ) -> FieldResult<Vec<ContractGas>> {
+        gas_by_contract(ctx, from_height, to_height).await
+    }
+
+    /// Get a page of blocks, optionally filtered by generator.
+    async fn blocks_page(
+        &self,
+        ctx: &Context<'_>,
+        page: Option<PageInput>,
+        generator: Option<String>,
+    ) -> FieldResult<BlockPage> {
+        blocks_page(ctx, page.unwrap_or_default(), generator).await
+    }
+
+    /// Get a page of the transactions included in blocks, optionally filtered
+    /// by called contract.
+    async fn transactions_page(
+        &self,
+        ctx: &Context<'_>,
+        page: Option<PageInput>,
+        contract: Option<String>,
+    ) -> FieldResult<TransactionPage> {
+        transactions_page(ctx, page.unwrap_or_default(), contract).await
+    }
+
+    /// Get a page of the mempool transactions, sorted by gas price and
+    /// optionally filtered by called contract.
+    async fn mempool_page(
+        &self,
+        ctx: &Context<'_>,
+        page: Option<PageInput>,
+        contract: Option<String>,
+    ) -> FieldResult<MempoolPage> {
+        mempool_page(ctx, page.unwrap_or_default(), contract).await
+    }
+
     async fn mempool_txs(
         &self,
         ctx: &Context<'_>,
@@ -243,13 +277,41 @@ impl Query {
     }
 
     /// Get all finalized contract events from a specific contract id.
+    ///
+    /// If `page` is set, only the requested page of events is returned.
     #[cfg(feature = "archive")]
     async fn finalized_events(
         &self,
         ctx: &Context<'_>,
         contract_id: String,
+        page: Option<PageInput>,
+    ) -> OptResult<ContractEvents> {
+        finalized_events_by_contractid(ctx, contract_id, page).await
+    }
+
+    /// Get the finalized events of a contract emitted in a range of block
+    /// heights, optionally filtered by topic.
+    ///
+    /// Events past the retention of the archive for the contract are not
+    /// returned.
+    #[cfg(feature = "archive")]
+    async fn contract_events_in_range(
+        &self,
+        ctx: &Context<'_>,
+        contract_id: String,
+        from_height: u64,
+        to_height: u64,
+        topics: Option<Vec<String>>,
     ) -> OptResult<ContractEvents> {
-        finalized_events_by_contractid(ctx, contract_id).await
+        let topics = topics.unwrap_or_default();
+        contract_events_in_range(
+            ctx,
+            contract_id,
+            from_height,
+            to_height,
+            topics,
+        )
+        .await
     }
 
     /// Check if a given block height matches a given block hash.
@@ -8,6 +8,7 @@
 
 use super::data::ContractEvents;
 use crate::http::chain::graphql::{DBContext, OptResult};
+use crate::http::query::PageInput;
 use async_graphql::{Context, FieldError};
 use dusk_core::abi::CONTRACT_ID_BYTES;
 
@@ -40,9 +41,32 @@ pub async fn events_by_hash(
     Ok(Some(ContractEvents(serde_json::from_str(&events)?)))
 }
 
+pub async fn contract_events_in_range(
+    ctx: &Context<'_>,
+    hex_contract_id: String,
+    from_height: u64,
+    to_height: u64,
+    topics: Vec<String>,
+) -> OptResult<ContractEvents> {
+    let (_, archive) = ctx.data::<DBContext>()?;
+
+    // shallow check if contract id is valid
+    if hex_contract_id.len() != CONTRACT_ID_BYTES * 2 {
+        return Err(FieldError::new("Invalid contract_id"));
+    }
+
+    let events = archive
+        .get_contract_events(&hex_contract_id, from_height, to_height, &topics)
+        .await
+        .map_err(|e| FieldError::new(format!("Cannot fetch events: {}", e)))?;
+
+    Ok(Some(ContractEvents(serde_json::to_value(events)?)))
+}
+
 pub async fn finalized_events_by_contractid(
     ctx: &Context<'_>,
     hex_contract_id: String,
+    page: Option<PageInput>,
 ) -> OptResult<ContractEvents> {
     let (_, archive) = ctx.data::<DBContext>()?;
 
@@ -56,5 +80,17 @@ pub async fn finalized_events_by_contractid(
         .await
         .map_err(|e| FieldError::new(format!("Cannot fetch events: {}", e)))?;
 
-    Ok(Some(ContractEvents(serde_json::to_value(events)?)))
+    let events = match page {
+        None => serde_json::to_value(events)?,
+        Some(page) => {
+            let events = events
+                .into_iter()
+                .enumerate()
+                .map(|(index, event)| (index as u64, event))
+                .collect();
+            serde_json::to_value(page.collect_unsorted(events, |_| true)?)?
+        }
+    };
+
+    Ok(Some(ContractEvents(events)))
 }
@@ -7,6 +7,9 @@
 use super::*;
 use node::database::rocksdb::MD_HASH_KEY;
 use node::database::{into_array, Metadata};
+use node_data::bls::PublicKeyBytes;
+
+use crate::http::query::{PageInput, MAX_SCANNED};
 
 pub async fn block_by_height(
     ctx: &Context<'_>,
@@ -106,6 +109,61 @@ pub async fn blocks_range(
     Ok(blocks)
 }
 
+pub async fn gas_by_contract(
+    ctx: &Context<'_>,
+    from: u64,
+    to: u64,
+) -> FieldResult<Vec<ContractGas>> {
+    if to.saturating_sub(from) >= MAX_SCANNED as u64 {
+        return Err(FieldError::new(format!(
+            "range must span at most {MAX_SCANNED} heights"
+        )));
+    }
+    let (db, _) = ctx.data::<DBContext>()?;
+    let gas = db.read().await.view(|t| t.gas_by_contract(from, to))?;
+    let gas = gas
+        .into_iter()
+        .map(|(contract, gas_spent)| ContractGas {
+            contract_id: hex::encode(contract.as_bytes()),
+            gas_spent,
+        })
+        .collect();
+    Ok(gas)
+}
+
+/// Get a page of blocks, optionally filtered by generator.
+pub async fn blocks_page(
+    ctx: &Context<'_>,
+    page: PageInput,
+    generator: Option<String>,
+) -> FieldResult<BlockPage> {
+    let generator =
+        generator.map(|g| g.parse::<PublicKeyBytes>()).transpose()?;
+    let (db, _) = ctx.data::<DBContext>()?;
+    let tip = last_block(ctx).await?.header().height;
+    let from = page.cursor::<u64>()?;
+
+    let page = db.read().await.view(|t| {
+        let blocks = page.heights(tip, from).map(|height| {
+            let block = t
+                .block_hash_by_height(height)?
+                .map(|hash| t.light_block(&hash))
+                .transpose()?
+                .flatten()
+                .ok_or_else(|| anyhow::anyhow!("Cannot find block {height}"))?;
+            anyhow::Ok((height, Block::from(block)))
+        });
+
+        page.collect(blocks, |b| {
+            generator
+                .as_ref()
+                .map_or(true, |g| b.header().generator_bls_pubkey == *g)
+        })
+    })?;
+
+    Ok(BlockPage(page))
+}
+
 /// Check if a block height matches a block hash for a block
 /// (finalized **or** unfinalized).
 pub(super) async fn check_block(
@@ -7,9 +7,12 @@
 use std::ops::Deref;
 
 use async_graphql::{FieldError, FieldResult, Object, SimpleObject};
+use dusk_core::abi::ExecutionError;
 use node::database::{Ledger, LightBlock, DB};
 use serde::{Deserialize, Serialize};
 
+use crate::http::query::Page;
+
 /// Pair of (block height, block hash) of the last block and the last finalized
 /// block.
 #[derive(Serialize, Deserialize)]
@@ -45,6 +48,29 @@ impl Block {
     }
 }
 
+/// Defines the GraphQL object of a page of items
+macro_rules! page_object {
+    ($name:ident, $item:ty) => {
+        pub struct $name(pub Page<$item>);
+
+        #[Object]
+        impl $name {
+            pub async fn items(&self) -> &Vec<$item> {
+                &self.0.items
+            }
+
+            /// Cursor of the following page, if any
+            pub async fn next_cursor(&self) -> Option<&str> {
+                self.0.next_cursor.as_deref()
+            }
+        }
+    };
+}
+
+page_object!(BlockPage, Block);
+page_object!(TransactionPage, SpentTransaction);
+page_object!(MempoolPage, Transaction<'static>);
+
 pub struct Header<'a>(&'a node_data::ledger::Header);
 pub struct SpentTransaction(pub node_data::ledger::SpentTransaction);
 pub struct Transaction<'a>(TransactionData<'a>);
@@ -116,7 +142,7 @@ impl Block {
             .transactions(ctx)
             .await?
             .iter()
-            .map(|t| t.0.gas_spent * t.0.inner.gas_price())
+            .map(|t| t.0.fee_charged)
             .sum();
         Ok(fees)
     }
@@ -169,6 +195,14 @@ impl Header<'_> {
         hex::encode(self.0.txroot)
     }
 
+    pub async fn provisioners_root(&self) -> String {
+        hex::encode(self.0.provisioners_root)
+    }
+
+    pub async fn extra_data(&self) -> String {
+        self.0.extra_data_lossy()
+    }
+
     pub async fn gas_limit(&self) -> u64 {
         self.0.gas_limit
     }
@@ -193,14 +227,26 @@ impl SpentTransaction {
         inner.into()
     }
 
-    pub async fn err(&self) -> &Option<String> {
-        &self.0.err
+    pub async fn err(&self) -> Option<String> {
+        self.0.err.as_ref().map(ToString::to_string)
+    }
+
+    pub async fn err_code(&self) -> Option<u16> {
+        self.0.err.as_ref().map(ExecutionError::code)
     }
 
     pub async fn gas_spent(&self) -> u64 {
         self.0.gas_spent
     }
 
+    pub async fn gas_refunded(&self) -> u64 {
+        self.0.gas_refunded
+    }
+
+    pub async fn fee_charged(&self) -> u64 {
+        self.0.fee_charged
+    }
+
     pub async fn block_hash(
         &self,
         ctx: &async_graphql::Context<'_>,
@@ -309,3 +355,10 @@ pub struct CallData {
     fn_name: String,
     data: String,
 }
+
+/// Gas spent calling a contract in a range of blocks
+#[derive(SimpleObject)]
+pub struct ContractGas {
+    pub contract_id: String,
+    pub gas_spent: u64,
+}
@@ -4,10 +4,29 @@
 //
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
+use std::cell::Cell;
+
 use super::*;
 use node::database::rocksdb::MD_HASH_KEY;
 use node::database::{Mempool, Metadata};
 
+use crate::http::query::{CursorKey, PageInput, SortOrder, MAX_SCANNED};
+
+/// Returns the contract called by `tx`
+pub(super) fn tx_contract(tx: &node_data::ledger::Transaction) -> ContractId {
+    tx.inner
+        .call()
+        .map(|c| c.contract)
+        .unwrap_or(TRANSFER_CONTRACT)
+}
+
+pub(super) fn parse_contract(contract: &str) -> FieldResult<ContractId> {
+    let bytes: [u8; 32] = hex::decode(contract)?
+        .try_into()
+        .map_err(|_| FieldError::new("Invalid contract"))?;
+    Ok(ContractId::from(bytes))
+}
+
 pub async fn tx_by_hash(
     ctx: &Context<'_>,
     hash: String,
@@ -74,3 +93,97 @@ pub async fn mempool_by_hash<'a>(
     let tx = db.read().await.view(|db| db.mempool_tx(hash))?;
     Ok(tx.map(|t| t.into()))
 }
+
+/// Get a page of the transactions included in blocks, optionally filtered by
+/// called contract.
+pub async fn transactions_page(
+    ctx: &Context<'_>,
+    page: PageInput,
+    contract: Option<String>,
+) -> FieldResult<TransactionPage> {
+    let contract = contract.as_deref().map(parse_contract).transpose()?;
+    let (db, _) = ctx.data::<DBContext>()?;
+    let tip = last_block(ctx).await?.header().height;
+    let from = page.cursor::<(u64, u64)>()?.map(|(height, _)| height);
+    let order = page.order();
+
+    let page = db.read().await.view(|t| {
+        let scanned = Cell::new(None);
+        let heights = page
+            .heights(tip, from)
+            .take(MAX_SCANNED)
+            .inspect(|height| scanned.set(Some(*height)));
+        let txs = heights.flat_map(|height| {
+            let block_txs = || {
+                let block = t
+                    .block_hash_by_height(height)?
+                    .map(|hash| t.light_block(&hash))
+                    .transpose()?
+                    .flatten()
+                    .ok_or_else(|| {
+                        anyhow::anyhow!("Cannot find block {height}")
+                    })?;
+
+                let mut txs: Vec<anyhow::Result<_>> = vec![];
+                for (index, id) in block.transactions_ids.iter().enumerate() {
+                    let tx = t.ledger_tx(id)?.ok_or_else(|| {
+                        anyhow::anyhow!("Cannot find transaction")
+                    })?;
+                    txs.push(Ok((
+                        (height, index as u64),
+                        SpentTransaction(tx),
+                    )));
+                }
+                if order == SortOrder::Desc {
+                    txs.reverse();
+                }
+                anyhow::Ok(txs)
+            };
+            block_txs().unwrap_or_else(|e| vec![Err(e)])
+        });
+
+        let mut txs_page = page.collect(txs, |tx| {
+            contract
+                .as_ref()
+                .map_or(true, |c| tx_contract(&tx.0.inner) == *c)
+        })?;
+
+        // Resume after the last scanned height if the scan was cut short
+        if txs_page.next_cursor.is_none() {
+            let (end, index) = match order {
+                SortOrder::Asc => (tip, u64::MAX),
+                SortOrder::Desc => (0, 0),
+            };
+            if let Some(height) = scanned.get().filter(|h| *h != end) {
+                txs_page.next_cursor = Some((height, index).encode());
+            }
+        }
+        anyhow::Ok(txs_page)
+    })?;
+
+    Ok(TransactionPage(page))
+}
+
+/// Get a page of the mempool transactions, sorted by gas price and optionally
+/// filtered by called contract.
+pub async fn mempool_page(
+    ctx: &Context<'_>,
+    page: PageInput,
+    contract: Option<String>,
+) -> FieldResult<MempoolPage> {
+    let contract = contract.as_deref().map(parse_contract).transpose()?;
+    let (db, _) = ctx.data::<DBContext>()?;
+
+    let page = db.read().await.view(|db| {
+        let txs = db
+            .mempool_txs_sorted_by_fee()?
+            .filter(|tx| {
+                contract.as_ref().map_or(true, |c| tx_contract(tx) == *c)
+            })
+            .map(|tx| ((tx.gas_price(), tx.id()), Transaction::from(tx)))
+            .collect();
+        page.collect_unsorted(txs, |_| true)
+    })?;
+
+    Ok(MempoolPage(page))
+}
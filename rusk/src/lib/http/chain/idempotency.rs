@@ -0,0 +1,159 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Idempotency keys of the transaction submissions.
+//!
+//! A client can attach a key to a submission, so that it can safely retry it:
+//! resubmitting with the same key returns the transaction originally submitted
+//! with it, instead of propagating the transaction again.
+//!
+//! A key is only recorded once its transaction is admitted, so that a
+//! rejected submission can be retried with the same key. Keys are bounded in
+//! length and retained in memory for a configurable time, the oldest ones
+//! being evicted once the store is full.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::http::{DEFAULT_IDEMPOTENCY_KEYS_CAP, DEFAULT_IDEMPOTENCY_KEY_TTL};
+
+/// Maximum length of an idempotency key, in bytes
+pub const MAX_IDEMPOTENCY_KEY_LEN: usize = 64;
+
+#[derive(Default)]
+struct Keys {
+    tx_ids: HashMap<String, [u8; 32]>,
+    /// Keys in insertion order, along with their insertion time
+    order: VecDeque<(Instant, String)>,
+}
+
+/// Bounded store of the idempotency keys, expiring them after a TTL
+#[derive(Clone)]
+pub struct IdempotencyKeys {
+    keys: Arc<Mutex<Keys>>,
+    ttl: Duration,
+    capacity: usize,
+}
+
+impl Keys {
+    fn evict_oldest(&mut self) {
+        if let Some((_, key)) = self.order.pop_front() {
+            self.tx_ids.remove(&key);
+        }
+    }
+}
+
+impl Default for IdempotencyKeys {
+    fn default() -> Self {
+        Self::new(DEFAULT_IDEMPOTENCY_KEY_TTL, DEFAULT_IDEMPOTENCY_KEYS_CAP)
+    }
+}
+
+impl IdempotencyKeys {
+    pub fn new(ttl: Duration, capacity: usize) -> Self {
+        Self {
+            keys: Arc::default(),
+            ttl,
+            capacity,
+        }
+    }
+
+    /// Checks that `key` is a valid idempotency key
+    pub fn validate(key: &str) -> anyhow::Result<()> {
+        if key.is_empty() || key.len() > MAX_IDEMPOTENCY_KEY_LEN {
+            anyhow::bail!(
+                "Idempotency key must be 1 to {MAX_IDEMPOTENCY_KEY_LEN} bytes \
+                 long"
+            );
+        }
+        Ok(())
+    }
+
+    /// Returns the id of the transaction submitted with `key`, if the key is
+    /// known and not expired.
+    pub fn get(&self, key: &str) -> Option<[u8; 32]> {
+        let mut keys = self.keys.lock().expect("keys lock to be acquired");
+        self.expire(&mut keys, Instant::now());
+        keys.tx_ids.get(key).copied()
+    }
+
+    /// Returns the id of the transaction submitted with `key`, storing
+    /// `tx_id` for it if the key is unknown or expired.
+    ///
+    /// Returns `None` if `tx_id` is stored, i.e. the submission is new.
+    pub fn get_or_insert(
+        &self,
+        key: &str,
+        tx_id: [u8; 32],
+    ) -> Option<[u8; 32]> {
+        let mut keys = self.keys.lock().expect("keys lock to be acquired");
+        let now = Instant::now();
+        self.expire(&mut keys, now);
+
+        if let Some(original) = keys.tx_ids.get(key) {
+            return Some(*original);
+        }
+
+        if self.capacity > 0 {
+            while keys.order.len() >= self.capacity {
+                keys.evict_oldest();
+            }
+            keys.tx_ids.insert(key.to_string(), tx_id);
+            keys.order.push_back((now, key.to_string()));
+        }
+        None
+    }
+
+    fn expire(&self, keys: &mut Keys, now: Instant) {
+        while let Some((inserted, _)) = keys.order.front() {
+            if now.duration_since(*inserted) < self.ttl {
+                break;
+            }
+            keys.evict_oldest();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resubmission_returns_original_tx() {
+        let keys = IdempotencyKeys::new(Duration::from_secs(60), 2);
+
+        assert_eq!(keys.get("a"), None);
+        assert_eq!(keys.get_or_insert("a", [1; 32]), None);
+        assert_eq!(keys.get("a"), Some([1; 32]));
+        assert_eq!(keys.get_or_insert("a", [2; 32]), Some([1; 32]));
+        assert_eq!(keys.get_or_insert("b", [2; 32]), None);
+
+        // The oldest key is evicted once the store is full
+        assert_eq!(keys.get_or_insert("c", [3; 32]), None);
+        assert_eq!(keys.get_or_insert("a", [4; 32]), None);
+        assert_eq!(keys.get_or_insert("c", [5; 32]), Some([3; 32]));
+    }
+
+    #[test]
+    fn keys_expire() {
+        let keys = IdempotencyKeys::new(Duration::ZERO, 10);
+
+        assert_eq!(keys.get_or_insert("a", [1; 32]), None);
+        assert_eq!(keys.get("a"), None);
+        assert_eq!(keys.get_or_insert("a", [2; 32]), None);
+    }
+
+    #[test]
+    fn keys_are_bounded() {
+        assert!(IdempotencyKeys::validate("key").is_ok());
+        assert!(IdempotencyKeys::validate("").is_err());
+
+        let key = "k".repeat(MAX_IDEMPOTENCY_KEY_LEN);
+        assert!(IdempotencyKeys::validate(&key).is_ok());
+        assert!(IdempotencyKeys::validate(&format!("{key}k")).is_err());
+    }
+}
@@ -17,6 +17,7 @@ use semver::{Prerelease, Version, VersionReq};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fmt::{Display, Formatter};
+use std::net::IpAddr;
 use std::pin::Pin;
 use std::str::FromStr;
 use std::sync::mpsc;
@@ -448,6 +449,9 @@ pub struct RuesEventUri {
 
 pub const RUES_LOCATION_PREFIX: &str = "/on";
 
+/// Topic of the subscriptions to all the topics of a component
+pub const RUES_ANY_TOPIC: &str = "*";
+
 impl Display for RuesEventUri {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         let component = &self.component;
@@ -506,6 +510,11 @@ impl RuesEventUri {
         })
     }
 
+    /// Returns `true` if the subscription matches the `event`.
+    ///
+    /// A subscription without entity matches the events of every entity of
+    /// its component, and the [`RUES_ANY_TOPIC`] topic matches the events of
+    /// every topic.
     pub fn matches(&self, event: &RuesEvent) -> bool {
         let event = &event.uri;
         if self.component != event.component {
@@ -516,7 +525,7 @@ impl RuesEventUri {
             return false;
         }
 
-        if self.topic != event.topic {
+        if self.topic != RUES_ANY_TOPIC && self.topic != event.topic {
             return false;
         }
         true
@@ -531,12 +540,49 @@ pub struct RuesEvent {
     pub data: DataType,
 }
 
+/// Client a request is attributed to by the server
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum RequestClient {
+    /// A client authenticated by the gateway with its API key
+    Key(String),
+    /// A client identified by its remote address
+    Address(IpAddr),
+}
+
+impl RequestClient {
+    /// Returns the identifier of the client, which doesn't disclose its API
+    /// key
+    pub fn id(&self) -> String {
+        match self {
+            RequestClient::Key(key) => {
+                let hash = blake3::hash(key.as_bytes()).to_hex();
+                format!("key:{}", &hash[..16])
+            }
+            RequestClient::Address(addr) => format!("ip:{addr}"),
+        }
+    }
+
+    /// Returns true if the client is the one of `entry`, either its API key
+    /// or its remote address
+    pub fn matches(&self, entry: &str) -> bool {
+        match self {
+            RequestClient::Key(key) => super::gateway::key_eq(key, entry),
+            RequestClient::Address(addr) => {
+                entry.parse::<IpAddr>().is_ok_and(|entry| entry == *addr)
+            }
+        }
+    }
+}
+
 /// A RUES Dispatch request event
 #[derive(Debug)]
 pub struct RuesDispatchEvent {
     pub uri: RuesEventUri,
     pub headers: serde_json::Map<String, serde_json::Value>,
     pub data: RequestData,
+    /// Client the request is attributed to, if known. Unlike the headers,
+    /// it's not set by the client itself.
+    pub client: Option<RequestClient>,
 }
 
 impl RuesDispatchEvent {
@@ -625,7 +671,12 @@ impl RuesDispatchEvent {
             }
         };
 
-        let ret = RuesDispatchEvent { headers, data, uri };
+        let ret = RuesDispatchEvent {
+            headers,
+            data,
+            uri,
+            client: None,
+        };
 
         Ok((ret, binary_response))
     }
@@ -0,0 +1,500 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Rate-limited public RPC gateway.
+//!
+//! When enabled, every RUES request is attributed to the API key of its
+//! `Rusk-Api-Key` header, or to its remote address under the anonymous quota
+//! if no key is given. Each client is given two budgets, one for the
+//! transaction submissions and one for any other request, and each method can
+//! be further limited per client. Requests over budget are rejected with a
+//! `429 Too Many Requests` and a `Retry-After` header.
+//!
+//! Keys are loaded from the configuration, and can be listed, added and
+//! removed at runtime with the admin key, through the `gateway` RUES target.
+//! Keys added at runtime are not persisted.
+
+use std::collections::{BTreeMap, HashMap};
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use subtle::ConstantTimeEq;
+
+/// Header carrying the API key of a request
+pub const API_KEY_HEADER: &str = "Rusk-Api-Key";
+
+/// RUES target of the key management requests
+pub(crate) const GATEWAY_TARGET: &str = "gateway";
+
+pub const DEFAULT_READ_PER_MINUTE: u32 = 600;
+pub const DEFAULT_SUBMIT_PER_MINUTE: u32 = 10;
+
+/// Number of buckets above which the idle ones are pruned
+const MAX_BUCKETS: usize = 100_000;
+
+/// Requests allowed per minute to a client
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Quota {
+    /// Budget of any request but the transaction submissions
+    pub read_per_minute: u32,
+    /// Budget of the transaction submissions
+    pub submit_per_minute: u32,
+}
+
+impl Default for Quota {
+    fn default() -> Self {
+        Self {
+            read_per_minute: DEFAULT_READ_PER_MINUTE,
+            submit_per_minute: DEFAULT_SUBMIT_PER_MINUTE,
+        }
+    }
+}
+
+/// An API key along with its quota
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyConfig {
+    pub key: String,
+    #[serde(flatten)]
+    pub quota: Quota,
+}
+
+/// Gateway configuration parameters
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GatewayConfig {
+    /// Key allowed to manage the API keys. Keys can't be managed at runtime
+    /// if `None`
+    pub admin_key: Option<String>,
+    /// Quota of the requests without API key, per remote address. Such
+    /// requests are rejected if `None`
+    pub anonymous: Option<Quota>,
+    /// API keys and their quota
+    pub keys: Vec<KeyConfig>,
+    /// Requests allowed per minute to a client for a given method, identified
+    /// as `<target>/<topic>`, e.g. `transactions/simulate`
+    pub method_limits: BTreeMap<String, u32>,
+}
+
+/// Budget a request is charged to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Budget {
+    Read,
+    Submit,
+}
+
+impl Budget {
+    fn of(target: &str, topic: &str) -> Self {
+        match (target, topic) {
+            ("transactions", "propagate")
+            | ("transactions", "propagate-sponsored")
+            | ("bundles", "propagate") => Budget::Submit,
+            _ => Budget::Read,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Budget::Read => "read",
+            Budget::Submit => "submit",
+        }
+    }
+
+    fn per_minute(&self, quota: &Quota) -> u32 {
+        match self {
+            Budget::Read => quota.read_per_minute,
+            Budget::Submit => quota.submit_per_minute,
+        }
+    }
+}
+
+/// A client of the gateway
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Client {
+    Key(String),
+    Anonymous(Option<IpAddr>),
+}
+
+/// What a client's request is charged to
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Limit {
+    Budget(Budget),
+    Method(String),
+}
+
+impl Limit {
+    fn name(&self) -> String {
+        match self {
+            Limit::Budget(budget) => budget.name().to_string(),
+            Limit::Method(method) => method.clone(),
+        }
+    }
+}
+
+/// A token bucket, refilled at the rate of its per-minute limit
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn new(per_minute: u32, now: Instant) -> Self {
+        Self {
+            tokens: per_minute as f64,
+            updated: now,
+        }
+    }
+
+    /// Refills the bucket and checks a token is available, returning the
+    /// time until one is otherwise. The token is not taken.
+    fn check(&mut self, per_minute: u32, now: Instant) -> Result<(), Duration> {
+        if per_minute == 0 {
+            return Err(Duration::from_secs(60));
+        }
+        let capacity = per_minute as f64;
+        let refill_per_sec = capacity / 60.0;
+        let elapsed = now.saturating_duration_since(self.updated);
+        self.tokens = (self.tokens + elapsed.as_secs_f64() * refill_per_sec)
+            .min(capacity);
+        self.updated = now;
+
+        if self.tokens >= 1.0 {
+            Ok(())
+        } else {
+            let wait = (1.0 - self.tokens) / refill_per_sec;
+            Err(Duration::from_secs_f64(wait))
+        }
+    }
+
+    /// Takes a token previously checked to be available
+    fn take(&mut self) {
+        self.tokens -= 1.0;
+    }
+}
+
+/// Compares two keys in constant time, so that their content can't be
+/// guessed from the time taken to reject them
+pub(crate) fn key_eq(a: &str, b: &str) -> bool {
+    a.as_bytes().ct_eq(b.as_bytes()).into()
+}
+
+/// Reason a request is rejected by the gateway
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Rejection {
+    /// The API key is missing while required, or unknown
+    InvalidKey,
+    /// The request is over the budget or the method limit of the client
+    RateLimited {
+        limit: String,
+        retry_after: Duration,
+    },
+}
+
+impl Rejection {
+    /// Returns the structured error body of the rejection
+    pub(crate) fn body(&self) -> Value {
+        match self {
+            Rejection::InvalidKey => json!({
+                "error": "invalid_api_key",
+                "message": format!("missing or unknown {API_KEY_HEADER}"),
+            }),
+            Rejection::RateLimited { limit, retry_after } => json!({
+                "error": "rate_limited",
+                "limit": limit,
+                "retry_after": retry_after_secs(retry_after),
+            }),
+        }
+    }
+}
+
+/// Returns the seconds to wait before retrying, rounded up
+pub(crate) fn retry_after_secs(retry_after: &Duration) -> u64 {
+    retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0)
+}
+
+/// Gateway enforcing the quota of the API keys
+#[derive(Debug)]
+pub struct Gateway {
+    admin_key: Option<String>,
+    anonymous: Option<Quota>,
+    method_limits: BTreeMap<String, u32>,
+    keys: Mutex<HashMap<String, Quota>>,
+    buckets: Mutex<HashMap<(Client, Limit), Bucket>>,
+}
+
+impl Gateway {
+    pub fn new(config: GatewayConfig) -> Self {
+        let keys = config
+            .keys
+            .into_iter()
+            .map(|key| (key.key, key.quota))
+            .collect();
+        Self {
+            admin_key: config.admin_key,
+            anonymous: config.anonymous,
+            method_limits: config.method_limits,
+            keys: Mutex::new(keys),
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns true if `key` is the admin key
+    pub(crate) fn is_admin(&self, key: Option<&str>) -> bool {
+        matches!(
+            (&self.admin_key, key),
+            (Some(admin), Some(key)) if key_eq(admin, key)
+        )
+    }
+
+    /// Returns the quota of `key`, if known.
+    ///
+    /// Every key is compared, so that the lookup takes the same time whatever
+    /// key matches.
+    fn quota(&self, key: &str) -> Option<Quota> {
+        let keys = self.keys.lock();
+        let mut quota = None;
+        for (known, known_quota) in keys.iter() {
+            if key_eq(known, key) {
+                quota = Some(*known_quota);
+            }
+        }
+        quota
+    }
+
+    /// Charges a request to `target`/`topic` to its client, identified by
+    /// `key`, or by `remote` if no key is given
+    pub(crate) fn check(
+        &self,
+        key: Option<&str>,
+        remote: Option<IpAddr>,
+        target: &str,
+        topic: &str,
+        now: Instant,
+    ) -> Result<(), Rejection> {
+        let (client, quota) = match key {
+            Some(key) => {
+                let quota = self.quota(key).ok_or(Rejection::InvalidKey)?;
+                (Client::Key(key.to_string()), quota)
+            }
+            None => {
+                let quota = self.anonymous.ok_or(Rejection::InvalidKey)?;
+                (Client::Anonymous(remote), quota)
+            }
+        };
+
+        let budget = Budget::of(target, topic);
+        let method = format!("{target}/{topic}");
+
+        let mut buckets = self.buckets.lock();
+        if buckets.len() > MAX_BUCKETS {
+            // Idle buckets are full, hence can be recreated at will
+            buckets.retain(|_, bucket| {
+                now.saturating_duration_since(bucket.updated)
+                    < Duration::from_secs(60)
+            });
+        }
+
+        let mut limits = vec![];
+        if let Some(&per_minute) = self.method_limits.get(&method) {
+            limits.push((Limit::Method(method), per_minute));
+        }
+        limits.push((Limit::Budget(budget), budget.per_minute(&quota)));
+
+        // A request is only charged if it's within all of its limits, so that
+        // a rejected request doesn't consume any token
+        for (limit, per_minute) in &limits {
+            buckets
+                .entry((client.clone(), limit.clone()))
+                .or_insert_with(|| Bucket::new(*per_minute, now))
+                .check(*per_minute, now)
+                .map_err(|retry_after| Rejection::RateLimited {
+                    limit: limit.name(),
+                    retry_after,
+                })?;
+        }
+        for (limit, _) in limits {
+            if let Some(bucket) = buckets.get_mut(&(client.clone(), limit)) {
+                bucket.take();
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Handles a key management request of the admin
+    pub(crate) fn handle_admin(
+        &self,
+        topic: &str,
+        data: &[u8],
+    ) -> anyhow::Result<Value> {
+        match topic {
+            "keys" => {
+                let keys: BTreeMap<_, _> = self
+                    .keys
+                    .lock()
+                    .iter()
+                    .map(|(key, quota)| (key.clone(), *quota))
+                    .collect();
+                Ok(serde_json::to_value(keys)?)
+            }
+            "add-key" => {
+                let key: KeyConfig = serde_json::from_slice(data)
+                    .map_err(|e| anyhow::anyhow!("Invalid key: {e}"))?;
+                self.keys.lock().insert(key.key, key.quota);
+                Ok(Value::Null)
+            }
+            "remove-key" => {
+                let key = String::from_utf8(data.to_vec())?;
+                let key = key.trim();
+                if self.keys.lock().remove(key).is_none() {
+                    anyhow::bail!("Unknown key");
+                }
+                self.buckets.lock().retain(
+                    |(client, _), _| !matches!(client, Client::Key(k) if k == key),
+                );
+                Ok(Value::Null)
+            }
+            _ => anyhow::bail!("Unsupported"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gateway() -> Gateway {
+        Gateway::new(GatewayConfig {
+            admin_key: Some("admin".into()),
+            anonymous: None,
+            keys: vec![KeyConfig {
+                key: "key".into(),
+                quota: Quota {
+                    read_per_minute: 60,
+                    submit_per_minute: 1,
+                },
+            }],
+            method_limits: [("transactions/simulate".to_string(), 2)].into(),
+        })
+    }
+
+    #[test]
+    fn gateway_quota() {
+        let gateway = gateway();
+        let now = Instant::now();
+        let check = |key, target, topic, now| {
+            gateway.check(key, None, target, topic, now)
+        };
+
+        assert_eq!(
+            check(None, "node", "info", now),
+            Err(Rejection::InvalidKey)
+        );
+        assert_eq!(
+            check(Some("unknown"), "node", "info", now),
+            Err(Rejection::InvalidKey)
+        );
+
+        // Submissions have their own budget
+        assert!(check(Some("key"), "transactions", "propagate", now).is_ok());
+        let rejection = check(Some("key"), "transactions", "propagate", now)
+            .expect_err("submit budget to be exhausted");
+        assert_eq!(
+            rejection,
+            Rejection::RateLimited {
+                limit: "submit".into(),
+                retry_after: Duration::from_secs(60),
+            }
+        );
+        assert!(check(Some("key"), "node", "info", now).is_ok());
+
+        // Methods are limited on top of the budgets
+        assert!(check(Some("key"), "transactions", "simulate", now).is_ok());
+        assert!(check(Some("key"), "transactions", "simulate", now).is_ok());
+        assert!(matches!(
+            check(Some("key"), "transactions", "simulate", now),
+            Err(Rejection::RateLimited { limit, .. })
+                if limit == "transactions/simulate"
+        ));
+
+        // Budgets are refilled over time
+        let later = now + Duration::from_secs(60);
+        assert!(check(Some("key"), "transactions", "propagate", later).is_ok());
+    }
+
+    #[test]
+    fn gateway_rejection_is_free() {
+        let gateway = Gateway::new(GatewayConfig {
+            keys: vec![KeyConfig {
+                key: "key".into(),
+                quota: Quota {
+                    read_per_minute: 1,
+                    submit_per_minute: 1,
+                },
+            }],
+            method_limits: [("transactions/simulate".to_string(), 2)].into(),
+            ..Default::default()
+        });
+        let now = Instant::now();
+        let check = || {
+            gateway.check(Some("key"), None, "transactions", "simulate", now)
+        };
+
+        assert!(check().is_ok());
+        assert!(matches!(
+            check(),
+            Err(Rejection::RateLimited { limit, .. }) if limit == "read"
+        ));
+
+        // The method token is not taken by the request over budget
+        let method = (
+            Client::Key("key".into()),
+            Limit::Method("transactions/simulate".into()),
+        );
+        assert_eq!(gateway.buckets.lock()[&method].tokens, 1.0);
+    }
+
+    #[test]
+    fn key_comparison() {
+        assert!(key_eq("key", "key"));
+        assert!(!key_eq("key", "kez"));
+        assert!(!key_eq("key", "key2"));
+        assert!(!key_eq("", "key"));
+    }
+
+    #[test]
+    fn gateway_key_management() {
+        let gateway = gateway();
+        let now = Instant::now();
+
+        assert!(gateway.is_admin(Some("admin")));
+        assert!(!gateway.is_admin(Some("key")));
+        assert!(!gateway.is_admin(None));
+
+        let key = br#"{"key": "new", "submit_per_minute": 5}"#;
+        gateway
+            .handle_admin("add-key", key)
+            .expect("key to be added");
+        assert!(gateway.check(Some("new"), None, "a", "b", now).is_ok());
+
+        let keys = gateway.handle_admin("keys", &[]).expect("keys listed");
+        assert_eq!(keys["new"]["submit_per_minute"], 5);
+        assert_eq!(keys["new"]["read_per_minute"], DEFAULT_READ_PER_MINUTE);
+
+        gateway
+            .handle_admin("remove-key", b"new")
+            .expect("key to be removed");
+        assert_eq!(
+            gateway.check(Some("new"), None, "a", "b", now),
+            Err(Rejection::InvalidKey)
+        );
+        assert!(gateway.handle_admin("remove-key", b"new").is_err());
+    }
+}
@@ -4,6 +4,12 @@
 //
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
+mod queue;
+
+pub use queue::{
+    JobInfo, JobStatus, Priority, ProvingQueue, ProvingQueueConfig,
+};
+
 use anyhow::anyhow;
 
 use dusk_core::transfer::phoenix::Prove;
@@ -11,6 +17,9 @@ use rusk_prover::LocalProver;
 
 use super::*;
 
+/// Client the jobs submitted by an unknown client are accounted to
+const ANONYMOUS_CLIENT: &str = "anonymous";
+
 #[async_trait]
 impl HandleRequest for LocalProver {
     fn can_handle_rues(&self, request: &RuesDispatchEvent) -> bool {
@@ -30,3 +39,74 @@ impl HandleRequest for LocalProver {
         Ok(ResponseData::new(response))
     }
 }
+
+impl ProvingQueue {
+    /// Creates a queue proving the jobs with the [`LocalProver`]
+    pub fn local(config: ProvingQueueConfig) -> io::Result<Self> {
+        Self::new(config, |input| {
+            LocalProver.prove(input).map_err(|e| e.to_string())
+        })
+    }
+}
+
+/// Prover serving both the immediate proofs and the proving jobs
+pub struct ProverService {
+    queue: Arc<ProvingQueue>,
+}
+
+impl ProverService {
+    /// Creates the service, starting the workers of `queue`
+    pub fn new(queue: Arc<ProvingQueue>) -> Self {
+        queue.start();
+        Self { queue }
+    }
+}
+
+#[async_trait]
+impl HandleRequest for ProverService {
+    fn can_handle_rues(&self, request: &RuesDispatchEvent) -> bool {
+        matches!(
+            request.uri.inner(),
+            ("prover", _, "prove")
+                | ("prover", None, "submit")
+                | ("prover", Some(_), "status" | "result")
+        )
+    }
+
+    async fn handle_rues(
+        &self,
+        request: &RuesDispatchEvent,
+    ) -> anyhow::Result<ResponseData> {
+        match request.uri.inner() {
+            ("prover", _, "prove") => LocalProver.handle_rues(request).await,
+            ("prover", None, "submit") => {
+                let priority = match &request.client {
+                    Some(client) => self.queue.priority_of(client),
+                    None => Priority::Wallet,
+                };
+                let client = request
+                    .client
+                    .as_ref()
+                    .map(RequestClient::id)
+                    .unwrap_or_else(|| ANONYMOUS_CLIENT.to_string());
+                let id = self.queue.submit(
+                    &client,
+                    priority,
+                    request.data.as_bytes().to_vec(),
+                )?;
+                Ok(ResponseData::new(serde_json::json!({ "id": id })))
+            }
+            ("prover", Some(id), "status") => {
+                let info = self
+                    .queue
+                    .status(id)
+                    .ok_or_else(|| anyhow!("Unknown job"))?;
+                Ok(ResponseData::new(serde_json::to_value(info)?))
+            }
+            ("prover", Some(id), "result") => {
+                Ok(ResponseData::new(self.queue.result(id)?))
+            }
+            _ => anyhow::bail!("Unsupported"),
+        }
+    }
+}
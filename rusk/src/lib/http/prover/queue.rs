@@ -0,0 +1,581 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Queue of the delegated proving jobs.
+//!
+//! Instead of waiting for a proof on the connection, a client can submit a
+//! proving job, poll its status and fetch its proof once done. Jobs are run by
+//! a fixed number of workers:
+//! - consensus-critical jobs, submitted by the clients configured as such, e.g.
+//!   the provisioner's own wallet, are always run before the wallet ones, the
+//!   jobs of the same priority being run in submission order;
+//! - a client can't have more than a given number of jobs running, nor queue
+//!   more than a given number of jobs.
+//!
+//! If a store directory is configured, each job is persisted in it as a JSON
+//! file, so that the unfinished jobs are resumed after a restart. Finished
+//! jobs are removed once their retention time elapses.
+
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashMap};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::{fs, io};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::Notify;
+use tracing::{error, info, warn};
+
+use crate::http::RequestClient;
+
+pub const DEFAULT_PROVING_WORKERS: usize = 2;
+pub const DEFAULT_MAX_RUNNING_PER_CLIENT: usize = 1;
+pub const DEFAULT_MAX_QUEUED_PER_CLIENT: usize = 16;
+pub const DEFAULT_JOB_RETENTION: Duration = Duration::from_secs(60 * 60);
+
+/// Extension of the persisted job files
+const JOB_FILE_EXTENSION: &str = "json";
+
+/// Proving queue configuration parameters
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProvingQueueConfig {
+    /// Directory the jobs are persisted in. Jobs are kept in memory only if
+    /// `None`
+    pub store_path: Option<PathBuf>,
+    /// Number of jobs run concurrently
+    pub workers: usize,
+    /// Number of jobs of a client run concurrently
+    pub max_running_per_client: usize,
+    /// Number of unfinished jobs a client can have
+    pub max_queued_per_client: usize,
+    /// Time a finished job is kept for
+    #[serde(with = "humantime_serde")]
+    pub job_retention: Duration,
+    /// API keys or remote addresses of the clients whose jobs are
+    /// consensus-critical
+    pub consensus_clients: Vec<String>,
+}
+
+impl Default for ProvingQueueConfig {
+    fn default() -> Self {
+        Self {
+            store_path: None,
+            workers: DEFAULT_PROVING_WORKERS,
+            max_running_per_client: DEFAULT_MAX_RUNNING_PER_CLIENT,
+            max_queued_per_client: DEFAULT_MAX_QUEUED_PER_CLIENT,
+            job_retention: DEFAULT_JOB_RETENTION,
+            consensus_clients: vec![],
+        }
+    }
+}
+
+/// Priority of a proving job
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Priority {
+    /// Proofs requested by the wallets
+    Wallet,
+    /// Proofs the provisioner depends on to take part in the consensus
+    Consensus,
+}
+
+/// Status of a proving job
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Done,
+    Failed,
+}
+
+impl JobStatus {
+    fn is_finished(&self) -> bool {
+        matches!(self, JobStatus::Done | JobStatus::Failed)
+    }
+}
+
+/// Public information about a proving job
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct JobInfo {
+    pub id: String,
+    pub priority: Priority,
+    pub status: JobStatus,
+    /// Number of jobs to be run before this one, if queued
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub position: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[serde_with::serde_as]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Job {
+    id: String,
+    client: String,
+    priority: Priority,
+    status: JobStatus,
+    /// Submission order, used to break the ties between jobs of the same
+    /// priority
+    seq: u64,
+    /// UNIX time the job finished at, in seconds
+    finished_at: Option<u64>,
+    #[serde_as(as = "serde_with::hex::Hex")]
+    input: Vec<u8>,
+    #[serde_as(as = "Option<serde_with::hex::Hex>")]
+    proof: Option<Vec<u8>>,
+    error: Option<String>,
+}
+
+/// A job waiting to be run
+#[derive(Debug, PartialEq, Eq)]
+struct Queued {
+    priority: Priority,
+    seq: Reverse<u64>,
+    id: String,
+}
+
+impl Ord for Queued {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.priority, self.seq).cmp(&(other.priority, other.seq))
+    }
+}
+
+impl PartialOrd for Queued {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[derive(Default)]
+struct State {
+    jobs: HashMap<String, Job>,
+    queue: BinaryHeap<Queued>,
+    /// Number of running jobs per client
+    running: HashMap<String, usize>,
+    next_seq: u64,
+}
+
+impl State {
+    fn enqueue(&mut self, job: &Job) {
+        self.queue.push(Queued {
+            priority: job.priority,
+            seq: Reverse(job.seq),
+            id: job.id.clone(),
+        });
+    }
+
+    /// Takes the job to run next, skipping the ones of the clients already
+    /// running as many jobs as allowed
+    fn take_next(&mut self, max_running_per_client: usize) -> Option<Job> {
+        let mut skipped = vec![];
+        let mut next = None;
+        while let Some(queued) = self.queue.pop() {
+            let Some(job) = self.jobs.get_mut(&queued.id) else {
+                continue;
+            };
+            let running = self.running.entry(job.client.clone()).or_default();
+            if *running >= max_running_per_client {
+                skipped.push(queued);
+                continue;
+            }
+            *running += 1;
+            job.status = JobStatus::Running;
+            next = Some(job.clone());
+            break;
+        }
+        self.queue.extend(skipped);
+        next
+    }
+
+    fn position(&self, job: &Job) -> Option<usize> {
+        if job.status != JobStatus::Queued {
+            return None;
+        }
+        let ahead = self
+            .queue
+            .iter()
+            .filter(|q| (q.priority, q.seq) > (job.priority, Reverse(job.seq)))
+            .count();
+        Some(ahead)
+    }
+}
+
+type ProveFn = dyn Fn(&[u8]) -> Result<Vec<u8>, String> + Send + Sync;
+
+/// Queue of the proving jobs, along with the workers running them
+pub struct ProvingQueue {
+    config: ProvingQueueConfig,
+    prove: Box<ProveFn>,
+    state: Mutex<State>,
+    notify: Notify,
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+impl ProvingQueue {
+    /// Creates a queue running its jobs with `prove`, resuming the unfinished
+    /// jobs of the configured store
+    pub fn new<F>(config: ProvingQueueConfig, prove: F) -> io::Result<Self>
+    where
+        F: Fn(&[u8]) -> Result<Vec<u8>, String> + Send + Sync + 'static,
+    {
+        let mut state = State::default();
+
+        if let Some(path) = &config.store_path {
+            fs::create_dir_all(path)?;
+            for entry in fs::read_dir(path)? {
+                let path = entry?.path();
+                if path.extension().and_then(|e| e.to_str())
+                    != Some(JOB_FILE_EXTENSION)
+                {
+                    continue;
+                }
+                let mut job: Job =
+                    match serde_json::from_slice(&fs::read(&path)?) {
+                        Ok(job) => job,
+                        Err(e) => {
+                            warn!("Skipping invalid job {path:?}: {e}");
+                            continue;
+                        }
+                    };
+                if !job.status.is_finished() {
+                    job.status = JobStatus::Queued;
+                    state.enqueue(&job);
+                }
+                state.next_seq = state.next_seq.max(job.seq + 1);
+                state.jobs.insert(job.id.clone(), job);
+            }
+            info!("Loaded {} proving jobs", state.jobs.len());
+        }
+
+        Ok(Self {
+            config,
+            prove: Box::new(prove),
+            state: Mutex::new(state),
+            notify: Notify::new(),
+        })
+    }
+
+    /// Spawns the workers running the jobs
+    pub fn start(self: &Arc<Self>) {
+        for _ in 0..self.config.workers.max(1) {
+            let queue = self.clone();
+            tokio::spawn(async move { queue.run_worker().await });
+        }
+    }
+
+    async fn run_worker(self: Arc<Self>) {
+        loop {
+            let notified = self.notify.notified();
+            let Some(job) = self.take_next() else {
+                notified.await;
+                continue;
+            };
+
+            let queue = self.clone();
+            let input = job.input.clone();
+            let res =
+                tokio::task::spawn_blocking(move || (queue.prove)(&input))
+                    .await
+                    .unwrap_or_else(|e| {
+                        Err(format!("Proving task failed: {e}"))
+                    });
+            self.finish(&job.id, res);
+
+            // A job of the same client may be runnable now
+            self.notify.notify_one();
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state
+            .lock()
+            .expect("proving queue lock to be acquired")
+    }
+
+    fn take_next(&self) -> Option<Job> {
+        let job = self
+            .lock()
+            .take_next(self.config.max_running_per_client.max(1))?;
+        self.persist(&job);
+        Some(job)
+    }
+
+    fn finish(&self, id: &str, res: Result<Vec<u8>, String>) {
+        let job = {
+            let mut state = self.lock();
+            let Some(job) = state.jobs.get_mut(id) else {
+                return;
+            };
+            match res {
+                Ok(proof) => {
+                    job.status = JobStatus::Done;
+                    job.proof = Some(proof);
+                }
+                Err(e) => {
+                    job.status = JobStatus::Failed;
+                    job.error = Some(e);
+                }
+            }
+            job.input.clear();
+            job.finished_at = Some(unix_now());
+            let job = job.clone();
+            if let Some(running) = state.running.get_mut(&job.client) {
+                *running = running.saturating_sub(1);
+            }
+            job
+        };
+        self.persist(&job);
+    }
+
+    /// Returns the priority of the jobs submitted by `client`
+    pub fn priority_of(&self, client: &RequestClient) -> Priority {
+        let consensus = &self.config.consensus_clients;
+        if consensus.iter().any(|entry| client.matches(entry)) {
+            Priority::Consensus
+        } else {
+            Priority::Wallet
+        }
+    }
+
+    /// Submits a job proving `input` on behalf of `client`, returning its id
+    pub fn submit(
+        &self,
+        client: &str,
+        priority: Priority,
+        input: Vec<u8>,
+    ) -> anyhow::Result<String> {
+        let job = {
+            let mut state = self.lock();
+            self.prune(&mut state);
+
+            let unfinished = state
+                .jobs
+                .values()
+                .filter(|j| j.client == client && !j.status.is_finished())
+                .count();
+            // Consensus-critical jobs are never turned down
+            if priority != Priority::Consensus
+                && unfinished >= self.config.max_queued_per_client
+            {
+                anyhow::bail!(
+                    "Too many unfinished jobs, max {}",
+                    self.config.max_queued_per_client
+                );
+            }
+
+            let mut id = hex::encode(rand::random::<[u8; 16]>());
+            while state.jobs.contains_key(&id) {
+                id = hex::encode(rand::random::<[u8; 16]>());
+            }
+
+            let job = Job {
+                id: id.clone(),
+                client: client.to_string(),
+                priority,
+                status: JobStatus::Queued,
+                seq: state.next_seq,
+                finished_at: None,
+                input,
+                proof: None,
+                error: None,
+            };
+            state.next_seq += 1;
+            state.enqueue(&job);
+            state.jobs.insert(id, job.clone());
+            job
+        };
+        self.persist(&job);
+        self.notify.notify_one();
+        Ok(job.id)
+    }
+
+    /// Returns the information about the job `id`
+    pub fn status(&self, id: &str) -> Option<JobInfo> {
+        let state = self.lock();
+        let job = state.jobs.get(id)?;
+        Some(JobInfo {
+            id: job.id.clone(),
+            priority: job.priority,
+            status: job.status,
+            position: state.position(job),
+            error: job.error.clone(),
+        })
+    }
+
+    /// Returns the proof of the job `id`, failing if it's not done
+    pub fn result(&self, id: &str) -> anyhow::Result<Vec<u8>> {
+        let state = self.lock();
+        let job = state
+            .jobs
+            .get(id)
+            .ok_or_else(|| anyhow::anyhow!("Unknown job"))?;
+        match (&job.status, &job.proof, &job.error) {
+            (JobStatus::Done, Some(proof), _) => Ok(proof.clone()),
+            (JobStatus::Failed, _, Some(e)) => anyhow::bail!("Job failed: {e}"),
+            _ => anyhow::bail!("Job not finished"),
+        }
+    }
+
+    /// Removes the finished jobs older than the retention time
+    fn prune(&self, state: &mut State) {
+        let retention = self.config.job_retention.as_secs();
+        let now = unix_now();
+        let expired: Vec<_> = state
+            .jobs
+            .values()
+            .filter(|j| {
+                j.finished_at
+                    .is_some_and(|at| now.saturating_sub(at) >= retention)
+            })
+            .map(|j| j.id.clone())
+            .collect();
+        for id in expired {
+            state.jobs.remove(&id);
+            if let Some(path) = self.job_path(&id) {
+                let _ = fs::remove_file(path);
+            }
+        }
+    }
+
+    fn job_path(&self, id: &str) -> Option<PathBuf> {
+        let path = self.config.store_path.as_ref()?;
+        Some(path.join(id).with_extension(JOB_FILE_EXTENSION))
+    }
+
+    fn persist(&self, job: &Job) {
+        let Some(path) = self.job_path(&job.id) else {
+            return;
+        };
+        let res = serde_json::to_vec(job)
+            .map_err(io::Error::from)
+            .and_then(|bytes| fs::write(&path, bytes));
+        if let Err(e) = res {
+            error!("Failed persisting proving job {}: {e}", job.id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn queue(config: ProvingQueueConfig) -> ProvingQueue {
+        ProvingQueue::new(config, |input| match input {
+            b"fail" => Err("invalid circuit".into()),
+            input => Ok(input.iter().rev().copied().collect()),
+        })
+        .expect("queue to be created")
+    }
+
+    #[test]
+    fn jobs_are_prioritized() {
+        let queue = queue(ProvingQueueConfig {
+            max_running_per_client: 1,
+            ..Default::default()
+        });
+
+        let a1 = queue.submit("a", Priority::Wallet, vec![1]).unwrap();
+        let a2 = queue.submit("a", Priority::Wallet, vec![2]).unwrap();
+        let b1 = queue.submit("b", Priority::Wallet, vec![3]).unwrap();
+        let node = queue.submit("node", Priority::Consensus, vec![4]).unwrap();
+
+        assert_eq!(queue.status(&node).unwrap().position, Some(0));
+        assert_eq!(queue.status(&b1).unwrap().position, Some(3));
+
+        // Consensus jobs are run first, while a client's second job waits
+        // for its first one to finish
+        let order: Vec<_> = (0..3).filter_map(|_| queue.take_next()).collect();
+        let order: Vec<_> = order.iter().map(|j| j.id.clone()).collect();
+        assert_eq!(order, vec![node.clone(), a1.clone(), b1.clone()]);
+        assert!(queue.take_next().is_none());
+
+        queue.finish(&a1, Ok(vec![1]));
+        assert_eq!(queue.take_next().map(|j| j.id), Some(a2));
+        assert_eq!(queue.result(&a1).unwrap(), vec![1]);
+        assert!(queue.result(&b1).is_err());
+    }
+
+    #[test]
+    fn consensus_clients() {
+        let queue = queue(ProvingQueueConfig {
+            consensus_clients: vec!["127.0.0.1".into(), "provisioner".into()],
+            ..Default::default()
+        });
+
+        let local = RequestClient::Address([127, 0, 0, 1].into());
+        let remote = RequestClient::Address([10, 0, 0, 1].into());
+        let key = RequestClient::Key("provisioner".into());
+        let other_key = RequestClient::Key("wallet".into());
+        assert_eq!(queue.priority_of(&local), Priority::Consensus);
+        assert_eq!(queue.priority_of(&key), Priority::Consensus);
+        assert_eq!(queue.priority_of(&remote), Priority::Wallet);
+        assert_eq!(queue.priority_of(&other_key), Priority::Wallet);
+
+        // API keys are not disclosed by the client ids
+        assert!(!key.id().contains("provisioner"));
+        assert_ne!(key.id(), other_key.id());
+        assert_eq!(local.id(), "ip:127.0.0.1");
+    }
+
+    #[test]
+    fn client_queue_is_bounded() {
+        let queue = queue(ProvingQueueConfig {
+            max_queued_per_client: 1,
+            ..Default::default()
+        });
+
+        queue.submit("a", Priority::Wallet, vec![1]).unwrap();
+        assert!(queue.submit("a", Priority::Wallet, vec![2]).is_err());
+        assert!(queue.submit("b", Priority::Wallet, vec![2]).is_ok());
+    }
+
+    #[tokio::test]
+    async fn jobs_are_resumed() {
+        let dir = tempfile::tempdir().expect("tempdir to be created");
+        let config = ProvingQueueConfig {
+            store_path: Some(dir.path().to_path_buf()),
+            ..Default::default()
+        };
+
+        let id = {
+            let queue = queue(config.clone());
+            let id = queue.submit("a", Priority::Wallet, vec![1, 2]).unwrap();
+            // The job is interrupted while running
+            queue.take_next().expect("job to be run");
+            id
+        };
+
+        let queue = Arc::new(queue(config));
+        assert_eq!(queue.status(&id).unwrap().status, JobStatus::Queued);
+        queue.start();
+
+        let failed = queue.submit("b", Priority::Wallet, b"fail".to_vec());
+        let failed = failed.unwrap();
+        for _ in 0..100 {
+            if queue.status(&id).unwrap().status.is_finished()
+                && queue.status(&failed).unwrap().status.is_finished()
+            {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(queue.result(&id).unwrap(), vec![2, 1]);
+        let info = queue.status(&failed).unwrap();
+        assert_eq!(info.status, JobStatus::Failed);
+        assert_eq!(info.error.as_deref(), Some("invalid circuit"));
+    }
+}
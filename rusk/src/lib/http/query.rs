@@ -0,0 +1,312 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Pagination, ordering and filtering shared by the query APIs.
+//!
+//! A paginated query takes a [`PageInput`] and returns a [`Page`]. Every item
+//! is keyed by a [`CursorKey`], which is both the key the items are sorted by
+//! and the opaque cursor handed back to fetch the following page.
+//!
+//! At most [`MAX_SCANNED`] items are scanned to fill a page. A page may then
+//! be returned with fewer items than requested, along with the cursor to
+//! resume the scan from.
+
+use async_graphql::{Enum, InputObject};
+use serde::{Deserialize, Serialize};
+
+/// Number of items of a page when no limit is given
+pub const DEFAULT_PAGE_SIZE: usize = 50;
+
+/// Maximum number of items of a page
+pub const MAX_PAGE_SIZE: usize = 1000;
+
+/// Maximum number of items, or of heights, scanned to fill a page
+pub const MAX_SCANNED: usize = 10_000;
+
+const KEY_SEPARATOR: char = '.';
+
+#[derive(
+    Enum, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, Hash,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    Asc,
+    #[default]
+    Desc,
+}
+
+/// Pagination and ordering of a list query
+#[derive(InputObject, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct PageInput {
+    /// Cursor returned along with the previous page
+    pub cursor: Option<String>,
+    /// Maximum number of items to return
+    pub limit: Option<u64>,
+    pub order: Option<SortOrder>,
+}
+
+/// A page of items, along with the cursor of the following page, if any
+#[derive(Serialize, Debug)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<String>,
+}
+
+/// A key items are sorted and paginated by
+pub trait CursorKey: Ord + Sized {
+    fn encode(&self) -> String;
+    fn decode(s: &str) -> anyhow::Result<Self>;
+}
+
+impl CursorKey for u64 {
+    fn encode(&self) -> String {
+        self.to_string()
+    }
+
+    fn decode(s: &str) -> anyhow::Result<Self> {
+        Ok(s.parse()?)
+    }
+}
+
+impl CursorKey for String {
+    fn encode(&self) -> String {
+        self.clone()
+    }
+
+    fn decode(s: &str) -> anyhow::Result<Self> {
+        Ok(s.to_string())
+    }
+}
+
+impl CursorKey for [u8; 32] {
+    fn encode(&self) -> String {
+        hex::encode(self)
+    }
+
+    fn decode(s: &str) -> anyhow::Result<Self> {
+        hex::decode(s)?
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("invalid cursor length"))
+    }
+}
+
+impl<A: CursorKey, B: CursorKey> CursorKey for (A, B) {
+    fn encode(&self) -> String {
+        format!("{}{KEY_SEPARATOR}{}", self.0.encode(), self.1.encode())
+    }
+
+    fn decode(s: &str) -> anyhow::Result<Self> {
+        let (a, b) = s
+            .split_once(KEY_SEPARATOR)
+            .ok_or_else(|| anyhow::anyhow!("invalid cursor"))?;
+        Ok((A::decode(a)?, B::decode(b)?))
+    }
+}
+
+impl PageInput {
+    pub fn order(&self) -> SortOrder {
+        self.order.unwrap_or_default()
+    }
+
+    pub fn limit(&self) -> anyhow::Result<usize> {
+        match self.limit {
+            None => Ok(DEFAULT_PAGE_SIZE),
+            Some(0) => Err(anyhow::anyhow!("limit must be positive")),
+            Some(limit) if limit as usize > MAX_PAGE_SIZE => {
+                Err(anyhow::anyhow!("limit must not exceed {MAX_PAGE_SIZE}"))
+            }
+            Some(limit) => Ok(limit as usize),
+        }
+    }
+
+    pub fn cursor<K: CursorKey>(&self) -> anyhow::Result<Option<K>> {
+        self.cursor.as_deref().map(K::decode).transpose()
+    }
+
+    /// Returns the heights to scan, in the page order, for items stored up to
+    /// height `tip`, resuming from height `from` (included) if any.
+    pub fn heights(
+        &self,
+        tip: u64,
+        from: Option<u64>,
+    ) -> Box<dyn Iterator<Item = u64>> {
+        match (self.order(), from) {
+            (SortOrder::Asc, None) => Box::new(0..=tip),
+            (SortOrder::Asc, Some(from)) => Box::new(from..=tip),
+            (SortOrder::Desc, None) => Box::new((0..=tip).rev()),
+            (SortOrder::Desc, Some(from)) => {
+                Box::new((0..=from.min(tip)).rev())
+            }
+        }
+    }
+
+    /// Collects the page of `items` following the cursor, keeping only the
+    /// items matching `filter`.
+    ///
+    /// `items` must be sorted by key in the page order. Items are consumed
+    /// lazily, only until the page is full or [`MAX_SCANNED`] of them are
+    /// scanned.
+    pub fn collect<K, T, I, F>(
+        &self,
+        items: I,
+        filter: F,
+    ) -> anyhow::Result<Page<T>>
+    where
+        K: CursorKey,
+        I: IntoIterator<Item = anyhow::Result<(K, T)>>,
+        F: Fn(&T) -> bool,
+    {
+        let limit = self.limit()?;
+        let order = self.order();
+        let cursor = self.cursor::<K>()?;
+
+        let mut page = vec![];
+        let mut last = None;
+        // Last item filtered out since the last one of the page
+        let mut skipped = None;
+        let mut scanned = 0;
+        for item in items {
+            let (key, item) = item?;
+            let after_cursor = match (&cursor, order) {
+                (None, _) => true,
+                (Some(cursor), SortOrder::Asc) => key > *cursor,
+                (Some(cursor), SortOrder::Desc) => key < *cursor,
+            };
+            if !after_cursor {
+                continue;
+            }
+            if scanned == MAX_SCANNED {
+                return Ok(Page {
+                    items: page,
+                    next_cursor: skipped.or(last).as_ref().map(K::encode),
+                });
+            }
+            scanned += 1;
+            if !filter(&item) {
+                skipped = Some(key);
+                continue;
+            }
+            if page.len() == limit {
+                return Ok(Page {
+                    items: page,
+                    next_cursor: last.as_ref().map(K::encode),
+                });
+            }
+            skipped = None;
+            last = Some(key);
+            page.push(item);
+        }
+
+        Ok(Page {
+            items: page,
+            next_cursor: None,
+        })
+    }
+
+    /// Same as [`PageInput::collect`], for `items` in any order
+    pub fn collect_unsorted<K, T, F>(
+        &self,
+        mut items: Vec<(K, T)>,
+        filter: F,
+    ) -> anyhow::Result<Page<T>>
+    where
+        K: CursorKey,
+        F: Fn(&T) -> bool,
+    {
+        match self.order() {
+            SortOrder::Asc => items.sort_by(|(a, _), (b, _)| a.cmp(b)),
+            SortOrder::Desc => items.sort_by(|(a, _), (b, _)| b.cmp(a)),
+        }
+        self.collect(items.into_iter().map(Ok), filter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(cursor: Option<String>, limit: u64, order: SortOrder) -> PageInput {
+        PageInput {
+            cursor,
+            limit: Some(limit),
+            order: Some(order),
+        }
+    }
+
+    #[test]
+    fn paginate_sorted_items() {
+        let items = || (0..10u64).map(|h| Ok((h, h)));
+
+        let first = page(None, 4, SortOrder::Asc)
+            .collect(items(), |h| h % 2 == 0)
+            .unwrap();
+        assert_eq!(first.items, vec![0, 2, 4, 6]);
+        assert_eq!(first.next_cursor.as_deref(), Some("6"));
+
+        let second = page(first.next_cursor, 4, SortOrder::Asc)
+            .collect(items(), |h| h % 2 == 0)
+            .unwrap();
+        assert_eq!(second.items, vec![8]);
+        assert_eq!(second.next_cursor, None);
+
+        let desc = page(Some("5".into()), 2, SortOrder::Desc);
+        let heights: Vec<_> = desc.heights(9, Some(5)).collect();
+        assert_eq!(heights, vec![5, 4, 3, 2, 1, 0]);
+        let desc = desc
+            .collect(heights.into_iter().map(|h| Ok((h, h))), |_| true)
+            .unwrap();
+        assert_eq!(desc.items, vec![4, 3]);
+        assert_eq!(desc.next_cursor.as_deref(), Some("3"));
+    }
+
+    #[test]
+    fn paginate_unsorted_items() {
+        let items = vec![((1u64, 7u64), 'b'), ((3, 0), 'c'), ((1, 2), 'a')];
+
+        let first = page(None, 2, SortOrder::Asc)
+            .collect_unsorted(items.clone(), |_| true)
+            .unwrap();
+        assert_eq!(first.items, vec!['a', 'b']);
+        assert_eq!(first.next_cursor.as_deref(), Some("1.7"));
+
+        let second = page(first.next_cursor, 2, SortOrder::Asc)
+            .collect_unsorted(items, |_| true)
+            .unwrap();
+        assert_eq!(second.items, vec!['c']);
+        assert_eq!(second.next_cursor, None);
+    }
+
+    #[test]
+    fn bounded_scan() {
+        let items = || (0..MAX_SCANNED as u64 * 2).map(|h| Ok((h, h)));
+        let rare = |h: &u64| *h == 1 || *h > MAX_SCANNED as u64 + 5;
+
+        let first = page(None, 4, SortOrder::Asc)
+            .collect(items(), rare)
+            .unwrap();
+        assert_eq!(first.items, vec![1]);
+        let cursor = MAX_SCANNED as u64 - 1;
+        assert_eq!(first.next_cursor, Some(cursor.to_string()));
+
+        let second = page(first.next_cursor, 4, SortOrder::Asc)
+            .collect(items(), rare)
+            .unwrap();
+        let next = MAX_SCANNED as u64 + 6;
+        assert_eq!(second.items, vec![next, next + 1, next + 2, next + 3]);
+    }
+
+    #[test]
+    fn invalid_page() {
+        assert!(page(None, 0, SortOrder::Asc).limit().is_err());
+        assert!(page(None, MAX_PAGE_SIZE as u64 + 1, SortOrder::Asc)
+            .limit()
+            .is_err());
+        assert!(page(Some("x".into()), 1, SortOrder::Asc)
+            .cursor::<u64>()
+            .is_err());
+    }
+}
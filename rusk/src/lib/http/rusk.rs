@@ -8,17 +8,34 @@ use super::*;
 
 use dusk_bytes::{DeserializableSlice, Serializable};
 use dusk_core::abi::ContractId;
+use dusk_core::encoding::Base58Check;
 use dusk_core::signatures::bls::PublicKey as BlsPublicKey;
 use dusk_core::stake::StakeFundOwner;
 use rusk_profile::CRS_17_HASH;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use serde_json::json;
-use std::sync::mpsc;
+use std::sync::{mpsc, LazyLock};
 use std::thread;
+use std::time::{Duration, Instant};
 
+use dusk_vm::proof::PageProof;
+use parking_lot::Mutex;
+
+use crate::http::query::PageInput;
 use crate::node::Rusk;
 
 const RUSK_FEEDER_HEADER: &str = "Rusk-Feeder";
+const RUSK_STATE_ROOT_HEADER: &str = "Rusk-State-Root";
+
+/// Maximum number of bytes of contract memory proven by a request
+const MAX_STATE_PROOF_LEN: usize = 64 * 1024;
+
+/// Maximum number of proofs served per second, to all the clients
+const MAX_PROOFS_PER_SECOND: u32 = 10;
+
+/// Start of the current second of proofs, and the proofs served during it
+static PROOFS_SERVED: LazyLock<Mutex<(Instant, u32)>> =
+    LazyLock::new(|| Mutex::new((Instant::now(), 0)));
 
 #[async_trait]
 impl HandleRequest for Rusk {
@@ -28,7 +45,9 @@ impl HandleRequest for Rusk {
             ("contracts", Some(_), _) => true,
             ("node", _, "provisioners") => true,
             ("account", Some(_), "status") => true,
+            ("account", Some(_), "stake-proof") => true,
             ("node", _, "crs") => true,
+            ("node", _, "contract-profiles") => true,
             _ => false,
         }
     }
@@ -37,15 +56,40 @@ impl HandleRequest for Rusk {
         request: &RuesDispatchEvent,
     ) -> anyhow::Result<ResponseData> {
         match request.uri.inner() {
+            ("contracts", Some(contract_id), "state-proof") => self
+                .get_state_proof(
+                    contract_id,
+                    request.data.as_bytes(),
+                    request.header(RUSK_STATE_ROOT_HEADER),
+                ),
             ("contracts", Some(contract_id), method) => {
                 let feeder = request.header(RUSK_FEEDER_HEADER).is_some();
                 let data = request.data.as_bytes();
-                self.handle_contract_query(contract_id, method, data, feeder)
+                match request.header(RUSK_STATE_ROOT_HEADER) {
+                    Some(root) => self.handle_historical_query(
+                        contract_id,
+                        method,
+                        data,
+                        root,
+                    ),
+                    None => self.handle_contract_query(
+                        contract_id,
+                        method,
+                        data,
+                        feeder,
+                    ),
+                }
+            }
+            ("node", _, "provisioners") => {
+                self.get_provisioners(request.data.as_bytes())
             }
-            ("node", _, "provisioners") => self.get_provisioners(),
 
             ("account", Some(pk), "status") => self.get_account(pk),
+            ("account", Some(pk), "stake-proof") => {
+                self.get_stake_proof(pk, request.header(RUSK_STATE_ROOT_HEADER))
+            }
             ("node", _, "crs") => self.get_crs(),
+            ("node", _, "contract-profiles") => self.get_contract_profiles(),
             _ => Err(anyhow::anyhow!("Unsupported")),
         }
     }
@@ -59,12 +103,7 @@ impl Rusk {
         data: &[u8],
         feeder: bool,
     ) -> anyhow::Result<ResponseData> {
-        let contract_bytes = hex::decode(contract)?;
-
-        let contract_bytes = contract_bytes
-            .try_into()
-            .map_err(|_| anyhow::anyhow!("Invalid contract bytes"))?;
-        let contract_id = ContractId::from_bytes(contract_bytes);
+        let contract_id = parse_contract_id(contract)?;
         let fn_name = topic.to_string();
         let data = data.to_vec();
         if feeder {
@@ -85,7 +124,94 @@ impl Rusk {
         }
     }
 
-    fn get_provisioners(&self) -> anyhow::Result<ResponseData> {
+    /// Queries a contract against the state at the hex encoded `root`.
+    ///
+    /// Only the states of the non-finalized blocks and of the last finalized
+    /// one are available, the older ones being merged.
+    fn handle_historical_query(
+        &self,
+        contract: &str,
+        topic: &str,
+        data: &[u8],
+        root: &serde_json::Value,
+    ) -> anyhow::Result<ResponseData> {
+        let contract_id = parse_contract_id(contract)?;
+        let root = root
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Invalid state root header"))?;
+        let root: [u8; 32] = hex::decode(root)?
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("Invalid state root"))?;
+
+        let data = self
+            .query_raw_at(root, contract_id, topic, data.to_vec())
+            .map_err(|e| anyhow::anyhow!("{e}"))?;
+        Ok(ResponseData::new(data))
+    }
+
+    /// Returns the proofs of inclusion of a range of the memory of a contract
+    /// in a state root, given a JSON encoded [`StateProofRequest`] in `data`.
+    ///
+    /// The proofs are against the hex encoded `root`, if given, or against
+    /// the current state root otherwise. Each memory page is returned along
+    /// with its rkyv serialized opening. At most [`MAX_STATE_PROOF_LEN`]
+    /// bytes are proven by a request.
+    fn get_state_proof(
+        &self,
+        contract: &str,
+        data: &[u8],
+        root: Option<&serde_json::Value>,
+    ) -> anyhow::Result<ResponseData> {
+        let contract_id = parse_contract_id(contract)?;
+        let request: StateProofRequest = serde_json::from_slice(data)?;
+        if request.len > MAX_STATE_PROOF_LEN {
+            anyhow::bail!("Cannot prove more than {MAX_STATE_PROOF_LEN} bytes");
+        }
+        let root = parse_state_root(root)?;
+        acquire_proof()?;
+
+        let (root, proofs) = self
+            .state_proofs(root, contract_id, request.offset, request.len)
+            .map_err(|e| anyhow::anyhow!("{e}"))?
+            .ok_or_else(|| anyhow::anyhow!("Contract not found"))?;
+
+        Ok(ResponseData::new(json!({
+            "state_root": hex::encode(root),
+            "pages": pages_json(proofs)?,
+        })))
+    }
+
+    /// Returns the proof of the stake of the account `pk` in a state root.
+    ///
+    /// The proof is against the hex encoded `root`, if given, or against the
+    /// current state root otherwise. It is made of the memory pages of the
+    /// stake contract holding the stake entry, each along with its rkyv
+    /// serialized opening, and of the location of the entry in the memory.
+    fn get_stake_proof(
+        &self,
+        pk: &str,
+        root: Option<&serde_json::Value>,
+    ) -> anyhow::Result<ResponseData> {
+        let pk = parse_account(pk)?;
+        let root = parse_state_root(root)?;
+        acquire_proof()?;
+
+        let proof = self
+            .stake_proof(root, &pk)
+            .map_err(|e| anyhow::anyhow!("{e}"))?
+            .ok_or_else(|| anyhow::anyhow!("Stake not found"))?;
+
+        Ok(ResponseData::new(json!({
+            "state_root": hex::encode(proof.state_root),
+            "offset": proof.offset,
+            "len": proof.len,
+            "pages": pages_json(proof.pages)?,
+        })))
+    }
+
+    /// Returns the provisioners, or the page of provisioners requested by a
+    /// JSON encoded [`PageInput`] in `data`, sorted by key.
+    fn get_provisioners(&self, data: &[u8]) -> anyhow::Result<ResponseData> {
         let prov: Vec<_> = self
             .provisioners(None)
             .expect("Cannot query state for provisioners")
@@ -107,15 +233,18 @@ impl Rusk {
             })
             .collect();
 
-        Ok(ResponseData::new(serde_json::to_value(prov)?))
+        if data.is_empty() {
+            return Ok(ResponseData::new(serde_json::to_value(prov)?));
+        }
+
+        let page: PageInput = serde_json::from_slice(data)?;
+        let prov = prov.into_iter().map(|p| (p.key.clone(), p)).collect();
+        let page = page.collect_unsorted(prov, |_| true)?;
+        Ok(ResponseData::new(serde_json::to_value(page)?))
     }
 
     fn get_account(&self, pk: &str) -> anyhow::Result<ResponseData> {
-        let pk = bs58::decode(pk)
-            .into_vec()
-            .map_err(|_| anyhow::anyhow!("Invalid bs58 account"))?;
-        let pk = BlsPublicKey::from_slice(&pk)
-            .map_err(|_| anyhow::anyhow!("Invalid bls account"))?;
+        let pk = parse_account(pk)?;
         let account = self
             .account(&pk)
             .map(|account| {
@@ -128,12 +257,110 @@ impl Rusk {
         Ok(ResponseData::new(account))
     }
 
+    /// Returns the execution profiles of the contracts, the slowest first.
+    ///
+    /// Profiles are only recorded when `debug_receipts` is enabled.
+    fn get_contract_profiles(&self) -> anyhow::Result<ResponseData> {
+        let profiles: Vec<_> = self
+            .profiles
+            .snapshot()
+            .into_iter()
+            .map(|(contract_id, stats)| {
+                let mut value = serde_json::to_value(stats)?;
+                value["contract"] = contract_id.to_string().into();
+                anyhow::Ok(value)
+            })
+            .collect::<anyhow::Result<_>>()?;
+        Ok(ResponseData::new(serde_json::to_value(profiles)?))
+    }
+
     fn get_crs(&self) -> anyhow::Result<ResponseData> {
         let crs = rusk_profile::get_common_reference_string()?;
         Ok(ResponseData::new(crs).with_header("crs-hash", CRS_17_HASH))
     }
 }
 
+/// Parses an account from either its plain base58 encoding or its
+/// checksummed one.
+fn parse_account(account: &str) -> anyhow::Result<BlsPublicKey> {
+    let bytes = bs58::decode(account)
+        .into_vec()
+        .map_err(|e| anyhow::anyhow!("Invalid bs58 account: {e}"))?;
+
+    if bytes.len() == BlsPublicKey::SIZE {
+        BlsPublicKey::from_slice(&bytes)
+            .map_err(|e| anyhow::anyhow!("Invalid bls account: {e:?}"))
+    } else {
+        BlsPublicKey::from_base58_check(account)
+            .map_err(|e| anyhow::anyhow!("Invalid account: {e:?}"))
+    }
+}
+
+fn parse_contract_id(contract: &str) -> anyhow::Result<ContractId> {
+    let contract_bytes = hex::decode(contract)?;
+
+    let contract_bytes = contract_bytes
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("Invalid contract bytes"))?;
+    Ok(ContractId::from_bytes(contract_bytes))
+}
+
+/// Parses the hex encoded state root of a `Rusk-State-Root` header, if any
+fn parse_state_root(
+    root: Option<&serde_json::Value>,
+) -> anyhow::Result<Option<[u8; 32]>> {
+    root.map(|root| {
+        let root = root
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Invalid state root header"))?;
+        hex::decode(root)?
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("Invalid state root"))
+    })
+    .transpose()
+}
+
+/// Reserves a proof, failing if [`MAX_PROOFS_PER_SECOND`] proofs were
+/// already served during the current second
+fn acquire_proof() -> anyhow::Result<()> {
+    let now = Instant::now();
+    let mut served = PROOFS_SERVED.lock();
+    if now.duration_since(served.0) >= Duration::from_secs(1) {
+        *served = (now, 0);
+    }
+    if served.1 >= MAX_PROOFS_PER_SECOND {
+        anyhow::bail!("Too many proofs requested, retry later");
+    }
+    served.1 += 1;
+    Ok(())
+}
+
+/// Encodes the memory pages of `proofs` along with their rkyv serialized
+/// openings
+fn pages_json(
+    proofs: Vec<PageProof>,
+) -> anyhow::Result<Vec<serde_json::Value>> {
+    proofs
+        .into_iter()
+        .map(|proof| {
+            let opening = rkyv::to_bytes::<_, 4096>(&proof.opening)
+                .map_err(|e| anyhow::anyhow!("{e}"))?;
+            anyhow::Ok(json!({
+                "index": proof.index,
+                "page": hex::encode(proof.page),
+                "opening": hex::encode(opening),
+            }))
+        })
+        .collect()
+}
+
+/// Range of the memory of a contract to prove
+#[derive(Deserialize)]
+struct StateProofRequest {
+    offset: usize,
+    len: usize,
+}
+
 #[derive(Serialize)]
 struct Provisioner {
     key: String,
@@ -73,8 +73,9 @@ impl Listener {
         })
     }
 
-    pub async fn accept(&self) -> io::Result<Stream> {
-        let (stream, _) = self.inner.accept().await?;
+    /// Accepts a connection, returning its stream and the address of its peer
+    pub async fn accept(&self) -> io::Result<(Stream, SocketAddr)> {
+        let (stream, remote) = self.inner.accept().await?;
 
         let stream = match &self.acceptor {
             None => Stream::Raw(stream),
@@ -84,7 +85,7 @@ impl Listener {
             }
         };
 
-        Ok(stream)
+        Ok((stream, remote))
     }
 
     pub fn local_addr(&self) -> io::Result<SocketAddr> {
@@ -4,24 +4,44 @@
 //
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
+mod auto_compound;
 mod events;
+mod migration;
+mod network_reload;
+mod preverify;
+mod profile;
 mod rusk;
+#[cfg(feature = "recovery-state")]
+mod snapshot;
+mod sponsorship;
+mod stake_rules;
 mod vm;
 
+use std::collections::BTreeSet;
 use std::path::PathBuf;
 use std::sync::Arc;
 
 use dusk_core::{dusk, Dusk};
 
+pub use auto_compound::{
+    AutoCompoundConfig, AutoCompoundSrv, AutoCompoundStatus, Stage,
+};
 use dusk_vm::VM;
+pub use migration::{node_migrations, upgrade_stake_contract, Migrations};
+pub use network_reload::{KadcastReload, NetworkReloadSrv};
 use node::database::rocksdb::{self, Backend};
+use node::mempool::conf::DEFAULT_MIN_REPLACEMENT_BUMP;
 use node::network::Kadcast;
 use node::LongLivedService;
-use parking_lot::RwLock;
+use node_data::events::contract::ContractEvent;
+use parking_lot::{Mutex, RwLock};
+pub use profile::{tx_profile_json, ContractProfiles, ContractStats};
+#[cfg(feature = "recovery-state")]
+pub use snapshot::{stage_state_snapshot, StagedSnapshot, StateExport};
 use tokio::sync::broadcast;
 pub use vm::*;
 
-use crate::http::RuesEvent;
+use crate::http::{IdempotencyKeys, RuesEvent};
 pub(crate) use events::ChainEventStreamer;
 #[cfg(feature = "archive")]
 use node::archive::Archive;
@@ -32,6 +52,17 @@ pub struct RuskTip {
     pub base: [u8; 32],
 }
 
+/// Outcome of the simulated execution of a transaction
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SimulationResult {
+    /// Gas spent by the transaction
+    pub gas_spent: u64,
+    /// Events emitted by the transaction
+    pub events: Vec<ContractEvent>,
+    /// Error the transaction failed with, if any
+    pub error: Option<String>,
+}
+
 #[derive(Clone)]
 pub struct Rusk {
     pub(crate) tip: Arc<RwLock<RuskTip>>,
@@ -42,6 +73,11 @@ pub struct Rusk {
     pub(crate) min_gas_limit: u64,
     pub(crate) feeder_gas_limit: u64,
     pub(crate) event_sender: broadcast::Sender<RuesEvent>,
+    pub(crate) migrations: Migrations,
+    pub(crate) profiles: ContractProfiles,
+    /// Commits created on the side of the chain state, by migration dry runs
+    /// and replays, which are the only ones that can be deleted on request.
+    pub(crate) side_commits: Arc<Mutex<BTreeSet<[u8; 32]>>>,
     #[cfg(feature = "archive")]
     pub archive: Archive,
 }
@@ -52,6 +88,14 @@ pub(crate) type Services =
 #[derive(Clone)]
 pub struct RuskNode {
     inner: node::Node<Kadcast<255>, Backend, Rusk>,
+    pub(crate) idempotency_keys: IdempotencyKeys,
+    pub(crate) auto_compound: Arc<RwLock<AutoCompoundStatus>>,
+    /// Minimum gas price increase, in percent, for a transaction to replace
+    /// the mempool ones
+    pub(crate) min_replacement_bump: u64,
+    /// Exports of the state served to the fast sync of other nodes
+    #[cfg(feature = "recovery-state")]
+    pub(crate) state_export: StateExport,
     #[cfg(feature = "archive")]
     archive: Archive,
 }
@@ -63,11 +107,46 @@ impl RuskNode {
     ) -> Self {
         Self {
             inner,
+            idempotency_keys: IdempotencyKeys::default(),
+            auto_compound: Arc::default(),
+            min_replacement_bump: DEFAULT_MIN_REPLACEMENT_BUMP,
+            #[cfg(feature = "recovery-state")]
+            state_export: StateExport::default(),
             #[cfg(feature = "archive")]
             archive,
         }
     }
 
+    /// Sets the status of the stake auto-compounding reported by the node.
+    pub fn with_auto_compound_status(
+        mut self,
+        status: Arc<RwLock<AutoCompoundStatus>>,
+    ) -> Self {
+        self.auto_compound = status;
+        self
+    }
+
+    /// Sets the minimum gas price increase, in percent, the mempool requires
+    /// to replace a transaction.
+    pub fn with_min_replacement_bump(mut self, bump: u64) -> Self {
+        self.min_replacement_bump = bump;
+        self
+    }
+
+    /// Sets the store of the idempotency keys of the transaction submissions.
+    pub fn with_idempotency_keys(mut self, keys: IdempotencyKeys) -> Self {
+        self.idempotency_keys = keys;
+        self
+    }
+
+    /// Sets the limits of the state exports served to the fast sync of other
+    /// nodes.
+    #[cfg(feature = "recovery-state")]
+    pub fn with_state_export(mut self, export: StateExport) -> Self {
+        self.state_export = export;
+        self
+    }
+
     #[cfg(feature = "archive")]
     pub fn with_archive(mut self, archive: Archive) -> Self {
         self.archive = archive;
@@ -94,7 +173,8 @@ impl RuskNode {
     }
 }
 
-/// Calculates the value that the coinbase notes should contain.
+/// Calculates the value that the coinbase notes should contain, out of the
+/// `emission` of the block and the fees it collects.
 ///
 /// 10% of the reward value goes to the Dusk address (rounded down).
 /// 70% of the reward value is considered fixed reward for Block Generator.
@@ -102,10 +182,10 @@ impl RuskNode {
 /// 10% of the reward value goes to the all validators/voters of previous block
 /// (rounded down).
 const fn coinbase_value(
-    block_height: u64,
+    emission: Dusk,
     dusk_spent: u64,
 ) -> (Dusk, Dusk, Dusk, Dusk) {
-    let reward_value = emission_amount(block_height) + dusk_spent;
+    let reward_value = emission + dusk_spent;
     let one_tenth_reward = reward_value / 10;
 
     let dusk_value = one_tenth_reward;
@@ -0,0 +1,554 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Auto-compounding of the stake rewards.
+//!
+//! When enabled, the node re-stakes the rewards accumulated by its provisioner
+//! once per epoch, if they exceed a threshold. Compounding takes two
+//! transactions, signed with the consensus keys: the rewards are first
+//! withdrawn to the provisioner account, then staked. A transaction is only
+//! submitted once the previous one is executed.
+//!
+//! Compounding is skipped if the stake isn't owned by the provisioner account,
+//! if the account can't pay for the gas, or if topping-up the eligible stake
+//! would lock part of the rewards and this isn't explicitly accepted.
+//!
+//! The next consensus keys, if any, are loaded along with the current ones.
+//! Once a key rotation moves the stake to them, they sign the compounding
+//! transactions in place of the current ones.
+
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{anyhow, bail};
+use async_trait::async_trait;
+use dusk_core::signatures::bls::{
+    PublicKey as BlsPublicKey, SecretKey as BlsSecretKey,
+};
+use dusk_core::stake::{StakeFundOwner, EPOCH};
+use dusk_core::transfer::moonlight::AccountData;
+use dusk_core::transfer::Transaction as ProtocolTransaction;
+use dusk_core::{dusk, Dusk};
+use node::database::rocksdb::MD_HASH_KEY;
+use node::database::{self, Ledger, Metadata};
+use node::{LongLivedService, Network};
+use node_data::keystore::{next_keys_path, unlock_file};
+use node_data::ledger::Transaction;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use tokio::time::sleep;
+use tracing::{info, warn};
+use wallet_core::transaction::{
+    moonlight_stake, moonlight_stake_reward, moonlight_stake_with_pop,
+};
+
+use crate::node::Rusk;
+
+/// Blocks after which a submitted transaction is considered lost
+const TX_TIMEOUT_BLOCKS: u64 = 60;
+
+/// Auto-compounding configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AutoCompoundConfig {
+    pub enabled: bool,
+
+    /// Minimum amount of rewards to compound, in LUX
+    pub threshold: Dusk,
+
+    /// Gas limit of each compounding transaction
+    pub gas_limit: u64,
+
+    /// Gas price of each compounding transaction
+    pub gas_price: u64,
+
+    /// Compound into an eligible stake, locking 10% of the compounded rewards
+    pub accept_topup_penalty: bool,
+
+    #[serde(with = "humantime_serde")]
+    pub check_interval: Duration,
+}
+
+impl Default for AutoCompoundConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            threshold: dusk(100.0),
+            gas_limit: 50_000_000,
+            gas_price: 1,
+            accept_topup_penalty: false,
+            check_interval: Duration::from_secs(10),
+        }
+    }
+}
+
+/// Stage of the compounding
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case", tag = "stage")]
+pub enum Stage {
+    #[default]
+    Idle,
+    /// Waiting for the rewards withdrawal to be executed
+    Withdrawing {
+        nonce: u64,
+        amount: Dusk,
+        balance: Dusk,
+        height: u64,
+    },
+    /// Waiting for the stake to be executed
+    Staking {
+        nonce: u64,
+        amount: Dusk,
+        balance: Dusk,
+        height: u64,
+    },
+}
+
+/// Status of the auto-compounding, as reported to the operator
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AutoCompoundStatus {
+    pub enabled: bool,
+    pub stage: Stage,
+    /// Last epoch the compounding was attempted at
+    pub last_epoch: Option<u64>,
+    /// Height and amount of the last compounding
+    pub last_compounded: Option<(u64, Dusk)>,
+    pub total_compounded: Dusk,
+    /// Reason the last attempt was skipped or failed, if any
+    pub last_error: Option<String>,
+}
+
+/// Compounding transaction to submit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Submission {
+    /// Withdrawal of the rewards to the provisioner account
+    Withdraw { nonce: u64, amount: Dusk },
+    /// Stake of the withdrawn rewards
+    Stake { nonce: u64, amount: Dusk },
+}
+
+pub struct AutoCompoundSrv {
+    conf: AutoCompoundConfig,
+    keys_path: String,
+    status: Arc<RwLock<AutoCompoundStatus>>,
+}
+
+impl AutoCompoundSrv {
+    pub fn new(conf: AutoCompoundConfig, keys_path: String) -> Self {
+        info!("AutoCompoundSrv::new with conf {conf:?}");
+        let status = AutoCompoundStatus {
+            enabled: conf.enabled,
+            ..Default::default()
+        };
+        Self {
+            conf,
+            keys_path,
+            status: Arc::new(RwLock::new(status)),
+        }
+    }
+
+    /// Returns a handle to the status of the auto-compounding
+    pub fn status(&self) -> Arc<RwLock<AutoCompoundStatus>> {
+        self.status.clone()
+    }
+
+    /// Advances the compounding at `height`, returning the transaction to
+    /// submit, if any.
+    ///
+    /// The VM is only queried, so that the lock on it isn't held while the
+    /// transaction is submitted.
+    fn on_tick(
+        &self,
+        rusk: &Rusk,
+        keys: &(BlsSecretKey, BlsPublicKey),
+        height: u64,
+    ) -> anyhow::Result<Option<(ProtocolTransaction, Submission)>> {
+        let (sk, pk) = keys;
+        let account = rusk.account(pk)?;
+
+        let submission = self.advance(&account, height, || {
+            self.compoundable(rusk, pk, height)
+        })?;
+        let Some(submission) = submission else {
+            return Ok(None);
+        };
+
+        let tx = match submission {
+            Submission::Withdraw { nonce, amount } => moonlight_stake_reward(
+                &mut rand::thread_rng(),
+                sk,
+                sk,
+                sk,
+                amount,
+                self.conf.gas_limit,
+                self.conf.gas_price,
+                nonce,
+                rusk.chain_id()?,
+            )
+            .map_err(|e| anyhow!("cannot build withdrawal: {e:?}"))?,
+            Submission::Stake { nonce, amount } => {
+                // The stake lands at the earliest in the next block, which
+                // might already require the proof of possession
+                let stake = if rusk.vm_config.with_stake_pop(height + 1) {
+                    moonlight_stake_with_pop
+                } else {
+                    moonlight_stake
+                };
+                stake(
+                    sk,
+                    sk,
+                    sk,
+                    amount,
+                    self.conf.gas_limit,
+                    self.conf.gas_price,
+                    nonce,
+                    rusk.chain_id()?,
+                )
+                .map_err(|e| anyhow!("cannot build stake: {e:?}"))?
+            }
+        };
+        Ok(Some((tx, submission)))
+    }
+
+    /// Moves the compounding to its next stage, given the provisioner
+    /// `account` at `height`.
+    ///
+    /// Returns the transaction to submit, if any.
+    fn advance(
+        &self,
+        account: &AccountData,
+        height: u64,
+        compoundable: impl FnOnce() -> anyhow::Result<Option<Dusk>>,
+    ) -> anyhow::Result<Option<Submission>> {
+        let max_fee = self.conf.gas_limit * self.conf.gas_price;
+        let stage = self.status.read().stage;
+
+        let (next, submission) = match stage {
+            Stage::Idle => {
+                let epoch = height / EPOCH;
+                if self.status.read().last_epoch == Some(epoch) {
+                    return Ok(None);
+                }
+                self.status.write().last_epoch = Some(epoch);
+
+                let Some(amount) = compoundable()? else {
+                    return Ok(None);
+                };
+                if account.balance < 2 * max_fee {
+                    bail!("balance {} can't pay for the gas", account.balance);
+                }
+
+                let nonce = account.nonce + 1;
+                let next = Stage::Withdrawing {
+                    nonce,
+                    amount,
+                    balance: account.balance,
+                    height,
+                };
+                (next, Some(Submission::Withdraw { nonce, amount }))
+            }
+
+            Stage::Withdrawing {
+                nonce,
+                amount,
+                balance,
+                height: submitted,
+            } => {
+                if account.nonce < nonce {
+                    return self.check_timeout(submitted, height).map(|_| None);
+                }
+                // The rewards must have reached the account, so that the
+                // account own funds are never staked
+                if account.balance + max_fee < balance + amount {
+                    self.status.write().stage = Stage::Idle;
+                    bail!("rewards withdrawal failed");
+                }
+
+                let nonce = account.nonce + 1;
+                let next = Stage::Staking {
+                    nonce,
+                    amount,
+                    balance: account.balance,
+                    height,
+                };
+                (next, Some(Submission::Stake { nonce, amount }))
+            }
+
+            Stage::Staking {
+                nonce,
+                amount,
+                balance,
+                height: submitted,
+            } => {
+                if account.nonce < nonce {
+                    return self.check_timeout(submitted, height).map(|_| None);
+                }
+                if account.balance + amount > balance {
+                    self.status.write().stage = Stage::Idle;
+                    bail!("stake of the withdrawn rewards failed");
+                }
+                info!(event = "rewards compounded", amount, height);
+                let mut status = self.status.write();
+                status.last_compounded = Some((height, amount));
+                status.total_compounded += amount;
+                status.last_error = None;
+                (Stage::Idle, None)
+            }
+        };
+
+        self.status.write().stage = next;
+        Ok(submission)
+    }
+
+    /// Returns the amount of rewards that can be compounded, if any
+    fn compoundable(
+        &self,
+        rusk: &Rusk,
+        pk: &BlsPublicKey,
+        height: u64,
+    ) -> anyhow::Result<Option<Dusk>> {
+        let keys = rusk
+            .stake_keys(pk)?
+            .ok_or_else(|| anyhow!("no stake found"))?;
+        if keys.owner != StakeFundOwner::Account(*pk) {
+            bail!("stake not owned by the provisioner account");
+        }
+
+        let stake = rusk
+            .provisioner(pk)?
+            .ok_or_else(|| anyhow!("no stake found"))?;
+        let amount = stake.amount.ok_or_else(|| anyhow!("no stake found"))?;
+
+        if stake.reward < self.conf.threshold {
+            info!(
+                event = "rewards compounding skipped",
+                reward = stake.reward,
+                threshold = self.conf.threshold,
+            );
+            return Ok(None);
+        }
+        if height >= amount.eligibility && !self.conf.accept_topup_penalty {
+            bail!("compounding into an eligible stake locks 10% of it");
+        }
+        Ok(Some(stake.reward))
+    }
+
+    /// Switches to the `next` keys once a key rotation moved the stake of the
+    /// `current` ones to them.
+    ///
+    /// The compounding in progress is given up, since its transactions are
+    /// signed with the current keys.
+    fn rotate_keys(
+        &self,
+        rusk: &Rusk,
+        current: &mut (BlsSecretKey, BlsPublicKey),
+        next: &mut Option<(BlsSecretKey, BlsPublicKey)>,
+    ) -> anyhow::Result<()> {
+        let Some((_, next_pk)) = next.as_ref() else {
+            return Ok(());
+        };
+        if rusk.stake_keys(&current.1)?.is_some()
+            || rusk.stake_keys(next_pk)?.is_none()
+        {
+            return Ok(());
+        }
+
+        *current = next.take().expect("next keys to be loaded");
+        self.status.write().stage = Stage::Idle;
+        info!(event = "rewards compounding keys rotated");
+        Ok(())
+    }
+
+    fn check_timeout(&self, submitted: u64, height: u64) -> anyhow::Result<()> {
+        if height > submitted + TX_TIMEOUT_BLOCKS {
+            self.status.write().stage = Stage::Idle;
+            bail!("transaction submitted at {submitted} not executed");
+        }
+        Ok(())
+    }
+
+    async fn submit<N: Network>(
+        network: &Arc<tokio::sync::RwLock<N>>,
+        tx: ProtocolTransaction,
+        submission: Submission,
+    ) {
+        let tx: Transaction = tx.into();
+        let (kind, amount) = match submission {
+            Submission::Withdraw { amount, .. } => ("withdraw", amount),
+            Submission::Stake { amount, .. } => ("stake", amount),
+        };
+        info!(
+            event = "rewards compounding tx submitted",
+            kind,
+            amount,
+            tx_id = hex::encode(tx.id()),
+        );
+        network.read().await.route_internal(tx.into());
+    }
+}
+
+#[async_trait]
+impl<N: Network, DB: database::DB> LongLivedService<N, DB, Rusk>
+    for AutoCompoundSrv
+{
+    async fn execute(
+        &mut self,
+        network: Arc<tokio::sync::RwLock<N>>,
+        db: Arc<tokio::sync::RwLock<DB>>,
+        vm: Arc<tokio::sync::RwLock<Rusk>>,
+    ) -> anyhow::Result<usize> {
+        if !self.conf.enabled {
+            return Ok(0);
+        }
+
+        let pwd = std::env::var("DUSK_CONSENSUS_KEYS_PASS")
+            .map_err(|_| anyhow!("DUSK_CONSENSUS_KEYS_PASS not set"))?;
+        let (sk, pk) =
+            unlock_file(Path::new(&self.keys_path), &pwd)?.into_keys();
+        let mut keys = (sk, *pk.inner());
+
+        let next_path = next_keys_path(Path::new(&self.keys_path));
+        let mut next_keys = match next_path.exists() {
+            true => {
+                let (sk, pk) = unlock_file(&next_path, &pwd)?.into_keys();
+                Some((sk, *pk.inner()))
+            }
+            false => None,
+        };
+
+        loop {
+            sleep(self.conf.check_interval).await;
+
+            let height = db.read().await.view(|t| {
+                let height = match t.op_read(MD_HASH_KEY)? {
+                    Some(hash) => t.block_header(&hash)?.map(|h| h.height),
+                    None => None,
+                };
+                anyhow::Ok(height)
+            });
+            let height = match height {
+                Ok(Some(height)) => height,
+                Ok(None) => continue,
+                Err(err) => {
+                    warn!(event = "cannot read the tip", ?err);
+                    continue;
+                }
+            };
+
+            let res = {
+                let rusk = vm.read().await;
+                self.rotate_keys(&rusk, &mut keys, &mut next_keys)
+                    .and_then(|_| self.on_tick(&rusk, &keys, height))
+            };
+            match res {
+                Ok(Some((tx, submission))) => {
+                    Self::submit(&network, tx, submission).await
+                }
+                Ok(None) => {}
+                Err(err) => {
+                    warn!(event = "rewards compounding failed", %err);
+                    self.status.write().last_error = Some(err.to_string());
+                }
+            }
+        }
+    }
+
+    /// Returns service name.
+    fn name(&self) -> &'static str {
+        "auto compound"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn srv() -> AutoCompoundSrv {
+        let conf = AutoCompoundConfig {
+            enabled: true,
+            gas_limit: 10,
+            gas_price: 1,
+            ..Default::default()
+        };
+        AutoCompoundSrv::new(conf, String::new())
+    }
+
+    fn account(nonce: u64, balance: u64) -> AccountData {
+        AccountData { nonce, balance }
+    }
+
+    #[test]
+    fn compound_rewards() {
+        let srv = srv();
+        let height = 2 * EPOCH;
+
+        // The rewards are withdrawn first, then staked
+        let submission = srv
+            .advance(&account(4, 100), height, || Ok(Some(50)))
+            .unwrap();
+        assert_eq!(
+            submission,
+            Some(Submission::Withdraw {
+                nonce: 5,
+                amount: 50
+            })
+        );
+
+        // Nothing is submitted until the withdrawal is executed
+        let pending = srv.advance(&account(4, 100), height + 1, || Ok(None));
+        assert_eq!(pending.unwrap(), None);
+
+        let submission = srv
+            .advance(&account(5, 140), height + 2, || Ok(None))
+            .unwrap();
+        assert_eq!(
+            submission,
+            Some(Submission::Stake {
+                nonce: 6,
+                amount: 50
+            })
+        );
+
+        let done = srv.advance(&account(6, 80), height + 3, || Ok(None));
+        assert_eq!(done.unwrap(), None);
+        let status = srv.status.read().clone();
+        assert_eq!(status.stage, Stage::Idle);
+        assert_eq!(status.last_compounded, Some((height + 3, 50)));
+        assert_eq!(status.total_compounded, 50);
+
+        // Compounding is attempted once per epoch
+        let again = srv.advance(&account(6, 80), height + 4, || Ok(Some(50)));
+        assert_eq!(again.unwrap(), None);
+    }
+
+    #[test]
+    fn compounding_safety_checks() {
+        let srv = srv();
+
+        // The account must pay for the gas of both transactions
+        let poor = srv.advance(&account(0, 19), EPOCH, || Ok(Some(50)));
+        assert!(poor.is_err());
+        assert_eq!(srv.status.read().stage, Stage::Idle);
+
+        // The account own funds are never staked
+        let srv = self::srv();
+        srv.advance(&account(0, 100), EPOCH, || Ok(Some(50)))
+            .unwrap();
+        let failed = srv.advance(&account(1, 100), EPOCH + 1, || Ok(None));
+        assert!(failed.is_err());
+        assert_eq!(srv.status.read().stage, Stage::Idle);
+
+        // A transaction not executed in time is given up
+        let srv = self::srv();
+        srv.advance(&account(0, 100), EPOCH, || Ok(Some(50)))
+            .unwrap();
+        let height = EPOCH + TX_TIMEOUT_BLOCKS + 1;
+        let lost = srv.advance(&account(0, 100), height, || Ok(None));
+        assert!(lost.is_err());
+        assert_eq!(srv.status.read().stage, Stage::Idle);
+    }
+}
@@ -0,0 +1,323 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Contract state migrations for hard forks.
+//!
+//! A migration is a named function altering the contracts state, e.g.
+//! re-keying stake entries or adjusting balances. It's registered in the
+//! [`Migrations`] registry and activated at the height configured for its name
+//! in the VM features, like any other feature:
+//!
+//! ```toml
+//! [vm.features]
+//! MIGRATION_NAME = 123456
+//! ```
+//!
+//! Migrations are run in the session of the activation block, before any
+//! transaction, so that their outcome is part of the state root of that block.
+//! Multiple migrations activated at the same height are run in registration
+//! order.
+//!
+//! A migration takes the session by value, so that it can replace the
+//! bytecode of a contract, see [`upgrade_stake_contract`]. The migrations run
+//! by the node are returned by [`node_migrations`].
+
+use std::sync::{mpsc, Arc};
+use std::{fmt, fs};
+
+use dusk_bytes::{DeserializableSlice, Serializable};
+use dusk_core::signatures::bls::PublicKey as BlsPublicKey;
+use dusk_core::stake::{StakeConfig, StakeData, StakeKeys, STAKE_CONTRACT};
+use dusk_vm::{ContractData, Session};
+use tracing::info;
+
+use super::{
+    ContractUpgrade, RuskVmConfig, FEATURE_STAKE_KEY_ROTATION,
+    FEATURE_STAKE_POP,
+};
+use crate::{Error, Result, DUSK_CONSENSUS_KEY};
+
+/// Features changing the stake calls the stake contract deployed before them
+/// can't decode, hence activating the stake contract upgrade
+const STAKE_UPGRADE_FEATURES: [&str; 2] =
+    [FEATURE_STAKE_POP, FEATURE_STAKE_KEY_ROTATION];
+
+type MigrationFn = dyn Fn(Session) -> Result<Session> + Send + Sync;
+
+#[derive(Clone)]
+struct Migration {
+    name: &'static str,
+    run: Arc<MigrationFn>,
+}
+
+/// Registry of the migrations known by the node
+#[derive(Clone, Default)]
+pub struct Migrations(Vec<Migration>);
+
+impl fmt::Debug for Migrations {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.0.iter().map(|m| m.name))
+            .finish()
+    }
+}
+
+impl Migrations {
+    /// Registers the migration `name`.
+    ///
+    /// # Panics
+    /// If a migration with the same name is already registered.
+    pub fn register<F>(&mut self, name: &'static str, run: F) -> &mut Self
+    where
+        F: Fn(Session) -> Result<Session> + Send + Sync + 'static,
+    {
+        assert!(
+            self.0.iter().all(|m| !m.name.eq_ignore_ascii_case(name)),
+            "migration {name} already registered"
+        );
+        self.0.push(Migration {
+            name,
+            run: Arc::new(run),
+        });
+        self
+    }
+
+    /// Returns the migrations activated at `block_height`
+    fn activated_at<'a>(
+        &'a self,
+        config: &'a RuskVmConfig,
+        block_height: u64,
+    ) -> impl Iterator<Item = &'a Migration> {
+        self.0
+            .iter()
+            .filter(move |m| config.feature(m.name) == Some(block_height))
+    }
+
+    /// Runs the migrations activated at `block_height` in `session`
+    pub(crate) fn apply(
+        &self,
+        config: &RuskVmConfig,
+        block_height: u64,
+        mut session: Session,
+    ) -> Result<Session> {
+        for migration in self.activated_at(config, block_height) {
+            info!(
+                event = "applying migration",
+                name = migration.name,
+                block_height
+            );
+            session = (migration.run)(session)?;
+        }
+        Ok(session)
+    }
+
+    /// Runs the migration `name` in `session`, regardless of its activation
+    pub(crate) fn apply_one(
+        &self,
+        name: &str,
+        session: Session,
+    ) -> Result<Session> {
+        let migration = self
+            .0
+            .iter()
+            .find(|m| m.name.eq_ignore_ascii_case(name))
+            .ok_or_else(|| Error::UnknownMigration(name.to_string()))?;
+        (migration.run)(session)
+    }
+}
+
+/// Returns the migrations run by a node configured with `config`.
+///
+/// The stake contract is upgraded, as configured, at the activation of the
+/// first of the features changing its calls. Otherwise the stake contract would
+/// fail all the calls required by such features, e.g. the stakes carrying a
+/// proof of possession or the key rotations. Hence an error is returned if any
+/// of them is activated after genesis without the upgrade. A feature activated
+/// at genesis requires the genesis state to deploy a stake contract supporting
+/// it.
+pub fn node_migrations(config: &RuskVmConfig) -> Result<Migrations> {
+    let mut migrations = Migrations::default();
+
+    let activation = STAKE_UPGRADE_FEATURES
+        .into_iter()
+        .filter_map(|name| config.feature(name).map(|height| (height, name)))
+        .filter(|(height, _)| *height > 0)
+        .min();
+    if let Some((height, name)) = activation {
+        let upgrade = config.stake_upgrade.as_ref().ok_or_else(|| {
+            let err = format!(
+                "{name} activated at {height} without a stake contract upgrade"
+            );
+            Error::Other(err.into())
+        })?;
+        let (bytecode, owner) = read_upgrade(upgrade)?;
+        migrations.register(name, upgrade_stake_contract(bytecode, owner));
+    }
+
+    Ok(migrations)
+}
+
+/// Reads the bytecode and the owner of `upgrade`
+fn read_upgrade(upgrade: &ContractUpgrade) -> Result<(Vec<u8>, Vec<u8>)> {
+    let bytecode = fs::read(&upgrade.bytecode)?;
+    let owner = match &upgrade.owner {
+        Some(owner) => {
+            let bytes = bs58::decode(owner)
+                .into_vec()
+                .map_err(|e| Error::Other(format!("{e:?}").into()))?;
+            BlsPublicKey::from_slice(&bytes)
+                .map_err(|e| Error::Other(format!("{e:?}").into()))?
+        }
+        None => *DUSK_CONSENSUS_KEY,
+    };
+    Ok((bytecode, owner.to_bytes().to_vec()))
+}
+
+/// Returns the migration replacing the stake contract with `bytecode`, owned
+/// by `owner`.
+///
+/// The stakes, the configuration and the burnt amount of the current contract
+/// are carried over to the new one, whose state layout may differ. The
+/// contract balance is kept by the transfer contract, under the unchanged
+/// contract ID.
+///
+/// E.g. the stake contract version 9, keeping track of the key rotations, is
+/// deployed by the node at the activation of `STAKE_KEY_ROTATION`, unless
+/// `STAKE_POP` is activated first, see [`node_migrations`].
+pub fn upgrade_stake_contract(
+    bytecode: Vec<u8>,
+    owner: Vec<u8>,
+) -> impl Fn(Session) -> Result<Session> + Send + Sync {
+    move |mut session| {
+        let config: StakeConfig = session
+            .call(STAKE_CONTRACT, "get_config", &(), u64::MAX)?
+            .data;
+        let burnt_amount: u64 = session
+            .call(STAKE_CONTRACT, "burnt_amount", &(), u64::MAX)?
+            .data;
+
+        let (sender, receiver) = mpsc::channel();
+        session.feeder_call::<_, ()>(
+            STAKE_CONTRACT,
+            "stakes",
+            &(),
+            u64::MAX,
+            sender,
+        )?;
+        let stakes = receiver
+            .into_iter()
+            .map(|bytes| {
+                rkyv::from_bytes::<(StakeKeys, StakeData)>(&bytes)
+                    .map_err(|e| Error::Other(format!("{e:?}").into()))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        info!(event = "upgrading stake contract", stakes = stakes.len());
+        let session = session.migrate(
+            STAKE_CONTRACT,
+            &bytecode,
+            ContractData::builder().owner(owner.clone()),
+            u64::MAX,
+            |contract, session| {
+                session.call::<_, ()>(
+                    contract,
+                    "set_config",
+                    &config,
+                    u64::MAX,
+                )?;
+                session.call::<_, ()>(
+                    contract,
+                    "set_burnt_amount",
+                    &burnt_amount,
+                    u64::MAX,
+                )?;
+                for stake in &stakes {
+                    session.call::<_, ()>(
+                        contract,
+                        "insert_stake",
+                        stake,
+                        u64::MAX,
+                    )?;
+                }
+                Ok(())
+            },
+        )?;
+        Ok(session)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    #[test]
+    fn migrations_activation() {
+        let mut migrations = Migrations::default();
+        migrations
+            .register("REKEY", Ok)
+            .register("ADJUST", Ok)
+            .register("UNSCHEDULED", Ok);
+
+        let mut config = RuskVmConfig::new();
+        config.features.insert("rekey".into(), 10);
+        config.features.insert("ADJUST".into(), 10);
+
+        let names = |height| {
+            migrations
+                .activated_at(&config, height)
+                .map(|m| m.name)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(10), vec!["REKEY", "ADJUST"]);
+        assert!(names(9).is_empty());
+        assert!(names(11).is_empty());
+    }
+
+    #[test]
+    fn stake_upgrade_registration() {
+        let mut config = RuskVmConfig::new();
+        let names = |config: &RuskVmConfig| {
+            let migrations = node_migrations(config).expect("migrations");
+            migrations.0.iter().map(|m| m.name).collect::<Vec<_>>()
+        };
+
+        // A feature activated at genesis requires no upgrade
+        config.features.insert(FEATURE_STAKE_POP.into(), 0);
+        assert!(names(&config).is_empty());
+
+        // A feature activated afterwards can't be without the upgrade
+        config.features.insert(FEATURE_STAKE_POP.into(), 10);
+        node_migrations(&config).expect_err("upgrade to be required");
+
+        let mut bytecode = tempfile::NamedTempFile::new().unwrap();
+        bytecode.write_all(b"bytecode").unwrap();
+        config.stake_upgrade = Some(ContractUpgrade {
+            bytecode: bytecode.path().into(),
+            owner: None,
+        });
+        assert_eq!(names(&config), vec![FEATURE_STAKE_POP]);
+
+        // The upgrade is run once, at the first activation
+        config.features.insert(FEATURE_STAKE_KEY_ROTATION.into(), 5);
+        assert_eq!(names(&config), vec![FEATURE_STAKE_KEY_ROTATION]);
+
+        config.stake_upgrade = Some(ContractUpgrade {
+            bytecode: bytecode.path().into(),
+            owner: Some("not an account".into()),
+        });
+        node_migrations(&config).expect_err("owner to be invalid");
+    }
+
+    #[test]
+    #[should_panic]
+    fn migrations_unique_names() {
+        Migrations::default()
+            .register("REKEY", Ok)
+            .register("rekey", Ok);
+    }
+}
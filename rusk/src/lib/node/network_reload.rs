@@ -0,0 +1,76 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Soft restart of the network on SIGHUP.
+//!
+//! The Kadcast configuration is loaded again and the peer is rebuilt with it,
+//! without restarting the other services: the consensus keeps its state and
+//! the database stays open. This allows a provisioner to follow a change of
+//! its public address.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use kadcast::config::Config as KadcastConfig;
+use node::database;
+use node::network::Kadcast;
+use node::LongLivedService;
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::RwLock;
+use tracing::{error, info};
+
+use crate::node::Rusk;
+
+/// Loads the Kadcast configuration to restart the network with
+pub type KadcastReload =
+    Box<dyn Fn() -> anyhow::Result<KadcastConfig> + Send + Sync>;
+
+pub struct NetworkReloadSrv {
+    reload: Option<KadcastReload>,
+}
+
+impl NetworkReloadSrv {
+    pub fn new(reload: Option<KadcastReload>) -> Self {
+        Self { reload }
+    }
+}
+
+#[async_trait]
+impl<DB: database::DB> LongLivedService<Kadcast<255>, DB, Rusk>
+    for NetworkReloadSrv
+{
+    async fn execute(
+        &mut self,
+        network: Arc<RwLock<Kadcast<255>>>,
+        _: Arc<RwLock<DB>>,
+        _: Arc<RwLock<Rusk>>,
+    ) -> anyhow::Result<usize> {
+        let Some(reload) = &self.reload else {
+            return Ok(0);
+        };
+
+        let mut hangup = signal(SignalKind::hangup())?;
+        while hangup.recv().await.is_some() {
+            info!(event = "network restart requested");
+
+            let restarted = match reload() {
+                Ok(conf) => network.write().await.restart(conf).await,
+                Err(err) => Err(err),
+            };
+            match restarted {
+                Ok(()) => info!(event = "network restarted"),
+                Err(err) => error!(event = "network restart failed", %err),
+            }
+        }
+
+        Ok(0)
+    }
+
+    /// Returns service name.
+    fn name(&self) -> &'static str {
+        "network_reload"
+    }
+}
@@ -0,0 +1,147 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Pre-verification of the transactions of a block being accepted.
+//!
+//! The transfer contract verifies the proofs and signatures of the
+//! transactions while executing them, one after the other. Ahead of the
+//! execution, they are verified in parallel, filling the verification caches
+//! of the VM so that the execution finds them already verified.
+//!
+//! The signatures of the sponsors of the sponsored transactions are verified
+//! along with the ones of their senders, which commit to the sponsor.
+//!
+//! The nullifiers are checked for duplicates before any of it, so that a
+//! block spending a note twice is rejected without verifying anything.
+//!
+//! A transaction failing any of these checks would fail its execution too,
+//! so rejecting the block early doesn't change its outcome.
+
+use std::collections::HashSet;
+use std::num::NonZeroUsize;
+use std::thread;
+
+use dusk_core::transfer::{
+    Transaction as ProtocolTransaction, TRANSFER_CONTRACT,
+};
+use dusk_core::BlsScalar;
+use node_data::ledger::Transaction;
+
+use crate::node::Rusk;
+use crate::{verifier, Error, Result};
+
+/// Checks the nullifiers of `txs` against each other and against the state
+/// at `prev_commit`, then verifies their proofs and signatures in parallel
+pub(crate) fn preverify_txs(
+    rusk: &Rusk,
+    prev_commit: [u8; 32],
+    txs: &[Transaction],
+) -> Result<()> {
+    let nullifiers: Vec<BlsScalar> = txs
+        .iter()
+        .filter_map(|tx| match &tx.inner {
+            ProtocolTransaction::Phoenix(tx) => Some(tx.nullifiers()),
+            ProtocolTransaction::Moonlight(_) => None,
+        })
+        .flatten()
+        .copied()
+        .collect();
+
+    let mut unique = HashSet::with_capacity(nullifiers.len());
+    if !nullifiers.iter().all(|n| unique.insert(n)) {
+        return Err(Error::DoubleNullifiers);
+    }
+
+    if !nullifiers.is_empty() {
+        let existing: Vec<BlsScalar> = rusk.query_at(
+            prev_commit,
+            TRANSFER_CONTRACT,
+            "existing_nullifiers",
+            &nullifiers,
+        )?;
+        if !existing.is_empty() {
+            return Err(Error::RepeatingNullifiers(existing));
+        }
+    }
+
+    match first_invalid(txs, is_valid) {
+        None => Ok(()),
+        Some(tx) => match &tx.inner {
+            ProtocolTransaction::Phoenix(_) => Err(Error::ProofVerification),
+            ProtocolTransaction::Moonlight(_) => {
+                Err(Error::SignatureVerification)
+            }
+        },
+    }
+}
+
+/// Returns whether the proof or signatures of `tx` are valid
+fn is_valid(tx: &Transaction) -> bool {
+    let valid = match (&tx.inner, tx.sponsorship()) {
+        (ProtocolTransaction::Phoenix(tx), _) => verifier::verify_proof(tx),
+        (ProtocolTransaction::Moonlight(tx), None) => {
+            verifier::verify_signature(tx)
+        }
+        (ProtocolTransaction::Moonlight(tx), Some(sponsorship)) => {
+            verifier::verify_sponsored_signature(tx, sponsorship.sponsor())
+        }
+    };
+    let sponsored = match tx.sponsorship() {
+        Some(sponsorship) => {
+            verifier::verify_sponsorship(&tx.inner, sponsorship)
+        }
+        None => Ok(true),
+    };
+    matches!((valid, sponsored), (Ok(true), Ok(true)))
+}
+
+/// Runs `verify` over `items` split across the available cores, returning
+/// the first item failing it.
+///
+/// An item whose verification panics is considered invalid.
+fn first_invalid<T, F>(items: &[T], verify: F) -> Option<&T>
+where
+    T: Sync,
+    F: Fn(&T) -> bool + Sync,
+{
+    if items.is_empty() {
+        return None;
+    }
+
+    let threads = thread::available_parallelism().map_or(1, NonZeroUsize::get);
+    let chunk_size = items.len().div_ceil(threads);
+    let verify = &verify;
+
+    thread::scope(|s| {
+        let handles: Vec<_> = items
+            .chunks(chunk_size)
+            .map(|chunk| {
+                let handle =
+                    s.spawn(move || chunk.iter().find(|item| !verify(item)));
+                (chunk, handle)
+            })
+            .collect();
+
+        handles.into_iter().find_map(|(chunk, handle)| {
+            handle.join().unwrap_or_else(|_| chunk.first())
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_first_invalid() {
+        let items: Vec<u32> = (0..1_000).collect();
+
+        assert_eq!(first_invalid(&items, |_| true), None);
+        assert_eq!(first_invalid(&items, |i| *i != 700), Some(&700));
+        assert_eq!(first_invalid(&items, |i| *i % 100 != 99), Some(&99));
+        assert_eq!(first_invalid(&[] as &[u32], |_| false), None);
+    }
+}
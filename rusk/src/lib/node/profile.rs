@@ -0,0 +1,183 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Per-contract aggregation of the transaction execution profiles.
+//!
+//! When `debug_receipts` is enabled in the VM configuration, every accepted
+//! transaction is profiled, and its profile is aggregated into the profile of
+//! the contracts it called. The wall time of a transaction is attributed to
+//! the contract it targets, so that the contracts jeopardizing the round
+//! budget can be singled out.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+use dusk_core::abi::ContractId;
+use dusk_core::transfer::{Transaction, TRANSFER_CONTRACT};
+use dusk_vm::profile::TxProfile;
+use parking_lot::RwLock;
+use serde::Serialize;
+use serde_json::{json, Value};
+
+/// Aggregated execution profile of a contract
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ContractStats {
+    /// Number of transactions targeting the contract
+    pub txs: u64,
+    /// Total wall time of the transactions targeting the contract, in µs
+    pub elapsed_us: u64,
+    /// Longest wall time of a transaction targeting the contract, in µs
+    pub max_elapsed_us: u64,
+    /// Number of calls to the contract, including inter-contract calls
+    pub calls: u64,
+    pub gas_spent: u64,
+    /// Growth of the contract memory, in bytes
+    pub mem_growth: u64,
+    /// Host queries performed by the transactions targeting the contract
+    pub host_calls: BTreeMap<&'static str, u64>,
+}
+
+/// Execution profiles of the contracts, aggregated since the node started
+#[derive(Debug, Clone, Default)]
+pub struct ContractProfiles {
+    stats: Arc<RwLock<HashMap<ContractId, ContractStats>>>,
+}
+
+impl ContractProfiles {
+    /// Aggregates the profile of the execution of `tx`
+    pub fn record(&self, tx: &Transaction, profile: &TxProfile) {
+        let target = tx
+            .call()
+            .map(|call| call.contract)
+            .unwrap_or(TRANSFER_CONTRACT);
+        let elapsed_us = profile.elapsed.as_micros() as u64;
+
+        let mut stats = self.stats.write();
+
+        let target = stats.entry(target).or_default();
+        target.txs += 1;
+        target.elapsed_us += elapsed_us;
+        target.max_elapsed_us = target.max_elapsed_us.max(elapsed_us);
+        for (name, count) in &profile.host_calls {
+            *target.host_calls.entry(*name).or_default() += count;
+        }
+
+        for (contract_id, contract) in &profile.contracts {
+            let called = stats.entry(*contract_id).or_default();
+            called.calls += contract.calls;
+            called.gas_spent += contract.gas_spent;
+            called.mem_growth += contract.mem_growth as u64;
+        }
+    }
+
+    /// Returns the profiles of the contracts, the slowest first
+    pub fn snapshot(&self) -> Vec<(ContractId, ContractStats)> {
+        let mut stats: Vec<_> = self
+            .stats
+            .read()
+            .iter()
+            .map(|(id, stats)| (*id, stats.clone()))
+            .collect();
+        stats.sort_by(|(_, a), (_, b)| b.elapsed_us.cmp(&a.elapsed_us));
+        stats
+    }
+}
+
+/// Returns the JSON representation of the profile of a transaction
+pub fn tx_profile_json(profile: &TxProfile) -> Value {
+    let contracts: serde_json::Map<_, _> = profile
+        .contracts
+        .iter()
+        .map(|(id, contract)| {
+            let value = json!({
+                "calls": contract.calls,
+                "gas_spent": contract.gas_spent,
+                "mem_growth": contract.mem_growth,
+            });
+            (id.to_string(), value)
+        })
+        .collect();
+
+    json!({
+        "elapsed_us": profile.elapsed.as_micros() as u64,
+        "host_calls": profile.host_calls,
+        "contracts": contracts,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use dusk_core::abi::CONTRACT_ID_BYTES;
+    use dusk_core::signatures::bls::SecretKey as BlsSecretKey;
+    use dusk_core::transfer::data::ContractCall;
+    use dusk_vm::profile::ContractProfile;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::*;
+
+    const CONTRACT: ContractId = ContractId::from_bytes([1; CONTRACT_ID_BYTES]);
+
+    fn tx(call: Option<ContractCall>) -> Transaction {
+        let rng = &mut StdRng::seed_from_u64(0xcafe);
+        let sk = BlsSecretKey::random(rng);
+        Transaction::moonlight(&sk, None, 0, 0, 1000, 1, 1, 0, call)
+            .expect("tx to be created")
+    }
+
+    fn profile(elapsed_ms: u64, contracts: &[(ContractId, u64)]) -> TxProfile {
+        TxProfile {
+            elapsed: Duration::from_millis(elapsed_ms),
+            host_calls: BTreeMap::from([("hash", 2)]),
+            contracts: contracts
+                .iter()
+                .map(|(id, gas_spent)| {
+                    let contract = ContractProfile {
+                        calls: 1,
+                        gas_spent: *gas_spent,
+                        mem_growth: 64,
+                    };
+                    (*id, contract)
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn aggregate_contract_profiles() {
+        let profiles = ContractProfiles::default();
+        let call = ContractCall::new(CONTRACT, "run", &()).unwrap();
+
+        // The wall time is attributed to the targeted contract, the gas to
+        // every called one
+        let contracts = [(TRANSFER_CONTRACT, 10), (CONTRACT, 90)];
+        profiles.record(&tx(Some(call.clone())), &profile(3, &contracts));
+        profiles.record(&tx(Some(call)), &profile(5, &[(CONTRACT, 50)]));
+        profiles.record(&tx(None), &profile(1, &[(TRANSFER_CONTRACT, 5)]));
+
+        let snapshot = profiles.snapshot();
+        assert_eq!(snapshot.len(), 2);
+
+        let (id, stats) = &snapshot[0];
+        assert_eq!(*id, CONTRACT);
+        assert_eq!(stats.txs, 2);
+        assert_eq!(stats.elapsed_us, 8_000);
+        assert_eq!(stats.max_elapsed_us, 5_000);
+        assert_eq!(stats.calls, 2);
+        assert_eq!(stats.gas_spent, 140);
+        assert_eq!(stats.mem_growth, 128);
+        assert_eq!(stats.host_calls.get("hash"), Some(&4));
+
+        let (id, stats) = &snapshot[1];
+        assert_eq!(*id, TRANSFER_CONTRACT);
+        assert_eq!(stats.txs, 1);
+        assert_eq!(stats.elapsed_us, 1_000);
+        assert_eq!(stats.calls, 2);
+        assert_eq!(stats.gas_spent, 15);
+    }
+}
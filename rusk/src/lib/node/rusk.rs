@@ -4,19 +4,20 @@
 //
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
+use std::collections::BTreeMap;
 use std::path::Path;
 use std::sync::{mpsc, Arc};
 use std::time::Instant;
 use std::{fs, io};
 
-use dusk_bytes::Serializable;
+use dusk_bytes::{DeserializableSlice, Serializable};
 use dusk_consensus::config::{
     ratification_extra, ratification_quorum, validation_extra,
     validation_quorum, MAX_NUMBER_OF_TRANSACTIONS,
     RATIFICATION_COMMITTEE_CREDITS, VALIDATION_COMMITTEE_CREDITS,
 };
 use dusk_consensus::operations::{CallParams, VerificationOutput, Voter};
-use dusk_core::abi::Event;
+use dusk_core::abi::{ContractError, ContractId, Event, ExecutionError};
 use dusk_core::signatures::bls::PublicKey as BlsPublicKey;
 use dusk_core::stake::{
     Reward, RewardReason, StakeData, StakeKeys, STAKE_CONTRACT,
@@ -25,22 +26,27 @@ use dusk_core::transfer::{
     moonlight::AccountData, PANIC_NONCE_NOT_READY, TRANSFER_CONTRACT,
 };
 use dusk_core::{BlsScalar, Dusk};
-use dusk_vm::{
-    execute, CallReceipt, Error as VMError, ExecutionConfig, Session, VM,
-};
+use dusk_vm::profile::{execute_profiled, TxProfile};
+use dusk_vm::trace::{execute_traced, TxTrace};
+use dusk_vm::{CallReceipt, Error as VMError, ExecutionConfig, Session, VM};
 #[cfg(feature = "archive")]
 use node::archive::Archive;
+use node::chain::StateDivergence;
+use node::vm::{BlockContext, ContractGas};
 use node_data::events::contract::ContractTxEvent;
 use node_data::ledger::{Hash, Slash, SpentTransaction, Transaction};
 use parking_lot::RwLock;
 use rusk_profile::to_rusk_state_id_path;
 use tokio::sync::broadcast;
-use tracing::info;
+use tracing::{debug, info};
 
-use super::RuskVmConfig;
+use super::sponsorship::{execute_sponsored, execute_tx, sponsorship_active};
+use super::stake_rules::StakeRules;
+use super::vm::FEATURE_ABI_PUBLIC_SENDER;
+use super::{ContractProfiles, Migrations, RuskVmConfig};
 use crate::bloom::Bloom;
 use crate::http::RuesEvent;
-use crate::node::{coinbase_value, Rusk, RuskTip};
+use crate::node::{coinbase_value, Rusk, RuskTip, SimulationResult};
 use crate::Error::InvalidCreditsCount;
 use crate::{Error, Result, DUSK_CONSENSUS_KEY};
 
@@ -89,28 +95,41 @@ impl Rusk {
             min_gas_limit,
             feeder_gas_limit,
             event_sender,
+            migrations: Migrations::default(),
+            profiles: ContractProfiles::default(),
+            side_commits: Arc::default(),
             #[cfg(feature = "archive")]
             archive,
         })
     }
 
+    /// Sets the contract state migrations known by the node.
+    ///
+    /// A migration is run at the block height its name is activated at in the
+    /// VM features.
+    pub fn with_migrations(mut self, migrations: Migrations) -> Self {
+        self.migrations = migrations;
+        self
+    }
+
     pub fn execute_transactions<I: Iterator<Item = Transaction>>(
         &self,
         params: &CallParams,
+        ctx: &BlockContext,
         txs: I,
     ) -> Result<(Vec<SpentTransaction>, Vec<Transaction>, VerificationOutput)>
     {
         let started = Instant::now();
 
-        let block_height = params.round;
-        let block_gas_limit = self.vm_config.block_gas_limit;
-        let generator = params.generator_pubkey.inner();
+        let block_height = ctx.height;
+        let block_gas_limit = ctx.gas_limit;
+        let (generator, execution_config) = block_execution(ctx)?;
         let to_slash = params.to_slash.clone();
         let prev_state_root = params.prev_state_root;
 
         let voters = &params.voters_pubkey[..];
 
-        let mut session =
+        let (mut session, block_events) =
             self.new_block_session(block_height, prev_state_root)?;
 
         let mut block_gas_left = block_gas_limit;
@@ -121,11 +140,19 @@ impl Rusk {
         let mut dusk_spent = 0;
 
         let mut event_bloom = Bloom::new();
+        event_bloom.add_events(&block_events);
 
-        let execution_config = self.vm_config.to_execution_config(block_height);
+        let mut stake_rules = StakeRules::new(self, prev_state_root, ctx)?;
+        let with_sponsorship = sponsorship_active(ctx, &mut session);
 
         // We always write the faults len in a u32
-        let mut size_left = params.max_txs_bytes - u32::SIZE;
+        let mut size_left = params.max_txs_bytes.saturating_sub(u32::SIZE);
+
+        // The bundle of transactions being executed, if any
+        let mut bundle: Option<BundleCheckpoint> = None;
+        // Each revert re-executes the transactions spent so far, the bundles
+        // past the maximum number of reverts are left out of the block
+        let mut bundle_reverts = 0;
 
         for unspent_tx in txs {
             if let Some(timeout) = self.vm_config.generation_timeout {
@@ -141,6 +168,57 @@ impl Rusk {
                 break;
             }
 
+            // A bundle is reverted as soon as one of its transactions is not
+            // included, or is not the next one to be executed
+            let position = unspent_tx.bundle().copied();
+            if let Some(cp) = bundle.take() {
+                let continued = position
+                    .is_some_and(|pos| pos.id == cp.id && pos.index == cp.fed);
+                if !cp.all_included(spent_txs.len())
+                    || (!cp.is_complete() && !continued)
+                {
+                    info!("Reverting bundle {}", hex::encode(cp.id));
+                    session = self.revert_bundle(
+                        &cp,
+                        block_height,
+                        prev_state_root,
+                        &execution_config,
+                        &mut spent_txs,
+                        &mut stake_rules,
+                    )?;
+                    bundle_reverts += 1;
+                    size_left = cp.size_left;
+                    block_gas_left = cp.block_gas_left;
+                    dusk_spent = cp.dusk_spent;
+                    event_bloom = cp.event_bloom;
+                } else if continued {
+                    bundle = Some(cp);
+                }
+            }
+
+            if let Some(pos) = position {
+                match bundle.as_mut() {
+                    Some(cp) => cp.fed += 1,
+                    None if pos.is_first()
+                        && bundle_reverts < MAX_BUNDLE_REVERTS =>
+                    {
+                        bundle = Some(BundleCheckpoint {
+                            id: pos.id,
+                            len: pos.len,
+                            fed: 1,
+                            spent_txs: spent_txs.len(),
+                            size_left,
+                            block_gas_left,
+                            dusk_spent,
+                            event_bloom,
+                        })
+                    }
+                    // The rest of a reverted bundle, or a bundle past the
+                    // maximum number of reverts
+                    None => continue,
+                }
+            }
+
             let tx_id_hex = hex::encode(unspent_tx.id());
             let tx_len = unspent_tx.size().unwrap_or_default();
 
@@ -154,7 +232,25 @@ impl Rusk {
                 continue;
             }
 
-            match execute(&mut session, &unspent_tx.inner, &execution_config) {
+            if unspent_tx.sponsorship().is_some() && !with_sponsorship {
+                info!("discard tx {tx_id_hex} due to inactive sponsorship");
+                discarded_txs.push(unspent_tx);
+                continue;
+            }
+
+            if let Some(rules) = stake_rules.as_mut() {
+                match rules.check(&unspent_tx.inner) {
+                    Ok(()) => {}
+                    Err(e @ Error::StakeRule(..)) => {
+                        info!("discard tx {tx_id_hex} due to {e}");
+                        discarded_txs.push(unspent_tx);
+                        continue;
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+
+            match execute_tx(&mut session, &unspent_tx, &execution_config) {
                 Ok(receipt) => {
                     let gas_spent = receipt.gas_spent;
 
@@ -163,15 +259,15 @@ impl Rusk {
                     // transaction, since it is technically valid.
                     if gas_spent > block_gas_left {
                         info!("Skipping {tx_id_hex} due gas_spent {gas_spent} greater than left: {block_gas_left}");
-                        session = self
+                        (session, _) = self
                             .new_block_session(block_height, prev_state_root)?;
 
                         for spent_tx in &spent_txs {
                             // We know these transactions were correctly
                             // executed before, so we don't bother checking.
-                            let _ = execute(
+                            let _ = execute_tx(
                                 &mut session,
-                                &spent_tx.inner.inner,
+                                &spent_tx.inner,
                                 &execution_config,
                             );
                         }
@@ -182,7 +278,7 @@ impl Rusk {
                     size_left -= tx_len;
 
                     // We're currently ignoring the result of successful calls
-                    let err = receipt.data.err().map(|e| format!("{e}"));
+                    let err = receipt.data.err().map(ExecutionError::from);
                     info!("Tx {tx_id_hex} executed with {gas_spent} gas and err {err:?}");
 
                     event_bloom.add_events(&receipt.events);
@@ -190,11 +286,18 @@ impl Rusk {
                     block_gas_left -= gas_spent;
                     let gas_price = unspent_tx.inner.gas_price();
                     dusk_spent += gas_spent * gas_price;
+                    if let Some(rules) = stake_rules.as_mut() {
+                        rules.mark(&unspent_tx.inner);
+                    }
                     spent_txs.push(SpentTransaction {
                         inner: unspent_tx,
                         gas_spent,
                         block_height,
                         err,
+                        gas_refunded: receipt
+                            .gas_limit
+                            .saturating_sub(gas_spent),
+                        fee_charged: gas_spent * gas_price,
                     });
                 }
                 Err(VMError::Panic(val)) if val == PANIC_NONCE_NOT_READY => {
@@ -214,11 +317,28 @@ impl Rusk {
             }
         }
 
+        if let Some(cp) = bundle.take() {
+            if !cp.all_included(spent_txs.len()) || !cp.is_complete() {
+                info!("Reverting bundle {}", hex::encode(cp.id));
+                session = self.revert_bundle(
+                    &cp,
+                    block_height,
+                    prev_state_root,
+                    &execution_config,
+                    &mut spent_txs,
+                    &mut stake_rules,
+                )?;
+                dusk_spent = cp.dusk_spent;
+                event_bloom = cp.event_bloom;
+            }
+        }
+
         let coinbase_events = reward_slash_and_update_root(
             &mut session,
             block_height,
+            ctx.economic.emission,
             dusk_spent,
-            generator,
+            &generator,
             to_slash,
             voters,
         )?;
@@ -238,33 +358,36 @@ impl Rusk {
     }
 
     /// Verify the given transactions are ok.
-    #[allow(clippy::too_many_arguments)]
     pub fn verify_transactions(
         &self,
         prev_commit: [u8; 32],
-        block_height: u64,
-        block_hash: Hash,
-        block_gas_limit: u64,
-        generator: &BlsPublicKey,
+        ctx: &BlockContext,
         txs: &[Transaction],
         slashing: Vec<Slash>,
         voters: &[Voter],
     ) -> Result<(Vec<SpentTransaction>, VerificationOutput)> {
-        let session = self.new_block_session(block_height, prev_commit)?;
-        let execution_config = self.vm_config.to_execution_config(block_height);
+        let (mut session, block_events) =
+            self.new_block_session(ctx.height, prev_commit)?;
+        if !sponsorship_active(ctx, &mut session) {
+            check_unsponsored(txs)?;
+        }
+        let (generator, execution_config) = block_execution(ctx)?;
 
         accept(
             session,
-            block_height,
-            block_hash,
-            block_gas_limit,
-            generator,
+            block_events,
+            ctx.height,
+            ctx.hash,
+            ctx.gas_limit,
+            ctx.economic.emission,
+            &generator,
             txs,
             slashing,
             voters,
             &execution_config,
+            None,
         )
-        .map(|(a, b, _, _)| (a, b))
+        .map(|(a, b, _, _, _)| (a, b))
     }
 
     /// Accept the given transactions.
@@ -278,14 +401,12 @@ impl Rusk {
     /// - VerificationOutput - The verification output.
     /// - Vec<ContractTxEvent> - All contract events that were emitted from the
     ///   given transactions.
+    /// - ContractGas - The gas spent calling each contract.
     #[allow(clippy::too_many_arguments)]
     pub fn accept_transactions(
         &self,
         prev_commit: [u8; 32],
-        block_height: u64,
-        block_gas_limit: u64,
-        block_hash: Hash,
-        generator: BlsPublicKey,
+        ctx: &BlockContext,
         txs: Vec<Transaction>,
         consistency_check: Option<VerificationOutput>,
         slashing: Vec<Slash>,
@@ -294,30 +415,47 @@ impl Rusk {
         Vec<SpentTransaction>,
         VerificationOutput,
         Vec<ContractTxEvent>,
+        ContractGas,
     )> {
-        let session = self.new_block_session(block_height, prev_commit)?;
-
-        let execution_config = self.vm_config.to_execution_config(block_height);
+        let block_height = ctx.height;
+        let block_hash = ctx.hash;
+        let (mut session, block_events) =
+            self.new_block_session(block_height, prev_commit)?;
+        if !sponsorship_active(ctx, &mut session) {
+            check_unsponsored(&txs)?;
+        }
 
-        let (spent_txs, verification_output, session, events) = accept(
-            session,
-            block_height,
-            block_hash,
-            block_gas_limit,
-            &generator,
-            &txs[..],
-            slashing,
-            voters,
-            &execution_config,
-        )?;
+        let (generator, execution_config) = block_execution(ctx)?;
+
+        let (spent_txs, verification_output, session, events, contract_gas) =
+            accept(
+                session,
+                block_events,
+                block_height,
+                block_hash,
+                ctx.gas_limit,
+                ctx.economic.emission,
+                &generator,
+                &txs[..],
+                slashing,
+                voters,
+                &execution_config,
+                self.vm_config.debug_receipts.then_some(&self.profiles),
+            )?;
 
         if let Some(expected_verification) = consistency_check {
             if expected_verification != verification_output {
                 // Drop the session if the resulting is inconsistent
                 // with the callers one.
-                return Err(Error::InconsistentState(Box::new(
+                let divergence = StateDivergence::new(
+                    block_height,
+                    block_hash,
+                    expected_verification,
                     verification_output,
-                )));
+                    &spent_txs,
+                    &events,
+                );
+                return Err(Error::InconsistentState(Box::new(divergence)));
             }
         }
 
@@ -331,7 +469,146 @@ impl Rusk {
         } // TODO: move this also in acceptor (async fn try_accept_block) where
           // stake events are filtered, to avoid looping twice?
 
-        Ok((spent_txs, verification_output, contract_events))
+        Ok((
+            spent_txs,
+            verification_output,
+            contract_events,
+            contract_gas,
+        ))
+    }
+
+    /// Re-executes the transactions of a historical block on top of
+    /// `prev_commit`, without checking the outcome nor moving the tip.
+    ///
+    /// The resulting state is stored in a side commit. Along with the spent
+    /// transactions and the verification output, returns whether such commit
+    /// didn't exist before, hence should be deleted once done with it.
+    #[allow(clippy::too_many_arguments)]
+    pub fn replay_transactions(
+        &self,
+        prev_commit: [u8; 32],
+        ctx: &BlockContext,
+        txs: &[Transaction],
+        slashing: Vec<Slash>,
+        voters: &[Voter],
+    ) -> Result<(Vec<SpentTransaction>, VerificationOutput, bool)> {
+        let session = self._session(ctx.height, Some(prev_commit))?;
+        let (session, block_events) =
+            self.prepare_block_session(ctx.height, session)?;
+
+        let (generator, execution_config) = block_execution(ctx)?;
+
+        let (spent_txs, verification_output, session, _, _) = accept(
+            session,
+            block_events,
+            ctx.height,
+            ctx.hash,
+            ctx.gas_limit,
+            ctx.economic.emission,
+            &generator,
+            txs,
+            slashing,
+            voters,
+            &execution_config,
+            None,
+        )?;
+
+        let new_commit =
+            !self.vm.commits().contains(&verification_output.state_root);
+        session.commit()?;
+        if new_commit {
+            self.side_commits
+                .lock()
+                .insert(verification_output.state_root);
+        }
+
+        Ok((spent_txs, verification_output, new_commit))
+    }
+
+    /// Re-executes the transaction at `index` of a historical block on top of
+    /// `prev_commit`, tracing its execution.
+    ///
+    /// The transactions preceding it in the block are executed first, so that
+    /// it's traced against the state it was originally executed on. Nothing is
+    /// committed.
+    pub fn trace_transaction(
+        &self,
+        prev_commit: [u8; 32],
+        block_height: u64,
+        txs: &[Transaction],
+        index: usize,
+    ) -> Result<(
+        CallReceipt<std::result::Result<Vec<u8>, ContractError>>,
+        TxTrace,
+    )> {
+        if !self.vm.commits().contains(&prev_commit) {
+            return Err(Error::CommitNotFound(prev_commit));
+        }
+        let tx = txs.get(index).ok_or_else(|| {
+            Error::Other(format!("No transaction at index {index}").into())
+        })?;
+
+        let session = self._session(block_height, Some(prev_commit))?;
+        let (mut session, _) =
+            self.prepare_block_session(block_height, session)?;
+
+        let execution_config = self.vm_config.to_execution_config(block_height);
+        for prev_tx in &txs[..index] {
+            execute_tx(&mut session, prev_tx, &execution_config)?;
+        }
+
+        let mut trace = None;
+        let receipt = execute_sponsored(&mut session, tx, |session| {
+            let (receipt, tx_trace) =
+                execute_traced(session, &tx.inner, &execution_config);
+            trace = Some(tx_trace);
+            receipt
+        })?;
+        Ok((receipt, trace.expect("the transaction to be traced")))
+    }
+
+    /// Executes `tx` on top of `state_root` as if it was included in a block
+    /// at `block_height`, profiling its execution if `profiled` is set.
+    /// Nothing is committed.
+    pub fn simulate_transaction(
+        &self,
+        state_root: [u8; 32],
+        block_height: u64,
+        tx: &Transaction,
+        profiled: bool,
+    ) -> Result<(SimulationResult, Option<TxProfile>)> {
+        if !self.vm.commits().contains(&state_root) {
+            return Err(Error::CommitNotFound(state_root));
+        }
+
+        let session = self._session(block_height, Some(state_root))?;
+        let (mut session, _) =
+            self.prepare_block_session(block_height, session)?;
+        let execution_config = self.vm_config.to_execution_config(block_height);
+
+        let mut profile = None;
+        let receipt = execute_sponsored(&mut session, tx, |session| {
+            if !profiled {
+                return dusk_vm::execute(session, &tx.inner, &execution_config);
+            }
+            let (receipt, tx_profile) =
+                execute_profiled(session, &tx.inner, &execution_config);
+            profile = Some(tx_profile);
+            receipt
+        });
+
+        let result = match receipt {
+            Ok(receipt) => SimulationResult {
+                gas_spent: receipt.gas_spent,
+                events: receipt.events.into_iter().map(Into::into).collect(),
+                error: receipt.data.err().map(|err| format!("{err:?}")),
+            },
+            Err(err) => SimulationResult {
+                error: Some(format!("{err:?}")),
+                ..Default::default()
+            },
+        };
+        Ok((result, profile))
     }
 
     pub fn finalize_state(
@@ -444,23 +721,112 @@ impl Rusk {
         self.query(STAKE_CONTRACT, "get_stake", pk)
     }
 
+    /// Returns the keys linked to the stake of `pk`.
+    pub fn stake_keys(&self, pk: &BlsPublicKey) -> Result<Option<StakeKeys>> {
+        self.query(STAKE_CONTRACT, "get_stake_keys", pk)
+    }
+
+    /// Reverts a candidate to its state before the execution of a bundle,
+    /// re-executing the transactions spent before it in a new session.
+    fn revert_bundle(
+        &self,
+        cp: &BundleCheckpoint,
+        block_height: u64,
+        prev_state_root: [u8; 32],
+        execution_config: &ExecutionConfig,
+        spent_txs: &mut Vec<SpentTransaction>,
+        stake_rules: &mut Option<StakeRules>,
+    ) -> Result<Session> {
+        for reverted in spent_txs.drain(cp.spent_txs..) {
+            if let Some(rules) = stake_rules.as_mut() {
+                rules.unmark(&reverted.inner.inner);
+            }
+        }
+
+        let (mut session, _) =
+            self.new_block_session(block_height, prev_state_root)?;
+        for spent_tx in spent_txs.iter() {
+            // We know these transactions were correctly executed before, so
+            // we don't bother checking.
+            let _ = execute_tx(&mut session, &spent_tx.inner, execution_config);
+        }
+
+        Ok(session)
+    }
+
     /// Opens a session for a new block proposal/verification.
     ///
     /// Before returning the session, "before_state_transition" of Stake
-    /// Contract is called
+    /// Contract is called, followed by the migrations activated at
+    /// `block_height`. The events emitted by the former, such as the stakes
+    /// moved by key rotations, are returned along with the session.
     pub(crate) fn new_block_session(
         &self,
         block_height: u64,
         commit: [u8; 32],
-    ) -> Result<Session> {
-        let mut session = self._session(block_height, None)?;
+    ) -> Result<(Session, Vec<Event>)> {
+        let session = self._session(block_height, None)?;
         if session.root() != commit {
             return Err(Error::TipChanged);
         }
-        let _: CallReceipt<()> = session
+        self.prepare_block_session(block_height, session)
+    }
+
+    /// Prepares `session` for the execution of a block at `block_height`,
+    /// calling "before_state_transition" of Stake Contract and running the
+    /// migrations activated at `block_height`.
+    ///
+    /// Returns the session along with the events of "before_state_transition".
+    fn prepare_block_session(
+        &self,
+        block_height: u64,
+        mut session: Session,
+    ) -> Result<(Session, Vec<Event>)> {
+        let receipt: CallReceipt<()> = session
             .call(STAKE_CONTRACT, "before_state_transition", &(), u64::MAX)
             .expect("before_state_transition to success");
-        Ok(session)
+        let session =
+            self.migrations
+                .apply(&self.vm_config, block_height, session)?;
+        Ok((session, receipt.events))
+    }
+
+    /// Runs the migration `name` on top of `commit` (the tip if `None`),
+    /// regardless of its activation height.
+    ///
+    /// The outcome is stored in a side commit, which is not set as the tip,
+    /// and whose root is returned so it can be inspected with queries.
+    /// The side commit should be removed with [`Rusk::delete_side_commit`]
+    /// once done with it.
+    pub fn dry_run_migration(
+        &self,
+        name: &str,
+        commit: Option<[u8; 32]>,
+    ) -> Result<[u8; 32]> {
+        let session = self.query_session(commit)?;
+        let session = self.migrations.apply_one(name, session)?;
+        let root = session.commit()?;
+        self.side_commits.lock().insert(root);
+        info!(event = "migration dry run", name, root = hex::encode(root));
+        Ok(root)
+    }
+
+    /// Deletes a side commit, i.e. one created by a migration dry run or by a
+    /// replay.
+    ///
+    /// Any other commit, such as the tip, the base commit, or the state of a
+    /// candidate block still in flight, cannot be deleted.
+    pub fn delete_side_commit(&self, commit: [u8; 32]) -> Result<()> {
+        let mut side_commits = self.side_commits.lock();
+        let tip = self.tip.read();
+        if commit == tip.current
+            || commit == tip.base
+            || !side_commits.remove(&commit)
+        {
+            return Err(Error::CommitNotFound(commit));
+        }
+        self.vm.delete_commit(commit)?;
+        Ok(())
     }
 
     /// Opens a session for query, setting a block height of zero since this
@@ -534,22 +900,78 @@ impl Rusk {
     }
 }
 
-#[allow(clippy::too_many_arguments)]
+/// Maximum number of bundles reverted while executing the transactions of a
+/// candidate block
+const MAX_BUNDLE_REVERTS: usize = 4;
+
+/// The candidate state before the execution of a bundle of transactions, to
+/// revert to if any of them is not included.
+struct BundleCheckpoint {
+    id: [u8; 32],
+    len: u32,
+    /// Number of transactions of the bundle fed to the execution so far
+    fed: u32,
+    spent_txs: usize,
+    size_left: usize,
+    block_gas_left: u64,
+    dusk_spent: u64,
+    event_bloom: Bloom,
+}
+
+impl BundleCheckpoint {
+    /// Whether all the transactions fed so far were included
+    fn all_included(&self, spent_txs: usize) -> bool {
+        self.spent_txs + self.fed as usize == spent_txs
+    }
+
+    fn is_complete(&self) -> bool {
+        self.fed >= self.len
+    }
+}
+
+/// Returns an error if any of `txs` is sponsored
+fn check_unsponsored(txs: &[Transaction]) -> Result<()> {
+    if txs.iter().any(|tx| tx.sponsorship().is_some()) {
+        return Err(Error::InactiveSponsorship);
+    }
+    Ok(())
+}
+
+/// Returns the generator of the block with the given context, along with the
+/// configuration its transactions are executed with
+fn block_execution(
+    ctx: &BlockContext,
+) -> Result<(BlsPublicKey, ExecutionConfig)> {
+    let generator = BlsPublicKey::from_slice(&ctx.generator.0)?;
+    let execution_config = ExecutionConfig {
+        gas_per_deploy_byte: ctx.economic.gas_per_deploy_byte,
+        min_deploy_points: ctx.economic.min_deploy_points,
+        min_deploy_gas_price: ctx.economic.min_deployment_gas_price,
+        with_public_sender: ctx.is_active(FEATURE_ABI_PUBLIC_SENDER),
+    };
+    Ok((generator, execution_config))
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
 fn accept(
     session: Session,
+    block_events: Vec<Event>,
     block_height: u64,
     block_hash: Hash,
     block_gas_limit: u64,
+    emission: Dusk,
     generator: &BlsPublicKey,
     txs: &[Transaction],
     slashing: Vec<Slash>,
     voters: &[Voter],
     execution_config: &ExecutionConfig,
+    profiles: Option<&ContractProfiles>,
 ) -> Result<(
     Vec<SpentTransaction>,
     VerificationOutput,
     Session,
     Vec<ContractTxEvent>,
+    ContractGas,
 )> {
     let mut session = session;
 
@@ -560,12 +982,38 @@ fn accept(
 
     let mut events = Vec::new();
     let mut event_bloom = Bloom::new();
+    let mut contract_gas = BTreeMap::new();
+
+    // The events emitted at the start of the block precede the ones of its
+    // transactions
+    event_bloom.add_events(&block_events);
+    events.extend(block_events.into_iter().map(|event| ContractTxEvent {
+        event: event.into(),
+        origin: block_hash,
+    }));
 
     for unspent_tx in txs {
         let tx = &unspent_tx.inner;
         let tx_id = unspent_tx.id();
-        let receipt = execute(&mut session, tx, execution_config)?;
+        let receipt = match profiles {
+            Some(profiles) => {
+                execute_sponsored(&mut session, unspent_tx, |session| {
+                    let (receipt, profile) =
+                        execute_profiled(session, tx, execution_config);
+                    debug!(
+                        event = "tx profiled",
+                        tx_id = hex::encode(tx_id),
+                        elapsed = ?profile.elapsed,
+                        host_calls = ?profile.host_calls,
+                    );
+                    profiles.record(tx, &profile);
+                    receipt
+                })?
+            }
+            None => execute_tx(&mut session, unspent_tx, execution_config)?,
+        };
 
+        bill_contract_gas(&mut contract_gas, &receipt);
         event_bloom.add_events(&receipt.events);
 
         let tx_events: Vec<_> = receipt
@@ -581,7 +1029,8 @@ fn accept(
 
         let gas_spent = receipt.gas_spent;
 
-        dusk_spent += gas_spent * tx.gas_price();
+        let fee_charged = gas_spent * tx.gas_price();
+        dusk_spent += fee_charged;
         block_gas_left = block_gas_left
             .checked_sub(gas_spent)
             .ok_or(Error::OutOfGas)?;
@@ -591,13 +1040,16 @@ fn accept(
             gas_spent,
             block_height,
             // We're currently ignoring the result of successful calls
-            err: receipt.data.err().map(|e| format!("{e}")),
+            err: receipt.data.err().map(ExecutionError::from),
+            gas_refunded: receipt.gas_limit.saturating_sub(gas_spent),
+            fee_charged,
         });
     }
 
     let coinbase_events = reward_slash_and_update_root(
         &mut session,
         block_height,
+        emission,
         dusk_spent,
         generator,
         slashing,
@@ -625,19 +1077,47 @@ fn accept(
         },
         session,
         events,
+        contract_gas.into_iter().collect(),
     ))
 }
 
+/// Bills the gas spent by the transaction executed with `receipt` to the
+/// contracts it called.
+///
+/// As in the execution profiles, each contract is billed the gas spent by its
+/// calls, including their nested calls. The transfer contract, through which
+/// every transaction is executed, is billed the whole gas spent by the
+/// transaction instead, which also counts the deployment charge and the gas
+/// limit spent by a failed call.
+fn bill_contract_gas(
+    contract_gas: &mut BTreeMap<ContractId, u64>,
+    receipt: &CallReceipt<std::result::Result<Vec<u8>, ContractError>>,
+) {
+    let mut tx_gas = BTreeMap::new();
+    for elem in receipt.call_tree.iter() {
+        let gas: &mut u64 = tx_gas.entry(elem.contract_id).or_default();
+        *gas = gas.saturating_add(elem.spent);
+    }
+    tx_gas.insert(TRANSFER_CONTRACT, receipt.gas_spent);
+
+    for (contract, gas) in tx_gas {
+        let total: &mut u64 = contract_gas.entry(contract).or_default();
+        *total = total.saturating_add(gas);
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 fn reward_slash_and_update_root(
     session: &mut Session,
     block_height: u64,
+    emission: Dusk,
     dusk_spent: Dusk,
     generator: &BlsPublicKey,
     slashing: Vec<Slash>,
     voters: &[Voter],
 ) -> Result<Vec<Event>> {
     let (dusk_value, generator_reward, generator_extra_reward, voters_reward) =
-        coinbase_value(block_height, dusk_spent);
+        coinbase_value(emission, dusk_spent);
 
     let credits = voters
         .iter()
@@ -0,0 +1,213 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Archives of the finalized state, exchanged by the fast sync.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc};
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, bail};
+use dusk_consensus::user::provisioners::Provisioners;
+use dusk_core::stake::{StakeData, StakeKeys, STAKE_CONTRACT};
+use dusk_vm::VM;
+use node::chain::conf::FastSyncParams;
+use node::chain::fast_sync::StagedState;
+use node_data::bls::PublicKey;
+use parking_lot::Mutex;
+use rusk_profile::to_rusk_state_id_path;
+use rusk_recovery_tools::state::{tar, verify_state_root};
+use tracing::info;
+
+use super::Rusk;
+
+impl Rusk {
+    /// Archives the state directory, returning the finalized root along with
+    /// the archive.
+    ///
+    /// The export fails if the archive is larger than `max_size` bytes, or if
+    /// the state is finalized further in the meantime, since the archived
+    /// commits could be merged while being read.
+    pub fn state_snapshot(
+        &self,
+        max_size: u64,
+    ) -> anyhow::Result<([u8; 32], Vec<u8>)> {
+        let base = self.base_root();
+
+        let file = tempfile::NamedTempFile::new()?;
+        tar::archive(&self.dir, file.path())
+            .map_err(|e| anyhow!("Cannot archive state: {e}"))?;
+
+        let size = file.as_file().metadata()?.len();
+        if size > max_size {
+            bail!("State archive of {size} bytes above {max_size} bytes");
+        }
+        if self.base_root() != base {
+            bail!("State finalized during the export");
+        }
+        Ok((base, fs::read(file.path())?))
+    }
+}
+
+/// Exports of the state served to the fast sync of other nodes
+#[derive(Clone)]
+pub struct StateExport {
+    enabled: bool,
+    pub(crate) max_size: u64,
+    interval: Duration,
+    last: Arc<Mutex<Option<Instant>>>,
+}
+
+impl Default for StateExport {
+    fn default() -> Self {
+        Self::new(&FastSyncParams::default())
+    }
+}
+
+impl StateExport {
+    pub fn new(conf: &FastSyncParams) -> Self {
+        Self {
+            enabled: conf.serve_state,
+            max_size: conf.max_state_size,
+            interval: conf.state_export_interval,
+            last: Arc::default(),
+        }
+    }
+
+    /// Reserves an export, failing if exports are disabled or if the last one
+    /// started less than the configured interval ago
+    pub(crate) fn acquire(&self) -> anyhow::Result<()> {
+        if !self.enabled {
+            bail!("State export disabled");
+        }
+
+        let now = Instant::now();
+        let mut last = self.last.lock();
+        if let Some(elapsed) = last.map(|last| now.duration_since(last)) {
+            if elapsed < self.interval {
+                let wait = self.interval - elapsed;
+                bail!("State exported recently, retry in {}s", wait.as_secs());
+            }
+        }
+        *last = Some(now);
+        Ok(())
+    }
+}
+
+/// State unpacked from a fast sync archive next to the state directory,
+/// replacing it once committed.
+///
+/// The unpacked state is removed if it isn't committed.
+pub struct StagedSnapshot {
+    vm: Option<VM>,
+    staging: PathBuf,
+    state_dir: PathBuf,
+    state_root: [u8; 32],
+    chain_id: u8,
+    gas_limit: u64,
+}
+
+/// Unpacks `archive` next to `state_dir`, if its finalized root is
+/// `state_root`.
+///
+/// The root is checked against the memory of the unpacked contracts, see
+/// [`verify_state_root`]. `chain_id` and `gas_limit` are the ones of the
+/// queries to the staged state.
+pub fn stage_state_snapshot(
+    state_dir: &Path,
+    archive: &[u8],
+    state_root: [u8; 32],
+    chain_id: u8,
+    gas_limit: u64,
+) -> anyhow::Result<StagedSnapshot> {
+    let staging = state_dir.with_extension("fast-sync");
+    if staging.exists() {
+        fs::remove_dir_all(&staging)?;
+    }
+    fs::create_dir_all(&staging)?;
+
+    let mut staged = StagedSnapshot {
+        vm: None,
+        staging,
+        state_dir: state_dir.to_path_buf(),
+        state_root,
+        chain_id,
+        gas_limit,
+    };
+
+    tar::unarchive(archive, &staged.staging)
+        .map_err(|e| anyhow!("Cannot unarchive state: {e}"))?;
+    let root = fs::read(to_rusk_state_id_path(&staged.staging))?;
+    if root != state_root {
+        bail!("State root mismatch");
+    }
+
+    let vm = VM::new(&staged.staging)
+        .map_err(|e| anyhow!("Cannot load state: {e}"))?;
+    verify_state_root(&vm, &staged.staging, state_root)
+        .map_err(|e| anyhow!("{e}"))?;
+    staged.vm = Some(vm);
+
+    Ok(staged)
+}
+
+impl StagedState for StagedSnapshot {
+    fn provisioners(&self) -> anyhow::Result<Provisioners> {
+        let vm = self
+            .vm
+            .as_ref()
+            .ok_or_else(|| anyhow!("State not staged"))?;
+        let mut session = vm
+            .session(self.state_root, self.chain_id, 0)
+            .map_err(|e| anyhow!("Cannot open state: {e}"))?;
+
+        let (sender, receiver) = mpsc::channel();
+        session
+            .feeder_call::<_, ()>(
+                STAKE_CONTRACT,
+                "stakes",
+                &(),
+                self.gas_limit,
+                sender,
+            )
+            .map_err(|e| anyhow!("Cannot get provisioners: {e}"))?;
+
+        let mut provisioners = Provisioners::empty();
+        for bytes in receiver {
+            let (keys, stake) =
+                rkyv::from_bytes::<(StakeKeys, StakeData)>(&bytes)
+                    .map_err(|e| anyhow!("Invalid stake: {e:?}"))?;
+            provisioners.add_member_with_stake(
+                PublicKey::new(keys.account),
+                Rusk::to_stake(stake),
+            );
+        }
+        Ok(provisioners)
+    }
+
+    fn commit(mut self) -> anyhow::Result<()> {
+        // The staged state files are released before being moved
+        self.vm = None;
+
+        if self.state_dir.exists() {
+            fs::remove_dir_all(&self.state_dir)?;
+        }
+        fs::rename(&self.staging, &self.state_dir)?;
+
+        info!("State restored at {}", hex::encode(self.state_root));
+        Ok(())
+    }
+}
+
+impl Drop for StagedSnapshot {
+    fn drop(&mut self) {
+        self.vm = None;
+        if self.staging.exists() {
+            let _ = fs::remove_dir_all(&self.staging);
+        }
+    }
+}
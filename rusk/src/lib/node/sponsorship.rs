@@ -0,0 +1,123 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Execution of the transactions whose gas is paid by a sponsor.
+//!
+//! Before a sponsored transaction is executed, its sponsorship is handed to
+//! the transfer contract, which verifies it along with the signature of the
+//! sender committing to the sponsor. The contract then charges the gas limit
+//! of the transaction to the sponsor, and refunds it the gas left after the
+//! execution. The sponsor ends up paying the gas spent, and the sender only
+//! the value transferred and deposited.
+//!
+//! A transaction with an invalid sponsorship, or whose sponsor can't cover
+//! its gas limit, is unspendable.
+//!
+//! Blocks can include sponsored transactions once the `TX_SPONSORSHIP`
+//! feature is active, and the transfer contract is one charging the sponsors.
+//! The transfer contract deployed before the sponsorships has no `sponsor`, nor
+//! `get_version`, method, hence the sponsored transactions are rejected until
+//! it's upgraded.
+
+use dusk_core::abi::ContractError;
+use dusk_core::transfer::sponsor::Sponsorship;
+use dusk_core::transfer::{
+    Transaction as ProtocolTransaction, TRANSFER_CONTRACT,
+};
+use dusk_vm::{
+    execute, CallReceipt, Error as VMError, ExecutionConfig, Session,
+};
+use node::vm::BlockContext;
+use node_data::ledger::Transaction;
+
+use super::vm::FEATURE_TX_SPONSORSHIP;
+use crate::node::Rusk;
+use crate::{verifier, Error};
+
+type Receipt = CallReceipt<Result<Vec<u8>, ContractError>>;
+
+/// Version of the transfer contract introducing the sponsorships
+const SPONSOR_TRANSFER_VERSION: u64 = 1;
+
+/// Returns true if the block within `ctx`, executed in `session`, can include
+/// sponsored transactions
+pub(crate) fn sponsorship_active(
+    ctx: &BlockContext,
+    session: &mut Session,
+) -> bool {
+    ctx.is_active(FEATURE_TX_SPONSORSHIP) && transfer_sponsors(session)
+}
+
+/// Returns true if the transfer contract in `session` charges the sponsors
+fn transfer_sponsors(session: &mut Session) -> bool {
+    session
+        .call::<_, u64>(TRANSFER_CONTRACT, "get_version", &(), u64::MAX)
+        .is_ok_and(|receipt| receipt.data >= SPONSOR_TRANSFER_VERSION)
+}
+
+/// Executes `tx`, charging its gas to its sponsor, if any.
+pub(crate) fn execute_tx(
+    session: &mut Session,
+    tx: &Transaction,
+    config: &ExecutionConfig,
+) -> Result<Receipt, VMError> {
+    execute_sponsored(session, tx, |session| {
+        execute(session, &tx.inner, config)
+    })
+}
+
+/// Executes `tx` through `execute`, charging its gas to its sponsor, if any.
+pub(crate) fn execute_sponsored<F>(
+    session: &mut Session,
+    tx: &Transaction,
+    execute: F,
+) -> Result<Receipt, VMError>
+where
+    F: FnOnce(&mut Session) -> Result<Receipt, VMError>,
+{
+    if let Some(sponsorship) = tx.sponsorship() {
+        session.call::<_, ()>(
+            TRANSFER_CONTRACT,
+            "sponsor",
+            &(tx.inner.hash(), sponsorship.clone()),
+            u64::MAX,
+        )?;
+    }
+    execute(session)
+}
+
+/// Checks the sponsorship of a transaction entering the mempool against the
+/// current state, to be included in a block at `block_height`
+pub(crate) fn preverify_sponsorship(
+    rusk: &Rusk,
+    tx: &ProtocolTransaction,
+    sponsorship: &Sponsorship,
+    block_height: u64,
+) -> anyhow::Result<()> {
+    let mut session = rusk
+        .query_session(None)
+        .map_err(|e| anyhow::anyhow!("Cannot open a session: {e}"))?;
+    if !rusk.vm_config.with_sponsorship(block_height)
+        || !transfer_sponsors(&mut session)
+    {
+        let err = Error::InactiveSponsorship;
+        return Err(anyhow::anyhow!("Invalid tx: {err}"));
+    }
+
+    let sponsor = rusk
+        .account(sponsorship.sponsor())
+        .map_err(|e| anyhow::anyhow!("Cannot check sponsor account: {e}"))?;
+    let max_fee = tx.gas_limit().saturating_mul(tx.gas_price());
+    if max_fee > sponsor.balance {
+        return Err(anyhow::anyhow!("Gas limit larger than sponsor holds"));
+    }
+
+    match verifier::verify_sponsorship(tx, sponsorship) {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(anyhow::anyhow!("Invalid sponsorship")),
+        Err(e) => Err(anyhow::anyhow!("Cannot verify the sponsorship: {e}")),
+    }
+}
@@ -0,0 +1,229 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Stake rules enforced on the transactions of a candidate block.
+//!
+//! The stake contract already rejects calls breaking its rules, but such
+//! transactions are still included in a block as failed ones. Once the
+//! `STAKE_RULES` feature is active, the calls to `stake`, `unstake`,
+//! `withdraw` and `rotate_key` are checked against the stake contract state at
+//! the parent of the candidate, and a candidate including an invalid call is
+//! rejected.
+//!
+//! Only the first call per account is checked, since the following ones
+//! depend on the state left by the previous ones.
+//!
+//! Once the `STAKE_POP` feature is active, every stake must also carry a valid
+//! proof of possession of its account key, so that no rogue key can take part
+//! in the aggregation of the committee signatures. Such stakes are only
+//! decoded by the stake contract it upgrades to, see
+//! [`crate::node::node_migrations`].
+//!
+//! The calls to `rotate_key` are rejected until the `STAKE_KEY_ROTATION`
+//! feature is active.
+
+use std::collections::BTreeSet;
+
+use dusk_bytes::Serializable;
+use dusk_core::signatures::bls::PublicKey as BlsPublicKey;
+use dusk_core::stake::{
+    parse_stake_call, StakeCall, StakeConfig, StakeData, VersionedWithdraw,
+    STAKE_CONTRACT,
+};
+use dusk_core::transfer::Transaction as ProtocolTransaction;
+use dusk_vm::Session;
+use node::vm::BlockContext;
+
+use super::vm::{
+    FEATURE_STAKE_KEY_ROTATION, FEATURE_STAKE_POP, FEATURE_STAKE_RULES,
+};
+use crate::node::Rusk;
+use crate::{Error, Result};
+
+pub(crate) struct StakeRules {
+    session: Session,
+    config: StakeConfig,
+    gas_limit: u64,
+    /// Whether the calls are checked against the stake contract state
+    with_rules: bool,
+    /// Whether stakes must carry a proof of possession
+    with_pop: bool,
+    /// Whether stake account keys can be rotated
+    with_rotation: bool,
+    checked: BTreeSet<[u8; BlsPublicKey::SIZE]>,
+}
+
+impl StakeRules {
+    /// Opens the stake rules at the parent state of a candidate within
+    /// `ctx`, returning `None` if no rule applies to it.
+    pub fn new(
+        rusk: &Rusk,
+        prev_commit: [u8; 32],
+        ctx: &BlockContext,
+    ) -> Result<Option<Self>> {
+        let with_rules = ctx.is_active(FEATURE_STAKE_RULES);
+        let with_pop = ctx.is_active(FEATURE_STAKE_POP);
+        let with_rotation = ctx.is_active(FEATURE_STAKE_KEY_ROTATION);
+        if !with_rules && !with_pop && with_rotation {
+            return Ok(None);
+        }
+
+        let mut session = rusk.query_session(Some(prev_commit))?;
+        let gas_limit = ctx.gas_limit;
+        let config = session
+            .call::<_, StakeConfig>(
+                STAKE_CONTRACT,
+                "get_config",
+                &(),
+                gas_limit,
+            )?
+            .data;
+
+        Ok(Some(Self {
+            session,
+            config,
+            gas_limit,
+            with_rules,
+            with_pop,
+            with_rotation,
+            checked: BTreeSet::new(),
+        }))
+    }
+
+    /// Checks the stake call of `tx`, if any.
+    ///
+    /// Transactions not calling the stake contract, or whose arguments can't
+    /// be decoded, are left to the contract.
+    pub fn check(&mut self, tx: &ProtocolTransaction) -> Result<()> {
+        let Some((account, call)) = stake_call(tx) else {
+            return Ok(());
+        };
+
+        if let StakeCall::Stake(stake) = &call {
+            let pop = stake.pop();
+            if self.with_pop && !pop.is_some_and(|pop| pop.verify(&account)) {
+                let reason = "stake without a valid proof of possession";
+                return Err(Error::StakeRule(Box::new(account), reason.into()));
+            }
+        }
+        if matches!(call, StakeCall::RotateKey(_)) && !self.with_rotation {
+            let reason = "key rotation not active";
+            return Err(Error::StakeRule(Box::new(account), reason.into()));
+        }
+
+        if !self.with_rules || self.checked.contains(&account.to_bytes()) {
+            return Ok(());
+        }
+
+        let stake = self
+            .session
+            .call::<_, Option<StakeData>>(
+                STAKE_CONTRACT,
+                "get_stake",
+                &account,
+                self.gas_limit,
+            )?
+            .data;
+        let amount = stake.as_ref().and_then(|s| s.amount);
+        let minimum_stake = self.config.minimum_stake;
+
+        let violation = match call {
+            StakeCall::Stake(stake) => {
+                let value = stake.stake().value();
+                (amount.is_none() && value < minimum_stake).then(|| {
+                    format!("stake {value} lower than minimum {minimum_stake}")
+                })
+            }
+            StakeCall::Unstake(VersionedWithdraw::V1(unstake)) => {
+                let value = unstake.transfer_withdraw().value();
+                match amount {
+                    None => Some("unstake with no stake".into()),
+                    Some(amount) if value > amount.total_funds() => {
+                        Some(format!("unstake {value} higher than staked"))
+                    }
+                    Some(amount) => {
+                        let left = amount.total_funds() - value;
+                        let below_minimum = left > 0 && left < minimum_stake;
+                        below_minimum.then(|| {
+                            format!("stake left {left} below {minimum_stake}")
+                        })
+                    }
+                }
+            }
+            StakeCall::Withdraw(VersionedWithdraw::V1(withdraw)) => {
+                let value = withdraw.transfer_withdraw().value();
+                let reward = stake.map(|s| s.reward).unwrap_or_default();
+                match stake {
+                    None => Some("withdraw with no stake".into()),
+                    Some(_) if value == 0 => {
+                        Some("withdraw of 0 reward".into())
+                    }
+                    Some(_) if value > reward => Some(format!(
+                        "withdraw {value} higher than reward {reward}"
+                    )),
+                    Some(_) => None,
+                }
+            }
+            StakeCall::RotateKey(_) => amount
+                .is_none()
+                .then(|| "key rotation with no stake".into()),
+        };
+
+        match violation {
+            Some(reason) => Err(Error::StakeRule(Box::new(account), reason)),
+            None => Ok(()),
+        }
+    }
+
+    /// Marks the account of the stake call of `tx` as checked.
+    pub fn mark(&mut self, tx: &ProtocolTransaction) {
+        if let Some((account, _)) = stake_call(tx) {
+            self.checked.insert(account.to_bytes());
+        }
+    }
+
+    /// Marks the account of the stake call of `tx` to be checked again, when
+    /// `tx` is reverted.
+    pub fn unmark(&mut self, tx: &ProtocolTransaction) {
+        if let Some((account, _)) = stake_call(tx) {
+            self.checked.remove(&account.to_bytes());
+        }
+    }
+}
+
+/// Checks all the stake calls of the transactions of a candidate block
+/// within `ctx`.
+pub(crate) fn verify_stake_rules<'a, I>(
+    rusk: &Rusk,
+    prev_commit: [u8; 32],
+    ctx: &BlockContext,
+    txs: I,
+) -> Result<()>
+where
+    I: IntoIterator<Item = &'a ProtocolTransaction>,
+{
+    let Some(mut rules) = StakeRules::new(rusk, prev_commit, ctx)? else {
+        return Ok(());
+    };
+    for tx in txs {
+        rules.check(tx)?;
+        rules.mark(tx);
+    }
+    Ok(())
+}
+
+fn stake_call(tx: &ProtocolTransaction) -> Option<(BlsPublicKey, StakeCall)> {
+    let call = parse_stake_call(tx.call()?).ok()??;
+    let account = match &call {
+        StakeCall::Stake(stake) => stake.stake().keys().account,
+        StakeCall::Unstake(VersionedWithdraw::V1(withdraw))
+        | StakeCall::Withdraw(VersionedWithdraw::V1(withdraw)) => {
+            *withdraw.account()
+        }
+        StakeCall::RotateKey(rotation) => *rotation.account(),
+    };
+    Some((account, call))
+}
@@ -7,6 +7,8 @@
 mod config;
 mod query;
 
+use std::collections::BTreeSet;
+
 use dusk_consensus::errors::VstError;
 use node_data::events::contract::ContractTxEvent;
 use tracing::{debug, info};
@@ -16,21 +18,29 @@ use dusk_consensus::operations::{CallParams, VerificationOutput, Voter};
 use dusk_consensus::user::provisioners::Provisioners;
 use dusk_consensus::user::stake::Stake;
 use dusk_core::{
-    signatures::bls::PublicKey as BlsPublicKey, stake::StakeData,
-    transfer::Transaction as ProtocolTransaction,
+    abi::ContractId, signatures::bls::PublicKey as BlsPublicKey,
+    stake::StakeData, transfer::Transaction as ProtocolTransaction,
+};
+use node::vm::{
+    BlockContext, ContractGas, EconomicParams, PreverificationResult,
+    VMExecution,
 };
-use node::vm::{PreverificationResult, VMExecution};
 use node_data::bls::PublicKey;
 use node_data::ledger::{Block, Slash, SpentTransaction, Transaction};
 
+use super::emission_amount;
+use super::preverify::preverify_txs;
+use super::sponsorship::preverify_sponsorship;
+use super::stake_rules::verify_stake_rules;
 use super::Rusk;
 pub use config::feature::*;
-pub use config::Config as RuskVmConfig;
+pub use config::{Config as RuskVmConfig, ContractUpgrade};
 
 impl VMExecution for Rusk {
     fn execute_state_transition<I: Iterator<Item = Transaction>>(
         &self,
         params: &CallParams,
+        ctx: &BlockContext,
         txs: I,
     ) -> anyhow::Result<(
         Vec<SpentTransaction>,
@@ -39,8 +49,9 @@ impl VMExecution for Rusk {
     )> {
         info!("Received execute_state_transition request");
 
-        let (txs, discarded_txs, verification_output) =
-            self.execute_transactions(params, txs).map_err(|inner| {
+        let (txs, discarded_txs, verification_output) = self
+            .execute_transactions(params, ctx, txs)
+            .map_err(|inner| {
                 anyhow::anyhow!("Cannot execute txs: {inner}!!")
             })?;
 
@@ -51,27 +62,31 @@ impl VMExecution for Rusk {
         &self,
         prev_commit: [u8; 32],
         blk: &Block,
+        ctx: &BlockContext,
         voters: &[Voter],
     ) -> Result<VerificationOutput, VstError> {
         info!("Received verify_state_transition request");
-        let generator = blk.header().generator_bls_pubkey;
-        let generator = BlsPublicKey::from_slice(&generator.0)
+        // Checked upfront to be reported as such
+        BlsPublicKey::from_slice(&ctx.generator.0)
             .map_err(VstError::InvalidGenerator)?;
 
         let slashing =
             Slash::from_block(blk).map_err(VstError::InvalidSlash)?;
 
+        let txs = blk.txs().iter().map(|tx| &tx.inner);
+        verify_stake_rules(self, prev_commit, ctx, txs).map_err(|inner| {
+            match inner {
+                crate::Error::StakeRule(..) => {
+                    VstError::InvalidStakeCall(format!("{inner}"))
+                }
+                inner => VstError::Generic(format!(
+                    "Cannot verify stake calls: {inner}!!"
+                )),
+            }
+        })?;
+
         let (_, verification_output) = self
-            .verify_transactions(
-                prev_commit,
-                blk.header().height,
-                blk.header().hash,
-                blk.header().gas_limit,
-                &generator,
-                blk.txs(),
-                slashing,
-                voters,
-            )
+            .verify_transactions(prev_commit, ctx, blk.txs(), slashing, voters)
             .map_err(|inner| {
                 if let crate::Error::TipChanged = inner {
                     VstError::TipChanged
@@ -87,26 +102,24 @@ impl VMExecution for Rusk {
         &self,
         prev_root: [u8; 32],
         blk: &Block,
+        ctx: &BlockContext,
         voters: &[Voter],
     ) -> anyhow::Result<(
         Vec<SpentTransaction>,
         VerificationOutput,
         Vec<ContractTxEvent>,
+        ContractGas,
     )> {
         debug!("Received accept request");
-        let generator = blk.header().generator_bls_pubkey;
-        let generator = BlsPublicKey::from_slice(&generator.0)
-            .map_err(|e| anyhow::anyhow!("Error in from_slice {e:?}"))?;
-
         let slashing = Slash::from_block(blk)?;
 
-        let (txs, verification_output, contract_events) = self
+        preverify_txs(self, prev_root, blk.txs())
+            .map_err(|inner| anyhow::anyhow!("Invalid txs: {inner}"))?;
+
+        let (txs, verification_output, contract_events, contract_gas) = self
             .accept_transactions(
                 prev_root,
-                blk.header().height,
-                blk.header().gas_limit,
-                blk.header().hash,
-                generator,
+                ctx,
                 blk.txs().clone(),
                 Some(VerificationOutput {
                     state_root: blk.header().state_hash,
@@ -115,9 +128,34 @@ impl VMExecution for Rusk {
                 slashing,
                 voters,
             )
-            .map_err(|inner| anyhow::anyhow!("Cannot accept txs: {inner}!!"))?;
+            .map_err(|inner| match inner {
+                // Passed as is for the acceptor to report it
+                crate::Error::InconsistentState(divergence) => {
+                    anyhow::Error::new(*divergence)
+                }
+                inner => anyhow::anyhow!("Cannot accept txs: {inner}!!"),
+            })?;
+
+        Ok((txs, verification_output, contract_events, contract_gas))
+    }
+
+    fn replay(
+        &self,
+        prev_root: [u8; 32],
+        blk: &Block,
+        ctx: &BlockContext,
+        voters: &[Voter],
+    ) -> anyhow::Result<(Vec<SpentTransaction>, VerificationOutput, bool)> {
+        debug!("Received replay request");
+        let slashing = Slash::from_block(blk)?;
 
-        Ok((txs, verification_output, contract_events))
+        self.replay_transactions(prev_root, ctx, blk.txs(), slashing, voters)
+            .map_err(|inner| anyhow::anyhow!("Cannot replay txs: {inner}"))
+    }
+
+    fn delete_side_commit(&self, commit: [u8; 32]) -> anyhow::Result<()> {
+        self.delete_side_commit(commit)
+            .map_err(|e| anyhow::anyhow!("Cannot delete commit: {e}"))
     }
 
     fn move_to_commit(&self, commit: [u8; 32]) -> anyhow::Result<()> {
@@ -140,8 +178,10 @@ impl VMExecution for Rusk {
     fn preverify(
         &self,
         tx: &Transaction,
+        block_height: u64,
     ) -> anyhow::Result<PreverificationResult> {
         info!("Received preverify request");
+        let sponsorship = tx.sponsorship().map(|s| (&tx.inner, s));
         let tx = &tx.inner;
 
         match tx {
@@ -176,9 +216,20 @@ impl VMExecution for Rusk {
                     anyhow::anyhow!("Cannot check account: {e}")
                 })?;
 
-                let max_value = tx
-                    .gas_limit()
-                    .checked_mul(tx.gas_price())
+                // The gas of a sponsored transaction is paid by its sponsor
+                let max_fee = match sponsorship {
+                    Some((protocol_tx, sponsorship)) => {
+                        preverify_sponsorship(
+                            self,
+                            protocol_tx,
+                            sponsorship,
+                            block_height,
+                        )?;
+                        Some(0)
+                    }
+                    None => tx.gas_limit().checked_mul(tx.gas_price()),
+                };
+                let max_value = max_fee
                     .and_then(|v| v.checked_add(tx.value()))
                     .and_then(|v| v.checked_add(tx.deposit()))
                     .ok_or(anyhow::anyhow!("Value spent will overflow"))?;
@@ -207,7 +258,16 @@ impl VMExecution for Rusk {
                     PreverificationResult::Valid
                 };
 
-                match crate::verifier::verify_signature(tx) {
+                let valid = match sponsorship {
+                    Some((_, sponsorship)) => {
+                        crate::verifier::verify_sponsored_signature(
+                            tx,
+                            sponsorship.sponsor(),
+                        )
+                    }
+                    None => crate::verifier::verify_signature(tx),
+                };
+                match valid {
                     Ok(true) => Ok(result),
                     Ok(false) => Err(anyhow::anyhow!("Invalid signature")),
                     Err(e) => {
@@ -232,6 +292,35 @@ impl VMExecution for Rusk {
         self.query_provisioners_change(Some(base_commit))
     }
 
+    fn get_provisioners_root(
+        &self,
+        block_height: u64,
+        base_commit: [u8; 32],
+    ) -> anyhow::Result<Option<[u8; 32]>> {
+        if !self.vm_config.with_provisioners_root(block_height) {
+            return Ok(None);
+        }
+        let provisioners = self.query_provisioners(Some(base_commit))?;
+        Ok(Some(provisioners.commitment()))
+    }
+
+    fn with_header_extra_data(&self, block_height: u64) -> bool {
+        self.vm_config.with_header_extra_data(block_height)
+    }
+
+    fn active_features(&self, block_height: u64) -> BTreeSet<String> {
+        self.vm_config.active_features(block_height)
+    }
+
+    fn economic_params(&self, block_height: u64) -> EconomicParams {
+        EconomicParams {
+            emission: emission_amount(block_height),
+            gas_per_deploy_byte: self.vm_config.gas_per_deploy_byte,
+            min_deploy_points: self.vm_config.min_deploy_points,
+            min_deployment_gas_price: self.vm_config.min_deployment_gas_price,
+        }
+    }
+
     fn get_provisioner(
         &self,
         pk: &BlsPublicKey,
@@ -251,6 +340,17 @@ impl VMExecution for Rusk {
         Ok(self.base_root())
     }
 
+    fn query_at(
+        &self,
+        state_root: [u8; 32],
+        contract: ContractId,
+        method: &str,
+        args: Vec<u8>,
+    ) -> anyhow::Result<Vec<u8>> {
+        self.query_raw_at(state_root, contract, method, args)
+            .map_err(|e| anyhow::anyhow!("Cannot query the state: {e}"))
+    }
+
     fn revert(&self, state_hash: [u8; 32]) -> anyhow::Result<[u8; 32]> {
         let state_hash = self
             .revert(state_hash)
@@ -332,7 +432,7 @@ impl Rusk {
             .collect())
     }
 
-    fn to_stake(stake: StakeData) -> Stake {
+    pub(crate) fn to_stake(stake: StakeData) -> Stake {
         let stake_amount = stake.amount.unwrap_or_default();
 
         let value = stake_amount.value;
@@ -4,7 +4,8 @@
 //
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
+use std::path::PathBuf;
 use std::time::Duration;
 
 use dusk_vm::ExecutionConfig;
@@ -48,8 +49,31 @@ pub struct Config {
     #[serde(default)]
     pub generation_timeout: Option<Duration>,
 
+    /// Profile the execution of every accepted transaction, aggregating the
+    /// profiles per contract and including them in the simulation receipts.
+    #[serde(default)]
+    pub debug_receipts: bool,
+
     /// Set of features to activate
     pub features: HashMap<String, u64>,
+
+    /// Upgrade of the stake contract, required to activate the stake features
+    /// changing its calls after genesis
+    #[serde(default)]
+    pub stake_upgrade: Option<ContractUpgrade>,
+}
+
+/// Bytecode replacing a deployed contract, see
+/// [`crate::node::upgrade_stake_contract`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContractUpgrade {
+    /// Path of the bytecode of the new contract
+    pub bytecode: PathBuf,
+
+    /// Base58 of the account key owning the new contract, the Dusk key if
+    /// unset
+    #[serde(default)]
+    pub owner: Option<String>,
 }
 
 impl Default for Config {
@@ -60,6 +84,13 @@ impl Default for Config {
 
 pub(crate) mod feature {
     pub const FEATURE_ABI_PUBLIC_SENDER: &str = "ABI_PUBLIC_SENDER";
+    pub const FEATURE_PROVISIONERS_ROOT: &str = "PROVISIONERS_ROOT";
+    pub const FEATURE_STAKE_RULES: &str = "STAKE_RULES";
+    pub const FEATURE_STAKE_POP: &str = "STAKE_POP";
+    pub const FEATURE_HEADER_EXTRA_DATA: &str = "HEADER_EXTRA_DATA";
+    pub use node::vm::FEATURE_CANONICAL_TX_ORDER;
+    pub const FEATURE_TX_SPONSORSHIP: &str = "TX_SPONSORSHIP";
+    pub const FEATURE_STAKE_KEY_ROTATION: &str = "STAKE_KEY_ROTATION";
 }
 
 impl Config {
@@ -70,7 +101,9 @@ impl Config {
             min_deploy_points: default_min_deploy_points(),
             block_gas_limit: default_block_gas_limit(),
             generation_timeout: None,
+            debug_receipts: false,
             features: HashMap::new(),
+            stake_upgrade: None,
         }
     }
 
@@ -117,20 +150,133 @@ impl Config {
         self
     }
 
+    /// Enable the profiling of the execution of transactions.
+    pub const fn with_debug_receipts(mut self, debug_receipts: bool) -> Self {
+        self.debug_receipts = debug_receipts;
+        self
+    }
+
     /// Create a new `Config` with the given parameters.
     pub fn to_execution_config(&self, block_height: u64) -> ExecutionConfig {
-        let with_public_sender: bool = self
-            .feature(feature::FEATURE_ABI_PUBLIC_SENDER)
-            .map(|activation| activation >= block_height)
-            .unwrap_or_default();
         ExecutionConfig {
             gas_per_deploy_byte: self.gas_per_deploy_byte,
             min_deploy_points: self.min_deploy_points,
             min_deploy_gas_price: self.min_deployment_gas_price,
-            with_public_sender,
+            with_public_sender: self.with_public_sender(block_height),
         }
     }
 
+    /// Returns true if the contracts called by the transactions of blocks at
+    /// `block_height` are given the public sender of the transaction
+    pub fn with_public_sender(&self, block_height: u64) -> bool {
+        self.feature(feature::FEATURE_ABI_PUBLIC_SENDER)
+            .map(|activation| activation >= block_height)
+            .unwrap_or_default()
+    }
+
+    /// Returns true if blocks at `block_height` must commit to the active
+    /// provisioner set
+    pub fn with_provisioners_root(&self, block_height: u64) -> bool {
+        self.feature(feature::FEATURE_PROVISIONERS_ROOT)
+            .map(|activation| block_height >= activation)
+            .unwrap_or_default()
+    }
+
+    /// Returns true if blocks at `block_height` carry the extra-data of their
+    /// generator.
+    ///
+    /// Since the extra-data is only part of the headers committing to the
+    /// provisioner set, it's never carried before such commitment.
+    pub fn with_header_extra_data(&self, block_height: u64) -> bool {
+        self.with_provisioners_root(block_height)
+            && self
+                .feature(feature::FEATURE_HEADER_EXTRA_DATA)
+                .map(|activation| block_height >= activation)
+                .unwrap_or_default()
+    }
+
+    /// Returns true if the stake calls of blocks at `block_height` must
+    /// satisfy the stake rules
+    pub fn with_stake_rules(&self, block_height: u64) -> bool {
+        self.feature(feature::FEATURE_STAKE_RULES)
+            .map(|activation| block_height >= activation)
+            .unwrap_or_default()
+    }
+
+    /// Returns true if the stakes of blocks at `block_height` must carry the
+    /// proof of possession of their account key
+    pub fn with_stake_pop(&self, block_height: u64) -> bool {
+        self.feature(feature::FEATURE_STAKE_POP)
+            .map(|activation| block_height >= activation)
+            .unwrap_or_default()
+    }
+
+    /// Returns true if the transactions of blocks at `block_height` must be
+    /// in canonical order
+    pub fn with_canonical_tx_order(&self, block_height: u64) -> bool {
+        self.feature(feature::FEATURE_CANONICAL_TX_ORDER)
+            .map(|activation| block_height >= activation)
+            .unwrap_or_default()
+    }
+
+    /// Returns true if blocks at `block_height` can include sponsored
+    /// transactions
+    pub fn with_sponsorship(&self, block_height: u64) -> bool {
+        self.feature(feature::FEATURE_TX_SPONSORSHIP)
+            .map(|activation| block_height >= activation)
+            .unwrap_or_default()
+    }
+
+    /// Returns true if the stakes of blocks at `block_height` can have their
+    /// account key rotated
+    pub fn with_key_rotation(&self, block_height: u64) -> bool {
+        self.feature(feature::FEATURE_STAKE_KEY_ROTATION)
+            .map(|activation| block_height >= activation)
+            .unwrap_or_default()
+    }
+
+    /// Returns the names of the features active at `block_height`
+    pub fn active_features(&self, block_height: u64) -> BTreeSet<String> {
+        [
+            (
+                feature::FEATURE_ABI_PUBLIC_SENDER,
+                self.with_public_sender(block_height),
+            ),
+            (
+                feature::FEATURE_PROVISIONERS_ROOT,
+                self.with_provisioners_root(block_height),
+            ),
+            (
+                feature::FEATURE_STAKE_RULES,
+                self.with_stake_rules(block_height),
+            ),
+            (
+                feature::FEATURE_STAKE_POP,
+                self.with_stake_pop(block_height),
+            ),
+            (
+                feature::FEATURE_HEADER_EXTRA_DATA,
+                self.with_header_extra_data(block_height),
+            ),
+            (
+                feature::FEATURE_CANONICAL_TX_ORDER,
+                self.with_canonical_tx_order(block_height),
+            ),
+            (
+                feature::FEATURE_TX_SPONSORSHIP,
+                self.with_sponsorship(block_height),
+            ),
+            (
+                feature::FEATURE_STAKE_KEY_ROTATION,
+                self.with_key_rotation(block_height),
+            ),
+        ]
+        .into_iter()
+        .filter(|(_, active)| *active)
+        .map(|(feature, _)| feature.to_string())
+        .collect()
+    }
+
     pub fn feature(&self, feature: &str) -> Option<u64> {
         self.features
             .iter()
@@ -5,12 +5,15 @@
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
 use crate::node::Rusk;
-use crate::Result;
+use crate::{Error, Result};
 
 use std::sync::mpsc;
 
 use bytecheck::CheckBytes;
 use dusk_core::abi::{ContractId, StandardBufSerializer};
+use dusk_core::signatures::bls::PublicKey as BlsPublicKey;
+use dusk_core::stake::STAKE_CONTRACT;
+use dusk_vm::proof::{page_proofs, PageProof};
 use node::vm::VMExecution;
 use rkyv::validation::validators::DefaultValidator;
 use rkyv::{Archive, Deserialize, Infallible, Serialize};
@@ -39,6 +42,93 @@ impl Rusk {
             .map_err(Into::into)
     }
 
+    /// Performs a raw query like [`Self::query_raw`], against the state at
+    /// the given commit instead of the tip.
+    pub fn query_raw_at<S, V>(
+        &self,
+        base_commit: [u8; 32],
+        contract_id: ContractId,
+        fn_name: S,
+        fn_arg: V,
+    ) -> Result<Vec<u8>>
+    where
+        S: AsRef<str>,
+        V: Into<Vec<u8>>,
+    {
+        if !self.vm.commits().contains(&base_commit) {
+            return Err(Error::CommitNotFound(base_commit));
+        }
+        let mut session = self.query_session(Some(base_commit))?;
+
+        session
+            .call_raw(
+                contract_id,
+                fn_name.as_ref(),
+                fn_arg,
+                self.get_block_gas_limit(),
+            )
+            .map(|receipt| receipt.data)
+            .map_err(Into::into)
+    }
+
+    /// Returns the proofs of the memory pages of a contract holding the `len`
+    /// bytes at `offset`, against the state at the given commit or at the
+    /// tip, along with its root.
+    ///
+    /// Returns `None` if the contract doesn't exist in that state.
+    pub fn state_proofs(
+        &self,
+        base_commit: Option<[u8; 32]>,
+        contract_id: ContractId,
+        offset: usize,
+        len: usize,
+    ) -> Result<Option<([u8; 32], Vec<PageProof>)>> {
+        let root = base_commit.unwrap_or_else(|| self.state_root());
+        if !self.vm.commits().contains(&root) {
+            return Err(Error::CommitNotFound(root));
+        }
+        let session = self.query_session(Some(root))?;
+
+        let proofs = page_proofs(&session, contract_id, offset, len);
+        Ok(proofs.map(|proofs| (root, proofs)))
+    }
+
+    /// Returns the proof of the stake of `account` against the state at the
+    /// given commit or at the tip.
+    ///
+    /// Returns `None` if the account has no stake in that state.
+    pub fn stake_proof(
+        &self,
+        base_commit: Option<[u8; 32]>,
+        account: &BlsPublicKey,
+    ) -> Result<Option<StakeProof>> {
+        let root = base_commit.unwrap_or_else(|| self.state_root());
+        if !self.vm.commits().contains(&root) {
+            return Err(Error::CommitNotFound(root));
+        }
+
+        let location: Option<(u64, u64)> =
+            self.query_at(root, STAKE_CONTRACT, "get_stake_location", account)?;
+        let Some((offset, len)) = location else {
+            return Ok(None);
+        };
+
+        let session = self.query_session(Some(root))?;
+        let pages = page_proofs(
+            &session,
+            STAKE_CONTRACT,
+            offset as usize,
+            len as usize,
+        )
+        .unwrap_or_default();
+        Ok(Some(StakeProof {
+            state_root: root,
+            offset,
+            len,
+            pages,
+        }))
+    }
+
     pub fn query<A, R>(
         &self,
         contract_id: ContractId,
@@ -60,6 +150,32 @@ impl Rusk {
         Ok(results.pop().unwrap())
     }
 
+    /// Performs a query like [`Self::query`], against the state at the given
+    /// commit instead of the tip.
+    pub fn query_at<A, R>(
+        &self,
+        base_commit: [u8; 32],
+        contract_id: ContractId,
+        call_name: &str,
+        call_arg: &A,
+    ) -> Result<R>
+    where
+        A: for<'b> Serialize<StandardBufSerializer<'b>>,
+        A::Archived: for<'b> bytecheck::CheckBytes<DefaultValidator<'b>>,
+        R: Archive,
+        R::Archived: Deserialize<R, Infallible>
+            + for<'b> CheckBytes<DefaultValidator<'b>>,
+    {
+        let mut session = self.query_session(Some(base_commit))?;
+        let receipt = session.call(
+            contract_id,
+            call_name,
+            call_arg,
+            self.get_block_gas_limit(),
+        )?;
+        Ok(receipt.data)
+    }
+
     fn query_seq<A, R, F>(
         &self,
         contract_id: ContractId,
@@ -153,3 +269,18 @@ impl Rusk {
         Ok(())
     }
 }
+
+/// The memory pages of the stake contract holding the entry of a stake, along
+/// with their openings against a state root.
+///
+/// The entry is the `(StakeData, StakeKeys)` pair of the account, as laid out
+/// in the memory of the contract.
+#[derive(Debug, Clone)]
+pub struct StakeProof {
+    pub state_root: [u8; 32],
+    /// Offset of the entry in the memory of the stake contract
+    pub offset: u64,
+    /// Size of the entry
+    pub len: u64,
+    pub pages: Vec<PageProof>,
+}
@@ -9,8 +9,11 @@
 use crate::error::Error;
 use crate::Result;
 
+use dusk_core::signatures::bls::PublicKey as AccountPublicKey;
 use dusk_core::transfer::moonlight::Transaction as MoonlightTransaction;
 use dusk_core::transfer::phoenix::Transaction as PhoenixTransaction;
+use dusk_core::transfer::sponsor::Sponsorship;
+use dusk_core::transfer::Transaction as ProtocolTransaction;
 use dusk_vm::host_queries;
 
 #[cfg(not(feature = "dynamic-verifier"))]
@@ -81,7 +84,7 @@ pub fn verify_proof(tx: &PhoenixTransaction) -> Result<bool> {
 
     // Maybe we want to handle internal serialization error too,
     // currently they map to `false`.
-    Ok(host_queries::verify_plonk(
+    Ok(host_queries::verify_plonk_cached(
         vd.to_vec(),
         tx.proof().to_vec(),
         tx.public_inputs(),
@@ -90,9 +93,34 @@ pub fn verify_proof(tx: &PhoenixTransaction) -> Result<bool> {
 
 /// Verifies the signature of the incoming transaction.
 pub fn verify_signature(tx: &MoonlightTransaction) -> Result<bool> {
-    Ok(host_queries::verify_bls(
+    Ok(host_queries::verify_bls_cached(
         tx.signature_message(),
         *tx.sender(),
         *tx.signature(),
     ))
 }
+
+/// Verifies the signature of the incoming transaction, committing to its
+/// `sponsor`.
+pub fn verify_sponsored_signature(
+    tx: &MoonlightTransaction,
+    sponsor: &AccountPublicKey,
+) -> Result<bool> {
+    Ok(host_queries::verify_bls_cached(
+        tx.sponsored_signature_message(sponsor),
+        *tx.sender(),
+        *tx.signature(),
+    ))
+}
+
+/// Verifies the signature of the sponsor of the incoming transaction.
+pub fn verify_sponsorship(
+    tx: &ProtocolTransaction,
+    sponsorship: &Sponsorship,
+) -> Result<bool> {
+    Ok(host_queries::verify_bls_cached(
+        Sponsorship::signature_message(tx),
+        *sponsorship.sponsor(),
+        *sponsorship.signature(),
+    ))
+}
@@ -0,0 +1,173 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Harness for the activation of a feature at a given height.
+//!
+//! Two acceptance pipelines are run from the same initial state: one under the
+//! rules before the fork, where the feature is never activated, and one under
+//! the rules after it, where the feature is activated at the given height.
+//! Blocks can be generated by either pipeline and verified by the other, so
+//! that a fork can be checked to reject the blocks it's meant to.
+
+use std::collections::BTreeMap;
+
+use dusk_consensus::config::{
+    RATIFICATION_COMMITTEE_CREDITS, VALIDATION_COMMITTEE_CREDITS,
+};
+use dusk_consensus::operations::{CallParams, Voter};
+use dusk_core::transfer::Transaction;
+use node::vm::{BlockContext, VMExecution};
+use node_data::ledger::{Block, Header, IterationsInfo};
+use rusk::node::RuskVmConfig;
+use rusk::{Rusk, DUSK_CONSENSUS_KEY};
+use rusk_recovery_tools::state::Snapshot;
+use tempfile::{tempdir, TempDir};
+
+use crate::common::state::new_state;
+
+/// Rules a pipeline runs under
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Rules {
+    /// The feature is never activated
+    PreFork,
+    /// The feature is activated at the activation height
+    PostFork,
+}
+
+pub struct ForkHarness {
+    pipelines: BTreeMap<Rules, (Rusk, TempDir)>,
+    pub activation: u64,
+}
+
+fn voters() -> Vec<Voter> {
+    let generator = node_data::bls::PublicKey::new(*DUSK_CONSENSUS_KEY);
+    let voters_size =
+        VALIDATION_COMMITTEE_CREDITS + RATIFICATION_COMMITTEE_CREDITS;
+    vec![(generator, 1); voters_size]
+}
+
+impl ForkHarness {
+    /// Creates both pipelines from `snapshot`, activating `feature` at
+    /// `activation` in the post-fork one
+    pub fn new(
+        snapshot: &Snapshot,
+        vm_config: RuskVmConfig,
+        feature: &str,
+        activation: u64,
+    ) -> rusk::Result<Self> {
+        let mut pre_fork = vm_config.clone();
+        pre_fork
+            .features
+            .retain(|k, _| !k.eq_ignore_ascii_case(feature));
+        let mut post_fork = pre_fork.clone();
+        post_fork.features.insert(feature.to_string(), activation);
+
+        let mut pipelines = BTreeMap::new();
+        for (rules, vm_config) in
+            [(Rules::PreFork, pre_fork), (Rules::PostFork, post_fork)]
+        {
+            let dir = tempdir().expect("temporary directory to be created");
+            let rusk = new_state(dir.path(), snapshot, vm_config)?;
+            pipelines.insert(rules, (rusk, dir));
+        }
+
+        let harness = Self {
+            pipelines,
+            activation,
+        };
+        assert_eq!(
+            harness.rusk(Rules::PreFork).state_root(),
+            harness.rusk(Rules::PostFork).state_root(),
+            "Both pipelines should start from the same state"
+        );
+        Ok(harness)
+    }
+
+    /// Returns the pipeline running under `rules`
+    pub fn rusk(&self, rules: Rules) -> &Rusk {
+        &self.pipelines[&rules].0
+    }
+
+    /// Generates a block at `height` under `rules`, including the
+    /// transactions surviving its candidate generation.
+    ///
+    /// The block is not accepted.
+    pub fn generate(
+        &self,
+        rules: Rules,
+        txs: &[Transaction],
+        height: u64,
+        gas_limit: u64,
+    ) -> anyhow::Result<Block> {
+        let rusk = self.rusk(rules);
+        let prev_state_root = rusk.state_root();
+        let generator_pubkey =
+            node_data::bls::PublicKey::new(*DUSK_CONSENSUS_KEY);
+        let generator_bls_pubkey = *generator_pubkey.bytes();
+
+        let params = CallParams {
+            round: height,
+            generator_pubkey,
+            to_slash: vec![],
+            voters_pubkey: voters(),
+            max_txs_bytes: usize::MAX,
+            prev_state_root,
+        };
+        let txs = txs.iter().map(|tx| tx.clone().into());
+        let ctx = BlockContext::candidate(height, generator_bls_pubkey, rusk);
+        let (spent_txs, _, output) =
+            rusk.execute_state_transition(&params, &ctx, txs)?;
+
+        let block = Block::new(
+            Header {
+                height,
+                gas_limit,
+                generator_bls_pubkey,
+                state_hash: output.state_root,
+                event_bloom: output.event_bloom,
+                failed_iterations: IterationsInfo::default(),
+                ..Default::default()
+            },
+            spent_txs.into_iter().map(|tx| tx.inner).collect(),
+            vec![],
+        )?;
+        Ok(block)
+    }
+
+    /// Runs `block` through the verification of the pipeline running under
+    /// `rules`, checking the block commits to the resulting state.
+    pub fn verify(&self, rules: Rules, block: &Block) -> anyhow::Result<()> {
+        let rusk = self.rusk(rules);
+        let ctx = BlockContext::new(block.header(), rusk);
+        let output = rusk.verify_state_transition(
+            rusk.state_root(),
+            block,
+            &ctx,
+            &voters(),
+        )?;
+        let header = block.header();
+        if output.state_root != header.state_hash
+            || output.event_bloom != header.event_bloom
+        {
+            anyhow::bail!("block doesn't commit to the verified state");
+        }
+        Ok(())
+    }
+
+    /// Accepts `block` in both pipelines.
+    ///
+    /// # Panics
+    /// If the block is not valid under both rules.
+    pub fn accept(&self, block: &Block) {
+        for (rules, (rusk, _)) in &self.pipelines {
+            self.verify(*rules, block)
+                .unwrap_or_else(|e| panic!("{rules:?} rejected block: {e}"));
+            let ctx = BlockContext::new(block.header(), rusk);
+            rusk.accept(rusk.state_root(), block, &ctx, &voters())
+                .unwrap_or_else(|e| panic!("{rules:?} failed to accept: {e}"));
+        }
+    }
+}
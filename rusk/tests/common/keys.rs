@@ -0,0 +1,230 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Deterministic provisioner keysets for multi-provisioner tests.
+
+use std::collections::HashMap;
+
+use dusk_bytes::Serializable;
+use dusk_consensus::commons::RoundUpdate;
+use dusk_consensus::config::exclude_next_generator;
+use dusk_consensus::user::cluster::Cluster;
+use dusk_consensus::user::committee::Committee;
+use dusk_consensus::user::provisioners::Provisioners;
+use dusk_consensus::user::sortition::Config as SortitionConfig;
+use dusk_core::signatures::bls::{
+    MultisigSignature as BlsMultisigSignature, PublicKey as BlsPublicKey,
+    SecretKey as BlsSecretKey,
+};
+use node_data::bls::PublicKey;
+use node_data::ledger::{Attestation, Header, StepVotes};
+use node_data::message::payload::{
+    QuorumType, RatificationResult, ValidationResult, Vote,
+};
+use node_data::StepName;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+/// A set of provisioners whose keys are derived from a fixed seed, so that the
+/// same keyset is generated on every run.
+pub struct Keyset {
+    keys: Vec<(PublicKey, BlsSecretKey)>,
+    provisioners: Provisioners,
+}
+
+#[allow(dead_code)]
+impl Keyset {
+    /// Generates `n` provisioners, each staking `stake` LUX.
+    pub fn new(seed: u64, n: usize, stake: u64) -> Self {
+        Self::with_stakes(seed, &vec![stake; n])
+    }
+
+    /// Generates one provisioner for each of the given stakes.
+    pub fn with_stakes(seed: u64, stakes: &[u64]) -> Self {
+        let rng = &mut StdRng::seed_from_u64(seed);
+
+        let mut keys = Vec::with_capacity(stakes.len());
+        let mut provisioners = Provisioners::empty();
+        for stake in stakes {
+            let sk = BlsSecretKey::random(rng);
+            let pk = PublicKey::new(BlsPublicKey::from(&sk));
+            provisioners.add_member_with_value(pk.clone(), *stake);
+            keys.push((pk, sk));
+        }
+
+        Self { keys, provisioners }
+    }
+
+    pub fn keys(&self) -> &[(PublicKey, BlsSecretKey)] {
+        &self.keys
+    }
+
+    pub fn provisioners(&self) -> &Provisioners {
+        &self.provisioners
+    }
+
+    /// Returns the `[[stake]]` entries of a genesis snapshot staking each
+    /// provisioner's stake.
+    pub fn stakes_toml(&self) -> String {
+        self.provisioners
+            .iter()
+            .map(|(pk, stake)| {
+                let address = bs58::encode(pk.inner().to_bytes()).into_string();
+                format!(
+                    "[[stake]]\naddress = \"{address}\"\namount = {}\n",
+                    stake.value()
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Returns the committee extracted for the given step of the round
+    /// following `tip_header`.
+    pub fn committee(
+        &self,
+        tip_header: &Header,
+        iteration: u8,
+        step: StepName,
+    ) -> Committee {
+        let round = tip_header.height + 1;
+        let seed = tip_header.seed;
+
+        let mut exclusion =
+            vec![self.provisioners.get_generator(iteration, seed, round)];
+        if exclude_next_generator(iteration) {
+            exclusion.push(self.provisioners.get_generator(
+                iteration + 1,
+                seed,
+                round,
+            ));
+        }
+
+        let cfg = SortitionConfig::new(seed, round, iteration, step, exclusion);
+        Committee::new(&self.provisioners, &cfg)
+    }
+
+    /// Returns the step votes of all committee members voting `vote`.
+    pub fn step_votes(
+        &self,
+        tip_header: &Header,
+        vote: &Vote,
+        step: StepName,
+        iteration: u8,
+    ) -> StepVotes {
+        self.step_votes_by(tip_header, vote, step, iteration, |_| true)
+    }
+
+    /// Returns the step votes of the committee members accepted by `filter`
+    /// voting `vote`.
+    ///
+    /// `filter` is given the index of the provisioner in [`Self::keys`].
+    pub fn step_votes_by<F: Fn(usize) -> bool>(
+        &self,
+        tip_header: &Header,
+        vote: &Vote,
+        step: StepName,
+        iteration: u8,
+        filter: F,
+    ) -> StepVotes {
+        let committee = self.committee(tip_header, iteration, step);
+
+        let mut signatures = vec![];
+        let mut cluster = Cluster::<PublicKey>::default();
+        for (idx, (pk, sk)) in self.keys.iter().enumerate() {
+            let Some(weight) = committee.votes_for(pk) else {
+                continue;
+            };
+            if !filter(idx) {
+                continue;
+            }
+
+            let ru = RoundUpdate::new(
+                pk.clone(),
+                sk.clone(),
+                tip_header,
+                HashMap::default(),
+                vec![],
+            );
+            let sig = match step {
+                StepName::Validation => {
+                    dusk_consensus::build_validation_payload(
+                        *vote, &ru, iteration,
+                    )
+                    .sign_info
+                    .signature
+                }
+                StepName::Ratification => {
+                    dusk_consensus::build_ratification_payload(
+                        &ru,
+                        iteration,
+                        &ValidationResult::new(
+                            StepVotes::default(),
+                            *vote,
+                            QuorumType::Valid,
+                        ),
+                    )
+                    .sign_info
+                    .signature
+                }
+                StepName::Proposal => {
+                    panic!("proposal step is not voted")
+                }
+            };
+            signatures.push(
+                BlsMultisigSignature::from_bytes(sig.inner())
+                    .expect("signature to be valid"),
+            );
+            cluster.add(pk, weight);
+        }
+
+        let Some((first, rest)) = signatures.split_first() else {
+            return StepVotes::default();
+        };
+        let aggregate_signature = first.aggregate(rest).to_bytes();
+        StepVotes::new(aggregate_signature, committee.bits(&cluster))
+    }
+
+    /// Returns a successful attestation for `block_hash` signed by all
+    /// committee members.
+    pub fn attestation(
+        &self,
+        tip_header: &Header,
+        block_hash: [u8; 32],
+        iteration: u8,
+    ) -> Attestation {
+        self.attestation_by(tip_header, block_hash, iteration, |_| true)
+    }
+
+    /// Returns a successful attestation for `block_hash` signed by the
+    /// committee members accepted by `filter`.
+    pub fn attestation_by<F: Fn(usize) -> bool>(
+        &self,
+        tip_header: &Header,
+        block_hash: [u8; 32],
+        iteration: u8,
+        filter: F,
+    ) -> Attestation {
+        let vote = Vote::Valid(block_hash);
+        Attestation {
+            result: RatificationResult::Success(vote),
+            validation: self.step_votes_by(
+                tip_header,
+                &vote,
+                StepName::Validation,
+                iteration,
+                &filter,
+            ),
+            ratification: self.step_votes_by(
+                tip_header,
+                &vote,
+                StepName::Ratification,
+                iteration,
+                &filter,
+            ),
+        }
+    }
+}
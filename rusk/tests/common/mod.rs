@@ -5,6 +5,8 @@
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
 pub mod block;
+pub mod fork;
+pub mod keys;
 pub mod state;
 pub mod wallet;
 
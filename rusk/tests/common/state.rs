@@ -7,7 +7,7 @@
 use std::{path::Path, usize};
 
 use dusk_bytes::Serializable;
-use node::vm::VMExecution;
+use node::vm::{BlockContext, VMExecution};
 use rusk::node::RuskVmConfig;
 use rusk::{Result, Rusk, DUSK_CONSENSUS_KEY};
 use rusk_recovery_tools::state::{self, Snapshot};
@@ -92,9 +92,9 @@ pub struct ExecuteResult {
 /// expected. If not `expected` is specified, all txs must be included in the
 /// block
 #[allow(dead_code)]
-pub fn generator_procedure(
+pub fn generator_procedure<T: Clone + Into<node_data::ledger::Transaction>>(
     rusk: &Rusk,
-    txs: &[Transaction],
+    txs: &[T],
     block_height: u64,
     block_gas_limit: u64,
     missed_generators: Vec<BlsPublicKey>,
@@ -108,7 +108,7 @@ pub fn generator_procedure(
 
     let txs: Vec<_> = txs.iter().map(|t| t.clone().into()).collect();
     for tx in &txs {
-        rusk.preverify(tx)?;
+        rusk.preverify(tx, block_height)?;
     }
 
     let generator_pubkey = node_data::bls::PublicKey::new(*DUSK_CONSENSUS_KEY);
@@ -147,8 +147,9 @@ pub fn generator_procedure(
         prev_state_root: prev_root,
     };
 
+    let ctx = BlockContext::candidate(round, generator_pubkey_bytes, rusk);
     let (transfer_txs, discarded, execute_output) =
-        rusk.execute_state_transition(&call_params, txs.into_iter())?;
+        rusk.execute_state_transition(&call_params, &ctx, txs.into_iter())?;
 
     assert_eq!(transfer_txs.len(), expected.executed, "all txs accepted");
     assert_eq!(discarded.len(), expected.discarded, "no discarded tx");
@@ -175,12 +176,13 @@ pub fn generator_procedure(
     )
     .expect("valid block");
 
+    let ctx = BlockContext::new(block.header(), rusk);
     let verify_output =
-        rusk.verify_state_transition(prev_root, &block, &voters)?;
+        rusk.verify_state_transition(prev_root, &block, &ctx, &voters)?;
     info!("verify_state_transition new verification: {verify_output}",);
 
     let (accept_txs, accept_output, _) =
-        rusk.accept(prev_root, &block, &voters)?;
+        rusk.accept(prev_root, &block, &ctx, &voters)?;
 
     assert_eq!(accept_txs.len(), expected.executed, "all txs accepted");
 
@@ -219,7 +221,7 @@ pub fn generator_procedure2(
 
     let txs: Vec<_> = txs.iter().map(|t| t.clone().into()).collect();
     for tx in &txs {
-        rusk.preverify(tx)?;
+        rusk.preverify(tx, block_height)?;
     }
 
     let generator = generator.unwrap_or(*DUSK_CONSENSUS_KEY);
@@ -259,8 +261,9 @@ pub fn generator_procedure2(
         prev_state_root: prev_root,
     };
 
+    let ctx = BlockContext::candidate(round, generator_pubkey_bytes, rusk);
     let (transfer_txs, discarded, execute_output) =
-        rusk.execute_state_transition(&call_params, txs.into_iter())?;
+        rusk.execute_state_transition(&call_params, &ctx, txs.into_iter())?;
 
     assert_eq!(transfer_txs.len(), expected.executed, "all txs accepted");
     assert_eq!(discarded.len(), expected.discarded, "no discarded tx");
@@ -287,12 +290,13 @@ pub fn generator_procedure2(
     )
     .expect("valid block");
 
+    let ctx = BlockContext::new(block.header(), rusk);
     let verify_output =
-        rusk.verify_state_transition(prev_root, &block, &voters)?;
+        rusk.verify_state_transition(prev_root, &block, &ctx, &voters)?;
     info!("verify_state_transition new verification: {verify_output}",);
 
     let (accept_txs, accept_output, _) =
-        rusk.accept(prev_root, &block, &voters)?;
+        rusk.accept(prev_root, &block, &ctx, &voters)?;
 
     assert_eq!(accept_txs.len(), expected.executed, "all txs accepted");
 
@@ -0,0 +1,129 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+
+use dusk_core::transfer::bundle::TransactionBundle as ProtocolBundle;
+use dusk_core::transfer::data::{ContractCall, TransactionData};
+use dusk_core::transfer::TRANSFER_CONTRACT;
+use node_data::ledger::{Transaction, TransactionBundle};
+use rand::prelude::*;
+use rand::rngs::StdRng;
+use rusk::node::RuskVmConfig;
+use rusk::{Result, Rusk};
+use tempfile::tempdir;
+
+use crate::common::logger;
+use crate::common::state::{generator_procedure, new_state, ExecuteResult};
+use crate::common::wallet::{
+    test_wallet as wallet, TestStateClient, TestStore,
+};
+
+const BLOCK_HEIGHT: u64 = 1;
+const BLOCK_GAS_LIMIT: u64 = 1_000_000_000_000;
+const INITIAL_BALANCE: u64 = 10_000_000_000;
+
+const GAS_LIMIT: u64 = 300_000_000; // All ok
+const GAS_LIMIT_UNSPENDABLE: u64 = 1_000; // Not enough to spend
+const GAS_PRICE: u64 = 1;
+const DEPOSIT: u64 = 0;
+
+const SENDER_INDEX_0: u8 = 0;
+const SENDER_INDEX_1: u8 = 1;
+const SENDER_INDEX_2: u8 = 2;
+
+// Creates the Rusk initial state for the tests below
+fn initial_state<P: AsRef<Path>>(dir: P) -> Result<Rusk> {
+    let snapshot = toml::from_str(include_str!("../config/unspendable.toml"))
+        .expect("Cannot deserialize config");
+    let vm_config = RuskVmConfig::new().with_block_gas_limit(BLOCK_GAS_LIMIT);
+
+    new_state(dir, &snapshot, vm_config)
+}
+
+#[tokio::test(flavor = "multi_thread")]
+pub async fn reverted_bundle() -> Result<()> {
+    // Setup the logger
+    logger();
+
+    let tmp = tempdir().expect("Should be able to create temporary directory");
+    let rusk = initial_state(&tmp)?;
+
+    let cache = Arc::new(RwLock::new(HashMap::new()));
+
+    // Create a wallet
+    let wallet = wallet::Wallet::new(
+        TestStore,
+        TestStateClient {
+            rusk: rusk.clone(),
+            cache,
+        },
+    );
+
+    let mut rng = StdRng::seed_from_u64(0xbeef);
+
+    let contract_call = ContractCall {
+        contract: TRANSFER_CONTRACT,
+        fn_name: String::from("root"),
+        fn_args: Vec::new(),
+    };
+    let mut execute = |sender, gas_limit| {
+        wallet
+            .phoenix_execute(
+                &mut rng,
+                sender,
+                gas_limit,
+                GAS_PRICE,
+                DEPOSIT,
+                TransactionData::Call(contract_call.clone()),
+            )
+            .expect("Making the transaction should succeed")
+    };
+
+    // A transaction executed before the bundle, re-executed by its revert
+    let tx = execute(SENDER_INDEX_2, GAS_LIMIT);
+
+    // The first transaction of the bundle is executed, the second one is
+    // discarded, which reverts the first one
+    let bundle = ProtocolBundle::new(vec![
+        execute(SENDER_INDEX_0, GAS_LIMIT),
+        execute(SENDER_INDEX_1, GAS_LIMIT_UNSPENDABLE),
+    ])
+    .expect("Making the bundle should succeed");
+
+    let mut txs: Vec<Transaction> = vec![tx.into()];
+    txs.extend(TransactionBundle::from(bundle).txs());
+
+    let expected = ExecuteResult {
+        discarded: 1,
+        executed: 1,
+    };
+
+    // The generated block is verified and accepted without the bundle
+    let spent_txs = generator_procedure(
+        &rusk,
+        &txs,
+        BLOCK_HEIGHT,
+        BLOCK_GAS_LIMIT,
+        vec![],
+        Some(expected),
+    )
+    .expect("generator procedure should succeed");
+    assert_eq!(spent_txs[0].inner.id(), txs[0].id());
+
+    let balance = wallet
+        .get_balance(SENDER_INDEX_0)
+        .expect("Getting the balance should succeed")
+        .value;
+    assert_eq!(
+        balance, INITIAL_BALANCE,
+        "The reverted transaction should not be spent"
+    );
+
+    Ok(())
+}
@@ -337,8 +337,8 @@ fn execute_transaction<'a, E: Into<Option<&'a str>>>(
         .next()
         .expect("Transaction must be executed");
 
-    let tx_error = tx.err.as_ref().map(|e| e.as_str());
+    let tx_error = tx.err.as_ref().map(ToString::to_string);
     let error = expected_error.into();
-    assert_eq!(tx_error, error, "Output error does not match");
+    assert_eq!(tx_error.as_deref(), error, "Output error does not match");
     tx
 }
@@ -6,6 +6,8 @@
 
 use std::path::Path;
 
+use dusk_core::abi::ContractId;
+use dusk_core::transfer::TRANSFER_CONTRACT;
 use rusk::{node::RuskVmConfig, Result, Rusk};
 use tempfile::tempdir;
 
@@ -40,6 +42,52 @@ pub async fn finalization() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test(flavor = "multi_thread")]
+pub async fn historical_queries() -> Result<()> {
+    let tmp = tempdir().expect("Should be able to create temporary directory");
+    let rusk = initial_state(&tmp)?;
+    let query = |root| {
+        rusk.query_raw_at(root, TRANSFER_CONTRACT, "chain_id", Vec::<u8>::new())
+    };
+
+    // The states of the non-finalized blocks are available
+    let roots = empty_blocks(&rusk, BLOCKS_NUM, false);
+    for root in &roots {
+        let chain_id = query(*root).expect("state to be queryable");
+        assert_eq!(chain_id, vec![rusk.chain_id()?]);
+    }
+
+    // Merged states are not available anymore
+    rusk.revert_to_base_root().expect("revert to work");
+    let roots = empty_blocks(&rusk, BLOCKS_NUM, true);
+    assert!(query(roots[1]).is_err(), "merged state to be dropped");
+    assert!(query(roots[BLOCKS_NUM as usize]).is_ok());
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+pub async fn state_proofs() -> Result<()> {
+    let tmp = tempdir().expect("Should be able to create temporary directory");
+    let rusk = initial_state(&tmp)?;
+    // The states of the non-finalized blocks are kept
+    let roots = empty_blocks(&rusk, BLOCKS_NUM, false);
+
+    for root in roots {
+        let (proved, proofs) = rusk
+            .state_proofs(Some(root), TRANSFER_CONTRACT, 0, 1)?
+            .expect("transfer contract to exist");
+        assert_eq!(proved, root);
+        assert_eq!(proofs.len(), 1);
+        assert!(proofs[0].verify(&root), "page to be included in the root");
+    }
+
+    let unknown = ContractId::from_bytes([0xff; 32]);
+    assert!(rusk.state_proofs(None, unknown, 0, 1)?.is_none());
+
+    Ok(())
+}
+
 fn empty_blocks(rusk: &Rusk, blocks: u64, finalize: bool) -> Vec<[u8; 32]> {
     let mut roots = vec![];
 
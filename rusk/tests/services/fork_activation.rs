@@ -0,0 +1,84 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use dusk_core::stake::DEFAULT_MINIMUM_STAKE;
+use rusk::node::{RuskVmConfig, FEATURE_STAKE_RULES};
+
+use crate::common::fork::{ForkHarness, Rules};
+use crate::common::logger;
+use crate::common::wallet::{
+    test_wallet as wallet, TestStateClient, TestStore,
+};
+
+const ACTIVATION: u64 = 10;
+const BLOCK_GAS_LIMIT: u64 = 100_000_000_000;
+const GAS_LIMIT: u64 = 10_000_000_000;
+const GAS_PRICE: u64 = 1;
+
+/// A stake lower than the minimum is included as a failed transaction before
+/// the activation of the stake rules, and makes the block invalid after it.
+#[tokio::test(flavor = "multi_thread")]
+pub async fn stake_rules_activation() -> anyhow::Result<()> {
+    logger();
+
+    let snapshot = toml::from_str(include_str!("../config/stake.toml"))
+        .expect("Cannot deserialize config");
+    let vm_config = RuskVmConfig::new().with_block_gas_limit(BLOCK_GAS_LIMIT);
+    let harness = ForkHarness::new(
+        &snapshot,
+        vm_config,
+        FEATURE_STAKE_RULES,
+        ACTIVATION,
+    )?;
+
+    let wallet = wallet::Wallet::new(
+        TestStore,
+        TestStateClient {
+            rusk: harness.rusk(Rules::PreFork).clone(),
+            cache: Arc::new(RwLock::new(HashMap::new())),
+        },
+    );
+    let tx = wallet
+        .moonlight_stake(0, 2, DEFAULT_MINIMUM_STAKE - 1, GAS_LIMIT, GAS_PRICE)
+        .expect("Failed to create a stake transaction");
+    let txs = [tx];
+
+    // Before the activation both rules agree
+    let height = ACTIVATION - 1;
+    for rules in [Rules::PreFork, Rules::PostFork] {
+        let block = harness.generate(rules, &txs, height, BLOCK_GAS_LIMIT)?;
+        assert_eq!(block.txs().len(), 1, "stake should be included");
+        harness.verify(Rules::PreFork, &block)?;
+        harness.verify(Rules::PostFork, &block)?;
+    }
+
+    // From the activation, a block valid under the old rules is rejected
+    let height = harness.activation;
+    let old_block =
+        harness.generate(Rules::PreFork, &txs, height, BLOCK_GAS_LIMIT)?;
+    assert_eq!(old_block.txs().len(), 1, "stake should be included");
+    harness.verify(Rules::PreFork, &old_block)?;
+    harness
+        .verify(Rules::PostFork, &old_block)
+        .expect_err("block breaking the stake rules should be rejected");
+
+    // The new rules exclude the stake, producing a block valid under both
+    let new_block =
+        harness.generate(Rules::PostFork, &txs, height, BLOCK_GAS_LIMIT)?;
+    assert!(new_block.txs().is_empty(), "stake should be discarded");
+    harness.accept(&new_block);
+
+    assert_eq!(
+        harness.rusk(Rules::PreFork).state_root(),
+        harness.rusk(Rules::PostFork).state_root(),
+        "Both pipelines should agree on the accepted state"
+    );
+
+    Ok(())
+}
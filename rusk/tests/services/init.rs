@@ -19,7 +19,7 @@ use rusk::{Result, Rusk};
 use rusk_recovery_tools::state;
 use tokio::sync::broadcast;
 
-use dusk_core::abi::ContractId;
+use dusk_core::abi::{ContractId, ExecutionError};
 use node_data::ledger::SpentTransaction;
 use tempfile::tempdir;
 use tracing::info;
@@ -192,7 +192,7 @@ pub async fn calling_init_via_tx_fails() -> Result<()> {
 
     assert_eq!(
         submit_transactions(&rusk, &wallet).err,
-        Some("Unknown".into())
+        Some(ExecutionError::Unknown("Unknown".into()))
     );
 
     // Check the state's root is changed from the original one
@@ -4,11 +4,13 @@
 //
 // Copyright (c) DUSK NETWORK. All rights reserved.
 
+pub mod bundle;
 pub mod contract_deployment;
 pub mod contract_stake;
 pub mod conversion;
 //pub mod deploy;
 pub mod finalization;
+pub mod fork_activation;
 pub mod gas_behavior;
 pub mod init;
 pub mod mainnet;
@@ -16,5 +18,8 @@ pub mod moonlight_stake;
 pub mod multi_transfer;
 pub mod owner_calls;
 pub mod phoenix_stake;
+pub mod quorum;
+pub mod simulate;
+pub mod trace;
 pub mod transfer;
 pub mod unspendable;
@@ -8,6 +8,7 @@ use std::path::Path;
 use std::sync::{Arc, RwLock};
 
 use dusk_core::stake::DEFAULT_MINIMUM_STAKE;
+use dusk_vm::proof::proven_bytes;
 
 use rand::prelude::*;
 use rand::rngs::StdRng;
@@ -170,6 +171,49 @@ pub async fn stake() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test(flavor = "multi_thread")]
+pub async fn stake_proof() -> Result<()> {
+    // Setup the logger
+    logger();
+
+    let tmp = tempdir().expect("Should be able to create temporary directory");
+    let rusk = stake_state(&tmp)?;
+
+    let cache = Arc::new(RwLock::new(HashMap::new()));
+
+    // Create a wallet
+    let wallet = wallet::Wallet::new(
+        TestStore,
+        TestStateClient {
+            rusk: rusk.clone(),
+            cache,
+        },
+    );
+
+    let account = wallet
+        .account_public_key(2)
+        .expect("account key to be derived");
+    let proof = rusk
+        .stake_proof(None, &account)?
+        .expect("stake to be proven");
+    assert_eq!(proof.state_root, rusk.state_root());
+    assert!(!proof.pages.is_empty());
+    for page in &proof.pages {
+        assert!(page.verify(&proof.state_root), "page to be in the root");
+    }
+    let entry =
+        proven_bytes(&proof.pages, proof.offset as usize, proof.len as usize)
+            .expect("stake entry to be held by the pages");
+    assert_eq!(entry.len() as u64, proof.len);
+
+    let unstaked = wallet
+        .account_public_key(5)
+        .expect("account key to be derived");
+    assert!(rusk.stake_proof(None, &unstaked)?.is_none());
+
+    Ok(())
+}
+
 /// Attempt to submit a management transaction intending it to fail. Verify that
 /// the reward amount remains unchanged and confirm that the transaction indeed
 /// fails
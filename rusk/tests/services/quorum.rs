@@ -0,0 +1,106 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use node::chain::verify_att;
+use node_data::ledger::Header;
+use node_data::message::payload::{RatificationResult, Vote};
+use node_data::message::ConsensusHeader;
+use node_data::StepName;
+use rusk::{node::RuskVmConfig, Result};
+use tempfile::tempdir;
+
+use crate::common::keys::Keyset;
+use crate::common::state::new_state;
+
+const KEYSET_SEED: u64 = 0xbeef;
+const PROVISIONERS: usize = 10;
+const STAKE: u64 = 1_000_000_000_000;
+const BLOCK_HASH: [u8; 32] = [1; 32];
+
+fn tip_header() -> Header {
+    Header {
+        seed: [5; 48].into(),
+        ..Default::default()
+    }
+}
+
+fn consensus_header(tip_header: &Header, iteration: u8) -> ConsensusHeader {
+    ConsensusHeader {
+        prev_block_hash: tip_header.hash,
+        round: tip_header.height + 1,
+        iteration,
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+pub async fn full_committee_reaches_quorum() {
+    let keyset = Keyset::new(KEYSET_SEED, PROVISIONERS, STAKE);
+    let tip_header = tip_header();
+
+    let att = keyset.attestation(&tip_header, BLOCK_HASH, 0);
+
+    let (validation, ratification, voters) = verify_att(
+        &att,
+        consensus_header(&tip_header, 0),
+        tip_header.seed,
+        keyset.provisioners(),
+        Some(RatificationResult::Success(Vote::Valid(BLOCK_HASH))),
+    )
+    .await
+    .expect("attestation to be valid");
+
+    assert!(validation.quorum_reached());
+    assert!(ratification.quorum_reached());
+    assert!(voters.len() > 1, "multiple provisioners should have voted");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+pub async fn minority_does_not_reach_quorum() {
+    let keyset = Keyset::new(KEYSET_SEED, PROVISIONERS, STAKE);
+    let tip_header = tip_header();
+
+    // Only a single member of the validation committee signs
+    let committee = keyset.committee(&tip_header, 0, StepName::Validation);
+    let signer = keyset
+        .keys()
+        .iter()
+        .position(|(pk, _)| committee.is_member(pk))
+        .expect("committee to have members");
+
+    let att =
+        keyset.attestation_by(&tip_header, BLOCK_HASH, 0, |idx| idx == signer);
+
+    let res = verify_att(
+        &att,
+        consensus_header(&tip_header, 0),
+        tip_header.seed,
+        keyset.provisioners(),
+        None,
+    )
+    .await;
+
+    assert!(res.is_err(), "a single provisioner should not reach quorum");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+pub async fn keyset_stakes_are_deployed() -> Result<()> {
+    let keyset = Keyset::new(KEYSET_SEED, PROVISIONERS, STAKE);
+
+    let tmp = tempdir().expect("Should be able to create temporary directory");
+    let snapshot = toml::from_str(&keyset.stakes_toml())
+        .expect("Cannot deserialize config");
+    let rusk = new_state(&tmp, &snapshot, RuskVmConfig::new())?;
+
+    for (pk, _) in keyset.keys() {
+        let stake = rusk
+            .provisioner(pk.inner())?
+            .and_then(|s| s.amount)
+            .expect("provisioner to be staked");
+        assert_eq!(stake.value, STAKE);
+    }
+
+    Ok(())
+}
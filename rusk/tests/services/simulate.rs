@@ -0,0 +1,111 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+
+use dusk_core::transfer::data::{ContractCall, TransactionData};
+use dusk_core::transfer::TRANSFER_CONTRACT;
+use node_data::ledger::Transaction;
+use rand::prelude::*;
+use rand::rngs::StdRng;
+use rusk::node::RuskVmConfig;
+use rusk::{Result, Rusk};
+use tempfile::tempdir;
+
+use crate::common::logger;
+use crate::common::state::new_state;
+use crate::common::wallet::{
+    test_wallet as wallet, TestStateClient, TestStore,
+};
+
+const BLOCK_HEIGHT: u64 = 1;
+const BLOCK_GAS_LIMIT: u64 = 1_000_000_000_000;
+
+const GAS_LIMIT: u64 = 300_000_000; // All ok
+const GAS_LIMIT_UNSPENDABLE: u64 = 1_000; // Not enough to spend
+const GAS_PRICE: u64 = 1;
+const DEPOSIT: u64 = 0;
+
+const SENDER_INDEX: u8 = 0;
+
+// Creates the Rusk initial state for the tests below
+fn initial_state<P: AsRef<Path>>(dir: P) -> Result<Rusk> {
+    let snapshot = toml::from_str(include_str!("../config/unspendable.toml"))
+        .expect("Cannot deserialize config");
+    let vm_config = RuskVmConfig::new().with_block_gas_limit(BLOCK_GAS_LIMIT);
+
+    new_state(dir, &snapshot, vm_config)
+}
+
+/// Simulates transactions on top of the tip, checking their outcome is
+/// reported and the state is left untouched.
+#[tokio::test(flavor = "multi_thread")]
+pub async fn simulate_on_tip() -> Result<()> {
+    logger();
+
+    let tmp = tempdir().expect("Should be able to create temporary directory");
+    let rusk = initial_state(&tmp)?;
+
+    let cache = Arc::new(RwLock::new(HashMap::new()));
+    let wallet = wallet::Wallet::new(
+        TestStore,
+        TestStateClient {
+            rusk: rusk.clone(),
+            cache,
+        },
+    );
+
+    let mut rng = StdRng::seed_from_u64(0xbeef);
+
+    let contract_call = ContractCall {
+        contract: TRANSFER_CONTRACT,
+        fn_name: String::from("root"),
+        fn_args: Vec::new(),
+    };
+    let mut execute = |gas_limit| -> Transaction {
+        wallet
+            .phoenix_execute(
+                &mut rng,
+                SENDER_INDEX,
+                gas_limit,
+                GAS_PRICE,
+                DEPOSIT,
+                TransactionData::Call(contract_call.clone()),
+            )
+            .expect("Making the transaction should succeed")
+            .into()
+    };
+
+    let root = rusk.state_root();
+
+    let tx = execute(GAS_LIMIT);
+    let (result, profile) =
+        rusk.simulate_transaction(root, BLOCK_HEIGHT, &tx, false)?;
+    assert!(result.error.is_none(), "Simulation must succeed");
+    assert!(result.gas_spent > 0, "Simulation must spend gas");
+    assert!(profile.is_none(), "Simulation must not be profiled");
+    assert_eq!(rusk.state_root(), root, "Simulating must not change state");
+
+    let (result, _) =
+        rusk.simulate_transaction(root, BLOCK_HEIGHT, &tx, false)?;
+    assert!(result.error.is_none(), "Simulation must be repeatable");
+
+    let tx = execute(GAS_LIMIT_UNSPENDABLE);
+    let (result, _) =
+        rusk.simulate_transaction(root, BLOCK_HEIGHT, &tx, false)?;
+    assert!(result.error.is_some(), "Unspendable tx must be reported");
+    assert_eq!(result.gas_spent, 0, "Unspendable tx must spend nothing");
+
+    assert!(
+        rusk.simulate_transaction([0xff; 32], BLOCK_HEIGHT, &tx, false)
+            .is_err(),
+        "Simulating on an unknown state root must fail"
+    );
+
+    Ok(())
+}
@@ -0,0 +1,92 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+
+use dusk_core::stake::{DEFAULT_MINIMUM_STAKE, STAKE_CONTRACT};
+use dusk_core::transfer::TRANSFER_CONTRACT;
+use node_data::ledger::Transaction;
+use rusk::node::RuskVmConfig;
+use rusk::{Result, Rusk};
+use tempfile::tempdir;
+
+use crate::common::logger;
+use crate::common::state::{generator_procedure, new_state};
+use crate::common::wallet::{
+    test_wallet as wallet, TestStateClient, TestStore,
+};
+
+const BLOCK_HEIGHT: u64 = 1;
+const BLOCK_GAS_LIMIT: u64 = 100_000_000_000;
+const GAS_LIMIT: u64 = 10_000_000_000;
+const GAS_PRICE: u64 = 1;
+
+// Creates the Rusk initial state for the tests below
+fn initial_state<P: AsRef<Path>>(dir: P) -> Result<Rusk> {
+    let snapshot = toml::from_str(include_str!("../config/stake.toml"))
+        .expect("Cannot deserialize config");
+    let vm_config = RuskVmConfig::new().with_block_gas_limit(BLOCK_GAS_LIMIT);
+
+    new_state(dir, &snapshot, vm_config)
+}
+
+/// Traces a stake transaction of an accepted block, checking the trace
+/// matches its original execution and leaves the state untouched.
+#[tokio::test(flavor = "multi_thread")]
+pub async fn trace_stake() -> Result<()> {
+    logger();
+
+    let tmp = tempdir().expect("Should be able to create temporary directory");
+    let rusk = initial_state(&tmp)?;
+
+    let cache = Arc::new(RwLock::new(HashMap::new()));
+    let wallet = wallet::Wallet::new(
+        TestStore,
+        TestStateClient {
+            rusk: rusk.clone(),
+            cache,
+        },
+    );
+
+    let tx = wallet
+        .moonlight_stake(0, 2, DEFAULT_MINIMUM_STAKE, GAS_LIMIT, GAS_PRICE)
+        .expect("Failed to create a stake transaction");
+
+    let prev_root = rusk.state_root();
+    let spent = generator_procedure(
+        &rusk,
+        &[tx.clone()],
+        BLOCK_HEIGHT,
+        BLOCK_GAS_LIMIT,
+        vec![],
+        None,
+    )
+    .expect("generator procedure to succeed");
+    let spent = spent.first().expect("Transaction must be executed");
+    assert!(spent.err.is_none(), "Stake transaction must succeed");
+
+    let root = rusk.state_root();
+    let txs: Vec<Transaction> = vec![tx.into()];
+    let (receipt, trace) = rusk
+        .trace_transaction(prev_root, BLOCK_HEIGHT, &txs, 0)
+        .expect("Transaction to be traced");
+
+    assert_eq!(receipt.gas_spent, spent.gas_spent);
+    assert_eq!(trace.calls[0].contract, TRANSFER_CONTRACT);
+    assert!(trace.calls.iter().any(|c| c.contract == STAKE_CONTRACT));
+    assert!(!trace.host_calls.is_empty(), "Host queries must be traced");
+    assert_eq!(rusk.state_root(), root, "Tracing must not change the state");
+
+    assert!(
+        rusk.trace_transaction(prev_root, BLOCK_HEIGHT, &txs, 1)
+            .is_err(),
+        "Tracing a missing transaction must fail"
+    );
+
+    Ok(())
+}
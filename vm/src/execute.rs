@@ -7,7 +7,9 @@
 mod config;
 
 use blake2b_simd::Params;
-use dusk_core::abi::{ContractError, ContractId, Metadata, CONTRACT_ID_BYTES};
+use dusk_core::abi::{
+    ContractError, ContractId, Metadata, PanicReason, CONTRACT_ID_BYTES,
+};
 use dusk_core::transfer::data::ContractBytecode;
 use dusk_core::transfer::{Transaction, TRANSFER_CONTRACT};
 use piecrust::{CallReceipt, Error, Session};
@@ -167,7 +169,7 @@ fn contract_deploy(
                 receipt.data = Err(ContractError::OutOfGas);
             } else if !verify_bytecode_hash(&deploy.bytecode) {
                 receipt.data = Err(ContractError::Panic(
-                    "failed bytecode hash check".into(),
+                    PanicReason::BytecodeHash.to_string(),
                 ))
             } else {
                 let result = session.deploy_raw(
@@ -185,7 +187,10 @@ fn contract_deploy(
                     // Should the gas spent by the INIT method charged too?
                     Ok(_) => receipt.gas_spent += deploy_charge,
                     Err(err) => {
-                        let msg = format!("failed deployment: {err:?}");
+                        let msg = format!(
+                            "{}: {err:?}",
+                            PanicReason::DeploymentFailed
+                        );
                         receipt.data = Err(ContractError::Panic(msg))
                     }
                 }
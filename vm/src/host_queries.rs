@@ -219,6 +219,57 @@ pub fn verify_bls_multisig(
     akey.verify(&sig, &msg).is_ok()
 }
 
+/// Verifies a PLONK proof like [`verify_plonk`], sharing the verification
+/// cache of the host query.
+///
+/// Verifying the proof of a transaction with this function ahead of its
+/// execution spares the transfer contract from verifying it again.
+pub fn verify_plonk_cached(
+    verifier_data: Vec<u8>,
+    proof: Vec<u8>,
+    public_inputs: Vec<BlsScalar>,
+) -> bool {
+    let args = (verifier_data, proof, public_inputs);
+    let hash = args_hash(&args);
+    if let Some(is_valid) = cache::get_plonk_verification(hash) {
+        return is_valid;
+    }
+
+    let (verifier_data, proof, public_inputs) = args;
+    let is_valid = verify_plonk(verifier_data, proof, public_inputs);
+    cache::put_plonk_verification(hash, is_valid);
+    is_valid
+}
+
+/// Verifies a BLS signature like [`verify_bls`], sharing the verification
+/// cache of the host query.
+pub fn verify_bls_cached(
+    msg: Vec<u8>,
+    pk: BlsPublicKey,
+    sig: BlsSignature,
+) -> bool {
+    let args = (msg, pk, sig);
+    let hash = args_hash(&args);
+    if let Some(is_valid) = cache::get_bls_verification(hash) {
+        return is_valid;
+    }
+
+    let (msg, pk, sig) = args;
+    let is_valid = verify_bls(msg, pk, sig);
+    cache::put_bls_verification(hash, is_valid);
+    is_valid
+}
+
+/// Hashes the arguments of a host query as archived by the contracts calling
+/// it, which is the key of the verification caches
+fn args_hash<A>(args: &A) -> [u8; blake2b_simd::OUTBYTES]
+where
+    A: Serialize<AllocSerializer<1024>>,
+{
+    let bytes = rkyv::to_bytes::<_, 1024>(args).expect("args should serialize");
+    *blake2b_simd::blake2b(&bytes).as_array()
+}
+
 fn wrap_host_query<A, R, F>(arg_buf: &mut [u8], arg_len: u32, closure: F) -> u32
 where
     F: FnOnce(A) -> R,
@@ -35,6 +35,9 @@ use self::host_queries::{
 pub(crate) mod cache;
 mod execute;
 pub mod host_queries;
+pub mod profile;
+pub mod proof;
+pub mod trace;
 
 /// The Virtual Machine (VM) for executing smart contracts in the Dusk Network.
 ///
@@ -241,22 +244,33 @@ impl VM {
     }
 
     fn register_host_queries(&mut self) {
-        self.0.register_host_query(Query::HASH, host_hash);
-        self.0
-            .register_host_query(Query::POSEIDON_HASH, host_poseidon_hash);
-        self.0
-            .register_host_query(Query::VERIFY_PLONK, host_verify_plonk);
-        self.0.register_host_query(
+        self.register_host_query(Query::HASH, host_hash);
+        self.register_host_query(Query::POSEIDON_HASH, host_poseidon_hash);
+        self.register_host_query(Query::VERIFY_PLONK, host_verify_plonk);
+        self.register_host_query(
             Query::VERIFY_GROTH16_BN254,
             host_verify_groth16_bn254,
         );
-        self.0
-            .register_host_query(Query::VERIFY_SCHNORR, host_verify_schnorr);
-        self.0
-            .register_host_query(Query::VERIFY_BLS, host_verify_bls);
-        self.0.register_host_query(
+        self.register_host_query(Query::VERIFY_SCHNORR, host_verify_schnorr);
+        self.register_host_query(Query::VERIFY_BLS, host_verify_bls);
+        self.register_host_query(
             Query::VERIFY_BLS_MULTISIG,
             host_verify_bls_multisig,
         );
     }
+
+    /// Registers the host query `name`, recording its calls for profiling and
+    /// tracing
+    fn register_host_query(
+        &mut self,
+        name: &'static str,
+        query: fn(&mut [u8], u32) -> u32,
+    ) {
+        self.0
+            .register_host_query(name, move |arg_buf: &mut [u8], arg_len| {
+                profile::record_host_call(name);
+                trace::record_host_call(name, arg_len);
+                query(arg_buf, arg_len)
+            });
+    }
 }
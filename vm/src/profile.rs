@@ -0,0 +1,129 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Profiling of the execution of transactions.
+//!
+//! Host queries are counted per thread: since a session executes contracts on
+//! the thread it's called from, the calls made during [`execute_profiled`] can
+//! be attributed to the profiled transaction.
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use dusk_core::abi::{ContractError, ContractId};
+use dusk_core::transfer::Transaction;
+use piecrust::{CallReceipt, Error, Session};
+
+use crate::execute::{execute, Config};
+
+type Receipt = CallReceipt<Result<Vec<u8>, ContractError>>;
+
+thread_local! {
+    static HOST_CALLS: RefCell<BTreeMap<&'static str, u64>> =
+        RefCell::new(BTreeMap::new());
+}
+
+/// Records a call to the host query `name`
+pub(crate) fn record_host_call(name: &'static str) {
+    HOST_CALLS.with(|calls| *calls.borrow_mut().entry(name).or_default() += 1);
+}
+
+fn take_host_calls() -> BTreeMap<&'static str, u64> {
+    HOST_CALLS.with(|calls| core::mem::take(&mut *calls.borrow_mut()))
+}
+
+/// Execution profile of a contract within a transaction
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ContractProfile {
+    /// Number of calls to the contract
+    pub calls: u64,
+    /// Gas spent by the calls, including their nested calls
+    pub gas_spent: u64,
+    /// Growth of the contract memory, in bytes
+    pub mem_growth: usize,
+}
+
+/// Execution profile of a transaction
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TxProfile {
+    /// Wall time of the execution
+    pub elapsed: Duration,
+    /// Number of calls per host query
+    pub host_calls: BTreeMap<&'static str, u64>,
+    /// Profile of every contract called
+    pub contracts: BTreeMap<ContractId, ContractProfile>,
+}
+
+/// Executes a transaction like [`execute`], profiling its execution.
+///
+/// # Returns
+/// The result of [`execute`], along with the profile of the execution.
+pub fn execute_profiled(
+    session: &mut Session,
+    tx: &Transaction,
+    config: &Config,
+) -> (Result<Receipt, Error>, TxProfile) {
+    // Discard the calls made outside of a profiled execution
+    take_host_calls();
+
+    let start = Instant::now();
+    let res = execute(session, tx, config);
+
+    let mut profile = TxProfile {
+        elapsed: start.elapsed(),
+        host_calls: take_host_calls(),
+        contracts: BTreeMap::new(),
+    };
+
+    if let Ok(receipt) = &res {
+        // Memory length of each contract before its first call
+        let mut mem_lens = BTreeMap::new();
+        for elem in receipt.call_tree.iter() {
+            let contract =
+                profile.contracts.entry(elem.contract_id).or_default();
+            contract.calls += 1;
+            contract.gas_spent += elem.spent;
+
+            let mem_len =
+                mem_lens.entry(elem.contract_id).or_insert(usize::MAX);
+            *mem_len = (*mem_len).min(elem.mem_len);
+        }
+
+        for (contract_id, mem_len) in mem_lens {
+            if let Ok(Some(current)) = session.memory_len(contract_id) {
+                if let Some(contract) = profile.contracts.get_mut(&contract_id)
+                {
+                    contract.mem_growth = current.saturating_sub(mem_len);
+                }
+            }
+        }
+    }
+
+    (res, profile)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn host_calls_per_thread() {
+        record_host_call("hash");
+        record_host_call("hash");
+        record_host_call("verify_bls");
+
+        // Calls made on other threads are not attributed to this one
+        std::thread::spawn(|| record_host_call("hash"))
+            .join()
+            .expect("thread to complete");
+
+        let calls = take_host_calls();
+        assert_eq!(calls.get("hash"), Some(&2));
+        assert_eq!(calls.get("verify_bls"), Some(&1));
+        assert!(take_host_calls().is_empty());
+    }
+}
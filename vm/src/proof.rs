@@ -0,0 +1,103 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Inclusion proofs of contract state in a state root.
+//!
+//! The state root commits to the memory of every contract, page by page. The
+//! pages holding a given item of a contract state, along with their
+//! openings, prove the item against the state root without trusting the
+//! node serving them.
+
+use alloc::vec::Vec;
+
+use dusk_core::abi::ContractId;
+use piecrust::{PageOpening, Session};
+
+/// A memory page of a contract, along with the opening proving its inclusion
+/// in a state root
+#[derive(Debug, Clone)]
+pub struct PageProof {
+    /// Index of the page in the contract memory
+    pub index: usize,
+    /// Content of the page
+    pub page: Vec<u8>,
+    /// Opening of the page against the state root
+    pub opening: PageOpening,
+}
+
+impl PageProof {
+    /// Returns `true` if the page is included in the given `state_root`
+    pub fn verify(&self, state_root: &[u8; 32]) -> bool {
+        self.opening.root().as_bytes() == state_root
+            && self.opening.verify(&self.page)
+    }
+}
+
+/// Returns the proofs of the memory pages of `contract` holding the `len`
+/// bytes at `offset`, against the state root of the `session`.
+///
+/// Returns `None` if the contract doesn't exist.
+pub fn page_proofs(
+    session: &Session,
+    contract: ContractId,
+    offset: usize,
+    len: usize,
+) -> Option<Vec<PageProof>> {
+    let end = offset.saturating_add(len.max(1));
+    let proofs = session
+        .memory_pages(contract)?
+        .filter(|(index, page, _)| {
+            let start = index * page.len();
+            start < end && offset < start + page.len()
+        })
+        .map(|(index, page, opening)| PageProof {
+            index,
+            page: page.to_vec(),
+            opening,
+        })
+        .collect();
+    Some(proofs)
+}
+
+/// Returns the `len` bytes at `offset` of the memory of a contract, if they
+/// are all held by the pages of `proofs`.
+///
+/// The proofs are expected to be verified against the state root first.
+pub fn proven_bytes(
+    proofs: &[PageProof],
+    offset: usize,
+    len: usize,
+) -> Option<Vec<u8>> {
+    let end = offset.checked_add(len)?;
+    let mut bytes = Vec::with_capacity(len);
+    let mut pos = offset;
+    while pos < end {
+        let (start, page) = proofs.iter().find_map(|proof| {
+            let start = proof.index * proof.page.len();
+            let held = start <= pos && pos < start + proof.page.len();
+            held.then_some((start, &proof.page))
+        })?;
+        let to = end.min(start + page.len());
+        bytes.extend_from_slice(&page[pos - start..to - start]);
+        pos = to;
+    }
+    Some(bytes)
+}
+
+/// Opens every memory page of `contract` in the `session`, returning `true`
+/// if they are all included in the given `state_root`.
+///
+/// Returns `None` if the contract doesn't exist.
+pub fn verify_memory(
+    session: &Session,
+    contract: ContractId,
+    state_root: &[u8; 32],
+) -> Option<bool> {
+    let valid = session.memory_pages(contract)?.all(|(_, page, opening)| {
+        opening.root().as_bytes() == state_root && opening.verify(page)
+    });
+    Some(valid)
+}
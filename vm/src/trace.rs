@@ -0,0 +1,156 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+//
+// Copyright (c) DUSK NETWORK. All rights reserved.
+
+//! Tracing of the execution of transactions.
+//!
+//! Like for profiling, host queries are recorded per thread, but only while a
+//! transaction is traced with [`execute_traced`], and in the order they're
+//! performed.
+
+use std::cell::RefCell;
+
+use dusk_core::abi::{ContractError, ContractId};
+use dusk_core::transfer::Transaction;
+use piecrust::{CallReceipt, Error, Session};
+
+use crate::execute::{execute, Config};
+
+type Receipt = CallReceipt<Result<Vec<u8>, ContractError>>;
+
+thread_local! {
+    static HOST_CALLS: RefCell<Option<Vec<HostCallTrace>>> =
+        const { RefCell::new(None) };
+}
+
+/// Records a call to the host query `name`, if a transaction is being traced
+pub(crate) fn record_host_call(name: &'static str, arg_len: u32) {
+    HOST_CALLS.with(|calls| {
+        if let Some(calls) = calls.borrow_mut().as_mut() {
+            calls.push(HostCallTrace { name, arg_len });
+        }
+    });
+}
+
+/// Starts recording the host queries performed on this thread
+fn start_host_calls() {
+    HOST_CALLS.with(|calls| *calls.borrow_mut() = Some(Vec::new()));
+}
+
+/// Stops recording the host queries performed on this thread, returning the
+/// ones recorded
+fn take_host_calls() -> Vec<HostCallTrace> {
+    HOST_CALLS
+        .with(|calls| calls.borrow_mut().take())
+        .unwrap_or_default()
+}
+
+/// A contract call performed by a transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CallTrace {
+    /// The called contract
+    pub contract: ContractId,
+    /// Gas limit of the call
+    pub gas_limit: u64,
+    /// Gas spent by the call, excluding its nested calls
+    pub gas_spent: u64,
+    /// Length of the contract memory when called, in bytes
+    pub mem_len: usize,
+}
+
+/// A host query performed by a transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HostCallTrace {
+    /// Name of the host query
+    pub name: &'static str,
+    /// Length of the argument of the query, in bytes
+    pub arg_len: u32,
+}
+
+/// Execution trace of a transaction
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TxTrace {
+    /// Contract calls, in the order they're performed. The first one is the
+    /// call to the transfer-contract, under which every other call is nested.
+    pub calls: Vec<CallTrace>,
+    /// Host queries, in the order they're performed
+    pub host_calls: Vec<HostCallTrace>,
+}
+
+/// Executes a transaction like [`execute`], tracing its execution.
+///
+/// # Returns
+/// The result of [`execute`], along with the trace of the execution. The
+/// contract calls of a transaction whose execution errored are not traced.
+pub fn execute_traced(
+    session: &mut Session,
+    tx: &Transaction,
+    config: &Config,
+) -> (Result<Receipt, Error>, TxTrace) {
+    start_host_calls();
+    let res = execute(session, tx, config);
+    let host_calls = take_host_calls();
+
+    let mut trace = TxTrace {
+        calls: Vec::new(),
+        host_calls,
+    };
+
+    if let Ok(receipt) = &res {
+        // The call tree is iterated from its rightmost leaf up to its root
+        trace.calls = receipt
+            .call_tree
+            .iter()
+            .map(|elem| CallTrace {
+                contract: elem.contract_id,
+                gas_limit: elem.limit,
+                gas_spent: elem.spent,
+                mem_len: elem.mem_len,
+            })
+            .collect();
+        trace.calls.reverse();
+    }
+
+    (res, trace)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn host_calls_while_tracing() {
+        // Calls are only recorded while a transaction is traced
+        record_host_call("hash", 32);
+        assert!(take_host_calls().is_empty());
+
+        start_host_calls();
+        record_host_call("hash", 32);
+        record_host_call("verify_bls", 192);
+
+        // Calls made on other threads are not attributed to this one
+        std::thread::spawn(|| record_host_call("hash", 64))
+            .join()
+            .expect("thread to complete");
+
+        let calls = take_host_calls();
+        assert_eq!(
+            calls,
+            vec![
+                HostCallTrace {
+                    name: "hash",
+                    arg_len: 32
+                },
+                HostCallTrace {
+                    name: "verify_bls",
+                    arg_len: 192
+                },
+            ]
+        );
+
+        record_host_call("hash", 32);
+        assert!(take_host_calls().is_empty());
+    }
+}
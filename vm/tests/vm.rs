@@ -30,6 +30,8 @@ use dusk_core::signatures::schnorr::{
     PublicKey as SchnorrPublicKey, SecretKey as SchnorrSecretKey,
 };
 use dusk_core::BlsScalar;
+use dusk_vm::host_queries;
+use dusk_vm::proof::{page_proofs, proven_bytes};
 use dusk_vm::{ContractData, Session, VM};
 use ff::Field;
 use rand::rngs::OsRng;
@@ -217,6 +219,34 @@ fn bls_signature() {
     assert!(!valid, "Stake Signature verification expected to fail");
 }
 
+#[test]
+fn bls_signature_cached() {
+    let vm = VM::ephemeral().expect("Instantiating VM should succeed");
+    let (mut session, contract_id) = instantiate(&vm, 0);
+
+    let message = b"some-cached-message".to_vec();
+
+    let sk = BlsSecretKey::random(&mut OsRng);
+    let pk = BlsPublicKey::from(&sk);
+    let sig = sk.sign(&message);
+
+    let wrong_sk = BlsSecretKey::random(&mut OsRng);
+    let wrong_pk = BlsPublicKey::from(&wrong_sk);
+
+    // Verifications ahead of the execution agree with the host query
+    for (pk, expected) in [(pk, true), (wrong_pk, false)] {
+        let cached = host_queries::verify_bls_cached(message.clone(), pk, sig);
+        assert_eq!(cached, expected);
+
+        let arg = (message.clone(), pk, sig);
+        let valid: bool = session
+            .call(contract_id, "verify_bls", &arg, POINT_LIMIT)
+            .expect("Query should succeed")
+            .data;
+        assert_eq!(valid, expected);
+    }
+}
+
 #[test]
 fn bls_multisig_signature() {
     let vm = VM::ephemeral().expect("Instantiating VM should succeed");
@@ -517,6 +547,38 @@ fn block_height() {
     assert_eq!(height, HEIGHT);
 }
 
+#[test]
+fn state_proofs() {
+    let vm = VM::ephemeral().expect("Instantiating VM should succeed");
+    let (session, contract_id) = instantiate(&vm, 0);
+    let root = session.root();
+
+    let proofs = page_proofs(&session, contract_id, 0, 1)
+        .expect("The contract should exist");
+    assert_eq!(proofs.len(), 1);
+    assert_eq!(proofs[0].index, 0);
+    assert!(proofs[0].verify(&root));
+    assert!(!proofs[0].verify(&[0; 32]));
+
+    let mut tampered = proofs[0].clone();
+    tampered.page[0] ^= 1;
+    assert!(!tampered.verify(&root));
+
+    // A range across two pages is proven by both
+    let page_len = proofs[0].page.len();
+    let proofs = page_proofs(&session, contract_id, page_len - 2, 4)
+        .expect("The contract should exist");
+    assert_eq!(proofs.len(), 2);
+    let bytes = proven_bytes(&proofs, page_len - 2, 4)
+        .expect("The range should be held by the pages");
+    assert_eq!(bytes[..2], proofs[0].page[page_len - 2..]);
+    assert_eq!(bytes[2..], proofs[1].page[..2]);
+    assert!(proven_bytes(&proofs, 0, 1).is_none());
+
+    let unknown = ContractId::from_bytes([0xff; 32]);
+    assert!(page_proofs(&session, unknown, 0, 1).is_none());
+}
+
 fn get_owner() -> &'static BlsPublicKey {
     static OWNER: OnceLock<BlsPublicKey> = OnceLock::new();
     OWNER.get_or_init(|| {
@@ -26,8 +26,7 @@ use core::{ptr, slice};
 
 use dusk_bytes::{DeserializableSlice, Serializable};
 use dusk_core::signatures::bls::PublicKey as BlsPublicKey;
-use dusk_core::stake::{Stake, STAKE_CONTRACT};
-use dusk_core::transfer::data::{ContractCall, TransactionData};
+use dusk_core::transfer::data::TransactionData;
 use dusk_core::transfer::moonlight::Transaction as MoonlightTransaction;
 use dusk_core::transfer::phoenix::{
     ArchivedNoteLeaf, Note, NoteLeaf, NoteOpening, Prove,
@@ -644,6 +643,58 @@ pub unsafe fn moonlight_stake(
     chain_id: u8,
     tx_ptr: *mut *mut u8,
     hash_ptr: &mut [u8; 64],
+) -> ErrorCode {
+    stake(
+        seed,
+        sender_index,
+        stake_value,
+        gas_limit,
+        gas_price,
+        nonce,
+        chain_id,
+        false,
+        tx_ptr,
+        hash_ptr,
+    )
+}
+
+#[no_mangle]
+pub unsafe fn moonlight_stake_with_pop(
+    seed: &Seed,
+    sender_index: u8,
+    stake_value: *const u64,
+    gas_limit: *const u64,
+    gas_price: *const u64,
+    nonce: *const u64,
+    chain_id: u8,
+    tx_ptr: *mut *mut u8,
+    hash_ptr: &mut [u8; 64],
+) -> ErrorCode {
+    stake(
+        seed,
+        sender_index,
+        stake_value,
+        gas_limit,
+        gas_price,
+        nonce,
+        chain_id,
+        true,
+        tx_ptr,
+        hash_ptr,
+    )
+}
+
+unsafe fn stake(
+    seed: &Seed,
+    sender_index: u8,
+    stake_value: *const u64,
+    gas_limit: *const u64,
+    gas_price: *const u64,
+    nonce: *const u64,
+    chain_id: u8,
+    with_pop: bool,
+    tx_ptr: *mut *mut u8,
+    hash_ptr: &mut [u8; 64],
 ) -> ErrorCode {
     let transfer_value = 0;
     let deposit = *stake_value;
@@ -651,10 +702,14 @@ pub unsafe fn moonlight_stake(
     let sender_sk = derive_bls_sk(&seed, sender_index);
     let stake_sk = sender_sk.clone();
 
-    let stake = Stake::new(&stake_sk, &stake_sk, *stake_value, chain_id);
-
-    let contract_call = ContractCall::new(STAKE_CONTRACT, "stake", &stake)
-        .or(Err(ErrorCode::ContractCallError))?;
+    let contract_call = crate::transaction::stake_call(
+        &stake_sk,
+        &stake_sk,
+        *stake_value,
+        chain_id,
+        with_pop,
+    )
+    .or(Err(ErrorCode::ContractCallError))?;
 
     let tx = crate::transaction::moonlight(
         &sender_sk,
@@ -15,4 +15,4 @@ pub mod pick;
 
 /// The maximum amount of input notes that can be spend in one
 /// phoenix-transaction
-pub const MAX_INPUT_NOTES: usize = 4;
+pub use dusk_core::transfer::phoenix::MAX_INPUT_NOTES;
@@ -13,7 +13,9 @@ use dusk_core::abi::ContractId;
 use dusk_core::signatures::bls::{
     PublicKey as BlsPublicKey, SecretKey as BlsSecretKey,
 };
-use dusk_core::stake::{Stake, Withdraw as StakeWithdraw, STAKE_CONTRACT};
+use dusk_core::stake::{
+    Stake, VersionedStake, Withdraw as StakeWithdraw, STAKE_CONTRACT,
+};
 use dusk_core::transfer::data::{
     ContractBytecode, ContractCall, ContractDeploy, TransactionData,
 };
@@ -133,6 +135,10 @@ pub fn moonlight(
 
 /// Create a [`Transaction`] to stake from phoenix-notes.
 ///
+/// The stake is sent with the legacy arguments, without the proof of
+/// possession of the account key. Use [`phoenix_stake_with_pop`] once the
+/// chain requires it.
+///
 /// # Errors
 /// The creation of a transaction is not possible and will error if:
 /// - one of the input-notes doesn't belong to the `phoenix_sender_sk`
@@ -153,6 +159,80 @@ pub fn phoenix_stake<R: RngCore + CryptoRng, P: Prove>(
     chain_id: u8,
     stake_value: u64,
     prover: &P,
+) -> Result<Transaction, Error> {
+    let contract_call =
+        stake_call(stake_sk, stake_owner_sk, stake_value, chain_id, false)?;
+
+    phoenix_stake_call(
+        rng,
+        phoenix_sender_sk,
+        inputs,
+        root,
+        gas_limit,
+        gas_price,
+        chain_id,
+        stake_value,
+        contract_call,
+        prover,
+    )
+}
+
+/// Create a [`Transaction`] to stake from phoenix-notes, along with the proof
+/// of possession of the account key.
+///
+/// Such stakes are only accepted once the chain activated the proofs of
+/// possession.
+///
+/// # Errors
+/// The creation of a transaction is not possible and will error if:
+/// - one of the input-notes doesn't belong to the `phoenix_sender_sk`
+/// - the transaction input doesn't cover the transaction costs
+/// - the `inputs` vector is either empty or larger than 4 elements
+/// - the `inputs` vector contains duplicate `Note`s
+/// - the `Prove` trait is implemented incorrectly
+#[allow(clippy::too_many_arguments)]
+pub fn phoenix_stake_with_pop<R: RngCore + CryptoRng, P: Prove>(
+    rng: &mut R,
+    phoenix_sender_sk: &PhoenixSecretKey,
+    stake_sk: &BlsSecretKey,
+    stake_owner_sk: &BlsSecretKey,
+    inputs: Vec<(Note, NoteOpening)>,
+    root: BlsScalar,
+    gas_limit: u64,
+    gas_price: u64,
+    chain_id: u8,
+    stake_value: u64,
+    prover: &P,
+) -> Result<Transaction, Error> {
+    let contract_call =
+        stake_call(stake_sk, stake_owner_sk, stake_value, chain_id, true)?;
+
+    phoenix_stake_call(
+        rng,
+        phoenix_sender_sk,
+        inputs,
+        root,
+        gas_limit,
+        gas_price,
+        chain_id,
+        stake_value,
+        contract_call,
+        prover,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+fn phoenix_stake_call<R: RngCore + CryptoRng, P: Prove>(
+    rng: &mut R,
+    phoenix_sender_sk: &PhoenixSecretKey,
+    inputs: Vec<(Note, NoteOpening)>,
+    root: BlsScalar,
+    gas_limit: u64,
+    gas_price: u64,
+    chain_id: u8,
+    stake_value: u64,
+    contract_call: ContractCall,
+    prover: &P,
 ) -> Result<Transaction, Error> {
     // in a staking transaction the receiver and refund-address is the sender
     let phoenix_receiver_pk = PhoenixPublicKey::from(phoenix_sender_sk);
@@ -162,10 +242,6 @@ pub fn phoenix_stake<R: RngCore + CryptoRng, P: Prove>(
     let is_transfer = false;
     let deposit = stake_value;
 
-    let stake = Stake::new(stake_sk, stake_owner_sk, stake_value, chain_id);
-
-    let contract_call = ContractCall::new(STAKE_CONTRACT, "stake", &stake)?;
-
     phoenix::<R, P>(
         rng,
         phoenix_sender_sk,
@@ -186,6 +262,10 @@ pub fn phoenix_stake<R: RngCore + CryptoRng, P: Prove>(
 
 /// Create a [`Transaction`] to stake from a Moonlight account.
 ///
+/// The stake is sent with the legacy arguments, without the proof of
+/// possession of the account key. Use [`moonlight_stake_with_pop`] once the
+/// chain requires it.
+///
 /// # Note
 /// The `moonlight_nonce` is NOT incremented and should be incremented by the
 /// caller of this function, if its not done so, rusk will throw 500 error
@@ -204,12 +284,54 @@ pub fn moonlight_stake(
     moonlight_nonce: u64,
     chain_id: u8,
 ) -> Result<Transaction, Error> {
+    let contract_call =
+        stake_call(stake_sk, stake_owner_sk, stake_value, chain_id, false)?;
+
     let transfer_value = 0;
     let deposit = stake_value;
 
-    let stake = Stake::new(stake_sk, stake_owner_sk, stake_value, chain_id);
+    moonlight(
+        moonlight_sender_sk,
+        None,
+        transfer_value,
+        deposit,
+        gas_limit,
+        gas_price,
+        moonlight_nonce,
+        chain_id,
+        Some(contract_call),
+    )
+}
+
+/// Create a [`Transaction`] to stake from a Moonlight account, along with the
+/// proof of possession of the account key.
+///
+/// Such stakes are only accepted once the chain activated the proofs of
+/// possession.
+///
+/// # Note
+/// The `moonlight_nonce` is NOT incremented and should be incremented by the
+/// caller of this function, if its not done so, rusk will throw 500 error
+///
+/// # Errors
+/// The creation of this transaction doesn't error, but still returns a result
+/// for the sake of API consistency.
+#[allow(clippy::too_many_arguments)]
+pub fn moonlight_stake_with_pop(
+    moonlight_sender_sk: &BlsSecretKey,
+    stake_sk: &BlsSecretKey,
+    stake_owner_sk: &BlsSecretKey,
+    stake_value: u64,
+    gas_limit: u64,
+    gas_price: u64,
+    moonlight_nonce: u64,
+    chain_id: u8,
+) -> Result<Transaction, Error> {
+    let contract_call =
+        stake_call(stake_sk, stake_owner_sk, stake_value, chain_id, true)?;
 
-    let contract_call = ContractCall::new(STAKE_CONTRACT, "stake", &stake)?;
+    let transfer_value = 0;
+    let deposit = stake_value;
 
     moonlight(
         moonlight_sender_sk,
@@ -224,6 +346,25 @@ pub fn moonlight_stake(
     )
 }
 
+/// Creates the call to stake `stake_value`, carrying the proof of possession
+/// of the account key if `with_pop` is set.
+pub(crate) fn stake_call(
+    stake_sk: &BlsSecretKey,
+    stake_owner_sk: &BlsSecretKey,
+    stake_value: u64,
+    chain_id: u8,
+    with_pop: bool,
+) -> Result<ContractCall, Error> {
+    let stake = Stake::new(stake_sk, stake_owner_sk, stake_value, chain_id);
+
+    if with_pop {
+        let stake = VersionedStake::with_pop(stake, stake_sk);
+        ContractCall::new(STAKE_CONTRACT, "stake", &stake)
+    } else {
+        ContractCall::new(STAKE_CONTRACT, "stake", &stake)
+    }
+}
+
 /// Create an unproven [`Transaction`] to withdraw stake rewards into a
 /// phoenix-note.
 ///